@@ -6,6 +6,15 @@
 //!
 //! Environment variables:
 //! - HORSEMAN_CALLBACK_PORT: Port where Tauri's HTTP server is listening
+//! - HORSEMAN_PERMISSION_TIMEOUT_SECS: How long Tauri waits for the user
+//!   (default 170); requests give up a few seconds after
+//! - HORSEMAN_CA_CERT: Extra PEM CA bundle to trust (proxies come from the
+//!   standard HTTPS_PROXY / NO_PROXY variables)
+//!
+//! `horseman-mcp --status [--json]` instead reports whether Horseman is
+//! running (see `status.rs`).
+
+mod status;
 
 use rmcp::{
     ServerHandler,
@@ -39,6 +48,9 @@ struct PermissionCallbackResponse {
     message: Option<String>,
     /// For AskUserQuestion: the user's answers (header -> answer)
     answers: Option<std::collections::HashMap<String, String>>,
+    /// Input the user edited before approving, replacing Claude's
+    #[serde(default)]
+    updated_input: Option<serde_json::Value>,
 }
 
 /// Input schema matching what Claude sends to permission-prompt-tool
@@ -65,19 +77,48 @@ pub struct HorsemanMcp {
     callback_port: u16,
     /// UI session ID from environment (for session tracking)
     ui_session_id: Option<String>,
+    /// How long a permission callback may take
+    request_timeout: std::time::Duration,
     /// HTTP client for making callbacks
     client: Arc<reqwest::Client>,
     /// Tool router
     tool_router: ToolRouter<Self>,
 }
 
+/// HTTP client honoring the proxy env vars and HORSEMAN_CA_CERT
+fn build_client() -> reqwest::Client {
+    let mut builder = reqwest::Client::builder();
+    if let Ok(path) = env::var("HORSEMAN_CA_CERT") {
+        match std::fs::read(&path).map_err(|e| e.to_string()).and_then(|pem| {
+            reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| e.to_string())
+        }) {
+            Ok(certs) => {
+                for cert in certs {
+                    builder = builder.add_root_certificate(cert);
+                }
+            }
+            Err(e) => error!("Ignoring HORSEMAN_CA_CERT {}: {}", path, e),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        error!("Failed to build HTTP client: {}", e);
+        reqwest::Client::new()
+    })
+}
+
 impl HorsemanMcp {
     pub fn new(callback_port: u16) -> Self {
         let ui_session_id = env::var("HORSEMAN_UI_SESSION_ID").ok();
+        let permission_timeout_secs: u64 = env::var("HORSEMAN_PERMISSION_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(170);
         Self {
             callback_port,
             ui_session_id,
-            client: Arc::new(reqwest::Client::new()),
+            // Tauri answers on its own timeout; this only catches a dead backend
+            request_timeout: std::time::Duration::from_secs(permission_timeout_secs + 5),
+            client: Arc::new(build_client()),
             tool_router: Self::tool_router(),
         }
     }
@@ -104,7 +145,7 @@ impl HorsemanMcp {
             .client
             .post(&url)
             .json(&request)
-            .timeout(std::time::Duration::from_secs(175))
+            .timeout(self.request_timeout)
             .send()
             .await
             .map_err(|e| format!("Failed to send request to Tauri: {}", e))?;
@@ -152,9 +193,17 @@ impl HorsemanMcp {
                 if response.allow {
                     info!("Permission allowed for '{}'", input.tool_name);
                     // For allow: { behavior: "allow", updatedInput: <record> }
+                    // The user may have edited the input before approving
+                    let approved_input = match response.updated_input {
+                        Some(edited) => {
+                            debug!("Using user-edited input for '{}'", input.tool_name);
+                            edited
+                        }
+                        None => input.input.clone(),
+                    };
                     // If answers are provided (AskUserQuestion), merge them into the input
                     let updated_input = if let Some(answers) = response.answers {
-                        let mut input_obj = input.input.clone();
+                        let mut input_obj = approved_input;
                         if let Some(obj) = input_obj.as_object_mut() {
                             let answer_count = answers.len();
                             obj.insert("answers".to_string(), serde_json::to_value(answers).unwrap_or_default());
@@ -168,7 +217,7 @@ impl HorsemanMcp {
                         input_obj
                     } else {
                         debug!("No AskUserQuestion answers in permission response");
-                        input.input.clone()
+                        approved_input
                     };
 
                     serde_json::json!({
@@ -214,6 +263,11 @@ impl ServerHandler for HorsemanMcp {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--status") {
+        std::process::exit(status::run(args.iter().any(|a| a == "--json")));
+    }
+
     // Initialize tracing - logs go to stderr (stdout is MCP protocol)
     tracing_subscriber::fmt()
         .with_env_filter(
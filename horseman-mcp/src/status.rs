@@ -0,0 +1,137 @@
+//! `horseman-mcp --status [--json]`: whether Horseman is running, from the
+//! heartbeat file the app rewrites every few seconds, for scripts and
+//! supervisors. Exits 0 when running, 1 when stopped, and 2 when the file
+//! stopped updating (Horseman hung, or crashed without cleaning up).
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Missed updates before the heartbeat counts as stale
+const STALE_AFTER_INTERVALS: u64 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Heartbeat {
+    pid: u32,
+    version: String,
+    hook_port: u16,
+    running_sessions: usize,
+    queued_sessions: usize,
+    started_at: u64,
+    updated_at: u64,
+    interval_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum State {
+    Running,
+    Stopped,
+    Stale,
+}
+
+impl State {
+    fn exit_code(self) -> i32 {
+        match self {
+            State::Running => 0,
+            State::Stopped => 1,
+            State::Stale => 2,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    state: State,
+    /// Since the last heartbeat
+    age_ms: Option<u64>,
+    heartbeat: Option<Heartbeat>,
+}
+
+/// Same place the app writes it: `<config dir>/horseman/heartbeat.json`
+fn heartbeat_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("horseman").join("heartbeat.json"))
+}
+
+fn assess(heartbeat: Option<Heartbeat>, now_ms: u64) -> Report {
+    match heartbeat {
+        None => Report {
+            state: State::Stopped,
+            age_ms: None,
+            heartbeat: None,
+        },
+        Some(heartbeat) => {
+            let age_ms = now_ms.saturating_sub(heartbeat.updated_at);
+            let state = if age_ms > heartbeat.interval_ms.max(1000) * STALE_AFTER_INTERVALS {
+                State::Stale
+            } else {
+                State::Running
+            };
+            Report {
+                state,
+                age_ms: Some(age_ms),
+                heartbeat: Some(heartbeat),
+            }
+        }
+    }
+}
+
+fn describe(report: &Report) -> String {
+    let (heartbeat, age_ms) = match (&report.heartbeat, report.age_ms) {
+        (Some(heartbeat), Some(age_ms)) => (heartbeat, age_ms),
+        _ => return "stopped: no heartbeat file".to_string(),
+    };
+    let summary = format!(
+        "pid {}, version {}, hook port {}, {} running / {} queued sessions, up {}s",
+        heartbeat.pid,
+        heartbeat.version,
+        heartbeat.hook_port,
+        heartbeat.running_sessions,
+        heartbeat.queued_sessions,
+        heartbeat.updated_at.saturating_sub(heartbeat.started_at) / 1000,
+    );
+    match report.state {
+        State::Stale => format!("stale: no heartbeat for {}s ({})", age_ms / 1000, summary),
+        _ => format!("running: {}", summary),
+    }
+}
+
+/// Print the status and return the exit code
+pub fn run(json: bool) -> i32 {
+    let heartbeat = heartbeat_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok());
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let report = assess(heartbeat, now_ms);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+    } else {
+        println!("{}", describe(&report));
+    }
+    report.state.exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeats_go_stale_after_missed_updates() {
+        let heartbeat = Heartbeat {
+            pid: 42,
+            version: "0.1.0".to_string(),
+            hook_port: 4000,
+            running_sessions: 1,
+            queued_sessions: 0,
+            started_at: 0,
+            updated_at: 100_000,
+            interval_ms: 5_000,
+        };
+        assert_eq!(assess(Some(heartbeat.clone()), 104_000).state, State::Running);
+        assert_eq!(assess(Some(heartbeat.clone()), 116_000).state, State::Stale);
+        assert_eq!(assess(None, 0).state.exit_code(), 1);
+        assert!(describe(&assess(Some(heartbeat), 101_000)).starts_with("running: pid 42"));
+    }
+}
@@ -1,3 +1,20 @@
+use sha2::{Digest, Sha256};
+
 fn main() {
+    bake_mcp_hash();
     tauri_build::build()
 }
+
+/// Bake the sidecar's SHA-256 in so the app can detect a swapped binary
+fn bake_mcp_hash() {
+    let target = std::env::var("TARGET").unwrap_or_default();
+    let path = format!("binaries/horseman-mcp-{}", target);
+    println!("cargo:rerun-if-changed={}", path);
+    if let Ok(bytes) = std::fs::read(&path) {
+        let hash: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+        println!("cargo:rustc-env=HORSEMAN_MCP_SHA256={}", hash);
+    } else {
+        // Without a hash an unsigned build refuses its bundled sidecar
+        println!("cargo:warning={} not found; the bundled horseman-mcp will be rejected", path);
+    }
+}
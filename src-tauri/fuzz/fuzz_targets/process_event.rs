@@ -0,0 +1,10 @@
+//! A session's stdout run through `process_event`. Run from src-tauri/ with
+//! `cargo +nightly fuzz run process_event`.
+#![no_main]
+
+use horseman_lib::fuzzing;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    fuzzing::process_events(data);
+});
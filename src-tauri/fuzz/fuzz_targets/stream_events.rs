@@ -0,0 +1,10 @@
+//! Stream-json stdout events. Run from src-tauri/ with
+//! `cargo +nightly fuzz run stream_events`.
+#![no_main]
+
+use horseman_lib::fuzzing;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    fuzzing::process_stream(data);
+});
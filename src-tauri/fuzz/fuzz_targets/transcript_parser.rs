@@ -0,0 +1,10 @@
+//! Transcript JSONL parsing. Run from src-tauri/ with
+//! `cargo +nightly fuzz run transcript_parser`.
+#![no_main]
+
+use horseman_lib::fuzzing;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    fuzzing::parse_transcript(data);
+});
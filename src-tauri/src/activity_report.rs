@@ -0,0 +1,280 @@
+//! Standup-style activity reports: every session touched in the last day or
+//! week, grouped by project, with what each accomplished (its end-of-session
+//! summary when there is one), its cost and active time within the period
+//! (from the cost ledger and time tracking, by local day). Optionally
+//! polished by a background AI job.
+
+use crate::background_ai::{self, JobRequest, Priority};
+use crate::commands::sessions::discover_sessions;
+use crate::debug_log;
+use crate::session_cache;
+use crate::store;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Duration;
+
+/// Session listing is a one-off here, so wait out slow storage
+const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+const POLISH_TIMEOUT: Duration = Duration::from_secs(120);
+/// Characters of a first prompt used as a title
+const TITLE_CHARS: usize = 120;
+/// Characters of a summary per session
+const ACCOMPLISHMENT_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Day,
+    Week,
+}
+
+impl ReportPeriod {
+    fn duration(self) -> chrono::Duration {
+        match self {
+            ReportPeriod::Day => chrono::Duration::days(1),
+            ReportPeriod::Week => chrono::Duration::days(7),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ReportPeriod::Day => "last 24 hours",
+            ReportPeriod::Week => "last 7 days",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SessionActivity {
+    title: String,
+    accomplishment: Option<String>,
+    cost_usd: Option<f64>,
+    active_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityReport {
+    pub period: ReportPeriod,
+    /// Local date of the oldest activity included, YYYY-MM-DD
+    pub from: String,
+    pub to: String,
+    pub markdown: String,
+    /// Whether a background AI job rewrote the report
+    pub polished: bool,
+    pub session_count: usize,
+    pub project_count: usize,
+    pub total_cost_usd: f64,
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max {
+        return text;
+    }
+    let mut out: String = text.chars().take(max).collect();
+    out.push('…');
+    out
+}
+
+fn format_duration(secs: u64) -> String {
+    let (hours, minutes) = (secs / 3600, (secs % 3600) / 60);
+    match (hours, minutes) {
+        (0, m) => format!("{}m", m),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// "$1.23 · 1h 5m", leaving out whatever is unknown or zero
+fn stats(cost_usd: Option<f64>, active_secs: u64) -> String {
+    let mut parts = Vec::new();
+    if let Some(cost) = cost_usd {
+        parts.push(format!("${:.2}", cost));
+    }
+    if active_secs >= 60 {
+        parts.push(format!("{} active", format_duration(active_secs)));
+    }
+    parts.join(" · ")
+}
+
+fn project_name(working_directory: &str) -> &str {
+    Path::new(working_directory)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(working_directory)
+}
+
+fn total_cost(sessions: &[SessionActivity]) -> Option<f64> {
+    let costs: Vec<f64> = sessions.iter().filter_map(|s| s.cost_usd).collect();
+    (!costs.is_empty()).then(|| costs.iter().sum())
+}
+
+/// Markdown for sessions grouped by working directory
+fn render(heading: &str, projects: &BTreeMap<String, Vec<SessionActivity>>) -> String {
+    let all: Vec<SessionActivity> = projects.values().flatten().cloned().collect();
+    let mut out = format!("# {}\n\n", heading);
+    if all.is_empty() {
+        out.push_str("No sessions in this period.\n");
+        return out;
+    }
+    let summary = stats(total_cost(&all), all.iter().map(|s| s.active_secs).sum());
+    out.push_str(&format!(
+        "{} session{} across {} project{}",
+        all.len(),
+        if all.len() == 1 { "" } else { "s" },
+        projects.len(),
+        if projects.len() == 1 { "" } else { "s" }
+    ));
+    if !summary.is_empty() {
+        out.push_str(&format!(" · {}", summary));
+    }
+    out.push('\n');
+
+    for (working_directory, sessions) in projects {
+        out.push_str(&format!("\n## {} (`{}`)\n\n", project_name(working_directory), working_directory));
+        let project_stats = stats(total_cost(sessions), sessions.iter().map(|s| s.active_secs).sum());
+        if !project_stats.is_empty() {
+            out.push_str(&format!("_{}_\n\n", project_stats));
+        }
+        for session in sessions {
+            out.push_str(&format!("- **{}**", session.title));
+            if let Some(accomplishment) = &session.accomplishment {
+                out.push_str(&format!(": {}", accomplishment));
+            }
+            let session_stats = stats(session.cost_usd, session.active_secs);
+            if !session_stats.is_empty() {
+                out.push_str(&format!(" ({})", session_stats));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn polish_with_claude(markdown: &str) -> Option<String> {
+    let prompt = format!(
+        "Rewrite this activity report as a concise standup update in Markdown. Keep the \
+         project headings, costs and times exactly as given; merge related bullet points \
+         and phrase them as accomplishments. Reply with only the Markdown.\n\n{}",
+        markdown
+    );
+    let result = background_ai::run(JobRequest {
+        label: "Polish activity report".to_string(),
+        prompt,
+        working_directory: std::env::temp_dir().to_string_lossy().to_string(),
+        model: None,
+        priority: Priority::High,
+        timeout: POLISH_TIMEOUT,
+    });
+    match result {
+        Ok(output) if !output.text.trim().is_empty() => Some(format!("{}\n", output.text.trim())),
+        Ok(_) => None,
+        Err(e) => {
+            debug_log!("REPORT", "Polish failed, keeping the plain report: {}", e);
+            None
+        }
+    }
+}
+
+/// Compile the report for `period`; with `polish`, a background AI job
+/// rewrites it (the plain report is kept if that fails)
+pub fn generate(period: ReportPeriod, polish: bool) -> Result<ActivityReport, String> {
+    let now = chrono::Local::now();
+    let since = now - period.duration();
+    let from = since.format("%Y-%m-%d").to_string();
+    let to = now.format("%Y-%m-%d").to_string();
+
+    let mut active_ms: HashMap<String, u64> = HashMap::new();
+    for entry in store::time_entries(&from, &to)? {
+        if let Some(id) = entry.claude_session_id {
+            *active_ms.entry(id).or_default() += entry.active_ms;
+        }
+    }
+
+    let costs = store::session_costs(&from, &to)?;
+
+    let listing = discover_sessions(LIST_TIMEOUT)?;
+    let mut projects: BTreeMap<String, Vec<SessionActivity>> = BTreeMap::new();
+    for session in listing.sessions {
+        if session.modified_at_ms < since.timestamp_millis() {
+            continue;
+        }
+        let accomplishment = store::summary(&session.id)
+            .ok()
+            .flatten()
+            .map(|s| s.summary)
+            .or_else(|| {
+                session_cache::get(&session.id, Path::new(&session.transcript_path))
+                    .ok()
+                    .and_then(|c| c.summary)
+            })
+            .map(|text| truncate(&text, ACCOMPLISHMENT_CHARS));
+        let title = session
+            .custom_title
+            .clone()
+            .or_else(|| session.title.clone())
+            .or_else(|| session.first_message.as_deref().map(|m| truncate(m, TITLE_CHARS)))
+            .unwrap_or_else(|| session.id.clone());
+        projects.entry(session.working_directory.clone()).or_default().push(SessionActivity {
+            title,
+            accomplishment,
+            cost_usd: costs.get(&session.id).copied(),
+            active_secs: active_ms.get(&session.id).copied().unwrap_or(0) / 1000,
+        });
+    }
+
+    let all: Vec<&SessionActivity> = projects.values().flatten().collect();
+    let session_count = all.len();
+    let total_cost_usd = all.iter().filter_map(|s| s.cost_usd).sum();
+    let heading = format!("Activity report: {} ({} to {})", period.label(), from, to);
+    let plain = render(&heading, &projects);
+    let polished = if polish && session_count > 0 { polish_with_claude(&plain) } else { None };
+
+    Ok(ActivityReport {
+        period,
+        from,
+        to,
+        polished: polished.is_some(),
+        markdown: polished.unwrap_or(plain),
+        session_count,
+        project_count: projects.len(),
+        total_cost_usd,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_projects_with_costs_and_time() {
+        let mut projects = BTreeMap::new();
+        projects.insert(
+            "/work/api".to_string(),
+            vec![
+                SessionActivity {
+                    title: "main — Fix auth".to_string(),
+                    accomplishment: Some("Fixed token refresh".to_string()),
+                    cost_usd: Some(1.5),
+                    active_secs: 3900,
+                },
+                SessionActivity {
+                    title: "Explore".to_string(),
+                    accomplishment: None,
+                    cost_usd: None,
+                    active_secs: 0,
+                },
+            ],
+        );
+        let markdown = render("Report", &projects);
+        assert_eq!(
+            markdown,
+            "# Report\n\n2 sessions across 1 project · $1.50 · 1h 5m active\n\n\
+             ## api (`/work/api`)\n\n_$1.50 · 1h 5m active_\n\n\
+             - **main — Fix auth**: Fixed token refresh ($1.50 · 1h 5m active)\n\
+             - **Explore**\n"
+        );
+        assert_eq!(render("Report", &BTreeMap::new()), "# Report\n\nNo sessions in this period.\n");
+    }
+}
@@ -0,0 +1,143 @@
+//! Crash- and sync-tool-safe file writes. A plain `fs::write` truncates the
+//! file first, so a crash or an iCloud/Dropbox sync racing the write can
+//! leave it empty or half-written. These helpers write a temp file in the
+//! same directory, fsync it, and rename it over the target.
+
+use crate::debug_log;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Marker in temp file names (".<name>.horseman-tmp-<uuid>")
+const TEMP_MARKER: &str = ".horseman-tmp-";
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Flush the directory entry so the rename itself survives a crash
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    if let Ok(d) = File::open(dir) {
+        let _ = d.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
+
+/// Atomically replace `path` with `contents`
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{}{}{}", file_name, TEMP_MARKER, Uuid::new_v4()));
+
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+
+    sync_dir(dir);
+    Ok(())
+}
+
+/// Like `write`, but first keeps the previous version as `<name>.bak`
+/// so `read_with_recovery` has something to fall back on
+pub fn write_with_backup(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if path.exists() {
+        let backup = backup_path(path);
+        if let Ok(previous) = fs::read(path) {
+            // Don't replace a good backup with a file that's already damaged
+            if !previous.is_empty() {
+                write(&backup, previous)?;
+            }
+        }
+    }
+    write(path, contents)
+}
+
+/// Read `path`, falling back to its `.bak` (and restoring it) if the file is
+/// missing, empty, or fails `is_valid`. Returns None if neither is usable.
+pub fn read_with_recovery(path: &Path, is_valid: impl Fn(&str) -> bool) -> Option<String> {
+    if let Ok(content) = fs::read_to_string(path) {
+        if !content.trim().is_empty() && is_valid(&content) {
+            return Some(content);
+        }
+        debug_log!("ATOMIC", "{:?} is empty or corrupt, trying backup", path);
+    }
+
+    let backup = fs::read_to_string(backup_path(path)).ok()?;
+    if backup.trim().is_empty() || !is_valid(&backup) {
+        debug_log!("ATOMIC", "Backup for {:?} is unusable too", path);
+        return None;
+    }
+
+    debug_log!("ATOMIC", "Recovered {:?} from backup", path);
+    if let Err(e) = write(path, &backup) {
+        debug_log!("ATOMIC", "Failed to restore {:?}: {}", path, e);
+    }
+    Some(backup)
+}
+
+/// Remove temp files left behind by writes interrupted mid-way
+pub fn cleanup_temp_files(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().contains(TEMP_MARKER) {
+            debug_log!("ATOMIC", "Removing stale temp file {:?}", entry.path());
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("horseman-atomic-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn write_replaces_contents_without_leftovers() {
+        let dir = temp_dir();
+        let path = dir.join("config.toml");
+        write(&path, "a = 1").unwrap();
+        write(&path, "a = 2").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a = 2");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recovers_truncated_file_from_backup() {
+        let dir = temp_dir();
+        let path = dir.join("config.toml");
+        write_with_backup(&path, "a = 1").unwrap();
+        write_with_backup(&path, "a = 2").unwrap();
+        // Simulate a sync tool truncating the file
+        fs::write(&path, "a = ").unwrap();
+
+        let valid = |s: &str| s.trim_end().ends_with(char::is_numeric);
+        assert_eq!(read_with_recovery(&path, valid).as_deref(), Some("a = 1"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a = 1");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,403 @@
+//! Shared queue for one-shot Claude calls made in the background (titles,
+//! summaries, drafts, ...). Jobs run one at a time, highest priority first,
+//! at most one per `backgroundAiMinIntervalSecs`, and are refused once
+//! today's spend reaches `backgroundAiDailyBudgetUsd`. Pausing (or turning
+//! off `backgroundAiEnabled`) is the kill switch: it stops the running job
+//! and fails everything queued. While offline, jobs stay queued.
+
+use crate::atomic_file;
+use crate::config;
+use crate::connectivity;
+#[cfg(feature = "analytics")]
+use crate::cost_ledger;
+use crate::debug_log;
+use crate::network;
+use crate::panic_guard;
+use crate::providers;
+use crate::supervisor;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Jobs waiting at once; a full queue only takes higher-priority jobs
+const MAX_QUEUED: usize = 20;
+/// How often the worker re-checks a paused or empty queue
+const IDLE_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    Low,
+    Normal,
+    /// Something the user is waiting on
+    High,
+}
+
+/// A one-shot `claude -p` call
+pub struct JobRequest {
+    /// Shown in the status while the job runs, e.g. "Refine CLAUDE.md"
+    pub label: String,
+    pub prompt: String,
+    pub working_directory: String,
+    pub model: Option<String>,
+    pub priority: Priority,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobOutput {
+    pub text: String,
+    pub cost_usd: f64,
+}
+
+type Reply = mpsc::Sender<Result<JobOutput, String>>;
+
+struct Job {
+    id: u64,
+    request: JobRequest,
+    reply: Reply,
+}
+
+/// Spend for one local day, persisted so a relaunch doesn't reset the cap
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DailySpend {
+    date: String,
+    usd: f64,
+}
+
+struct Queue {
+    jobs: Vec<Job>,
+    next_id: u64,
+    paused: bool,
+    /// Label and PID (also its process group) of the job being run
+    running: Option<(String, u32)>,
+    last_started: Option<Instant>,
+    spend: DailySpend,
+}
+
+static QUEUE: Lazy<Mutex<Queue>> = Lazy::new(|| {
+    Mutex::new(Queue {
+        jobs: Vec::new(),
+        next_id: 0,
+        paused: false,
+        running: None,
+        last_started: None,
+        spend: load_spend(),
+    })
+});
+static WAKE: Condvar = Condvar::new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundAiStatus {
+    /// Paused at runtime or disabled in config
+    pub paused: bool,
+    /// Jobs are held until the network returns
+    pub offline: bool,
+    pub queued: usize,
+    /// Label of the job being run
+    pub running: Option<String>,
+    pub spent_today_usd: f64,
+    pub daily_budget_usd: f64,
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+fn spend_path() -> Option<PathBuf> {
+    config::artifacts_dir().map(|d| d.join("background_ai_spend.json"))
+}
+
+fn load_spend() -> DailySpend {
+    spend_path()
+        .and_then(|p| fs::read_to_string(p).ok())
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn save_spend(spend: &DailySpend) {
+    let path = match spend_path() {
+        Some(p) => p,
+        None => return,
+    };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(content) = serde_json::to_string(spend) {
+        if let Err(e) = atomic_file::write(&path, content) {
+            debug_log!("BG_AI", "Failed to save spend: {}", e);
+        }
+    }
+}
+
+/// Spend so far today, starting over when the date changes
+fn spent_today(spend: &mut DailySpend) -> f64 {
+    let today = today();
+    if spend.date != today {
+        *spend = DailySpend { date: today, usd: 0.0 };
+    }
+    spend.usd
+}
+
+/// Index of the job to run next: highest priority, then oldest
+fn pick_next(jobs: &[Job]) -> Option<usize> {
+    jobs.iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.request.priority.cmp(&b.request.priority).then(b.id.cmp(&a.id)))
+        .map(|(i, _)| i)
+}
+
+/// Index of the job to drop so one with `priority` fits: lowest priority,
+/// then newest, and only if it ranks below `priority`
+fn pick_evicted(jobs: &[Job], priority: Priority) -> Option<usize> {
+    jobs.iter()
+        .enumerate()
+        .filter(|(_, j)| j.request.priority < priority)
+        .min_by(|(_, a), (_, b)| a.request.priority.cmp(&b.request.priority).then(b.id.cmp(&a.id)))
+        .map(|(i, _)| i)
+}
+
+fn is_paused(queue: &Queue) -> bool {
+    queue.paused || !config::background_ai_enabled()
+}
+
+/// Queue a job; the receiver gets its result once it has run
+pub fn submit(request: JobRequest) -> Result<mpsc::Receiver<Result<JobOutput, String>>, String> {
+    let mut queue = panic_guard::lock_or_recover(&QUEUE, "background AI queue");
+    if is_paused(&queue) {
+        return Err("Background AI is paused".to_string());
+    }
+    let budget = config::background_ai_daily_budget_usd();
+    if spent_today(&mut queue.spend) >= budget {
+        return Err(format!("Background AI daily budget (${:.2}) reached", budget));
+    }
+    if queue.jobs.len() >= MAX_QUEUED {
+        let evicted = pick_evicted(&queue.jobs, request.priority)
+            .ok_or_else(|| "Background AI queue is full".to_string())?;
+        let job = queue.jobs.remove(evicted);
+        debug_log!("BG_AI", "Dropping '{}' for a higher-priority job", job.request.label);
+        let _ = job.reply.send(Err("Dropped for a higher-priority background job".to_string()));
+    }
+
+    let (reply, receiver) = mpsc::channel();
+    let id = queue.next_id;
+    queue.next_id += 1;
+    debug_log!("BG_AI", "Queued '{}' ({:?})", request.label, request.priority);
+    queue.jobs.push(Job { id, request, reply });
+    WAKE.notify_all();
+    Ok(receiver)
+}
+
+/// Queue a job and block until it has run
+pub fn run(request: JobRequest) -> Result<JobOutput, String> {
+    submit(request)?
+        .recv()
+        .map_err(|_| "Background AI worker stopped".to_string())?
+}
+
+/// Kill switch: pausing stops the running job and fails every queued one
+pub fn set_paused(paused: bool) {
+    let mut queue = panic_guard::lock_or_recover(&QUEUE, "background AI queue");
+    queue.paused = paused;
+    if paused {
+        cancel_all(&mut queue, "Background AI was paused");
+    }
+    WAKE.notify_all();
+}
+
+fn cancel_all(queue: &mut Queue, reason: &str) {
+    for job in queue.jobs.drain(..) {
+        let _ = job.reply.send(Err(reason.to_string()));
+    }
+    if let Some((ref label, pid)) = queue.running {
+        debug_log!("BG_AI", "Stopping '{}' (process group {})", label, pid);
+        #[cfg(unix)]
+        unsafe {
+            libc::killpg(pid as i32, libc::SIGTERM);
+        }
+    }
+}
+
+pub fn status() -> BackgroundAiStatus {
+    let mut queue = panic_guard::lock_or_recover(&QUEUE, "background AI queue");
+    BackgroundAiStatus {
+        paused: is_paused(&queue),
+        offline: !connectivity::is_online(),
+        queued: queue.jobs.len(),
+        running: queue.running.as_ref().map(|(label, _)| label.clone()),
+        spent_today_usd: spent_today(&mut queue.spend),
+        daily_budget_usd: config::background_ai_daily_budget_usd(),
+    }
+}
+
+/// Block until a job may start, then take it off the queue
+fn next_job() -> Job {
+    let mut queue = panic_guard::lock_or_recover(&QUEUE, "background AI queue");
+    loop {
+        if is_paused(&queue) {
+            // Also catches `backgroundAiEnabled` being switched off in config
+            cancel_all(&mut queue, "Background AI is paused");
+        } else if !queue.jobs.is_empty() && connectivity::is_online() {
+            let budget = config::background_ai_daily_budget_usd();
+            if spent_today(&mut queue.spend) >= budget {
+                cancel_all(&mut queue, &format!("Background AI daily budget (${:.2}) reached", budget));
+                continue;
+            }
+            let wait = queue
+                .last_started
+                .map(|t| config::background_ai_min_interval().saturating_sub(t.elapsed()))
+                .unwrap_or_default();
+            if wait.is_zero() {
+                if let Some(index) = pick_next(&queue.jobs) {
+                    queue.last_started = Some(Instant::now());
+                    return queue.jobs.remove(index);
+                }
+            }
+            queue = WAKE
+                .wait_timeout(queue, wait.max(Duration::from_millis(100)))
+                .map(|(guard, _)| guard)
+                .unwrap_or_else(|e| e.into_inner().0);
+            continue;
+        }
+        queue = WAKE
+            .wait_timeout(queue, IDLE_WAIT)
+            .map(|(guard, _)| guard)
+            .unwrap_or_else(|e| e.into_inner().0);
+    }
+}
+
+/// Run `claude -p` with JSON output and read the result and its cost
+fn execute(request: &JobRequest) -> Result<JobOutput, String> {
+    let mut args = vec![
+        "-p".to_string(),
+        request.prompt.clone(),
+        "--output-format".to_string(),
+        "json".to_string(),
+    ];
+    if let Some(ref model) = request.model {
+        args.push("--model".to_string());
+        args.push(model.clone());
+    }
+    let escaped: Vec<String> = args.iter().map(|a| format!("'{}'", a.replace('\'', "'\"'\"'"))).collect();
+    let command = format!("{} {}", config::claude_binary(), escaped.join(" "));
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+
+    let start = Instant::now();
+    let mut shell = Command::new(&shell);
+    shell
+        .envs(network::spawn_env())
+        .envs(providers::spawn_env(None)?)
+        .args(["-l", "-c", &command])
+        .current_dir(&request.working_directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // Own process group, so stopping the job stops claude and not just the
+    // login shell that started it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+    let mut child = shell.spawn().map_err(|e| format!("Failed to spawn claude: {}", e))?;
+    panic_guard::lock_or_recover(&QUEUE, "background AI queue").running = Some((request.label.clone(), child.id()));
+
+    let mut stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+    let reader = std::thread::spawn(move || {
+        let mut buf = String::new();
+        let _ = stdout.read_to_string(&mut buf);
+        buf
+    });
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) if start.elapsed() > request.timeout => {
+                #[cfg(unix)]
+                unsafe {
+                    libc::killpg(child.id() as i32, libc::SIGKILL);
+                }
+                let _ = child.kill();
+                let _ = child.wait();
+                break Err(format!("Timed out after {}s", request.timeout.as_secs()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(200)),
+            Err(e) => break Err(format!("Failed to wait for claude: {}", e)),
+        }
+    };
+    panic_guard::lock_or_recover(&QUEUE, "background AI queue").running = None;
+    let status = status?;
+    let output = reader.join().map_err(|_| "Output reader panicked".to_string())?;
+
+    let json: serde_json::Value = serde_json::from_str(output.trim())
+        .map_err(|_| format!("claude exited with {:?} without a result", status.code()))?;
+    let cost_usd = json.get("total_cost_usd").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    // Failed jobs are billed too, so this comes before the error check
+    #[cfg(feature = "analytics")]
+    cost_ledger::record_turn(
+        &format!("background:{}", request.label),
+        json.get("session_id").and_then(|v| v.as_str()).map(str::to_string),
+        &request.working_directory,
+        &json,
+        cost_usd,
+    );
+    let text = json.get("result").and_then(|v| v.as_str()).unwrap_or("").trim().to_string();
+    if !status.success() || json.get("is_error").and_then(|v| v.as_bool()) == Some(true) {
+        return Err(format!("claude failed: {}", text));
+    }
+    Ok(JobOutput { text, cost_usd })
+}
+
+fn record_cost(cost_usd: f64) {
+    let mut queue = panic_guard::lock_or_recover(&QUEUE, "background AI queue");
+    spent_today(&mut queue.spend);
+    queue.spend.usd += cost_usd;
+    save_spend(&queue.spend);
+}
+
+/// Start the supervised worker
+pub fn spawn() {
+    supervisor::spawn_thread("backgroundAi", || loop {
+        let job = next_job();
+        debug_log!("BG_AI", "Running '{}'", job.request.label);
+        let result = execute(&job.request);
+        match result {
+            Ok(ref output) => {
+                debug_log!("BG_AI", "'{}' done (${:.4})", job.request.label, output.cost_usd);
+                record_cost(output.cost_usd);
+            }
+            Err(ref e) => debug_log!("BG_AI", "'{}' failed: {}", job.request.label, e),
+        }
+        let _ = job.reply.send(result);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: u64, priority: Priority) -> Job {
+        Job {
+            id,
+            request: JobRequest {
+                label: id.to_string(),
+                prompt: String::new(),
+                working_directory: String::new(),
+                model: None,
+                priority,
+                timeout: Duration::from_secs(1),
+            },
+            reply: mpsc::channel().0,
+        }
+    }
+
+    #[test]
+    fn runs_highest_priority_oldest_first_and_evicts_lowest_newest() {
+        let jobs = vec![job(0, Priority::Low), job(1, Priority::High), job(2, Priority::High), job(3, Priority::Low)];
+        assert_eq!(pick_next(&jobs), Some(1));
+        assert_eq!(pick_evicted(&jobs, Priority::Normal), Some(3));
+        assert_eq!(pick_evicted(&jobs, Priority::Low), None);
+    }
+}
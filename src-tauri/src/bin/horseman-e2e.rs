@@ -0,0 +1,6 @@
+//! Smoke test against the real Claude CLI. Run from src-tauri/ with
+//! `cargo run --features e2e --bin horseman-e2e`.
+
+fn main() {
+    std::process::exit(horseman_lib::e2e::run())
+}
@@ -0,0 +1,127 @@
+//! Spend thresholds (`dailyBudgetUsd`, `monthlyBudgetUsd`) checked against
+//! the cost ledger after every turn. Passing `budgetWarningPercent` of a
+//! limit sends `budget.warning` and reaching it `budget.exceeded`, each once
+//! per day or month. With `budgetConfirmSpawn`, new sessions are refused
+//! while over budget unless the spawn is confirmed.
+
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, BudgetPeriod};
+use crate::panic_guard;
+use crate::store::{self, CostGrouping};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Level {
+    Ok,
+    Warning,
+    Exceeded,
+}
+
+/// Highest level announced per period, with the day or month it was for
+static ANNOUNCED: Lazy<Mutex<HashMap<BudgetPeriod, (String, Level)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn level(spent_usd: f64, limit_usd: f64, warning_percent: u32) -> Level {
+    if spent_usd >= limit_usd {
+        Level::Exceeded
+    } else if spent_usd >= limit_usd * f64::from(warning_percent) / 100.0 {
+        Level::Warning
+    } else {
+        Level::Ok
+    }
+}
+
+/// (period key, first day) of the current day or month, local time
+fn current(period: BudgetPeriod) -> (String, String) {
+    let today = chrono::Local::now().date_naive();
+    match period {
+        BudgetPeriod::Daily => (today.format("%Y-%m-%d").to_string(), today.format("%Y-%m-%d").to_string()),
+        BudgetPeriod::Monthly => (today.format("%Y-%m").to_string(), today.format("%Y-%m-01").to_string()),
+    }
+}
+
+/// Configured limits with what has been spent against them so far
+fn spending() -> Result<Vec<(BudgetPeriod, String, f64, f64)>, String> {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let limits = [
+        (BudgetPeriod::Daily, config::daily_budget_usd()),
+        (BudgetPeriod::Monthly, config::monthly_budget_usd()),
+    ];
+    let mut spending = Vec::new();
+    for (period, limit) in limits {
+        let limit = match limit {
+            Some(limit) => limit,
+            None => continue,
+        };
+        let (key, from) = current(period);
+        let spent = store::cost_totals(&from, &today, None, CostGrouping::Day)?.iter().map(|t| t.cost_usd).sum();
+        spending.push((period, key, spent, limit));
+    }
+    Ok(spending)
+}
+
+/// Announce newly crossed thresholds; called after each turn is recorded
+pub fn check(app: &AppHandle) {
+    let spending = match spending() {
+        Ok(spending) => spending,
+        Err(e) => {
+            debug_log!("BUDGET", "Failed to read spend: {}", e);
+            return;
+        }
+    };
+    let warning_percent = config::budget_warning_percent();
+    for (period, key, spent_usd, limit_usd) in spending {
+        let reached = level(spent_usd, limit_usd, warning_percent);
+        {
+            let mut announced = panic_guard::lock_or_recover(&ANNOUNCED, "budget alerts");
+            let previous = match announced.get(&period) {
+                Some((announced_key, level)) if *announced_key == key => *level,
+                _ => Level::Ok,
+            };
+            if reached <= previous {
+                continue;
+            }
+            announced.insert(period, (key, reached));
+        }
+        debug_log!("BUDGET", "{:?} spend ${:.2} of ${:.2}: {:?}", period, spent_usd, limit_usd, reached);
+        let event = match reached {
+            Level::Exceeded => BackendEvent::BudgetExceeded { period, spent_usd, limit_usd },
+            _ => BackendEvent::BudgetWarning { period, spent_usd, limit_usd },
+        };
+        let _ = events::emit(app, event);
+    }
+}
+
+/// Refuse a new session while over budget, if `budgetConfirmSpawn` is on
+/// and the caller hasn't confirmed
+pub fn check_spawn(confirmed: bool) -> Result<(), String> {
+    if confirmed || !config::budget_confirm_spawn() {
+        return Ok(());
+    }
+    let over = spending()?.into_iter().find(|(_, _, spent, limit)| spent >= limit);
+    match over {
+        Some((period, _, spent, limit)) => Err(format!(
+            "{} budget exceeded (${:.2} of ${:.2} spent). Confirm to start the session anyway.",
+            period.label(),
+            spent,
+            limit
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_follow_the_thresholds() {
+        assert_eq!(level(1.0, 10.0, 80), Level::Ok);
+        assert_eq!(level(8.0, 10.0, 80), Level::Warning);
+        assert_eq!(level(10.0, 10.0, 80), Level::Exceeded);
+        assert!(Level::Exceeded > Level::Warning && Level::Warning > Level::Ok);
+    }
+}
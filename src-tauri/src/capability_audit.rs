@@ -0,0 +1,138 @@
+//! Reports which sensitive webview capabilities the app ships with, so the
+//! app's own attack surface is visible (logged at startup and queryable).
+
+use crate::debug_log;
+use serde::Serialize;
+
+const CAPABILITIES: &str = include_str!("../capabilities/default.json");
+const TAURI_CONF: &str = include_str!("../tauri.conf.json");
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SensitiveCapability {
+    pub identifier: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityAudit {
+    pub total_permissions: usize,
+    pub sensitive: Vec<SensitiveCapability>,
+}
+
+/// Why a permission is sensitive, if it is
+fn sensitivity(identifier: &str, unscoped: bool) -> Option<String> {
+    let reason = if identifier.starts_with("shell:") {
+        "webview can spawn or control processes"
+    } else if identifier.starts_with("fs:") {
+        if unscoped {
+            "webview has unscoped filesystem access"
+        } else {
+            "webview has filesystem access"
+        }
+    } else if identifier == "process:allow-exit" || identifier == "process:allow-restart" {
+        "webview can exit or restart the app"
+    } else if identifier == "updater:allow-download-and-install" {
+        "webview can install updates"
+    } else if identifier == "clipboard-manager:allow-read-text" {
+        "webview can read the clipboard"
+    } else {
+        return None;
+    };
+    Some(reason.to_string())
+}
+
+fn audit_json(capabilities: &str, tauri_conf: &str) -> Result<CapabilityAudit, String> {
+    let caps: serde_json::Value =
+        serde_json::from_str(capabilities).map_err(|e| format!("Invalid capabilities file: {}", e))?;
+    let permissions = caps
+        .get("permissions")
+        .and_then(|p| p.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut sensitive = Vec::new();
+    for permission in &permissions {
+        let (identifier, unscoped) = match permission {
+            serde_json::Value::String(id) => (id.as_str(), false),
+            serde_json::Value::Object(obj) => {
+                let id = obj.get("identifier").and_then(|i| i.as_str()).unwrap_or("");
+                let unscoped = obj
+                    .get("allow")
+                    .and_then(|a| a.as_array())
+                    .map(|scopes| {
+                        scopes
+                            .iter()
+                            .any(|s| s.get("path").and_then(|p| p.as_str()) == Some("**"))
+                    })
+                    .unwrap_or(false);
+                (id, unscoped)
+            }
+            _ => continue,
+        };
+        if let Some(reason) = sensitivity(identifier, unscoped) {
+            sensitive.push(SensitiveCapability { identifier: identifier.to_string(), reason });
+        }
+    }
+
+    let conf: serde_json::Value = serde_json::from_str(tauri_conf).unwrap_or_default();
+    if conf.pointer("/app/security/csp").map(|c| c.is_null()).unwrap_or(true) {
+        sensitive.push(SensitiveCapability {
+            identifier: "app.security.csp".to_string(),
+            reason: "no Content Security Policy".to_string(),
+        });
+    }
+    if conf.pointer("/app/withGlobalTauri").and_then(|v| v.as_bool()) == Some(true) {
+        sensitive.push(SensitiveCapability {
+            identifier: "app.withGlobalTauri".to_string(),
+            reason: "Tauri API exposed on window.__TAURI__".to_string(),
+        });
+    }
+
+    Ok(CapabilityAudit { total_permissions: permissions.len(), sensitive })
+}
+
+/// Audit the capabilities this build was compiled with
+pub fn audit() -> Result<CapabilityAudit, String> {
+    audit_json(CAPABILITIES, TAURI_CONF)
+}
+
+/// Log the audit at startup
+pub fn log_startup_audit() {
+    match audit() {
+        Ok(report) => {
+            debug_log!(
+                "SECURITY",
+                "{} capability permissions, {} sensitive",
+                report.total_permissions,
+                report.sensitive.len()
+            );
+            for cap in &report.sensitive {
+                debug_log!("SECURITY", "  {}: {}", cap.identifier, cap.reason);
+            }
+        }
+        Err(e) => debug_log!("SECURITY", "Capability audit failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_sensitive_permissions() {
+        let caps = r#"{"permissions": [
+            "core:default",
+            "shell:allow-spawn",
+            {"identifier": "fs:allow-read-file", "allow": [{"path": "**"}]},
+            "dialog:allow-open"
+        ]}"#;
+        let conf = r#"{"app": {"security": {"csp": "default-src 'self'"}}}"#;
+        let report = audit_json(caps, conf).unwrap();
+        assert_eq!(report.total_permissions, 4);
+        let ids: Vec<&str> = report.sensitive.iter().map(|c| c.identifier.as_str()).collect();
+        assert_eq!(ids, vec!["shell:allow-spawn", "fs:allow-read-file"]);
+        assert_eq!(report.sensitive[1].reason, "webview has unscoped filesystem access");
+    }
+}
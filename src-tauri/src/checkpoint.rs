@@ -0,0 +1,253 @@
+//! Undo journal for workspace files Horseman itself changes (applied
+//! suggested edits, CLAUDE.md writes), independent of git so it also works
+//! outside repositories. Undone entries stay in the journal as the redo
+//! stack until a new change is recorded.
+
+use crate::atomic_file;
+use crate::config;
+use crate::debug_log;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// A single file mutation made by Horseman (not by Claude's own tools).
+/// Stores the full before/after contents so the change can be undone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointEntry {
+    pub id: String,
+    pub path: String,
+    /// File contents before the change (None = file did not exist)
+    pub before: Option<String>,
+    /// File contents after the change (None = file was deleted)
+    pub after: Option<String>,
+    /// Short description, e.g. "Apply suggested edit"
+    pub label: String,
+    pub created_at: String,
+    /// Set while the change is undone (it can then be redone)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undone_at: Option<String>,
+}
+
+/// Directory holding one JSON file per checkpoint entry
+fn checkpoints_dir() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|d| d.join("checkpoints"))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+/// The journal kept in a directory (`checkpoints_dir()` outside tests)
+struct Journal(PathBuf);
+
+impl Journal {
+    fn open() -> Result<Self, String> {
+        checkpoints_dir().map(Journal)
+    }
+
+    fn entry_path(&self, id: &str) -> PathBuf {
+        self.0.join(format!("{}.json", id))
+    }
+
+    fn save(&self, entry: &CheckpointEntry) -> Result<(), String> {
+        let content = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize checkpoint: {}", e))?;
+        atomic_file::write(&self.entry_path(&entry.id), content)
+            .map_err(|e| format!("Failed to write checkpoint: {}", e))
+    }
+
+    fn record(
+        &self,
+        path: &Path,
+        before: Option<String>,
+        after: Option<String>,
+        label: &str,
+    ) -> Result<CheckpointEntry, String> {
+        fs::create_dir_all(&self.0)
+            .map_err(|e| format!("Failed to create checkpoints directory: {}", e))?;
+
+        for undone in self.load_all().into_iter().filter(|e| e.undone_at.is_some()) {
+            let _ = fs::remove_file(self.entry_path(&undone.id));
+        }
+
+        let entry = CheckpointEntry {
+            id: Uuid::new_v4().to_string(),
+            path: path.to_string_lossy().to_string(),
+            before,
+            after,
+            label: label.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            undone_at: None,
+        };
+        self.save(&entry)?;
+
+        debug_log!("CHECKPOINT", "Recorded {} for {:?} ({})", entry.id, path, label);
+        Ok(entry)
+    }
+
+    /// Load an entry by ID
+    fn load(&self, id: &str) -> Result<CheckpointEntry, String> {
+        let content = fs::read_to_string(self.entry_path(id))
+            .map_err(|e| format!("Checkpoint not found: {} ({})", id, e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse checkpoint {}: {}", id, e))
+    }
+
+    /// Every entry, undone ones included, newest first
+    fn load_all(&self) -> Vec<CheckpointEntry> {
+        let entries = match fs::read_dir(&self.0) {
+            Ok(e) => e,
+            Err(_) => return vec![],
+        };
+
+        let mut checkpoints: Vec<CheckpointEntry> = entries
+            .flatten()
+            .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some("json"))
+            .filter_map(|e| fs::read_to_string(e.path()).ok())
+            .filter_map(|c| serde_json::from_str(&c).ok())
+            .collect();
+
+        checkpoints.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        checkpoints
+    }
+
+    fn list(&self) -> Vec<CheckpointEntry> {
+        self.load_all().into_iter().filter(|e| e.undone_at.is_none()).collect()
+    }
+
+    fn undo(&self, id: &str, force: bool) -> Result<CheckpointEntry, String> {
+        let mut entry = self.load(id)?;
+        if entry.undone_at.is_some() {
+            return Err(format!("Checkpoint {} is already undone", id));
+        }
+        let path = PathBuf::from(&entry.path);
+
+        let current = fs::read_to_string(&path).ok();
+        if !force && current != entry.after {
+            return Err(format!(
+                "{} changed since checkpoint {} was taken; refusing to overwrite",
+                entry.path, id
+            ));
+        }
+
+        restore(&path, entry.before.as_deref())?;
+        entry.undone_at = Some(chrono::Utc::now().to_rfc3339());
+        self.save(&entry)?;
+
+        debug_log!("CHECKPOINT", "Undid {} for {}", id, entry.path);
+        Ok(entry)
+    }
+
+    fn redo_last(&self, force: bool) -> Result<CheckpointEntry, String> {
+        let mut entry = self
+            .load_all()
+            .into_iter()
+            .filter(|e| e.undone_at.is_some())
+            .max_by(|a, b| a.undone_at.cmp(&b.undone_at))
+            .ok_or("Nothing to redo")?;
+        let path = PathBuf::from(&entry.path);
+
+        let current = fs::read_to_string(&path).ok();
+        if !force && current != entry.before {
+            return Err(format!("{} changed since it was undone; refusing to overwrite", entry.path));
+        }
+
+        restore(&path, entry.after.as_deref())?;
+        entry.undone_at = None;
+        self.save(&entry)?;
+
+        debug_log!("CHECKPOINT", "Redid {} for {}", entry.id, entry.path);
+        Ok(entry)
+    }
+}
+
+/// Record a file change in the checkpoint journal. A new change discards
+/// whatever could be redone. Record before writing the file, so a change
+/// is never made without a way back.
+pub fn record(path: &Path, before: Option<String>, after: Option<String>, label: &str) -> Result<CheckpointEntry, String> {
+    Journal::open()?.record(path, before, after, label)
+}
+
+/// Drop an entry whose change was never made (the write after `record` failed)
+pub fn discard(id: &str) {
+    if let Ok(journal) = Journal::open() {
+        let _ = fs::remove_file(journal.entry_path(id));
+    }
+}
+
+/// Write `contents` to `path` (atomically, like Horseman's other writes),
+/// or remove it for None
+fn restore(path: &Path, contents: Option<&str>) -> Result<(), String> {
+    match contents {
+        Some(contents) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+            }
+            atomic_file::write(path, contents).map_err(|e| format!("Failed to restore {:?}: {}", path, e))
+        }
+        None if path.exists() => fs::remove_file(path).map_err(|e| format!("Failed to remove {:?}: {}", path, e)),
+        None => Ok(()),
+    }
+}
+
+/// List the changes that can be undone, newest first
+pub fn list() -> Vec<CheckpointEntry> {
+    Journal::open().map(|journal| journal.list()).unwrap_or_default()
+}
+
+/// Undo a checkpointed change by restoring the "before" contents.
+/// Refuses if the file was modified since the checkpoint, unless `force` is set.
+pub fn undo(id: &str, force: bool) -> Result<CheckpointEntry, String> {
+    Journal::open()?.undo(id, force)
+}
+
+/// Undo the newest change that isn't undone yet
+pub fn undo_last(force: bool) -> Result<CheckpointEntry, String> {
+    let journal = Journal::open()?;
+    let entry = journal.list().into_iter().next().ok_or("Nothing to undo")?;
+    journal.undo(&entry.id, force)
+}
+
+/// Re-apply the most recently undone change. Refuses if the file was
+/// modified since the undo, unless `force` is set.
+pub fn redo_last(force: bool) -> Result<CheckpointEntry, String> {
+    Journal::open()?.redo_last(force)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoes_and_redoes_changes() {
+        let root = std::env::temp_dir().join(format!("horseman-checkpoint-{}", Uuid::new_v4()));
+        let journal = Journal(root.join("checkpoints"));
+        let file = root.join("work/CLAUDE.md");
+
+        let created = journal.record(&file, None, Some("v1".to_string()), "Write CLAUDE.md").unwrap();
+        restore(&file, Some("v1")).unwrap();
+        journal.record(&file, Some("v1".to_string()), Some("v2".to_string()), "Write CLAUDE.md").unwrap();
+        restore(&file, Some("v2")).unwrap();
+
+        let newest = journal.list()[0].id.clone();
+        journal.undo(&newest, false).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+        assert_eq!(journal.list().len(), 1);
+
+        // Edited since the undo: redo refuses unless forced
+        fs::write(&file, "edited").unwrap();
+        assert!(journal.redo_last(false).is_err());
+        journal.redo_last(true).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+
+        journal.undo(&newest, false).unwrap();
+        journal.undo(&created.id, false).unwrap();
+        assert!(!file.exists());
+        assert!(journal.undo(&created.id, false).is_err());
+
+        // A new change drops the redo stack
+        journal.record(&file, None, Some("v3".to_string()), "Apply suggested edit").unwrap();
+        assert_eq!(journal.load_all().len(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+//! Horseman's own per-session records (notes, pins, permission decisions,
+//! verification results, slash commands), kept in a sidecar JSONL next to
+//! Claude's transcript, never inside it, and merged into parse results.
+
+use super::raw_log::safe_file_stem;
+use crate::atomic_file;
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+/// Serializes appends and rewrites across threads
+static WRITE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnnotationKind {
+    Note,
+    Pin,
+    PermissionDecision,
+    Verification,
+    /// A slash command run in a PTY (`/compact`, `/clear`, ...)
+    SlashCommand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    pub id: String,
+    pub kind: AnnotationKind,
+    /// Unix millis
+    pub created_at: i64,
+    /// Transcript message the annotation is attached to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Kind-specific payload (decision, verification steps, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Annotation {
+    pub fn new(kind: AnnotationKind) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            kind,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            message_id: None,
+            tool_use_id: None,
+            text: None,
+            data: None,
+        }
+    }
+}
+
+/// `<artifacts>/annotations/<claude_session_id>.jsonl`
+fn annotations_path(claude_session_id: &str) -> Result<PathBuf, String> {
+    let dir = config::artifacts_dir()
+        .map(|d| d.join("annotations"))
+        .ok_or_else(|| "Could not determine artifacts directory".to_string())?;
+    Ok(dir.join(format!("{}.jsonl", safe_file_stem(claude_session_id)?)))
+}
+
+/// Append an annotation to a session's sidecar file
+pub fn append(claude_session_id: &str, annotation: &Annotation) -> Result<(), String> {
+    let path = annotations_path(claude_session_id)?;
+    let line = serde_json::to_string(annotation)
+        .map_err(|e| format!("Failed to serialize annotation: {}", e))?;
+
+    let _guard = panic_guard::lock_or_recover(&WRITE_LOCK, "annotations");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create annotations directory: {}", e))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write annotation: {}", e))
+}
+
+/// All annotations for a session, oldest first. Unreadable lines are skipped.
+pub fn load(claude_session_id: &str) -> Vec<Annotation> {
+    let content = match annotations_path(claude_session_id).and_then(|p| {
+        fs::read_to_string(&p).map_err(|e| e.to_string())
+    }) {
+        Ok(c) => c,
+        Err(_) => return vec![],
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Sidecar file of the session whose transcript is `transcript_path`
+pub fn path_for_transcript(transcript_path: &Path) -> Option<PathBuf> {
+    transcript_path.file_stem().and_then(|s| s.to_str()).and_then(|id| annotations_path(id).ok())
+}
+
+/// Annotations of the session whose transcript is `transcript_path`
+pub fn for_transcript(transcript_path: &Path) -> Vec<Annotation> {
+    transcript_path.file_stem().and_then(|s| s.to_str()).map(load).unwrap_or_default()
+}
+
+/// Remove one annotation by ID. Returns whether it existed.
+pub fn remove(claude_session_id: &str, annotation_id: &str) -> Result<bool, String> {
+    let path = annotations_path(claude_session_id)?;
+    let _guard = panic_guard::lock_or_recover(&WRITE_LOCK, "annotations");
+    let mut annotations = load(claude_session_id);
+    let before = annotations.len();
+    annotations.retain(|a| a.id != annotation_id);
+    if annotations.len() == before {
+        return Ok(false);
+    }
+
+    let mut content = String::new();
+    for a in &annotations {
+        let line = serde_json::to_string(a)
+            .map_err(|e| format!("Failed to serialize annotation: {}", e))?;
+        content.push_str(&line);
+        content.push('\n');
+    }
+    atomic_file::write(&path, content)
+        .map_err(|e| format!("Failed to rewrite {:?}: {}", path, e))?;
+    Ok(true)
+}
+
+/// Delete a session's sidecar file. Returns whether it existed.
+pub fn remove_all(claude_session_id: &str) -> Result<bool, String> {
+    let path = annotations_path(claude_session_id)?;
+    let _guard = panic_guard::lock_or_recover(&WRITE_LOCK, "annotations");
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(format!("Failed to remove {:?}: {}", path, e)),
+    }
+}
+
+/// Record a backend-originated annotation for a running UI session. Dropped
+/// (with a log line) if the Claude session ID isn't known yet.
+pub fn record_for_ui_session(app: &AppHandle, ui_session_id: &str, annotation: Annotation) {
+    let claude_session_id = app
+        .try_state::<ClaudeState>()
+        .and_then(|state| state.lock().claude_session_id(ui_session_id));
+    let result = match claude_session_id {
+        Some(id) => append(&id, &annotation),
+        None => Err("no Claude session ID yet".to_string()),
+    };
+    if let Err(e) = result {
+        debug_log!("ANNOTATIONS", "[{}] Dropped {:?} annotation: {}", ui_session_id, annotation.kind, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_without_empty_fields() {
+        let mut note = Annotation::new(AnnotationKind::Note);
+        note.text = Some("check this".to_string());
+        let line = serde_json::to_string(&note).unwrap();
+        assert!(line.contains(r#""kind":"note""#));
+        assert!(!line.contains("messageId"));
+        let parsed: Annotation = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.text.as_deref(), Some("check this"));
+    }
+}
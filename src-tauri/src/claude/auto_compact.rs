@@ -0,0 +1,126 @@
+//! Automatic compaction between turns. Once a turn ends with context usage
+//! above `autoCompactPercent`, `/compact` is sent as the next turn (the
+//! headless equivalent of the slash command) and `session.compacting` /
+//! `session.compacted` bracket it, so long sessions don't hit the context
+//! limit mid-turn.
+
+use super::transcript_backup;
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use crate::panic_guard;
+use crate::verification::fix_loop;
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+pub const COMPACT_COMMAND: &str = "/compact";
+/// How long to wait for a one-shot process to exit before resuming it
+const EXIT_WAIT: Duration = Duration::from_secs(10);
+
+/// UI sessions whose current turn is a compaction
+static COMPACTING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn over_threshold(percent: f64, threshold: Option<u32>) -> bool {
+    threshold.is_some_and(|threshold| percent >= f64::from(threshold))
+}
+
+/// Called when a turn's result arrives. Returns whether that turn was a
+/// compaction, finishing it with `session.compacted`.
+pub fn on_result(app: &AppHandle, ui_session_id: &str, error: Option<String>) -> bool {
+    if !panic_guard::lock_or_recover(&COMPACTING, "auto compact").remove(ui_session_id) {
+        return false;
+    }
+    debug_log!("COMPACT", "[{}] Compaction finished (error: {:?})", ui_session_id, error);
+    let _ = events::emit(
+        app,
+        BackendEvent::SessionCompacted {
+            ui_session_id: ui_session_id.to_string(),
+            error,
+        },
+    );
+    true
+}
+
+/// Compact after a turn that left context usage at `percent`, if that's over
+/// the threshold. Fix loops are left alone, since they send the next turn.
+pub fn maybe_compact(app: &AppHandle, ui_session_id: &str, percent: f64, persistent: bool) {
+    if !over_threshold(percent, config::auto_compact_percent()) || fix_loop::is_running(ui_session_id) {
+        return;
+    }
+    if !panic_guard::lock_or_recover(&COMPACTING, "auto compact").insert(ui_session_id.to_string()) {
+        return;
+    }
+    debug_log!("COMPACT", "[{}] Context at {:.1}%, compacting", ui_session_id, percent);
+    let _ = events::emit(
+        app,
+        BackendEvent::SessionCompacting {
+            ui_session_id: ui_session_id.to_string(),
+            context_percent: percent,
+        },
+    );
+
+    // Turns the user sends meanwhile take precedence over compacting
+    let turn = app.state::<ClaudeState>().lock().turn(ui_session_id);
+    let app = app.clone();
+    let ui_session_id = ui_session_id.to_string();
+    std::thread::spawn(move || {
+        // One-shot processes exit right after their result; resume once they have
+        let deadline = Instant::now() + EXIT_WAIT;
+        let state = app.state::<ClaudeState>();
+        let result = loop {
+            let mut manager = state.lock();
+            if manager.turn(&ui_session_id) != turn {
+                break Err("Skipped: a newer turn started".to_string());
+            }
+            if persistent || !manager.is_running(&ui_session_id) {
+                // Copying the transcripts can take a while, so not under the lock
+                let claude_session_id = manager.claude_session_id(&ui_session_id);
+                drop(manager);
+                if let Some(claude_session_id) = claude_session_id {
+                    transcript_backup::before_command(&claude_session_id, COMPACT_COMMAND);
+                }
+                let mut manager = state.lock();
+                if manager.turn(&ui_session_id) != turn {
+                    break Err("Skipped: a newer turn started".to_string());
+                }
+                break manager.compact_session(&app, &ui_session_id);
+            }
+            if Instant::now() >= deadline {
+                break Err("Skipped: the session is still running".to_string());
+            }
+            drop(manager);
+            std::thread::sleep(Duration::from_millis(100));
+        };
+        if let Err(e) = result {
+            debug_log!("COMPACT", "[{}] Not compacting: {}", ui_session_id, e);
+            on_result(&app, &ui_session_id, Some(e));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::SessionUsage;
+
+    #[test]
+    fn compacts_past_the_threshold() {
+        let usage = SessionUsage {
+            input_tokens: 150_000,
+            output_tokens: 5_000,
+            cache_read_tokens: 900_000,
+            cache_creation_tokens: 5_000,
+            context_window: 200_000,
+            cost: None,
+        };
+        let percent = usage.context_percent();
+        assert_eq!(percent, 80.0);
+        assert!(over_threshold(percent, Some(80)));
+        assert!(!over_threshold(percent, Some(85)));
+        assert!(!over_threshold(percent, None));
+    }
+}
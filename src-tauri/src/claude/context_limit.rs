@@ -0,0 +1,113 @@
+//! Warnings as a session nears its context limit. After each turn, context
+//! usage is checked against `contextWarningLevels`; reaching a level sends
+//! `usage.threshold` with an estimate of the turns left at the average
+//! growth per turn so far.
+
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+#[derive(Debug, Default)]
+struct Tracker {
+    last_percent: Option<f64>,
+    /// Summed growth and the number of turns it was measured over, since
+    /// usage last dropped
+    growth_total: f64,
+    growth_turns: u32,
+    /// Highest level already warned about
+    warned: u32,
+}
+
+impl Tracker {
+    /// Record a turn that ended at `percent`; returns the level newly reached
+    fn record(&mut self, percent: f64, levels: &[u32]) -> Option<u32> {
+        let reached = |percent: f64| levels.iter().copied().filter(|l| f64::from(*l) <= percent).max();
+        match self.last_percent {
+            // Compacted or cleared: start over, warning again as levels are reached
+            Some(last) if percent < last => {
+                self.growth_total = 0.0;
+                self.growth_turns = 0;
+                self.warned = reached(percent).unwrap_or(0);
+            }
+            Some(last) => {
+                self.growth_total += percent - last;
+                self.growth_turns += 1;
+            }
+            None => {}
+        }
+        self.last_percent = Some(percent);
+        let level = reached(percent)?;
+        if level <= self.warned {
+            return None;
+        }
+        self.warned = level;
+        Some(level)
+    }
+
+    fn remaining_turns(&self) -> Option<u32> {
+        if self.growth_turns == 0 || self.growth_total <= 0.0 {
+            return None;
+        }
+        let per_turn = self.growth_total / f64::from(self.growth_turns);
+        let left = (100.0 - self.last_percent?).max(0.0);
+        Some((left / per_turn).floor() as u32)
+    }
+}
+
+static TRACKERS: Lazy<Mutex<HashMap<String, Tracker>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Called with the context usage a turn ended at
+pub fn on_turn_end(app: &AppHandle, ui_session_id: &str, percent: f64) {
+    let levels = config::context_warning_levels();
+    let (level, remaining_turns) = {
+        let mut trackers = panic_guard::lock_or_recover(&TRACKERS, "context limit");
+        let tracker = trackers.entry(ui_session_id.to_string()).or_default();
+        match tracker.record(percent, &levels) {
+            Some(level) => (level, tracker.remaining_turns()),
+            None => return,
+        }
+    };
+    debug_log!("USAGE", "[{}] Context at {:.1}% ({:?} turns left)", ui_session_id, percent, remaining_turns);
+    let _ = events::emit(
+        app,
+        BackendEvent::UsageThreshold {
+            ui_session_id: ui_session_id.to_string(),
+            level,
+            context_percent: percent,
+            remaining_turns,
+        },
+    );
+}
+
+/// Drop a removed session's tracking
+pub fn forget(ui_session_id: &str) {
+    panic_guard::lock_or_recover(&TRACKERS, "context limit").remove(ui_session_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_once_per_level_and_estimates_turns() {
+        let levels = [70, 90, 95];
+        let mut tracker = Tracker::default();
+        assert_eq!(tracker.record(50.0, &levels), None);
+        assert_eq!(tracker.remaining_turns(), None);
+        assert_eq!(tracker.record(60.0, &levels), None);
+        assert_eq!(tracker.record(72.0, &levels), Some(70));
+        // 11 points per turn, 28 left
+        assert_eq!(tracker.remaining_turns(), Some(2));
+        assert_eq!(tracker.record(80.0, &levels), None);
+        assert_eq!(tracker.record(96.0, &levels), Some(95));
+        // Compaction drops usage; levels warn again when reached
+        assert_eq!(tracker.record(30.0, &levels), None);
+        assert_eq!(tracker.remaining_turns(), None);
+        assert_eq!(tracker.record(75.0, &levels), Some(70));
+    }
+}
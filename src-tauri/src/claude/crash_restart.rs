@@ -0,0 +1,127 @@
+//! Restart policy for sessions whose process dies mid-turn (see
+//! `crashMaxRetries` / `crashRetryBackoffMs`): the Claude session is resumed
+//! in a fresh process after an exponential backoff.
+
+use super::process::lock_tracking;
+use super::StreamTrackingState;
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Sent to the resumed process in place of the turn that was lost
+pub const RESUME_PROMPT: &str =
+    "The previous process exited unexpectedly in the middle of your last turn. Continue where you left off.";
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How the end of a process should be reported
+pub enum ExitOutcome {
+    /// Ended normally, was interrupted, or was replaced
+    Finished,
+    /// Died mid-turn with no retries left
+    Crashed(Option<i32>),
+    /// Died mid-turn; `session.restarting` was sent and a resume is scheduled
+    Restarting,
+}
+
+/// Delay before restart `attempt` (0-based), or None once `max_retries` are used up
+pub(crate) fn retry_delay(attempt: u32, max_retries: u32, base: Duration) -> Option<Duration> {
+    if attempt >= max_retries {
+        return None;
+    }
+    let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+    Some(base.saturating_mul(factor).min(MAX_BACKOFF))
+}
+
+/// Called by the stdout reader once the process closed stdout, with its
+/// exit code if it exited on its own (see `ClaudeManager::reap_exited`)
+pub fn on_exit(
+    app: &AppHandle,
+    ui_session_id: &str,
+    tracking: &Arc<Mutex<StreamTrackingState>>,
+    exit: Option<Option<i32>>,
+) -> ExitOutcome {
+    let (mid_turn, attempt, claude_session_id) = {
+        let state = lock_tracking(tracking);
+        (state.awaiting_result, state.crash_restarts, state.claude_session_id.clone())
+    };
+    if !mid_turn {
+        return ExitOutcome::Finished;
+    }
+
+    let exit_code = match exit {
+        Some(code) if code != Some(0) => code,
+        _ => return ExitOutcome::Finished,
+    };
+    debug_log!("CRASH", "[{}] Process exited mid-turn (code {:?})", ui_session_id, exit_code);
+
+    let max_retries = config::crash_max_retries();
+    let (claude_session_id, delay) = match (
+        claude_session_id,
+        retry_delay(attempt, max_retries, config::crash_retry_backoff()),
+    ) {
+        (Some(id), Some(delay)) => (id, delay),
+        _ => return ExitOutcome::Crashed(exit_code),
+    };
+
+    if !app.state::<ClaudeState>().lock().mark_restart_pending(ui_session_id, tracking) {
+        return ExitOutcome::Finished;
+    }
+    debug_log!("CRASH", "[{}] Restart {}/{} in {:?}", ui_session_id, attempt + 1, max_retries, delay);
+    let _ = events::emit(
+        app,
+        BackendEvent::SessionRestarting {
+            ui_session_id: ui_session_id.to_string(),
+            claude_session_id,
+            attempt: attempt + 1,
+            max_retries,
+            delay_ms: delay.as_millis() as u64,
+            exit_code,
+        },
+    );
+
+    let app = app.clone();
+    let ui_session_id = ui_session_id.to_string();
+    let tracking = tracking.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let result = app
+            .state::<ClaudeState>()
+            .lock()
+            .resume_after_crash(&app, &ui_session_id, &tracking, attempt + 1);
+        if let Ok(false) = result {
+            debug_log!("CRASH", "[{}] Restart cancelled", ui_session_id);
+        } else if let Err(e) = result {
+            debug_log!("CRASH", "[{}] Restart failed: {}", ui_session_id, e);
+            let _ = events::emit(
+                &app,
+                BackendEvent::SessionEnded {
+                    ui_session_id,
+                    exit_code,
+                    error: Some(format!("Claude exited unexpectedly and could not be restarted: {}", e)),
+                    reason: None,
+                },
+            );
+        }
+    });
+    ExitOutcome::Restarting
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_retries_run_out() {
+        let base = Duration::from_millis(500);
+        assert_eq!(retry_delay(0, 3, base), Some(Duration::from_millis(500)));
+        assert_eq!(retry_delay(2, 3, base), Some(Duration::from_millis(2000)));
+        assert_eq!(retry_delay(3, 3, base), None);
+        assert_eq!(retry_delay(0, 0, base), None);
+        assert_eq!(retry_delay(30, 40, base), Some(MAX_BACKOFF));
+    }
+}
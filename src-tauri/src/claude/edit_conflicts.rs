@@ -0,0 +1,128 @@
+//! Warnings for parallel sessions in one repository. Files each session
+//! edits are remembered until the session is removed; when a session edits
+//! a file that another running session in the same directory has edited,
+//! `workspace.conflict_risk` is sent so the user can step in before one
+//! overwrites the other's changes.
+
+use crate::commands::ClaudeState;
+use crate::events::{self, BackendEvent, ToolCall};
+use crate::hooks::path_scope;
+use crate::panic_guard;
+use crate::verification;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug)]
+struct Touched {
+    working_directory: PathBuf,
+    files: HashSet<PathBuf>,
+}
+
+static TOUCHED: Lazy<Mutex<HashMap<String, Touched>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The file an edit tool call writes, relative paths taken from the
+/// session's working directory
+fn edited_path(tool_name: &str, tool_input: &serde_json::Value, working_directory: &Path) -> Option<PathBuf> {
+    if !verification::EDIT_TOOLS.contains(&tool_name) {
+        return None;
+    }
+    let path = tool_input
+        .get("file_path")
+        .or_else(|| tool_input.get("notebook_path"))
+        .and_then(|v| v.as_str())
+        .filter(|p| !p.is_empty())?;
+    Some(path_scope::normalize(&working_directory.join(path)))
+}
+
+/// Record that `ui_session_id` edited `path`; on its first edit of the
+/// file, returns the other sessions in the same directory that edited it
+fn record(
+    touched: &mut HashMap<String, Touched>,
+    ui_session_id: &str,
+    working_directory: &Path,
+    path: PathBuf,
+) -> Vec<String> {
+    let entry = touched.entry(ui_session_id.to_string()).or_insert_with(|| Touched {
+        working_directory: working_directory.to_path_buf(),
+        files: HashSet::new(),
+    });
+    entry.working_directory = working_directory.to_path_buf();
+    if !entry.files.insert(path.clone()) {
+        return Vec::new();
+    }
+    let mut others: Vec<String> = touched
+        .iter()
+        .filter(|(id, t)| {
+            id.as_str() != ui_session_id && t.working_directory == working_directory && t.files.contains(&path)
+        })
+        .map(|(id, _)| id.clone())
+        .collect();
+    others.sort();
+    others
+}
+
+/// Note a started tool call, warning if it edits a file another running
+/// session has edited
+pub fn observe(app: &AppHandle, ui_session_id: &str, working_directory: &str, tool: &ToolCall) {
+    let working_directory = path_scope::normalize(Path::new(working_directory));
+    let path = match edited_path(&tool.name, &tool.input, &working_directory) {
+        Some(path) => path,
+        None => return,
+    };
+    let others = record(
+        &mut panic_guard::lock_or_recover(&TOUCHED, "edit conflicts"),
+        ui_session_id,
+        &working_directory,
+        path.clone(),
+    );
+    if others.is_empty() {
+        return;
+    }
+    // Only sessions still working can trample this one's edits
+    let others: Vec<String> = {
+        let state = app.state::<ClaudeState>();
+        let mut manager = state.lock();
+        others.into_iter().filter(|id| manager.is_running(id)).collect()
+    };
+    if others.is_empty() {
+        return;
+    }
+    let _ = events::emit(
+        app,
+        BackendEvent::WorkspaceConflictRisk {
+            ui_session_id: ui_session_id.to_string(),
+            working_directory: working_directory.to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            other_session_ids: others,
+        },
+    );
+}
+
+pub fn forget(ui_session_id: &str) {
+    panic_guard::lock_or_recover(&TOUCHED, "edit conflicts").remove(ui_session_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_overlapping_edits_once_per_session() {
+        let edit = |path: &str| serde_json::json!({ "file_path": path });
+        let repo = Path::new("/repo");
+        let main = edited_path("Edit", &edit("src/../src/main.rs"), repo).unwrap();
+        assert_eq!(main, PathBuf::from("/repo/src/main.rs"));
+        assert_eq!(edited_path("Write", &edit("/repo/src/main.rs"), repo), Some(main.clone()));
+        assert_eq!(edited_path("Read", &edit("/repo/src/main.rs"), repo), None);
+
+        let mut touched = HashMap::new();
+        assert!(record(&mut touched, "a", repo, main.clone()).is_empty());
+        assert!(record(&mut touched, "c", Path::new("/other"), main.clone()).is_empty());
+        assert_eq!(record(&mut touched, "b", repo, main.clone()), vec!["a"]);
+        assert!(record(&mut touched, "b", repo, main.clone()).is_empty());
+        assert!(record(&mut touched, "b", repo, PathBuf::from("/repo/lib.rs")).is_empty());
+    }
+}
@@ -0,0 +1,27 @@
+//! Background policy that suspends sessions whose process has gone idle
+//! (see `idleSuspendMinutes`). Suspended sessions resume on the next message.
+//! A quiet process running a tool or waiting on a prompt isn't idle.
+
+use crate::commands::{ClaudeState, HookState};
+use crate::config;
+use crate::hooks;
+use crate::supervisor;
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Start the supervised idle checker
+pub fn spawn(app: AppHandle) {
+    supervisor::spawn_thread("idleSuspend", move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        if let Some(idle) = config::idle_suspend_after() {
+            let awaiting_answer = app
+                .try_state::<HookState>()
+                .map(|hook_state| hooks::sessions_awaiting_answer(&hook_state.0))
+                .unwrap_or_else(HashSet::new);
+            app.state::<ClaudeState>().lock().suspend_idle(&app, idle, &awaiting_answer);
+        }
+    });
+}
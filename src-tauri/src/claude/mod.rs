@@ -1,3 +1,26 @@
+pub mod annotations;
+pub mod auto_compact;
+pub mod context_limit;
+pub mod crash_restart;
+pub mod edit_conflicts;
+pub mod idle_suspend;
+pub mod parse_cache;
 pub mod process;
+pub mod rate_limit;
+pub mod raw_log;
+pub mod reaper;
+pub mod resume_recovery;
+pub mod schema_drift;
+pub mod session_summary;
+pub mod stdout_lines;
+pub mod subagent_usage;
+pub mod suggested_edit;
+pub mod suggestions;
+pub mod tool_catalog;
+pub mod tool_timeline;
+pub mod transcript_backup;
+pub mod transcript_page;
+pub mod transcript_tail;
+pub mod transcript_watch;
 
 pub use process::*;
@@ -0,0 +1,334 @@
+//! Parsed transcripts keyed by path, mtime and size, so reopening a large
+//! session doesn't parse it again. The key also stamps the annotations
+//! sidecar, the subagent transcripts the result was built from and the
+//! redaction settings. The projects-directory watcher drops entries whose
+//! transcript directory changed; with `transcriptDiskCache` on, results are
+//! also kept on disk across restarts. Annotations are merged in after the
+//! lookup, so the result always carries the current ones.
+
+use super::annotations::{self, Annotation};
+use super::process::{
+    extract_agent_id_from_result, parse_transcript_with_subagents, TranscriptParseResult, PARSER_VERSION,
+};
+use crate::atomic_file;
+use crate::config;
+use crate::debug_log;
+use crate::redaction;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Total size of the transcripts whose results are kept in memory; a
+/// parsed result takes roughly as much as the JSONL it came from
+const MEMORY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// What the cached result was parsed from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileKey {
+    modified_at: String,
+    len: u64,
+    /// Redaction settings the result was masked with
+    #[serde(default)]
+    redaction: String,
+    /// mtime and size of the annotations sidecar, empty if there is none
+    #[serde(default)]
+    annotations: String,
+    /// `<agent id>:<mtime>:<size>` of each subagent transcript the result
+    /// loaded; only known after parsing, so checked against the cached result
+    #[serde(default)]
+    subagents: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskEntry {
+    parser_version: u32,
+    key: FileKey,
+    result: TranscriptParseResult,
+}
+
+/// A parse result shared with the cache, plus the session's annotations
+pub struct ParsedTranscript {
+    pub result: Arc<TranscriptParseResult>,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Serializes as a `TranscriptParseResult` with `annotations` filled in
+impl Serialize for ParsedTranscript {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct View<'a> {
+            #[serde(flatten)]
+            result: &'a TranscriptParseResult,
+            annotations: &'a [Annotation],
+        }
+        View {
+            result: &self.result,
+            annotations: &self.annotations,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[derive(Default)]
+struct ParseCache {
+    entries: HashMap<PathBuf, (FileKey, Arc<TranscriptParseResult>)>,
+    /// Least recently used first
+    order: VecDeque<PathBuf>,
+    /// Sum of the cached transcripts' sizes
+    bytes: u64,
+}
+
+impl ParseCache {
+    fn touch(&mut self, path: &Path) {
+        self.order.retain(|p| p != path);
+        self.order.push_back(path.to_path_buf());
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some((key, _)) = self.entries.remove(path) {
+            self.bytes -= key.len;
+        }
+        self.order.retain(|p| p != path);
+    }
+
+    /// Cache `result`, evicting the least recently used results until the
+    /// total fits (the newest is always kept)
+    fn insert(&mut self, path: &Path, key: FileKey, result: Arc<TranscriptParseResult>) {
+        self.remove(path);
+        self.bytes += key.len;
+        self.entries.insert(path.to_path_buf(), (key, result));
+        self.touch(path);
+        while self.bytes > MEMORY_BYTES && self.order.len() > 1 {
+            if let Some(oldest) = self.order.pop_front() {
+                self.remove(&oldest);
+            }
+        }
+    }
+}
+
+static CACHE: Lazy<Mutex<ParseCache>> = Lazy::new(|| Mutex::new(ParseCache::default()));
+
+/// `<mtime>:<size>` of `path`, or `None` if it can't be read
+fn stamp(path: &Path) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified: chrono::DateTime<chrono::Utc> = metadata.modified().ok()?.into();
+    Some(format!("{}:{}", modified.to_rfc3339(), metadata.len()))
+}
+
+/// Key of `path` as it is now, without the subagent stamps
+fn file_key(path: &Path) -> Option<FileKey> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified: chrono::DateTime<chrono::Utc> = metadata.modified().ok()?.into();
+    Some(FileKey {
+        modified_at: modified.to_rfc3339(),
+        len: metadata.len(),
+        redaction: redaction::fingerprint(),
+        annotations: annotations::path_for_transcript(path).and_then(|p| stamp(&p)).unwrap_or_default(),
+        subagents: vec![],
+    })
+}
+
+/// Stamps of the subagent transcripts behind the Task calls in `result`,
+/// nested ones included, as `with_subagents` looks them up
+fn subagent_stamps(transcript_path: &Path, result: &TranscriptParseResult) -> Vec<String> {
+    let dir = match transcript_path.parent() {
+        Some(d) => d,
+        None => return vec![],
+    };
+    let mut agent_ids: Vec<String> = result
+        .messages
+        .iter()
+        .flat_map(|m| m.tool_calls.iter().flatten())
+        .chain(&result.subagent_tools)
+        .filter(|t| t.name == "Task")
+        .filter_map(|t| extract_agent_id_from_result(t.output.as_deref()?))
+        .collect();
+    agent_ids.sort();
+    agent_ids.dedup();
+    agent_ids
+        .into_iter()
+        .map(|id| {
+            let file_stamp = stamp(&dir.join(format!("{}.jsonl", id))).unwrap_or_default();
+            format!("{}:{}", id, file_stamp)
+        })
+        .collect()
+}
+
+/// Whether `cached`, stored under `cached_key`, is still what parsing
+/// `transcript_path` would give for the current `key`
+fn is_fresh(transcript_path: &Path, key: &FileKey, cached_key: &FileKey, cached: &TranscriptParseResult) -> bool {
+    cached_key.modified_at == key.modified_at
+        && cached_key.len == key.len
+        && cached_key.redaction == key.redaction
+        && cached_key.annotations == key.annotations
+        && cached_key.subagents == subagent_stamps(transcript_path, cached)
+}
+
+fn disk_path(transcript_path: &Path) -> Option<PathBuf> {
+    let hash = Sha256::digest(transcript_path.to_string_lossy().as_bytes());
+    config::artifacts_dir().map(|d| d.join("transcript_cache").join(format!("{:x}.json", hash)))
+}
+
+fn load_from_disk(transcript_path: &Path, key: &FileKey) -> Option<TranscriptParseResult> {
+    let content = fs::read_to_string(disk_path(transcript_path)?).ok()?;
+    let entry: DiskEntry = serde_json::from_str(&content).ok()?;
+    (entry.parser_version == PARSER_VERSION && is_fresh(transcript_path, key, &entry.key, &entry.result))
+        .then_some(entry.result)
+}
+
+fn save_to_disk(transcript_path: &Path, key: &FileKey, result: &TranscriptParseResult) {
+    let path = match disk_path(transcript_path) {
+        Some(p) => p,
+        None => return,
+    };
+    let entry = DiskEntry {
+        parser_version: PARSER_VERSION,
+        key: key.clone(),
+        result: result.clone(),
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_string(&entry).map_err(|e| e.to_string()))
+        .and_then(|content| atomic_file::write(&path, content).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        debug_log!("PARSE_CACHE", "Failed to write {:?}: {}", path, e);
+    }
+}
+
+/// `parse_transcript_with_subagents`, reusing the last result while the
+/// transcript, its subagent transcripts, annotations and redaction settings
+/// are unchanged, with current annotations
+pub fn parse(transcript_path: &Path) -> ParsedTranscript {
+    ParsedTranscript {
+        result: parse_shared(transcript_path),
+        annotations: annotations::for_transcript(transcript_path),
+    }
+}
+
+fn parse_shared(transcript_path: &Path) -> Arc<TranscriptParseResult> {
+    let mut key = match file_key(transcript_path) {
+        Some(k) => k,
+        None => return Arc::new(parse_transcript_with_subagents(transcript_path)),
+    };
+
+    if let Ok(mut cache) = CACHE.lock() {
+        let hit = cache
+            .entries
+            .get(transcript_path)
+            .filter(|(k, r)| is_fresh(transcript_path, &key, k, r))
+            .map(|(_, r)| Arc::clone(r));
+        if let Some(result) = hit {
+            cache.touch(transcript_path);
+            debug_log!("PARSE_CACHE", "Memory hit for {:?}", transcript_path);
+            return result;
+        }
+    }
+
+    let disk = config::transcript_disk_cache();
+    let result = match disk.then(|| load_from_disk(transcript_path, &key)).flatten() {
+        Some(result) => {
+            debug_log!("PARSE_CACHE", "Disk hit for {:?}", transcript_path);
+            key.subagents = subagent_stamps(transcript_path, &result);
+            result
+        }
+        None => {
+            let result = parse_transcript_with_subagents(transcript_path);
+            key.subagents = subagent_stamps(transcript_path, &result);
+            if disk {
+                save_to_disk(transcript_path, &key, &result);
+            }
+            result
+        }
+    };
+
+    let result = Arc::new(result);
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.insert(transcript_path, key, Arc::clone(&result));
+    }
+    result
+}
+
+/// Drop cached results affected by a change to `changed`: the transcript
+/// itself and, since subagent transcripts sit next to their parent, every
+/// transcript in the same directory
+pub fn invalidate(changed: &Path) {
+    let dir = changed.parent();
+    if let Ok(mut cache) = CACHE.lock() {
+        let stale: Vec<PathBuf> = cache.entries.keys().filter(|p| p.parent() == dir).cloned().collect();
+        for path in stale {
+            cache.remove(&path);
+        }
+    }
+    if let Some(path) = disk_path(changed) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let key = |len: u64| FileKey {
+            modified_at: String::new(),
+            len,
+            redaction: String::new(),
+            annotations: String::new(),
+            subagents: vec![],
+        };
+        let quarter = MEMORY_BYTES / 4;
+        let path = |i: u64| PathBuf::from(format!("/p/{}.jsonl", i));
+        let mut cache = ParseCache::default();
+        for i in 0..4 {
+            cache.insert(&path(i), key(quarter), Arc::default());
+        }
+        cache.touch(&path(0));
+        cache.insert(&path(4), key(quarter), Arc::default());
+
+        assert!(cache.entries.contains_key(&path(0)));
+        assert!(!cache.entries.contains_key(&path(1)));
+        assert_eq!((cache.entries.len(), cache.bytes), (4, MEMORY_BYTES));
+
+        // A result bigger than the whole budget still replaces the rest
+        cache.insert(&path(5), key(MEMORY_BYTES * 2), Arc::default());
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.bytes, MEMORY_BYTES * 2);
+    }
+
+    #[test]
+    fn stale_when_a_subagent_transcript_changes() {
+        let dir = std::env::temp_dir().join(format!("horseman-parse-cache-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let transcript = dir.join("session.jsonl");
+        fs::write(&transcript, "").unwrap();
+        fs::write(dir.join("agent-1.jsonl"), "{}\n").unwrap();
+        let task = serde_json::from_value(serde_json::json!({
+            "id": "t1",
+            "name": "Task",
+            "input": {},
+            "status": "completed",
+            "output": "{\"agentId\": \"agent-1\"}",
+        }))
+        .unwrap();
+        let result = TranscriptParseResult {
+            subagent_tools: vec![task],
+            ..Default::default()
+        };
+        let mut key = file_key(&transcript).unwrap();
+        key.subagents = subagent_stamps(&transcript, &result);
+        assert!(is_fresh(&transcript, &file_key(&transcript).unwrap(), &key, &result));
+
+        fs::write(dir.join("agent-1.jsonl"), "{}\n{}\n").unwrap();
+        assert!(!is_fresh(&transcript, &file_key(&transcript).unwrap(), &key, &result));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
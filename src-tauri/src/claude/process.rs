@@ -1,25 +1,99 @@
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
 use uuid::Uuid;
+#[cfg(feature = "analytics")]
+use crate::budget;
+use crate::commands::{get_claude_version, ClaudeState};
 use crate::config;
+use crate::connectivity;
+#[cfg(feature = "analytics")]
+use crate::cost_ledger;
 use crate::debug_log;
-use crate::hooks;
+use crate::profile_span;
+use crate::git::{self, GitContext};
+use crate::hooks::{self, mcp_cleanup, mcp_servers};
+use crate::memory_watch;
+use crate::network;
+use crate::open_sessions;
+use crate::providers;
+use crate::redaction;
+use crate::session_cache;
+use crate::session_env::{self, SessionEnvironment};
+use crate::workspace_diff::{self, WorkspaceSnapshot};
+use crate::todo_sync;
+use super::annotations::Annotation;
+use super::{auto_compact, context_limit, rate_limit};
+use super::{crash_restart, raw_log, resume_recovery, schema_drift, session_summary, subagent_usage};
+use super::{edit_conflicts, stdout_lines, tool_catalog, transcript_watch};
+use super::stdout_lines::{StdoutLine, StdoutLines};
+use super::suggestions::{self, TurnOutcome};
+use crate::panic_guard;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use crate::verification::{self, fix_loop};
 use crate::events::{
+    self,
     BackendEvent,
     Message,
+    PermissionMode,
     Question,
+    SessionEndReason,
     SessionUsage,
     SubagentInfo,
     TodoItem,
     ToolCall,
     ToolUpdate,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use chrono::Utc;
+use once_cell::sync::Lazy;
+
+/// Max number of assistant messages kept in the text cache
+const MESSAGE_TEXT_CACHE_SIZE: usize = 500;
+
+/// Recently seen assistant message text keyed by message ID, so commands
+/// like `apply_suggested_edit` can refer to a message without the frontend
+/// shipping its content back.
+#[derive(Default)]
+struct MessageTextCache {
+    texts: HashMap<String, String>,
+    /// Insertion order for eviction
+    order: VecDeque<String>,
+}
+
+static MESSAGE_TEXT_CACHE: Lazy<Mutex<MessageTextCache>> =
+    Lazy::new(|| Mutex::new(MessageTextCache::default()));
+
+/// Remember an assistant message's text (append = merge streamed chunks)
+fn remember_message_text(message_id: &str, text: &str, append: bool) {
+    if text.is_empty() {
+        return;
+    }
+    if let Ok(mut cache) = MESSAGE_TEXT_CACHE.lock() {
+        match cache.texts.get_mut(message_id) {
+            Some(existing) if append => existing.push_str(text),
+            Some(existing) => *existing = text.to_string(),
+            None => {
+                cache.texts.insert(message_id.to_string(), text.to_string());
+                cache.order.push_back(message_id.to_string());
+                while cache.order.len() > MESSAGE_TEXT_CACHE_SIZE {
+                    if let Some(oldest) = cache.order.pop_front() {
+                        cache.texts.remove(&oldest);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Look up the text of a recently seen assistant message
+pub fn cached_message_text(message_id: &str) -> Option<String> {
+    MESSAGE_TEXT_CACHE.lock().ok()?.texts.get(message_id).cloned()
+}
 
 /// State tracked during stream parsing for parent-child tool linking
 #[derive(Debug, Default)]
@@ -28,22 +102,232 @@ pub struct StreamTrackingState {
     pub active_task_stack: Vec<String>,
     /// Map tool_id -> tool_name for lookups
     pub tool_names: HashMap<String, String>,
+    /// Tools started but without a result yet; the process is quiet while
+    /// they run, but not idle
+    pub running_tools: HashSet<String>,
     /// Transcript path for this session (extracted from system event)
     pub transcript_path: Option<PathBuf>,
     /// Claude session ID from system event
     pub claude_session_id: Option<String>,
+    /// Working directory the session was spawned in
+    pub working_directory: String,
+    /// Whether the current turn used a file-editing tool
+    pub turn_edited_files: bool,
+    /// Keep-alive session: a result ends the turn, not the process
+    pub persistent: bool,
+    /// When the process last wrote to stdout (for idle suspend)
+    pub last_activity: Option<Instant>,
+    /// Mode passed to `--permission-mode`, reported on session.started
+    pub permission_mode: Option<PermissionMode>,
+    /// Set from the result event when Claude stopped early (e.g. max turns)
+    pub end_reason: Option<SessionEndReason>,
+    /// Cost already entered in the cost ledger for this process
+    #[cfg(feature = "analytics")]
+    pub recorded_cost: f64,
+    /// Git branch/worktree at spawn time, recorded once the Claude session ID is known
+    pub git_context: Option<GitContext>,
+    /// What the process was started with, recorded once the Claude session ID is known
+    pub environment: Option<SessionEnvironment>,
+    /// Working tree at spawn, captured in the background and recorded once
+    /// the Claude session ID is known if the session doesn't have one yet
+    pub workspace_snapshot: Option<WorkspaceSnapshot>,
+    /// Message currently streaming via `stream_event` deltas
+    pub streaming_message_id: Option<String>,
+    /// Messages whose text/thinking already went out as `message.delta`
+    pub streamed_message_ids: HashSet<String>,
+    /// A turn was sent and its result event hasn't arrived yet
+    pub awaiting_result: bool,
+    /// Crash restarts since the last completed turn
+    pub crash_restarts: u32,
+    /// Set when the process was reaped after exiting on its own (not
+    /// interrupted); `exit_code` is None if a signal ended it
+    pub reaped: bool,
+    pub exit_code: Option<i32>,
+}
+
+/// Lock a session's tracking state. A reader thread that panicked while
+/// holding it must not stop the session (or its transcript fallback) from
+/// updating, so a poisoned lock is recovered.
+pub(crate) fn lock_tracking(tracking: &Mutex<StreamTrackingState>) -> MutexGuard<'_, StreamTrackingState> {
+    panic_guard::lock_or_recover(tracking, "tracking state")
 }
 
 /// State for a single Claude session
 pub struct ClaudeSession {
-    #[allow(dead_code)] // Stored for debugging/future use
     pub ui_session_id: String,
-    #[allow(dead_code)]
     pub working_directory: String,
     pub child: Option<Child>,
     /// Stream tracking state (shared with reader thread)
-    #[allow(dead_code)]
     pub tracking: Arc<Mutex<StreamTrackingState>>,
+    /// Claude session to resume on the next message after an idle suspend
+    pub suspended: Option<String>,
+    /// Live only for keep-alive sessions (`options.persistent`)
+    pub stdin: Option<ChildStdin>,
+    /// Spawn options, reused when a follow-up respawns the process
+    pub options: SessionOptions,
+    /// A crash restart is waiting out its backoff; interrupting cancels it
+    pub pending_restart: bool,
+}
+
+/// Per-spawn options for a Claude process, persisted with open sessions so
+/// a relaunch resumes them the same way
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionOptions {
+    pub model: Option<String>,
+    /// Keep one process alive across turns (`--input-format stream-json`)
+    pub persistent: bool,
+    /// `--permission-mode`; None leaves the CLI default
+    pub permission_mode: Option<PermissionMode>,
+    /// Tools allowed without a permission prompt (`--allowedTools`)
+    pub allowed_tools: Vec<String>,
+    /// Tools Claude may never use (`--disallowedTools`)
+    pub disallowed_tools: Vec<String>,
+    /// `--max-turns`; None falls back to the `maxTurns` config, 0 is unlimited
+    pub max_turns: Option<u32>,
+    /// Extra directories Claude may access (`--add-dir`, once per directory)
+    pub add_dirs: Vec<String>,
+    /// Resume into a new Claude session ID (`--fork-session`) instead of
+    /// appending to the resumed one; only ever applies to one spawn
+    #[serde(skip)]
+    pub fork_session: bool,
+    /// Extended thinking token budget (`MAX_THINKING_TOKENS`); 0 disables
+    /// thinking, None leaves the CLI default
+    pub thinking_budget: Option<u32>,
+    /// Stream text token by token (`--include-partial-messages`)
+    pub partial_messages: bool,
+    /// Provider profile whose env is applied; None uses `activeProvider`
+    pub provider: Option<String>,
+    /// Start a new session even while over budget (`budgetConfirmSpawn`)
+    #[serde(skip)]
+    pub confirm_over_budget: bool,
+}
+
+/// A spawn waiting for a free slot under `maxConcurrentSessions`
+struct QueuedSpawn {
+    ui_session_id: String,
+    working_directory: String,
+    initial_prompt: Option<String>,
+    resume_session: Option<String>,
+    options: SessionOptions,
+}
+
+impl QueuedSpawn {
+    /// Fold a later spawn for the same session into this one: its prompt is
+    /// appended, so nothing the user sent while waiting is lost. A spawn
+    /// with other settings is refused rather than silently applied to both
+    /// prompts or dropped.
+    fn merge(&mut self, newer: QueuedSpawn) -> Result<(), String> {
+        let resume_conflicts = matches!(
+            (&self.resume_session, &newer.resume_session),
+            (Some(queued), Some(newer)) if queued != newer
+        );
+        if newer.working_directory != self.working_directory || newer.options != self.options || resume_conflicts {
+            return Err(format!(
+                "Session {} is already queued with other settings; cancel it or wait for it to start",
+                self.ui_session_id
+            ));
+        }
+        self.resume_session = self.resume_session.take().or(newer.resume_session);
+        self.initial_prompt = match (self.initial_prompt.take(), newer.initial_prompt) {
+            (Some(queued), Some(newer)) => Some(format!("{}\n\n{}", queued, newer)),
+            (queued, newer) => queued.or(newer),
+        };
+        Ok(())
+    }
+}
+
+/// How long the stdout reader waits for the process to exit after EOF
+const EXIT_GRACE: Duration = Duration::from_secs(2);
+/// Extra MCP tool time beyond the permission wait, for the round trip
+const MCP_TOOL_TIMEOUT_MARGIN: Duration = Duration::from_secs(10);
+
+/// What `ClaudeManager::reap_exited` found for a process
+pub enum ReapOutcome {
+    /// Exited on its own with this code (None = killed by a signal)
+    Exited(Option<i32>),
+    Running,
+    /// Interrupted, or the session was respawned or removed
+    Gone,
+}
+
+impl ClaudeSession {
+    /// `try_wait` the child; if it has exited, drop it and record the exit
+    /// code on the tracking state. Returns whether the child is still running.
+    fn reap_child(&mut self) -> bool {
+        let child = match self.child.as_mut() {
+            Some(child) => child,
+            None => return false,
+        };
+        match child.try_wait() {
+            Ok(None) => true,
+            Ok(Some(status)) => {
+                debug_log!("REAPER", "[{}] Process exited with {:?}", self.ui_session_id, status.code());
+                let mut state = lock_tracking(&self.tracking);
+                state.reaped = true;
+                state.exit_code = status.code();
+                drop(state);
+                self.child = None;
+                self.stdin = None;
+                false
+            }
+            Err(e) => {
+                debug_log!("REAPER", "[{}] try_wait failed: {}", self.ui_session_id, e);
+                false
+            }
+        }
+    }
+}
+
+/// Exit code of the process behind `tracking` once it has exited on its own
+/// (usually moments after stdout closes), polled without holding the manager
+/// lock. None if it was interrupted, replaced, or outlived the grace period.
+fn wait_for_exit(app: &AppHandle, ui_session_id: &str, tracking: &Arc<Mutex<StreamTrackingState>>) -> Option<Option<i32>> {
+    let deadline = Instant::now() + EXIT_GRACE;
+    loop {
+        let outcome = app.state::<ClaudeState>().lock().reap_exited(ui_session_id, tracking);
+        match outcome {
+            ReapOutcome::Exited(code) => return Some(code),
+            ReapOutcome::Running if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            _ => return None,
+        }
+    }
+}
+
+/// Snapshot the working tree without holding up the spawn (hashing a big
+/// tree takes a while). Recorded once the Claude session ID is known, by
+/// the system event or here, whichever comes last.
+fn spawn_workspace_capture(ui_session_id: String, working_directory: String, tracking: Arc<Mutex<StreamTrackingState>>) {
+    std::thread::spawn(move || {
+        let snapshot = match workspace_diff::capture(&working_directory) {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+        let claude_session_id = {
+            let mut state = lock_tracking(&tracking);
+            match state.claude_session_id.clone() {
+                Some(id) => id,
+                None => {
+                    state.workspace_snapshot = Some(snapshot);
+                    return;
+                }
+            }
+        };
+        if let Err(e) = session_cache::record_workspace_snapshot(&claude_session_id, &snapshot) {
+            debug_log!("SPAWN", "[{}] Failed to record workspace snapshot: {}", ui_session_id, e);
+        }
+    });
+}
+
+/// Write one user turn to a keep-alive session's stdin
+fn write_user_message(stdin: &mut ChildStdin, content: &str) -> Result<(), String> {
+    let message = serde_json::json!({
+        "type": "user",
+        "message": { "role": "user", "content": content },
+    });
+    writeln!(stdin, "{}", message)
+        .and_then(|_| stdin.flush())
+        .map_err(|e| format!("Failed to write to Claude stdin: {}", e))
 }
 
 /// Manager for all Claude sessions
@@ -53,6 +337,14 @@ pub struct ClaudeManager {
     callback_port: Option<u16>,
     /// Path to horseman-mcp binary
     mcp_binary_path: Option<String>,
+    /// Spawns held back by `maxConcurrentSessions`, oldest first
+    queue: VecDeque<QueuedSpawn>,
+    /// MCP config each session's process was spawned with; sessions in the
+    /// same directory share one file
+    mcp_configs: HashMap<String, PathBuf>,
+    /// Turns started per session, so work scheduled after a turn can tell
+    /// whether another has started since
+    turns: HashMap<String, u64>,
 }
 
 impl ClaudeManager {
@@ -61,9 +353,21 @@ impl ClaudeManager {
             sessions: HashMap::new(),
             callback_port: None,
             mcp_binary_path: None,
+            queue: VecDeque::new(),
+            mcp_configs: HashMap::new(),
+            turns: HashMap::new(),
         }
     }
 
+    /// Turns started for `ui_session_id` so far, queued spawns included
+    pub fn turn(&self, ui_session_id: &str) -> u64 {
+        self.turns.get(ui_session_id).copied().unwrap_or(0)
+    }
+
+    fn begin_turn(&mut self, ui_session_id: &str) {
+        *self.turns.entry(ui_session_id.to_string()).or_insert(0) += 1;
+    }
+
     /// Set the callback server port and resolve MCP binary path
     pub fn set_hook_port(&mut self, port: u16) {
         self.callback_port = Some(port);
@@ -81,7 +385,8 @@ impl ClaudeManager {
         }
     }
 
-    /// Spawn a new Claude process
+    /// Spawn a new Claude process, or queue it (emitting `session.queued`)
+    /// while `maxConcurrentSessions` processes are already running
     pub fn spawn_session(
         &mut self,
         app: &AppHandle,
@@ -89,12 +394,182 @@ impl ClaudeManager {
         working_directory: String,
         initial_prompt: Option<String>,
         resume_session: Option<String>,
-        model: Option<String>,
+        options: SessionOptions,
+    ) -> Result<String, String> {
+        // Every new session passes here, whoever starts it; its later turns don't
+        #[cfg(feature = "analytics")]
+        if !self.sessions.contains_key(&ui_session_id) && !self.is_queued(&ui_session_id) {
+            budget::check_spawn(options.confirm_over_budget)?;
+        }
+        self.begin_turn(&ui_session_id);
+        // A follow-up sent while the session waits joins its queued spawn
+        if let Some(queued) = self.queue.iter_mut().find(|q| q.ui_session_id == ui_session_id) {
+            queued.merge(QueuedSpawn {
+                ui_session_id: ui_session_id.clone(),
+                working_directory,
+                initial_prompt,
+                resume_session,
+                options,
+            })?;
+            debug_log!("SPAWN", "Added a follow-up to queued session {}", ui_session_id);
+            return Ok(ui_session_id);
+        }
+        let limit = match config::max_concurrent_sessions() {
+            Some(limit) => limit,
+            None => {
+                return self.start_session(app, ui_session_id, working_directory, initial_prompt, resume_session, options)
+            }
+        };
+        // Replacing a running session frees its slot; anyone already waiting goes first
+        if self.queue.is_empty() && self.running_count(&ui_session_id) < limit {
+            return self.start_session(app, ui_session_id, working_directory, initial_prompt, resume_session, options);
+        }
+        if initial_prompt.is_none() && resume_session.is_none() {
+            return Err("Initial prompt required for new session".to_string());
+        }
+
+        debug_log!("SPAWN", "Queueing session {} (limit {})", ui_session_id, limit);
+        self.queue.push_back(QueuedSpawn {
+            ui_session_id: ui_session_id.clone(),
+            working_directory,
+            initial_prompt,
+            resume_session,
+            options,
+        });
+        let _ = events::emit(
+            app,
+            BackendEvent::SessionQueued {
+                ui_session_id: ui_session_id.clone(),
+                position: self.queue.len(),
+                limit,
+            },
+        );
+        Ok(ui_session_id)
+    }
+
+    /// Running processes, not counting `except`'s
+    fn running_count(&mut self, except: &str) -> usize {
+        self.sessions
+            .iter_mut()
+            .filter(|(id, _)| id.as_str() != except)
+            .map(|(_, s)| s.reap_child())
+            .filter(|running| *running)
+            .count()
+    }
+
+    /// Sessions with a live process
+    pub fn running_sessions(&mut self) -> usize {
+        self.running_count("")
+    }
+
+    /// Sessions waiting for a free slot
+    pub fn queued_sessions(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Whether `ui_session_id` is waiting for a free slot
+    pub fn is_queued(&self, ui_session_id: &str) -> bool {
+        self.queue.iter().any(|q| q.ui_session_id == ui_session_id)
+    }
+
+    /// Start queued spawns while there are free slots. Called when a process
+    /// ends, and periodically by the reaper in case an exit went unnoticed.
+    pub fn admit_queued(&mut self, app: &AppHandle) {
+        if self.queue.is_empty() {
+            return;
+        }
+        let limit = config::max_concurrent_sessions().unwrap_or(usize::MAX);
+        let mut admitted = false;
+        while self.running_count("") < limit {
+            let queued = match self.queue.pop_front() {
+                Some(q) => q,
+                None => break,
+            };
+            admitted = true;
+            let id = queued.ui_session_id.clone();
+            debug_log!("SPAWN", "Admitting queued session {}", id);
+            let started = self.start_session(
+                app,
+                queued.ui_session_id,
+                queued.working_directory,
+                queued.initial_prompt,
+                queued.resume_session,
+                queued.options,
+            );
+            if let Err(e) = started {
+                let _ = events::emit(
+                    app,
+                    BackendEvent::SessionEnded {
+                        ui_session_id: id,
+                        exit_code: None,
+                        error: Some(e),
+                        reason: None,
+                    },
+                );
+            }
+        }
+        if admitted && limit != usize::MAX {
+            for (i, queued) in self.queue.iter().enumerate() {
+                let _ = events::emit(
+                    app,
+                    BackendEvent::SessionQueued {
+                        ui_session_id: queued.ui_session_id.clone(),
+                        position: i + 1,
+                        limit,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Drop a queued spawn, emitting `session.ended` for it. Returns whether it was queued.
+    fn cancel_queued(&mut self, app: &AppHandle, ui_session_id: &str) -> bool {
+        if !self.is_queued(ui_session_id) {
+            return false;
+        }
+        debug_log!("SPAWN", "Cancelling queued session {}", ui_session_id);
+        self.queue.retain(|q| q.ui_session_id != ui_session_id);
+        let _ = events::emit(
+            app,
+            BackendEvent::SessionEnded {
+                ui_session_id: ui_session_id.to_string(),
+                exit_code: None,
+                error: None,
+                reason: None,
+            },
+        );
+        true
+    }
+
+    /// Start a Claude process now, regardless of the concurrency limit
+    fn start_session(
+        &mut self,
+        app: &AppHandle,
+        ui_session_id: String,
+        working_directory: String,
+        initial_prompt: Option<String>,
+        resume_session: Option<String>,
+        options: SessionOptions,
     ) -> Result<String, String> {
         debug_log!("SPAWN", "Starting session (ui_session_id: {})", ui_session_id);
         debug_log!("SPAWN", "Working directory: {}", working_directory);
         debug_log!("SPAWN", "Initial prompt: {:?}", initial_prompt);
-        debug_log!("SPAWN", "Resume session: {:?}", resume_session);
+        debug_log!("SPAWN", "Resume session: {:?} (fork: {})", resume_session, options.fork_session);
+        debug_log!("SPAWN", "Persistent: {}", options.persistent);
+        debug_log!("SPAWN", "Permission mode: {:?}", options.permission_mode);
+        memory_watch::watch_project(&working_directory);
+
+        // Auto-resume a session that was suspended while idle
+        let resume_session = match resume_session {
+            Some(id) => Some(id),
+            None => {
+                let suspended = self.sessions.get(&ui_session_id).and_then(|s| s.suspended.clone());
+                if let Some(ref id) = suspended {
+                    debug_log!("SPAWN", "Resuming suspended Claude session {}", id);
+                }
+                suspended
+            }
+        };
 
         if self.sessions.contains_key(&ui_session_id) {
             debug_log!("SPAWN", "Replacing existing session {}", ui_session_id);
@@ -131,39 +606,95 @@ impl ClaudeManager {
             "stream-json".to_string(),
             "--verbose".to_string(),
         ];
+        if options.persistent {
+            args.push("--input-format".to_string());
+            args.push("stream-json".to_string());
+        }
 
         // Write MCP config and add flags if we have the binary
-        let mcp_config_path = self.setup_mcp_config(&working_directory, &ui_session_id)?;
+        let mcp_config_path = self.setup_mcp_config(app, &working_directory, &ui_session_id)?;
         if let Some(config_path) = mcp_config_path {
+            let path = PathBuf::from(&config_path);
+            mcp_cleanup::record(&path);
+            self.mcp_configs.insert(ui_session_id.clone(), path);
             args.push("--mcp-config".to_string());
             args.push(config_path);
             args.push("--permission-prompt-tool".to_string());
             args.push("mcp__horseman__request_permission".to_string());
         }
 
+        // Tool lists and --add-dir are variadic in the CLI, so they go before
+        // flags that take a single value, and the prompt follows `--`
+        if !options.allowed_tools.is_empty() {
+            args.push("--allowedTools".to_string());
+            args.push(options.allowed_tools.join(","));
+        }
+        if !options.disallowed_tools.is_empty() {
+            args.push("--disallowedTools".to_string());
+            args.push(options.disallowed_tools.join(","));
+        }
+
+        for dir in &options.add_dirs {
+            args.push("--add-dir".to_string());
+            args.push(dir.clone());
+        }
+
         // Resume existing session if provided
         if let Some(ref resume_id) = resume_session {
             args.push("--resume".to_string());
             args.push(resume_id.clone());
+            if options.fork_session {
+                args.push("--fork-session".to_string());
+            }
         }
 
         // Set model if provided
-        if let Some(ref model_name) = model {
+        if let Some(ref model_name) = options.model {
             args.push("--model".to_string());
             args.push(model_name.clone());
         }
 
-        // Add initial prompt (required for new sessions)
-        if let Some(prompt) = initial_prompt {
-            args.push(prompt);
-        } else if resume_session.is_none() {
+        let max_turns = match options.max_turns {
+            Some(turns) => Some(turns).filter(|t| *t > 0),
+            None => config::default_max_turns(),
+        };
+        if let Some(max_turns) = max_turns {
+            args.push("--max-turns".to_string());
+            args.push(max_turns.to_string());
+        }
+
+        if let Some(mode) = options.permission_mode {
+            args.push("--permission-mode".to_string());
+            args.push(mode.as_cli_arg().to_string());
+        }
+
+        if options.partial_messages {
+            args.push("--include-partial-messages".to_string());
+        }
+
+        let environment = session_env::capture(&args, options.model.as_deref());
+
+        // Add initial prompt (required for new sessions). Keep-alive sessions
+        // get it on stdin once the process is up.
+        if initial_prompt.is_none() && resume_session.is_none() {
             return Err("Initial prompt required for new session".to_string());
         }
+        let has_prompt = initial_prompt.is_some();
+        let stdin_prompt = if options.persistent {
+            initial_prompt
+        } else {
+            if let Some(prompt) = initial_prompt {
+                args.push("--".to_string());
+                args.push(prompt);
+            }
+            None
+        };
 
         debug_log!("SPAWN", "Command: claude {}", args.join(" "));
 
         // Spawn the process via login shell to inherit user's PATH (for NVM, Volta, etc.)
-        // IMPORTANT: Use Stdio::null() for stdin - piped stdin causes Claude to block
+        // IMPORTANT: Use Stdio::null() for stdin unless --input-format stream-json
+        // is set - otherwise piped stdin causes Claude to block
         let claude_bin = config::claude_binary();
         debug_log!("SPAWN", "Using Claude binary: {}", claude_bin);
 
@@ -179,10 +710,23 @@ impl ClaudeManager {
         // Use login shell (-l) to source .zshrc/.bashrc which sets up NVM/Volta/etc.
         // This ensures node is in PATH even when launched from GUI
         let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
-        let mut child = Command::new(&shell)
+        let mut command = Command::new(&shell);
+        command.envs(network::spawn_env());
+        command.envs(providers::spawn_env(options.provider.as_deref())?);
+        if let Some(budget) = options.thinking_budget {
+            debug_log!("SPAWN", "MAX_THINKING_TOKENS={}", budget);
+            command.env("MAX_THINKING_TOKENS", budget.to_string());
+        }
+        // Claude gives MCP tool calls 180s; make room for a longer permission wait
+        let permission_timeout = config::permission_timeout();
+        if permission_timeout.as_secs() > config::DEFAULT_PERMISSION_TIMEOUT_SECS {
+            let tool_timeout_ms = (permission_timeout + MCP_TOOL_TIMEOUT_MARGIN).as_millis();
+            command.env("MCP_TOOL_TIMEOUT", tool_timeout_ms.to_string());
+        }
+        let mut child = command
             .args(["-l", "-c", &full_command])
             .current_dir(&working_directory)
-            .stdin(Stdio::null())
+            .stdin(if options.persistent { Stdio::piped() } else { Stdio::null() })
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
@@ -201,32 +745,85 @@ impl ClaudeManager {
         // Take ownership of stdout/stderr
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
         let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+        let mut stdin = child.stdin.take();
+        if let (Some(prompt), Some(ref mut pipe)) = (stdin_prompt, stdin.as_mut()) {
+            write_user_message(pipe, &prompt)?;
+        }
 
         // Spawn stderr reader thread
         let ui_session_id_stderr = ui_session_id.clone();
+        let app_stderr = app.clone();
+        let resume_stderr = resume_session.clone();
+        let working_directory_stderr = working_directory.clone();
         std::thread::spawn(move || {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-                match line {
-                    Ok(line) if !line.is_empty() => {
-                        debug_log!("STDERR", "[{}] {}", ui_session_id_stderr, line);
-                    }
-                    Err(e) => {
-                        debug_log!("STDERR", "[{}] Read error: {}", ui_session_id_stderr, e);
-                        break;
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                let reader = BufReader::new(stderr);
+                let mut resume_failed = false;
+                for line in reader.lines() {
+                    match line {
+                        Ok(line) if !line.is_empty() => {
+                            debug_log!("STDERR", "[{}] {}", ui_session_id_stderr, line);
+                            if connectivity::is_network_error(&line) {
+                                connectivity::report_network_error(&app_stderr);
+                            }
+                            rate_limit::on_stderr(&ui_session_id_stderr, &line);
+                            if let (false, Some(resume_id)) = (resume_failed, &resume_stderr) {
+                                if let Some(reason) = resume_recovery::resume_failure_reason(&line) {
+                                    resume_failed = true;
+                                    let primer_available = resume_recovery::build_primer(
+                                        &working_directory_stderr,
+                                        resume_id,
+                                        &ui_session_id_stderr,
+                                    )
+                                    .is_some();
+                                    debug_log!("STDERR", "[{}] Resume of {} failed: {}", ui_session_id_stderr, resume_id, reason);
+                                    let _ = events::emit(
+                                        &app_stderr,
+                                        BackendEvent::SessionResumeFailed {
+                                            ui_session_id: ui_session_id_stderr.clone(),
+                                            claude_session_id: resume_id.clone(),
+                                            reason,
+                                            primer_available,
+                                        },
+                                    );
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            debug_log!("STDERR", "[{}] Read error: {}", ui_session_id_stderr, e);
+                            break;
+                        }
+                        _ => {}
                     }
-                    _ => {}
                 }
+            }));
+            if let Err(payload) = result {
+                panic_guard::emit_reader_crashed(&app_stderr, Some(&ui_session_id_stderr), None, "stderr", payload.as_ref(), false);
             }
             debug_log!("STDERR", "[{}] Reader thread ended", ui_session_id_stderr);
         });
 
+        let known_session_id = resume_session.clone().filter(|_| !options.fork_session);
+
         // Create tracking state for this session
-        let tracking = Arc::new(Mutex::new(StreamTrackingState::default()));
-        if let Some(ref resume_id) = resume_session {
-            if let Ok(mut state) = tracking.lock() {
-                state.claude_session_id = Some(resume_id.clone());
-            }
+        let tracking = Arc::new(Mutex::new(StreamTrackingState {
+            working_directory: working_directory.clone(),
+            last_activity: Some(Instant::now()),
+            persistent: options.persistent,
+            permission_mode: options.permission_mode,
+            git_context: git::context(&working_directory),
+            environment: Some(environment),
+            awaiting_result: has_prompt,
+            ..Default::default()
+        }));
+        // A fork gets a new Claude session ID, reported by the system event
+        if let Some(ref resume_id) = known_session_id {
+            lock_tracking(&tracking).claude_session_id = Some(resume_id.clone());
+        }
+
+        // Sessions keep the snapshot from their first Horseman run
+        if known_session_id.as_deref().and_then(session_cache::workspace_snapshot).is_none() {
+            spawn_workspace_capture(ui_session_id.clone(), working_directory.clone(), tracking.clone());
         }
 
         // Spawn stdout reader thread
@@ -235,80 +832,140 @@ impl ClaudeManager {
         let tracking_clone = tracking.clone();
         std::thread::spawn(move || {
             debug_log!("STDOUT", "[{}] Reader thread started", ui_session_id_clone);
-            let reader = BufReader::new(stdout);
+            let mut raw_log = raw_log::RawLogWriter::open(&ui_session_id_clone);
             let mut line_count = 0;
 
-            for line in reader.lines() {
-                match line {
-                    Ok(line) if !line.is_empty() => {
-                        line_count += 1;
-                        let truncated = if line.len() > 300 {
-                                            // Find valid UTF-8 boundary
-                                            let mut end = 300;
-                                            while !line.is_char_boundary(end) && end > 0 {
-                                                end -= 1;
-                                            }
-                                            &line[..end]
-                                        } else {
-                                            &line[..]
-                                        };
-                                        debug_log!("STDOUT", "[{}] Line {}: {}", ui_session_id_clone, line_count, truncated);
-
-                        // Try to parse as JSON
-                        match serde_json::from_str::<serde_json::Value>(&line) {
-                            Ok(event) => {
-                                let event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("unknown");
-                                debug_log!("STDOUT", "[{}] Parsed event type: {}", ui_session_id_clone, event_type);
-
-                                let emit_result = process_event(
-                                    &event,
-                                    &tracking_clone,
-                                    &app_handle,
-                                    &ui_session_id_clone,
-                                );
-
-                                if let Err(e) = emit_result {
-                                    debug_log!("EMIT", "[{}] Emit error: {}", ui_session_id_clone, e);
-                                }
+            let read_result = catch_unwind(AssertUnwindSafe(|| {
+                let max_line_bytes = config::max_stdout_line_bytes();
+                let spill_dir = stdout_lines::spill_dir(&ui_session_id_clone).ok();
+                let reader = StdoutLines::new(BufReader::new(stdout), max_line_bytes, spill_dir);
+                for line in reader {
+                    match line {
+                        Ok(StdoutLine::Oversized(oversized)) => {
+                            line_count += 1;
+                            debug_log!(
+                                "STDOUT",
+                                "[{}] Line {} is {} bytes, over the {} byte limit; spilled to {:?}",
+                                ui_session_id_clone,
+                                line_count,
+                                oversized.bytes,
+                                max_line_bytes,
+                                oversized.spill_path
+                            );
+                            let substitute = oversized.substitute();
+                            let _ = events::emit(
+                                &app_handle,
+                                BackendEvent::SessionLineTruncated {
+                                    ui_session_id: ui_session_id_clone.clone(),
+                                    line_number: line_count,
+                                    bytes: oversized.bytes,
+                                    limit_bytes: max_line_bytes,
+                                    event_type: oversized.event_type,
+                                    tool_use_id: oversized.tool_use_id,
+                                    spill_path: oversized.spill_path.map(|p| p.to_string_lossy().to_string()),
+                                },
+                            );
+                            // The stand-in finishes the tool call or turn the line would have
+                            raw_log.write_line(&substitute);
+                            let handled = catch_unwind(AssertUnwindSafe(|| {
+                                handle_stdout_line(&substitute, line_count, &tracking_clone, &app_handle, &ui_session_id_clone)
+                            }));
+                            if let Err(payload) = handled {
+                                panic_guard::emit_reader_crashed(&app_handle, Some(&ui_session_id_clone), None, "stdout", payload.as_ref(), false);
                             }
-                            Err(e) => {
-                                debug_log!("STDOUT", "[{}] JSON parse error: {} - raw: {}", ui_session_id_clone, e, &line[..line.len().min(100)]);
+                        }
+                        Ok(StdoutLine::Line(line)) if !line.is_empty() => {
+                            line_count += 1;
+                            raw_log.write_line(&line);
+
+                            // A panic while handling one line must not kill the reader
+                            let handled = catch_unwind(AssertUnwindSafe(|| {
+                                handle_stdout_line(&line, line_count, &tracking_clone, &app_handle, &ui_session_id_clone)
+                            }));
+                            if let Err(payload) = handled {
+                                panic_guard::emit_reader_crashed(&app_handle, Some(&ui_session_id_clone), None, "stdout", payload.as_ref(), false);
                             }
                         }
+                        Err(e) => {
+                            debug_log!("STDOUT", "[{}] Read error: {}", ui_session_id_clone, e);
+                            break;
+                        }
+                        _ => {}
                     }
-                    Err(e) => {
-                        debug_log!("STDOUT", "[{}] Read error: {}", ui_session_id_clone, e);
-                        break;
-                    }
-                    _ => {}
                 }
-            }
+            }));
             debug_log!("STDOUT", "[{}] Reader thread ended after {} lines", ui_session_id_clone, line_count);
 
-            // Emit session ended when stdout closes (process finished)
+            if let Err(payload) = read_result {
+                // Keep the UI updating from the transcript file instead
+                let fallback = transcript_watch::spawn_fallback(
+                    app_handle.clone(),
+                    ui_session_id_clone.clone(),
+                    tracking_clone.clone(),
+                );
+                panic_guard::emit_reader_crashed(&app_handle, Some(&ui_session_id_clone), None, "stdout", payload.as_ref(), fallback);
+                if fallback {
+                    // The fallback emits session.ended when the transcript goes idle
+                    return;
+                }
+                let _ = events::emit(
+                    &app_handle,
+                    BackendEvent::SessionEnded {
+                        ui_session_id: ui_session_id_clone.clone(),
+                        exit_code: None,
+                        error: Some(format!("Output reader crashed: {}", panic_guard::panic_message(payload.as_ref()))),
+                        reason: None,
+                    },
+                );
+                app_handle.state::<ClaudeState>().lock().admit_queued(&app_handle);
+                return;
+            }
+
+            // Emit session ended when stdout closes (process finished),
+            // unless it died mid-turn and is being resumed
+            let exit = wait_for_exit(&app_handle, &ui_session_id_clone, &tracking_clone);
+            let (exit_code, error) = match crash_restart::on_exit(&app_handle, &ui_session_id_clone, &tracking_clone, exit) {
+                crash_restart::ExitOutcome::Restarting => return,
+                crash_restart::ExitOutcome::Crashed(code) => {
+                    (code, Some("Claude exited unexpectedly before finishing the turn".to_string()))
+                }
+                crash_restart::ExitOutcome::Finished => (exit.flatten(), None),
+            };
             debug_log!("EMIT", "[{}] Emitting session.ended (process finished)", ui_session_id_clone);
-            let _ = app_handle.emit(
-                "horseman-event",
+            let reason = lock_tracking(&tracking_clone).end_reason;
+            if error.is_none() && reason.is_none() {
+                session_summary::on_session_end(&app_handle, &ui_session_id_clone, &tracking_clone);
+            }
+            let _ = events::emit(
+                &app_handle,
                 BackendEvent::SessionEnded {
                     ui_session_id: ui_session_id_clone.clone(),
-                    exit_code: None,
-                    error: None,
+                    exit_code,
+                    error,
+                    reason,
                 },
             );
+            let state = app_handle.state::<ClaudeState>();
+            let mut manager = state.lock();
+            manager.release_mcp_config(&ui_session_id_clone);
+            manager.admit_queued(&app_handle);
         });
 
         // If resuming, we already know the Claude session ID - emit session.started now.
-        if let Some(ref resume_id) = resume_session {
+        if let Some(ref resume_id) = known_session_id {
             debug_log!("EMIT", "[{}] Emitting session.started (resume)", ui_session_id);
-            let _ = app.emit(
-                "horseman-event",
+            let _ = events::emit(
+                app,
                 BackendEvent::SessionStarted {
                     ui_session_id: ui_session_id.clone(),
                     claude_session_id: resume_id.clone(),
+                    permission_mode: options.permission_mode,
                 },
             );
         }
 
+        open_sessions::record(&ui_session_id, known_session_id.as_deref(), &working_directory, &options);
+
         // Store session keyed by UI session ID
         self.sessions.insert(
             ui_session_id.clone(),
@@ -317,6 +974,10 @@ impl ClaudeManager {
                 working_directory,
                 child: Some(child),
                 tracking,
+                suspended: None,
+                stdin,
+                options,
+                pending_restart: false,
             },
         );
 
@@ -326,7 +987,7 @@ impl ClaudeManager {
 
     /// Setup MCP config for permission handling
     /// Returns the config file path if successful, None if MCP not available
-    fn setup_mcp_config(&self, working_directory: &str, ui_session_id: &str) -> Result<Option<String>, String> {
+    fn setup_mcp_config(&self, app: &AppHandle, working_directory: &str, ui_session_id: &str) -> Result<Option<String>, String> {
         let port = match self.callback_port {
             Some(p) => p,
             None => {
@@ -349,25 +1010,143 @@ impl ClaudeManager {
             }
         };
 
+        // Never hand a tampered sidecar the permission callback
+        if let Err(reason) = hooks::integrity::verify_mcp_binary(&mcp_path) {
+            let _ = events::emit(
+                app,
+                BackendEvent::McpIntegrityFailed {
+                    path: mcp_path.clone(),
+                    reason: reason.clone(),
+                },
+            );
+            return Err(format!("Refusing to use horseman-mcp at {}: {}", mcp_path, reason));
+        }
+
+        let (extra_servers, skipped) = mcp_servers::resolve(working_directory);
+        for skipped in skipped {
+            debug_log!("MCP", "[{}] Skipping MCP server {}: {}", ui_session_id, skipped.name, skipped.reason);
+            let _ = events::emit(
+                app,
+                BackendEvent::McpServerSkipped {
+                    ui_session_id: ui_session_id.to_string(),
+                    name: skipped.name,
+                    reason: skipped.reason,
+                },
+            );
+        }
         let config_path = hooks::write_mcp_config(
             Path::new(working_directory),
             port,
             &mcp_path,
             ui_session_id,
+            &extra_servers,
         )?;
 
         Ok(Some(config_path))
     }
 
+    /// Send a follow-up turn. Keep-alive sessions with a live process get it
+    /// on stdin; otherwise a new process resumes `claude_session_id`. The
+    /// model is fixed when the process starts, so a keep-alive session that
+    /// switches models is restarted (or refused while a turn is running).
+    pub fn send_message(
+        &mut self,
+        app: &AppHandle,
+        ui_session_id: &str,
+        working_directory: String,
+        content: String,
+        claude_session_id: String,
+        model: Option<String>,
+    ) -> Result<(), String> {
+        let mut options = self
+            .sessions
+            .get(ui_session_id)
+            .map(|s| s.options.clone())
+            .unwrap_or_default();
+        let model_changed = options.model != model;
+        options.model = model;
+        // Follow-ups continue the fork itself
+        options.fork_session = false;
+        if options.persistent && self.is_running(ui_session_id) && model_changed {
+            let busy = self
+                .sessions
+                .get(ui_session_id)
+                .is_some_and(|s| lock_tracking(&s.tracking).awaiting_result);
+            if busy {
+                return Err("Can't switch models while a turn is running".to_string());
+            }
+            debug_log!("SEND", "[{}] Model changed, restarting the live process", ui_session_id);
+        } else if options.persistent && self.is_running(ui_session_id) {
+            if let Some(session) = self.sessions.get_mut(ui_session_id) {
+                if let Some(ref mut stdin) = session.stdin {
+                    debug_log!("SEND", "[{}] Writing turn to live process", ui_session_id);
+                    *self.turns.entry(ui_session_id.to_string()).or_insert(0) += 1;
+                    let mut state = lock_tracking(&session.tracking);
+                    state.last_activity = Some(Instant::now());
+                    state.awaiting_result = true;
+                    // A limit hit on an earlier turn doesn't describe this one
+                    state.end_reason = None;
+                    drop(state);
+                    return write_user_message(stdin, &content);
+                }
+            }
+        }
+
+        self.spawn_session(
+            app,
+            ui_session_id.to_string(),
+            working_directory,
+            Some(content),
+            Some(claude_session_id),
+            options,
+        )?;
+        Ok(())
+    }
+
+    /// Send a turn the app itself starts, with the session's own directory
+    /// and model
+    pub fn send_followup(&mut self, app: &AppHandle, ui_session_id: &str, content: String) -> Result<(), String> {
+        let claude_session_id = self
+            .claude_session_id(ui_session_id)
+            .ok_or_else(|| "No Claude session to continue".to_string())?;
+        let session = self.sessions.get(ui_session_id).ok_or_else(|| "Session not found".to_string())?;
+        let working_directory = session.working_directory.clone();
+        let model = session.options.model.clone();
+        self.send_message(app, ui_session_id, working_directory, content, claude_session_id, model)
+    }
+
+    /// Send `/compact` as the next turn. Back up the transcript first,
+    /// without this lock held (see `transcript_backup`).
+    pub fn compact_session(&mut self, app: &AppHandle, ui_session_id: &str) -> Result<(), String> {
+        self.send_followup(app, ui_session_id, auto_compact::COMPACT_COMMAND.to_string())
+    }
+
     /// Interrupt a session (send SIGTERM)
     pub fn interrupt_session(&mut self, app: &AppHandle, session_id: &str) -> Result<(), String> {
         debug_log!("INTERRUPT", "Interrupting session {}", session_id);
+        rate_limit::forget(session_id);
+        if self.cancel_queued(app, session_id) {
+            return Ok(());
+        }
 
         let session = self
             .sessions
             .get_mut(session_id)
             .ok_or_else(|| format!("Session not found: {}", session_id))?;
 
+        if std::mem::take(&mut session.pending_restart) {
+            debug_log!("INTERRUPT", "Cancelled pending crash restart of {}", session_id);
+            let _ = events::emit(
+                app,
+                BackendEvent::SessionEnded {
+                    ui_session_id: session_id.to_string(),
+                    exit_code: lock_tracking(&session.tracking).exit_code,
+                    error: None,
+                    reason: None,
+                },
+            );
+        }
+
         if let Some(ref mut child) = session.child {
             debug_log!("INTERRUPT", "Sending SIGTERM to PID {}", child.id());
 
@@ -386,51 +1165,378 @@ impl ClaudeManager {
             match child.wait() {
                 Ok(status) => {
                     debug_log!("INTERRUPT", "Process exited with status: {:?}", status.code());
-                    let _ = app.emit(
-                        "horseman-event",
+                    let _ = events::emit(
+                        app,
                         BackendEvent::SessionEnded {
                             ui_session_id: session_id.to_string(),
                             exit_code: status.code(),
                             error: None,
+                            reason: None,
                         },
                     );
                 }
                 Err(e) => {
                     debug_log!("INTERRUPT", "Wait error: {}", e);
-                    let _ = app.emit(
-                        "horseman-event",
+                    let _ = events::emit(
+                        app,
                         BackendEvent::SessionEnded {
                             ui_session_id: session_id.to_string(),
                             exit_code: None,
                             error: Some(format!("Failed to wait for process: {}", e)),
+                            reason: None,
                         },
                     );
                 }
             }
 
             session.child = None;
+            session.stdin = None;
+        }
+
+        Ok(())
+    }
+
+    /// Check if a session is running
+    pub fn is_running(&mut self, session_id: &str) -> bool {
+        self.sessions.get_mut(session_id).is_some_and(|s| s.reap_child())
+    }
+
+    /// Interrupt running sessions whose process has been silent for `idle`,
+    /// remembering their Claude session so the next message resumes it.
+    /// Sessions running a tool or in `awaiting_answer` (a prompt is open) are left alone.
+    pub fn suspend_idle(&mut self, app: &AppHandle, idle: Duration, awaiting_answer: &HashSet<String>) -> Vec<String> {
+        let idle_ids: Vec<(String, String, u64)> = self
+            .sessions
+            .iter()
+            .filter(|(id, _)| !awaiting_answer.contains(*id))
+            .filter_map(|(id, session)| {
+                let state = lock_tracking(&session.tracking);
+                if !state.running_tools.is_empty() {
+                    return None;
+                }
+                let idle_for = state.last_activity?.elapsed();
+                let claude_session_id = state.claude_session_id.clone()?;
+                (idle_for >= idle).then(|| (id.clone(), claude_session_id, idle_for.as_secs()))
+            })
+            .collect();
+
+        let mut suspended = Vec::new();
+        for (id, claude_session_id, idle_seconds) in idle_ids {
+            if !self.is_running(&id) {
+                continue;
+            }
+            debug_log!("IDLE", "Suspending session {} after {}s idle", id, idle_seconds);
+            if let Err(e) = self.interrupt_session(app, &id) {
+                debug_log!("IDLE", "Failed to suspend {}: {}", id, e);
+                continue;
+            }
+            if let Some(session) = self.sessions.get_mut(&id) {
+                session.suspended = Some(claude_session_id.clone());
+            }
+            let _ = events::emit(
+                app,
+                BackendEvent::SessionSuspended {
+                    ui_session_id: id.clone(),
+                    claude_session_id,
+                    idle_seconds,
+                },
+            );
+            suspended.push(id);
+        }
+        suspended
+    }
+
+    /// (ui_session_id, claude_session_id) of every suspended session
+    pub fn suspended_sessions(&self) -> Vec<(String, String)> {
+        self.sessions
+            .iter()
+            .filter_map(|(id, s)| s.suspended.clone().map(|c| (id.clone(), c)))
+            .collect()
+    }
+
+    /// Directory a UI session runs in
+    pub fn working_directory(&self, ui_session_id: &str) -> Option<String> {
+        self.sessions.get(ui_session_id).map(|s| s.working_directory.clone())
+    }
+
+    /// Directories sessions may work in: each working directory and its
+    /// `--add-dir`s
+    pub fn readable_roots(&self) -> Vec<String> {
+        self.sessions
+            .values()
+            .flat_map(|s| std::iter::once(s.working_directory.clone()).chain(s.options.add_dirs.iter().cloned()))
+            .collect()
+    }
+
+    /// Claude session ID of a UI session, once the CLI has reported it
+    pub fn claude_session_id(&self, ui_session_id: &str) -> Option<String> {
+        let session = self.sessions.get(ui_session_id)?;
+        lock_tracking(&session.tracking)
+            .claude_session_id
+            .clone()
+            .or_else(|| session.suspended.clone())
+    }
+
+    /// UI session whose process is currently running `claude_session_id`
+    pub fn running_session_for(&mut self, claude_session_id: &str) -> Option<String> {
+        let ids: Vec<String> = self.sessions.keys().cloned().collect();
+        ids.into_iter().find(|id| {
+            self.claude_session_id(id).as_deref() == Some(claude_session_id) && self.is_running(id)
+        })
+    }
+
+    /// Claude session IDs of sessions that were given `directory` via `--add-dir`
+    pub fn claude_sessions_with_add_dir(&self, directory: &str) -> Vec<String> {
+        let directory = directory.trim_end_matches('/');
+        self.sessions
+            .iter()
+            .filter(|(_, s)| s.options.add_dirs.iter().any(|d| d.trim_end_matches('/') == directory))
+            .filter_map(|(id, _)| self.claude_session_id(id))
+            .collect()
+    }
+
+    /// Running keep-alive sessions, optionally only those in `directory`.
+    /// These loaded CLAUDE.md at spawn and won't see later edits.
+    pub fn live_persistent_sessions(&mut self, directory: Option<&str>) -> Vec<String> {
+        let directory = directory.map(|d| d.trim_end_matches('/'));
+        let ids: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, s)| s.options.persistent)
+            .filter(|(_, s)| directory.is_none_or(|d| s.working_directory.trim_end_matches('/') == d))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.into_iter().filter(|id| self.is_running(id)).collect()
+    }
+
+    /// (ui_session_id, claude_session_id, working_directory) of sessions whose
+    /// process is running and either mid-turn or active within `window`
+    #[cfg(feature = "analytics")]
+    pub fn active_sessions(&mut self, window: Duration) -> Vec<(String, Option<String>, String)> {
+        let mut active = Vec::new();
+        for (id, session) in self.sessions.iter_mut() {
+            if !session.reap_child() {
+                continue;
+            }
+            let state = lock_tracking(&session.tracking);
+            if state.awaiting_result || state.last_activity.is_some_and(|t| t.elapsed() < window) {
+                active.push((id.clone(), state.claude_session_id.clone(), state.working_directory.clone()));
+            }
+        }
+        active
+    }
+
+    /// Stop a keep-alive session's process and resume its Claude session in a
+    /// fresh one (waiting for input), so memory files are re-read. Other
+    /// sessions already start a new process on every message.
+    pub fn restart_session(&mut self, app: &AppHandle, ui_session_id: &str) -> Result<(), String> {
+        let claude_session_id = self
+            .claude_session_id(ui_session_id)
+            .ok_or_else(|| format!("Session has no Claude session to resume: {}", ui_session_id))?;
+        let session = self
+            .sessions
+            .get(ui_session_id)
+            .ok_or_else(|| format!("Session not found: {}", ui_session_id))?;
+        let working_directory = session.working_directory.clone();
+        let mut options = session.options.clone();
+        if !options.persistent {
+            return Err("Only keep-alive sessions need a restart".to_string());
+        }
+        options.fork_session = false;
+
+        debug_log!("MANAGER", "Restarting session {} (resume {})", ui_session_id, claude_session_id);
+        self.interrupt_session(app, ui_session_id)?;
+        self.spawn_session(app, ui_session_id.to_string(), working_directory, None, Some(claude_session_id), options)?;
+        Ok(())
+    }
+
+    /// Reap the process behind `tracking` if it has exited, or report the
+    /// exit code the reaper already recorded for it
+    pub fn reap_exited(&mut self, ui_session_id: &str, tracking: &Arc<Mutex<StreamTrackingState>>) -> ReapOutcome {
+        let session = match self.sessions.get_mut(ui_session_id) {
+            Some(session) if Arc::ptr_eq(&session.tracking, tracking) => session,
+            _ => return ReapOutcome::Gone,
+        };
+        if session.reap_child() {
+            return ReapOutcome::Running;
+        }
+        let state = lock_tracking(tracking);
+        if state.reaped {
+            ReapOutcome::Exited(state.exit_code)
+        } else {
+            ReapOutcome::Gone
+        }
+    }
+
+    /// `try_wait` every child so exited processes don't linger as zombies.
+    /// Returns how many were reaped.
+    pub fn reap_exited_children(&mut self) -> usize {
+        self.sessions
+            .values_mut()
+            .filter(|s| s.child.is_some())
+            .map(|s| s.reap_child())
+            .filter(|running| !running)
+            .count()
+    }
+
+    /// Note that the process behind `tracking` will be resumed after a crash
+    /// backoff. False if the session has moved on (respawned or removed).
+    pub fn mark_restart_pending(&mut self, ui_session_id: &str, tracking: &Arc<Mutex<StreamTrackingState>>) -> bool {
+        match self.sessions.get_mut(ui_session_id) {
+            Some(session) if Arc::ptr_eq(&session.tracking, tracking) && session.child.is_none() => {
+                session.pending_restart = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Resume the Claude session of a process that died mid-turn
+    /// (`attempt` is the 1-based restart count). Ok(false) if the restart
+    /// was cancelled by an interrupt in the meantime.
+    pub fn resume_after_crash(
+        &mut self,
+        app: &AppHandle,
+        ui_session_id: &str,
+        tracking: &Arc<Mutex<StreamTrackingState>>,
+        attempt: u32,
+    ) -> Result<bool, String> {
+        let (working_directory, mut options) = match self.sessions.get_mut(ui_session_id) {
+            Some(session) if Arc::ptr_eq(&session.tracking, tracking) && session.pending_restart => {
+                session.pending_restart = false;
+                (session.working_directory.clone(), session.options.clone())
+            }
+            _ => return Ok(false),
+        };
+        let claude_session_id = self
+            .claude_session_id(ui_session_id)
+            .ok_or_else(|| format!("Session has no Claude session to resume: {}", ui_session_id))?;
+        options.fork_session = false;
+
+        debug_log!("MANAGER", "Resuming crashed session {} (attempt {})", ui_session_id, attempt);
+        self.spawn_session(
+            app,
+            ui_session_id.to_string(),
+            working_directory,
+            Some(crash_restart::RESUME_PROMPT.to_string()),
+            Some(claude_session_id),
+            options,
+        )?;
+        if let Some(session) = self.sessions.get(ui_session_id) {
+            lock_tracking(&session.tracking).crash_restarts = attempt;
+        }
+        Ok(true)
+    }
+
+    /// Interrupt every running session, returning the ones that were running
+    pub fn interrupt_all(&mut self, app: &AppHandle) -> Vec<String> {
+        let queued: Vec<String> = self.queue.iter().map(|q| q.ui_session_id.clone()).collect();
+        let mut interrupted = Vec::new();
+        for id in queued {
+            if self.cancel_queued(app, &id) {
+                interrupted.push(id);
+            }
+        }
+        let ids: Vec<String> = self.sessions.keys().cloned().collect();
+        for id in ids {
+            let restarting = self.sessions.get(&id).is_some_and(|s| s.pending_restart);
+            if (restarting || self.is_running(&id)) && self.interrupt_session(app, &id).is_ok() {
+                interrupted.push(id);
+            }
+        }
+        interrupted
+    }
+
+    /// SIGTERM every child at once, give them `grace` to exit, then SIGKILL
+    /// the rest. No events are sent; used when the app quits. Returns how
+    /// many processes were stopped.
+    pub fn terminate_all(&mut self, grace: Duration) -> usize {
+        let mut running: Vec<&mut ClaudeSession> = self.sessions.values_mut().collect();
+        running.retain_mut(|s| s.reap_child());
+        let count = running.len();
+        for session in &running {
+            if let Some(ref child) = session.child {
+                debug_log!("SHUTDOWN", "[{}] Sending SIGTERM to PID {}", session.ui_session_id, child.id());
+                #[cfg(unix)]
+                unsafe {
+                    libc::kill(child.id() as i32, libc::SIGTERM);
+                }
+            }
+        }
+        let deadline = Instant::now() + grace;
+        let mut remaining = running;
+        while !remaining.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+            remaining.retain_mut(|s| s.reap_child());
+        }
+        for session in remaining {
+            if let Some(mut child) = session.child.take() {
+                debug_log!("SHUTDOWN", "[{}] Killing PID {}", session.ui_session_id, child.id());
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            session.stdin = None;
+        }
+        count
+    }
+
+    /// Interrupt every running session and drop all session state
+    pub fn shutdown_all(&mut self, app: &AppHandle) {
+        self.interrupt_all(app);
+        self.sessions.clear();
+        self.queue.clear();
+        // Interrupted processes have long since read their config
+        let paths: HashSet<PathBuf> = self.mcp_configs.drain().map(|(_, path)| path).collect();
+        for path in paths {
+            mcp_cleanup::remove(&path);
+        }
+    }
+
+    /// Register a session persisted from a previous run, without a process.
+    /// Like an idle-suspended session, it resumes on the next message.
+    pub fn restore_session(
+        &mut self,
+        ui_session_id: &str,
+        working_directory: String,
+        claude_session_id: String,
+        options: SessionOptions,
+    ) {
+        if self.sessions.contains_key(ui_session_id) {
+            return;
         }
-
-        Ok(())
+        let tracking = StreamTrackingState {
+            working_directory: working_directory.clone(),
+            persistent: options.persistent,
+            claude_session_id: Some(claude_session_id.clone()),
+            ..Default::default()
+        };
+        self.sessions.insert(
+            ui_session_id.to_string(),
+            ClaudeSession {
+                ui_session_id: ui_session_id.to_string(),
+                working_directory,
+                child: None,
+                tracking: Arc::new(Mutex::new(tracking)),
+                suspended: Some(claude_session_id),
+                stdin: None,
+                options,
+                pending_restart: false,
+            },
+        );
     }
 
-    /// Check if a session is running
-    pub fn is_running(&mut self, session_id: &str) -> bool {
-        if let Some(session) = self.sessions.get_mut(session_id) {
-            if let Some(ref mut child) = session.child {
-                match child.try_wait() {
-                    Ok(Some(_)) => {
-                        session.child = None;
-                        false
-                    }
-                    Ok(None) => true,
-                    Err(_) => false,
-                }
-            } else {
-                false
-            }
-        } else {
-            false
+    /// Delete the MCP config a session's process was spawned with, unless
+    /// it has been respawned or another running session shares the file
+    pub fn release_mcp_config(&mut self, ui_session_id: &str) {
+        if self.is_running(ui_session_id) {
+            return;
+        }
+        let path = match self.mcp_configs.remove(ui_session_id) {
+            Some(path) => path,
+            None => return,
+        };
+        if !self.mcp_configs.values().any(|p| *p == path) {
+            mcp_cleanup::remove(&path);
         }
     }
 
@@ -438,6 +1544,17 @@ impl ClaudeManager {
     pub fn remove_session(&mut self, session_id: &str) {
         debug_log!("MANAGER", "Removing session {}", session_id);
         self.sessions.remove(session_id);
+        self.turns.remove(session_id);
+        self.release_mcp_config(session_id);
+        self.queue.retain(|q| q.ui_session_id != session_id);
+        open_sessions::forget(session_id);
+        todo_sync::forget(session_id);
+        context_limit::forget(session_id);
+        rate_limit::forget(session_id);
+        tool_catalog::forget(session_id);
+        edit_conflicts::forget(session_id);
+        connectivity::forget(session_id);
+        stdout_lines::forget(session_id);
     }
 }
 
@@ -492,7 +1609,7 @@ fn extract_subagent_info(input: Option<&serde_json::Value>) -> Option<SubagentIn
 }
 
 /// Extract agent ID from Task tool result
-fn extract_agent_id_from_result(content: &str) -> Option<String> {
+pub(crate) fn extract_agent_id_from_result(content: &str) -> Option<String> {
     // Try to parse as JSON first
     if let Ok(json) = serde_json::from_str::<serde_json::Value>(content) {
         if let Some(agent_id) = json.get("agentId").and_then(|v| v.as_str()) {
@@ -556,13 +1673,13 @@ fn read_subagent_transcript(base_transcript_path: &Path, agent_id: &str) -> Vec<
     tool_ids
 }
 
-struct ParsedAssistant {
+pub(crate) struct ParsedAssistant {
     message: Message,
     tool_calls: Vec<ToolCall>,
     todos: Option<Vec<TodoItem>>,
 }
 
-fn normalize_output(content: Option<&serde_json::Value>) -> String {
+pub(crate) fn normalize_output(content: Option<&serde_json::Value>) -> String {
     match content {
         Some(value) if value.is_string() => value.as_str().unwrap_or("").to_string(),
         Some(value) if value.is_null() => String::new(),
@@ -571,7 +1688,7 @@ fn normalize_output(content: Option<&serde_json::Value>) -> String {
     }
 }
 
-fn parse_assistant_event(
+pub(crate) fn parse_assistant_event(
     event: &serde_json::Value,
     tracking: &Arc<Mutex<StreamTrackingState>>,
     is_streaming: bool,
@@ -579,6 +1696,7 @@ fn parse_assistant_event(
     let content = event.get("message")?.get("content")?.as_array()?;
     let event_parent_id = event.get("parent_tool_use_id").and_then(|v| v.as_str());
     let mut text = String::new();
+    let mut thinking = String::new();
     let mut tool_calls: Vec<ToolCall> = Vec::new();
     let mut todos: Option<Vec<TodoItem>> = None;
 
@@ -591,6 +1709,13 @@ fn parse_assistant_event(
             continue;
         }
 
+        if item_type == "thinking" {
+            if let Some(thinking_part) = item.get("thinking").and_then(|v| v.as_str()) {
+                thinking.push_str(thinking_part);
+            }
+            continue;
+        }
+
         if item_type == "tool_use" {
             let tool_id = item.get("id")
                 .and_then(|v| v.as_str())
@@ -603,7 +1728,7 @@ fn parse_assistant_event(
             let tool_input = item.get("input").cloned().unwrap_or(serde_json::Value::Null);
 
             let (parent_id, subagent) = {
-                let mut state = tracking.lock().ok()?;
+                let mut state = lock_tracking(tracking);
                 let parent_id = resolve_parent_tool_id(
                     Some(&tool_name),
                     Some(&tool_input),
@@ -611,6 +1736,7 @@ fn parse_assistant_event(
                     &state.active_task_stack,
                 );
                 state.tool_names.insert(tool_id.clone(), tool_name.clone());
+                state.running_tools.insert(tool_id.clone());
 
                 let subagent = if tool_name == "Task" {
                     state.active_task_stack.push(tool_id.clone());
@@ -655,14 +1781,15 @@ fn parse_assistant_event(
                 output: None,
                 error: None,
                 parent_tool_id: parent_id,
-                started_at: Some(Utc::now().to_rfc3339()),
+                started_at: Some(event_timestamp(event)),
                 ended_at: None,
                 subagent,
+                usage: None,
             });
         }
     }
 
-    if text.is_empty() && tool_calls.is_empty() {
+    if text.is_empty() && thinking.is_empty() && tool_calls.is_empty() {
         return None;
     }
 
@@ -676,16 +1803,28 @@ fn parse_assistant_event(
         id: message_id,
         role: "assistant".to_string(),
         text,
+        thinking: if thinking.is_empty() { None } else { Some(thinking) },
         tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls.clone()) },
         file_blocks: None,
         is_streaming: Some(is_streaming),
-        timestamp: Utc::now().to_rfc3339(),
+        timestamp: event_timestamp(event),
+        usage: event.get("message").and_then(subagent_usage::for_message),
     };
 
     Some(ParsedAssistant { message, tool_calls, todos })
 }
 
-fn parse_usage(event: &serde_json::Value) -> Option<SessionUsage> {
+/// When an event happened: its `timestamp` (transcript lines carry one),
+/// else now, since live stream events are handled as they arrive
+fn event_timestamp(event: &serde_json::Value) -> String {
+    event
+        .get("timestamp")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| Utc::now().to_rfc3339())
+}
+
+pub(crate) fn parse_usage(event: &serde_json::Value) -> Option<SessionUsage> {
     let usage = event.get("usage")?.as_object()?;
     let model_usage = event.get("modelUsage").and_then(|v| v.as_object());
     let context_window = model_usage
@@ -705,20 +1844,20 @@ fn parse_usage(event: &serde_json::Value) -> Option<SessionUsage> {
     })
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PendingQuestionFromTranscript {
     pub tool_use_id: String,
     pub questions: Vec<Question>,
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TranscriptSummary {
     pub summary: String,
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct TranscriptParseResult {
     pub messages: Vec<Message>,
@@ -730,60 +1869,211 @@ pub struct TranscriptParseResult {
     /// Tools from subagent transcripts, with parent_tool_id set
     #[serde(default)]
     pub subagent_tools: Vec<ToolCall>,
+    /// Combined token usage of all subagents; their cost is already in
+    /// total_cost_usd, as the CLI reports it
+    pub subagent_usage: Option<SessionUsage>,
+    /// Set when storage was too slow and the result is empty/partial
+    pub slow_storage: bool,
+    /// Horseman's sidecar annotations for this session (see `annotations`);
+    /// merged in after parsing, so cached results never hold stale ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+/// Version of the transcript parser output. Bump when parse_transcript_content
+/// changes what it produces so cached session metadata gets regenerated.
+pub const PARSER_VERSION: u32 = 9;
+
+/// Transcript lines longer than this are replaced instead of buffered (one
+/// line holds a whole tool result, so a runaway command output can be huge)
+pub(crate) const MAX_TRANSCRIPT_LINE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Lines of a transcript read through a bounded buffer. A line longer than
+/// `max` bytes is never held in memory whole; a stand-in takes its place (a
+/// placeholder tool result, if that's what it held), so the tool call it
+/// finished still shows as finished.
+pub(crate) struct BoundedLines<R>(StdoutLines<R>);
+
+impl<R: BufRead> BoundedLines<R> {
+    pub(crate) fn new(reader: R, max: usize) -> Self {
+        Self(StdoutLines::new(reader, max, None))
+    }
+}
+
+impl<R: BufRead> Iterator for BoundedLines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        match self.0.next()? {
+            Ok(StdoutLine::Line(line)) => Some(line),
+            Ok(StdoutLine::Oversized(line)) => {
+                debug_log!("TRANSCRIPT", "Replaced a transcript line of {} bytes", line.bytes);
+                Some(line.substitute())
+            }
+            Err(e) => {
+                debug_log!("TRANSCRIPT", "Read error: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Stream a transcript file's lines with bounded memory
+pub(crate) fn transcript_lines(path: &Path) -> std::io::Result<BoundedLines<BufReader<std::fs::File>>> {
+    let file = std::fs::File::open(path)?;
+    Ok(BoundedLines::new(BufReader::new(file), MAX_TRANSCRIPT_LINE_BYTES))
+}
+
+/// Cut `output` to at most `max` bytes (on a char boundary), noting how much was dropped
+fn truncate_output(mut output: String, max: Option<usize>) -> String {
+    let max = match max {
+        Some(max) if output.len() > max => max,
+        _ => return output,
+    };
+    let mut end = max;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    let dropped = output.len() - end;
+    output.truncate(end);
+    output.push_str(&format!("\n… [{} bytes truncated]", dropped));
+    output
 }
 
 pub fn parse_transcript_content(content: &str) -> TranscriptParseResult {
-    let mut messages: Vec<Message> = Vec::new();
-    let mut summaries: Vec<TranscriptSummary> = Vec::new();
-    // Track message IDs to merge duplicate assistant events (Claude emits one per tool)
-    let mut message_index_by_id: HashMap<String, usize> = HashMap::new();
-    struct ToolResult {
-        output: String,
-        is_error: bool,
+    parse_transcript_lines(content.lines())
+}
+
+/// Parse a transcript file line by line, without reading it into memory whole
+pub fn parse_transcript_file(path: &Path) -> std::io::Result<TranscriptParseResult> {
+    Ok(parse_transcript_lines(transcript_lines(path)?))
+}
+
+pub(crate) fn parse_transcript_lines<I, S>(lines: I) -> TranscriptParseResult
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    profile_span!("parse_transcript");
+    let mut parser = TranscriptParser::new();
+    for line in lines {
+        parser.feed(line.as_ref());
     }
+    parser.finish()
+}
 
-    let mut tool_results: HashMap<String, ToolResult> = HashMap::new();
-    let mut current_todos: Option<Vec<TodoItem>> = None;
-    let mut last_user_text: Option<String> = None;
-    let mut last_result_event: Option<serde_json::Value> = None;
+struct ToolResult {
+    output: String,
+    is_error: bool,
+    ended_at: String,
+}
 
-    struct AskUserQuestionCall {
-        tool_use_id: String,
-        questions: Vec<Question>,
+impl ToolResult {
+    fn apply(&self, tool: &mut ToolCall) {
+        tool.output = Some(self.output.clone());
+        tool.status = if self.is_error { "error" } else { "completed" }.to_string();
+        tool.error = if self.is_error { Some(self.output.clone()) } else { None };
+        tool.ended_at = Some(self.ended_at.clone());
     }
+}
 
-    let mut ask_user_question_calls: Vec<AskUserQuestionCall> = Vec::new();
-    let tracking = Arc::new(Mutex::new(StreamTrackingState::default()));
+struct AskUserQuestionCall {
+    tool_use_id: String,
+    questions: Vec<Question>,
+}
 
-    for line in content.lines() {
+/// Transcript lines parsed one at a time, so a transcript that keeps growing
+/// can be followed without re-parsing what was already read
+pub(crate) struct TranscriptParser {
+    max_output: Option<usize>,
+    messages: Vec<Message>,
+    summaries: Vec<TranscriptSummary>,
+    // Track message IDs to merge duplicate assistant events (Claude emits one per tool)
+    message_index_by_id: HashMap<String, usize>,
+    /// Message holding each tool call, so a later result can be applied
+    message_index_by_tool: HashMap<String, usize>,
+    tool_results: HashMap<String, ToolResult>,
+    current_todos: Option<Vec<TodoItem>>,
+    /// Latest user prompt not yet followed by an assistant reply; its ID is
+    /// kept until then so followers see one message
+    pending_user: Option<Message>,
+    last_result_event: Option<serde_json::Value>,
+    ask_user_question_calls: Vec<AskUserQuestionCall>,
+    tracking: Arc<Mutex<StreamTrackingState>>,
+    /// Messages added or changed since `take_changed`, by index
+    changed: std::collections::BTreeSet<usize>,
+}
+
+impl TranscriptParser {
+    pub(crate) fn new() -> Self {
+        Self {
+            max_output: config::transcript_max_tool_output_bytes(),
+            messages: Vec::new(),
+            summaries: Vec::new(),
+            message_index_by_id: HashMap::new(),
+            message_index_by_tool: HashMap::new(),
+            tool_results: HashMap::new(),
+            current_todos: None,
+            pending_user: None,
+            last_result_event: None,
+            ask_user_question_calls: Vec::new(),
+            tracking: Arc::new(Mutex::new(StreamTrackingState::default())),
+            changed: std::collections::BTreeSet::new(),
+        }
+    }
+
+    fn set_user_text(&mut self, text: String, timestamp: String) {
+        match self.pending_user.as_mut() {
+            Some(pending) => {
+                pending.text = text;
+                pending.timestamp = timestamp;
+            }
+            None => {
+                self.pending_user = Some(Message {
+                    id: Uuid::new_v4().to_string(),
+                    role: "user".to_string(),
+                    text,
+                    thinking: None,
+                    tool_calls: None,
+                    file_blocks: None,
+                    is_streaming: None,
+                    timestamp,
+                    usage: None,
+                });
+            }
+        }
+    }
+
+    /// Parse one transcript line
+    pub(crate) fn feed(&mut self, line: &str) {
         let trimmed = line.trim();
         if trimmed.is_empty() {
-            continue;
+            return;
         }
 
         let event = match serde_json::from_str::<serde_json::Value>(trimmed) {
             Ok(value) => value,
-            Err(_) => continue,
+            Err(_) => return,
         };
 
         let event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("");
 
         if event_type == "result" {
-            last_result_event = Some(event);
-            continue;
+            self.last_result_event = Some(event);
+            return;
         }
 
         if event_type == "summary" {
             if let Some(summary_text) = event.get("summary").and_then(|v| v.as_str()) {
-                summaries.push(TranscriptSummary {
+                self.summaries.push(TranscriptSummary {
                     summary: summary_text.to_string(),
                 });
             }
-            continue;
+            return;
         }
 
         if event_type.is_empty() || event_type == "queue-operation" || event_type == "system" {
-            continue;
+            return;
         }
 
         if event_type == "user" {
@@ -791,58 +2081,61 @@ pub fn parse_transcript_content(content: &str) -> TranscriptParseResult {
             if let Some(text) = content.and_then(|c| c.as_str()) {
                 let text_trimmed = text.trim();
                 if !text_trimmed.is_empty() {
-                    last_user_text = Some(text_trimmed.to_string());
+                    self.set_user_text(text_trimmed.to_string(), event_timestamp(&event));
                 }
             } else if let Some(items) = content.and_then(|c| c.as_array()) {
                 for item in items {
                     if item.get("type").and_then(|v| v.as_str()) == Some("text") {
                         if let Some(text) = item.get("text").and_then(|v| v.as_str()) {
-                            last_user_text = Some(text.to_string());
+                            self.set_user_text(text.to_string(), event_timestamp(&event));
                         }
                     }
 
                     if item.get("type").and_then(|v| v.as_str()) == Some("tool_result") {
                         if let Some(tool_use_id) = item.get("tool_use_id").and_then(|v| v.as_str()) {
                             let output = normalize_output(item.get("content"));
+                            // Task results end with the agent ID subagent loading needs
+                            let is_task =
+                                lock_tracking(&self.tracking).tool_names.get(tool_use_id).is_some_and(|n| n == "Task");
+                            let output = if is_task { output } else { truncate_output(output, self.max_output) };
                             let is_error = item.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
-                            tool_results.insert(tool_use_id.to_string(), ToolResult { output, is_error });
+                            let ended_at = event_timestamp(&event);
+                            let result = ToolResult { output, is_error, ended_at };
+                            // tool_result events come AFTER their assistant events
+                            if let Some(&idx) = self.message_index_by_tool.get(tool_use_id) {
+                                let tools = self.messages[idx].tool_calls.iter_mut().flatten();
+                                for tool in tools.filter(|t| t.id == tool_use_id && t.status == "running") {
+                                    result.apply(tool);
+                                    self.changed.insert(idx);
+                                }
+                            }
+                            self.tool_results.insert(tool_use_id.to_string(), result);
                         }
                     }
                 }
             }
-            continue;
+            return;
         }
 
         if event_type == "assistant" {
-            if let Some(text) = last_user_text.take() {
-                let user_msg = Message {
-                    id: Uuid::new_v4().to_string(),
-                    role: "user".to_string(),
-                    text,
-                    tool_calls: None,
-                    file_blocks: None,
-                    is_streaming: None,
-                    timestamp: Utc::now().to_rfc3339(),
-                };
-                messages.push(user_msg);
+            if let Some(user_msg) = self.pending_user.take() {
+                self.changed.insert(self.messages.len());
+                self.messages.push(user_msg);
             }
 
-            if let Some(mut parsed) = parse_assistant_event(&event, &tracking, false) {
+            if let Some(mut parsed) = parse_assistant_event(&event, &self.tracking, false) {
                 // Process tool calls
                 let mut updated_calls = Vec::new();
                 if let Some(tool_calls) = parsed.message.tool_calls.take() {
                     for mut tool in tool_calls {
-                        if let Some(result) = tool_results.get(&tool.id) {
-                            tool.output = Some(result.output.clone());
-                            tool.status = if result.is_error { "error" } else { "completed" }.to_string();
-                            tool.error = if result.is_error { Some(result.output.clone()) } else { None };
-                            tool.ended_at = Some(Utc::now().to_rfc3339());
+                        if let Some(result) = self.tool_results.get(&tool.id) {
+                            result.apply(&mut tool);
                         }
 
                         if tool.name == "AskUserQuestion" {
                             if let Some(questions_value) = tool.input.get("questions") {
                                 if let Ok(questions) = serde_json::from_value::<Vec<Question>>(questions_value.clone()) {
-                                    ask_user_question_calls.push(AskUserQuestionCall {
+                                    self.ask_user_question_calls.push(AskUserQuestionCall {
                                         tool_use_id: tool.id.clone(),
                                         questions,
                                     });
@@ -855,101 +2148,135 @@ pub fn parse_transcript_content(content: &str) -> TranscriptParseResult {
                 }
 
                 if let Some(todos) = parsed.todos.take() {
-                    current_todos = Some(todos);
+                    self.current_todos = Some(todos);
                 }
 
                 // Check if we've seen this message ID before (Claude emits multiple events per message)
                 let msg_id = parsed.message.id.clone();
-                if let Some(&existing_idx) = message_index_by_id.get(&msg_id) {
-                    // Merge into existing message
-                    let existing = &mut messages[existing_idx];
-                    // Append text
-                    if !parsed.message.text.is_empty() {
-                        existing.text.push_str(&parsed.message.text);
+                let idx = match self.message_index_by_id.get(&msg_id) {
+                    Some(&existing_idx) => {
+                        // Merge into existing message
+                        let existing = &mut self.messages[existing_idx];
+                        // Append text
+                        if !parsed.message.text.is_empty() {
+                            existing.text.push_str(&parsed.message.text);
+                        }
+                        if let Some(thinking) = parsed.message.thinking.take() {
+                            existing.thinking.get_or_insert_with(String::new).push_str(&thinking);
+                        }
+                        // Every line of a message repeats its usage; the last is final
+                        if parsed.message.usage.is_some() {
+                            existing.usage = parsed.message.usage.take();
+                        }
+                        // Merge tool calls
+                        for tool in &updated_calls {
+                            self.message_index_by_tool.insert(tool.id.clone(), existing_idx);
+                        }
+                        if !updated_calls.is_empty() {
+                            if let Some(ref mut existing_tools) = existing.tool_calls {
+                                existing_tools.extend(updated_calls);
+                            } else {
+                                existing.tool_calls = Some(updated_calls);
+                            }
+                        }
+                        existing_idx
                     }
-                    // Merge tool calls
-                    if !updated_calls.is_empty() {
-                        if let Some(ref mut existing_tools) = existing.tool_calls {
-                            existing_tools.extend(updated_calls);
-                        } else {
-                            existing.tool_calls = Some(updated_calls);
+                    None => {
+                        // New message
+                        let idx = self.messages.len();
+                        for tool in &updated_calls {
+                            self.message_index_by_tool.insert(tool.id.clone(), idx);
                         }
+                        parsed.message.tool_calls = if updated_calls.is_empty() { None } else { Some(updated_calls) };
+                        self.message_index_by_id.insert(msg_id, idx);
+                        self.messages.push(parsed.message);
+                        idx
                     }
-                } else {
-                    // New message
-                    parsed.message.tool_calls = if updated_calls.is_empty() { None } else { Some(updated_calls) };
-                    let idx = messages.len();
-                    message_index_by_id.insert(msg_id, idx);
-                    messages.push(parsed.message);
-                }
+                };
+                self.changed.insert(idx);
             }
-            continue;
         }
     }
 
-    if let Some(text) = last_user_text {
-        messages.push(Message {
-            id: Uuid::new_v4().to_string(),
-            role: "user".to_string(),
-            text,
-            tool_calls: None,
-            file_blocks: None,
-            is_streaming: None,
-            timestamp: Utc::now().to_rfc3339(),
-        });
+    /// Messages added or changed since the last call, including a prompt
+    /// still waiting for its reply
+    pub(crate) fn take_changed(&mut self) -> Vec<Message> {
+        let mut changed: Vec<Message> =
+            std::mem::take(&mut self.changed).into_iter().map(|idx| self.messages[idx].clone()).collect();
+        for message in changed.iter().filter(|m| m.role == "assistant") {
+            remember_message_text(&message.id, &message.text, false);
+        }
+        changed.extend(self.pending_user.clone());
+        changed
     }
 
-    // Second pass: apply tool results collected during parsing
-    // (tool_result events come AFTER their corresponding assistant events in the transcript)
-    for message in &mut messages {
-        if let Some(ref mut tool_calls) = message.tool_calls {
-            for tool in tool_calls {
-                if tool.status == "running" {
-                    if let Some(result) = tool_results.get(&tool.id) {
-                        tool.output = Some(result.output.clone());
-                        tool.status = if result.is_error { "error" } else { "completed" }.to_string();
-                        tool.error = if result.is_error { Some(result.output.clone()) } else { None };
-                        tool.ended_at = Some(Utc::now().to_rfc3339());
-                    }
-                }
-            }
-        }
+    pub(crate) fn todos(&self) -> Option<Vec<TodoItem>> {
+        self.current_todos.clone()
     }
 
-    let mut pending_question: Option<PendingQuestionFromTranscript> = None;
-    for call in ask_user_question_calls {
-        if !tool_results.contains_key(&call.tool_use_id) {
-            pending_question = Some(PendingQuestionFromTranscript {
-                tool_use_id: call.tool_use_id,
-                questions: call.questions,
-            });
+    /// Usage and total cost from the last result event
+    pub(crate) fn usage(&self) -> (Option<SessionUsage>, Option<f64>) {
+        match &self.last_result_event {
+            Some(result_event) => (
+                parse_usage(result_event),
+                result_event.get("total_cost_usd").and_then(|v| v.as_f64()),
+            ),
+            None => (None, None),
         }
     }
 
-    let mut usage: Option<SessionUsage> = None;
-    let mut total_cost_usd: Option<f64> = None;
+    /// Everything parsed so far
+    pub(crate) fn snapshot(&self) -> TranscriptParseResult {
+        let mut messages = self.messages.clone();
+        messages.extend(self.pending_user.clone());
+        self.result(messages)
+    }
 
-    if let Some(result_event) = last_result_event {
-        usage = parse_usage(&result_event);
-        total_cost_usd = result_event.get("total_cost_usd").and_then(|v| v.as_f64());
+    fn finish(mut self) -> TranscriptParseResult {
+        let mut messages = std::mem::take(&mut self.messages);
+        messages.extend(self.pending_user.take());
+        self.result(messages)
     }
 
-    TranscriptParseResult {
-        messages,
-        todos: current_todos,
-        usage,
-        total_cost_usd,
-        pending_question,
-        summaries,
-        subagent_tools: vec![],
+    fn result(&self, messages: Vec<Message>) -> TranscriptParseResult {
+        for message in &messages {
+            if message.role == "assistant" {
+                remember_message_text(&message.id, &message.text, false);
+            }
+        }
+
+        let mut pending_question: Option<PendingQuestionFromTranscript> = None;
+        for call in &self.ask_user_question_calls {
+            if !self.tool_results.contains_key(&call.tool_use_id) {
+                pending_question = Some(PendingQuestionFromTranscript {
+                    tool_use_id: call.tool_use_id.clone(),
+                    questions: call.questions.clone(),
+                });
+            }
+        }
+
+        let (usage, total_cost_usd) = self.usage();
+        TranscriptParseResult {
+            messages,
+            todos: self.current_todos.clone(),
+            usage,
+            total_cost_usd,
+            pending_question,
+            summaries: self.summaries.clone(),
+            subagent_tools: vec![],
+            subagent_usage: None,
+            slow_storage: false,
+            annotations: vec![],
+        }
     }
 }
 
 /// Parse a transcript file including all subagent transcripts
 /// This recursively loads Task tool children from their separate transcript files
 pub fn parse_transcript_with_subagents(transcript_path: &Path) -> TranscriptParseResult {
-    let content = match std::fs::read_to_string(transcript_path) {
-        Ok(c) => c,
+    profile_span!("parse_transcript_with_subagents", path = %transcript_path.display());
+    let result = match parse_transcript_file(transcript_path) {
+        Ok(r) => r,
         Err(e) => {
             debug_log!("TRANSCRIPT", "Failed to read transcript: {}", e);
             return TranscriptParseResult {
@@ -960,88 +2287,227 @@ pub fn parse_transcript_with_subagents(transcript_path: &Path) -> TranscriptPars
                 pending_question: None,
                 summaries: vec![],
                 subagent_tools: vec![],
+                subagent_usage: None,
+                slow_storage: false,
+                annotations: vec![],
             };
         }
     };
 
-    let mut result = parse_transcript_content(&content);
+    with_subagents(result, transcript_path)
+}
+
+/// How many levels of subagents (Task calls inside subagent transcripts)
+/// are loaded; also stops agent IDs that refer back to each other
+pub(crate) const MAX_SUBAGENT_DEPTH: usize = 8;
+
+/// Load the subagent transcript behind `task`, and recursively those of the
+/// Task calls in it, pushing their tools onto `tools`. Sets `task`'s usage
+/// and tool count; returns its usage including nested subagents.
+fn load_subagent(
+    parent_dir: &Path,
+    task: &mut ToolCall,
+    depth: usize,
+    visited: &mut HashSet<String>,
+    tools: &mut Vec<ToolCall>,
+) -> Option<SessionUsage> {
+    let agent_id = extract_agent_id_from_result(task.output.as_deref()?)?;
+    if depth > MAX_SUBAGENT_DEPTH || !visited.insert(agent_id.clone()) {
+        debug_log!("TRANSCRIPT", "Not loading subagent {} (depth {}, or already loaded)", agent_id, depth);
+        return None;
+    }
+    let subagent_path = parent_dir.join(format!("{}.jsonl", agent_id));
+    debug_log!("TRANSCRIPT", "Loading subagent transcript: {:?}", subagent_path);
+    // Usage is tallied from the same pass, so the file is read once
+    let mut tally = subagent_usage::Tally::default();
+    let parsed = parse_transcript_lines(transcript_lines(&subagent_path).ok()?.inspect(|line| tally.add_line(line)));
+    let mut usage = tally.total();
+    for message in parsed.messages {
+        for mut tool in message.tool_calls.into_iter().flatten() {
+            if tool.parent_tool_id.is_none() {
+                tool.parent_tool_id = Some(task.id.clone());
+            }
+            let mut nested_tools = Vec::new();
+            if tool.name == "Task" {
+                if let Some(nested) = load_subagent(parent_dir, &mut tool, depth + 1, visited, &mut nested_tools) {
+                    usage = Some(subagent_usage::add(usage, &nested));
+                }
+            }
+            tools.push(tool);
+            tools.extend(nested_tools);
+        }
+    }
+
+    let child_count = tools.iter().filter(|t| t.parent_tool_id.as_ref() == Some(&task.id)).count();
+    if child_count > 0 {
+        if let Some(ref mut subagent) = task.subagent {
+            subagent.tool_count = Some(child_count);
+        }
+    }
+    task.usage = usage.clone();
+    usage
+}
+
+/// Load the subagent transcripts of the Task tools in `result` (nested
+/// ones included) and attach their tools and usage. Secrets are masked here
+/// if redaction is on, since every parse ends up in this function.
+pub fn with_subagents(mut result: TranscriptParseResult, transcript_path: &Path) -> TranscriptParseResult {
     let parent_dir = match transcript_path.parent() {
         Some(d) => d,
-        None => return result,
+        None => return redaction::redact_serialized(result),
     };
 
-    // Collect subagent tools from Task tool outputs
-    let mut all_subagent_tools: Vec<ToolCall> = Vec::new();
-
-    for message in &result.messages {
-        if let Some(ref tools) = message.tool_calls {
-            for tool in tools {
-                if tool.name == "Task" {
-                    if let Some(ref output) = tool.output {
-                        if let Some(agent_id) = extract_agent_id_from_result(output) {
-                            let subagent_path = parent_dir.join(format!("{}.jsonl", agent_id));
-                            if subagent_path.exists() {
-                                debug_log!("TRANSCRIPT", "Loading subagent transcript: {:?}", subagent_path);
-                                let sub_content = match std::fs::read_to_string(&subagent_path) {
-                                    Ok(c) => c,
-                                    Err(_) => continue,
-                                };
-                                let sub_result = parse_transcript_content(&sub_content);
-
-                                // Extract tools from subagent messages, set parent_tool_id
-                                for sub_message in sub_result.messages {
-                                    if let Some(sub_tools) = sub_message.tool_calls {
-                                        for mut sub_tool in sub_tools {
-                                            // Set parent to the Task tool
-                                            if sub_tool.parent_tool_id.is_none() {
-                                                sub_tool.parent_tool_id = Some(tool.id.clone());
-                                            }
-                                            all_subagent_tools.push(sub_tool);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+    let mut subagent_tools: Vec<ToolCall> = Vec::new();
+    let mut visited: HashSet<String> = HashSet::new();
+    for message in &mut result.messages {
+        for tool in message.tool_calls.iter_mut().flatten() {
+            if tool.name != "Task" {
+                continue;
+            }
+            // Task usage covers the whole subtree, so only top-level Tasks add to the total
+            if let Some(usage) = load_subagent(parent_dir, tool, 1, &mut visited, &mut subagent_tools) {
+                result.subagent_usage = Some(subagent_usage::add(result.subagent_usage.take(), &usage));
             }
         }
     }
 
-    // Update Task tools with tool_count
-    for message in &mut result.messages {
-        if let Some(ref mut tools) = message.tool_calls {
-            for tool in tools {
-                if tool.name == "Task" {
-                    let child_count = all_subagent_tools
-                        .iter()
-                        .filter(|t| t.parent_tool_id.as_ref() == Some(&tool.id))
-                        .count();
-                    if child_count > 0 {
-                        if let Some(ref mut subagent) = tool.subagent {
-                            subagent.tool_count = Some(child_count);
-                        }
-                    }
-                }
+    result.subagent_tools = subagent_tools;
+    // Last, so the agent IDs in Task results are found and suggested edits
+    // (cached during the parse) still apply the real text
+    redaction::redact_serialized(result)
+}
+
+/// Log and dispatch one stream-json line from Claude's stdout
+fn handle_stdout_line(
+    line: &str,
+    line_count: usize,
+    tracking: &Arc<Mutex<StreamTrackingState>>,
+    app: &AppHandle,
+    ui_session_id: &str,
+) {
+    profile_span!("stream_line", session = ui_session_id, line = line_count);
+    let truncated = if line.len() > 300 {
+        // Find valid UTF-8 boundary
+        let mut end = 300;
+        while !line.is_char_boundary(end) && end > 0 {
+            end -= 1;
+        }
+        &line[..end]
+    } else {
+        line
+    };
+    debug_log!("STDOUT", "[{}] Line {}: {}", ui_session_id, line_count, truncated);
+
+    lock_tracking(tracking).last_activity = Some(Instant::now());
+
+    // Try to parse as JSON
+    match serde_json::from_str::<serde_json::Value>(line) {
+        Ok(event) => {
+            let event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("unknown");
+            debug_log!("STDOUT", "[{}] Parsed event type: {}", ui_session_id, event_type);
+            schema_drift::record(&event);
+
+            if let Err(e) = process_event(&event, tracking, Some(app), ui_session_id) {
+                debug_log!("EMIT", "[{}] Emit error: {}", ui_session_id, e);
+            }
+        }
+        Err(e) => {
+            let raw: String = line.chars().take(100).collect();
+            debug_log!("STDOUT", "[{}] JSON parse error: {} - raw: {}", ui_session_id, e, raw);
+        }
+    }
+}
+
+/// Forward `--include-partial-messages` deltas as `message.delta`. Subagent
+/// streams are skipped; their messages arrive whole as before.
+fn handle_stream_event(
+    event: &serde_json::Value,
+    tracking: &Arc<Mutex<StreamTrackingState>>,
+    app: Option<&AppHandle>,
+    ui_session_id: &str,
+) -> Result<(), String> {
+    if event.get("parent_tool_use_id").is_some_and(|p| !p.is_null()) {
+        return Ok(());
+    }
+    let inner = match event.get("event") {
+        Some(inner) => inner,
+        None => return Ok(()),
+    };
+    let mut state = lock_tracking(tracking);
+
+    match inner.get("type").and_then(|v| v.as_str()).unwrap_or("") {
+        "message_start" => {
+            state.streaming_message_id = inner
+                .get("message")
+                .and_then(|m| m.get("id"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+        }
+        "content_block_delta" => {
+            let message_id = match state.streaming_message_id.clone() {
+                Some(id) => id,
+                None => return Ok(()),
+            };
+            let delta = inner.get("delta");
+            let text = delta
+                .filter(|d| d.get("type").and_then(|v| v.as_str()) == Some("text_delta"))
+                .and_then(|d| d.get("text"))
+                .and_then(|v| v.as_str());
+            let thinking = delta
+                .filter(|d| d.get("type").and_then(|v| v.as_str()) == Some("thinking_delta"))
+                .and_then(|d| d.get("thinking"))
+                .and_then(|v| v.as_str());
+            if text.is_none() && thinking.is_none() {
+                return Ok(());
+            }
+            state.streamed_message_ids.insert(message_id.clone());
+            drop(state);
+
+            if let Some(text) = text {
+                remember_message_text(&message_id, text, true);
             }
+            emit(
+                app,
+                BackendEvent::MessageDelta {
+                    ui_session_id: ui_session_id.to_string(),
+                    message_id,
+                    text: text.map(|s| s.to_string()),
+                    thinking: thinking.map(|s| s.to_string()),
+                },
+            );
+        }
+        "message_stop" => {
+            state.streaming_message_id = None;
         }
+        _ => {}
     }
+    Ok(())
+}
 
-    result.subagent_tools = all_subagent_tools;
-    result
+/// Emit unless `process_event` is running without an app
+fn emit(app: Option<&AppHandle>, event: BackendEvent) {
+    if let Some(app) = app {
+        let _ = events::emit(app, event);
+    }
 }
 
-fn process_event(
+/// Apply one stdout event to the session. Without an app (fuzzing) only the
+/// tracking state is updated; nothing is emitted, persisted or started.
+pub(crate) fn process_event(
     event: &serde_json::Value,
     tracking: &Arc<Mutex<StreamTrackingState>>,
-    app: &AppHandle,
+    app: Option<&AppHandle>,
     ui_session_id: &str,
 ) -> Result<(), String> {
     let event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("");
 
     match event_type {
         "system" => {
-            if let Ok(mut state) = tracking.lock() {
+            let mut record_git = None;
+            let mut record_environment = None;
+            let mut record_snapshot = None;
+            {
+                let mut state = lock_tracking(tracking);
                 if let Some(transcript_path) = event.get("transcript_path").and_then(|v| v.as_str()) {
                     state.transcript_path = Some(PathBuf::from(transcript_path));
                     debug_log!("TOOL_TRACK", "Set transcript path: {}", transcript_path);
@@ -1050,31 +2516,83 @@ fn process_event(
                 if let Some(session_id) = event.get("session_id").and_then(|v| v.as_str()) {
                     let should_emit = state.claude_session_id.as_deref() != Some(session_id);
                     state.claude_session_id = Some(session_id.to_string());
-                    if should_emit {
-                        let _ = app.emit(
-                            "horseman-event",
+                    record_git = state.git_context.take().map(|git| (session_id.to_string(), git));
+                    record_environment = state
+                        .environment
+                        .take()
+                        .map(|env| (session_id.to_string(), env, state.working_directory.clone()));
+                    record_snapshot = state.workspace_snapshot.take().map(|snap| (session_id.to_string(), snap));
+                    if let Some(app) = app.filter(|_| should_emit) {
+                        open_sessions::set_claude_session_id(ui_session_id, session_id);
+                        let _ = events::emit(
+                            app,
                             BackendEvent::SessionStarted {
                                 ui_session_id: ui_session_id.to_string(),
                                 claude_session_id: session_id.to_string(),
+                                permission_mode: state.permission_mode,
                             },
                         );
                     }
                 }
             }
+            if let Some((session_id, git)) = record_git {
+                if let Err(e) = session_cache::record_git_context(&session_id, &git) {
+                    debug_log!("STDOUT", "[{}] Failed to record git context: {}", ui_session_id, e);
+                }
+            }
+            if let Some((session_id, snapshot)) = record_snapshot {
+                if let Err(e) = session_cache::record_workspace_snapshot(&session_id, &snapshot) {
+                    debug_log!("STDOUT", "[{}] Failed to record workspace snapshot: {}", ui_session_id, e);
+                }
+            }
+            if event.get("subtype").and_then(|v| v.as_str()) == Some("init") {
+                tool_catalog::record_init(ui_session_id, event);
+            }
+            if let Some((session_id, mut environment, working_directory)) = record_environment {
+                session_env::complete(&mut environment, event, &working_directory);
+                if environment.claude_version.is_none() {
+                    environment.claude_version = get_claude_version();
+                }
+                if let Err(e) = session_cache::record_environment(&session_id, &environment) {
+                    debug_log!("STDOUT", "[{}] Failed to record environment: {}", ui_session_id, e);
+                }
+            }
         }
         "assistant" => {
-            if let Some(parsed) = parse_assistant_event(event, tracking, true) {
-                let _ = app.emit(
-                    "horseman-event",
-                    BackendEvent::MessageAssistant {
-                        ui_session_id: ui_session_id.to_string(),
-                        message: parsed.message,
-                    },
-                );
+            if let Some(mut parsed) = parse_assistant_event(event, tracking, true) {
+                let streamed = lock_tracking(tracking).streamed_message_ids.contains(&parsed.message.id);
+                if streamed {
+                    // Already sent (and cached) as deltas; keep only the tool calls
+                    parsed.message.text.clear();
+                    parsed.message.thinking = None;
+                }
+                if let Some(thinking) = parsed.message.thinking.take() {
+                    emit(
+                        app,
+                        BackendEvent::MessageThinking {
+                            ui_session_id: ui_session_id.to_string(),
+                            message_id: parsed.message.id.clone(),
+                            thinking,
+                        },
+                    );
+                }
+                // Thinking-only events carry no visible content of their own
+                if !parsed.message.text.is_empty() || parsed.message.tool_calls.is_some() {
+                    remember_message_text(&parsed.message.id, &parsed.message.text, true);
+                    emit(
+                        app,
+                        BackendEvent::MessageAssistant {
+                            ui_session_id: ui_session_id.to_string(),
+                            message: parsed.message,
+                        },
+                    );
+                }
 
                 if let Some(todos) = parsed.todos {
-                    let _ = app.emit(
-                        "horseman-event",
+                    let working_directory = lock_tracking(tracking).working_directory.clone();
+                    todo_sync::observe(ui_session_id, &working_directory, &todos);
+                    emit(
+                        app,
                         BackendEvent::TodosUpdated {
                             ui_session_id: ui_session_id.to_string(),
                             todos,
@@ -1082,9 +2600,17 @@ fn process_event(
                     );
                 }
 
+                if parsed.tool_calls.iter().any(|t| verification::EDIT_TOOLS.contains(&t.name.as_str())) {
+                    lock_tracking(tracking).turn_edited_files = true;
+                }
+
+                let working_directory = lock_tracking(tracking).working_directory.clone();
                 for tool in parsed.tool_calls {
-                    let _ = app.emit(
-                        "horseman-event",
+                    if let Some(app) = app {
+                        edit_conflicts::observe(app, ui_session_id, &working_directory, &tool);
+                    }
+                    emit(
+                        app,
                         BackendEvent::ToolStarted {
                             ui_session_id: ui_session_id.to_string(),
                             tool,
@@ -1115,23 +2641,26 @@ fn process_event(
 
                     let is_error = item.get("is_error").and_then(|v| v.as_bool()).unwrap_or(false);
                     let output = normalize_output(item.get("content"));
+                    lock_tracking(tracking).running_tools.remove(&tool_use_id);
 
                     if is_error {
-                        let _ = app.emit(
-                            "horseman-event",
+                        emit(
+                            app,
                             BackendEvent::ToolError {
                                 ui_session_id: ui_session_id.to_string(),
                                 tool_id: tool_use_id.clone(),
                                 error: output.clone(),
+                                ended_at: event_timestamp(event),
                             },
                         );
                     } else {
-                        let _ = app.emit(
-                            "horseman-event",
+                        emit(
+                            app,
                             BackendEvent::ToolCompleted {
                                 ui_session_id: ui_session_id.to_string(),
                                 tool_id: tool_use_id.clone(),
                                 output: output.clone(),
+                                ended_at: event_timestamp(event),
                             },
                         );
                     }
@@ -1141,9 +2670,10 @@ fn process_event(
                             parent_tool_id: Some(parent_id),
                             status: None,
                             subagent: None,
+                            usage: None,
                         };
-                        let _ = app.emit(
-                            "horseman-event",
+                        emit(
+                            app,
                             BackendEvent::ToolUpdated {
                                 ui_session_id: ui_session_id.to_string(),
                                 tool_id: tool_use_id.clone(),
@@ -1153,7 +2683,7 @@ fn process_event(
                     }
 
                     let (is_task, transcript_path) = {
-                        let state = tracking.lock().map_err(|_| "Failed to lock tracking state")?;
+                        let state = lock_tracking(tracking);
                         let is_task = state.tool_names.get(&tool_use_id) == Some(&"Task".to_string());
                         (is_task, state.transcript_path.clone())
                     };
@@ -1163,15 +2693,31 @@ fn process_event(
                             debug_log!("TOOL_TRACK", "Task {} completed with agentId: {}", tool_use_id, agent_id);
 
                             if let Some(ref transcript_path) = transcript_path {
+                                if let Some(usage) = subagent_usage::for_agent(transcript_path, &agent_id) {
+                                    let update = ToolUpdate {
+                                        usage: Some(usage),
+                                        ..Default::default()
+                                    };
+                                    emit(
+                                        app,
+                                        BackendEvent::ToolUpdated {
+                                            ui_session_id: ui_session_id.to_string(),
+                                            tool_id: tool_use_id.clone(),
+                                            update,
+                                        },
+                                    );
+                                }
+
                                 let child_tool_ids = read_subagent_transcript(transcript_path, &agent_id);
                                 for child_id in child_tool_ids {
                                     let update = ToolUpdate {
                                         parent_tool_id: Some(tool_use_id.clone()),
                                         status: None,
                                         subagent: None,
+                                        usage: None,
                                     };
-                                    let _ = app.emit(
-                                        "horseman-event",
+                                    emit(
+                                        app,
                                         BackendEvent::ToolUpdated {
                                             ui_session_id: ui_session_id.to_string(),
                                             tool_id: child_id,
@@ -1182,7 +2728,8 @@ fn process_event(
                             }
                         }
 
-                        if let Ok(mut state) = tracking.lock() {
+                        {
+                            let mut state = lock_tracking(tracking);
                             state.active_task_stack.retain(|id| id != &tool_use_id);
                             debug_log!(
                                 "TOOL_TRACK",
@@ -1195,19 +2742,211 @@ fn process_event(
                 }
             }
         }
+        "stream_event" => {
+            handle_stream_event(event, tracking, app, ui_session_id)?;
+        }
         "result" => {
-            if let Some(usage) = parse_usage(event) {
-                let _ = app.emit(
-                    "horseman-event",
+            let failed = event.get("is_error").and_then(|v| v.as_bool()) == Some(true);
+            {
+                let mut state = lock_tracking(tracking);
+                state.streaming_message_id = None;
+                state.streamed_message_ids.clear();
+                state.running_tools.clear();
+                state.awaiting_result = false;
+                state.crash_restarts = 0;
+            }
+            let usage = parse_usage(event);
+            let cost = usage.as_ref().and_then(|u| u.cost);
+            let context_percent = usage.as_ref().map(SessionUsage::context_percent);
+
+            // Turn finished - verify the build if Claude touched files
+            let hit_turn_limit = event.get("subtype").and_then(|v| v.as_str()) == Some("error_max_turns");
+            let (edited_in, claude_session_id, persistent, working_directory) = {
+                let mut state = lock_tracking(tracking);
+                if hit_turn_limit {
+                    debug_log!("STDOUT", "[{}] Turn limit reached", ui_session_id);
+                    state.end_reason = Some(SessionEndReason::MaxTurns);
+                }
+                let edited = std::mem::take(&mut state.turn_edited_files);
+                let working_directory = state.working_directory.clone();
+                (
+                    edited.then(|| working_directory.clone()),
+                    state.claude_session_id.clone(),
+                    state.persistent,
+                    working_directory,
+                )
+            };
+            let reply = event.get("result").and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+            // The rest reports, records and starts follow-up work
+            let app = match app {
+                Some(app) => app,
+                None => return Ok(()),
+            };
+            if failed && event.get("result").and_then(|v| v.as_str()).is_some_and(connectivity::is_network_error) {
+                connectivity::report_network_error(app);
+            }
+            #[cfg(feature = "analytics")]
+            {
+                // Keep-alive processes report running totals, so record only what this turn added
+                let (turn_cost, claude_session_id, project) = {
+                    let mut state = lock_tracking(tracking);
+                    let total = cost.unwrap_or(state.recorded_cost);
+                    let turn_cost = (total - state.recorded_cost).max(0.0);
+                    state.recorded_cost = total;
+                    (turn_cost, state.claude_session_id.clone(), state.working_directory.clone())
+                };
+                cost_ledger::record_turn(ui_session_id, claude_session_id, &project, event, turn_cost);
+                budget::check(app);
+            }
+            if let Some(usage) = usage {
+                let _ = events::emit(
+                    app,
                     BackendEvent::UsageUpdated {
                         ui_session_id: ui_session_id.to_string(),
                         usage,
                     },
                 );
             }
+            let compacted = auto_compact::on_result(app, ui_session_id, failed.then(|| reply.clone()));
+            let retrying = rate_limit::on_result(app, ui_session_id, failed, &reply);
+            if !compacted && !retrying {
+                let turn = TurnOutcome {
+                    failed,
+                    hit_turn_limit,
+                    edited_files: edited_in.is_some(),
+                    reply,
+                };
+                suggestions::on_turn_end(app, ui_session_id, &working_directory, turn);
+            }
+            // A keep-alive process stays up, so session.ended won't mark the turn done
+            if persistent {
+                let _ = events::emit(
+                    app,
+                    BackendEvent::TurnCompleted {
+                        ui_session_id: ui_session_id.to_string(),
+                    },
+                );
+            }
+            fix_loop::record_turn(ui_session_id, claude_session_id, cost);
+            match edited_in {
+                Some(working_directory) => {
+                    if verification::spawn_verification(app, ui_session_id, &working_directory) {
+                        debug_log!("VERIFY", "[{}] Started post-turn verification", ui_session_id);
+                    } else {
+                        fix_loop::stop(app, ui_session_id, "unverified");
+                    }
+                }
+                // Nothing changed, so another loop iteration can't help
+                None => {
+                    fix_loop::stop(app, ui_session_id, "noEdits");
+                }
+            }
+            if let Some(percent) = context_percent {
+                context_limit::on_turn_end(app, ui_session_id, percent);
+                if !failed && !compacted {
+                    auto_compact::maybe_compact(app, ui_session_id, percent, persistent);
+                }
+            }
         }
         _ => {}
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracking_survives_a_panicking_reader() {
+        let tracking = Arc::new(Mutex::new(StreamTrackingState::default()));
+        let reader = tracking.clone();
+        let _ = std::thread::spawn(move || {
+            let _state = reader.lock();
+            panic!("reader crashed");
+        })
+        .join();
+        assert!(tracking.is_poisoned());
+        lock_tracking(&tracking).awaiting_result = true;
+        assert!(lock_tracking(&tracking).awaiting_result);
+    }
+
+    fn task_line(message_id: &str, tool_id: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","message":{{"id":"{}","content":[{{"type":"tool_use","id":"{}","name":"Task","input":{{"description":"d","prompt":"p"}}}}]}}}}"#,
+            message_id, tool_id
+        )
+    }
+
+    fn result_line(tool_id: &str, agent_id: &str) -> String {
+        format!(
+            r#"{{"type":"user","message":{{"content":[{{"type":"tool_result","tool_use_id":"{}","content":"agentId: {}"}}]}}}}"#,
+            tool_id, agent_id
+        )
+    }
+
+    #[test]
+    fn loads_nested_subagents_once() {
+        let dir = std::env::temp_dir().join(format!("horseman-subagents-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, lines: &[String]| std::fs::write(dir.join(name), lines.join("\n")).unwrap();
+        let main = vec![
+            r#"{"type":"user","message":{"content":"go"}}"#.to_string(),
+            task_line("m1", "task1"),
+            result_line("task1", "agent-a"),
+        ];
+        write("main.jsonl", &main);
+        // agent-a starts agent-b, which points back at agent-a
+        write("agent-a.jsonl", &[task_line("a1", "task2"), result_line("task2", "agent-b")]);
+        write("agent-b.jsonl", &[task_line("b1", "task3"), result_line("task3", "agent-a")]);
+
+        let path = dir.join("main.jsonl");
+        let result = with_subagents(parse_transcript_content(&main.join("\n")), &path);
+        let parents: Vec<(&str, Option<&str>)> = result
+            .subagent_tools
+            .iter()
+            .map(|t| (t.id.as_str(), t.parent_tool_id.as_deref()))
+            .collect();
+        assert_eq!(parents, vec![("task2", Some("task1")), ("task3", Some("task2"))]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn follow_ups_join_queued_spawns() {
+        let spawn = |prompt: Option<&str>, resume: Option<&str>, model: &str| QueuedSpawn {
+            ui_session_id: "s1".to_string(),
+            working_directory: "/work".to_string(),
+            initial_prompt: prompt.map(str::to_string),
+            resume_session: resume.map(str::to_string),
+            options: SessionOptions { model: Some(model.to_string()), ..Default::default() },
+        };
+        let mut queued = spawn(Some("first"), None, "opus");
+        queued.merge(spawn(Some("second"), Some("c1"), "opus")).unwrap();
+        queued.merge(spawn(None, Some("c1"), "opus")).unwrap();
+        assert_eq!(queued.initial_prompt.as_deref(), Some("first\n\nsecond"));
+        assert_eq!(queued.resume_session.as_deref(), Some("c1"));
+
+        assert!(queued.merge(spawn(Some("third"), None, "haiku")).is_err());
+        assert!(queued.merge(spawn(Some("third"), Some("c2"), "opus")).is_err());
+        assert_eq!(queued.initial_prompt.as_deref(), Some("first\n\nsecond"));
+        assert_eq!(queued.options.model.as_deref(), Some("opus"));
+    }
+
+    #[test]
+    fn bounded_lines_replace_oversized_lines() {
+        let huge = format!(
+            r#"{{"parentUuid":"p","type":"user","message":{{"content":[{{"tool_use_id":"toolu_1","content":"{}"}}]}}}}"#,
+            "x".repeat(200)
+        );
+        let input = format!("short\n{}\nafter\nlast", huge);
+        let lines: Vec<String> = BoundedLines::new(std::io::BufReader::with_capacity(8, input.as_bytes()), 128).collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!((lines[0].as_str(), lines[2].as_str(), lines[3].as_str()), ("short", "after", "last"));
+        let stand_in: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(stand_in["message"]["content"][0]["tool_use_id"], "toolu_1");
+        assert_eq!(truncate_output("héllo".to_string(), Some(2)), "h\n… [5 bytes truncated]");
+        assert_eq!(truncate_output("ok".to_string(), Some(2)), "ok");
+    }
+}
@@ -0,0 +1,213 @@
+//! Rate-limit and overload handling. A turn that fails with an API 429/529
+//! error or a usage-limit message (in its result, or on stderr while it ran)
+//! is reported once as `session.rate_limited`, with the reset time when
+//! Claude gives one. With `rateLimitMaxRetries`, the failed turn is
+//! continued after a backoff (or once the limit resets).
+
+use super::crash_restart::retry_delay;
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, RateLimitKind};
+use crate::panic_guard;
+use chrono::{DateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Sent in place of the turn that failed
+pub const RETRY_PROMPT: &str =
+    "The previous turn failed because the API was rate limited or overloaded. Continue where you left off.";
+
+/// Longest wait for a limit to reset before giving up on retrying
+const MAX_RESET_WAIT: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// `API Error: 429 {"type":"error","error":{"type":"rate_limit_error",...}}`,
+/// as the CLI reports a failed request: the status and the JSON error body
+static API_ERROR: Lazy<Regex> = Lazy::new(|| Regex::new(r"API Error: (\d{3})\b\s*(\{.*\})?").unwrap());
+/// "Claude AI usage limit reached|1760000000", "retry-after: 30",
+/// "retry after 30 seconds"
+static RESET_EPOCH: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)limit reached\|(\d{9,11})").unwrap());
+static RETRY_AFTER: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)retry[- ]after:?\s*(\d+)").unwrap());
+
+#[derive(Default)]
+struct RetryState {
+    /// Retries made for the current failure streak
+    attempts: u32,
+    /// Bumped per scheduled retry, so a superseded one doesn't fire
+    generation: u64,
+    /// Whether a retry is waiting
+    pending: bool,
+    /// First limit error on stderr during the current turn
+    stderr_error: Option<(RateLimitKind, String)>,
+}
+
+static SESSIONS: Lazy<std::sync::Mutex<HashMap<String, RetryState>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// What kind of limit `text` (a stderr line or result message) reports:
+/// a usage-limit message, or an API error whose body's type (or, without a
+/// body, whose status) is a rate limit or overload
+pub fn detect(text: &str) -> Option<RateLimitKind> {
+    if RESET_EPOCH.is_match(text) {
+        return Some(RateLimitKind::RateLimited);
+    }
+    let caps = API_ERROR.captures(text)?;
+    let body: Option<serde_json::Value> = caps.get(2).and_then(|body| serde_json::from_str(body.as_str()).ok());
+    let error_type = body.as_ref().and_then(|body| body.pointer("/error/type")?.as_str());
+    match (error_type, &caps[1]) {
+        (Some("overloaded_error"), _) | (None, "529") => Some(RateLimitKind::Overloaded),
+        (Some("rate_limit_error"), _) | (None, "429") => Some(RateLimitKind::RateLimited),
+        _ => None,
+    }
+}
+
+/// When the limit resets, if `text` says
+fn resets_at(text: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Some(caps) = RESET_EPOCH.captures(text) {
+        return Utc.timestamp_opt(caps[1].parse().ok()?, 0).single();
+    }
+    let seconds: i64 = RETRY_AFTER.captures(text)?[1].parse().ok()?;
+    Some(now + chrono::Duration::seconds(seconds))
+}
+
+/// First line of an error, for the event
+fn summary(text: &str) -> String {
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("");
+    line.chars().take(300).collect()
+}
+
+/// Check a stderr line. The first limit error of a turn is kept for its
+/// result, which reports it; the CLI logs one per attempt while it retries.
+pub fn on_stderr(ui_session_id: &str, line: &str) {
+    let kind = match detect(line) {
+        Some(kind) => kind,
+        None => return,
+    };
+    panic_guard::lock_or_recover(&SESSIONS, "rate limits")
+        .entry(ui_session_id.to_string())
+        .or_default()
+        .stderr_error
+        .get_or_insert_with(|| (kind, line.to_string()));
+}
+
+fn emit(
+    app: &AppHandle,
+    ui_session_id: &str,
+    kind: RateLimitKind,
+    text: &str,
+    retry: Option<(u32, Duration)>,
+    max_retries: Option<u32>,
+) {
+    let now = Utc::now();
+    debug_log!("RATE_LIMIT", "[{}] {:?}: {} (retry: {:?})", ui_session_id, kind, summary(text), retry);
+    let _ = events::emit(
+        app,
+        BackendEvent::SessionRateLimited {
+            ui_session_id: ui_session_id.to_string(),
+            kind,
+            message: summary(text),
+            resets_at: resets_at(text, now).map(|t| t.to_rfc3339()),
+            attempt: retry.map(|(attempt, _)| attempt),
+            max_retries,
+            delay_ms: retry.map(|(_, delay)| delay.as_millis() as u64),
+        },
+    );
+}
+
+/// Called when a turn's result arrives; reports the turn if it failed on a
+/// limit. Returns whether a retry of a rate-limited turn was scheduled.
+pub fn on_result(app: &AppHandle, ui_session_id: &str, failed: bool, text: &str) -> bool {
+    let mut sessions = panic_guard::lock_or_recover(&SESSIONS, "rate limits");
+    let stderr_error = sessions.get_mut(ui_session_id).and_then(|state| state.stderr_error.take());
+    let (kind, text) = match (failed, detect(text), stderr_error) {
+        (true, Some(kind), _) => (kind, text.to_string()),
+        // The result only says the turn failed; stderr said why
+        (true, None, Some(stderr_error)) => stderr_error,
+        _ => {
+            // A turn got through (or failed otherwise): the streak is over
+            sessions.remove(ui_session_id);
+            return false;
+        }
+    };
+    let text = text.as_str();
+    let state = sessions.entry(ui_session_id.to_string()).or_default();
+    let max_retries = config::rate_limit_max_retries();
+    let backoff = retry_delay(state.attempts, max_retries, config::rate_limit_retry_backoff());
+    let reset_wait = resets_at(text, Utc::now()).and_then(|t| (t - Utc::now()).to_std().ok());
+    let delay = match (backoff, reset_wait) {
+        (Some(_), Some(wait)) if wait > MAX_RESET_WAIT => None,
+        (Some(backoff), Some(wait)) => Some(backoff.max(wait)),
+        (backoff, _) => backoff,
+    };
+    let delay = match delay {
+        Some(delay) => delay,
+        None => {
+            sessions.remove(ui_session_id);
+            drop(sessions);
+            emit(app, ui_session_id, kind, text, None, None);
+            return false;
+        }
+    };
+    state.attempts += 1;
+    state.generation += 1;
+    state.pending = true;
+    let (attempt, generation) = (state.attempts, state.generation);
+    drop(sessions);
+    emit(app, ui_session_id, kind, text, Some((attempt, delay)), Some(max_retries));
+
+    let app = app.clone();
+    let ui_session_id = ui_session_id.to_string();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        {
+            let mut sessions = panic_guard::lock_or_recover(&SESSIONS, "rate limits");
+            match sessions.get_mut(&ui_session_id) {
+                Some(state) if state.pending && state.generation == generation => state.pending = false,
+                _ => {
+                    debug_log!("RATE_LIMIT", "[{}] Retry {} superseded", ui_session_id, attempt);
+                    return;
+                }
+            }
+        }
+        let result = app
+            .state::<ClaudeState>()
+            .lock()
+            .send_followup(&app, &ui_session_id, RETRY_PROMPT.to_string());
+        if let Err(e) = result {
+            debug_log!("RATE_LIMIT", "[{}] Retry failed: {}", ui_session_id, e);
+        }
+    });
+    true
+}
+
+/// Drop a session's retry state, cancelling any pending retry. Called when
+/// the user interrupts or sends a turn of their own, and on removal.
+pub fn forget(ui_session_id: &str) {
+    panic_guard::lock_or_recover(&SESSIONS, "rate limits").remove(ui_session_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_limits_and_reset_times() {
+        let overloaded = r#"API Error: 529 {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(detect(overloaded), Some(RateLimitKind::Overloaded));
+        assert_eq!(detect("Claude AI usage limit reached|1760000000"), Some(RateLimitKind::RateLimited));
+        assert_eq!(detect("API Error: 429 Too Many Requests"), Some(RateLimitKind::RateLimited));
+        let server_error = r#"API Error: 500 {"type":"error","error":{"type":"api_error","message":"Overloaded"}}"#;
+        assert_eq!(detect(server_error), None);
+        assert_eq!(detect("Fixed the rate limit handling; the API was overloaded"), None);
+
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let reset = resets_at("Claude AI usage limit reached|1760000000", now).unwrap();
+        assert_eq!(reset.timestamp(), 1_760_000_000);
+        let after = resets_at("429 Too Many Requests (retry-after: 30)", now).unwrap();
+        assert_eq!((after - now).num_seconds(), 30);
+        assert_eq!(resets_at("overloaded", now), None);
+    }
+}
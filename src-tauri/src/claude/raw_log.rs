@@ -0,0 +1,146 @@
+//! Tee of the raw stream-json stdout for each session, so parser bugs can be
+//! diagnosed (and sessions re-parsed) without rerunning Claude. Secrets are
+//! masked as lines are written if redaction is on.
+
+use crate::config;
+use crate::debug_log;
+use crate::redaction;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Directory holding one `<ui_session_id>.jsonl` file per session
+fn raw_logs_dir() -> Result<PathBuf, String> {
+    config::artifacts_dir()
+        .map(|d| d.join("raw"))
+        .ok_or_else(|| "Could not determine artifacts directory".to_string())
+}
+
+/// Session ID made safe for use as a file name (can't escape the dir)
+pub(crate) fn safe_file_stem(session_id: &str) -> Result<String, String> {
+    let safe: String = session_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if safe.is_empty() {
+        return Err("Session ID is empty".to_string());
+    }
+    Ok(safe)
+}
+
+/// Path of a session's raw log
+pub fn raw_log_path(ui_session_id: &str) -> Result<PathBuf, String> {
+    Ok(raw_logs_dir()?.join(format!("{}.jsonl", safe_file_stem(ui_session_id)?)))
+}
+
+/// Appends raw stdout lines for one process. Resumed turns append to the same file.
+pub struct RawLogWriter {
+    file: Option<File>,
+}
+
+impl RawLogWriter {
+    /// Open the session's raw log for appending. Failures are logged and
+    /// leave the writer disabled - the tee must never break a session.
+    pub fn open(ui_session_id: &str) -> Self {
+        let file = raw_log_path(ui_session_id).and_then(|path| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create raw log directory: {}", e))?;
+            }
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| format!("Failed to open raw log {:?}: {}", path, e))
+        });
+
+        match file {
+            Ok(f) => Self { file: Some(f) },
+            Err(e) => {
+                debug_log!("RAW_LOG", "[{}] {}", ui_session_id, e);
+                Self { file: None }
+            }
+        }
+    }
+
+    pub fn write_line(&mut self, line: &str) {
+        if let Some(ref mut file) = self.file {
+            if writeln!(file, "{}", redaction::redact_line(line)).is_err() {
+                // Disk full or similar - stop trying for this process
+                self.file = None;
+            }
+        }
+    }
+}
+
+/// Claude session IDs a raw log's `system` events report
+fn claude_sessions_in(path: &Path) -> HashSet<String> {
+    let lines = match super::process::transcript_lines(path) {
+        Ok(lines) => lines,
+        Err(_) => return HashSet::new(),
+    };
+    lines
+        .filter(|line| line.contains("\"system\""))
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(&line).ok())
+        .filter(|event| event.get("type").and_then(|t| t.as_str()) == Some("system"))
+        .filter_map(|event| event.get("session_id").and_then(|s| s.as_str()).map(str::to_string))
+        .collect()
+}
+
+/// Delete the raw logs that only ever ran `claude_session_id`; logs of UI
+/// sessions that also ran other Claude sessions are kept. Returns the
+/// removed paths.
+pub fn remove_for_claude_session(claude_session_id: &str) -> Vec<PathBuf> {
+    let entries = match raw_logs_dir().map(fs::read_dir) {
+        Ok(Ok(entries)) => entries,
+        _ => return vec![],
+    };
+    let mut removed = Vec::new();
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let sessions = claude_sessions_in(&path);
+        if sessions.len() != 1 || !sessions.contains(claude_session_id) {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => removed.push(path),
+            Err(e) => debug_log!("RAW_LOG", "Failed to remove {:?}: {}", path, e),
+        }
+    }
+    removed
+}
+
+/// Read a session's raw stream-json log
+pub fn read(ui_session_id: &str) -> Result<String, String> {
+    let path = raw_log_path(ui_session_id)?;
+    fs::read_to_string(&path)
+        .map_err(|e| format!("No raw log for session {} ({})", ui_session_id, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_claude_sessions_a_raw_log_ran() {
+        let path = std::env::temp_dir().join(format!("horseman-raw-{}.jsonl", uuid::Uuid::new_v4()));
+        fs::write(
+            &path,
+            concat!(
+                r#"{"type":"system","subtype":"init","session_id":"a"}"#,
+                "\n",
+                r#"{"type":"assistant","session_id":"b","message":{"content":"system"}}"#,
+                "\n",
+                r#"{"type":"system","subtype":"init","session_id":"c"}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let sessions = claude_sessions_in(&path);
+        fs::remove_file(&path).ok();
+        assert_eq!(sessions, HashSet::from(["a".to_string(), "c".to_string()]));
+    }
+}
@@ -0,0 +1,25 @@
+//! Background reaper: stdout readers only notice EOF, so something has to
+//! `wait()` on exited claude processes or they linger as zombies.
+//! It also starts queued spawns whose slot freed without a reader noticing.
+
+use crate::commands::ClaudeState;
+use crate::debug_log;
+use crate::supervisor;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const REAP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start the supervised reaper
+pub fn spawn(app: AppHandle) {
+    supervisor::spawn_thread("processReaper", move || loop {
+        std::thread::sleep(REAP_INTERVAL);
+        let state = app.state::<ClaudeState>();
+        let mut manager = state.lock();
+        let reaped = manager.reap_exited_children();
+        manager.admit_queued(&app);
+        if reaped > 0 {
+            debug_log!("REAPER", "Reaped {} exited process(es)", reaped);
+        }
+    });
+}
@@ -0,0 +1,227 @@
+//! Recovery for `--resume` failures where Claude has already pruned the
+//! session: detect the stderr message and build a primer from the old
+//! transcript (or our raw log) so a fresh session can carry on.
+
+use super::raw_log;
+use crate::config;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Most recent messages included in a primer
+const PRIMER_MESSAGES: usize = 10;
+/// Per-message character cap in a primer
+const PRIMER_MESSAGE_CHARS: usize = 500;
+/// Most recent messages kept per session when consolidating several
+const CONSOLIDATE_MESSAGES: usize = 4;
+
+/// Recognize Claude's "session not found" stderr line, returning the reason
+pub fn resume_failure_reason(line: &str) -> Option<String> {
+    let lower = line.to_lowercase();
+    let matched = lower.contains("no conversation found")
+        || (lower.contains("session") && lower.contains("not found"));
+    if matched {
+        Some(line.trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Text of a user/assistant message, skipping tool calls and results
+pub(crate) fn message_text(json: &serde_json::Value) -> Option<String> {
+    let content = json.get("message")?.get("content")?;
+    let text = match content {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+/// Condense a JSONL transcript (Claude's or a raw stream-json log) into the
+/// latest compaction summary plus the last few messages
+pub(crate) fn condense(content: &str) -> Option<String> {
+    condense_to(content, PRIMER_MESSAGES)
+}
+
+fn condense_to(content: &str, max_messages: usize) -> Option<String> {
+    let mut summary: Option<String> = None;
+    let mut messages: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let json: serde_json::Value = match serde_json::from_str(line) {
+            Ok(j) => j,
+            Err(_) => continue,
+        };
+        match json.get("type").and_then(|t| t.as_str()) {
+            Some("summary") => {
+                summary = json.get("summary").and_then(|s| s.as_str()).map(|s| s.to_string());
+            }
+            Some(role @ ("user" | "assistant")) => {
+                if let Some(text) = message_text(&json) {
+                    let mut text = text;
+                    if text.chars().count() > PRIMER_MESSAGE_CHARS {
+                        text = text.chars().take(PRIMER_MESSAGE_CHARS).collect();
+                        text.push('…');
+                    }
+                    messages.push(format!("{}: {}", role, text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if summary.is_none() && messages.is_empty() {
+        return None;
+    }
+
+    let mut out = String::new();
+    if let Some(s) = summary {
+        out.push_str(&format!("Summary: {}\n\n", s));
+    }
+    let start = messages.len().saturating_sub(max_messages);
+    out.push_str(&messages[start..].join("\n\n"));
+    Some(out.trim_end().to_string())
+}
+
+/// Build a condensed history of the lost session, preferring Claude's
+/// transcript and falling back to Horseman's raw stdout log
+pub fn build_primer(working_directory: &str, claude_session_id: &str, ui_session_id: &str) -> Option<String> {
+    let transcript = config::projects_dir()
+        .join(working_directory.replace('/', "-"))
+        .join(format!("{}.jsonl", claude_session_id));
+
+    let from_transcript = fs::read_to_string(&transcript).ok().and_then(|c| condense(&c));
+    from_transcript.or_else(|| {
+        let path = raw_log::raw_log_path(ui_session_id).ok()?;
+        condense(&fs::read_to_string(path).ok()?)
+    })
+}
+
+/// First message for the replacement session
+pub fn primed_prompt(primer: Option<&str>, content: &str) -> String {
+    match primer {
+        Some(history) => format!(
+            "The previous conversation could not be resumed, so this is a new session. \
+             Here is what happened so far:\n\n{}\n\n---\n\n{}",
+            history, content
+        ),
+        None => content.to_string(),
+    }
+}
+
+/// Transcript of a Claude session, whichever project it belongs to. IDs that
+/// aren't a plain file stem (e.g. containing `/` or `..`) never match.
+pub fn find_transcript(claude_session_id: &str) -> Option<PathBuf> {
+    if raw_log::safe_file_stem(claude_session_id).ok()? != claude_session_id {
+        return None;
+    }
+    let file_name = format!("{}.jsonl", claude_session_id);
+    fs::read_dir(config::projects_dir())
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path().join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// Subagent transcripts belonging to a session: its `<id>/` directory (newer
+/// CLIs) and `agent-*.jsonl` siblings whose records name it as their session
+pub fn subagent_transcripts(transcript_path: &Path, claude_session_id: &str) -> Vec<PathBuf> {
+    let dir = match transcript_path.parent() {
+        Some(d) => d,
+        None => return vec![],
+    };
+    let mut found = Vec::new();
+    let session_dir = dir.join(claude_session_id);
+    if session_dir.is_dir() {
+        found.push(session_dir);
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return found,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_agent = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("agent-") && n.ends_with(".jsonl"));
+        if !is_agent {
+            continue;
+        }
+        let first_line = fs::File::open(&path)
+            .ok()
+            .and_then(|f| BufReader::new(f).lines().next())
+            .and_then(|l| l.ok());
+        let owner = first_line
+            .and_then(|l| serde_json::from_str::<serde_json::Value>(&l).ok())
+            .and_then(|v| v.get("sessionId").and_then(|s| s.as_str()).map(str::to_string));
+        if owner.as_deref() == Some(claude_session_id) {
+            found.push(path);
+        }
+    }
+    found
+}
+
+/// Shorter history of one of several sessions being consolidated
+pub fn condense_for_consolidation(content: &str) -> Option<String> {
+    condense_to(content, CONSOLIDATE_MESSAGES)
+}
+
+/// First message for a session that merges several earlier ones, given
+/// (label, condensed history) for each in chronological order
+pub fn consolidated_prompt(histories: &[(String, String)], content: &str) -> String {
+    let sections: Vec<String> = histories
+        .iter()
+        .enumerate()
+        .map(|(i, (label, history))| format!("## Session {}: {}\n\n{}", i + 1, label, history))
+        .collect();
+    format!(
+        "This work was spread over {} earlier sessions. Here is what happened in each, \
+         oldest first. Treat it as one combined context.\n\n{}\n\n---\n\n{}",
+        histories.len(),
+        sections.join("\n\n"),
+        content
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_ids_that_are_not_file_stems() {
+        assert_eq!(find_transcript("../../etc/passwd"), None);
+        assert_eq!(find_transcript(""), None);
+    }
+
+    #[test]
+    fn detects_missing_session() {
+        assert!(resume_failure_reason("No conversation found with session ID: abc").is_some());
+        assert!(resume_failure_reason("Error: session abc not found").is_some());
+        assert!(resume_failure_reason("Warning: deprecated flag").is_none());
+    }
+
+    #[test]
+    fn condenses_summary_and_recent_text() {
+        let content = [
+            r#"{"type":"summary","summary":"Refactored parser"}"#,
+            r#"{"type":"user","message":{"content":"Add tests"}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit"},{"type":"text","text":"Done."}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","content":"ok"}]}}"#,
+        ]
+        .join("\n");
+        let primer = condense(&content).unwrap();
+        assert_eq!(primer, "Summary: Refactored parser\n\nuser: Add tests\n\nassistant: Done.");
+    }
+}
@@ -0,0 +1,275 @@
+//! Validation pass over stream-json stdout events. Unknown event types and
+//! fields with an unexpected shape are collected into a drift report, so a
+//! Claude CLI release that changes its output shows up as concrete entries
+//! instead of silently missing UI updates.
+
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Distinct drift keys kept; further new keys are dropped
+const MAX_ENTRIES: usize = 200;
+/// Characters of the offending event kept as an example
+const EXAMPLE_CHARS: usize = 500;
+
+const EVENT_TYPES: &[&str] = &["system", "assistant", "user", "result", "stream_event"];
+const ASSISTANT_BLOCKS: &[&str] = &["text", "tool_use", "thinking", "redacted_thinking"];
+const USER_BLOCKS: &[&str] = &["text", "tool_result", "image"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DriftKind {
+    UnknownEventType,
+    UnknownContentBlock,
+    UnexpectedShape,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftIssue {
+    pub kind: DriftKind,
+    /// Stable identifier, e.g. `assistant.message.content`
+    pub key: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftEntry {
+    pub kind: DriftKind,
+    pub key: String,
+    pub detail: String,
+    pub count: u64,
+    /// Unix millis
+    pub first_seen: i64,
+    pub last_seen: i64,
+    /// Truncated first event that triggered the entry
+    pub example: String,
+    /// CLI version reported when the entry was first seen
+    pub cli_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    /// Most recent `claude_code_version` from a system init event
+    pub cli_version: Option<String>,
+    pub events_checked: u64,
+    /// Most frequent first
+    pub entries: Vec<DriftEntry>,
+}
+
+#[derive(Default)]
+struct DriftState {
+    cli_version: Option<String>,
+    events_checked: u64,
+    entries: HashMap<String, DriftEntry>,
+}
+
+static STATE: Lazy<Mutex<DriftState>> = Lazy::new(|| Mutex::new(DriftState::default()));
+
+fn kind_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Check `value` is one of `expected` kinds; a missing value is only an
+/// issue when `required`
+fn expect(issues: &mut Vec<DriftIssue>, key: &str, value: Option<&Value>, expected: &[&str], required: bool) {
+    let detail = match value {
+        Some(v) if expected.contains(&kind_of(v)) => return,
+        Some(v) => format!("expected {}, got {}", expected.join(" or "), kind_of(v)),
+        None if required => format!("missing (expected {})", expected.join(" or ")),
+        None => return,
+    };
+    issues.push(DriftIssue { kind: DriftKind::UnexpectedShape, key: key.to_string(), detail });
+}
+
+fn check_blocks(issues: &mut Vec<DriftIssue>, event_type: &str, content: &Value, known: &[&str]) {
+    let blocks = match content.as_array() {
+        Some(b) => b,
+        None => return,
+    };
+    for block in blocks {
+        let block_type = match block.get("type").and_then(|t| t.as_str()) {
+            Some(t) => t,
+            None => {
+                expect(issues, &format!("{}.message.content[].type", event_type), block.get("type"), &["string"], true);
+                continue;
+            }
+        };
+        if !known.contains(&block_type) {
+            issues.push(DriftIssue {
+                kind: DriftKind::UnknownContentBlock,
+                key: format!("{}.content.{}", event_type, block_type),
+                detail: format!("unhandled {} content block `{}`", event_type, block_type),
+            });
+            continue;
+        }
+        let prefix = format!("{}.content.{}", event_type, block_type);
+        match block_type {
+            "text" => expect(issues, &format!("{}.text", prefix), block.get("text"), &["string"], true),
+            "tool_use" => {
+                expect(issues, &format!("{}.id", prefix), block.get("id"), &["string"], true);
+                expect(issues, &format!("{}.name", prefix), block.get("name"), &["string"], true);
+            }
+            "tool_result" => {
+                expect(issues, &format!("{}.tool_use_id", prefix), block.get("tool_use_id"), &["string"], true);
+                expect(issues, &format!("{}.content", prefix), block.get("content"), &["string", "array", "null"], false);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Every drift issue in one stdout event (pure, no recording)
+pub fn check_event(event: &Value) -> Vec<DriftIssue> {
+    let mut issues = Vec::new();
+    let event_type = match event.get("type").and_then(|t| t.as_str()) {
+        Some(t) => t,
+        None => {
+            expect(&mut issues, "type", event.get("type"), &["string"], true);
+            return issues;
+        }
+    };
+    if !EVENT_TYPES.contains(&event_type) {
+        issues.push(DriftIssue {
+            kind: DriftKind::UnknownEventType,
+            key: event_type.to_string(),
+            detail: format!("unhandled event type `{}`", event_type),
+        });
+        return issues;
+    }
+
+    match event_type {
+        "system" => {
+            expect(&mut issues, "system.subtype", event.get("subtype"), &["string"], true);
+            expect(&mut issues, "system.session_id", event.get("session_id"), &["string"], false);
+        }
+        "assistant" | "user" => {
+            let message = event.get("message");
+            expect(&mut issues, &format!("{}.message", event_type), message, &["object"], true);
+            if let Some(message) = message.filter(|m| m.is_object()) {
+                let content = message.get("content");
+                if event_type == "assistant" {
+                    expect(&mut issues, "assistant.message.id", message.get("id"), &["string"], true);
+                    expect(&mut issues, "assistant.message.content", content, &["array"], true);
+                } else {
+                    expect(&mut issues, "user.message.content", content, &["array", "string"], true);
+                }
+                let known = if event_type == "assistant" { ASSISTANT_BLOCKS } else { USER_BLOCKS };
+                if let Some(content) = content {
+                    check_blocks(&mut issues, event_type, content, known);
+                }
+            }
+        }
+        "result" => {
+            expect(&mut issues, "result.subtype", event.get("subtype"), &["string"], true);
+            expect(&mut issues, "result.usage", event.get("usage"), &["object"], false);
+            expect(&mut issues, "result.total_cost_usd", event.get("total_cost_usd"), &["number"], false);
+        }
+        "stream_event" => {
+            expect(&mut issues, "stream_event.event", event.get("event"), &["object"], true);
+        }
+        _ => {}
+    }
+    issues
+}
+
+/// Validate one stdout event and fold any issues into the report
+pub fn record(event: &Value) {
+    let issues = check_event(event);
+    let mut state = panic_guard::lock_or_recover(&STATE, "schema drift");
+    state.events_checked += 1;
+
+    if event.get("type").and_then(|t| t.as_str()) == Some("system") {
+        if let Some(version) = event.get("claude_code_version").and_then(|v| v.as_str()) {
+            state.cli_version = Some(version.to_string());
+        }
+    }
+    if issues.is_empty() {
+        return;
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let cli_version = state.cli_version.clone();
+    for issue in issues {
+        let map_key = format!("{:?}:{}", issue.kind, issue.key);
+        if let Some(entry) = state.entries.get_mut(&map_key) {
+            entry.count += 1;
+            entry.last_seen = now;
+            continue;
+        }
+        if state.entries.len() >= MAX_ENTRIES {
+            continue;
+        }
+        debug_log!("SCHEMA_DRIFT", "New drift: {} ({})", issue.key, issue.detail);
+        let example = event.to_string().chars().take(EXAMPLE_CHARS).collect();
+        state.entries.insert(map_key, DriftEntry {
+            kind: issue.kind,
+            key: issue.key,
+            detail: issue.detail,
+            count: 1,
+            first_seen: now,
+            last_seen: now,
+            example,
+            cli_version: cli_version.clone(),
+        });
+    }
+}
+
+/// Snapshot of everything recorded since startup
+pub fn report() -> DriftReport {
+    let state = panic_guard::lock_or_recover(&STATE, "schema drift");
+    let mut entries: Vec<DriftEntry> = state.entries.values().cloned().collect();
+    entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    DriftReport {
+        cli_version: state.cli_version.clone(),
+        events_checked: state.events_checked,
+        entries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn known_events_pass() {
+        let event = json!({
+            "type": "assistant",
+            "message": { "id": "m1", "content": [
+                { "type": "text", "text": "hi" },
+                { "type": "tool_use", "id": "t1", "name": "Read", "input": {} },
+            ]},
+        });
+        assert!(check_event(&event).is_empty());
+        assert!(check_event(&json!({ "type": "user", "message": { "content": "hi" } })).is_empty());
+    }
+
+    #[test]
+    fn flags_unknown_types_and_shapes() {
+        let issues = check_event(&json!({ "type": "rate_limit" }));
+        assert_eq!(issues[0].kind, DriftKind::UnknownEventType);
+
+        let issues = check_event(&json!({
+            "type": "assistant",
+            "message": { "id": "m1", "content": [{ "type": "citation" }] },
+        }));
+        assert_eq!(issues[0].key, "assistant.content.citation");
+
+        let issues = check_event(&json!({ "type": "result", "subtype": "success", "usage": 5 }));
+        assert_eq!(issues[0].key, "result.usage");
+        assert_eq!(issues[0].detail, "expected object, got number");
+    }
+}
@@ -0,0 +1,177 @@
+//! "What changed and why" summaries (see `sessionSummaryEnabled`). When a
+//! session's process ends normally with uncommitted changes in its working
+//! directory, a low-priority background job summarizes the transcript and
+//! the diff. The result is stored with the session's metadata and offered as
+//! the suggested commit message while those changes stay uncommitted.
+//! Each turn ends the process, so the job waits for the session to go quiet
+//! for `DEBOUNCE` and only the last end in a burst of turns summarizes.
+
+use super::resume_recovery;
+use super::process::lock_tracking;
+use super::StreamTrackingState;
+use crate::background_ai::{self, JobRequest, Priority};
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use crate::git;
+use crate::panic_guard;
+use crate::store::{self, SessionSummary};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// Diff characters included in the prompt (and hashed to match summaries)
+pub const MAX_DIFF_CHARS: usize = 20_000;
+const JOB_TIMEOUT: Duration = Duration::from_secs(180);
+/// Quiet period after a turn before the session is summarized
+const DEBOUNCE: Duration = Duration::from_secs(120);
+
+/// Latest end per Claude session; a waiting job that is no longer the
+/// latest gives way to the newer one
+static GENERATIONS: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn bump_generation(claude_session_id: &str) -> u64 {
+    let mut generations = panic_guard::lock_or_recover(&GENERATIONS, "summary generations");
+    let generation = generations.entry(claude_session_id.to_string()).or_insert(0);
+    *generation += 1;
+    *generation
+}
+
+/// True (and forgotten) if no end was recorded for the session since `generation`
+fn take_if_latest(claude_session_id: &str, generation: u64) -> bool {
+    let mut generations = panic_guard::lock_or_recover(&GENERATIONS, "summary generations");
+    if generations.get(claude_session_id) != Some(&generation) {
+        return false;
+    }
+    generations.remove(claude_session_id);
+    true
+}
+
+/// Commit message from the latest session summary in `dir`, if it was
+/// written for exactly the changes that are uncommitted now
+pub fn suggested_commit_message(dir: &str) -> Option<String> {
+    let hash = git::diff_hash(&git::working_diff(dir, MAX_DIFF_CHARS)?);
+    store::summary_for_diff(dir, &hash).map(|s| s.commit_message)
+}
+
+fn prompt(history: &str, diff: &str) -> String {
+    format!(
+        "Below is the end of a coding session and the uncommitted changes it left in the repository.\n\n\
+         Write a commit message for these changes: a subject line of at most 72 characters in the \
+         imperative mood, a blank line, then two to four sentences on what changed and why. \
+         Reply with the commit message only.\n\n\
+         ## Session\n\n{}\n\n## Changes\n\n{}",
+        history, diff
+    )
+}
+
+/// Subject line and body of the model's reply
+fn split_commit_message(text: &str) -> (String, String) {
+    let text = text.trim().trim_matches('`').trim();
+    let (subject, body) = text.split_once('\n').unwrap_or((text, ""));
+    (subject.trim().to_string(), body.trim().to_string())
+}
+
+fn summarize(
+    app: &AppHandle,
+    ui_session_id: &str,
+    claude_session_id: &str,
+    working_directory: &str,
+) -> Result<(), String> {
+    let diff = match git::working_diff(working_directory, MAX_DIFF_CHARS) {
+        Some(diff) => diff,
+        None => return Ok(()),
+    };
+    let diff_hash = git::diff_hash(&diff);
+    if store::summary(claude_session_id)?.is_some_and(|s| s.diff_hash == diff_hash) {
+        return Ok(());
+    }
+    let history = resume_recovery::find_transcript(claude_session_id)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| resume_recovery::condense(&content))
+        .unwrap_or_default();
+
+    debug_log!("SUMMARY", "[{}] Summarizing session {}", ui_session_id, claude_session_id);
+    let output = background_ai::run(JobRequest {
+        label: "Summarize session".to_string(),
+        prompt: prompt(&history, &diff),
+        working_directory: working_directory.to_string(),
+        model: None,
+        priority: Priority::Low,
+        timeout: JOB_TIMEOUT,
+    })?;
+    let (subject, body) = split_commit_message(&output.text);
+    if subject.is_empty() {
+        return Err("Summary was empty".to_string());
+    }
+    let commit_message = if body.is_empty() { subject.clone() } else { format!("{}\n\n{}", subject, body) };
+    let summary = SessionSummary {
+        claude_session_id: claude_session_id.to_string(),
+        working_directory: working_directory.to_string(),
+        summary: if body.is_empty() { subject } else { body },
+        commit_message,
+        diff_hash,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    store::set_summary(&summary)?;
+    let _ = events::emit(
+        app,
+        BackendEvent::SessionSummarized {
+            ui_session_id: ui_session_id.to_string(),
+            summary,
+        },
+    );
+    Ok(())
+}
+
+/// Called once a process ended without error; queues the summary job to run
+/// after `DEBOUNCE` unless another turn ends first
+pub fn on_session_end(app: &AppHandle, ui_session_id: &str, tracking: &Arc<Mutex<StreamTrackingState>>) {
+    if !config::session_summary_enabled() {
+        return;
+    }
+    let (claude_session_id, working_directory) = {
+        let state = lock_tracking(tracking);
+        match state.claude_session_id.clone() {
+            Some(id) => (id, state.working_directory.clone()),
+            None => return,
+        }
+    };
+    let app = app.clone();
+    let ui_session_id = ui_session_id.to_string();
+    let generation = bump_generation(&claude_session_id);
+    std::thread::spawn(move || {
+        std::thread::sleep(DEBOUNCE);
+        if !take_if_latest(&claude_session_id, generation) {
+            return;
+        }
+        if let Err(e) = summarize(&app, &ui_session_id, &claude_session_id, &working_directory) {
+            debug_log!("SUMMARY", "[{}] No summary: {}", ui_session_id, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_subject_from_body() {
+        let (subject, body) = split_commit_message("```\nFix login redirect\n\nThe form posted to the old route.\n```");
+        assert_eq!(subject, "Fix login redirect");
+        assert_eq!(body, "The form posted to the old route.");
+        assert_eq!(split_commit_message("Bump deps"), ("Bump deps".to_string(), String::new()));
+    }
+
+    #[test]
+    fn only_the_latest_end_summarizes() {
+        let first = bump_generation("debounced");
+        let second = bump_generation("debounced");
+        assert!(!take_if_latest("debounced", first));
+        assert!(take_if_latest("debounced", second));
+        assert!(!take_if_latest("debounced", second));
+    }
+}
@@ -0,0 +1,277 @@
+//! Claude's stdout split into lines with bounded memory. A line over
+//! `maxStdoutLineBytes` (usually one huge tool result) is streamed to a file
+//! under `<artifacts>/oversized-lines/<ui_session_id>/` instead of buffered,
+//! and reported so the UI can say the output was cut. In its place the
+//! stream gets a short stand-in line, so the tool call or turn it would
+//! have finished still finishes.
+
+use super::raw_log::safe_file_stem;
+use crate::config;
+use crate::debug_log;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Start of an oversized line kept to describe it
+pub(crate) const HEAD_BYTES: usize = 4096;
+/// Spilled lines older than this are deleted when a new one is written
+const SPILL_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// First `type` in the line: the event type in stream-json, where it leads,
+/// and in transcript user lines, where it comes before `message`
+static EVENT_TYPE: Lazy<Regex> = Lazy::new(|| Regex::new(r#""type"\s*:\s*"([^"]+)""#).unwrap());
+static TOOL_USE_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r#""tool_use_id"\s*:\s*"([^"]+)""#).unwrap());
+
+pub enum StdoutLine {
+    Line(String),
+    /// A line over the limit, dropped from the stream
+    Oversized(OversizedLine),
+}
+
+pub struct OversizedLine {
+    pub bytes: u64,
+    /// Stream-json event type, when it comes before the cut
+    pub event_type: Option<String>,
+    /// Tool whose result the line held, if it says so early on
+    pub tool_use_id: Option<String>,
+    /// Where the whole line was written; None when spilling failed
+    pub spill_path: Option<PathBuf>,
+}
+
+impl OversizedLine {
+    pub(crate) fn from_head(head: &[u8], bytes: u64, spill_path: Option<PathBuf>) -> Self {
+        let head = String::from_utf8_lossy(head);
+        let capture = |re: &Regex| re.captures(&head).map(|c| c[1].to_string());
+        Self {
+            bytes,
+            event_type: capture(&EVENT_TYPE),
+            tool_use_id: capture(&TOOL_USE_ID),
+            spill_path,
+        }
+    }
+
+    fn placeholder(&self) -> String {
+        let saved = match &self.spill_path {
+            Some(path) => format!("; the full output was saved to {}", path.display()),
+            None => String::new(),
+        };
+        format!("[Output cut by Horseman: {} bytes is over the line limit{}]", self.bytes, saved)
+    }
+
+    /// A stream-json line to handle instead: a tool result or a failed
+    /// turn result holding a placeholder, or else a bare marker. Each
+    /// carries `horseman_truncated` so raw logs show what was cut.
+    pub fn substitute(&self) -> String {
+        let marker = serde_json::json!({
+            "bytes": self.bytes,
+            "spill_path": self.spill_path.as_ref().map(|p| p.to_string_lossy()),
+        });
+        let line = match (self.event_type.as_deref(), &self.tool_use_id) {
+            (Some("user"), Some(tool_use_id)) => serde_json::json!({
+                "type": "user",
+                "message": {
+                    "role": "user",
+                    "content": [{
+                        "type": "tool_result",
+                        "tool_use_id": tool_use_id,
+                        "content": self.placeholder(),
+                        "is_error": false,
+                    }],
+                },
+                "horseman_truncated": marker,
+            }),
+            (Some("result"), _) => serde_json::json!({
+                "type": "result",
+                "subtype": "error_during_execution",
+                "is_error": true,
+                "result": self.placeholder(),
+                "horseman_truncated": marker,
+            }),
+            _ => serde_json::json!({
+                "type": "horseman_truncated",
+                "event_type": self.event_type,
+                "horseman_truncated": marker,
+            }),
+        };
+        line.to_string()
+    }
+}
+
+/// Directory for a session's spilled lines
+pub fn spill_dir(ui_session_id: &str) -> Result<PathBuf, String> {
+    let dir = config::artifacts_dir()
+        .map(|d| d.join("oversized-lines"))
+        .ok_or_else(|| "Could not determine artifacts directory".to_string())?;
+    Ok(dir.join(safe_file_stem(ui_session_id)?))
+}
+
+/// Delete a removed session's spilled lines
+pub fn forget(ui_session_id: &str) {
+    if let Ok(dir) = spill_dir(ui_session_id) {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+/// Delete spilled lines past their TTL
+fn prune(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .is_some_and(|age| age > SPILL_TTL);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// A line being streamed to disk
+struct Spill {
+    file: Option<(File, PathBuf)>,
+    head: Vec<u8>,
+    bytes: u64,
+}
+
+impl Spill {
+    fn start(dir: Option<&Path>, line_number: usize) -> Self {
+        let file = dir.and_then(|dir| {
+            prune(dir);
+            let name = format!("{}-line-{}.jsonl", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"), line_number);
+            let path = dir.join(name);
+            let file = fs::create_dir_all(dir).and_then(|_| File::create(&path));
+            match file {
+                Ok(file) => Some((file, path)),
+                Err(e) => {
+                    debug_log!("STDOUT", "Failed to spill oversized line to {:?}: {}", path, e);
+                    None
+                }
+            }
+        });
+        Self { file, head: Vec::new(), bytes: 0 }
+    }
+
+    fn write(&mut self, chunk: &[u8]) {
+        if self.head.len() < HEAD_BYTES {
+            let take = chunk.len().min(HEAD_BYTES - self.head.len());
+            self.head.extend_from_slice(&chunk[..take]);
+        }
+        self.bytes += chunk.len() as u64;
+        if let Some((file, path)) = &mut self.file {
+            if let Err(e) = file.write_all(chunk) {
+                debug_log!("STDOUT", "Failed to write spilled line {:?}: {}", path, e);
+                let _ = fs::remove_file(&*path);
+                self.file = None;
+            }
+        }
+    }
+
+    fn finish(self) -> OversizedLine {
+        OversizedLine::from_head(&self.head, self.bytes, self.file.map(|(_, path)| path))
+    }
+}
+
+/// Lines of a process's stdout. Unlike `BufRead::lines`, a line longer than
+/// `max` bytes is never held in memory whole.
+pub struct StdoutLines<R> {
+    reader: R,
+    max: usize,
+    spill_dir: Option<PathBuf>,
+    line_number: usize,
+}
+
+impl<R: BufRead> StdoutLines<R> {
+    /// `spill_dir` None drops oversized lines without keeping a copy
+    pub fn new(reader: R, max: usize, spill_dir: Option<PathBuf>) -> Self {
+        Self {
+            reader,
+            max,
+            spill_dir,
+            line_number: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StdoutLines<R> {
+    type Item = std::io::Result<StdoutLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = Vec::new();
+        let mut spill: Option<Spill> = None;
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) => return Some(Err(e)),
+            };
+            if available.is_empty() {
+                // A final line without a newline still counts
+                return match spill {
+                    Some(spill) => Some(Ok(StdoutLine::Oversized(spill.finish()))),
+                    None if line.is_empty() => None,
+                    None => Some(Ok(StdoutLine::Line(String::from_utf8_lossy(&line).into_owned()))),
+                };
+            }
+            let (chunk, used, end_of_line) = match available.iter().position(|b| *b == b'\n') {
+                Some(i) => (&available[..i], i + 1, true),
+                None => (available, available.len(), false),
+            };
+            if spill.is_none() && line.len() + chunk.len() > self.max {
+                let mut started = Spill::start(self.spill_dir.as_deref(), self.line_number + 1);
+                started.write(&std::mem::take(&mut line));
+                spill = Some(started);
+            }
+            match &mut spill {
+                Some(spill) => spill.write(chunk),
+                None => line.extend_from_slice(chunk),
+            }
+            self.reader.consume(used);
+            if end_of_line {
+                self.line_number += 1;
+                return Some(Ok(match spill {
+                    Some(spill) => StdoutLine::Oversized(spill.finish()),
+                    None => StdoutLine::Line(String::from_utf8_lossy(&line).into_owned()),
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spills_oversized_lines() {
+        let dir = std::env::temp_dir().join(format!("horseman-stdout-lines-{}", std::process::id()));
+        let huge = format!(r#"{{"type":"user","content":[{{"tool_use_id":"toolu_1","content":"{}"}}]}}"#, "x".repeat(200));
+        let input = format!("{{\"type\":\"system\"}}\n{}\nlast", huge);
+        let reader = std::io::BufReader::with_capacity(8, input.as_bytes());
+        let lines: Vec<StdoutLine> = StdoutLines::new(reader, 64, Some(dir.clone())).map(Result::unwrap).collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(&lines[0], StdoutLine::Line(l) if l == r#"{"type":"system"}"#));
+        match &lines[1] {
+            StdoutLine::Oversized(line) => {
+                assert_eq!(line.bytes, huge.len() as u64);
+                assert_eq!(line.event_type.as_deref(), Some("user"));
+                assert_eq!(line.tool_use_id.as_deref(), Some("toolu_1"));
+                let spilled = fs::read_to_string(line.spill_path.as_ref().unwrap()).unwrap();
+                assert_eq!(spilled, huge);
+                let substitute: serde_json::Value = serde_json::from_str(&line.substitute()).unwrap();
+                assert_eq!(substitute["message"]["content"][0]["tool_use_id"], "toolu_1");
+                assert_eq!(substitute["horseman_truncated"]["bytes"], huge.len() as u64);
+            }
+            StdoutLine::Line(_) => panic!("expected an oversized line"),
+        }
+        assert!(matches!(&lines[2], StdoutLine::Line(l) if l == "last"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+//! Token counts from assistant messages: per message, and rolled up for
+//! subagent transcripts, so Task tools can show what their subagent used.
+//! No cost is estimated here; the CLI's `total_cost_usd` already covers
+//! subagents and is the only cost Horseman reports.
+
+use crate::config;
+use crate::events::SessionUsage;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Sum two usages. Token counts and cost add; the context window is kept.
+pub fn add(total: Option<SessionUsage>, usage: &SessionUsage) -> SessionUsage {
+    match total {
+        Some(mut total) => {
+            total.input_tokens += usage.input_tokens;
+            total.output_tokens += usage.output_tokens;
+            total.cache_read_tokens += usage.cache_read_tokens;
+            total.cache_creation_tokens += usage.cache_creation_tokens;
+            total.cost = match (total.cost, usage.cost) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            };
+            total
+        }
+        None => usage.clone(),
+    }
+}
+
+/// Tokens of one assistant API message (the `message` object of an
+/// assistant event)
+pub fn for_message(message: &serde_json::Value) -> Option<SessionUsage> {
+    let usage = message.get("usage")?.as_object()?;
+    let tokens = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    Some(SessionUsage {
+        input_tokens: tokens("input_tokens"),
+        output_tokens: tokens("output_tokens"),
+        cache_read_tokens: tokens("cache_read_input_tokens"),
+        cache_creation_tokens: tokens("cache_creation_input_tokens"),
+        context_window: config::context_window() as u64,
+        cost: None,
+    })
+}
+
+/// Running total of a subagent transcript's usage, fed line by line.
+/// Claude writes one assistant line per content block with the same usage,
+/// so each message ID is counted once.
+#[derive(Default)]
+pub struct Tally {
+    seen: HashSet<String>,
+    total: Option<SessionUsage>,
+}
+
+impl Tally {
+    pub fn add_line(&mut self, line: &str) {
+        let event = match serde_json::from_str::<serde_json::Value>(line.trim()) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        if event.get("type").and_then(|t| t.as_str()) != Some("assistant") {
+            return;
+        }
+        let message = match event.get("message") {
+            Some(m) => m,
+            None => return,
+        };
+        let call = match for_message(message) {
+            Some(u) => u,
+            None => return,
+        };
+        if let Some(id) = message.get("id").and_then(|v| v.as_str()) {
+            if !self.seen.insert(id.to_string()) {
+                return;
+            }
+        }
+        self.total = Some(add(self.total.take(), &call));
+    }
+
+    pub fn total(self) -> Option<SessionUsage> {
+        self.total
+    }
+}
+
+/// Total usage of a subagent transcript's lines
+pub fn from_lines<I, S>(lines: I) -> Option<SessionUsage>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut tally = Tally::default();
+    for line in lines {
+        tally.add_line(line.as_ref());
+    }
+    tally.total()
+}
+
+/// Usage of the subagent `agent_id`, whose transcript sits next to the parent's
+pub fn for_agent(parent_transcript: &Path, agent_id: &str) -> Option<SessionUsage> {
+    let path = parent_transcript.parent()?.join(format!("{}.jsonl", agent_id));
+    from_lines(super::process::transcript_lines(&path).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_each_message_once() {
+        let content = [
+            r#"{"type":"user","message":{"content":"go"}}"#,
+            r#"{"type":"assistant","message":{"id":"m1","model":"claude-sonnet-4-5","usage":{"input_tokens":1000000,"output_tokens":0}}}"#,
+            r#"{"type":"assistant","message":{"id":"m1","model":"claude-sonnet-4-5","usage":{"input_tokens":1000000,"output_tokens":0}}}"#,
+            r#"{"type":"assistant","message":{"id":"m2","model":"claude-sonnet-4-5","usage":{"input_tokens":0,"output_tokens":100000,"cache_read_input_tokens":1000000}}}"#,
+        ]
+        .join("\n");
+
+        let usage = from_lines(content.lines()).expect("usage");
+        assert_eq!(usage.input_tokens, 1_000_000);
+        assert_eq!(usage.output_tokens, 100_000);
+        assert_eq!(usage.cache_read_tokens, 1_000_000);
+        assert_eq!(usage.cost, None);
+    }
+
+    #[test]
+    fn no_usage_without_assistant_messages() {
+        assert!(from_lines([r#"{"type":"user","message":{"content":"hi"}}"#]).is_none());
+    }
+}
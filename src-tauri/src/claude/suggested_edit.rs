@@ -0,0 +1,193 @@
+//! Apply edits that Claude proposed in prose (fenced code blocks) rather than
+//! through the Edit/Write tools.
+
+/// A fenced code block extracted from message text
+#[derive(Debug, Clone, PartialEq)]
+pub struct FencedBlock {
+    /// Info string after the opening fence (e.g. "rust", "diff")
+    pub lang: String,
+    pub body: String,
+}
+
+impl FencedBlock {
+    /// Whether this block should be applied as a unified diff
+    pub fn is_diff(&self) -> bool {
+        self.lang == "diff" || self.lang == "patch" || self.body.lines().any(|l| l.starts_with("@@ "))
+    }
+}
+
+/// Extract all ``` fenced code blocks from markdown text, in order
+pub fn extract_fenced_blocks(text: &str) -> Vec<FencedBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match current.take() {
+            None => {
+                if let Some(info) = trimmed.strip_prefix("```") {
+                    let lang = info.split_whitespace().next().unwrap_or("").to_lowercase();
+                    current = Some((lang, Vec::new()));
+                }
+            }
+            Some((lang, mut lines)) => {
+                if trimmed.starts_with("```") {
+                    let mut body = lines.join("\n");
+                    body.push('\n');
+                    blocks.push(FencedBlock { lang, body });
+                } else {
+                    lines.push(line);
+                    current = Some((lang, lines));
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+struct Hunk {
+    /// 1-based start line in the original file (hint only)
+    old_start: usize,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+}
+
+fn parse_hunks(diff: &str) -> Result<Vec<Hunk>, String> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            let old_start = header
+                .split_whitespace()
+                .next()
+                .and_then(|r| r.strip_prefix('-'))
+                .and_then(|r| r.split(',').next())
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(1);
+            hunks.push(Hunk { old_start, old_lines: Vec::new(), new_lines: Vec::new() });
+            continue;
+        }
+
+        // File headers before the first hunk
+        let hunk = match hunks.last_mut() {
+            Some(h) => h,
+            None => continue,
+        };
+
+        if let Some(rest) = line.strip_prefix('-') {
+            hunk.old_lines.push(rest.to_string());
+        } else if let Some(rest) = line.strip_prefix('+') {
+            hunk.new_lines.push(rest.to_string());
+        } else if let Some(rest) = line.strip_prefix(' ') {
+            hunk.old_lines.push(rest.to_string());
+            hunk.new_lines.push(rest.to_string());
+        } else if line.is_empty() {
+            // Some models drop the leading space on blank context lines
+            hunk.old_lines.push(String::new());
+            hunk.new_lines.push(String::new());
+        } else if line.starts_with('\\') {
+            // "\ No newline at end of file"
+            continue;
+        } else {
+            return Err(format!("Unexpected line in diff hunk: {}", line));
+        }
+    }
+
+    if hunks.is_empty() {
+        return Err("No hunks found in diff".to_string());
+    }
+    Ok(hunks)
+}
+
+/// Find `needle` in `haystack` at or after `from`, preferring the match closest to `hint`
+fn find_lines(haystack: &[String], needle: &[String], from: usize, hint: usize) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(hint.clamp(from, haystack.len()));
+    }
+    if needle.len() > haystack.len() {
+        return None;
+    }
+    (from..=haystack.len() - needle.len())
+        .filter(|&i| haystack[i..i + needle.len()] == *needle)
+        .min_by_key(|&i| i.abs_diff(hint))
+}
+
+/// Apply a unified diff to `original`, locating hunks by their context lines
+pub fn apply_unified_diff(original: &str, diff: &str) -> Result<String, String> {
+    let hunks = parse_hunks(diff)?;
+    let mut lines: Vec<String> = original.lines().map(|l| l.to_string()).collect();
+    let mut cursor = 0usize;
+    // Track how much earlier hunks shifted line numbers
+    let mut offset: isize = 0;
+
+    for (i, hunk) in hunks.iter().enumerate() {
+        let hint = ((hunk.old_start as isize - 1) + offset).max(0) as usize;
+        let pos = find_lines(&lines, &hunk.old_lines, cursor, hint)
+            .ok_or_else(|| format!("Hunk {} does not match the current file contents", i + 1))?;
+
+        lines.splice(pos..pos + hunk.old_lines.len(), hunk.new_lines.iter().cloned());
+        cursor = pos + hunk.new_lines.len();
+        offset += hunk.new_lines.len() as isize - hunk.old_lines.len() as isize;
+    }
+
+    let mut result = lines.join("\n");
+    if original.ends_with('\n') || original.is_empty() {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Compute the new file contents for a suggested block
+pub fn apply_block(original: Option<&str>, block: &FencedBlock) -> Result<String, String> {
+    if block.is_diff() {
+        apply_unified_diff(original.unwrap_or(""), &block.body)
+    } else {
+        Ok(block.body.clone())
+    }
+}
+
+/// Render a unified diff between two versions of a file for preview
+pub fn preview_diff(path: &str, before: &str, after: &str) -> String {
+    similar::TextDiff::from_lines(before, after)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_blocks_with_lang() {
+        let text = "Try this:\n```rust\nfn main() {}\n```\nand\n```diff\n@@ -1 +1 @@\n-a\n+b\n```\n";
+        let blocks = extract_fenced_blocks(text);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].lang, "rust");
+        assert_eq!(blocks[0].body, "fn main() {}\n");
+        assert!(!blocks[0].is_diff());
+        assert!(blocks[1].is_diff());
+    }
+
+    #[test]
+    fn applies_diff_by_context() {
+        let original = "one\ntwo\nthree\nfour\n";
+        let diff = "--- a/f\n+++ b/f\n@@ -2,2 +2,2 @@\n two\n-three\n+THREE\n";
+        assert_eq!(apply_unified_diff(original, diff).unwrap(), "one\ntwo\nTHREE\nfour\n");
+    }
+
+    #[test]
+    fn applies_diff_with_wrong_line_numbers() {
+        let original = "a\nb\nc\nd\ne\n";
+        let diff = "@@ -40,1 +40,2 @@\n d\n+inserted\n";
+        assert_eq!(apply_unified_diff(original, diff).unwrap(), "a\nb\nc\nd\ninserted\ne\n");
+    }
+
+    #[test]
+    fn rejects_non_matching_hunk() {
+        let diff = "@@ -1 +1 @@\n-missing\n+x\n";
+        assert!(apply_unified_diff("a\nb\n", diff).is_err());
+    }
+}
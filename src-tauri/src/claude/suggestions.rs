@@ -0,0 +1,156 @@
+//! Quick-reply suggestions offered after each turn. Two or three short
+//! follow-ups are picked by rules from how the turn ended ("Run the tests"
+//! after edits, "Continue" at the turn limit, ...) and sent right away as
+//! `suggestions.updated`. With `quickReplies = "polished"`, a low-priority
+//! background job then rewrites them for the turn and sends them again,
+//! unless another turn has started by the time it's done.
+
+use crate::background_ai::{self, JobRequest, Priority};
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const MAX_SUGGESTIONS: usize = 3;
+/// Characters of the turn's final reply shown to the polishing job
+const MAX_REPLY_CHARS: usize = 4_000;
+const JOB_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickReplies {
+    Off,
+    /// Rule-based suggestions only
+    Rules,
+    /// Rule-based first, then rewritten by a background AI job
+    Polished,
+}
+
+/// How a turn ended, as far as suggestions care
+#[derive(Debug, Default)]
+pub struct TurnOutcome {
+    pub failed: bool,
+    pub hit_turn_limit: bool,
+    pub edited_files: bool,
+    /// Claude's final reply
+    pub reply: String,
+}
+
+fn rule_suggestions(turn: &TurnOutcome) -> Vec<String> {
+    let asked = turn.reply.trim_end().ends_with('?');
+    let candidates: &[&str] = if turn.failed {
+        &["Try again", "What went wrong?"]
+    } else if turn.hit_turn_limit {
+        &["Continue", "Summarize where you got to"]
+    } else if asked {
+        &["Yes, go ahead", "No, leave it"]
+    } else if turn.edited_files {
+        &["Run the tests", "Explain the change", "Commit this"]
+    } else {
+        &["Go ahead", "Explain in more detail"]
+    };
+    candidates.iter().take(MAX_SUGGESTIONS).map(|s| s.to_string()).collect()
+}
+
+fn polish_prompt(turn: &TurnOutcome, suggestions: &[String]) -> String {
+    let reply: String = turn.reply.chars().take(MAX_REPLY_CHARS).collect();
+    format!(
+        "A coding assistant just finished a turn with this reply:\n\n{}\n\n\
+         Suggest {} short follow-up messages the user is likely to send next (at most 6 words each), \
+         improving on these: {}. Reply with one suggestion per line and nothing else.",
+        reply,
+        MAX_SUGGESTIONS,
+        suggestions.join("; ")
+    )
+}
+
+/// Suggestions from the polishing job's reply, without list markers or quotes
+fn parse_polished(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim().trim_start_matches(['-', '*', '•']).trim())
+        .map(|line| line.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ')').trim())
+        .map(|line| line.trim_matches('"').trim().to_string())
+        .filter(|line| !line.is_empty())
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+fn emit(app: &AppHandle, ui_session_id: &str, suggestions: Vec<String>, polished: bool) {
+    let _ = events::emit(
+        app,
+        BackendEvent::SuggestionsUpdated {
+            ui_session_id: ui_session_id.to_string(),
+            suggestions,
+            polished,
+        },
+    );
+}
+
+/// Called when a turn's result arrives
+pub fn on_turn_end(app: &AppHandle, ui_session_id: &str, working_directory: &str, turn: TurnOutcome) {
+    let mode = config::quick_replies();
+    if mode == QuickReplies::Off {
+        return;
+    }
+    let suggestions = rule_suggestions(&turn);
+    emit(app, ui_session_id, suggestions.clone(), false);
+    if mode != QuickReplies::Polished || turn.failed {
+        return;
+    }
+
+    let request = JobRequest {
+        label: "Suggest replies".to_string(),
+        prompt: polish_prompt(&turn, &suggestions),
+        working_directory: working_directory.to_string(),
+        model: None,
+        priority: Priority::Low,
+        timeout: JOB_TIMEOUT,
+    };
+    let turn = app.state::<ClaudeState>().lock().turn(ui_session_id);
+    let app = app.clone();
+    let ui_session_id = ui_session_id.to_string();
+    std::thread::spawn(move || match background_ai::run(request) {
+        Ok(output) => {
+            let polished = parse_polished(&output.text);
+            // Held while emitting, so no turn can start in between
+            let state = app.state::<ClaudeState>();
+            let manager = state.lock();
+            if manager.turn(&ui_session_id) != turn {
+                debug_log!("SUGGEST", "[{}] Dropping polished suggestions for turn {}", ui_session_id, turn);
+                return;
+            }
+            if !polished.is_empty() {
+                emit(&app, &ui_session_id, polished, true);
+            }
+        }
+        Err(e) => debug_log!("SUGGEST", "[{}] Not polished: {}", ui_session_id, e),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_suggestions_from_the_outcome() {
+        let edited = TurnOutcome {
+            edited_files: true,
+            reply: "Done.".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(rule_suggestions(&edited), ["Run the tests", "Explain the change", "Commit this"]);
+        let question = TurnOutcome {
+            edited_files: true,
+            reply: "Should I also update the docs?\n".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(rule_suggestions(&question)[0], "Yes, go ahead");
+        assert_eq!(
+            parse_polished("1. Run cargo test\n- \"Commit it\"\n\n* Undo that\nextra"),
+            ["Run cargo test", "Commit it", "Undo that"]
+        );
+    }
+}
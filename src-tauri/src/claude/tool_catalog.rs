@@ -0,0 +1,396 @@
+//! Which tools a session can use, for the permission policy editor and the
+//! allowed-tools picker: Claude's built-ins (as the session's init event
+//! listed them, or the known set before it has run) plus the tools of MCP
+//! servers. Servers from `mcpServers` / `projectMcpServers` are asked
+//! directly with an MCP initialize handshake and `tools/list`; servers
+//! Claude loads itself only show up once a session has reported them.
+//! Probe results are cached per working directory until the config changes.
+
+use crate::config;
+use crate::debug_log;
+use crate::hooks::mcp_servers::{self, McpServerConfig, HORSEMAN_SERVER};
+use crate::login_shell;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a server gets to answer the handshake and list its tools
+const PROBE_TIMEOUT: Duration = Duration::from_secs(8);
+/// `tools/list` pages read before giving up on a paginating server
+const MAX_PAGES: usize = 10;
+const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Claude's built-in tools, used until a session reports its own list
+const BUILTIN_TOOLS: &[(&str, &str)] = &[
+    ("Task", "Run a subagent"),
+    ("Bash", "Run shell commands"),
+    ("BashOutput", "Read output of a background shell"),
+    ("KillShell", "Stop a background shell"),
+    ("Glob", "Find files by pattern"),
+    ("Grep", "Search file contents"),
+    ("Read", "Read files"),
+    ("Edit", "Edit files"),
+    ("MultiEdit", "Make several edits to a file"),
+    ("Write", "Create or overwrite files"),
+    ("NotebookEdit", "Edit Jupyter notebooks"),
+    ("WebFetch", "Fetch a URL"),
+    ("WebSearch", "Search the web"),
+    ("TodoWrite", "Update the todo list"),
+    ("ExitPlanMode", "Leave plan mode"),
+    ("AskUserQuestion", "Ask the user questions"),
+    ("SlashCommand", "Run a slash command"),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogTool {
+    /// As used in allowed/disallowed tool lists, e.g. `mcp__github__search`
+    pub name: String,
+    /// MCP server the tool comes from; None for built-ins
+    pub server: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProbeStatus {
+    Ok,
+    Failed,
+    /// Invalid config; sessions leave it out too
+    Skipped,
+    /// sse/http servers aren't probed
+    NotProbed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerProbe {
+    pub name: String,
+    pub status: ProbeStatus,
+    pub error: Option<String>,
+    pub tool_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolCatalog {
+    pub tools: Vec<CatalogTool>,
+    pub servers: Vec<ServerProbe>,
+    /// Whether the built-ins come from the session's own init event
+    pub reported_by_session: bool,
+}
+
+/// Probed tools and server results per working directory, with the config
+/// revision they were probed under
+type Probed = (Vec<CatalogTool>, Vec<ServerProbe>);
+static PROBED: Lazy<Mutex<HashMap<String, (u64, Probed)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Tool names each UI session's latest init event listed
+static REPORTED: Lazy<Mutex<HashMap<String, Vec<String>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Remember the tools a session's init event lists
+pub fn record_init(ui_session_id: &str, init_event: &serde_json::Value) {
+    let tools: Vec<String> = match init_event.get("tools").and_then(|v| v.as_array()) {
+        Some(tools) => tools.iter().filter_map(|t| t.as_str().map(str::to_string)).collect(),
+        None => return,
+    };
+    panic_guard::lock_or_recover(&REPORTED, "reported tools").insert(ui_session_id.to_string(), tools);
+}
+
+pub fn forget(ui_session_id: &str) {
+    panic_guard::lock_or_recover(&REPORTED, "reported tools").remove(ui_session_id);
+}
+
+/// Server of an `mcp__<server>__<tool>` name
+fn mcp_server_of(name: &str) -> Option<&str> {
+    name.strip_prefix("mcp__")?.split("__").next()
+}
+
+/// Kills the probed server, and anything it started, however the probe ends
+struct ProbeProcess(Child);
+
+impl Drop for ProbeProcess {
+    fn drop(&mut self) {
+        // The server leads its own process group (see `probe_stdio`)
+        #[cfg(unix)]
+        unsafe {
+            libc::killpg(self.0.id() as i32, libc::SIGKILL);
+        }
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn send(stdin: &mut ChildStdin, message: serde_json::Value) -> Result<(), String> {
+    writeln!(stdin, "{}", message)
+        .and_then(|_| stdin.flush())
+        .map_err(|e| format!("Failed to write to server: {}", e))
+}
+
+/// Wait for the response to request `id`
+fn response(lines: &Receiver<String>, id: u64, deadline: Instant) -> Result<serde_json::Value, String> {
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let line = lines.recv_timeout(remaining).map_err(|_| "Server did not respond".to_string())?;
+        let message: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            // Servers sometimes log to stdout
+            Err(_) => continue,
+        };
+        if message.get("id").and_then(|v| v.as_u64()) != Some(id) {
+            continue;
+        }
+        if let Some(error) = message.get("error") {
+            let text = error.get("message").and_then(|v| v.as_str()).unwrap_or("unknown error");
+            return Err(format!("Server error: {}", text));
+        }
+        return Ok(message.get("result").cloned().unwrap_or_default());
+    }
+}
+
+/// Tools in a `tools/list` result
+fn listed_tools(server: &str, result: &serde_json::Value) -> Vec<CatalogTool> {
+    let tools = result.get("tools").and_then(|v| v.as_array()).map(Vec::as_slice).unwrap_or(&[]);
+    tools
+        .iter()
+        .filter_map(|tool| {
+            let name = tool.get("name")?.as_str()?;
+            Some(CatalogTool {
+                name: format!("mcp__{}__{}", server, name),
+                server: Some(server.to_string()),
+                description: tool.get("description").and_then(|v| v.as_str()).map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Start a stdio server, handshake and list its tools
+fn probe_stdio(name: &str, server: &McpServerConfig, working_directory: &Path) -> Result<Vec<CatalogTool>, String> {
+    let command = server.command.as_deref().ok_or("no command given")?;
+    // Through a login shell, so `npx`, `uvx` etc. resolve as they do for
+    // Claude; `exec` makes the server the group leader in the shell's place
+    let script = std::iter::once(command)
+        .chain(server.args.iter().map(String::as_str))
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut shell = Command::new(login_shell::shell());
+    shell
+        .args(["-l", "-c", &format!("exec {}", script)])
+        .envs(&server.env)
+        .current_dir(working_directory)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // Own process group, so servers started through `npx` & co. go down
+    // with their launcher
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+    let child = shell.spawn().map_err(|e| format!("Failed to start {}: {}", command, e))?;
+    let mut process = ProbeProcess(child);
+    let mut stdin = process.0.stdin.take().ok_or("Failed to open server stdin")?;
+    let stdout = process.0.stdout.take().ok_or("Failed to open server stdout")?;
+    let (tx, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let deadline = Instant::now() + PROBE_TIMEOUT;
+    send(
+        &mut stdin,
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": MCP_PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "horseman", "version": env!("CARGO_PKG_VERSION") }
+            }
+        }),
+    )?;
+    response(&lines, 0, deadline)?;
+    send(&mut stdin, serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))?;
+
+    let mut tools = Vec::new();
+    let mut cursor: Option<String> = None;
+    for page in 1..=MAX_PAGES as u64 {
+        let params = match &cursor {
+            Some(cursor) => serde_json::json!({ "cursor": cursor }),
+            None => serde_json::json!({}),
+        };
+        send(
+            &mut stdin,
+            serde_json::json!({ "jsonrpc": "2.0", "id": page, "method": "tools/list", "params": params }),
+        )?;
+        let result = response(&lines, page, deadline)?;
+        tools.extend(listed_tools(name, &result));
+        cursor = result.get("nextCursor").and_then(|v| v.as_str()).map(str::to_string);
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(tools)
+}
+
+/// Probe every configured server in parallel
+fn probe_configured(working_directory: &str) -> (Vec<CatalogTool>, Vec<ServerProbe>) {
+    let (servers, skipped) = mcp_servers::resolve(working_directory);
+    let mut probes: Vec<ServerProbe> = skipped
+        .into_iter()
+        .map(|s| ServerProbe {
+            name: s.name,
+            status: ProbeStatus::Skipped,
+            error: Some(s.reason),
+            tool_count: 0,
+        })
+        .collect();
+    let handles: Vec<_> = servers
+        .into_iter()
+        .map(|(name, server)| {
+            let working_directory = working_directory.to_string();
+            std::thread::spawn(move || {
+                let result = match server.transport.as_deref().unwrap_or("stdio") {
+                    "stdio" => Some(probe_stdio(&name, &server, Path::new(&working_directory))),
+                    _ => None,
+                };
+                (name, result)
+            })
+        })
+        .collect();
+
+    let mut tools = Vec::new();
+    for handle in handles {
+        let (name, result) = match handle.join() {
+            Ok(joined) => joined,
+            Err(_) => continue,
+        };
+        let (status, error, found) = match result {
+            Some(Ok(found)) => (ProbeStatus::Ok, None, found),
+            Some(Err(e)) => {
+                debug_log!("TOOLS", "Probing MCP server {} failed: {}", name, e);
+                (ProbeStatus::Failed, Some(e), vec![])
+            }
+            None => (ProbeStatus::NotProbed, None, vec![]),
+        };
+        probes.push(ServerProbe {
+            name,
+            status,
+            error,
+            tool_count: found.len(),
+        });
+        tools.extend(found);
+    }
+    probes.sort_by(|a, b| a.name.cmp(&b.name));
+    (tools, probes)
+}
+
+/// Built-ins, probed MCP tools and session-reported tools, one entry per
+/// name, without Horseman's own permission server
+fn merge(reported: Option<&[String]>, probed: Vec<CatalogTool>) -> Vec<CatalogTool> {
+    let mut tools: BTreeMap<String, CatalogTool> = BTreeMap::new();
+    let descriptions: HashMap<&str, &str> = BUILTIN_TOOLS.iter().copied().collect();
+    let builtin = |name: &str| CatalogTool {
+        name: name.to_string(),
+        server: None,
+        description: descriptions.get(name).map(|d| d.to_string()),
+    };
+    match reported {
+        Some(names) => {
+            for name in names {
+                let tool = match mcp_server_of(name) {
+                    Some(server) => CatalogTool {
+                        name: name.clone(),
+                        server: Some(server.to_string()),
+                        description: None,
+                    },
+                    None => builtin(name),
+                };
+                tools.insert(name.clone(), tool);
+            }
+        }
+        None => tools.extend(BUILTIN_TOOLS.iter().map(|(name, _)| (name.to_string(), builtin(name)))),
+    }
+    // Probed entries carry descriptions, so they win
+    tools.extend(probed.into_iter().map(|tool| (tool.name.clone(), tool)));
+    tools.retain(|_, tool| tool.server.as_deref() != Some(HORSEMAN_SERVER));
+    tools.into_values().collect()
+}
+
+/// `probe_configured`, reusing the last result for `working_directory`
+/// while the config is unchanged
+fn probe_cached(working_directory: &str) -> Probed {
+    let revision = config::revision();
+    let cached = panic_guard::lock_or_recover(&PROBED, "probed tools").get(working_directory).cloned();
+    if let Some((probed_at, probed)) = cached {
+        if probed_at == revision {
+            return probed;
+        }
+    }
+    let probed = probe_configured(working_directory);
+    panic_guard::lock_or_recover(&PROBED, "probed tools")
+        .insert(working_directory.to_string(), (revision, probed.clone()));
+    probed
+}
+
+/// The catalog for a session (by UI session ID) in `working_directory`.
+/// Blocks while servers are probed, unless an earlier probe can be reused.
+pub fn catalog(ui_session_id: Option<&str>, working_directory: &str) -> ToolCatalog {
+    let reported =
+        ui_session_id.and_then(|id| panic_guard::lock_or_recover(&REPORTED, "reported tools").get(id).cloned());
+    let (probed, servers) = probe_cached(working_directory);
+    ToolCatalog {
+        tools: merge(reported.as_deref(), probed),
+        servers,
+        reported_by_session: reported.is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_reported_builtin_and_probed_tools() {
+        let probed = listed_tools(
+            "github",
+            &serde_json::json!({ "tools": [{ "name": "search", "description": "Search code" }] }),
+        );
+        assert_eq!(probed[0].name, "mcp__github__search");
+
+        let reported: Vec<String> = [
+            "Bash",
+            "Read",
+            "mcp__github__search",
+            "mcp__horseman__request_permission",
+            "mcp__db__query",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let tools = merge(Some(&reported), probed.clone());
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Bash", "Read", "mcp__db__query", "mcp__github__search"]);
+        assert_eq!(tools[0].description.as_deref(), Some("Run shell commands"));
+        assert_eq!(tools[2].server.as_deref(), Some("db"));
+        assert_eq!(tools[3].description.as_deref(), Some("Search code"));
+
+        let unreported = merge(None, probed);
+        assert_eq!(unreported.len(), BUILTIN_TOOLS.len() + 1);
+    }
+}
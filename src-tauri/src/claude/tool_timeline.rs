@@ -0,0 +1,139 @@
+//! Gantt-style spans of every tool call in a session, subagent tools nested
+//! under their Task, for seeing where a slow turn spent its time.
+
+use super::process::TranscriptParseResult;
+use crate::events::ToolCall;
+use chrono::DateTime;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolSpan {
+    pub tool_id: String,
+    pub name: String,
+    pub status: String,
+    /// Task the tool ran under, for subagent tools
+    pub parent_tool_id: Option<String>,
+    /// Nesting level (0 = called by the main session)
+    pub depth: usize,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    /// Offset from the timeline start
+    pub start_ms: i64,
+    /// None while the tool is still running
+    pub duration_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolTimeline {
+    /// Start of the earliest tool call
+    pub started_at: Option<String>,
+    /// From the first start to the last end
+    pub duration_ms: i64,
+    /// Parents before their children; siblings in start order
+    pub spans: Vec<ToolSpan>,
+}
+
+fn millis(timestamp: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.timestamp_millis())
+}
+
+/// Lay out the tool calls of a parsed transcript (including subagent tools).
+/// Tools without a parseable start time are left out.
+pub fn build(parsed: &TranscriptParseResult) -> ToolTimeline {
+    let tools: Vec<(&ToolCall, i64)> = parsed
+        .messages
+        .iter()
+        .flat_map(|m| m.tool_calls.iter().flatten())
+        .chain(parsed.subagent_tools.iter())
+        .filter_map(|tool| Some((tool, millis(tool.started_at.as_deref()?)?)))
+        .collect();
+    let origin = match tools.iter().map(|(_, start)| *start).min() {
+        Some(origin) => origin,
+        None => return ToolTimeline::default(),
+    };
+
+    // Children by parent; tools whose parent isn't in the timeline are roots
+    let known: HashSet<&str> = tools.iter().map(|(t, _)| t.id.as_str()).collect();
+    let mut children: HashMap<Option<&str>, Vec<(&ToolCall, i64)>> = HashMap::new();
+    for &(tool, start) in &tools {
+        let parent = tool.parent_tool_id.as_deref().filter(|p| known.contains(p) && *p != tool.id);
+        children.entry(parent).or_default().push((tool, start));
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|(_, start)| *start);
+    }
+
+    let mut spans = Vec::new();
+    let mut end = origin;
+    // Depth-first, so each Task is followed by the tools its subagent ran
+    let mut stack: Vec<(&ToolCall, i64, usize)> = children
+        .remove(&None)
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .map(|(tool, start)| (tool, start, 0))
+        .collect();
+    while let Some((tool, start, depth)) = stack.pop() {
+        let ended = tool.ended_at.as_deref().and_then(millis);
+        end = end.max(ended.unwrap_or(start));
+        spans.push(ToolSpan {
+            tool_id: tool.id.clone(),
+            name: tool.name.clone(),
+            status: tool.status.clone(),
+            parent_tool_id: tool.parent_tool_id.clone(),
+            depth,
+            started_at: tool.started_at.clone().unwrap_or_default(),
+            ended_at: tool.ended_at.clone(),
+            start_ms: start - origin,
+            duration_ms: ended.map(|e| (e - start).max(0)),
+        });
+        if let Some(nested) = children.remove(&Some(tool.id.as_str())) {
+            stack.extend(nested.into_iter().rev().map(|(t, s)| (t, s, depth + 1)));
+        }
+    }
+
+    ToolTimeline {
+        started_at: spans.first().map(|s| s.started_at.clone()),
+        duration_ms: end - origin,
+        spans,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::parse_transcript_content;
+
+    #[test]
+    fn nests_subagent_tools_under_their_task() {
+        let content = [
+            r#"{"type":"assistant","timestamp":"2025-01-01T00:00:00Z","message":{"id":"m1","content":[{"type":"tool_use","id":"read","name":"Read","input":{}}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-01T00:00:02Z","message":{"content":[{"type":"tool_result","tool_use_id":"read","content":"ok"}]}}"#,
+            r#"{"type":"assistant","timestamp":"2025-01-01T00:00:03Z","message":{"id":"m2","content":[{"type":"tool_use","id":"task","name":"Task","input":{"description":"d","subagent_type":"general"}}]}}"#,
+            r#"{"type":"user","timestamp":"2025-01-01T00:00:10Z","message":{"content":[{"type":"tool_result","tool_use_id":"task","content":"done"}]}}"#,
+        ]
+        .join("\n");
+        let mut parsed = parse_transcript_content(&content);
+        let mut grep = parsed.messages[0].tool_calls.as_ref().unwrap()[0].clone();
+        grep.id = "grep".to_string();
+        grep.parent_tool_id = Some("task".to_string());
+        grep.started_at = Some("2025-01-01T00:00:04Z".to_string());
+        grep.ended_at = Some("2025-01-01T00:00:06Z".to_string());
+        parsed.subagent_tools.push(grep);
+
+        let timeline = build(&parsed);
+        let order: Vec<(&str, usize, i64, Option<i64>)> = timeline
+            .spans
+            .iter()
+            .map(|s| (s.tool_id.as_str(), s.depth, s.start_ms, s.duration_ms))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("read", 0, 0, Some(2_000)), ("task", 0, 3_000, Some(7_000)), ("grep", 1, 4_000, Some(2_000))]
+        );
+        assert_eq!(timeline.duration_ms, 10_000);
+    }
+}
@@ -0,0 +1,316 @@
+//! Copies of a session's transcript and subagent transcripts, taken before
+//! `/clear` or `/compact` discard context, so it can be restored. Kept under
+//! `<artifacts>/transcript-backups/<claude_session_id>/<backup_id>/`; only
+//! the newest `transcriptBackupsKept` per session are kept, and the oldest
+//! of any session go once all together pass `transcriptBackupsMaxBytes`.
+//! Sessions about to pass Claude's `cleanupPeriodDays` are archived the
+//! same way. Copies can be large, so callers take them without holding the
+//! session manager's lock.
+
+use super::raw_log::safe_file_stem;
+use super::resume_recovery;
+use crate::atomic_file;
+use crate::config;
+use crate::data_transfer::collect_files;
+use crate::debug_log;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const MANIFEST_FILE: &str = "backup.json";
+const FILES_DIR: &str = "files";
+/// Slash commands that throw away or rewrite the conversation
+const DESTRUCTIVE_COMMANDS: &[&str] = &["/clear", "/reset", "/compact"];
+/// Reason recorded on archives of sessions Claude is about to delete
+pub const EXPIRY_REASON: &str = "cleanupPeriodDays";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptBackup {
+    pub id: String,
+    pub claude_session_id: String,
+    pub created_at: String,
+    /// What the backup was taken for, e.g. "/compact"
+    pub reason: String,
+    /// Claude project directory the files were copied from
+    pub project_dir: String,
+    /// Relative to `project_dir`; the transcript comes first
+    pub files: Vec<String>,
+    pub total_bytes: u64,
+}
+
+/// Whether `slash_command` discards context worth backing up
+pub fn is_destructive(slash_command: &str) -> bool {
+    let name = slash_command.split_whitespace().next().unwrap_or("");
+    DESTRUCTIVE_COMMANDS.contains(&name)
+}
+
+fn backups_root() -> Result<PathBuf, String> {
+    config::artifacts_dir()
+        .map(|d| d.join("transcript-backups"))
+        .ok_or_else(|| "Could not determine artifacts directory".to_string())
+}
+
+fn session_backups_dir(claude_session_id: &str) -> Result<PathBuf, String> {
+    Ok(backups_root()?.join(safe_file_stem(claude_session_id)?))
+}
+
+/// A manifest path that stays inside the directory it's joined to
+fn safe_relative(path: &str) -> Option<PathBuf> {
+    let relative = PathBuf::from(path);
+    let plain = relative.components().all(|c| matches!(c, Component::Normal(_)));
+    (plain && !path.is_empty()).then_some(relative)
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<u64, String> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    fs::copy(from, to).map_err(|e| format!("Failed to copy {:?}: {}", from, e))
+}
+
+/// Copy the session's files without pruning older backups
+fn snapshot(claude_session_id: &str, reason: &str) -> Result<TranscriptBackup, String> {
+    let transcript_path = resume_recovery::find_transcript(claude_session_id)
+        .ok_or_else(|| format!("No transcript found for session {}", claude_session_id))?;
+    let project_dir = transcript_path.parent().ok_or("Transcript has no project directory")?;
+
+    let mut files = vec![PathBuf::from(format!("{}.jsonl", claude_session_id))];
+    for path in resume_recovery::subagent_transcripts(&transcript_path, claude_session_id) {
+        if path.is_dir() {
+            collect_files(project_dir, &path, &mut files).map_err(|e| format!("Failed to list {:?}: {}", path, e))?;
+        } else if let Ok(relative) = path.strip_prefix(project_dir) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    let size: u64 = files
+        .iter()
+        .filter_map(|relative| fs::metadata(project_dir.join(relative)).ok())
+        .map(|m| m.len())
+        .sum();
+    if let Some(max_bytes) = config::transcript_backups_max_bytes() {
+        if size > max_bytes {
+            return Err(format!("Transcripts take {} bytes, more than transcriptBackupsMaxBytes", size));
+        }
+    }
+
+    let id = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ").to_string();
+    let backup_dir = session_backups_dir(claude_session_id)?.join(&id);
+    let mut total_bytes = 0;
+    for relative in &files {
+        total_bytes += copy_file(&project_dir.join(relative), &backup_dir.join(FILES_DIR).join(relative))?;
+    }
+
+    let backup = TranscriptBackup {
+        id,
+        claude_session_id: claude_session_id.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        reason: reason.to_string(),
+        project_dir: project_dir.to_string_lossy().to_string(),
+        files: files.iter().map(|f| f.to_string_lossy().to_string()).collect(),
+        total_bytes,
+    };
+    let manifest = serde_json::to_string_pretty(&backup).map_err(|e| e.to_string())?;
+    atomic_file::write(&backup_dir.join(MANIFEST_FILE), manifest)
+        .map_err(|e| format!("Failed to write backup manifest: {}", e))?;
+    debug_log!(
+        "BACKUP",
+        "Backed up {} ({} files, {} bytes) before {}",
+        claude_session_id,
+        files.len(),
+        total_bytes,
+        reason
+    );
+    Ok(backup)
+}
+
+fn remove(backup: &TranscriptBackup) {
+    if let Ok(dir) = session_backups_dir(&backup.claude_session_id).map(|d| d.join(&backup.id)) {
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            debug_log!("BACKUP", "Failed to prune {:?}: {}", dir, e);
+        }
+    }
+}
+
+/// Delete all but the newest `keep` backups of a session
+fn prune(claude_session_id: &str, keep: usize) {
+    list(claude_session_id).iter().skip(keep).for_each(remove);
+}
+
+/// The oldest of `backups` to delete so the rest fit in `max_bytes`,
+/// never `newest`
+fn over_limit(mut backups: Vec<TranscriptBackup>, max_bytes: u64, newest: &TranscriptBackup) -> Vec<TranscriptBackup> {
+    let mut total: u64 = backups.iter().map(|b| b.total_bytes).sum();
+    // IDs are creation timestamps
+    backups.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut removed = Vec::new();
+    for backup in backups {
+        if total <= max_bytes {
+            break;
+        }
+        if backup.id == newest.id && backup.claude_session_id == newest.claude_session_id {
+            continue;
+        }
+        total -= backup.total_bytes;
+        removed.push(backup);
+    }
+    removed
+}
+
+/// Delete the oldest backups of any session once all of them together
+/// pass `transcriptBackupsMaxBytes`
+fn enforce_max_bytes(newest: &TranscriptBackup) {
+    let max_bytes = match config::transcript_backups_max_bytes() {
+        Some(max_bytes) => max_bytes,
+        None => return,
+    };
+    let sessions = match backups_root().map(fs::read_dir) {
+        Ok(Ok(sessions)) => sessions,
+        _ => return,
+    };
+    let backups = sessions
+        .flatten()
+        .flat_map(|entry| list_in(&entry.path()))
+        .collect();
+    for backup in over_limit(backups, max_bytes, newest) {
+        debug_log!("BACKUP", "Over transcriptBackupsMaxBytes, removing {}/{}", backup.claude_session_id, backup.id);
+        remove(&backup);
+    }
+}
+
+/// Back up a session's transcripts before `reason` (a slash command) runs.
+/// None when backups are turned off.
+pub fn create(claude_session_id: &str, reason: &str) -> Result<Option<TranscriptBackup>, String> {
+    let keep = config::transcript_backups_kept();
+    if keep == 0 {
+        return Ok(None);
+    }
+    let backup = snapshot(claude_session_id, reason)?;
+    prune(claude_session_id, keep);
+    enforce_max_bytes(&backup);
+    Ok(Some(backup))
+}
+
+/// Back up before a destructive slash command; failures are only logged so
+/// the command still runs
+pub fn before_command(claude_session_id: &str, slash_command: &str) {
+    if !is_destructive(slash_command) {
+        return;
+    }
+    let reason = slash_command.split_whitespace().next().unwrap_or(slash_command);
+    if let Err(e) = create(claude_session_id, reason) {
+        debug_log!("BACKUP", "No backup of {} before {}: {}", claude_session_id, reason, e);
+    }
+}
+
+/// Archive a session before Claude's cleanup deletes it. Taken even when
+/// `transcriptBackupsKept` is 0, and not pruning the session's older backups.
+pub fn archive(claude_session_id: &str) -> Result<TranscriptBackup, String> {
+    let backup = snapshot(claude_session_id, EXPIRY_REASON)?;
+    enforce_max_bytes(&backup);
+    Ok(backup)
+}
+
+/// Delete every backup of a session (when the session itself is deleted).
+/// Returns the removed directory, if there was one.
+pub fn remove_all(claude_session_id: &str) -> Result<Option<PathBuf>, String> {
+    let dir = session_backups_dir(claude_session_id)?;
+    if !dir.exists() {
+        return Ok(None);
+    }
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove {:?}: {}", dir, e))?;
+    Ok(Some(dir))
+}
+
+/// A session's backups, newest first
+pub fn list(claude_session_id: &str) -> Vec<TranscriptBackup> {
+    match session_backups_dir(claude_session_id) {
+        Ok(dir) => list_in(&dir),
+        Err(_) => vec![],
+    }
+}
+
+/// Backups in one session's backup directory, newest first
+fn list_in(dir: &Path) -> Vec<TranscriptBackup> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut backups: Vec<TranscriptBackup> = entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path().join(MANIFEST_FILE)).ok())
+        .filter_map(|manifest| serde_json::from_str(&manifest).ok())
+        .collect();
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    backups
+}
+
+/// Copy a backup's files back over the session's transcripts. The current
+/// transcripts are backed up first, so a restore can itself be undone.
+/// Returns that backup.
+pub fn restore(claude_session_id: &str, backup_id: &str) -> Result<Option<TranscriptBackup>, String> {
+    let backup = list(claude_session_id)
+        .into_iter()
+        .find(|b| b.id == backup_id)
+        .ok_or_else(|| format!("No backup {} for session {}", backup_id, claude_session_id))?;
+    let files: Vec<PathBuf> = backup
+        .files
+        .iter()
+        .map(|f| safe_relative(f).ok_or_else(|| format!("Invalid path in backup manifest: {}", f)))
+        .collect::<Result<_, _>>()?;
+
+    // Not pruned here, which could remove the backup being restored
+    let previous = match config::transcript_backups_kept() {
+        0 => None,
+        _ => snapshot(claude_session_id, "restore").ok(),
+    };
+    let source = session_backups_dir(claude_session_id)?.join(&backup.id).join(FILES_DIR);
+    let project_dir = PathBuf::from(&backup.project_dir);
+    for relative in &files {
+        copy_file(&source.join(relative), &project_dir.join(relative))?;
+    }
+    debug_log!("BACKUP", "Restored {} from backup {}", claude_session_id, backup.id);
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_destructive_commands_and_unsafe_paths() {
+        assert!(is_destructive("/compact keep the API notes"));
+        assert!(is_destructive("/clear"));
+        assert!(!is_destructive("/cost"));
+        assert!(!is_destructive("/compactor"));
+        assert_eq!(safe_relative("abc/agent-1.jsonl"), Some(PathBuf::from("abc/agent-1.jsonl")));
+        assert_eq!(safe_relative("../other/x.jsonl"), None);
+        assert_eq!(safe_relative("/etc/passwd"), None);
+        assert_eq!(safe_relative(""), None);
+    }
+
+    #[test]
+    fn removes_oldest_backups_past_the_size_limit() {
+        let backup = |session: &str, id: &str, total_bytes: u64| TranscriptBackup {
+            id: id.to_string(),
+            claude_session_id: session.to_string(),
+            created_at: String::new(),
+            reason: "/compact".to_string(),
+            project_dir: String::new(),
+            files: vec![],
+            total_bytes,
+        };
+        let newest = backup("a", "20250103T000000.000Z", 600);
+        let backups = vec![
+            backup("b", "20250102T000000.000Z", 300),
+            newest.clone(),
+            backup("a", "20250101T000000.000Z", 300),
+        ];
+        let removed: Vec<String> = over_limit(backups.clone(), 1000, &newest).into_iter().map(|b| b.id).collect();
+        assert_eq!(removed, vec!["20250101T000000.000Z"]);
+        assert!(over_limit(backups.clone(), 1200, &newest).is_empty());
+        // The backup just taken stays even when it alone is over the limit
+        assert_eq!(over_limit(backups, 100, &newest).len(), 2);
+    }
+}
@@ -0,0 +1,224 @@
+//! Page-at-a-time transcript parsing, so huge transcripts don't have to be
+//! read, parsed and sent over IPC in one piece. Pages end at turn boundaries
+//! (a user prompt), which keeps each tool call together with its result;
+//! only a turn too big for one page is split.
+
+use super::annotations;
+use super::process::{parse_transcript_content, with_subagents, TranscriptParseResult, MAX_TRANSCRIPT_LINE_BYTES};
+use super::stdout_lines::{OversizedLine, HEAD_BYTES};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Messages per page unless the caller asks otherwise
+pub const DEFAULT_PAGE_MESSAGES: usize = 200;
+/// Most messages a page holds, whatever the caller asks for
+const MAX_PAGE_MESSAGES: usize = 2000;
+/// Transcript bytes a page reads at most
+const MAX_PAGE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// How much one page may take
+struct PageLimits {
+    /// Messages; the page ends at the first turn past it
+    messages: usize,
+    /// Hard caps, reached mid-turn only by a turn too big for one page
+    max_messages: usize,
+    max_bytes: u64,
+    /// Longer lines are replaced like oversized stdout lines
+    max_line_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptPage {
+    /// Messages of this page. Todos, usage and cost cover this page only,
+    /// so the last page carries the session's latest todos.
+    #[serde(flatten)]
+    pub result: TranscriptParseResult,
+    /// Byte offset the page starts at
+    pub cursor: u64,
+    /// Pass back as `cursor` for the next page; None on the last page
+    pub next_cursor: Option<u64>,
+    pub total_bytes: u64,
+}
+
+/// Tracks how many messages `parse_transcript_content` will produce from the
+/// lines seen so far
+#[derive(Default)]
+struct MessageCounter {
+    messages: usize,
+    assistant_ids: HashSet<String>,
+}
+
+impl MessageCounter {
+    /// Count one transcript line; returns true if it starts a user turn
+    fn add(&mut self, line: &str) -> bool {
+        let json: serde_json::Value = match serde_json::from_str(line.trim()) {
+            Ok(j) => j,
+            Err(_) => return false,
+        };
+        let message = json.get("message");
+        match json.get("type").and_then(|t| t.as_str()) {
+            Some("user") => {
+                let content = message.and_then(|m| m.get("content"));
+                let has_text = match content {
+                    Some(serde_json::Value::String(s)) => !s.trim().is_empty(),
+                    Some(serde_json::Value::Array(blocks)) => {
+                        let kinds: Vec<&str> = blocks.iter().filter_map(|b| b.get("type")?.as_str()).collect();
+                        kinds.contains(&"text") && !kinds.contains(&"tool_result")
+                    }
+                    _ => false,
+                };
+                if has_text {
+                    self.messages += 1;
+                }
+                has_text
+            }
+            Some("assistant") => {
+                let id = message.and_then(|m| m.get("id")).and_then(|i| i.as_str()).unwrap_or_default();
+                if self.assistant_ids.insert(id.to_string()) {
+                    self.messages += 1;
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+/// One line of at most `max` bytes, the bytes it took up in the transcript,
+/// and whether it was complete (newline-terminated). A longer line is skipped
+/// without buffering it and replaced by `OversizedLine::substitute`.
+fn read_line(reader: &mut impl BufRead, max: usize) -> std::io::Result<Option<(String, u64, bool)>> {
+    let mut head = Vec::new();
+    if reader.by_ref().take(max as u64 + 1).read_until(b'\n', &mut head)? == 0 {
+        return Ok(None);
+    }
+    if head.ends_with(b"\n") || head.len() <= max {
+        let complete = head.ends_with(b"\n");
+        return Ok(Some((String::from_utf8_lossy(&head).into_owned(), head.len() as u64, complete)));
+    }
+    let mut bytes = head.len() as u64;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            // Still being written: nothing to substitute yet
+            return Ok(Some((String::new(), bytes, false)));
+        }
+        match available.iter().position(|b| *b == b'\n') {
+            Some(i) => {
+                reader.consume(i + 1);
+                bytes += i as u64 + 1;
+                break;
+            }
+            None => {
+                let used = available.len();
+                reader.consume(used);
+                bytes += used as u64;
+            }
+        }
+    }
+    let line = OversizedLine::from_head(&head[..HEAD_BYTES.min(head.len())], bytes, None).substitute();
+    Ok(Some((format!("{}\n", line), bytes, true)))
+}
+
+/// Read lines from `reader` until the turn that would take the page past
+/// `limits.messages` starts, or a hard cap is reached. Returns the page's
+/// content and the transcript bytes it covers.
+fn read_page(reader: &mut impl BufRead, limits: &PageLimits) -> std::io::Result<(String, u64)> {
+    let mut content = String::new();
+    let mut consumed = 0;
+    let mut counter = MessageCounter::default();
+    while let Some((line, len, complete)) = read_line(reader, limits.max_line_bytes)? {
+        if !complete && serde_json::from_str::<serde_json::Value>(&line).is_err() {
+            // A line still being written
+            break;
+        }
+        if consumed > 0 && consumed + len > limits.max_bytes {
+            break;
+        }
+        let before = counter.messages;
+        if counter.add(&line) && before >= limits.messages {
+            break;
+        }
+        if counter.messages > limits.max_messages {
+            break;
+        }
+        content.push_str(&line);
+        consumed += len;
+    }
+    Ok((content, consumed))
+}
+
+/// Parse the page of `transcript_path` starting at byte `cursor` (0 for the
+/// first page), with subagent tools for the Task calls on it
+pub fn parse_page(transcript_path: &Path, cursor: u64, limit: usize) -> Result<TranscriptPage, String> {
+    let mut file = File::open(transcript_path).map_err(|e| format!("Failed to open transcript: {}", e))?;
+    let total_bytes = file.metadata().map(|m| m.len()).map_err(|e| format!("Failed to stat transcript: {}", e))?;
+    if cursor > total_bytes {
+        return Err(format!("Cursor {} is past the end of the transcript ({} bytes)", cursor, total_bytes));
+    }
+    file.seek(SeekFrom::Start(cursor)).map_err(|e| format!("Failed to seek transcript: {}", e))?;
+    let limits = PageLimits {
+        messages: limit.clamp(1, MAX_PAGE_MESSAGES),
+        max_messages: MAX_PAGE_MESSAGES,
+        max_bytes: MAX_PAGE_BYTES,
+        max_line_bytes: MAX_TRANSCRIPT_LINE_BYTES,
+    };
+    let (content, len) = read_page(&mut BufReader::new(file), &limits)
+        .map_err(|e| format!("Failed to read transcript: {}", e))?;
+    let end = cursor + len;
+    let mut result = with_subagents(parse_transcript_content(&content), transcript_path);
+    result.annotations = annotations::for_transcript(transcript_path);
+    Ok(TranscriptPage {
+        result,
+        cursor,
+        next_cursor: (end < total_bytes && len > 0).then_some(end),
+        total_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_end_at_user_turns() {
+        let lines = [
+            r#"{"type":"user","message":{"content":"First"}}"#,
+            r#"{"type":"assistant","message":{"id":"a1","content":[{"type":"tool_use","id":"t1","name":"Read","input":{}}]}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#,
+            r#"{"type":"assistant","message":{"id":"a1","content":[{"type":"text","text":"Done"}]}}"#,
+            r#"{"type":"user","message":{"content":"Second"}}"#,
+            r#"{"type":"assistant","message":{"id":"a2","content":[{"type":"text","text":"Ok"}]}}"#,
+        ];
+        let content = lines.join("\n") + "\n";
+        let limits = PageLimits {
+            messages: 2,
+            max_messages: MAX_PAGE_MESSAGES,
+            max_bytes: MAX_PAGE_BYTES,
+            max_line_bytes: MAX_TRANSCRIPT_LINE_BYTES,
+        };
+        let mut reader = std::io::Cursor::new(content.as_bytes());
+        let (page, len) = read_page(&mut reader, &limits).unwrap();
+        assert_eq!(page, lines[..4].join("\n") + "\n");
+        assert_eq!(len as usize, page.len());
+        let (rest, _) = read_page(&mut std::io::Cursor::new(&content.as_bytes()[page.len()..]), &limits).unwrap();
+        assert_eq!(rest, lines[4..].join("\n") + "\n");
+
+        // Caps split a turn that is too big, and long lines are stood in for
+        let capped = PageLimits {
+            max_messages: 1,
+            max_line_bytes: 100,
+            ..limits
+        };
+        let (page, len) = read_page(&mut std::io::Cursor::new(content.as_bytes()), &capped).unwrap();
+        assert_eq!(page.lines().count(), 3);
+        assert!(page.lines().nth(1).unwrap().contains("horseman_truncated"));
+        assert_eq!(len as usize, lines[..3].join("\n").len() + 1);
+        let (page, _) = read_page(&mut std::io::Cursor::new(&content.as_bytes()[len as usize..]), &capped).unwrap();
+        assert_eq!(page, format!("{}\n", lines[3]));
+    }
+}
@@ -0,0 +1,223 @@
+//! Live view of transcripts Horseman isn't driving (e.g. a `claude` session
+//! running in a terminal). A tailed transcript is watched for growth; new
+//! complete lines are fed to a parser that keeps its state between reads, and
+//! only the messages they added or changed are sent as `transcript.appended`.
+
+use super::process::{TranscriptParseResult, TranscriptParser};
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use crate::fs_watch::FsWatcher;
+use crate::panic_guard;
+use notify::RecursiveMode;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// Quiet period before a burst of writes is parsed; also how often the
+/// tail checks whether it was stopped
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Parse at least this often while the transcript is being written
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+struct Tail {
+    stop: Arc<AtomicBool>,
+    /// Windows viewing the transcript; the tail stops when the last leaves
+    viewers: usize,
+}
+
+static TAILS: Lazy<Mutex<HashMap<PathBuf, Tail>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The complete lines at the start of `content` and their length in bytes
+fn complete_lines(content: &[u8]) -> (&[u8], usize) {
+    match content.iter().rposition(|b| *b == b'\n') {
+        Some(i) => (&content[..=i], i + 1),
+        None => (&[], 0),
+    }
+}
+
+/// Bytes of `path` from `position` to its end
+fn read_from(path: &Path, position: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(position))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+struct TailState {
+    path: PathBuf,
+    claude_session_id: String,
+    /// Holds the complete lines read so far
+    parser: TranscriptParser,
+    position: u64,
+}
+
+impl TailState {
+    /// Read what was appended since the last call. Returns Some(reset) when
+    /// there is something to parse.
+    fn read(&mut self) -> Result<Option<bool>, String> {
+        let len = fs::metadata(&self.path).map(|m| m.len()).map_err(|e| e.to_string())?;
+        let reset = len < self.position;
+        if reset {
+            self.parser = TranscriptParser::new();
+            self.position = 0;
+        } else if len == self.position {
+            return Ok(None);
+        }
+        let bytes = read_from(&self.path, self.position).map_err(|e| e.to_string())?;
+        let (lines, consumed) = complete_lines(&bytes);
+        if consumed == 0 && !reset {
+            return Ok(None);
+        }
+        for line in String::from_utf8_lossy(lines).lines() {
+            self.parser.feed(line);
+        }
+        self.position += consumed as u64;
+        Ok(Some(reset))
+    }
+
+    fn emit(&mut self, app: &AppHandle, reset: bool) {
+        let messages = self.parser.take_changed();
+        if messages.is_empty() && !reset {
+            return;
+        }
+        let (usage, total_cost_usd) = self.parser.usage();
+        let _ = events::emit(
+            app,
+            BackendEvent::TranscriptAppended {
+                claude_session_id: self.claude_session_id.clone(),
+                transcript_path: self.path.to_string_lossy().to_string(),
+                messages,
+                todos: self.parser.todos(),
+                usage,
+                total_cost_usd,
+                reset,
+            },
+        );
+    }
+}
+
+fn run(app: &AppHandle, mut state: TailState, stop: &AtomicBool) -> Result<(), String> {
+    let dir = state.path.parent().ok_or("Transcript has no parent directory")?.to_path_buf();
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = FsWatcher::new(&format!("transcriptTail {}", state.claude_session_id), tx)?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    let mut first_dirty: Option<Instant> = None;
+    while !stop.load(Ordering::Relaxed) {
+        watcher.tick();
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(Ok(event)) => {
+                if event.paths.iter().any(|p| p == &state.path) {
+                    first_dirty.get_or_insert_with(Instant::now);
+                }
+                if first_dirty.is_none_or(|t| t.elapsed() < MAX_DELAY) {
+                    continue;
+                }
+            }
+            Ok(Err(e)) => return Err(format!("Watch error: {}", e)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Err("Watcher stopped".to_string()),
+        }
+        if first_dirty.take().is_none() {
+            continue;
+        }
+        if !state.path.exists() {
+            return Ok(());
+        }
+        if let Some(reset) = state.read()? {
+            state.emit(app, reset);
+        }
+    }
+    Ok(())
+}
+
+/// Start tailing `transcript_path` (or join an existing tail) and return the
+/// transcript as parsed at that point; later changes arrive as
+/// `transcript.appended`. Subagent transcripts aren't followed.
+pub fn start(app: &AppHandle, transcript_path: &Path) -> Result<TranscriptParseResult, String> {
+    let claude_session_id = transcript_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or("Invalid transcript path")?;
+    let mut state = TailState {
+        path: transcript_path.to_path_buf(),
+        claude_session_id,
+        parser: TranscriptParser::new(),
+        position: 0,
+    };
+    state.read()?;
+    let parsed = state.parser.snapshot();
+    state.parser.take_changed();
+
+    let mut tails = panic_guard::lock_or_recover(&TAILS, "transcript tails");
+    if let Some(tail) = tails.get_mut(transcript_path) {
+        tail.viewers += 1;
+        return Ok(parsed);
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    tails.insert(transcript_path.to_path_buf(), Tail { stop: stop.clone(), viewers: 1 });
+    drop(tails);
+
+    let app = app.clone();
+    let path = transcript_path.to_path_buf();
+    std::thread::spawn(move || {
+        debug_log!("TRANSCRIPT_TAIL", "Tailing {:?}", path);
+        if let Err(e) = run(&app, state, &stop) {
+            debug_log!("TRANSCRIPT_TAIL", "Stopped tailing {:?}: {}", path, e);
+        }
+        let mut tails = panic_guard::lock_or_recover(&TAILS, "transcript tails");
+        if tails.get(&path).is_some_and(|t| Arc::ptr_eq(&t.stop, &stop)) {
+            tails.remove(&path);
+        }
+    });
+    Ok(parsed)
+}
+
+/// Leave a tail; the last viewer to leave stops it
+pub fn stop(transcript_path: &Path) {
+    let mut tails = panic_guard::lock_or_recover(&TAILS, "transcript tails");
+    if let Some(tail) = tails.get_mut(transcript_path) {
+        tail.viewers = tail.viewers.saturating_sub(1);
+        if tail.viewers == 0 {
+            tail.stop.store(true, Ordering::Relaxed);
+            tails.remove(transcript_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feeds_only_new_lines_and_reports_what_changed() {
+        let assistant = |id: &str, block: &str| {
+            format!(r#"{{"type":"assistant","message":{{"id":"{}","role":"assistant","content":[{}]}}}}"#, id, block)
+        };
+        let mut parser = TranscriptParser::new();
+        parser.feed(r#"{"type":"user","message":{"role":"user","content":"Run the tests"}}"#);
+        let prompt = parser.take_changed();
+        assert_eq!(prompt.iter().map(|m| m.role.as_str()).collect::<Vec<_>>(), vec!["user"]);
+
+        parser.feed(&assistant("m1", r#"{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}"#));
+        let changed = parser.take_changed();
+        // The prompt keeps its ID once the reply arrives
+        assert_eq!(changed.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec![prompt[0].id.as_str(), "m1"]);
+        assert!(parser.take_changed().is_empty());
+
+        parser.feed(r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#);
+        let changed = parser.take_changed();
+        assert_eq!(changed.len(), 1);
+        let tool = &changed[0].tool_calls.as_ref().unwrap()[0];
+        assert_eq!((tool.status.as_str(), tool.output.as_deref()), ("completed", Some("ok")));
+        assert_eq!(parser.snapshot().messages.len(), 2);
+        assert_eq!(complete_lines(b"{}\n{\"par"), (&b"{}\n"[..], 3));
+    }
+}
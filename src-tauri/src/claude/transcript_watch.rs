@@ -0,0 +1,89 @@
+//! Fallback when the stdout reader dies: follow the session's transcript file
+//! and feed new lines through the normal event processing so the UI keeps
+//! receiving data.
+
+use super::process::{lock_tracking, process_event, StreamTrackingState};
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use crate::panic_guard;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Stop following once the transcript hasn't grown for this long
+const IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn file_len(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Follow the transcript from its current end until it goes idle, then emit
+/// `session.ended`. Returns false (and starts nothing) if the transcript path
+/// isn't known yet.
+pub fn spawn_fallback(app: AppHandle, ui_session_id: String, tracking: Arc<Mutex<StreamTrackingState>>) -> bool {
+    let transcript_path = match lock_tracking(&tracking).transcript_path.clone() {
+        Some(p) => p,
+        None => return false,
+    };
+
+    std::thread::spawn(move || {
+        debug_log!("TRANSCRIPT_WATCH", "[{}] Following {:?}", ui_session_id, transcript_path);
+        let mut position = file_len(&transcript_path);
+        let mut pending = String::new();
+        let mut last_growth = Instant::now();
+
+        while last_growth.elapsed() < IDLE_TIMEOUT {
+            std::thread::sleep(POLL_INTERVAL);
+
+            let len = file_len(&transcript_path);
+            if len <= position {
+                continue;
+            }
+
+            let mut chunk = String::new();
+            let read = File::open(&transcript_path).and_then(|mut f| {
+                f.seek(SeekFrom::Start(position))?;
+                f.take(len - position).read_to_string(&mut chunk)
+            });
+            if let Err(e) = read {
+                debug_log!("TRANSCRIPT_WATCH", "[{}] Read error: {}", ui_session_id, e);
+                continue;
+            }
+            position = len;
+            last_growth = Instant::now();
+            pending.push_str(&chunk);
+
+            // Only process complete lines; keep a trailing partial line for next poll
+            while let Some(newline) = pending.find('\n') {
+                let line: String = pending.drain(..=newline).collect();
+                let event = match serde_json::from_str::<serde_json::Value>(line.trim()) {
+                    Ok(e) => e,
+                    Err(_) => continue,
+                };
+                let result = catch_unwind(AssertUnwindSafe(|| {
+                    process_event(&event, &tracking, Some(&app), &ui_session_id)
+                }));
+                if let Err(payload) = result {
+                    panic_guard::emit_reader_crashed(&app, Some(&ui_session_id), None, "transcript", payload.as_ref(), false);
+                }
+            }
+        }
+
+        debug_log!("TRANSCRIPT_WATCH", "[{}] Transcript idle, stopping", ui_session_id);
+        let _ = events::emit(
+            &app,
+            BackendEvent::SessionEnded {
+                ui_session_id,
+                exit_code: None,
+                error: None,
+                reason: lock_tracking(&tracking).end_reason,
+            },
+        );
+    });
+    true
+}
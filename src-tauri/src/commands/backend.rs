@@ -0,0 +1,172 @@
+use crate::background_ai::{self, BackgroundAiStatus};
+use crate::claude::schema_drift::{self, DriftReport};
+use crate::claude::ClaudeManager;
+use crate::capability_audit::{self, CapabilityAudit};
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, EmergencyStopReport};
+use crate::hooks;
+use crate::slash::{SlashManager, SlashState};
+use crate::supervisor::{self, ServiceHealth};
+use crate::verification::fix_loop;
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use super::{ClaudeState, HookServerPort, HookState};
+
+/// An optional subsystem: whether this build includes it (Cargo feature)
+/// and whether the config turns it on
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureStatus {
+    pub name: &'static str,
+    pub compiled: bool,
+    pub enabled: bool,
+}
+
+/// Escape hatch: stop everything and rebuild the session managers without
+/// restarting the app. Use when a manager is wedged after a panic.
+#[tauri::command]
+pub fn reset_backend_state(
+    app: AppHandle,
+    claude_state: State<ClaudeState>,
+    slash_state: State<SlashState>,
+    hook_port: State<HookServerPort>,
+) -> Result<(), String> {
+    debug_log!("CMD", "reset_backend_state called");
+
+    fix_loop::stop_all(&app, "stopped");
+
+    {
+        let mut manager = claude_state.lock();
+        manager.shutdown_all(&app);
+        let mut fresh = ClaudeManager::new();
+        fresh.set_hook_port(hook_port.0);
+        *manager = fresh;
+    }
+
+    {
+        let mut manager = slash_state.lock();
+        manager.cancel_all();
+        *manager = SlashManager::new();
+    }
+
+    debug_log!("CMD", "  SUCCESS: backend state reset");
+    Ok(())
+}
+
+/// Panic button: deny all pending permissions, interrupt every Claude
+/// session and kill every slash command's process group
+#[tauri::command]
+pub async fn emergency_stop_all(
+    app: AppHandle,
+    claude_state: State<'_, ClaudeState>,
+    slash_state: State<'_, SlashState>,
+    hook_state: State<'_, HookState>,
+) -> Result<EmergencyStopReport, String> {
+    debug_log!("CMD", "emergency_stop_all called");
+
+    fix_loop::stop_all(&app, "stopped");
+    let denied_requests = hooks::deny_all_pending(&hook_state.0, "Stopped by user").await;
+    let interrupted_sessions = claude_state.lock().interrupt_all(&app);
+    let killed_slash_commands = slash_state.lock().kill_all();
+
+    let report = EmergencyStopReport {
+        interrupted_sessions,
+        killed_slash_commands,
+        denied_requests,
+    };
+    debug_log!(
+        "CMD",
+        "  Stopped {} sessions, {} slash commands, denied {} requests",
+        report.interrupted_sessions.len(),
+        report.killed_slash_commands.len(),
+        report.denied_requests.len()
+    );
+    let _ = events::emit(&app, BackendEvent::EmergencyStopped { report: report.clone() });
+    Ok(report)
+}
+
+/// Interrupt every active Claude process, cancel all slash commands and
+/// deny all pending permission requests. Fix loops are stopped first so
+/// none of them starts another turn.
+#[tauri::command]
+pub async fn kill_all_sessions(
+    app: AppHandle,
+    claude_state: State<'_, ClaudeState>,
+    slash_state: State<'_, SlashState>,
+    hook_state: State<'_, HookState>,
+) -> Result<EmergencyStopReport, String> {
+    debug_log!("CMD", "kill_all_sessions called");
+
+    fix_loop::stop_all(&app, "stopped");
+    let interrupted_sessions = claude_state.lock().interrupt_all(&app);
+    let killed_slash_commands = slash_state.lock().kill_all();
+    // Their hooks would otherwise keep waiting on the user
+    let denied_requests = hooks::deny_all_pending(&hook_state.0, "Session killed by user").await;
+
+    debug_log!(
+        "CMD",
+        "  Killed {} sessions and {} slash commands, denied {} requests",
+        interrupted_sessions.len(),
+        killed_slash_commands.len(),
+        denied_requests.len()
+    );
+    let report = EmergencyStopReport {
+        interrupted_sessions,
+        killed_slash_commands,
+        denied_requests,
+    };
+    let _ = events::emit(&app, BackendEvent::EmergencyStopped { report: report.clone() });
+    Ok(report)
+}
+
+/// Health of supervised background services
+#[tauri::command]
+pub fn get_service_health() -> Vec<ServiceHealth> {
+    supervisor::health()
+}
+
+/// Queue, spend and pause state of background Claude calls
+#[tauri::command]
+pub fn get_background_ai_status() -> BackgroundAiStatus {
+    background_ai::status()
+}
+
+/// Kill switch for background Claude calls: pausing stops the running job
+/// and drops everything queued
+#[tauri::command]
+pub fn set_background_ai_paused(paused: bool) -> BackgroundAiStatus {
+    debug_log!("CMD", "set_background_ai_paused({})", paused);
+    background_ai::set_paused(paused);
+    background_ai::status()
+}
+
+/// Sensitive webview capabilities this build ships with
+#[tauri::command]
+pub fn get_capability_audit() -> Result<CapabilityAudit, String> {
+    capability_audit::audit()
+}
+
+/// Unknown stream-json event types and unexpected field shapes seen since
+/// startup, for spotting Claude CLI output changes
+#[tauri::command]
+pub fn get_schema_drift_report() -> DriftReport {
+    schema_drift::report()
+}
+
+/// Optional subsystems, so the UI can hide what this build or config leaves out
+#[tauri::command]
+pub fn get_enabled_features() -> Vec<FeatureStatus> {
+    let search = cfg!(feature = "search");
+    let analytics = cfg!(feature = "analytics");
+    #[cfg(feature = "profiling")]
+    let profiling = (true, config::profiling_enabled());
+    #[cfg(not(feature = "profiling"))]
+    let profiling = (false, false);
+    vec![
+        FeatureStatus { name: "search", compiled: search, enabled: search && config::search_enabled() },
+        FeatureStatus { name: "analytics", compiled: analytics, enabled: analytics && config::analytics_enabled() },
+        FeatureStatus { name: "profiling", compiled: profiling.0, enabled: profiling.1 },
+    ]
+}
@@ -1,12 +1,31 @@
-use crate::claude::ClaudeManager;
+use crate::claude::tool_catalog::{self, ToolCatalog};
+use crate::claude::{rate_limit, resume_recovery, ClaudeManager, SessionOptions};
+use crate::commands::{sessions, HookState};
+use crate::config;
+use crate::connectivity::{self, QueuedSend};
 use crate::debug_log;
+use crate::hooks;
+use crate::events::PermissionMode;
+use crate::open_sessions;
+use crate::panic_guard;
+use crate::prompt_history;
+use crate::session_expiry;
+use crate::verification::fix_loop;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::fs;
+use std::sync::{Mutex, MutexGuard};
 use tauri::{AppHandle, State};
 
 /// State wrapper for ClaudeManager
 pub struct ClaudeState(pub Mutex<ClaudeManager>);
 
+impl ClaudeState {
+    /// Lock the manager, recovering from a poisoned mutex
+    pub fn lock(&self) -> MutexGuard<'_, ClaudeManager> {
+        panic_guard::lock_or_recover(&self.0, "ClaudeManager")
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SpawnSessionArgs {
     pub ui_session_id: String,
@@ -14,11 +33,38 @@ pub struct SpawnSessionArgs {
     pub initial_prompt: Option<String>,
     pub resume_session: Option<String>,
     pub model: Option<String>,
+    /// Keep the process alive between turns and send follow-ups on stdin
+    pub persistent: Option<bool>,
+    /// default / plan / acceptEdits / bypassPermissions
+    pub permission_mode: Option<PermissionMode>,
+    /// Pre-authorized tools or rules, e.g. `Read`, `Bash(git log:*)`
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    /// Tools or rules that are always blocked
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+    /// Stop after this many agentic turns (overrides the `maxTurns` config)
+    pub max_turns: Option<u32>,
+    /// Extra directories Claude may read and edit besides the working directory
+    #[serde(default)]
+    pub add_dirs: Vec<String>,
+    /// Extended thinking token budget; 0 disables thinking
+    pub thinking_budget: Option<u32>,
+    /// Emit `message.delta` as text is generated (`--include-partial-messages`)
+    pub stream_partial: Option<bool>,
+    /// Provider profile (Anthropic API / Bedrock / Vertex); None uses `activeProvider`
+    pub provider: Option<String>,
+    /// Start even when over budget and `budgetConfirmSpawn` is on
+    pub confirm_over_budget: Option<bool>,
 }
 
 #[derive(Serialize)]
 pub struct SpawnSessionResult {
     pub session_id: String,
+    /// Held back while offline; sent when `connectivity.changed` reports
+    /// the network is back
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub queued: bool,
 }
 
 /// Spawn a new Claude session
@@ -34,11 +80,21 @@ pub fn spawn_claude_session(
     debug_log!("CMD", "  initial_prompt: {:?}", args.initial_prompt);
     debug_log!("CMD", "  resume_session: {:?}", args.resume_session);
     debug_log!("CMD", "  model: {:?}", args.model);
+    debug_log!("CMD", "  persistent: {:?}", args.persistent);
+    debug_log!("CMD", "  permission_mode: {:?}", args.permission_mode);
+    debug_log!("CMD", "  allowed_tools: {:?}", args.allowed_tools);
+    debug_log!("CMD", "  disallowed_tools: {:?}", args.disallowed_tools);
+    debug_log!("CMD", "  max_turns: {:?}", args.max_turns);
+    debug_log!("CMD", "  add_dirs: {:?}", args.add_dirs);
+    debug_log!("CMD", "  thinking_budget: {:?}", args.thinking_budget);
+    debug_log!("CMD", "  stream_partial: {:?}", args.stream_partial);
+    debug_log!("CMD", "  provider: {:?}", args.provider);
+
+    if let Some(ref prompt) = args.initial_prompt {
+        prompt_history::record(&args.working_directory, prompt, args.resume_session.as_deref());
+    }
 
-    let mut manager = state.0.lock().map_err(|e| {
-        debug_log!("CMD", "  ERROR: Failed to lock manager: {}", e);
-        e.to_string()
-    })?;
+    let mut manager = state.lock();
 
     let session_id = manager.spawn_session(
         &app,
@@ -46,14 +102,28 @@ pub fn spawn_claude_session(
         args.working_directory,
         args.initial_prompt,
         args.resume_session,
-        args.model,
+        SessionOptions {
+            model: args.model,
+            persistent: args.persistent.unwrap_or(false),
+            permission_mode: args.permission_mode,
+            allowed_tools: args.allowed_tools,
+            disallowed_tools: args.disallowed_tools,
+            max_turns: args.max_turns,
+            add_dirs: args.add_dirs,
+            fork_session: false,
+            thinking_budget: args.thinking_budget,
+            partial_messages: args.stream_partial.unwrap_or(false),
+            provider: args.provider,
+            confirm_over_budget: args.confirm_over_budget.unwrap_or(false),
+        },
     )?;
 
     debug_log!("CMD", "  SUCCESS: session_id = {}", session_id);
-    Ok(SpawnSessionResult { session_id })
+    Ok(SpawnSessionResult { session_id, queued: false })
 }
 
-/// Send a follow-up message to a Claude session using --resume
+/// Send a follow-up message to a Claude session: written to stdin for a
+/// live keep-alive session, otherwise via a new process with --resume
 #[tauri::command]
 pub fn send_claude_message(
     app: AppHandle,
@@ -71,23 +141,197 @@ pub fn send_claude_message(
     debug_log!("CMD", "  content: {}", &content[..content.len().min(100)]);
     debug_log!("CMD", "  model: {:?}", model);
 
-    let mut manager = state.0.lock().map_err(|e| {
-        debug_log!("CMD", "  ERROR: Failed to lock manager: {}", e);
-        e.to_string()
-    })?;
+    prompt_history::record(&working_directory, &content, Some(&claude_session_id));
+    // The user's own turn replaces a pending rate-limit retry
+    rate_limit::forget(&ui_session_id);
+
+    if !connectivity::is_online() {
+        connectivity::queue_send(QueuedSend {
+            ui_session_id: ui_session_id.clone(),
+            working_directory,
+            content,
+            claude_session_id,
+            model,
+        });
+        return Ok(SpawnSessionResult { session_id: ui_session_id, queued: true });
+    }
+
+    let mut manager = state.lock();
+
+    manager.send_message(&app, &ui_session_id, working_directory, content, claude_session_id, model)?;
+
+    debug_log!("CMD", "  SUCCESS: message sent to {}", ui_session_id);
+    Ok(SpawnSessionResult { session_id: ui_session_id, queued: false })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecoverResumeArgs {
+    pub ui_session_id: String,
+    /// The Claude session that could not be resumed
+    pub claude_session_id: String,
+    pub working_directory: String,
+    /// The message that was being sent when the resume failed
+    pub content: String,
+    pub model: Option<String>,
+}
+
+/// Fallback after `session.resume_failed`: start a fresh Claude session
+/// primed with a condensed history of the lost one
+#[tauri::command]
+pub fn recover_failed_resume(
+    app: AppHandle,
+    state: State<ClaudeState>,
+    args: RecoverResumeArgs,
+) -> Result<SpawnSessionResult, String> {
+    debug_log!("CMD", "recover_failed_resume called");
+    debug_log!("CMD", "  ui_session_id: {}", args.ui_session_id);
+    debug_log!("CMD", "  claude_session_id: {}", args.claude_session_id);
+
+    let primer = resume_recovery::build_primer(
+        &args.working_directory,
+        &args.claude_session_id,
+        &args.ui_session_id,
+    );
+    debug_log!("CMD", "  primer available: {}", primer.is_some());
+    let prompt = resume_recovery::primed_prompt(primer.as_deref(), &args.content);
+
+    let mut manager = state.lock();
+    manager.spawn_session(
+        &app,
+        args.ui_session_id.clone(),
+        args.working_directory,
+        Some(prompt),
+        None,
+        SessionOptions {
+            model: args.model,
+            ..Default::default()
+        },
+    )?;
+
+    Ok(SpawnSessionResult { session_id: args.ui_session_id, queued: false })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConsolidateSessionsArgs {
+    /// New UI session that receives the merged context
+    pub ui_session_id: String,
+    /// Claude sessions to merge, in any order
+    pub claude_session_ids: Vec<String>,
+    pub working_directory: String,
+    /// First message of the new session
+    pub prompt: String,
+    pub model: Option<String>,
+    pub persistent: Option<bool>,
+}
+
+/// Start a new session primed with the condensed history (summary and key
+/// messages) of several related ones, oldest first
+#[tauri::command]
+pub fn consolidate_sessions(
+    app: AppHandle,
+    state: State<ClaudeState>,
+    args: ConsolidateSessionsArgs,
+) -> Result<SpawnSessionResult, String> {
+    debug_log!("CMD", "consolidate_sessions called");
+    debug_log!("CMD", "  ui_session_id: {}", args.ui_session_id);
+    debug_log!("CMD", "  claude_session_ids: {:?}", args.claude_session_ids);
+
+    let projects_dir = config::projects_dir();
+    let cleanup_period_days = session_expiry::cleanup_period_days();
+    let mut found: Vec<(i64, String, String)> = Vec::new();
+    for id in &args.claude_session_ids {
+        let path = match resume_recovery::find_transcript(id) {
+            Some(path) => path,
+            None => {
+                debug_log!("CMD", "  no transcript for {}", id);
+                continue;
+            }
+        };
+        let history = match fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| resume_recovery::condense_for_consolidation(&c))
+        {
+            Some(history) => history,
+            None => continue,
+        };
+        let session = sessions::session_for_transcript(&projects_dir, &path, cleanup_period_days);
+        let modified = session.as_ref().map(|s| s.modified_at_ms).unwrap_or(0);
+        let label = session
+            .and_then(|s| s.title.or(s.first_message))
+            .unwrap_or_else(|| id.clone());
+        found.push((modified, label, history));
+    }
+    if found.is_empty() {
+        return Err("None of the selected sessions have any history to consolidate".to_string());
+    }
+    found.sort_by_key(|(modified, _, _)| *modified);
+    let histories: Vec<(String, String)> = found.into_iter().map(|(_, label, history)| (label, history)).collect();
+    debug_log!("CMD", "  consolidating {} sessions", histories.len());
+    let prompt = resume_recovery::consolidated_prompt(&histories, &args.prompt);
+
+    let mut manager = state.lock();
+    manager.spawn_session(
+        &app,
+        args.ui_session_id.clone(),
+        args.working_directory,
+        Some(prompt),
+        None,
+        SessionOptions {
+            model: args.model,
+            persistent: args.persistent.unwrap_or(false),
+            ..Default::default()
+        },
+    )?;
+
+    Ok(SpawnSessionResult { session_id: args.ui_session_id, queued: false })
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForkSessionArgs {
+    /// New UI session that receives the fork
+    pub ui_session_id: String,
+    /// Claude session to branch from (left untouched)
+    pub claude_session_id: String,
+    pub working_directory: String,
+    /// First message of the new branch
+    pub prompt: String,
+    pub model: Option<String>,
+    pub persistent: Option<bool>,
+    pub permission_mode: Option<PermissionMode>,
+}
 
-    // Spawn new process with --resume to continue the session
-    let new_session_id = manager.spawn_session(
+/// Branch a conversation at its current point: resume `claude_session_id`
+/// with `--fork-session` into a new UI session. The fork's own Claude
+/// session ID arrives with `session.started`.
+#[tauri::command]
+pub fn fork_claude_session(
+    app: AppHandle,
+    state: State<ClaudeState>,
+    args: ForkSessionArgs,
+) -> Result<SpawnSessionResult, String> {
+    debug_log!("CMD", "fork_claude_session called");
+    debug_log!("CMD", "  ui_session_id: {}", args.ui_session_id);
+    debug_log!("CMD", "  claude_session_id: {}", args.claude_session_id);
+    debug_log!("CMD", "  working_directory: {}", args.working_directory);
+
+    let mut manager = state.lock();
+    manager.spawn_session(
         &app,
-        ui_session_id.clone(),
-        working_directory,
-        Some(content),
-        Some(claude_session_id),
-        model,
+        args.ui_session_id.clone(),
+        args.working_directory,
+        Some(args.prompt),
+        Some(args.claude_session_id),
+        SessionOptions {
+            model: args.model,
+            persistent: args.persistent.unwrap_or(false),
+            permission_mode: args.permission_mode,
+            fork_session: true,
+            ..Default::default()
+        },
     )?;
 
-    debug_log!("CMD", "  SUCCESS: resumed with session_id = {}", new_session_id);
-    Ok(SpawnSessionResult { session_id: ui_session_id })
+    debug_log!("CMD", "  SUCCESS: forked into {}", args.ui_session_id);
+    Ok(SpawnSessionResult { session_id: args.ui_session_id, queued: false })
 }
 
 /// Interrupt a Claude session
@@ -97,27 +341,116 @@ pub fn interrupt_claude_session(
     state: State<ClaudeState>,
     ui_session_id: String,
 ) -> Result<(), String> {
-    let mut manager = state.0.lock().map_err(|e| e.to_string())?;
+    fix_loop::stop(&app, &ui_session_id, "stopped");
+    let mut manager = state.lock();
     manager.interrupt_session(&app, &ui_session_id)
 }
 
-/// Check if a Claude session is running
+/// Restart a keep-alive session so it re-reads CLAUDE.md and rules
+/// (offered after `memory.changed`)
+#[tauri::command]
+pub fn restart_claude_session(
+    app: AppHandle,
+    state: State<ClaudeState>,
+    ui_session_id: String,
+) -> Result<(), String> {
+    debug_log!("CMD", "restart_claude_session called for {}", ui_session_id);
+    state.lock().restart_session(&app, &ui_session_id)
+}
+
+#[derive(Serialize)]
+pub struct SuspendedSession {
+    pub ui_session_id: String,
+    pub claude_session_id: String,
+}
+
+/// Sessions suspended while idle (they resume on the next message)
+#[tauri::command]
+pub fn list_suspended_sessions(state: State<ClaudeState>) -> Vec<SuspendedSession> {
+    state
+        .lock()
+        .suspended_sessions()
+        .into_iter()
+        .map(|(ui_session_id, claude_session_id)| SuspendedSession { ui_session_id, claude_session_id })
+        .collect()
+}
+
+#[derive(Serialize)]
+pub struct RestoredSession {
+    pub ui_session_id: String,
+    pub claude_session_id: String,
+    pub working_directory: String,
+    pub model: Option<String>,
+    pub persistent: bool,
+    pub last_active_at: String,
+    /// None if Claude has since pruned the transcript
+    pub transcript_path: Option<String>,
+}
+
+/// Sessions that were open when Horseman last quit, most recent first.
+/// Each is registered with the manager so its next message resumes it
+/// with the options it was last spawned with.
+#[tauri::command]
+pub fn restore_sessions(state: State<ClaudeState>) -> Vec<RestoredSession> {
+    let mut manager = state.lock();
+    open_sessions::resumable()
+        .into_iter()
+        .filter_map(|s| {
+            let claude_session_id = s.claude_session_id?;
+            let (model, persistent) = (s.options.model.clone(), s.options.persistent);
+            manager.restore_session(&s.ui_session_id, s.working_directory.clone(), claude_session_id.clone(), s.options);
+            let transcript_path = resume_recovery::find_transcript(&claude_session_id)
+                .map(|p| p.to_string_lossy().to_string());
+            Some(RestoredSession {
+                ui_session_id: s.ui_session_id,
+                claude_session_id,
+                working_directory: s.working_directory,
+                model,
+                persistent,
+                last_active_at: s.last_active_at,
+                transcript_path,
+            })
+        })
+        .collect()
+}
+
+/// Check if a Claude session is running (or queued to start)
 #[tauri::command]
 pub fn is_claude_running(
     state: State<ClaudeState>,
     ui_session_id: String,
 ) -> Result<bool, String> {
-    let mut manager = state.0.lock().map_err(|e| e.to_string())?;
-    Ok(manager.is_running(&ui_session_id))
+    let mut manager = state.lock();
+    Ok(manager.is_running(&ui_session_id) || manager.is_queued(&ui_session_id))
 }
 
-/// Remove a Claude session
+/// Remove a Claude session, along with its session-scoped approvals
 #[tauri::command]
-pub fn remove_claude_session(
-    state: State<ClaudeState>,
+pub async fn remove_claude_session(
+    state: State<'_, ClaudeState>,
+    hook_state: State<'_, HookState>,
     ui_session_id: String,
 ) -> Result<(), String> {
-    let mut manager = state.0.lock().map_err(|e| e.to_string())?;
-    manager.remove_session(&ui_session_id);
+    state.lock().remove_session(&ui_session_id);
+    hooks::forget_session(&hook_state.0, &ui_session_id).await;
     Ok(())
 }
+
+/// Tools available to a session: built-ins plus those of its MCP servers,
+/// which are started briefly to list them. Without a running session,
+/// `working_directory` picks the project.
+#[tauri::command]
+pub async fn get_tool_catalog(
+    state: State<'_, ClaudeState>,
+    ui_session_id: Option<String>,
+    working_directory: Option<String>,
+) -> Result<ToolCatalog, String> {
+    let working_directory = ui_session_id
+        .as_deref()
+        .and_then(|id| state.lock().working_directory(id))
+        .or(working_directory)
+        .ok_or("No session or working directory given")?;
+    tauri::async_runtime::spawn_blocking(move || tool_catalog::catalog(ui_session_id.as_deref(), &working_directory))
+        .await
+        .map_err(|e| format!("Tool catalog task failed: {}", e))
+}
@@ -0,0 +1,379 @@
+use crate::atomic_file;
+use crate::background_ai::{self, JobRequest, Priority};
+use crate::checkpoint;
+use crate::debug_log;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Max time to wait for Claude to refine a draft
+const REFINE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A command the draft tells Claude to use
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectCommand {
+    /// build / test / lint / dev / typecheck / format
+    pub purpose: String,
+    pub command: String,
+    /// Manifest the command was found in
+    pub source: String,
+}
+
+/// What manifest parsing found out about a project
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectProfile {
+    pub name: Option<String>,
+    pub languages: Vec<String>,
+    /// Package managers / build tools (cargo, pnpm, poetry, ...)
+    pub tools: Vec<String>,
+    pub commands: Vec<ProjectCommand>,
+}
+
+/// Draft CLAUDE.md for review; nothing is written until `write_claude_md`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeMdDraft {
+    pub content: String,
+    pub profile: ProjectProfile,
+    /// Whether Claude rewrote the template draft
+    pub refined: bool,
+    /// The project already has a CLAUDE.md
+    pub exists: bool,
+}
+
+impl ProjectProfile {
+    fn add_language(&mut self, language: &str) {
+        if !self.languages.iter().any(|l| l == language) {
+            self.languages.push(language.to_string());
+        }
+    }
+
+    fn add_tool(&mut self, tool: &str) {
+        if !self.tools.iter().any(|t| t == tool) {
+            self.tools.push(tool.to_string());
+        }
+    }
+
+    /// Add a command unless one already covers `purpose`
+    fn add_command(&mut self, purpose: &str, command: String, source: &str) {
+        if !self.commands.iter().any(|c| c.purpose == purpose) {
+            self.commands.push(ProjectCommand {
+                purpose: purpose.to_string(),
+                command,
+                source: source.to_string(),
+            });
+        }
+    }
+}
+
+/// Script names in package.json / Makefile targets worth documenting
+const SCRIPT_PURPOSES: &[(&str, &str)] = &[
+    ("build", "build"),
+    ("test", "test"),
+    ("lint", "lint"),
+    ("typecheck", "typecheck"),
+    ("format", "format"),
+    ("dev", "dev"),
+];
+
+fn scan_cargo(profile: &mut ProjectProfile, content: &str) {
+    let manifest: toml::Value = match toml::from_str(content) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    profile.add_language("Rust");
+    profile.add_tool("cargo");
+    if profile.name.is_none() {
+        profile.name = manifest
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+            .map(|s| s.to_string());
+    }
+    let workspace = if manifest.get("workspace").is_some() { " --workspace" } else { "" };
+    profile.add_command("build", format!("cargo build{}", workspace), "Cargo.toml");
+    profile.add_command("test", format!("cargo test{}", workspace), "Cargo.toml");
+    profile.add_command("lint", format!("cargo clippy{} -- -D warnings", workspace), "Cargo.toml");
+    profile.add_command("format", "cargo fmt".to_string(), "Cargo.toml");
+}
+
+/// `package_manager` comes from the lockfile next to package.json
+fn scan_package_json(profile: &mut ProjectProfile, content: &str, package_manager: &str, typescript: bool) {
+    let manifest: serde_json::Value = match serde_json::from_str(content) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    let has_dep = |name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|key| manifest.get(key).and_then(|d| d.get(name)).is_some())
+    };
+    profile.add_language(if typescript || has_dep("typescript") { "TypeScript" } else { "JavaScript" });
+    profile.add_tool(package_manager);
+    if profile.name.is_none() {
+        profile.name = manifest.get("name").and_then(|n| n.as_str()).map(|s| s.to_string());
+    }
+    if let Some(scripts) = manifest.get("scripts").and_then(|s| s.as_object()) {
+        for (script, purpose) in SCRIPT_PURPOSES {
+            if scripts.contains_key(*script) {
+                profile.add_command(purpose, format!("{} run {}", package_manager, script), "package.json");
+            }
+        }
+    }
+}
+
+fn scan_pyproject(profile: &mut ProjectProfile, content: &str) {
+    let manifest: toml::Value = match toml::from_str(content) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    profile.add_language("Python");
+    let tool = manifest.get("tool");
+    let runner = if tool.and_then(|t| t.get("poetry")).is_some() {
+        profile.add_tool("poetry");
+        "poetry run "
+    } else if tool.and_then(|t| t.get("uv")).is_some() {
+        profile.add_tool("uv");
+        "uv run "
+    } else {
+        ""
+    };
+    if profile.name.is_none() {
+        profile.name = manifest
+            .get("project")
+            .and_then(|p| p.get("name"))
+            .or_else(|| tool.and_then(|t| t.get("poetry")).and_then(|p| p.get("name")))
+            .and_then(|n| n.as_str())
+            .map(|s| s.to_string());
+    }
+    if tool.and_then(|t| t.get("pytest")).is_some() || content.contains("pytest") {
+        profile.add_command("test", format!("{}pytest", runner), "pyproject.toml");
+    }
+    if tool.and_then(|t| t.get("ruff")).is_some() {
+        profile.add_command("lint", format!("{}ruff check .", runner), "pyproject.toml");
+        profile.add_command("format", format!("{}ruff format .", runner), "pyproject.toml");
+    }
+    if tool.and_then(|t| t.get("mypy")).is_some() {
+        profile.add_command("typecheck", format!("{}mypy .", runner), "pyproject.toml");
+    }
+}
+
+fn scan_go_mod(profile: &mut ProjectProfile, content: &str) {
+    profile.add_language("Go");
+    if profile.name.is_none() {
+        profile.name = content
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("module "))
+            .map(|m| m.trim().to_string());
+    }
+    profile.add_command("build", "go build ./...".to_string(), "go.mod");
+    profile.add_command("test", "go test ./...".to_string(), "go.mod");
+    profile.add_command("lint", "go vet ./...".to_string(), "go.mod");
+}
+
+fn scan_makefile(profile: &mut ProjectProfile, content: &str) {
+    profile.add_tool("make");
+    for (target, purpose) in SCRIPT_PURPOSES {
+        let defined = content
+            .lines()
+            .any(|l| l.strip_prefix(target).is_some_and(|rest| rest.starts_with(':')));
+        if defined {
+            profile.add_command(purpose, format!("make {}", target), "Makefile");
+        }
+    }
+}
+
+/// Inspect the manifests in `dir`. Language-specific manifests win over a
+/// Makefile for the same purpose.
+pub(crate) fn scan_project(dir: &Path) -> ProjectProfile {
+    let mut profile = ProjectProfile::default();
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+    let exists = |name: &str| dir.join(name).exists();
+
+    if let Some(content) = read("Cargo.toml") {
+        scan_cargo(&mut profile, &content);
+    }
+    if let Some(content) = read("package.json") {
+        let package_manager = if exists("pnpm-lock.yaml") {
+            "pnpm"
+        } else if exists("yarn.lock") {
+            "yarn"
+        } else if exists("bun.lockb") || exists("bun.lock") {
+            "bun"
+        } else {
+            "npm"
+        };
+        scan_package_json(&mut profile, &content, package_manager, exists("tsconfig.json"));
+    }
+    if let Some(content) = read("pyproject.toml") {
+        scan_pyproject(&mut profile, &content);
+    } else if exists("requirements.txt") {
+        profile.add_language("Python");
+    }
+    if let Some(content) = read("go.mod") {
+        scan_go_mod(&mut profile, &content);
+    }
+    if exists("Gemfile") {
+        profile.add_language("Ruby");
+        profile.add_tool("bundler");
+    }
+    if exists("pom.xml") {
+        profile.add_language("Java");
+        profile.add_tool("maven");
+        profile.add_command("build", "mvn package".to_string(), "pom.xml");
+        profile.add_command("test", "mvn test".to_string(), "pom.xml");
+    } else if exists("build.gradle") || exists("build.gradle.kts") {
+        profile.add_language(if exists("build.gradle.kts") { "Kotlin" } else { "Java" });
+        profile.add_tool("gradle");
+        profile.add_command("build", "./gradlew build".to_string(), "build.gradle");
+        profile.add_command("test", "./gradlew test".to_string(), "build.gradle");
+    }
+    if let Some(content) = read("Makefile") {
+        scan_makefile(&mut profile, &content);
+    }
+    profile
+}
+
+/// Template CLAUDE.md for a profile
+pub(crate) fn render_draft(profile: &ProjectProfile, fallback_name: &str) -> String {
+    let mut out = format!("# {}\n\n", profile.name.as_deref().unwrap_or(fallback_name));
+    out.push_str("## Overview\n\n");
+    if profile.languages.is_empty() {
+        out.push_str("<!-- Describe what this project does. -->\n");
+    } else {
+        out.push_str(&format!(
+            "<!-- Describe what this project does. -->\n\nWritten in {}",
+            profile.languages.join(", ")
+        ));
+        if !profile.tools.is_empty() {
+            out.push_str(&format!(" (tooling: {})", profile.tools.join(", ")));
+        }
+        out.push_str(".\n");
+    }
+
+    if !profile.commands.is_empty() {
+        out.push_str("\n## Commands\n\n```bash\n");
+        for command in &profile.commands {
+            out.push_str(&format!("{:<40} # {}\n", command.command, command.purpose));
+        }
+        out.push_str("```\n");
+    }
+
+    out.push_str("\n## Conventions\n\n<!-- Code style, architecture notes, things Claude should avoid. -->\n");
+    if profile.commands.iter().any(|c| c.purpose == "test") {
+        out.push_str("\n- Run the tests after making changes.\n");
+    }
+    out
+}
+
+/// Ask Claude to improve the draft using what it can see in the project.
+/// Returns None if the call fails, takes too long, or the background AI
+/// queue refuses it (paused or over budget).
+fn refine_with_claude(working_directory: &str, draft: &str) -> Option<String> {
+    let prompt = format!(
+        "Improve this draft CLAUDE.md for the project in the current directory. \
+         Keep the commands unless they are wrong, fill in the overview and conventions \
+         from the code, and stay concise. Reply with only the markdown.\n\n{}",
+        draft
+    );
+    let result = background_ai::run(JobRequest {
+        label: "Refine CLAUDE.md".to_string(),
+        prompt,
+        working_directory: working_directory.to_string(),
+        model: None,
+        priority: Priority::High,
+        timeout: REFINE_TIMEOUT,
+    });
+    match result {
+        Ok(output) if !output.text.is_empty() => Some(format!("{}\n", output.text)),
+        Ok(_) => None,
+        Err(e) => {
+            debug_log!("CLAUDE_MD", "Refine failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Draft a CLAUDE.md from the project's manifests, optionally refined by a
+/// one-shot Claude call. Returned for review; use `write_claude_md` to save.
+#[tauri::command]
+pub async fn generate_claude_md(working_directory: String, refine: Option<bool>) -> Result<ClaudeMdDraft, String> {
+    debug_log!("CLAUDE_MD", "Generating draft for {} (refine: {:?})", working_directory, refine);
+    tauri::async_runtime::spawn_blocking(move || {
+        let dir = Path::new(&working_directory);
+        if !dir.is_dir() {
+            return Err(format!("Not a directory: {}", working_directory));
+        }
+        let profile = scan_project(dir);
+        let fallback_name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Project".to_string());
+        let draft = render_draft(&profile, &fallback_name);
+
+        let refined = if refine.unwrap_or(false) {
+            refine_with_claude(&working_directory, &draft)
+        } else {
+            None
+        };
+        Ok(ClaudeMdDraft {
+            refined: refined.is_some(),
+            content: refined.unwrap_or(draft),
+            profile,
+            exists: dir.join("CLAUDE.md").exists(),
+        })
+    })
+    .await
+    .map_err(|e| format!("CLAUDE.md generation task failed: {}", e))?
+}
+
+/// Save a reviewed CLAUDE.md into the project root
+#[tauri::command]
+pub fn write_claude_md(working_directory: String, content: String, overwrite: Option<bool>) -> Result<String, String> {
+    let path = Path::new(&working_directory).join("CLAUDE.md");
+    if path.exists() && !overwrite.unwrap_or(false) {
+        return Err(format!("{} already exists", path.display()));
+    }
+    let before = fs::read_to_string(&path).ok();
+    let entry = checkpoint::record(&path, before, Some(content.clone()), "Write CLAUDE.md")?;
+    if let Err(e) = atomic_file::write(&path, &content) {
+        checkpoint::discard(&entry.id);
+        return Err(format!("Failed to write CLAUDE.md: {}", e));
+    }
+    debug_log!("CLAUDE_MD", "Wrote {:?}", path);
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_commands_from_manifests() {
+        let mut profile = ProjectProfile::default();
+        scan_cargo(&mut profile, "[package]\nname = \"demo\"\n");
+        scan_package_json(
+            &mut profile,
+            r#"{"name":"web","scripts":{"dev":"vite","test":"vitest","lint":"eslint ."},"devDependencies":{"typescript":"5"}}"#,
+            "pnpm",
+            false,
+        );
+        scan_makefile(&mut profile, "test:\n\tcargo test\nrelease: build\n");
+
+        assert_eq!(profile.name.as_deref(), Some("demo"));
+        assert_eq!(profile.languages, vec!["Rust", "TypeScript"]);
+        let command = |purpose: &str| {
+            profile.commands.iter().find(|c| c.purpose == purpose).map(|c| c.command.as_str())
+        };
+        // Cargo came first, so it owns build/test/lint
+        assert_eq!(command("test"), Some("cargo test"));
+        assert_eq!(command("dev"), Some("pnpm run dev"));
+
+        let draft = render_draft(&profile, "fallback");
+        assert!(draft.starts_with("# demo\n"));
+        assert!(draft.contains("pnpm run dev"));
+    }
+}
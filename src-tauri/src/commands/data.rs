@@ -0,0 +1,80 @@
+use crate::data_transfer::{self, ExportReport, ImportReport, SessionImportReport};
+use crate::debug_log;
+use std::path::Path;
+
+/// Bundle config, stores, session metadata and (optionally) transcripts
+/// into one archive for moving to another machine
+#[tauri::command]
+pub async fn export_horseman_data(
+    archive_path: String,
+    include_transcripts: Option<bool>,
+) -> Result<ExportReport, String> {
+    debug_log!("CMD", "export_horseman_data called");
+    debug_log!("CMD", "  archive_path: {}", archive_path);
+    debug_log!("CMD", "  include_transcripts: {:?}", include_transcripts);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        data_transfer::export(Path::new(&archive_path), include_transcripts.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Restore data from an archive made by `export_horseman_data`
+#[tauri::command]
+pub async fn import_horseman_data(
+    archive_path: String,
+    overwrite: Option<bool>,
+) -> Result<ImportReport, String> {
+    debug_log!("CMD", "import_horseman_data called");
+    debug_log!("CMD", "  archive_path: {}", archive_path);
+    debug_log!("CMD", "  overwrite: {:?}", overwrite);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        data_transfer::import(Path::new(&archive_path), overwrite.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))?
+}
+
+/// Zip one session (transcript, subagent transcripts, annotations and
+/// metadata) for sharing
+#[tauri::command]
+pub async fn export_session_archive(
+    claude_session_id: String,
+    archive_path: String,
+) -> Result<ExportReport, String> {
+    debug_log!("CMD", "export_session_archive called");
+    debug_log!("CMD", "  claude_session_id: {}", claude_session_id);
+    debug_log!("CMD", "  archive_path: {}", archive_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        data_transfer::export_session(&claude_session_id, Path::new(&archive_path))
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Restore a session archive into the projects dir, optionally under a
+/// different working directory than the one it was exported from
+#[tauri::command]
+pub async fn import_session_archive(
+    archive_path: String,
+    working_directory: Option<String>,
+    overwrite: Option<bool>,
+) -> Result<SessionImportReport, String> {
+    debug_log!("CMD", "import_session_archive called");
+    debug_log!("CMD", "  archive_path: {}", archive_path);
+    debug_log!("CMD", "  working_directory: {:?}", working_directory);
+    debug_log!("CMD", "  overwrite: {:?}", overwrite);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        data_transfer::import_session(
+            Path::new(&archive_path),
+            working_directory.as_deref(),
+            overwrite.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))?
+}
@@ -1,4 +1,5 @@
 use crate::config::{self, get_config, resolve_claude_binary};
+use crate::fs_watch::{self, WatcherStatus};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -20,6 +21,8 @@ pub struct DiagnosticsInfo {
     pub spawn_test: SpawnTestResult,
     /// Environment info
     pub environment: EnvironmentInfo,
+    /// File watchers and whether they use native events or polling
+    pub watchers: Vec<WatcherStatus>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -262,6 +265,7 @@ pub fn get_diagnostics() -> DiagnosticsInfo {
         file_access,
         spawn_test,
         environment,
+        watchers: fs_watch::statuses(),
     }
 }
 
@@ -0,0 +1,127 @@
+use crate::atomic_file;
+use crate::checkpoint::{self, CheckpointEntry};
+use crate::claude::cached_message_text;
+use crate::commands::ClaudeState;
+use crate::claude::suggested_edit::{apply_block, extract_fenced_blocks, preview_diff};
+use crate::debug_log;
+use crate::external_diff::{self, ExternalDiff};
+use crate::hooks::path_scope;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedEditResult {
+    pub target_path: String,
+    /// Unified diff of the change (for preview)
+    pub preview: String,
+    /// False when called with dry_run
+    pub applied: bool,
+    /// Checkpoint entry that can undo this change
+    pub checkpoint_id: Option<String>,
+}
+
+/// Apply a code block Claude suggested in prose to a file.
+/// Diff blocks are applied as patches; other blocks replace the file contents.
+/// The target must be inside a session's working directory or added dirs.
+#[tauri::command]
+pub fn apply_suggested_edit(
+    state: State<'_, ClaudeState>,
+    message_id: String,
+    block_index: usize,
+    target_path: String,
+    dry_run: Option<bool>,
+) -> Result<SuggestedEditResult, String> {
+    debug_log!("CMD", "apply_suggested_edit called");
+    debug_log!("CMD", "  message_id: {}", message_id);
+    debug_log!("CMD", "  block_index: {}", block_index);
+    debug_log!("CMD", "  target_path: {}", target_path);
+
+    if !Path::new(&target_path).is_absolute() {
+        return Err(format!("Target path must be absolute: {}", target_path));
+    }
+    let resolved = path_scope::resolve(Path::new(&target_path));
+    let roots = state.lock().readable_roots();
+    if !roots.iter().any(|root| resolved.starts_with(path_scope::resolve(Path::new(root)))) {
+        return Err(format!("{} is outside the sessions' directories", target_path));
+    }
+    let path = resolved.as_path();
+
+    let text = cached_message_text(&message_id)
+        .ok_or_else(|| format!("Message not found: {}", message_id))?;
+    let blocks = extract_fenced_blocks(&text);
+    let block = blocks.get(block_index).ok_or_else(|| {
+        format!("Message has {} code blocks, no block at index {}", blocks.len(), block_index)
+    })?;
+
+    let before = fs::read_to_string(path).ok();
+    let after = apply_block(before.as_deref(), block)?;
+    let preview = preview_diff(&target_path, before.as_deref().unwrap_or(""), &after);
+
+    if dry_run.unwrap_or(false) {
+        return Ok(SuggestedEditResult {
+            target_path,
+            preview,
+            applied: false,
+            checkpoint_id: None,
+        });
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+    }
+    let entry = checkpoint::record(path, before, Some(after.clone()), "Apply suggested edit")?;
+    if let Err(e) = atomic_file::write(path, &after) {
+        checkpoint::discard(&entry.id);
+        return Err(format!("Failed to write {}: {}", target_path, e));
+    }
+
+    debug_log!("CMD", "  SUCCESS: applied, checkpoint {}", entry.id);
+    Ok(SuggestedEditResult {
+        target_path,
+        preview,
+        applied: true,
+        checkpoint_id: Some(entry.id),
+    })
+}
+
+/// Open an Edit/MultiEdit/Write tool call's change in the configured
+/// external diff tool. `transcript_path` is the session's transcript; calls
+/// made by subagents are found through it.
+#[tauri::command]
+pub async fn open_external_diff(tool_id: String, transcript_path: String) -> Result<ExternalDiff, String> {
+    debug_log!("CMD", "open_external_diff called: {} in {}", tool_id, transcript_path);
+    tauri::async_runtime::spawn_blocking(move || external_diff::open(&tool_id, Path::new(&transcript_path)))
+        .await
+        .map_err(|e| format!("External diff task failed: {}", e))?
+}
+
+/// List Horseman checkpoints (newest first)
+#[tauri::command]
+pub fn list_checkpoints() -> Vec<CheckpointEntry> {
+    checkpoint::list()
+}
+
+/// Undo a checkpointed change
+#[tauri::command]
+pub fn undo_checkpoint(checkpoint_id: String, force: Option<bool>) -> Result<CheckpointEntry, String> {
+    debug_log!("CMD", "undo_checkpoint called: {}", checkpoint_id);
+    checkpoint::undo(&checkpoint_id, force.unwrap_or(false))
+}
+
+/// Undo the newest Horseman workspace change that isn't undone yet
+#[tauri::command]
+pub fn undo_last_workspace_change(force: Option<bool>) -> Result<CheckpointEntry, String> {
+    debug_log!("CMD", "undo_last_workspace_change called");
+    checkpoint::undo_last(force.unwrap_or(false))
+}
+
+/// Re-apply the most recently undone Horseman workspace change
+#[tauri::command]
+pub fn redo_last_workspace_change(force: Option<bool>) -> Result<CheckpointEntry, String> {
+    debug_log!("CMD", "redo_last_workspace_change called");
+    checkpoint::redo_last(force.unwrap_or(false))
+}
@@ -1,6 +1,20 @@
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::hooks::path_scope;
 use ignore::WalkBuilder;
 use serde::{Deserialize, Serialize};
+use std::fs;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Largest file `read_text_file` will return
+const MAX_READ_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Saved clipboard images older than this are deleted on the next save
+const CLIPBOARD_IMAGE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
 /// A file entry for autocomplete
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,3 +121,115 @@ pub fn glob_files(
 
     Ok(results)
 }
+
+/// Read a text file for display (file references in chat). Replaces the
+/// webview's direct fs plugin access, so only files under a session's
+/// working directory or extra directories can be read, symlinks resolved.
+#[tauri::command]
+pub fn read_text_file(state: State<'_, ClaudeState>, path: String) -> Result<String, String> {
+    if !Path::new(&path).is_absolute() {
+        return Err(format!("Not an absolute path: {}", path));
+    }
+    let resolved = path_scope::resolve(Path::new(&path));
+    let roots = state.lock().readable_roots();
+    if !roots.iter().any(|root| resolved.starts_with(path_scope::resolve(Path::new(root)))) {
+        return Err(format!("{} is outside the sessions' directories", path));
+    }
+    let meta = fs::metadata(&resolved).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if !meta.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+    if meta.len() > MAX_READ_BYTES {
+        return Err(format!("File too large to display ({} bytes): {}", meta.len(), path));
+    }
+    fs::read_to_string(&resolved).map_err(|e| format!("Failed to read {}: {}", path, e))
+}
+
+/// A clipboard image written to disk so it can be attached to a prompt
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardImage {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Delete saved clipboard images past their TTL
+fn prune_clipboard_images(dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .is_some_and(|age| age > CLIPBOARD_IMAGE_TTL);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Encode RGBA pixels as PNG
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    writer
+        .write_image_data(rgba)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    writer.finish().map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(out)
+}
+
+/// Save the image on the clipboard as a temporary PNG and return its path
+/// and size. The clipboard plugin only hands raw pixels to the webview.
+#[tauri::command]
+pub fn save_clipboard_image(app: AppHandle) -> Result<ClipboardImage, String> {
+    let image = app
+        .clipboard()
+        .read_image()
+        .map_err(|e| format!("No image on the clipboard: {}", e))?;
+    let (width, height) = (image.width(), image.height());
+    let png = encode_png(image.rgba(), width, height)?;
+
+    let dir = config::artifacts_dir()
+        .map(|d| d.join("clipboard"))
+        .ok_or("Could not determine artifacts directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create clipboard directory: {}", e))?;
+    prune_clipboard_images(&dir);
+
+    let path = dir.join(format!("clipboard-{}.png", uuid::Uuid::new_v4()));
+    fs::write(&path, png).map_err(|e| format!("Failed to write clipboard image: {}", e))?;
+    debug_log!("FILES", "Saved clipboard image {}x{} to {:?}", width, height, path);
+
+    Ok(ClipboardImage {
+        path: path.to_string_lossy().to_string(),
+        width,
+        height,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_rgba_as_png() {
+        let pixels = [255u8, 0, 0, 255, 0, 255, 0, 128];
+        let png = encode_png(&pixels, 2, 1).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let decoder = png::Decoder::new(png.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(&buf[..info.buffer_size()], &pixels);
+    }
+}
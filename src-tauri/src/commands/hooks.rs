@@ -1,5 +1,10 @@
 use crate::debug_log;
+use crate::events::SessionPendingQuestion;
+use crate::hooks::mcp_servers::SkippedServer;
+use crate::hooks::permission_rules::RuleImport;
+use crate::hooks::types::{ApprovalScope, PermissionLane, PermissionResponse, SessionPriority};
 use crate::hooks::HookServerState;
+use crate::pending_questions;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
@@ -7,8 +12,11 @@ use tauri::State;
 /// State wrapper for hook server
 pub struct HookState(pub Arc<HookServerState>);
 
-/// Respond to a pending permission request
+/// Respond to a pending permission request. An approval can also cover the
+/// tool for the rest of the session, or for files under `allow_path_prefix`,
+/// and can run the tool with `updated_input` instead of Claude's input.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn respond_permission(
     state: State<'_, HookState>,
     request_id: String,
@@ -16,7 +24,9 @@ pub async fn respond_permission(
     message: Option<String>,
     tool_name: Option<String>,
     allow_for_session: Option<bool>,
+    allow_path_prefix: Option<String>,
     answers: Option<HashMap<String, String>>,
+    updated_input: Option<serde_json::Value>,
 ) -> Result<(), String> {
     debug_log!("CMD", "respond_permission called");
     debug_log!("CMD", "  request_id: {}", request_id);
@@ -24,17 +34,65 @@ pub async fn respond_permission(
     debug_log!("CMD", "  message: {:?}", message);
     debug_log!("CMD", "  tool_name: {:?}", tool_name);
     debug_log!("CMD", "  allow_for_session: {:?}", allow_for_session);
+    debug_log!("CMD", "  allow_path_prefix: {:?}", allow_path_prefix);
     debug_log!("CMD", "  answers: {:?}", answers);
+    debug_log!("CMD", "  updated_input: {:?}", updated_input);
 
-    crate::hooks::respond_permission(
-        &state.0,
-        request_id,
+    let scope = match (allow_path_prefix, allow_for_session) {
+        (Some(prefix), _) => ApprovalScope::PathPrefix(prefix),
+        (None, Some(true)) => ApprovalScope::Session,
+        (None, _) => ApprovalScope::Once,
+    };
+    let response = PermissionResponse {
         allow,
         message,
-        tool_name,
-        allow_for_session.unwrap_or(false),
         answers,
-    ).await
+        updated_input,
+    };
+    crate::hooks::respond_permission(&state.0, request_id, response, tool_name, scope).await
+}
+
+/// Import the permission allow/deny lists from Claude's user settings and
+/// `project`'s `.claude` settings into `permissionRules`, optionally writing
+/// Horseman's rules back so the CLI follows them too
+#[tauri::command]
+pub fn import_claude_permission_rules(project: Option<String>, write_back: Option<bool>) -> Result<RuleImport, String> {
+    debug_log!("CMD", "import_claude_permission_rules called (project: {:?}, write back: {:?})", project, write_back);
+    crate::hooks::permission_rules::import(project.as_deref(), write_back.unwrap_or(false))
+}
+
+/// Always allow Bash commands like `command` from now on, saving the rule
+/// (`prefix`, or one derived from the command) to the config. Returns it.
+#[tauri::command]
+pub fn add_bash_allow_rule(command: String, prefix: Option<String>) -> Result<String, String> {
+    debug_log!("CMD", "add_bash_allow_rule called: {} (prefix: {:?})", command, prefix);
+    crate::hooks::bash_rules::promote(&command, prefix)
+}
+
+/// Expand and validate the extra MCP servers configured for a project;
+/// returns the ones sessions there would leave out
+#[tauri::command]
+pub fn check_mcp_servers(working_directory: String) -> Vec<SkippedServer> {
+    crate::hooks::mcp_servers::resolve(&working_directory).1
+}
+
+/// Pending permissions and questions grouped by session, in the order they
+/// should be presented
+#[tauri::command]
+pub async fn get_permission_queue(state: State<'_, HookState>) -> Result<Vec<PermissionLane>, String> {
+    Ok(crate::hooks::permission_queue(&state.0).await)
+}
+
+/// Move a session's prompts ahead of (or behind) other sessions' prompts
+#[tauri::command]
+pub async fn set_session_priority(
+    state: State<'_, HookState>,
+    ui_session_id: String,
+    priority: SessionPriority,
+) -> Result<(), String> {
+    debug_log!("CMD", "set_session_priority called: {} -> {:?}", ui_session_id, priority);
+    crate::hooks::set_session_priority(&state.0, ui_session_id, priority).await;
+    Ok(())
 }
 
 /// Get the hook server port (useful for debugging)
@@ -45,3 +103,9 @@ pub fn get_hook_server_port(state: State<'_, HookServerPort>) -> u16 {
 
 /// State for hook server port
 pub struct HookServerPort(pub u16);
+
+/// Unanswered questions found by the startup scan (None while it is running)
+#[tauri::command]
+pub fn get_startup_pending_questions() -> Option<Vec<SessionPendingQuestion>> {
+    pending_questions::startup_result()
+}
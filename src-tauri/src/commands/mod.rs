@@ -1,15 +1,31 @@
+pub mod backend;
 pub mod claude;
+pub mod claude_md;
+pub mod data;
 pub mod diagnostics;
+pub mod edits;
 pub mod files;
 pub mod hooks;
+pub mod models;
+pub mod schema;
 pub mod sessions;
 pub mod slash;
 pub mod status;
+pub mod storage;
+pub mod verification;
 
+pub use backend::*;
 pub use claude::*;
+pub use claude_md::*;
+pub use data::*;
 pub use diagnostics::*;
+pub use edits::*;
 pub use files::*;
 pub use hooks::*;
+pub use models::*;
+pub use schema::*;
 pub use sessions::*;
 pub use slash::*;
 pub use status::*;
+pub use storage::*;
+pub use verification::*;
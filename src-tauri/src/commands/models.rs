@@ -0,0 +1,119 @@
+use crate::config;
+use crate::debug_log;
+use crate::providers::{self, ProviderKind};
+use super::get_claude_version;
+use serde::Serialize;
+
+/// A model the frontend can pass as `model` when spawning a session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    /// Value for `--model` (alias or full model ID)
+    pub id: String,
+    pub display_name: String,
+    pub context_window: u64,
+    /// Alias that always tracks the latest model of its family
+    pub alias: bool,
+}
+
+struct ModelSpec {
+    id: &'static str,
+    display_name: &'static str,
+    context_window: u64,
+    alias: bool,
+    /// First Claude CLI version that accepts the model
+    min_cli_version: Option<&'static str>,
+}
+
+/// Bundled model list; the CLI has no command to enumerate models
+const MODELS: &[ModelSpec] = &[
+    ModelSpec { id: "sonnet", display_name: "Sonnet 4.5", context_window: 200_000, alias: true, min_cli_version: None },
+    ModelSpec { id: "opus", display_name: "Opus 4.5", context_window: 200_000, alias: true, min_cli_version: None },
+    ModelSpec { id: "haiku", display_name: "Haiku 4.5", context_window: 200_000, alias: true, min_cli_version: None },
+    ModelSpec { id: "claude-opus-4-5", display_name: "Opus 4.5", context_window: 200_000, alias: false, min_cli_version: Some("2.0.51") },
+    ModelSpec { id: "claude-sonnet-4-5", display_name: "Sonnet 4.5", context_window: 200_000, alias: false, min_cli_version: Some("2.0.0") },
+    ModelSpec { id: "claude-haiku-4-5", display_name: "Haiku 4.5", context_window: 200_000, alias: false, min_cli_version: Some("2.0.17") },
+    ModelSpec { id: "claude-opus-4-1", display_name: "Opus 4.1", context_window: 200_000, alias: false, min_cli_version: Some("1.0.69") },
+];
+
+/// Parse "2.1.12" into comparable parts (non-numeric parts count as 0)
+fn version_parts(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|p| p.chars().take_while(|c| c.is_ascii_digit()).collect::<String>().parse().unwrap_or(0))
+        .collect()
+}
+
+/// Models usable with `cli_version`. An unknown version can't be gated,
+/// so everything is returned.
+fn models_for_version(cli_version: Option<&str>) -> Vec<ModelInfo> {
+    MODELS
+        .iter()
+        .filter(|m| match (m.min_cli_version, cli_version) {
+            (Some(min), Some(version)) => version_parts(version) >= version_parts(min),
+            _ => true,
+        })
+        .map(|m| ModelInfo {
+            id: m.id.to_string(),
+            display_name: m.display_name.to_string(),
+            context_window: m.context_window,
+            alias: m.alias,
+        })
+        .collect()
+}
+
+/// Models the installed Claude CLI supports, aliases first
+#[tauri::command]
+pub async fn list_available_models() -> Result<Vec<ModelInfo>, String> {
+    let version = tauri::async_runtime::spawn_blocking(get_claude_version)
+        .await
+        .map_err(|e| format!("Model listing task failed: {}", e))?;
+    debug_log!("MODELS", "Listing models for Claude CLI {:?}", version);
+    Ok(models_for_version(version.as_deref()))
+}
+
+/// A configured provider profile and whether it can be used
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStatus {
+    pub name: String,
+    pub kind: ProviderKind,
+    /// Used by sessions that don't pick a provider
+    pub active: bool,
+    /// Missing setting or credentials; None if the profile looks usable
+    pub problem: Option<String>,
+}
+
+/// Provider profiles from the config, validated
+#[tauri::command]
+pub fn list_provider_profiles() -> Vec<ProviderStatus> {
+    let active = config::active_provider();
+    let mut statuses: Vec<ProviderStatus> = config::provider_profiles()
+        .into_iter()
+        .map(|(name, profile)| ProviderStatus {
+            active: active.as_deref() == Some(name.as_str()),
+            kind: profile.kind,
+            problem: providers::env_for(&profile)
+                .err()
+                .or_else(|| providers::credential_problem(&profile)),
+            name,
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.name.cmp(&b.name));
+    statuses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gates_models_on_cli_version() {
+        let ids = |v| models_for_version(v).into_iter().map(|m| m.id).collect::<Vec<_>>();
+        assert!(ids(Some("2.0.51")).contains(&"claude-opus-4-5".to_string()));
+        assert!(!ids(Some("2.0.17")).contains(&"claude-opus-4-5".to_string()));
+        assert!(ids(Some("2.0.17")).contains(&"claude-haiku-4-5".to_string()));
+        assert!(!ids(Some("1.0.128")).contains(&"claude-sonnet-4-5".to_string()));
+        assert_eq!(ids(None).len(), MODELS.len());
+    }
+}
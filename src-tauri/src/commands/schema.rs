@@ -0,0 +1,45 @@
+use crate::debug_log;
+use crate::events::{self, EventFilter, MIN_SUPPORTED_SCHEMA_VERSION, SCHEMA_VERSION};
+use serde::Serialize;
+use tauri::WebviewWindow;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaVersionInfo {
+    /// Newest event schema the backend speaks
+    pub version: u32,
+    /// Oldest schema the backend can still emit
+    pub min_supported: u32,
+    /// Version currently used for emitted events
+    pub negotiated: u32,
+}
+
+/// Get the backend's event schema version
+#[tauri::command]
+pub fn get_backend_schema_version() -> SchemaVersionInfo {
+    SchemaVersionInfo {
+        version: SCHEMA_VERSION,
+        min_supported: MIN_SUPPORTED_SCHEMA_VERSION,
+        negotiated: events::negotiated_schema_version(),
+    }
+}
+
+/// Tell the backend which event schema the frontend understands.
+/// Returns the version that will be emitted from now on.
+#[tauri::command]
+pub fn negotiate_event_schema(frontend_version: u32) -> Result<u32, String> {
+    debug_log!("CMD", "negotiate_event_schema called: frontend v{}", frontend_version);
+    let version = events::negotiate_schema_version(frontend_version)?;
+    if version != SCHEMA_VERSION {
+        debug_log!("CMD", "  Emitting events with compat schema v{}", version);
+    }
+    Ok(version)
+}
+
+/// Limit which `horseman-event`s reach the calling window to the given
+/// sessions and event kinds; `null` receives everything again
+#[tauri::command]
+pub fn subscribe_events(window: WebviewWindow, filter: Option<EventFilter>) {
+    debug_log!("CMD", "subscribe_events called for window {}: {:?}", window.label(), filter);
+    events::subscribe(window.label(), filter);
+}
@@ -1,9 +1,53 @@
+#[cfg(feature = "analytics")]
+use crate::activity_report::{self, ActivityReport, ReportPeriod};
+#[cfg(feature = "analytics")]
+use crate::atomic_file;
+#[cfg(feature = "analytics")]
+use crate::cost_ledger::{self, CostReport};
 use crate::config;
 use crate::debug_log;
-use crate::claude::{parse_transcript_with_subagents, TranscriptParseResult};
+use crate::events::{self, BackendEvent};
+use crate::git;
+use crate::open_sessions;
+use crate::prompt_history;
+use crate::slow_io;
+use crate::store::{self, PromptEntry, SessionMeta, SessionSummary};
+#[cfg(feature = "analytics")]
+use crate::store::CostGrouping;
+#[cfg(feature = "analytics")]
+use crate::time_tracking::{self, TimeReport};
+#[cfg(feature = "search")]
+use crate::search_index::{self, SearchHit};
+use crate::session_cache::{self, CacheUpdate, SessionMetadata};
+use crate::session_expiry;
+use crate::workspace_diff::{self, WorkspaceDiff};
+use crate::claude::annotations::{self, Annotation, AnnotationKind};
+use crate::claude::transcript_backup::{self, TranscriptBackup};
+use crate::claude::transcript_page::{self, TranscriptPage};
+use crate::claude::tool_timeline::{self, ToolTimeline};
+use crate::claude::{resume_recovery, session_summary, transcript_tail};
+use crate::claude::parse_cache::{self, ParsedTranscript};
+use crate::claude::{raw_log, TranscriptParseResult, PARSER_VERSION};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+#[cfg(feature = "analytics")]
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::ClaudeState;
+
+/// Max time to spend listing sessions before returning a partial list
+const LIST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Max time to read/parse a single transcript
+const PARSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listing for maintenance commands, which can afford to wait longer
+const REPARSE_LIST_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// Session info discovered from Claude transcripts
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,8 +55,91 @@ pub struct DiscoveredSession {
     pub id: String,
     pub working_directory: String,
     pub transcript_path: String,
+    /// Local time for display only; sort by `modified_at_ms`
     pub modified_at: String,
+    /// Unix millis of the transcript's last modification
+    pub modified_at_ms: i64,
     pub first_message: Option<String>,
+    /// Branch the session was started on
+    pub git_branch: Option<String>,
+    /// Default display title (`branch — first prompt`)
+    pub title: Option<String>,
+    /// Title the user gave the session, shown instead of `title`
+    #[serde(default)]
+    pub custom_title: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Days until Claude's `cleanupPeriodDays` deletes the transcript; 0 or
+    /// less means its next start will
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+}
+
+/// Ordering for session listings. Ties (and the secondary key for
+/// project/title) fall back to newest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionSort {
+    #[default]
+    Modified,
+    Project,
+    Title,
+}
+
+impl DiscoveredSession {
+    fn sort_title(&self) -> String {
+        self.custom_title
+            .as_deref()
+            .or(self.title.as_deref())
+            .or(self.first_message.as_deref())
+            .unwrap_or(&self.id)
+            .to_lowercase()
+    }
+
+    fn apply_meta(&mut self, meta: SessionMeta) {
+        self.custom_title = meta.title;
+        self.pinned = meta.pinned;
+        self.tags = meta.tags;
+    }
+}
+
+/// Pinned sessions come first whatever the sort
+fn sort_sessions(sessions: &mut [DiscoveredSession], sort: SessionSort) {
+    sessions.sort_by(|a, b| {
+        let primary = b.pinned.cmp(&a.pinned).then_with(|| match sort {
+            SessionSort::Modified => std::cmp::Ordering::Equal,
+            SessionSort::Project => a.working_directory.cmp(&b.working_directory),
+            SessionSort::Title => a.sort_title().cmp(&b.sort_title()),
+        });
+        primary
+            .then_with(|| b.modified_at_ms.cmp(&a.modified_at_ms))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Discovered sessions plus a warning flag when storage was too slow to
+/// list everything in time (network drives, cloud-synced folders)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionListing {
+    pub sessions: Vec<DiscoveredSession>,
+    pub slow_storage: bool,
+}
+
+/// Outcome of re-parsing every stored transcript
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReparseReport {
+    pub parser_version: u32,
+    pub scanned: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Session IDs whose cached metadata changed
+    pub changed_sessions: Vec<String>,
+    /// "session_id: error" for transcripts that could not be parsed
+    pub errors: Vec<String>,
 }
 
 /// Get the Claude projects directory (from config or default)
@@ -22,7 +149,7 @@ fn claude_projects_dir() -> PathBuf {
 
 /// Decode an escaped directory name back to a path
 /// e.g., "-Users-ryandonofrio-Desktop-horseman" -> "/Users/ryandonofrio/Desktop/horseman"
-fn decode_dir_name(name: &str) -> String {
+pub(crate) fn decode_dir_name(name: &str) -> String {
     // Claude escapes paths by replacing "/" with "-" and prepending "-"
     // So "-Users-foo-bar" becomes "/Users/foo/bar"
     if name.starts_with('-') {
@@ -33,11 +160,12 @@ fn decode_dir_name(name: &str) -> String {
 }
 
 /// Extract first user message from a transcript file
-fn extract_first_message(path: &PathBuf) -> Option<String> {
-    let content = fs::read_to_string(path).ok()?;
+fn extract_first_message(path: &Path) -> Option<String> {
+    // Stop at the first user message instead of reading the whole file
+    let reader = BufReader::new(fs::File::open(path).ok()?);
 
-    for line in content.lines() {
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
+    for line in reader.lines().map_while(Result::ok) {
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
             // Look for user type messages with actual content
             if json.get("type").and_then(|v| v.as_str()) == Some("user") {
                 if let Some(message) = json.get("message") {
@@ -70,22 +198,82 @@ fn extract_first_message(path: &PathBuf) -> Option<String> {
     None
 }
 
-/// List all sessions from Claude's transcript directory
-#[tauri::command]
-pub fn list_claude_sessions() -> Result<Vec<DiscoveredSession>, String> {
-    let projects_dir = claude_projects_dir();
-    debug_log!("SESSIONS", "Listing Claude sessions from {:?}", projects_dir);
-
-    if !projects_dir.exists() {
-        debug_log!("SESSIONS", "Projects directory does not exist: {:?}", projects_dir);
-        return Ok(vec![]);
+/// Build the listing entry for one top-level transcript. `cleanup_period_days`
+/// is read once by the caller rather than per file.
+fn session_from_file(file_path: &Path, working_directory: String, cleanup_period_days: i64) -> DiscoveredSession {
+    let session_id = file_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    // Get modification time
+    let modified: Option<chrono::DateTime<chrono::Local>> = fs::metadata(file_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(|t| t.into());
+    let modified_at = modified
+        .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let modified_at_ms = modified.map(|d| d.timestamp_millis()).unwrap_or(0);
+
+    // Extract first message for display
+    let first_message = extract_first_message(file_path);
+    let git_branch = session_cache::git_context(&session_id)
+        .and_then(|g| g.branch)
+        .or_else(|| git::branch_from_transcript(file_path));
+    let title = git::default_title(git_branch.as_deref(), first_message.as_deref());
+
+    DiscoveredSession {
+        id: session_id,
+        working_directory,
+        transcript_path: file_path.to_string_lossy().to_string(),
+        modified_at,
+        modified_at_ms,
+        first_message,
+        git_branch,
+        title,
+        custom_title: None,
+        pinned: false,
+        tags: Vec::new(),
+        expires_in_days: session_expiry::expires_in_days(modified_at_ms, cleanup_period_days),
     }
+}
 
-    let mut sessions = Vec::new();
+/// Listing entry for `path` if it is a top-level session transcript
+/// (`<projects>/<project>/<id>.jsonl`) that still exists
+pub(crate) fn session_for_transcript(
+    projects_dir: &Path,
+    path: &Path,
+    cleanup_period_days: i64,
+) -> Option<DiscoveredSession> {
+    if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+        return None;
+    }
+    let project_dir = path.parent()?;
+    if project_dir.parent()? != projects_dir || !path.is_file() {
+        return None;
+    }
+    let dir_name = project_dir.file_name()?.to_string_lossy().to_string();
+    let mut session = session_from_file(path, decode_dir_name(&dir_name), cleanup_period_days);
+    match store::get(&session.id) {
+        Ok(meta) => session.apply_meta(meta),
+        Err(e) => debug_log!("SESSIONS", "No metadata for {}: {}", session.id, e),
+    }
+    Some(session)
+}
 
+/// Walk the projects dir, sending each discovered session as it is found
+fn scan_sessions(projects_dir: &Path, tx: &mpsc::Sender<Result<DiscoveredSession, String>>) {
+    let cleanup_period_days = session_expiry::cleanup_period_days();
     // Iterate through project directories
-    let entries = fs::read_dir(&projects_dir)
-        .map_err(|e| format!("Failed to read projects directory: {}", e))?;
+    let entries = match fs::read_dir(projects_dir) {
+        Ok(e) => e,
+        Err(e) => {
+            let _ = tx.send(Err(format!("Failed to read projects directory: {}", e)));
+            return;
+        }
+    };
 
     for entry in entries.flatten() {
         let project_path = entry.path();
@@ -103,73 +291,190 @@ pub fn list_claude_sessions() -> Result<Vec<DiscoveredSession>, String> {
 
                 // Only process .jsonl files at the top level (not subagents)
                 if file_path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
-                    let session_id = file_path
-                        .file_stem()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("unknown")
-                        .to_string();
-
-                    // Get modification time
-                    let modified_at = file.metadata()
-                        .and_then(|m| m.modified())
-                        .map(|t| {
-                            let datetime: chrono::DateTime<chrono::Local> = t.into();
-                            datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-                        })
-                        .unwrap_or_else(|_| "unknown".to_string());
-
-                    // Extract first message for display
-                    let first_message = extract_first_message(&file_path);
-
-                    sessions.push(DiscoveredSession {
-                        id: session_id,
-                        working_directory: working_directory.clone(),
-                        transcript_path: file_path.to_string_lossy().to_string(),
-                        modified_at,
-                        first_message,
-                    });
+                    let session = session_from_file(&file_path, working_directory.clone(), cleanup_period_days);
+                    if tx.send(Ok(session)).is_err() {
+                        // Caller gave up waiting
+                        return;
+                    }
                 }
             }
         }
     }
+}
 
-    // Sort by modification time (newest first)
-    sessions.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+/// Discover sessions, returning whatever was found within `timeout`.
+/// Slow storage yields a partial listing with `slow_storage` set.
+pub(crate) fn discover_sessions(timeout: Duration) -> Result<SessionListing, String> {
+    let projects_dir = claude_projects_dir();
+    debug_log!("SESSIONS", "Listing Claude sessions from {:?}", projects_dir);
 
-    debug_log!("SESSIONS", "Found {} sessions", sessions.len());
-    Ok(sessions)
+    let exists_dir = projects_dir.clone();
+    match slow_io::with_timeout(timeout, move || exists_dir.exists()) {
+        Some(true) => {}
+        Some(false) => {
+            debug_log!("SESSIONS", "Projects directory does not exist: {:?}", projects_dir);
+            return Ok(SessionListing::default());
+        }
+        None => {
+            debug_log!("SESSIONS", "Timed out checking projects directory: {:?}", projects_dir);
+            return Ok(SessionListing { sessions: vec![], slow_storage: true });
+        }
+    }
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || scan_sessions(&projects_dir, &tx));
+
+    let deadline = Instant::now() + timeout;
+    let mut listing = SessionListing::default();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(Ok(session)) => listing.sessions.push(session),
+            Ok(Err(e)) => return Err(e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                debug_log!("SESSIONS", "Listing timed out after {} sessions (slow storage)", listing.sessions.len());
+                listing.slow_storage = true;
+                break;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let mut meta = store::all();
+    for session in &mut listing.sessions {
+        if let Some(m) = meta.remove(&session.id) {
+            session.apply_meta(m);
+        }
+    }
+
+    // Newest first; callers can re-sort with `sort_sessions`
+    sort_sessions(&mut listing.sessions, SessionSort::Modified);
+
+    debug_log!("SESSIONS", "Found {} sessions", listing.sessions.len());
+    Ok(listing)
 }
 
-/// List sessions for a specific working directory
+/// List all sessions from Claude's transcript directory (newest first
+/// unless `sort` says otherwise), optionally only those on `branch`
 #[tauri::command]
-pub fn list_sessions_for_directory(working_directory: String) -> Result<Vec<DiscoveredSession>, String> {
+pub async fn list_claude_sessions(
+    sort: Option<SessionSort>,
+    branch: Option<String>,
+) -> Result<SessionListing, String> {
+    let mut listing = tauri::async_runtime::spawn_blocking(|| discover_sessions(LIST_TIMEOUT))
+        .await
+        .map_err(|e| format!("Session listing task failed: {}", e))??;
+    if let Some(branch) = branch {
+        listing.sessions.retain(|s| s.git_branch.as_deref() == Some(branch.as_str()));
+    }
+    let sort = sort.unwrap_or_default();
+    if sort != SessionSort::Modified {
+        sort_sessions(&mut listing.sessions, sort);
+    }
+    Ok(listing)
+}
+
+/// List sessions for a specific working directory, including sessions
+/// started elsewhere that were given it as an additional directory
+#[tauri::command]
+pub async fn list_sessions_for_directory(
+    state: State<'_, ClaudeState>,
+    working_directory: String,
+    sort: Option<SessionSort>,
+    branch: Option<String>,
+) -> Result<SessionListing, String> {
     debug_log!("SESSIONS", "Listing sessions for: {}", working_directory);
 
-    let all_sessions = list_claude_sessions()?;
-    let filtered: Vec<_> = all_sessions
-        .into_iter()
-        .filter(|s| s.working_directory == working_directory)
-        .collect();
+    let added_to = state.lock().claude_sessions_with_add_dir(&working_directory);
+    let mut listing = list_claude_sessions(sort, branch).await?;
+    listing
+        .sessions
+        .retain(|s| s.working_directory == working_directory || added_to.contains(&s.id));
 
-    debug_log!("SESSIONS", "Found {} sessions for {}", filtered.len(), working_directory);
-    Ok(filtered)
+    debug_log!("SESSIONS", "Found {} sessions for {}", listing.sessions.len(), working_directory);
+    Ok(listing)
 }
 
 /// Read transcript content for a session
 #[tauri::command]
-pub fn read_session_transcript(transcript_path: String) -> Result<String, String> {
+pub async fn read_session_transcript(transcript_path: String) -> Result<String, String> {
     debug_log!("SESSIONS", "Reading transcript: {}", transcript_path);
 
-    fs::read_to_string(&transcript_path)
-        .map_err(|e| format!("Failed to read transcript: {}", e))
+    let path = transcript_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        slow_io::with_timeout(PARSE_TIMEOUT, move || fs::read_to_string(&path))
+            .ok_or_else(|| format!("Timed out reading transcript (slow storage): {}", transcript_path))?
+            .map_err(|e| format!("Failed to read transcript: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Transcript read task failed: {}", e))?
 }
 
-/// Parse transcript content for a session (including subagent transcripts)
+/// Parse transcript content for a session (including subagent transcripts).
+/// On slow storage, returns an empty result with `slowStorage` set.
 #[tauri::command]
-pub fn parse_session_transcript(transcript_path: String) -> Result<TranscriptParseResult, String> {
+pub async fn parse_session_transcript(transcript_path: String) -> Result<ParsedTranscript, String> {
     debug_log!("SESSIONS", "Parsing transcript with subagents: {}", transcript_path);
 
-    Ok(parse_transcript_with_subagents(Path::new(&transcript_path)))
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(&transcript_path);
+        slow_io::with_timeout(PARSE_TIMEOUT, move || parse_cache::parse(&path))
+            .unwrap_or_else(|| {
+                debug_log!("SESSIONS", "Timed out parsing {} (slow storage)", transcript_path);
+                ParsedTranscript {
+                    result: Arc::new(TranscriptParseResult {
+                        slow_storage: true,
+                        ..Default::default()
+                    }),
+                    annotations: vec![],
+                }
+            })
+    })
+    .await
+    .map_err(|e| format!("Transcript parse task failed: {}", e))
+}
+
+/// One page of a transcript, starting at byte `cursor` (default 0) and
+/// holding about `limit` messages (default 200, at most 2000). Keep passing
+/// `nextCursor` back until it is null.
+#[tauri::command]
+pub async fn parse_session_transcript_page(
+    transcript_path: String,
+    cursor: Option<u64>,
+    limit: Option<usize>,
+) -> Result<TranscriptPage, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(&transcript_path);
+        let cursor = cursor.unwrap_or(0);
+        let limit = limit.unwrap_or(transcript_page::DEFAULT_PAGE_MESSAGES);
+        slow_io::with_timeout(PARSE_TIMEOUT, move || transcript_page::parse_page(&path, cursor, limit))
+            .ok_or_else(|| format!("Timed out parsing transcript (slow storage): {}", transcript_path))?
+    })
+    .await
+    .map_err(|e| format!("Transcript parse task failed: {}", e))?
+}
+
+/// Start/end spans of every tool call in a transcript, subagent tools nested
+/// under their Task, for a timeline of where a turn spent its time
+#[tauri::command]
+pub async fn get_tool_timeline(transcript_path: String) -> Result<ToolTimeline, String> {
+    debug_log!("SESSIONS", "Building tool timeline for: {}", transcript_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(&transcript_path);
+        slow_io::with_timeout(PARSE_TIMEOUT, move || tool_timeline::build(&parse_cache::parse(&path).result))
+            .ok_or_else(|| format!("Timed out parsing transcript (slow storage): {}", transcript_path))
+    })
+    .await
+    .map_err(|e| format!("Tool timeline task failed: {}", e))?
+}
+
+/// Read the raw stream-json stdout Horseman captured for a session
+/// (all turns, appended in order)
+#[tauri::command]
+pub fn get_raw_session_log(ui_session_id: String) -> Result<String, String> {
+    debug_log!("SESSIONS", "Reading raw log for: {}", ui_session_id);
+
+    raw_log::read(&ui_session_id)
 }
 
 /// Extract the compaction summary from a transcript (if present)
@@ -218,3 +523,402 @@ pub fn get_transcript_path(working_directory: String, session_id: String) -> Res
 
     Ok(transcript_path.to_string_lossy().to_string())
 }
+
+/// Get cached metadata for a session (rebuilt if stale)
+#[tauri::command]
+pub async fn get_session_metadata(session_id: String, transcript_path: String) -> Result<SessionMetadata, String> {
+    tauri::async_runtime::spawn_blocking(move || session_cache::get(&session_id, Path::new(&transcript_path)))
+        .await
+        .map_err(|e| format!("Session metadata task failed: {}", e))?
+}
+
+/// Re-parse every stored transcript with the current parser and regenerate
+/// cached metadata, reporting which sessions changed
+#[tauri::command]
+pub async fn reparse_all_sessions() -> Result<ReparseReport, String> {
+    tauri::async_runtime::spawn_blocking(reparse_all)
+        .await
+        .map_err(|e| format!("Re-parse task failed: {}", e))?
+}
+
+fn reparse_all() -> Result<ReparseReport, String> {
+    debug_log!("SESSIONS", "Re-parsing all sessions (parser v{})", PARSER_VERSION);
+
+    let mut report = ReparseReport {
+        parser_version: PARSER_VERSION,
+        ..Default::default()
+    };
+
+    let listing = discover_sessions(REPARSE_LIST_TIMEOUT)?;
+    if listing.slow_storage {
+        report.errors.push("Storage too slow to list every session; results are partial".to_string());
+    }
+
+    for session in listing.sessions {
+        report.scanned += 1;
+        match session_cache::refresh(&session.id, Path::new(&session.transcript_path)) {
+            Ok((_, CacheUpdate::Created)) => report.created += 1,
+            Ok((_, CacheUpdate::Updated)) => {
+                report.updated += 1;
+                report.changed_sessions.push(session.id);
+            }
+            Ok((_, CacheUpdate::Unchanged)) => report.unchanged += 1,
+            Err(e) => report.errors.push(format!("{}: {}", session.id, e)),
+        }
+    }
+
+    debug_log!(
+        "SESSIONS",
+        "Re-parse done: {} scanned, {} created, {} updated, {} unchanged, {} errors",
+        report.scanned,
+        report.created,
+        report.updated,
+        report.unchanged,
+        report.errors.len()
+    );
+    Ok(report)
+}
+
+#[derive(Deserialize)]
+pub struct AddAnnotationArgs {
+    pub claude_session_id: String,
+    pub kind: AnnotationKind,
+    pub message_id: Option<String>,
+    pub text: Option<String>,
+    pub data: Option<serde_json::Value>,
+}
+
+/// Record a UI-originated annotation (note, pin, ...) in the session's
+/// sidecar file. Claude's transcript is never modified.
+#[tauri::command]
+pub fn add_session_annotation(args: AddAnnotationArgs) -> Result<Annotation, String> {
+    debug_log!("SESSIONS", "Adding {:?} annotation to {}", args.kind, args.claude_session_id);
+    let mut annotation = Annotation::new(args.kind);
+    annotation.message_id = args.message_id;
+    annotation.text = args.text;
+    annotation.data = args.data;
+    annotations::append(&args.claude_session_id, &annotation)?;
+    Ok(annotation)
+}
+
+/// All sidecar annotations for a session, oldest first
+#[tauri::command]
+pub fn list_session_annotations(claude_session_id: String) -> Vec<Annotation> {
+    annotations::load(&claude_session_id)
+}
+
+/// Delete an annotation (e.g. unpin). Returns false if it did not exist.
+#[tauri::command]
+pub fn remove_session_annotation(claude_session_id: String, annotation_id: String) -> Result<bool, String> {
+    debug_log!("SESSIONS", "Removing annotation {} from {}", annotation_id, claude_session_id);
+    annotations::remove(&claude_session_id, &annotation_id)
+}
+
+/// Transcript backups taken before `/clear` and `/compact`, newest first
+#[tauri::command]
+pub fn list_transcript_backups(claude_session_id: String) -> Vec<TranscriptBackup> {
+    transcript_backup::list(&claude_session_id)
+}
+
+/// Put a backup's transcripts back in place. Refuses while a process is
+/// running the session. Returns the backup of what was replaced, if any.
+#[tauri::command]
+pub async fn restore_transcript_backup(
+    state: State<'_, ClaudeState>,
+    claude_session_id: String,
+    backup_id: String,
+) -> Result<Option<TranscriptBackup>, String> {
+    debug_log!("SESSIONS", "Restoring backup {} of {}", backup_id, claude_session_id);
+    if let Some(ui_session_id) = state.lock().running_session_for(&claude_session_id) {
+        return Err(format!("Session is still running in {}; stop it first", ui_session_id));
+    }
+    tauri::async_runtime::spawn_blocking(move || transcript_backup::restore(&claude_session_id, &backup_id))
+        .await
+        .map_err(|e| format!("Restore task failed: {}", e))?
+}
+
+/// Titles, pin and tags the user set on a session
+#[tauri::command]
+pub fn get_session_meta(claude_session_id: String) -> Result<SessionMeta, String> {
+    store::get(&claude_session_id)
+}
+
+/// Rename a session; None or a blank title restores the default
+#[tauri::command]
+pub fn set_session_title(claude_session_id: String, title: Option<String>) -> Result<SessionMeta, String> {
+    debug_log!("SESSIONS", "Setting title of {}: {:?}", claude_session_id, title);
+    store::set_title(&claude_session_id, title)
+}
+
+#[tauri::command]
+pub fn set_session_pinned(claude_session_id: String, pinned: bool) -> Result<SessionMeta, String> {
+    debug_log!("SESSIONS", "Setting pinned of {}: {}", claude_session_id, pinned);
+    store::set_pinned(&claude_session_id, pinned)
+}
+
+/// Replace a session's tags
+#[tauri::command]
+pub fn set_session_tags(claude_session_id: String, tags: Vec<String>) -> Result<SessionMeta, String> {
+    debug_log!("SESSIONS", "Setting tags of {}: {:?}", claude_session_id, tags);
+    store::set_tags(&claude_session_id, &tags)
+}
+
+/// Prompts previously sent in `project` (a working directory), newest
+/// first, or ranked by fuzzy match when `query` is given
+#[tauri::command]
+pub fn get_prompt_history(
+    project: String,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<PromptEntry>, String> {
+    prompt_history::recall(&project, query.as_deref(), limit.unwrap_or(prompt_history::DEFAULT_LIMIT))
+}
+
+/// Forget the prompts sent in `project`, or in every project when None.
+/// Returns how many were removed.
+#[tauri::command]
+pub fn clear_prompt_history(project: Option<String>) -> Result<usize, String> {
+    debug_log!("SESSIONS", "Clearing prompt history of {:?}", project);
+    prompt_history::clear(project.as_deref())
+}
+
+/// Forget everything the user set on a session
+#[tauri::command]
+pub fn clear_session_meta(claude_session_id: String) -> Result<bool, String> {
+    store::delete(&claude_session_id)
+}
+
+/// Delete a session's transcript and subagent transcripts, and everything
+/// Horseman stored about it (raw logs and transcript backups included).
+/// Refuses while a process is running it.
+#[tauri::command]
+pub fn delete_claude_session(
+    app: AppHandle,
+    state: State<'_, ClaudeState>,
+    claude_session_id: String,
+) -> Result<Vec<String>, String> {
+    debug_log!("SESSIONS", "Deleting session {}", claude_session_id);
+    if claude_session_id.is_empty() || !claude_session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid session ID: {}", claude_session_id));
+    }
+    if let Some(ui_session_id) = state.lock().running_session_for(&claude_session_id) {
+        return Err(format!("Session is still running in {}; stop it first", ui_session_id));
+    }
+    let transcript_path = resume_recovery::find_transcript(&claude_session_id)
+        .ok_or_else(|| format!("No transcript found for session {}", claude_session_id))?;
+
+    let mut removed = Vec::new();
+    for path in resume_recovery::subagent_transcripts(&transcript_path, &claude_session_id) {
+        let result = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        match result {
+            Ok(()) => removed.push(path.to_string_lossy().to_string()),
+            Err(e) => debug_log!("SESSIONS", "Failed to remove {:?}: {}", path, e),
+        }
+    }
+    fs::remove_file(&transcript_path).map_err(|e| format!("Failed to delete transcript: {}", e))?;
+    removed.push(transcript_path.to_string_lossy().to_string());
+
+    let raw_logs = raw_log::remove_for_claude_session(&claude_session_id);
+    removed.extend(raw_logs.iter().map(|p| p.to_string_lossy().to_string()));
+    match transcript_backup::remove_all(&claude_session_id) {
+        Ok(Some(dir)) => removed.push(dir.to_string_lossy().to_string()),
+        Ok(None) => {}
+        Err(e) => debug_log!("SESSIONS", "Failed to remove backups of {}: {}", claude_session_id, e),
+    }
+    for cleanup in [
+        store::delete(&claude_session_id).map(|_| ()),
+        annotations::remove_all(&claude_session_id).map(|_| ()),
+        session_cache::forget(&claude_session_id),
+    ] {
+        if let Err(e) = cleanup {
+            debug_log!("SESSIONS", "Cleanup for {} failed: {}", claude_session_id, e);
+        }
+    }
+    open_sessions::forget_claude_session(&claude_session_id);
+
+    debug_log!("SESSIONS", "Deleted {} ({} files)", claude_session_id, removed.len());
+    let _ = events::emit(
+        &app,
+        BackendEvent::SessionDeleted {
+            claude_session_id,
+            removed_files: removed.clone(),
+        },
+    );
+    Ok(removed)
+}
+
+/// Error for commands whose subsystem was left out of this build
+#[cfg(not(all(feature = "search", feature = "analytics")))]
+fn not_built(feature: &str) -> String {
+    format!("This build was compiled without the `{}` feature", feature)
+}
+
+/// Active time per project and day for `from..=to` (YYYY-MM-DD, default
+/// today), optionally limited to one project
+#[cfg(feature = "analytics")]
+#[tauri::command]
+pub fn get_time_report(
+    from: Option<String>,
+    to: Option<String>,
+    project: Option<String>,
+) -> Result<TimeReport, String> {
+    time_tracking::report(from, to, project.as_deref())
+}
+
+/// Spend per day, week or project (`group_by`, default day) for
+/// `from..=to` (YYYY-MM-DD, default today), optionally for one project
+#[cfg(feature = "analytics")]
+#[tauri::command]
+pub fn get_cost_report(
+    from: Option<String>,
+    to: Option<String>,
+    group_by: Option<CostGrouping>,
+    project: Option<String>,
+) -> Result<CostReport, String> {
+    cost_ledger::report(from, to, group_by.unwrap_or(CostGrouping::Day), project.as_deref())
+}
+
+/// Standup report of sessions from the last day or week, grouped by project.
+/// With `polish`, a background AI job rewrites it. The Markdown can also be
+/// written to `export_path` and/or copied to the clipboard.
+#[cfg(feature = "analytics")]
+#[tauri::command]
+pub async fn generate_activity_report(
+    app: AppHandle,
+    period: ReportPeriod,
+    polish: Option<bool>,
+    export_path: Option<String>,
+    copy_to_clipboard: Option<bool>,
+) -> Result<ActivityReport, String> {
+    let polish = polish.unwrap_or(false);
+    let report = tauri::async_runtime::spawn_blocking(move || activity_report::generate(period, polish))
+        .await
+        .map_err(|e| format!("Report task failed: {}", e))??;
+    if let Some(path) = export_path {
+        atomic_file::write(Path::new(&path), &report.markdown)
+            .map_err(|e| format!("Failed to write report: {}", e))?;
+    }
+    if copy_to_clipboard.unwrap_or(false) {
+        app.clipboard()
+            .write_text(report.markdown.clone())
+            .map_err(|e| format!("Failed to copy report: {}", e))?;
+    }
+    Ok(report)
+}
+
+#[cfg(not(feature = "analytics"))]
+#[tauri::command]
+pub fn get_time_report(
+    from: Option<String>,
+    to: Option<String>,
+    project: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let _ = (from, to, project);
+    Err(not_built("analytics"))
+}
+
+#[cfg(not(feature = "analytics"))]
+#[tauri::command]
+pub fn get_cost_report(
+    from: Option<String>,
+    to: Option<String>,
+    group_by: Option<serde_json::Value>,
+    project: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let _ = (from, to, group_by, project);
+    Err(not_built("analytics"))
+}
+
+#[cfg(not(feature = "analytics"))]
+#[tauri::command]
+pub fn generate_activity_report(
+    period: serde_json::Value,
+    polish: Option<bool>,
+    export_path: Option<String>,
+    copy_to_clipboard: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let _ = (period, polish, export_path, copy_to_clipboard);
+    Err(not_built("analytics"))
+}
+
+/// End-of-session summary of what changed and why, if one was written
+#[tauri::command]
+pub fn get_session_summary(claude_session_id: String) -> Result<Option<SessionSummary>, String> {
+    store::summary(&claude_session_id)
+}
+
+/// Everything the session changed in its repository since Horseman first
+/// ran it, grouped by file
+#[tauri::command]
+pub async fn get_session_workspace_diff(claude_session_id: String) -> Result<WorkspaceDiff, String> {
+    debug_log!("SESSIONS", "Workspace diff for {}", claude_session_id);
+    tauri::async_runtime::spawn_blocking(move || {
+        let snapshot = session_cache::workspace_snapshot(&claude_session_id).ok_or_else(|| {
+            format!(
+                "No workspace snapshot for session {}; it wasn't started by Horseman in a git repository",
+                claude_session_id
+            )
+        })?;
+        workspace_diff::diff(&claude_session_id, snapshot)
+    })
+    .await
+    .map_err(|e| format!("Workspace diff task failed: {}", e))?
+}
+
+/// Commit message for the uncommitted changes in `working_directory`, from
+/// the session summary written for exactly those changes
+#[tauri::command]
+pub async fn get_suggested_commit_message(working_directory: String) -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        session_summary::suggested_commit_message(&working_directory)
+    })
+    .await
+    .map_err(|e| format!("Commit message task failed: {}", e))
+}
+
+/// Follow a transcript that something else (e.g. the CLI in a terminal) is
+/// writing. Returns the transcript parsed so far; new and changed messages
+/// arrive as `transcript.appended` until `untail_transcript`.
+#[tauri::command]
+pub async fn tail_transcript(app: AppHandle, transcript_path: String) -> Result<TranscriptParseResult, String> {
+    debug_log!("SESSIONS", "Tailing transcript: {}", transcript_path);
+    tauri::async_runtime::spawn_blocking(move || transcript_tail::start(&app, Path::new(&transcript_path)))
+        .await
+        .map_err(|e| format!("Transcript tail task failed: {}", e))?
+}
+
+/// Stop following a transcript started with `tail_transcript`
+#[tauri::command]
+pub fn untail_transcript(transcript_path: String) {
+    transcript_tail::stop(Path::new(&transcript_path));
+}
+
+/// Full-text search over every session transcript (subagents included),
+/// optionally limited to one project. Matches are wrapped in `[` `]`.
+#[cfg(feature = "search")]
+#[tauri::command]
+pub async fn search_transcripts(
+    query: String,
+    working_directory: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    if !config::search_enabled() {
+        return Err("Transcript search is turned off (searchEnabled)".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        search_index::search(&query, working_directory.as_deref(), limit)
+    })
+    .await
+    .map_err(|e| format!("Search task failed: {}", e))?
+}
+
+#[cfg(not(feature = "search"))]
+#[tauri::command]
+pub fn search_transcripts(
+    query: String,
+    working_directory: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let _ = (query, working_directory, limit);
+    Err(not_built("search"))
+}
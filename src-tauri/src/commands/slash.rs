@@ -1,3 +1,4 @@
+use crate::claude::transcript_backup;
 use crate::debug_log;
 use crate::slash::SlashState;
 use serde::{Deserialize, Serialize};
@@ -27,10 +28,9 @@ pub fn run_slash_command(
     debug_log!("CMD", "  working_directory: {}", args.working_directory);
     debug_log!("CMD", "  slash_command: {}", args.slash_command);
 
-    let mut manager = state.0.lock().map_err(|e| {
-        debug_log!("CMD", "  ERROR: Failed to lock SlashManager: {}", e);
-        e.to_string()
-    })?;
+    // Before taking the lock: copying the transcripts can take a while
+    transcript_backup::before_command(&args.claude_session_id, &args.slash_command);
+    let mut manager = state.lock();
 
     let command_id = manager.run_command(
         &app,
@@ -52,10 +52,7 @@ pub fn cancel_slash_command(
     debug_log!("CMD", "cancel_slash_command called");
     debug_log!("CMD", "  command_id: {}", command_id);
 
-    let mut manager = state.0.lock().map_err(|e| {
-        debug_log!("CMD", "  ERROR: Failed to lock SlashManager: {}", e);
-        e.to_string()
-    })?;
+    let mut manager = state.lock();
 
     manager.cancel(&command_id)?;
 
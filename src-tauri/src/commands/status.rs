@@ -27,7 +27,7 @@ pub struct MemoryFile {
 }
 
 /// Get Claude version from CLI
-fn get_claude_version() -> Option<String> {
+pub(crate) fn get_claude_version() -> Option<String> {
     let claude = resolve_claude_binary();
     match Command::new(&claude).arg("--version").output() {
         Ok(output) => {
@@ -0,0 +1,51 @@
+use crate::atomic_file;
+use crate::debug_log;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+const STATE_FILE: &str = "state.json";
+
+fn app_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Could not determine app data directory: {}", e))
+}
+
+/// Path of the persisted UI state. Older builds joined the app data dir and
+/// file name without a separator, so that sibling file is migrated once.
+fn ui_state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app_data_dir(app)?;
+    let path = dir.join(STATE_FILE);
+    let legacy = PathBuf::from(format!("{}{}", dir.to_string_lossy(), STATE_FILE));
+    if !path.exists() && legacy.is_file() {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        match fs::rename(&legacy, &path) {
+            Ok(()) => debug_log!("STORAGE", "Migrated UI state from {:?}", legacy),
+            Err(e) => debug_log!("STORAGE", "Failed to migrate UI state from {:?}: {}", legacy, e),
+        }
+    }
+    Ok(path)
+}
+
+/// Read the persisted UI state (None if nothing saved yet)
+#[tauri::command]
+pub fn load_ui_state(app: AppHandle) -> Result<Option<String>, String> {
+    let path = ui_state_path(&app)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(&path)
+        .map(Some)
+        .map_err(|e| format!("Failed to read UI state: {}", e))
+}
+
+/// Persist the UI state
+#[tauri::command]
+pub fn save_ui_state(app: AppHandle, content: String) -> Result<(), String> {
+    let path = ui_state_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    atomic_file::write(&path, content).map_err(|e| format!("Failed to write UI state: {}", e))
+}
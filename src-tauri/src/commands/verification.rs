@@ -0,0 +1,126 @@
+use super::claude::{send_claude_message, ClaudeState, SpawnSessionResult};
+use crate::claude::SessionOptions;
+use crate::debug_log;
+use crate::verification::{self, fix_loop};
+use serde::Deserialize;
+use tauri::{AppHandle, State};
+
+/// Run the project's verification commands now (outside the post-turn trigger).
+/// Returns false if no commands are configured for the directory.
+#[tauri::command]
+pub fn run_verification(
+    app: AppHandle,
+    ui_session_id: String,
+    working_directory: String,
+) -> Result<bool, String> {
+    debug_log!("CMD", "run_verification called");
+    debug_log!("CMD", "  ui_session_id: {}", ui_session_id);
+    debug_log!("CMD", "  working_directory: {}", working_directory);
+
+    Ok(verification::spawn_verification(&app, &ui_session_id, &working_directory))
+}
+
+/// Preview the "fix these failures" prompt built from the last verification run
+#[tauri::command]
+pub fn get_verification_fix_prompt(ui_session_id: String) -> Option<String> {
+    verification::last_results(&ui_session_id)
+        .and_then(|steps| verification::format_failure_prompt(&steps))
+}
+
+/// Send the last verification failures to Claude as a follow-up message
+#[tauri::command]
+pub fn send_verification_fix(
+    app: AppHandle,
+    state: State<ClaudeState>,
+    ui_session_id: String,
+    claude_session_id: String,
+    working_directory: String,
+    model: Option<String>,
+) -> Result<SpawnSessionResult, String> {
+    debug_log!("CMD", "send_verification_fix called for {}", ui_session_id);
+
+    let steps = verification::last_results(&ui_session_id)
+        .ok_or_else(|| format!("No verification results for session {}", ui_session_id))?;
+    let prompt = verification::format_failure_prompt(&steps)
+        .ok_or("Last verification passed, nothing to fix")?;
+
+    send_claude_message(app, state, ui_session_id, claude_session_id, working_directory, prompt, model)
+}
+
+#[derive(Deserialize)]
+pub struct StartFixLoopArgs {
+    pub ui_session_id: String,
+    pub working_directory: String,
+    pub prompt: String,
+    /// Resume this Claude session; None starts a new one
+    pub claude_session_id: Option<String>,
+    pub model: Option<String>,
+    pub max_iterations: Option<u32>,
+    pub max_cost_usd: Option<f64>,
+}
+
+/// Start a fix-and-retry loop: send `prompt`, then after each turn that edits
+/// files run verification and feed failures back until it passes or the
+/// iteration/cost budget is used up. Progress is reported via `loop.iteration`.
+#[tauri::command]
+pub fn start_fix_loop(
+    app: AppHandle,
+    state: State<ClaudeState>,
+    args: StartFixLoopArgs,
+) -> Result<SpawnSessionResult, String> {
+    debug_log!("CMD", "start_fix_loop called");
+    debug_log!("CMD", "  ui_session_id: {}", args.ui_session_id);
+    debug_log!("CMD", "  max_iterations: {:?}", args.max_iterations);
+    debug_log!("CMD", "  max_cost_usd: {:?}", args.max_cost_usd);
+
+    let limits = fix_loop::LoopLimits {
+        max_iterations: args.max_iterations.unwrap_or(fix_loop::DEFAULT_MAX_ITERATIONS),
+        max_cost_usd: args.max_cost_usd,
+    };
+    fix_loop::start(
+        &app,
+        &args.ui_session_id,
+        &args.working_directory,
+        args.model.clone(),
+        args.claude_session_id.clone(),
+        limits,
+    )?;
+
+    let mut manager = state.lock();
+    let result = match args.claude_session_id {
+        Some(claude_session_id) => manager.send_message(
+            &app,
+            &args.ui_session_id,
+            args.working_directory,
+            args.prompt,
+            claude_session_id,
+            args.model,
+        ),
+        None => manager
+            .spawn_session(
+                &app,
+                args.ui_session_id.clone(),
+                args.working_directory,
+                Some(args.prompt),
+                None,
+                SessionOptions {
+                    model: args.model,
+                    ..Default::default()
+                },
+            )
+            .map(|_| ()),
+    };
+    if let Err(e) = result {
+        fix_loop::stop(&app, &args.ui_session_id, "error");
+        return Err(e);
+    }
+
+    Ok(SpawnSessionResult { session_id: args.ui_session_id, queued: false })
+}
+
+/// Stop a running fix loop (the current turn keeps running)
+#[tauri::command]
+pub fn stop_fix_loop(app: AppHandle, ui_session_id: String) -> bool {
+    debug_log!("CMD", "stop_fix_loop called for {}", ui_session_id);
+    fix_loop::stop(&app, &ui_session_id, "stopped")
+}
@@ -1,13 +1,33 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use once_cell::sync::Lazy;
+use crate::atomic_file;
 use crate::debug_log;
+use crate::hooks::mcp_servers::McpServerConfig;
+use crate::hooks::permission_rules::PermissionRules;
+use crate::hooks::types::PermissionTimeoutAction;
+use crate::providers::ProviderProfile;
+use crate::claude::suggestions::QuickReplies;
+use crate::redaction::RedactionConfig;
+use crate::todo_sync::TodoSyncTarget;
 
 /// Cached resolved claude binary path
 static RESOLVED_CLAUDE_BINARY: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
 
+const DEFAULT_MAX_STDOUT_LINE_BYTES: usize = 16 * 1024 * 1024;
+const DEFAULT_TRANSCRIPT_MAX_TOOL_OUTPUT_BYTES: usize = 1024 * 1024;
+const DEFAULT_TRANSCRIPT_BACKUPS_MAX_BYTES: u64 = 1024 * 1024 * 1024;
+const DEFAULT_PROMPT_HISTORY_DAYS: u32 = 90;
+const DEFAULT_TODO_SYNC_FILE: &str = "TODO.md";
+/// Just under the 180s Claude gives an MCP tool call
+pub const DEFAULT_PERMISSION_TIMEOUT_SECS: u64 = 170;
+const MIN_PERMISSION_TIMEOUT_SECS: u64 = 10;
+
 /// User-configurable settings for Horseman
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default, rename_all = "camelCase")]
@@ -20,80 +40,348 @@ pub struct HorsemanConfig {
     pub debug_log_path: Option<PathBuf>,
     /// Context window size fallback (default: 200000)
     pub context_window: Option<usize>,
+    /// Commands to run after a turn that edited files, keyed by project directory
+    /// e.g. "/Users/me/app" = ["cargo check", "cargo test"]
+    pub verification_commands: Option<HashMap<String, Vec<String>>>,
+    /// Interrupt sessions whose process has been idle this long (None = never)
+    pub idle_suspend_minutes: Option<u64>,
+    /// Default `--max-turns` for new sessions (None = unlimited)
+    pub max_turns: Option<u32>,
+    /// Claude processes allowed to run at once; further spawns wait in a
+    /// queue (None = unlimited)
+    pub max_concurrent_sessions: Option<u32>,
+    /// Offer to restart keep-alive sessions when CLAUDE.md or rules change (default: true)
+    pub prompt_restart_on_memory_change: Option<bool>,
+    /// Respawn a session with `--resume` this many times when its process
+    /// dies mid-turn (default: 0, never)
+    pub crash_max_retries: Option<u32>,
+    /// Delay before the first crash restart, doubled for each further attempt
+    pub crash_retry_backoff_ms: Option<u64>,
+    /// Bash commands auto-approved by prefix, e.g. "git status" (see
+    /// `hooks::bash_rules`)
+    pub bash_allow_prefixes: Option<Vec<String>>,
+    /// Tool rules in Claude's settings syntax, e.g. "WebSearch" or
+    /// "Bash(npm run:*)"; deny rules refuse without asking (see
+    /// `hooks::permission_rules`)
+    pub permission_rules: Option<PermissionRules>,
+    /// Rules keyed by project directory, added to `permissionRules` for
+    /// sessions there
+    pub project_permission_rules: Option<HashMap<String, PermissionRules>>,
+    /// Extra MCP servers for every session, merged into the generated
+    /// `--mcp-config` (see `hooks::mcp_servers`). `${VAR}` is left for
+    /// Claude to expand, so `.horseman-mcp.json` never holds its value.
+    pub mcp_servers: Option<HashMap<String, McpServerConfig>>,
+    /// Extra MCP servers keyed by project directory; override `mcpServers`
+    /// of the same name
+    pub project_mcp_servers: Option<HashMap<String, HashMap<String, McpServerConfig>>>,
+    /// Leave `.horseman-mcp.json` files in project directories after
+    /// sessions end, for debugging (default: false)
+    pub keep_mcp_configs: Option<bool>,
+    /// Longest stdout line parsed; longer ones are written to disk and
+    /// dropped from the stream (default: 16 MiB)
+    pub max_stdout_line_bytes: Option<usize>,
+    /// Continue a turn refused for rate limits or overload this many times
+    /// (default: 0, never)
+    pub rate_limit_max_retries: Option<u32>,
+    /// Delay before the first rate-limit retry, doubled for each further
+    /// attempt; a later reset time given by the API wins
+    pub rate_limit_retry_backoff_ms: Option<u64>,
+    /// Seconds a permission prompt or question waits for an answer
+    /// (default: 170; at least 10). Longer waits raise Claude's MCP tool
+    /// timeout to match.
+    pub permission_timeout_secs: Option<u64>,
+    /// What an unanswered permission prompt resolves to (default: deny)
+    pub permission_timeout_action: Option<PermissionTimeoutAction>,
+    /// Allow background Claude calls (titles, summaries, ...) (default: true)
+    pub background_ai_enabled: Option<bool>,
+    /// Daily spend cap for background Claude calls, in USD (default: 1.0)
+    pub background_ai_daily_budget_usd: Option<f64>,
+    /// Minimum gap between background Claude calls (default: 5)
+    pub background_ai_min_interval_secs: Option<u64>,
+    /// host:port probed to detect offline mode ("" disables detection)
+    pub connectivity_probe_host: Option<String>,
+    /// Named provider presets (Anthropic API, Bedrock, Vertex)
+    pub provider_profiles: Option<HashMap<String, ProviderProfile>>,
+    /// Profile used by sessions that don't pick one (None = CLI defaults)
+    pub active_provider: Option<String>,
+    /// Proxy for Claude processes and the updater, e.g. "http://proxy:8080"
+    pub https_proxy: Option<String>,
+    /// Comma-separated hosts that bypass `httpsProxy` (loopback always does)
+    pub no_proxy: Option<String>,
+    /// Extra PEM CA bundle to trust, e.g. a corporate TLS inspection root
+    pub ca_cert_path: Option<PathBuf>,
+    /// Mirror session todo lists to a Markdown file or GitHub issues (default: off)
+    pub todo_sync_target: Option<TodoSyncTarget>,
+    /// File the Markdown target writes, relative to the working directory
+    pub todo_sync_file: Option<String>,
+    /// Seconds between todo syncs (default: 60)
+    pub todo_sync_interval_secs: Option<u64>,
+    /// Summarize uncommitted changes when a session ends (default: false)
+    pub session_summary_enabled: Option<bool>,
+    /// Also send a plain-sentence `narration` event for each significant
+    /// event, for screen readers (default: false)
+    pub narration_enabled: Option<bool>,
+    /// Context usage percents at which `usage.threshold` is sent
+    /// (default: 70, 90, 95; empty = never)
+    pub context_warning_levels: Option<Vec<u32>>,
+    /// Transcript backups kept per session, taken before `/clear` and
+    /// `/compact` (default: 5; 0 = no backups)
+    pub transcript_backups_kept: Option<usize>,
+    /// Size all transcript backups together may take; the oldest are
+    /// deleted past it (default: 1 GiB, 0 = no limit)
+    pub transcript_backups_max_bytes: Option<u64>,
+    /// Copy sessions into the transcript backups shortly before Claude's
+    /// `cleanupPeriodDays` deletes them (default: false)
+    pub archive_expiring_sessions: Option<bool>,
+    /// Send `/compact` between turns once context usage reaches this
+    /// percent (None = never)
+    pub auto_compact_percent: Option<u32>,
+    /// Suggested follow-ups after each turn: off, rules or polished (default: rules)
+    pub quick_replies: Option<QuickReplies>,
+    /// Cut tool outputs longer than this many bytes when parsing transcripts
+    /// (default: 1 MiB, 0 = keep them whole)
+    pub transcript_max_tool_output_bytes: Option<usize>,
+    /// Also keep parsed transcripts on disk so they survive restarts (default: false)
+    pub transcript_disk_cache: Option<bool>,
+    /// Poll watched files instead of using native file events: true always
+    /// polls, false never does (None = poll only where native events can't
+    /// work, e.g. network mounts)
+    pub poll_file_watchers: Option<bool>,
+    /// Secret masking in parsed transcripts and live tool output
+    pub redaction: Option<RedactionConfig>,
+    /// Diff command for `open_external_diff`, e.g. "meld" or "code --diff --wait".
+    /// The before and after files are appended, or substituted for
+    /// `{before}` and `{after}` when present.
+    pub external_diff_tool: Option<String>,
+    /// Keep the transcript search index warm (default: true). Needs the
+    /// `search` build feature.
+    pub search_enabled: Option<bool>,
+    /// Track active time per session (default: true). Needs the `analytics`
+    /// build feature.
+    pub analytics_enabled: Option<bool>,
+    /// Write a Chrome trace of hot-path spans to `<artifacts>/profiles/`
+    /// (default: false). Needs the `profiling` build feature.
+    pub profiling_enabled: Option<bool>,
+    /// Spend per day, in USD, before `budget.exceeded` is sent (None = no limit)
+    pub daily_budget_usd: Option<f64>,
+    /// Spend per calendar month, in USD (None = no limit)
+    pub monthly_budget_usd: Option<f64>,
+    /// Percent of a budget at which `budget.warning` is sent (default: 80)
+    pub budget_warning_percent: Option<u32>,
+    /// Refuse new sessions while over budget unless the spawn is confirmed
+    /// (default: false)
+    pub budget_confirm_spawn: Option<bool>,
+    /// Days sent prompts stay in the prompt history (default: 90, 0 = keep
+    /// them until cleared)
+    pub prompt_history_days: Option<u32>,
+}
+
+/// Config file values and what is derived from them, rebuilt only when the
+/// file values change
+struct ConfigState {
+    /// Values from config.toml, without env overrides
+    file: HorsemanConfig,
+    /// `file` with env overrides applied
+    resolved: Arc<HorsemanConfig>,
+    /// `resolved` with unset fields taken from `defaults()`
+    settings: Arc<HorsemanConfig>,
+}
+
+impl ConfigState {
+    fn new(file: HorsemanConfig) -> Self {
+        let resolved = apply_env_overrides(file.clone());
+        let settings = with_defaults(&resolved);
+        ConfigState {
+            file,
+            resolved: Arc::new(resolved),
+            settings: Arc::new(settings),
+        }
+    }
 }
 
 /// Global config state
-static CONFIG: Lazy<Mutex<HorsemanConfig>> = Lazy::new(|| {
-    Mutex::new(load_config_from_disk())
+static CONFIG: Lazy<Mutex<ConfigState>> = Lazy::new(|| {
+    Mutex::new(ConfigState::new(load_config_from_disk()))
 });
 
+/// Value of every setting that has one when it isn't configured. The only
+/// place defaults are written down; getters and `effective_config` read it.
+/// Defaults that depend on the machine (claude binary, projects dir) are
+/// resolved separately.
+fn defaults() -> HorsemanConfig {
+    HorsemanConfig {
+        debug_log_path: Some(PathBuf::from("horseman-debug.log")),
+        context_window: Some(200_000),
+        prompt_restart_on_memory_change: Some(true),
+        crash_max_retries: Some(0),
+        crash_retry_backoff_ms: Some(2000),
+        bash_allow_prefixes: Some(Vec::new()),
+        permission_rules: Some(PermissionRules::default()),
+        project_permission_rules: Some(HashMap::new()),
+        mcp_servers: Some(HashMap::new()),
+        project_mcp_servers: Some(HashMap::new()),
+        keep_mcp_configs: Some(false),
+        max_stdout_line_bytes: Some(DEFAULT_MAX_STDOUT_LINE_BYTES),
+        rate_limit_max_retries: Some(0),
+        rate_limit_retry_backoff_ms: Some(10_000),
+        permission_timeout_secs: Some(DEFAULT_PERMISSION_TIMEOUT_SECS),
+        permission_timeout_action: Some(PermissionTimeoutAction::default()),
+        background_ai_enabled: Some(true),
+        background_ai_daily_budget_usd: Some(1.0),
+        background_ai_min_interval_secs: Some(5),
+        connectivity_probe_host: Some("api.anthropic.com:443".to_string()),
+        todo_sync_file: Some(DEFAULT_TODO_SYNC_FILE.to_string()),
+        todo_sync_interval_secs: Some(60),
+        session_summary_enabled: Some(false),
+        narration_enabled: Some(false),
+        context_warning_levels: Some(vec![70, 90, 95]),
+        transcript_backups_kept: Some(5),
+        transcript_backups_max_bytes: Some(DEFAULT_TRANSCRIPT_BACKUPS_MAX_BYTES),
+        archive_expiring_sessions: Some(false),
+        quick_replies: Some(QuickReplies::Rules),
+        transcript_max_tool_output_bytes: Some(DEFAULT_TRANSCRIPT_MAX_TOOL_OUTPUT_BYTES),
+        transcript_disk_cache: Some(false),
+        search_enabled: Some(true),
+        analytics_enabled: Some(true),
+        profiling_enabled: Some(false),
+        budget_warning_percent: Some(80),
+        budget_confirm_spawn: Some(false),
+        prompt_history_days: Some(DEFAULT_PROMPT_HISTORY_DAYS),
+        ..HorsemanConfig::default()
+    }
+}
+
+/// `config` with every unset field taken from `defaults()`
+fn with_defaults(config: &HorsemanConfig) -> HorsemanConfig {
+    let (mut merged, configured) = match (serde_json::to_value(defaults()), serde_json::to_value(config)) {
+        (Ok(d), Ok(c)) => (d, c),
+        _ => return config.clone(),
+    };
+    if let (Some(merged), Some(configured)) = (merged.as_object_mut(), configured.as_object()) {
+        for (key, value) in configured {
+            if !value.is_null() {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    serde_json::from_value(merged).unwrap_or_else(|_| config.clone())
+}
+
+/// Incremented on every config change (only while holding the CONFIG lock),
+/// so writers can detect that someone else changed it in between
+static CONFIG_REVISION: AtomicU64 = AtomicU64::new(0);
+
+/// Config plus the revision it was read at
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSnapshot {
+    pub config: HorsemanConfig,
+    pub revision: u64,
+}
+
 /// Get the config directory path
-fn config_dir() -> Option<PathBuf> {
+pub fn config_dir() -> Option<PathBuf> {
     dirs::config_dir().map(|d| d.join("horseman"))
 }
 
+/// Get the directory for files Horseman generates (raw logs, exports, ...)
+pub fn artifacts_dir() -> Option<PathBuf> {
+    config_dir().map(|d| d.join("artifacts"))
+}
+
 /// Get the config file path
 fn config_path() -> Option<PathBuf> {
     config_dir().map(|d| d.join("config.toml"))
 }
 
-/// Load config from disk
+/// Load config from disk (file values only; env overrides are applied on read)
 fn load_config_from_disk() -> HorsemanConfig {
+    for o in env_overrides() {
+        debug_log!("CONFIG", "Overriding {} from env {}: {}", o.key, o.env_var, o.value);
+    }
+
     let path = match config_path() {
         Some(p) => p,
-        None => return apply_env_overrides(HorsemanConfig::default()),
+        None => return HorsemanConfig::default(),
     };
 
+    if let Some(dir) = path.parent() {
+        atomic_file::cleanup_temp_files(dir);
+    }
+
     if !path.exists() {
         debug_log!("CONFIG", "No config file found at {:?}, using defaults", path);
-        return apply_env_overrides(HorsemanConfig::default());
-    }
-
-    match fs::read_to_string(&path) {
-        Ok(content) => {
-            match toml::from_str::<HorsemanConfig>(&content) {
-                Ok(config) => {
-                    debug_log!("CONFIG", "Loaded config from {:?}", path);
-                    apply_env_overrides(config)
-                }
-                Err(e) => {
-                    debug_log!("CONFIG", "Failed to parse config: {}", e);
-                    apply_env_overrides(HorsemanConfig::default())
-                }
-            }
+        return HorsemanConfig::default();
+    }
+
+    // Falls back to config.toml.bak if a sync tool or crash left the file damaged
+    let content = atomic_file::read_with_recovery(&path, |c| toml::from_str::<HorsemanConfig>(c).is_ok());
+    match content.map(|c| toml::from_str::<HorsemanConfig>(&c)) {
+        Some(Ok(config)) => {
+            debug_log!("CONFIG", "Loaded config from {:?}", path);
+            config
+        }
+        Some(Err(e)) => {
+            debug_log!("CONFIG", "Failed to parse config: {}", e);
+            HorsemanConfig::default()
         }
-        Err(e) => {
-            debug_log!("CONFIG", "Failed to read config file: {}", e);
-            apply_env_overrides(HorsemanConfig::default())
+        None => {
+            debug_log!("CONFIG", "Config file is unreadable or corrupt, using defaults");
+            HorsemanConfig::default()
         }
     }
 }
 
-/// Apply environment variable overrides
-fn apply_env_overrides(mut config: HorsemanConfig) -> HorsemanConfig {
+/// A config field overridden by an environment variable
+struct EnvOverride {
+    /// camelCase config key
+    key: &'static str,
+    env_var: &'static str,
+    /// JSON value for the key (null = explicitly disabled)
+    value: serde_json::Value,
+}
+
+/// Environment variable overrides currently in effect
+fn env_overrides() -> Vec<EnvOverride> {
+    let mut overrides = Vec::new();
     if let Ok(val) = std::env::var("HORSEMAN_CLAUDE_BIN") {
-        debug_log!("CONFIG", "Overriding claude_binary from env: {}", val);
-        config.claude_binary = Some(val);
+        overrides.push(EnvOverride { key: "claudeBinary", env_var: "HORSEMAN_CLAUDE_BIN", value: val.into() });
     }
     if let Ok(val) = std::env::var("HORSEMAN_PROJECTS_DIR") {
-        debug_log!("CONFIG", "Overriding projects_dir from env: {}", val);
-        config.projects_dir = Some(PathBuf::from(val));
+        overrides.push(EnvOverride { key: "projectsDir", env_var: "HORSEMAN_PROJECTS_DIR", value: val.into() });
     }
     if let Ok(val) = std::env::var("HORSEMAN_DEBUG_LOG") {
-        debug_log!("CONFIG", "Overriding debug_log_path from env: {}", val);
-        if val.to_lowercase() == "none" || val.is_empty() {
-            config.debug_log_path = None;
+        let value = if val.to_lowercase() == "none" || val.is_empty() {
+            serde_json::Value::Null
         } else {
-            config.debug_log_path = Some(PathBuf::from(val));
-        }
+            val.into()
+        };
+        overrides.push(EnvOverride { key: "debugLogPath", env_var: "HORSEMAN_DEBUG_LOG", value });
     }
     if let Ok(val) = std::env::var("HORSEMAN_CONTEXT_WINDOW") {
         if let Ok(size) = val.parse::<usize>() {
-            debug_log!("CONFIG", "Overriding context_window from env: {}", size);
-            config.context_window = Some(size);
+            overrides.push(EnvOverride { key: "contextWindow", env_var: "HORSEMAN_CONTEXT_WINDOW", value: size.into() });
+        }
+    }
+    overrides
+}
+
+/// Apply environment variable overrides
+fn apply_env_overrides(config: HorsemanConfig) -> HorsemanConfig {
+    let overrides = env_overrides();
+    if overrides.is_empty() {
+        return config;
+    }
+    let mut value = match serde_json::to_value(&config) {
+        Ok(v) => v,
+        Err(_) => return config,
+    };
+    if let Some(obj) = value.as_object_mut() {
+        for o in overrides {
+            obj.insert(o.key.to_string(), o.value);
         }
     }
-    config
+    serde_json::from_value(value).unwrap_or(config)
 }
 
 /// Save config to disk
@@ -110,24 +398,133 @@ fn save_config_to_disk(config: &HorsemanConfig) -> Result<(), String> {
     let content = toml::to_string_pretty(config)
         .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
-    fs::write(&path, content)
+    atomic_file::write_with_backup(&path, content)
         .map_err(|e| format!("Failed to write config file: {}", e))?;
 
     debug_log!("CONFIG", "Saved config to {:?}", path);
     Ok(())
 }
 
-/// Get the current config
+/// Get the current config (file values with env overrides applied)
 pub fn get_config() -> HorsemanConfig {
-    CONFIG.lock().unwrap().clone()
+    (*CONFIG.lock().unwrap().resolved).clone()
+}
+
+/// The current config with defaults filled in, for the getters below
+fn settings() -> Arc<HorsemanConfig> {
+    CONFIG.lock().unwrap().settings.clone()
+}
+
+/// Bumped on every config change, so derived values can be cached against it
+pub fn revision() -> u64 {
+    CONFIG_REVISION.load(Ordering::SeqCst)
+}
+
+/// Get the current config with its revision
+pub fn get_config_snapshot() -> ConfigSnapshot {
+    let state = CONFIG.lock().unwrap();
+    ConfigSnapshot {
+        config: (*state.resolved).clone(),
+        revision: CONFIG_REVISION.load(Ordering::SeqCst),
+    }
+}
+
+/// Re-read the config from disk (e.g. after an import replaced the file)
+pub fn reload_config() {
+    let loaded = ConfigState::new(load_config_from_disk());
+    {
+        let mut state = CONFIG.lock().unwrap();
+        *state = loaded;
+        CONFIG_REVISION.fetch_add(1, Ordering::SeqCst);
+    }
+    applied();
+}
+
+/// Bring runtime state that follows the config in line after a change.
+/// Called without the CONFIG lock held.
+fn applied() {
+    #[cfg(feature = "profiling")]
+    crate::profiling::apply_config();
 }
 
 /// Update the config and save to disk
 pub fn update_config(updates: HorsemanConfig) -> Result<HorsemanConfig, String> {
-    let mut config = CONFIG.lock().unwrap();
-    *config = updates.clone();
-    save_config_to_disk(&config)?;
-    Ok(config.clone())
+    let resolved = {
+        let mut state = CONFIG.lock().unwrap();
+        save_config_to_disk(&updates)?;
+        *state = ConfigState::new(updates);
+        CONFIG_REVISION.fetch_add(1, Ordering::SeqCst);
+        (*state.resolved).clone()
+    };
+    applied();
+    Ok(resolved)
+}
+
+/// Apply a JSON merge patch (RFC 7386) to `target`: objects merge
+/// recursively, `null` removes a key, anything else replaces it
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let patch_obj = match patch.as_object() {
+        Some(p) => p,
+        None => {
+            *target = patch.clone();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(Default::default());
+    }
+    if let Some(target_obj) = target.as_object_mut() {
+        for (key, value) in patch_obj {
+            if value.is_null() {
+                target_obj.remove(key);
+            } else {
+                merge_patch(target_obj.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Patch only the fields present in `patch` (camelCase keys, `null` clears).
+/// If `expected_revision` is given and the config changed since, fails with a
+/// conflict instead of overwriting the other writer's changes.
+pub fn patch_config(patch: &serde_json::Value, expected_revision: Option<u64>) -> Result<ConfigSnapshot, String> {
+    if !patch.is_object() {
+        return Err("Config patch must be an object".to_string());
+    }
+
+    let snapshot = patch_locked(patch, expected_revision)?;
+    applied();
+    Ok(snapshot)
+}
+
+/// `patch_config` under the CONFIG lock
+fn patch_locked(patch: &serde_json::Value, expected_revision: Option<u64>) -> Result<ConfigSnapshot, String> {
+    let mut state = CONFIG.lock().unwrap();
+    let revision = CONFIG_REVISION.load(Ordering::SeqCst);
+    if let Some(expected) = expected_revision {
+        if expected != revision {
+            return Err(format!(
+                "Config conflict: expected revision {} but current is {}; reload and retry",
+                expected, revision
+            ));
+        }
+    }
+
+    let mut value = serde_json::to_value(&state.file)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    merge_patch(&mut value, patch);
+    let patched: HorsemanConfig = serde_json::from_value(value)
+        .map_err(|e| format!("Invalid config patch: {}", e))?;
+
+    save_config_to_disk(&patched)?;
+    *state = ConfigState::new(patched);
+    let revision = CONFIG_REVISION.fetch_add(1, Ordering::SeqCst) + 1;
+    debug_log!("CONFIG", "Patched config (revision {})", revision);
+
+    Ok(ConfigSnapshot {
+        config: (*state.resolved).clone(),
+        revision,
+    })
 }
 
 // --- Accessor functions for other modules ---
@@ -215,6 +612,7 @@ pub fn resolve_claude_binary() -> String {
 // }
 
 /// Get a helpful error message when claude is not found
+#[allow(clippy::empty_line_after_doc_comments)]
 pub fn claude_not_found_error() -> String {
     let searched: Vec<String> = claude_search_paths()
         .iter()
@@ -249,6 +647,11 @@ pub fn projects_dir() -> PathBuf {
     get_config().projects_dir.unwrap_or_else(default_projects_dir)
 }
 
+/// Claude's user settings, next to its projects directory
+pub fn claude_settings_path() -> Option<PathBuf> {
+    projects_dir().parent().map(|dir| dir.join("settings.json"))
+}
+
 /// Default projects directory
 pub fn default_projects_dir() -> PathBuf {
     dirs::home_dir()
@@ -256,9 +659,369 @@ pub fn default_projects_dir() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from(".claude/projects"))
 }
 
-/// Get the context window fallback (default: 200000)
+/// Get the context window fallback
 pub fn context_window() -> usize {
-    get_config().context_window.unwrap_or(200000)
+    settings().context_window.unwrap_or_default()
+}
+
+/// Get the verification commands configured for a project directory
+pub fn verification_commands(working_directory: &str) -> Vec<String> {
+    settings()
+        .verification_commands
+        .as_ref()
+        .and_then(|by_project| by_project.get(working_directory.trim_end_matches('/')).cloned())
+        .unwrap_or_default()
+}
+
+/// How long a session's process may sit idle before it is suspended
+pub fn idle_suspend_after() -> Option<Duration> {
+    settings()
+        .idle_suspend_minutes
+        .filter(|m| *m > 0)
+        .map(|m| Duration::from_secs(m * 60))
+}
+
+/// Turn limit for sessions that don't set their own
+pub fn default_max_turns() -> Option<u32> {
+    settings().max_turns.filter(|t| *t > 0)
+}
+
+/// Cap on simultaneously running Claude processes
+pub fn max_concurrent_sessions() -> Option<usize> {
+    settings().max_concurrent_sessions.filter(|n| *n > 0).map(|n| n as usize)
+}
+
+/// Forced file watcher mode; None picks per watched path
+pub fn poll_file_watchers() -> Option<bool> {
+    settings().poll_file_watchers
+}
+
+/// Whether significant events are also narrated as plain sentences
+pub fn narration_enabled() -> bool {
+    settings().narration_enabled.unwrap_or_default()
+}
+
+pub fn context_warning_levels() -> Vec<u32> {
+    settings().context_warning_levels.clone().unwrap_or_default()
+}
+
+pub fn transcript_backups_kept() -> usize {
+    settings().transcript_backups_kept.unwrap_or_default()
+}
+
+/// Limit on the size of all transcript backups, None when set to 0
+pub fn transcript_backups_max_bytes() -> Option<u64> {
+    settings().transcript_backups_max_bytes.filter(|b| *b > 0)
+}
+
+pub fn archive_expiring_sessions() -> bool {
+    settings().archive_expiring_sessions.unwrap_or_default()
+}
+
+/// Context usage, in percent, that triggers compaction between turns
+pub fn auto_compact_percent() -> Option<u32> {
+    settings().auto_compact_percent.filter(|p| *p > 0)
+}
+
+/// How quick-reply suggestions are made
+pub fn quick_replies() -> QuickReplies {
+    settings().quick_replies.unwrap_or(QuickReplies::Rules)
+}
+
+/// Limit on tool output kept per tool call when parsing transcripts, None
+/// when set to 0
+pub fn transcript_max_tool_output_bytes() -> Option<usize> {
+    settings().transcript_max_tool_output_bytes.filter(|b| *b > 0)
+}
+
+/// Whether parsed transcripts are cached on disk as well as in memory
+pub fn transcript_disk_cache() -> bool {
+    settings().transcript_disk_cache.unwrap_or_default()
+}
+
+/// Secret redaction settings
+pub fn redaction() -> RedactionConfig {
+    settings().redaction.clone().unwrap_or_default()
+}
+
+/// Command used to open tool call diffs externally
+pub fn external_diff_tool() -> Option<String> {
+    settings().external_diff_tool.clone().filter(|t| !t.trim().is_empty())
+}
+
+/// Whether `memory.changed` should ask to restart affected sessions
+pub fn prompt_restart_on_memory_change() -> bool {
+    settings().prompt_restart_on_memory_change.unwrap_or_default()
+}
+
+/// How many times a session that crashed mid-turn is resumed automatically
+pub fn crash_max_retries() -> u32 {
+    settings().crash_max_retries.unwrap_or_default()
+}
+
+/// Base delay before resuming a crashed session
+pub fn crash_retry_backoff() -> Duration {
+    Duration::from_millis(settings().crash_retry_backoff_ms.unwrap_or_default())
+}
+
+pub fn bash_allow_prefixes() -> Vec<String> {
+    settings().bash_allow_prefixes.clone().unwrap_or_default()
+}
+
+pub fn permission_rules() -> PermissionRules {
+    settings().permission_rules.clone().unwrap_or_default()
+}
+
+/// Permission rules configured for one project directory
+pub fn project_permission_rules(working_directory: &str) -> PermissionRules {
+    settings()
+        .project_permission_rules
+        .as_ref()
+        .and_then(|by_project| by_project.get(working_directory.trim_end_matches('/')).cloned())
+        .unwrap_or_default()
+}
+
+pub fn mcp_servers() -> HashMap<String, McpServerConfig> {
+    settings().mcp_servers.clone().unwrap_or_default()
+}
+
+/// Extra MCP servers configured for one project directory
+pub fn project_mcp_servers(working_directory: &str) -> HashMap<String, McpServerConfig> {
+    settings()
+        .project_mcp_servers
+        .as_ref()
+        .and_then(|by_project| by_project.get(working_directory.trim_end_matches('/')).cloned())
+        .unwrap_or_default()
+}
+
+/// Whether MCP config files are left behind for debugging
+pub fn keep_mcp_configs() -> bool {
+    settings().keep_mcp_configs.unwrap_or_default()
+}
+
+/// Longest stream-json line held in memory (0 = the default)
+pub fn max_stdout_line_bytes() -> usize {
+    settings()
+        .max_stdout_line_bytes
+        .filter(|b| *b > 0)
+        .unwrap_or(DEFAULT_MAX_STDOUT_LINE_BYTES)
+}
+
+/// How many times a rate-limited turn is continued automatically
+pub fn rate_limit_max_retries() -> u32 {
+    settings().rate_limit_max_retries.unwrap_or_default()
+}
+
+/// Base delay before continuing a rate-limited turn
+pub fn rate_limit_retry_backoff() -> Duration {
+    Duration::from_millis(settings().rate_limit_retry_backoff_ms.unwrap_or_default())
+}
+
+/// How long a permission prompt or question waits for the user
+pub fn permission_timeout() -> Duration {
+    let secs = settings().permission_timeout_secs.unwrap_or_default();
+    Duration::from_secs(secs.max(MIN_PERMISSION_TIMEOUT_SECS))
+}
+
+pub fn permission_timeout_action() -> PermissionTimeoutAction {
+    settings().permission_timeout_action.unwrap_or_default()
+}
+
+/// Kill switch for the background AI queue
+pub fn background_ai_enabled() -> bool {
+    settings().background_ai_enabled.unwrap_or_default()
+}
+
+/// Most background AI calls may cost per day
+pub fn background_ai_daily_budget_usd() -> f64 {
+    settings().background_ai_daily_budget_usd.unwrap_or_default()
+}
+
+/// Minimum gap between background AI calls
+pub fn background_ai_min_interval() -> Duration {
+    Duration::from_secs(settings().background_ai_min_interval_secs.unwrap_or_default())
+}
+
+/// Reachability probe target for offline detection ("" = disabled)
+pub fn connectivity_probe_host() -> String {
+    settings().connectivity_probe_host.clone().unwrap_or_default()
+}
+
+/// Provider presets by name
+pub fn provider_profiles() -> HashMap<String, ProviderProfile> {
+    settings().provider_profiles.clone().unwrap_or_default()
+}
+
+/// Default provider profile for new sessions
+pub fn active_provider() -> Option<String> {
+    settings().active_provider.clone().filter(|p| !p.is_empty())
+}
+
+/// Proxy for outbound HTTPS
+pub fn https_proxy() -> Option<String> {
+    settings().https_proxy.clone().filter(|p| !p.is_empty())
+}
+
+/// Hosts that bypass the proxy
+pub fn no_proxy() -> Option<String> {
+    settings().no_proxy.clone().filter(|p| !p.is_empty())
+}
+
+/// Extra CA bundle to trust
+pub fn ca_cert_path() -> Option<PathBuf> {
+    settings().ca_cert_path.clone().filter(|p| !p.as_os_str().is_empty())
+}
+
+/// Where session todo lists are mirrored, if anywhere
+pub fn todo_sync_target() -> Option<TodoSyncTarget> {
+    settings().todo_sync_target
+}
+
+/// Markdown file (relative to the working directory) for todo sync ("" = the default)
+pub fn todo_sync_file() -> String {
+    settings()
+        .todo_sync_file
+        .clone()
+        .filter(|f| !f.is_empty())
+        .unwrap_or_else(|| DEFAULT_TODO_SYNC_FILE.to_string())
+}
+
+pub fn todo_sync_interval() -> Duration {
+    Duration::from_secs(settings().todo_sync_interval_secs.unwrap_or_default().max(5))
+}
+
+/// Write a "what changed and why" summary when a session ends
+pub fn session_summary_enabled() -> bool {
+    settings().session_summary_enabled.unwrap_or_default()
+}
+
+pub fn search_enabled() -> bool {
+    settings().search_enabled.unwrap_or_default()
+}
+
+pub fn analytics_enabled() -> bool {
+    settings().analytics_enabled.unwrap_or_default()
+}
+
+#[cfg(feature = "profiling")]
+pub fn profiling_enabled() -> bool {
+    settings().profiling_enabled.unwrap_or_default()
+}
+
+#[cfg(feature = "analytics")]
+pub fn daily_budget_usd() -> Option<f64> {
+    settings().daily_budget_usd.filter(|usd| *usd > 0.0)
+}
+
+#[cfg(feature = "analytics")]
+pub fn monthly_budget_usd() -> Option<f64> {
+    settings().monthly_budget_usd.filter(|usd| *usd > 0.0)
+}
+
+#[cfg(feature = "analytics")]
+pub fn budget_warning_percent() -> u32 {
+    settings().budget_warning_percent.unwrap_or_default().min(100)
+}
+
+#[cfg(feature = "analytics")]
+pub fn budget_confirm_spawn() -> bool {
+    settings().budget_confirm_spawn.unwrap_or_default()
+}
+
+/// Days prompts are kept in the prompt history, None when set to 0
+pub fn prompt_history_days() -> Option<u32> {
+    settings().prompt_history_days.filter(|d| *d > 0)
+}
+
+/// Where an effective config value came from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+    /// Per-project entry in the config file
+    Project,
+}
+
+/// One resolved config value and its provenance
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveValue {
+    /// camelCase config key
+    pub key: String,
+    pub value: serde_json::Value,
+    pub source: ConfigSource,
+    /// Env var that set the value (source = env)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_var: Option<String>,
+}
+
+/// Resolve every config value with its source. With a working directory,
+/// per-project values (verification commands) are resolved for that project.
+pub fn effective_config(working_directory: Option<&str>) -> Vec<EffectiveValue> {
+    let file = serde_json::to_value(&CONFIG.lock().unwrap().file).unwrap_or_default();
+    let overrides = env_overrides();
+
+    let mut defaults = match serde_json::to_value(defaults()) {
+        Ok(serde_json::Value::Object(defaults)) => defaults,
+        _ => Default::default(),
+    };
+    // Resolved per machine rather than fixed
+    defaults.insert(
+        "claudeBinary".to_string(),
+        find_claude_binary().unwrap_or_else(|| "claude".to_string()).into(),
+    );
+    defaults.insert("projectsDir".to_string(), default_projects_dir().to_string_lossy().to_string().into());
+
+    let mut values: Vec<EffectiveValue> = defaults
+        .into_iter()
+        // Per project, resolved below
+        .filter(|(key, _)| key != "verificationCommands")
+        .map(|(key, default)| {
+            if let Some(o) = overrides.iter().find(|o| o.key == key.as_str()) {
+                return EffectiveValue {
+                    key,
+                    value: o.value.clone(),
+                    source: ConfigSource::Env,
+                    env_var: Some(o.env_var.to_string()),
+                };
+            }
+            match file.get(&key) {
+                Some(v) if !v.is_null() => EffectiveValue {
+                    key,
+                    value: v.clone(),
+                    source: ConfigSource::File,
+                    env_var: None,
+                },
+                _ => EffectiveValue {
+                    key,
+                    value: default,
+                    source: ConfigSource::Default,
+                    env_var: None,
+                },
+            }
+        })
+        .collect();
+
+    let all_commands = file.get("verificationCommands").filter(|v| !v.is_null());
+    let verification = match (working_directory, all_commands) {
+        (Some(dir), Some(by_project)) => match by_project.get(dir.trim_end_matches('/')) {
+            Some(commands) => (commands.clone(), ConfigSource::Project),
+            None => (serde_json::json!([]), ConfigSource::Default),
+        },
+        (Some(_), None) => (serde_json::json!([]), ConfigSource::Default),
+        (None, Some(by_project)) => (by_project.clone(), ConfigSource::File),
+        (None, None) => (serde_json::json!({}), ConfigSource::Default),
+    };
+    values.push(EffectiveValue {
+        key: "verificationCommands".to_string(),
+        value: verification.0,
+        source: verification.1,
+        env_var: None,
+    });
+
+    values
 }
 
 // --- Tauri Commands ---
@@ -273,6 +1036,28 @@ pub fn update_horseman_config(config: HorsemanConfig) -> Result<HorsemanConfig,
     update_config(config)
 }
 
+/// Get every config value with where it came from (default, file, env, project)
+#[tauri::command]
+pub fn get_effective_config(working_directory: Option<String>) -> Vec<EffectiveValue> {
+    effective_config(working_directory.as_deref())
+}
+
+/// Get the config along with its revision (for `update_horseman_config_patch`)
+#[tauri::command]
+pub fn get_horseman_config_snapshot() -> ConfigSnapshot {
+    get_config_snapshot()
+}
+
+/// Update only the given fields. Pass the revision from the last snapshot
+/// to get a conflict error if another writer changed the config meanwhile.
+#[tauri::command]
+pub fn update_horseman_config_patch(
+    patch: serde_json::Value,
+    expected_revision: Option<u64>,
+) -> Result<ConfigSnapshot, String> {
+    patch_config(&patch, expected_revision)
+}
+
 #[tauri::command]
 pub fn get_config_path() -> Option<String> {
     config_path().map(|p| p.to_string_lossy().to_string())
@@ -282,6 +1067,24 @@ pub fn get_config_path() -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn merge_patch_sets_clears_and_merges() {
+        let mut target = serde_json::json!({
+            "claudeBinary": "claude",
+            "contextWindow": 100,
+            "verificationCommands": { "/a": ["make"] }
+        });
+        let patch = serde_json::json!({
+            "contextWindow": null,
+            "verificationCommands": { "/b": ["cargo test"] }
+        });
+        merge_patch(&mut target, &patch);
+        assert_eq!(target["claudeBinary"], "claude");
+        assert!(target.get("contextWindow").is_none());
+        assert_eq!(target["verificationCommands"]["/a"][0], "make");
+        assert_eq!(target["verificationCommands"]["/b"][0], "cargo test");
+    }
+
     #[test]
     fn config_serializes_to_camel_case() {
         let config = HorsemanConfig {
@@ -289,6 +1092,7 @@ mod tests {
             projects_dir: Some(PathBuf::from("/home/user/.claude/projects")),
             debug_log_path: None,
             context_window: Some(150000),
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&config).unwrap();
@@ -345,6 +1149,20 @@ mod tests {
             "missing /usr/local/bin path");
     }
 
+    #[test]
+    fn defaults_fill_only_unset_fields() {
+        let config = HorsemanConfig {
+            context_window: Some(100_000),
+            transcript_backups_max_bytes: Some(0),
+            ..HorsemanConfig::default()
+        };
+        let settings = with_defaults(&config);
+        assert_eq!(settings.context_window, Some(100_000));
+        assert_eq!(settings.transcript_backups_max_bytes, Some(0));
+        assert_eq!(settings.transcript_backups_kept, Some(5));
+        assert_eq!(settings.max_turns, None);
+    }
+
     #[test]
     fn context_window_default() {
         // With None, should return 200000
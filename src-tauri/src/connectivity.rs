@@ -0,0 +1,239 @@
+//! Offline mode. A reachability probe (and network errors on Claude's
+//! stderr) decide whether we're online. While offline, follow-up messages
+//! are queued instead of spawning a process that would fail, background AI
+//! jobs wait, and `connectivity.changed` tells the UI. Queued messages are
+//! sent once the probe succeeds again. With `httpsProxy` set, the probe
+//! asks the proxy to CONNECT to the probe host, since that's the route
+//! Claude's requests take.
+
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use crate::panic_guard;
+use crate::supervisor;
+use once_cell::sync::Lazy;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const ONLINE_INTERVAL: Duration = Duration::from_secs(30);
+const OFFLINE_INTERVAL: Duration = Duration::from_secs(5);
+
+static ONLINE: AtomicBool = AtomicBool::new(true);
+/// A probe started by `report_network_error` is running
+static PROBING: AtomicBool = AtomicBool::new(false);
+static QUEUED_SENDS: Lazy<Mutex<Vec<QueuedSend>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A follow-up held back until the network returns
+pub struct QueuedSend {
+    pub ui_session_id: String,
+    pub working_directory: String,
+    pub content: String,
+    pub claude_session_id: String,
+    pub model: Option<String>,
+}
+
+pub fn is_online() -> bool {
+    ONLINE.load(Ordering::SeqCst)
+}
+
+/// Whether a line of Claude's stderr looks like a connection failure
+pub fn is_network_error(line: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "enotfound",
+        "econnrefused",
+        "econnreset",
+        "etimedout",
+        "eai_again",
+        "getaddrinfo",
+        "fetch failed",
+        "connection error",
+        "network error",
+    ];
+    let lower = line.to_lowercase();
+    MARKERS.iter().any(|m| lower.contains(m))
+}
+
+/// `host:port` of an `http://` proxy that can be probed without
+/// credentials; None for TLS or authenticated proxies
+fn proxy_authority(proxy: &str) -> Option<String> {
+    let rest = match proxy.split_once("://") {
+        Some(("http", rest)) => rest,
+        Some(_) => return None,
+        None => proxy,
+    };
+    let authority = rest.split('/').next().filter(|a| !a.is_empty() && !a.contains('@'))?;
+    let has_port = authority.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    Some(if has_port { authority.to_string() } else { format!("{}:80", authority) })
+}
+
+fn connect(host: &str) -> Option<TcpStream> {
+    let addrs = host.to_socket_addrs().ok()?;
+    addrs.into_iter().find_map(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).ok())
+}
+
+/// Whether `proxy` agrees to tunnel to `host`
+fn probe_via_proxy(proxy: &str, host: &str) -> bool {
+    let mut stream = match connect(proxy) {
+        Some(stream) => stream,
+        None => return false,
+    };
+    let _ = stream.set_read_timeout(Some(PROBE_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(PROBE_TIMEOUT));
+    if write!(stream, "CONNECT {host} HTTP/1.1\r\nHost: {host}\r\n\r\n").is_err() {
+        return false;
+    }
+    let mut status = String::new();
+    if BufReader::new(stream).read_line(&mut status).is_err() {
+        return false;
+    }
+    status.split_whitespace().nth(1) == Some("200")
+}
+
+/// Can we reach the probe host, through `httpsProxy` when one is set?
+/// Always true when the probe is disabled (`connectivityProbeHost` set to
+/// ""), or the proxy can't be probed (TLS or credentials), in which case
+/// Claude's own errors are all there is to go on.
+fn probe() -> bool {
+    let host = config::connectivity_probe_host();
+    if host.is_empty() {
+        return true;
+    }
+    match config::https_proxy() {
+        Some(proxy) => match proxy_authority(&proxy) {
+            Some(proxy) => probe_via_proxy(&proxy, &host),
+            None => true,
+        },
+        None => connect(&host).is_some(),
+    }
+}
+
+fn set_online(app: &AppHandle, online: bool) {
+    if ONLINE.swap(online, Ordering::SeqCst) == online {
+        return;
+    }
+    let queued = if online {
+        std::mem::take(&mut *panic_guard::lock_or_recover(&QUEUED_SENDS, "queued sends"))
+    } else {
+        Vec::new()
+    };
+    debug_log!("NET", "Connectivity changed: online={} ({} queued sends)", online, queued.len());
+    let _ = events::emit(
+        app,
+        BackendEvent::ConnectivityChanged {
+            online,
+            queued_sends: queued.len(),
+        },
+    );
+    if !queued.is_empty() {
+        flush(app, queued);
+    }
+}
+
+/// Send follow-ups that were queued while offline, oldest first
+fn flush(app: &AppHandle, queued: Vec<QueuedSend>) {
+    let state = app.state::<ClaudeState>();
+    for send in queued {
+        debug_log!("NET", "[{}] Sending queued message", send.ui_session_id);
+        let result = state.lock().send_message(
+            app,
+            &send.ui_session_id,
+            send.working_directory,
+            send.content,
+            send.claude_session_id,
+            send.model,
+        );
+        if let Err(e) = result {
+            let _ = events::emit(
+                app,
+                BackendEvent::SessionEnded {
+                    ui_session_id: send.ui_session_id,
+                    exit_code: None,
+                    error: Some(format!("Failed to send queued message: {}", e)),
+                    reason: None,
+                },
+            );
+        }
+    }
+}
+
+/// Hold a follow-up until the network returns
+pub fn queue_send(send: QueuedSend) {
+    debug_log!("NET", "[{}] Offline, queuing message", send.ui_session_id);
+    panic_guard::lock_or_recover(&QUEUED_SENDS, "queued sends").push(send);
+}
+
+/// A spawned process hit a network error: re-probe now instead of waiting
+/// for the next scheduled check. Errors arriving while a probe runs share it.
+pub fn report_network_error(app: &AppHandle) {
+    if !is_online() || PROBING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if !probe() {
+            set_online(&app, false);
+        }
+        PROBING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Drop a removed session's queued follow-ups
+pub fn forget(ui_session_id: &str) {
+    panic_guard::lock_or_recover(&QUEUED_SENDS, "queued sends").retain(|send| send.ui_session_id != ui_session_id);
+}
+
+/// Start the supervised reachability probe
+pub fn spawn(app: AppHandle) {
+    supervisor::spawn_thread("connectivity", move || loop {
+        std::thread::sleep(if is_online() { ONLINE_INTERVAL } else { OFFLINE_INTERVAL });
+        set_online(&app, probe());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_network_errors() {
+        assert!(is_network_error("API Error: Connection error."));
+        assert!(is_network_error("getaddrinfo ENOTFOUND api.anthropic.com"));
+        assert!(!is_network_error("Warning: deprecated flag"));
+    }
+
+    #[test]
+    fn probes_plain_http_proxies_only() {
+        assert_eq!(proxy_authority("http://proxy:8080").as_deref(), Some("proxy:8080"));
+        assert_eq!(proxy_authority("proxy.corp/").as_deref(), Some("proxy.corp:80"));
+        assert_eq!(proxy_authority("http://user:pw@proxy:8080"), None);
+        assert_eq!(proxy_authority("https://proxy:8443"), None);
+    }
+
+    /// A proxy on loopback that answers one CONNECT with `status`
+    fn fake_proxy(status: &'static str) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            BufReader::new(&stream).read_line(&mut request).unwrap();
+            assert_eq!(request, "CONNECT unreachable.invalid:443 HTTP/1.1\r\n");
+            write!(&stream, "HTTP/1.1 {}\r\n\r\n", status).unwrap();
+        });
+        addr
+    }
+
+    /// A session behind a proxy that only the proxy can get out of stays
+    /// online, so its follow-ups are sent rather than queued
+    #[test]
+    fn proxied_sessions_stay_online() {
+        assert!(probe_via_proxy(&fake_proxy("200 Connection established"), "unreachable.invalid:443"));
+        assert!(!probe_via_proxy(&fake_proxy("403 Forbidden"), "unreachable.invalid:443"));
+    }
+}
@@ -0,0 +1,90 @@
+//! Spend across sessions, for budgeting. Every finished turn's cost, tokens,
+//! model, project and duration is appended to a ledger in the metadata
+//! store, as is every background AI job (under a `background:<label>`
+//! session); `report` totals it per day, week or project.
+
+use crate::claude::process::parse_usage;
+use crate::debug_log;
+use crate::events::SessionUsage;
+use crate::store::{self, CostEntry, CostGrouping, CostTotal};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostReport {
+    pub from: String,
+    pub to: String,
+    pub group_by: CostGrouping,
+    /// In key order
+    pub groups: Vec<CostTotal>,
+    pub total_cost_usd: f64,
+    pub total_turns: u64,
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Model with the highest cost in a result event's `modelUsage`
+fn main_model(result: &serde_json::Value) -> Option<String> {
+    let cost = |usage: &serde_json::Value| usage.get("costUSD").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    result
+        .get("modelUsage")?
+        .as_object()?
+        .iter()
+        .max_by(|a, b| cost(a.1).total_cmp(&cost(b.1)))
+        .map(|(model, _)| model.clone())
+}
+
+/// Record the turn a result event finished. `cost_usd` is this turn's share
+/// (keep-alive processes report running totals).
+pub fn record_turn(
+    ui_session_id: &str,
+    claude_session_id: Option<String>,
+    project: &str,
+    result: &serde_json::Value,
+    cost_usd: f64,
+) {
+    // Not gated on `analyticsEnabled` (time tracking): budgets read this ledger
+    let usage = parse_usage(result);
+    let tokens = |field: fn(&SessionUsage) -> u64| usage.as_ref().map_or(0, field);
+    let entry = CostEntry {
+        ui_session_id: ui_session_id.to_string(),
+        claude_session_id,
+        project: project.trim_end_matches('/').to_string(),
+        model: main_model(result),
+        day: today(),
+        ended_at: chrono::Utc::now().to_rfc3339(),
+        duration_ms: result.get("duration_ms").and_then(|v| v.as_u64()).unwrap_or(0),
+        cost_usd,
+        input_tokens: tokens(|u| u.input_tokens),
+        output_tokens: tokens(|u| u.output_tokens),
+        cache_read_tokens: tokens(|u| u.cache_read_tokens),
+        cache_creation_tokens: tokens(|u| u.cache_creation_tokens),
+    };
+    if let Err(e) = store::record_cost(&entry) {
+        debug_log!("COST", "[{}] Failed to record cost: {}", ui_session_id, e);
+    }
+}
+
+/// Spend between `from` and `to` (YYYY-MM-DD, inclusive; default: today)
+/// grouped per day, week or project, optionally for one project only
+pub fn report(
+    from: Option<String>,
+    to: Option<String>,
+    group_by: CostGrouping,
+    project: Option<&str>,
+) -> Result<CostReport, String> {
+    let from = from.unwrap_or_else(today);
+    let to = to.unwrap_or_else(today);
+    let project = project.map(|p| p.trim_end_matches('/'));
+    let groups = store::cost_totals(&from, &to, project, group_by)?;
+    Ok(CostReport {
+        total_cost_usd: groups.iter().map(|g| g.cost_usd).sum(),
+        total_turns: groups.iter().map(|g| g.turns).sum(),
+        from,
+        to,
+        group_by,
+        groups,
+    })
+}
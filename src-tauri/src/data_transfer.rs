@@ -0,0 +1,555 @@
+//! Export/import of Horseman's data (config, stores, session metadata and
+//! optionally Claude transcripts) as a single zip archive, for moving to a
+//! new machine.
+
+use crate::atomic_file;
+use crate::claude::annotations;
+use crate::claude::resume_recovery;
+use crate::config;
+use crate::debug_log;
+use crate::session_cache;
+use crate::session_env::SessionEnvironment;
+use crate::store::{self, SessionMeta};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bump when the archive layout changes
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+/// Archive prefix for the Horseman config dir
+const HORSEMAN_PREFIX: &str = "horseman";
+/// Archive prefix for the Claude projects dir
+const TRANSCRIPTS_PREFIX: &str = "transcripts";
+
+/// Large per-session raw stdout logs only travel with transcripts
+const RAW_LOGS_DIR: &str = "artifacts/raw";
+/// The search index stays open while the app runs and is rebuilt from the
+/// transcripts, so imports leave the current one alone
+const SEARCH_INDEX_FILE: &str = "artifacts/search.db";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    format_version: u32,
+    app_version: String,
+    exported_at: String,
+    includes_transcripts: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportReport {
+    pub archive_path: String,
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub includes_transcripts: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub files_written: usize,
+    /// Files that already existed and were kept (overwrite = false)
+    pub files_skipped: usize,
+    pub includes_transcripts: bool,
+}
+
+/// Recursively collect files under `root`, as paths relative to it
+pub(crate) fn collect_files(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(root, &path, out)?;
+        } else if file_type.is_file() {
+            if let Ok(relative) = path.strip_prefix(root) {
+                out.push(relative.to_path_buf());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Zip entry names always use forward slashes
+fn entry_name(prefix: &str, relative: &Path) -> String {
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    format!("{}/{}", prefix, parts.join("/"))
+}
+
+fn add_tree(
+    zip: &mut ZipWriter<File>,
+    prefix: &str,
+    root: &Path,
+    skip: impl Fn(&Path) -> bool,
+    report: &mut ExportReport,
+) -> Result<(), String> {
+    if !root.exists() {
+        return Ok(());
+    }
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files)
+        .map_err(|e| format!("Failed to list {:?}: {}", root, e))?;
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for relative in files {
+        if skip(&relative) {
+            continue;
+        }
+        let content = fs::read(root.join(&relative))
+            .map_err(|e| format!("Failed to read {:?}: {}", relative, e))?;
+        zip.start_file(entry_name(prefix, &relative), options)
+            .map_err(|e| format!("Failed to add {:?}: {}", relative, e))?;
+        zip.write_all(&content)
+            .map_err(|e| format!("Failed to write {:?}: {}", relative, e))?;
+        report.file_count += 1;
+        report.total_bytes += content.len() as u64;
+    }
+    Ok(())
+}
+
+/// Write all Horseman data to a zip archive at `archive_path`
+pub fn export(archive_path: &Path, include_transcripts: bool) -> Result<ExportReport, String> {
+    let config_dir = config::config_dir().ok_or("Could not determine config directory")?;
+
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    let file = File::create(archive_path)
+        .map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+
+    let mut report = ExportReport {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        file_count: 0,
+        total_bytes: 0,
+        includes_transcripts: include_transcripts,
+    };
+
+    let manifest = Manifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        includes_transcripts: include_transcripts,
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.start_file(MANIFEST_NAME, SimpleFileOptions::default())
+        .map_err(|e| format!("Failed to add manifest: {}", e))?;
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    // Never bundle the archive into itself if it's exported inside the config dir
+    let archive_relative = archive_path.strip_prefix(&config_dir).ok().map(|p| p.to_path_buf());
+    add_tree(
+        &mut zip,
+        HORSEMAN_PREFIX,
+        &config_dir,
+        |p| {
+            Some(p) == archive_relative.as_deref()
+                || (!include_transcripts && p.starts_with(RAW_LOGS_DIR))
+        },
+        &mut report,
+    )?;
+
+    if include_transcripts {
+        add_tree(&mut zip, TRANSCRIPTS_PREFIX, &config::projects_dir(), |_| false, &mut report)?;
+    }
+
+    zip.finish().map_err(|e| format!("Failed to finalize archive: {}", e))?;
+
+    debug_log!(
+        "DATA",
+        "Exported {} files ({} bytes) to {:?}",
+        report.file_count,
+        report.total_bytes,
+        archive_path
+    );
+    Ok(report)
+}
+
+/// Restore Horseman data from an archive made by `export`.
+/// Existing files are kept unless `overwrite` is set.
+pub fn import(archive_path: &Path, overwrite: bool) -> Result<ImportReport, String> {
+    let config_dir = config::config_dir().ok_or("Could not determine config directory")?;
+    let projects_dir = config::projects_dir();
+
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| format!("Not a valid archive: {}", e))?;
+
+    let manifest: Manifest = {
+        let entry = archive
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| "Archive is missing its manifest (not a Horseman export?)".to_string())?;
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid manifest: {}", e))?
+    };
+    if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "Archive format v{} is newer than this version of Horseman supports (v{})",
+            manifest.format_version, ARCHIVE_FORMAT_VERSION
+        ));
+    }
+
+    let mut report = ImportReport {
+        includes_transcripts: manifest.includes_transcripts,
+        ..Default::default()
+    };
+
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        if entry.is_dir() || entry.name() == MANIFEST_NAME {
+            continue;
+        }
+
+        // enclosed_name rejects absolute paths and ".." (zip slip)
+        let name = match entry.enclosed_name() {
+            Some(n) => n,
+            None => {
+                debug_log!("DATA", "Skipping unsafe archive entry: {}", entry.name());
+                continue;
+            }
+        };
+        let target = if let Ok(rest) = name.strip_prefix(HORSEMAN_PREFIX) {
+            if rest == Path::new(SEARCH_INDEX_FILE) {
+                continue;
+            }
+            config_dir.join(rest)
+        } else if let Ok(rest) = name.strip_prefix(TRANSCRIPTS_PREFIX) {
+            projects_dir.join(rest)
+        } else {
+            continue;
+        };
+
+        if target.exists() && !overwrite {
+            report.files_skipped += 1;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        // Read the whole entry first so a bad archive never leaves a half-written file
+        let mut content = Vec::new();
+        io::copy(&mut entry, &mut content)
+            .map_err(|e| format!("Failed to extract {:?}: {}", target, e))?;
+        let written = if target == config_dir.join(store::DB_FILE) {
+            store::while_closed(|| atomic_file::write(&target, &content))
+        } else {
+            atomic_file::write(&target, &content)
+        };
+        written.map_err(|e| format!("Failed to write {:?}: {}", target, e))?;
+        report.files_written += 1;
+    }
+
+    config::reload_config();
+
+    debug_log!(
+        "DATA",
+        "Imported {} files ({} skipped) from {:?}",
+        report.files_written,
+        report.files_skipped,
+        archive_path
+    );
+    Ok(report)
+}
+
+/// Bump when the single-session archive layout changes
+const SESSION_FORMAT_VERSION: u32 = 1;
+const SESSION_MANIFEST_NAME: &str = "session.json";
+/// Archive prefix for the transcript files, relative to their project dir
+const SESSION_FILES_PREFIX: &str = "project";
+const SESSION_ANNOTATIONS_NAME: &str = "annotations.jsonl";
+
+/// Describes a single-session archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionManifest {
+    format_version: u32,
+    app_version: String,
+    exported_at: String,
+    claude_session_id: String,
+    /// Claude's encoded project dir name (the original working directory)
+    project_dir_name: String,
+    /// Transcript files, relative to the project dir
+    files: Vec<String>,
+    /// Title, pin and tags from the metadata store
+    meta: Option<SessionMeta>,
+    /// What Horseman last ran the session with
+    #[serde(default)]
+    environment: Option<SessionEnvironment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionImportReport {
+    pub claude_session_id: String,
+    pub transcript_path: String,
+    pub files_written: usize,
+}
+
+/// Zip one session's transcript, subagent transcripts, annotations and
+/// metadata into `archive_path`, for sharing with another machine
+pub fn export_session(claude_session_id: &str, archive_path: &Path) -> Result<ExportReport, String> {
+    let transcript_path = resume_recovery::find_transcript(claude_session_id)
+        .ok_or_else(|| format!("No transcript found for session {}", claude_session_id))?;
+    let project_dir = transcript_path.parent().ok_or("Transcript has no project directory")?;
+
+    let mut files = vec![PathBuf::from(format!("{}.jsonl", claude_session_id))];
+    for path in resume_recovery::subagent_transcripts(&transcript_path, claude_session_id) {
+        if path.is_dir() {
+            let mut nested = Vec::new();
+            collect_files(project_dir, &path, &mut nested)
+                .map_err(|e| format!("Failed to list {:?}: {}", path, e))?;
+            files.extend(nested);
+        } else if let Ok(relative) = path.strip_prefix(project_dir) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    let file = File::create(archive_path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let mut report = ExportReport {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        file_count: 0,
+        total_bytes: 0,
+        includes_transcripts: true,
+    };
+
+    let manifest = SessionManifest {
+        format_version: SESSION_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        claude_session_id: claude_session_id.to_string(),
+        project_dir_name: project_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        files: files.iter().map(|f| entry_name(SESSION_FILES_PREFIX, f)).collect(),
+        meta: store::get(claude_session_id).ok(),
+        environment: session_cache::environment(claude_session_id),
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.start_file(SESSION_MANIFEST_NAME, SimpleFileOptions::default())
+        .map_err(|e| format!("Failed to add manifest: {}", e))?;
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let annotations = annotations::load(claude_session_id);
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+    for relative in &files {
+        let content = fs::read(project_dir.join(relative))
+            .map_err(|e| format!("Failed to read {:?}: {}", relative, e))?;
+        entries.push((entry_name(SESSION_FILES_PREFIX, relative), content));
+    }
+    if !annotations.is_empty() {
+        let mut content = String::new();
+        for a in &annotations {
+            content.push_str(&serde_json::to_string(a).map_err(|e| format!("Failed to serialize annotation: {}", e))?);
+            content.push('\n');
+        }
+        entries.push((SESSION_ANNOTATIONS_NAME.to_string(), content.into_bytes()));
+    }
+    for (name, content) in entries {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {}: {}", name, e))?;
+        zip.write_all(&content).map_err(|e| format!("Failed to write {}: {}", name, e))?;
+        report.file_count += 1;
+        report.total_bytes += content.len() as u64;
+    }
+
+    zip.finish().map_err(|e| format!("Failed to finalize archive: {}", e))?;
+    debug_log!("DATA", "Exported session {} ({} files) to {:?}", claude_session_id, report.file_count, archive_path);
+    Ok(report)
+}
+
+/// A project directory name is a single path component, so the import
+/// can't leave the projects dir
+fn is_project_dir_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['/', '\\'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+}
+
+/// Whether a path inside the archive's project dir belongs to session `id`:
+/// its transcript, a file in its own directory, or a top-level subagent
+/// transcript (older CLIs)
+fn is_session_file(path: &Path, id: &str) -> bool {
+    let parts: Option<Vec<String>> = path
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    match parts.as_deref() {
+        Some([file]) => *file == format!("{}.jsonl", id) || (file.starts_with("agent-") && file.ends_with(".jsonl")),
+        Some([dir, _, ..]) => dir == id,
+        _ => false,
+    }
+}
+
+/// Restore a session archive made by `export_session` into the projects
+/// dir, under `working_directory`'s project if given, else the original
+/// one. Fails if the session already exists unless `overwrite` is set.
+pub fn import_session(
+    archive_path: &Path,
+    working_directory: Option<&str>,
+    overwrite: bool,
+) -> Result<SessionImportReport, String> {
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Not a valid archive: {}", e))?;
+
+    let manifest: SessionManifest = {
+        let entry = archive
+            .by_name(SESSION_MANIFEST_NAME)
+            .map_err(|_| "Archive is missing its manifest (not a Horseman session export?)".to_string())?;
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid manifest: {}", e))?
+    };
+    if manifest.format_version > SESSION_FORMAT_VERSION {
+        return Err(format!(
+            "Session archive format v{} is newer than this version of Horseman supports (v{})",
+            manifest.format_version, SESSION_FORMAT_VERSION
+        ));
+    }
+    let id = &manifest.claude_session_id;
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid session ID in archive: {}", id));
+    }
+    let project_dir_name = match working_directory {
+        Some(dir) => dir.trim_end_matches('/').replace('/', "-"),
+        None => manifest.project_dir_name.clone(),
+    };
+    if !is_project_dir_name(&project_dir_name) {
+        return Err(format!("Invalid project directory: {}", project_dir_name));
+    }
+    if let Some(existing) = resume_recovery::find_transcript(id) {
+        if !overwrite {
+            return Err(format!("Session {} already exists at {:?}", id, existing));
+        }
+    }
+    let transcript_entry = format!("{}/{}.jsonl", SESSION_FILES_PREFIX, id);
+    if archive.by_name(&transcript_entry).is_err() {
+        return Err("Archive did not contain the session transcript".to_string());
+    }
+
+    let project_dir = config::projects_dir().join(&project_dir_name);
+    let mut files_written = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read archive entry {}: {}", i, e))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = match entry.enclosed_name() {
+            Some(n) => n,
+            None => {
+                debug_log!("DATA", "Skipping unsafe archive entry: {}", entry.name());
+                continue;
+            }
+        };
+        if name == Path::new(SESSION_ANNOTATIONS_NAME) {
+            let mut content = String::new();
+            io::Read::read_to_string(&mut entry, &mut content)
+                .map_err(|e| format!("Failed to read annotations: {}", e))?;
+            // Don't duplicate annotations already restored by an earlier import
+            if annotations::load(id).is_empty() {
+                for annotation in content.lines().filter_map(|l| serde_json::from_str(l).ok()) {
+                    annotations::append(id, &annotation)?;
+                }
+            }
+            continue;
+        }
+        let rest = match name.strip_prefix(SESSION_FILES_PREFIX) {
+            Ok(rest) => rest.to_path_buf(),
+            Err(_) => continue,
+        };
+        if !is_session_file(&rest, id) {
+            debug_log!("DATA", "Skipping archive entry of another session: {}", entry.name());
+            continue;
+        }
+        let target = project_dir.join(&rest);
+        // Top-level subagent transcripts sit beside other sessions' files
+        let is_agent_file = rest.components().count() == 1 && rest != Path::new(&format!("{}.jsonl", id));
+        if is_agent_file && target.exists() && !overwrite {
+            debug_log!("DATA", "Not replacing existing {:?}", target);
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        let mut out = File::create(&target).map_err(|e| format!("Failed to create {:?}: {}", target, e))?;
+        io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to extract {:?}: {}", target, e))?;
+        files_written += 1;
+    }
+
+    // Metadata set on this machine wins over the archive's
+    if let Some(meta) = manifest.meta {
+        let current = store::get(id).unwrap_or_default();
+        if current.title.is_none() && !current.pinned && current.tags.is_empty() {
+            let restored = store::set_title(id, meta.title)
+                .and_then(|_| store::set_pinned(id, meta.pinned))
+                .and_then(|_| store::set_tags(id, &meta.tags));
+            if let Err(e) = restored {
+                debug_log!("DATA", "Failed to restore metadata for {}: {}", id, e);
+            }
+        }
+    }
+
+    // A session run here since has its own environment
+    if let (Some(environment), None) = (manifest.environment, session_cache::environment(id)) {
+        if let Err(e) = session_cache::record_environment(id, &environment) {
+            debug_log!("DATA", "Failed to restore environment for {}: {}", id, e);
+        }
+    }
+
+    let transcript_path = project_dir.join(format!("{}.jsonl", id));
+    debug_log!("DATA", "Imported session {} ({} files) into {:?}", id, files_written, project_dir);
+    Ok(SessionImportReport {
+        claude_session_id: id.clone(),
+        transcript_path: transcript_path.to_string_lossy().to_string(),
+        files_written,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_stay_inside_the_session() {
+        assert!(is_project_dir_name("-Users-me-code-app"));
+        assert!(!is_project_dir_name("/etc"));
+        assert!(!is_project_dir_name("a/b"));
+        assert!(!is_project_dir_name(".."));
+        assert!(!is_project_dir_name("."));
+        assert!(!is_project_dir_name(""));
+
+        let id = "abc-123";
+        assert!(is_session_file(Path::new("abc-123.jsonl"), id));
+        assert!(is_session_file(Path::new("abc-123/subagents/agent-1.jsonl"), id));
+        assert!(is_session_file(Path::new("agent-9.jsonl"), id));
+        assert!(!is_session_file(Path::new("other.jsonl"), id));
+        assert!(!is_session_file(Path::new("other/subagents/agent-1.jsonl"), id));
+        assert!(!is_session_file(Path::new("abc-123"), id));
+        assert!(!is_session_file(Path::new("../abc-123.jsonl"), id));
+    }
+}
@@ -63,7 +63,28 @@ macro_rules! debug_log {
     };
 }
 
+/// Open a profiling span that lasts until the end of the enclosing block
+/// (see `profiling`); compiles to nothing without the `profiling` feature
+#[macro_export]
+macro_rules! profile_span {
+    ($name:literal $(, $($field:tt)*)?) => {
+        #[cfg(feature = "profiling")]
+        let _profile_span = tracing::info_span!($name $(, $($field)*)?).entered();
+    };
+}
+
+/// Push buffered log writes to disk (call before the app exits)
+pub fn flush() {
+    if let Ok(mut guard) = LOG_FILE.lock() {
+        if let Some(ref mut file) = *guard {
+            let _ = file.flush();
+            let _ = file.sync_all();
+        }
+    }
+}
+
 /// Clear the log file (call on app start)
+#[allow(clippy::writeln_empty_string)]
 pub fn clear_log() {
     let path = log_path();
     if let Ok(mut file) = File::create(&path) {
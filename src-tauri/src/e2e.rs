@@ -0,0 +1,216 @@
+//! End-to-end smoke test against the locally installed Claude CLI. Only built
+//! with the `e2e` feature (see the `horseman-e2e` binary): starts the real
+//! backend without windows, asks a cheap model to run one Bash command,
+//! approves the permission prompt and checks the events the UI would get.
+//!
+//! Needs `claude` logged in and `cargo build -p horseman-mcp` beforehand.
+//! `HORSEMAN_E2E_MODEL` overrides the model (default `haiku`).
+
+use crate::claude::{ClaudeManager, SessionOptions};
+use crate::commands::{ClaudeState, HookState};
+use crate::events::PermissionMode;
+use crate::{hooks, shutdown, TokioRuntime};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener, Manager};
+
+/// Printed by the tool call; must show up in its output
+const MARKER: &str = "horseman-e2e-ok";
+const DEFAULT_MODEL: &str = "haiku";
+/// Give up if the session hasn't ended by then
+const TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Event kinds the session must emit, in this order (others may come between)
+const EXPECTED: &[&str] = &[
+    "session.started",
+    "tool.started",
+    "permission.requested",
+    "permission.resolved",
+    "tool.completed",
+    "message.assistant",
+    "session.ended",
+];
+
+/// Check that `kinds` contains `EXPECTED` as an ordered subsequence
+fn check_sequence(kinds: &[String]) -> Result<(), String> {
+    let mut remaining = kinds.iter();
+    for expected in EXPECTED {
+        if !remaining.any(|kind| kind == expected) {
+            return Err(format!("Missing `{}` in event sequence: {}", expected, kinds.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+fn field<'a>(event: &'a Value, name: &str) -> Option<&'a str> {
+    event.get(name).and_then(|v| v.as_str())
+}
+
+/// Drive one session and check what it emitted
+fn scenario(app: &AppHandle, events: mpsc::Receiver<Value>, working_directory: &Path) -> Result<(), String> {
+    hooks::get_mcp_binary_path()?;
+    let ui_session_id = format!("e2e-{}", uuid::Uuid::new_v4());
+    let model = std::env::var("HORSEMAN_E2E_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string());
+    println!("Spawning claude ({}) in {:?}", model, working_directory);
+
+    app.state::<ClaudeState>().lock().spawn_session(
+        app,
+        ui_session_id.clone(),
+        working_directory.to_string_lossy().to_string(),
+        Some(format!(
+            "Use the Bash tool to run `echo {}`, then reply with only the command's output.",
+            MARKER
+        )),
+        None,
+        SessionOptions {
+            model: Some(model),
+            persistent: false,
+            permission_mode: Some(PermissionMode::Default),
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+            max_turns: Some(3),
+            add_dirs: Vec::new(),
+            fork_session: false,
+            thinking_budget: Some(0),
+            partial_messages: false,
+            provider: None,
+            confirm_over_budget: false,
+        },
+    )?;
+
+    let mut kinds = Vec::new();
+    let mut approved = Vec::new();
+    let mut tool_output = None;
+    let started = Instant::now();
+    loop {
+        let remaining = TIMEOUT.checked_sub(started.elapsed()).unwrap_or_default();
+        let event = events
+            .recv_timeout(remaining)
+            .map_err(|_| format!("Timed out after {:?}; events so far: {}", TIMEOUT, kinds.join(", ")))?;
+        let kind = field(&event, "type").unwrap_or_default().to_string();
+        let ours = match kind.as_str() {
+            "permission.resolved" => field(&event, "requestId").is_some_and(|id| approved.iter().any(|a| a == id)),
+            _ => field(&event, "uiSessionId") == Some(ui_session_id.as_str()),
+        };
+        if !ours {
+            continue;
+        }
+        println!("  {}", kind);
+        match kind.as_str() {
+            "permission.requested" => {
+                let request_id = field(&event, "requestId").unwrap_or_default().to_string();
+                let tool_name = field(&event, "toolName").unwrap_or_default();
+                if tool_name != "Bash" {
+                    return Err(format!("Expected a Bash permission request, got {}", tool_name));
+                }
+                let hook_state = app.state::<HookState>();
+                let response = hooks::types::PermissionResponse {
+                    allow: true,
+                    message: None,
+                    answers: None,
+                    updated_input: None,
+                };
+                app.state::<TokioRuntime>().0.block_on(hooks::respond_permission(
+                    &hook_state.0,
+                    request_id.clone(),
+                    response,
+                    None,
+                    hooks::types::ApprovalScope::Once,
+                ))?;
+                approved.push(request_id);
+            }
+            "tool.completed" => {
+                tool_output = field(&event, "output").map(str::to_string);
+            }
+            "session.ended" => {
+                kinds.push(kind);
+                if let Some(error) = field(&event, "error") {
+                    return Err(format!("Session ended with error: {}", error));
+                }
+                if event.get("exitCode").and_then(|c| c.as_i64()) != Some(0) {
+                    return Err(format!("Session ended with exit code {}", event["exitCode"]));
+                }
+                break;
+            }
+            _ => {}
+        }
+        kinds.push(kind);
+    }
+
+    check_sequence(&kinds)?;
+    match tool_output {
+        Some(output) if output.contains(MARKER) => Ok(()),
+        other => Err(format!("Tool output doesn't contain {:?}: {:?}", MARKER, other)),
+    }
+}
+
+/// Run the smoke test; returns the process exit code (0 = passed)
+pub fn run() -> i32 {
+    let mut context = tauri::generate_context!();
+    context.config_mut().app.windows.clear();
+
+    let app = tauri::Builder::default()
+        .setup(|app| {
+            let rt = tokio::runtime::Runtime::new()?;
+            let (port, hook_state) = rt.block_on(hooks::start_hook_server(app.handle().clone()))?;
+            let mut manager = ClaudeManager::new();
+            manager.set_hook_port(port);
+            app.manage(ClaudeState(Mutex::new(manager)));
+            app.manage(HookState(hook_state));
+            app.manage(TokioRuntime(rt));
+
+            let (tx, rx) = mpsc::channel();
+            app.listen_any("horseman-event", move |event| {
+                if let Ok(payload) = serde_json::from_str::<Value>(event.payload()) {
+                    let _ = tx.send(payload);
+                }
+            });
+
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                let working_directory = std::env::temp_dir().join(format!("horseman-e2e-{}", std::process::id()));
+                let result = std::fs::create_dir_all(&working_directory)
+                    .map_err(|e| format!("Failed to create {:?}: {}", working_directory, e))
+                    .and_then(|_| scenario(&handle, rx, &working_directory));
+                let _ = std::fs::remove_dir_all(&working_directory);
+                match result {
+                    Ok(()) => {
+                        println!("horseman-e2e: passed");
+                        handle.exit(0);
+                    }
+                    Err(e) => {
+                        eprintln!("horseman-e2e: FAILED: {}", e);
+                        handle.exit(1);
+                    }
+                }
+            });
+            Ok(())
+        })
+        .build(context)
+        .expect("error while building tauri application");
+
+    app.run_return(|app, event| {
+        if let tauri::RunEvent::Exit = event {
+            shutdown::run(app);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_allows_extra_events_but_not_reordering() {
+        let kinds = |s: &str| s.split(' ').map(str::to_string).collect::<Vec<_>>();
+        let ok = "session.started usage.updated tool.started permission.requested permission.resolved \
+                  tool.completed message.assistant usage.updated session.ended";
+        assert!(check_sequence(&kinds(ok)).is_ok());
+        let reordered = "session.started tool.started permission.resolved permission.requested \
+                         tool.completed message.assistant session.ended";
+        assert!(check_sequence(&kinds(reordered)).is_err());
+    }
+}
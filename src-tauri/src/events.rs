@@ -1,7 +1,48 @@
-use serde::{Deserialize, Serialize};
+use crate::commands::DiscoveredSession;
+use crate::hooks::types::SessionPriority;
+use crate::narration;
+use crate::store::SessionSummary;
+use crate::panic_guard;
+use crate::redaction;
+use crate::profile_span;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, EventTarget};
+
+/// Current BackendEvent schema version. Bump when a payload changes shape.
+///   1 - original unversioned payloads
+///   2 - every payload carries `schemaVersion`
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Oldest schema version the backend can still emit (compat shim)
+pub const MIN_SUPPORTED_SCHEMA_VERSION: u32 = SCHEMA_VERSION - 1;
+
+/// Schema version negotiated with the frontend (defaults to current)
+static NEGOTIATED_SCHEMA_VERSION: AtomicU32 = AtomicU32::new(SCHEMA_VERSION);
+
+/// Agree on the schema version to emit, given the newest one the frontend
+/// understands. A newer frontend gets our current version.
+pub fn negotiate_schema_version(frontend_version: u32) -> Result<u32, String> {
+    if frontend_version < MIN_SUPPORTED_SCHEMA_VERSION {
+        return Err(format!(
+            "Frontend event schema v{} is too old (backend supports v{}-v{})",
+            frontend_version, MIN_SUPPORTED_SCHEMA_VERSION, SCHEMA_VERSION
+        ));
+    }
+    let version = frontend_version.min(SCHEMA_VERSION);
+    NEGOTIATED_SCHEMA_VERSION.store(version, Ordering::Relaxed);
+    Ok(version)
+}
+
+pub fn negotiated_schema_version() -> u32 {
+    NEGOTIATED_SCHEMA_VERSION.load(Ordering::Relaxed)
+}
 
 /// Subagent info for Task tools
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SubagentInfo {
     #[serde(rename = "type")]
@@ -13,7 +54,7 @@ pub struct SubagentInfo {
     pub tool_count: Option<usize>,
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolCall {
     pub id: String,
@@ -32,6 +73,9 @@ pub struct ToolCall {
     pub ended_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subagent: Option<SubagentInfo>,
+    /// Task tools: tokens of the subagent's own turns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<SessionUsage>,
 }
 
 #[derive(Clone, Serialize, Debug, Default)]
@@ -43,14 +87,19 @@ pub struct ToolUpdate {
     pub status: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subagent: Option<SubagentInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<SessionUsage>,
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Message {
     pub id: String,
     pub role: String,
     pub text: String,
+    /// Extended thinking from `thinking` content blocks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -58,9 +107,12 @@ pub struct Message {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub is_streaming: Option<bool>,
     pub timestamp: String,
+    /// Assistant messages: tokens of the API call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<SessionUsage>,
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TodoItem {
     pub content: String,
@@ -68,7 +120,7 @@ pub struct TodoItem {
     pub active_form: String,
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionUsage {
     pub input_tokens: u64,
@@ -80,6 +132,18 @@ pub struct SessionUsage {
     pub cost: Option<f64>,
 }
 
+impl SessionUsage {
+    /// Context used, in percent, counted the way the context meter counts it
+    /// (cache reads excluded)
+    pub fn context_percent(&self) -> f64 {
+        if self.context_window == 0 {
+            return 0.0;
+        }
+        let used = self.input_tokens + self.output_tokens + self.cache_creation_tokens;
+        used as f64 * 100.0 / self.context_window as f64
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct QuestionOption {
@@ -106,9 +170,135 @@ pub struct PendingQuestion {
     pub timestamp: i64,
 }
 
+/// An unanswered AskUserQuestion found in a transcript
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPendingQuestion {
+    pub session_id: String,
+    pub working_directory: String,
+    pub transcript_path: String,
+    pub tool_use_id: String,
+    pub questions: Vec<Question>,
+}
+
+/// Everything `emergency_stop_all` shut down
+#[derive(Clone, Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EmergencyStopReport {
+    pub interrupted_sessions: Vec<String>,
+    pub killed_slash_commands: Vec<String>,
+    pub denied_requests: Vec<String>,
+}
+
+/// Result of one post-turn verification command
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationStep {
+    pub command: String,
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Combined stdout/stderr (tail only, trimmed)
+    pub output: String,
+    /// Structured results if the output came from a known test runner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tests: Option<TestReport>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestReport {
+    /// "cargo", "jest", or "pytest"
+    pub framework: String,
+    pub passed: u32,
+    pub failed: u32,
+    pub failures: Vec<TestFailure>,
+}
+
+#[derive(Clone, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestFailure {
+    pub name: String,
+    /// file:line[:col] if the runner reported one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Claude CLI `--permission-mode` values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    Default,
+    Plan,
+    AcceptEdits,
+    BypassPermissions,
+}
+
+impl PermissionMode {
+    pub fn as_cli_arg(self) -> &'static str {
+        match self {
+            PermissionMode::Default => "default",
+            PermissionMode::Plan => "plan",
+            PermissionMode::AcceptEdits => "acceptEdits",
+            PermissionMode::BypassPermissions => "bypassPermissions",
+        }
+    }
+}
+
+/// Non-error reasons a Claude process stopped early
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionEndReason {
+    /// `--max-turns` limit reached
+    MaxTurns,
+}
+
+/// Which spend limit a budget event is about. Only sent with the
+/// `analytics` build feature.
+#[cfg_attr(not(feature = "analytics"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    pub fn label(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "Daily",
+            BudgetPeriod::Monthly => "Monthly",
+        }
+    }
+}
+
+/// Why the API refused a turn
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RateLimitKind {
+    /// 429s and plan usage limits
+    RateLimited,
+    /// 529 / `overloaded_error`
+    Overloaded,
+}
+
+/// Where a changed memory file lives
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryScope {
+    /// `~/.claude/CLAUDE.md` and `~/.claude/rules`
+    User,
+    /// A project's CLAUDE.md, CLAUDE.local.md or `.claude/rules`
+    Project,
+}
+
 /// Unified backend event payload for frontend listeners.
+/// Serialized via the impl below, which stamps the negotiated schema version.
 #[derive(Clone, Serialize, Debug)]
-#[serde(tag = "type")]
+#[serde(tag = "type", remote = "Self")]
 pub enum BackendEvent {
     #[serde(rename = "session.started")]
     SessionStarted {
@@ -116,6 +306,9 @@ pub enum BackendEvent {
         ui_session_id: String,
         #[serde(rename = "claudeSessionId")]
         claude_session_id: String,
+        /// `--permission-mode` the process was started with (None = CLI default)
+        #[serde(rename = "permissionMode")]
+        permission_mode: Option<PermissionMode>,
     },
     #[serde(rename = "session.ended")]
     SessionEnded {
@@ -125,6 +318,9 @@ pub enum BackendEvent {
         exit_code: Option<i32>,
         #[serde(skip_serializing_if = "Option::is_none")]
         error: Option<String>,
+        /// Why Claude stopped, when it wasn't a normal finish
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reason: Option<SessionEndReason>,
     },
     #[serde(rename = "message.assistant")]
     MessageAssistant {
@@ -132,6 +328,27 @@ pub enum BackendEvent {
         ui_session_id: String,
         message: Message,
     },
+    #[serde(rename = "message.thinking")]
+    MessageThinking {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(rename = "messageId")]
+        message_id: String,
+        thinking: String,
+    },
+    /// Incremental text or thinking of a message still being generated
+    /// (only with `--include-partial-messages`)
+    #[serde(rename = "message.delta")]
+    MessageDelta {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(rename = "messageId")]
+        message_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        thinking: Option<String>,
+    },
     #[serde(rename = "tool.started")]
     ToolStarted {
         #[serde(rename = "uiSessionId")]
@@ -153,6 +370,9 @@ pub enum BackendEvent {
         #[serde(rename = "toolId")]
         tool_id: String,
         output: String,
+        /// When the tool result arrived (RFC 3339)
+        #[serde(rename = "endedAt")]
+        ended_at: String,
     },
     #[serde(rename = "tool.error")]
     ToolError {
@@ -161,6 +381,8 @@ pub enum BackendEvent {
         #[serde(rename = "toolId")]
         tool_id: String,
         error: String,
+        #[serde(rename = "endedAt")]
+        ended_at: String,
     },
     #[serde(rename = "todos.updated")]
     TodosUpdated {
@@ -184,12 +406,25 @@ pub enum BackendEvent {
         tool_input: serde_json::Value,
         #[serde(rename = "uiSessionId")]
         ui_session_id: Option<String>,
+        /// The session's priority lane (see `set_session_priority`)
+        priority: SessionPriority,
     },
     #[serde(rename = "permission.resolved")]
     PermissionResolved {
         #[serde(rename = "requestId")]
         request_id: String,
     },
+    /// Countdown for a permission prompt or question, sent every few
+    /// seconds until it's answered or times out
+    #[serde(rename = "permission.expiring")]
+    PermissionExpiring {
+        #[serde(rename = "requestId")]
+        request_id: String,
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: Option<String>,
+        #[serde(rename = "secondsLeft")]
+        seconds_left: u64,
+    },
     #[serde(rename = "question.requested")]
     QuestionRequested {
         #[serde(rename = "requestId")]
@@ -201,6 +436,104 @@ pub enum BackendEvent {
         #[serde(rename = "requestId")]
         request_id: String,
     },
+    #[serde(rename = "sessions.added")]
+    SessionsAdded {
+        session: DiscoveredSession,
+    },
+    #[serde(rename = "sessions.updated")]
+    SessionsUpdated {
+        session: DiscoveredSession,
+    },
+    /// Carries the last known listing entry for the deleted transcript
+    #[serde(rename = "sessions.removed")]
+    SessionsRemoved {
+        session: DiscoveredSession,
+    },
+    /// New or changed messages in a transcript being tailed (`tail_transcript`).
+    /// With `reset`, the file was rewritten and `messages` is the whole list.
+    #[serde(rename = "transcript.appended")]
+    TranscriptAppended {
+        #[serde(rename = "claudeSessionId")]
+        claude_session_id: String,
+        #[serde(rename = "transcriptPath")]
+        transcript_path: String,
+        messages: Vec<Message>,
+        todos: Option<Vec<TodoItem>>,
+        usage: Option<SessionUsage>,
+        #[serde(rename = "totalCostUsd")]
+        total_cost_usd: Option<f64>,
+        reset: bool,
+    },
+    /// An end-of-session summary is ready (see `sessionSummaryEnabled`)
+    #[serde(rename = "session.summarized")]
+    SessionSummarized {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        summary: SessionSummary,
+    },
+    /// Context usage reached one of `contextWarningLevels` (sent once per
+    /// level until usage drops again, e.g. after compaction)
+    #[serde(rename = "usage.threshold")]
+    UsageThreshold {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        level: u32,
+        #[serde(rename = "contextPercent")]
+        context_percent: f64,
+        /// Turns left at the average growth so far; None until it's known
+        #[serde(rename = "remainingTurns")]
+        remaining_turns: Option<u32>,
+    },
+    /// Context usage passed `autoCompactPercent`; `/compact` is running
+    #[serde(rename = "session.compacting")]
+    SessionCompacting {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(rename = "contextPercent")]
+        context_percent: f64,
+    },
+    #[serde(rename = "session.compacted")]
+    SessionCompacted {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// Follow-ups to offer after a turn (see `quickReplies`); sent again
+    /// with `polished` once a background job has rewritten them
+    #[serde(rename = "suggestions.updated")]
+    SuggestionsUpdated {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        suggestions: Vec<String>,
+        polished: bool,
+    },
+    /// A session was deleted from inside the app (`delete_claude_session`)
+    #[serde(rename = "session.deleted")]
+    SessionDeleted {
+        #[serde(rename = "claudeSessionId")]
+        claude_session_id: String,
+        #[serde(rename = "removedFiles")]
+        removed_files: Vec<String>,
+    },
+    #[serde(rename = "memory.changed")]
+    MemoryChanged {
+        path: String,
+        scope: MemoryScope,
+        /// Project the file belongs to (None for user-level memory)
+        #[serde(rename = "workingDirectory")]
+        working_directory: Option<String>,
+        /// Keep-alive sessions still running with the old memory loaded
+        #[serde(rename = "affectedSessions")]
+        affected_sessions: Vec<String>,
+        /// Ask the user to restart the affected sessions
+        #[serde(rename = "promptRestart")]
+        prompt_restart: bool,
+    },
+    #[serde(rename = "questions.pending_on_startup")]
+    QuestionsPendingOnStartup {
+        sessions: Vec<SessionPendingQuestion>,
+    },
     #[serde(rename = "slash.output")]
     SlashOutput {
         #[serde(rename = "commandId")]
@@ -231,4 +564,312 @@ pub enum BackendEvent {
         command_id: String,
         message: String,
     },
+    #[serde(rename = "verification.completed")]
+    VerificationCompleted {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        passed: bool,
+        steps: Vec<VerificationStep>,
+    },
+    /// The session edited a file that other running sessions in the same
+    /// directory have also edited (sent once per session and file)
+    #[serde(rename = "workspace.conflict_risk")]
+    WorkspaceConflictRisk {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(rename = "workingDirectory")]
+        working_directory: String,
+        path: String,
+        #[serde(rename = "otherSessionIds")]
+        other_session_ids: Vec<String>,
+    },
+    #[serde(rename = "session.reader_crashed")]
+    SessionReaderCrashed {
+        #[serde(rename = "uiSessionId", skip_serializing_if = "Option::is_none")]
+        ui_session_id: Option<String>,
+        #[serde(rename = "commandId", skip_serializing_if = "Option::is_none")]
+        command_id: Option<String>,
+        /// "stdout", "stderr", "pty", or "transcript"
+        reader: String,
+        message: String,
+        /// Whether a transcript-watch fallback took over
+        fallback: bool,
+    },
+    /// A stdout line over `maxStdoutLineBytes` was dropped from the stream
+    /// (and written to `spillPath`), so the UI is missing that event
+    #[serde(rename = "session.line_truncated")]
+    SessionLineTruncated {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(rename = "lineNumber")]
+        line_number: usize,
+        bytes: u64,
+        #[serde(rename = "limitBytes")]
+        limit_bytes: usize,
+        #[serde(rename = "eventType")]
+        event_type: Option<String>,
+        /// Tool whose result was dropped, if known
+        #[serde(rename = "toolUseId")]
+        tool_use_id: Option<String>,
+        #[serde(rename = "spillPath")]
+        spill_path: Option<String>,
+    },
+    #[serde(rename = "turn.completed")]
+    TurnCompleted {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+    },
+    #[serde(rename = "session.suspended")]
+    SessionSuspended {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        /// Resumed automatically on the next message
+        #[serde(rename = "claudeSessionId")]
+        claude_session_id: String,
+        #[serde(rename = "idleSeconds")]
+        idle_seconds: u64,
+    },
+    /// `maxConcurrentSessions` processes are already running; the spawn waits
+    /// in a queue and starts when a slot frees (then session.started follows).
+    /// Re-sent with the new position when the queue moves.
+    #[serde(rename = "session.queued")]
+    SessionQueued {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        /// 1-based place in the queue
+        position: usize,
+        limit: usize,
+    },
+    /// The process died mid-turn and will be resumed after `delay_ms`
+    /// (sent instead of session.ended)
+    #[serde(rename = "session.restarting")]
+    SessionRestarting {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(rename = "claudeSessionId")]
+        claude_session_id: String,
+        /// 1-based restart attempt
+        attempt: u32,
+        #[serde(rename = "maxRetries")]
+        max_retries: u32,
+        #[serde(rename = "delayMs")]
+        delay_ms: u64,
+        #[serde(rename = "exitCode")]
+        exit_code: Option<i32>,
+    },
+    /// The API refused a turn for rate limits or overload. With retries on,
+    /// the turn is continued after `delayMs`.
+    #[serde(rename = "session.rate_limited")]
+    SessionRateLimited {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        kind: RateLimitKind,
+        message: String,
+        /// RFC 3339, when Claude says when the limit resets
+        #[serde(rename = "resetsAt")]
+        resets_at: Option<String>,
+        /// 1-based retry attempt; None when not retrying
+        attempt: Option<u32>,
+        #[serde(rename = "maxRetries")]
+        max_retries: Option<u32>,
+        #[serde(rename = "delayMs")]
+        delay_ms: Option<u64>,
+    },
+    /// Network lost or back. While offline, follow-ups are queued and sent
+    /// when it returns.
+    #[serde(rename = "connectivity.changed")]
+    ConnectivityChanged {
+        online: bool,
+        /// Messages queued while offline, sent now that we're back online
+        #[serde(rename = "queuedSends")]
+        queued_sends: usize,
+    },
+    #[serde(rename = "session.resume_failed")]
+    SessionResumeFailed {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        #[serde(rename = "claudeSessionId")]
+        claude_session_id: String,
+        reason: String,
+        /// Whether old history was found to prime a fresh session with
+        #[serde(rename = "primerAvailable")]
+        primer_available: bool,
+    },
+    #[serde(rename = "mcp.integrity_failed")]
+    McpIntegrityFailed {
+        path: String,
+        reason: String,
+    },
+    /// A configured MCP server was left out of a session's MCP config
+    #[serde(rename = "mcp.server_skipped")]
+    McpServerSkipped {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        name: String,
+        reason: String,
+    },
+    #[serde(rename = "emergency.stopped")]
+    EmergencyStopped {
+        report: EmergencyStopReport,
+    },
+    /// Spend passed `budgetWarningPercent` of a daily or monthly budget
+    #[serde(rename = "budget.warning")]
+    BudgetWarning {
+        period: BudgetPeriod,
+        #[serde(rename = "spentUsd")]
+        spent_usd: f64,
+        #[serde(rename = "limitUsd")]
+        limit_usd: f64,
+    },
+    /// Spend reached a daily or monthly budget
+    #[serde(rename = "budget.exceeded")]
+    BudgetExceeded {
+        period: BudgetPeriod,
+        #[serde(rename = "spentUsd")]
+        spent_usd: f64,
+        #[serde(rename = "limitUsd")]
+        limit_usd: f64,
+    },
+    /// One plain sentence describing the event just sent, for screen readers
+    /// and minimal log views (see `narrationEnabled`)
+    #[serde(rename = "narration")]
+    Narration {
+        #[serde(rename = "uiSessionId", skip_serializing_if = "Option::is_none")]
+        ui_session_id: Option<String>,
+        text: String,
+    },
+    #[serde(rename = "loop.iteration")]
+    LoopIteration {
+        #[serde(rename = "uiSessionId")]
+        ui_session_id: String,
+        iteration: u32,
+        #[serde(rename = "maxIterations")]
+        max_iterations: u32,
+        #[serde(rename = "costUsd")]
+        cost_usd: f64,
+        /// running, retrying, passed, failed, budgetExceeded, noEdits, unverified, stopped, error
+        status: String,
+    },
+}
+
+impl Serialize for BackendEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = BackendEvent::serialize(self, serde_json::value::Serializer)
+            .map_err(serde::ser::Error::custom)?;
+        // v1 payloads had no version field
+        let version = negotiated_schema_version();
+        if version >= 2 {
+            if let Some(obj) = value.as_object_mut() {
+                obj.insert("schemaVersion".to_string(), version.into());
+            }
+        }
+        value.serialize(serializer)
+    }
+}
+
+/// Which backend events a window wants. Windows without a filter get all.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilter {
+    /// UI sessions to receive events for (None = all). Events not tied to a
+    /// session always pass.
+    pub session_ids: Option<Vec<String>>,
+    /// Event types (`stream.delta`) or prefixes ending in `.` (`session.`)
+    /// to receive (None = all)
+    pub kinds: Option<Vec<String>>,
+}
+
+impl EventFilter {
+    fn matches(&self, payload: &serde_json::Value) -> bool {
+        if let (Some(kinds), Some(kind)) = (&self.kinds, payload.get("type").and_then(|t| t.as_str())) {
+            let wanted = kinds
+                .iter()
+                .any(|k| k == kind || (k.ends_with('.') && kind.starts_with(k.as_str())));
+            if !wanted {
+                return false;
+            }
+        }
+        match (&self.session_ids, payload.get("uiSessionId").and_then(|s| s.as_str())) {
+            (Some(ids), Some(id)) => ids.iter().any(|i| i == id),
+            _ => true,
+        }
+    }
+}
+
+/// Event filters by window label
+static SUBSCRIPTIONS: Lazy<Mutex<HashMap<String, EventFilter>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Set (or with None, clear) the filter for a window's `horseman-event`s
+pub fn subscribe(window_label: &str, filter: Option<EventFilter>) {
+    let mut subscriptions = panic_guard::lock_or_recover(&SUBSCRIPTIONS, "event subscriptions");
+    match filter {
+        Some(filter) => subscriptions.insert(window_label.to_string(), filter),
+        None => subscriptions.remove(window_label),
+    };
+}
+
+/// Emit a `horseman-event` to every window whose filter it matches
+pub fn emit(app: &AppHandle, event: BackendEvent) -> tauri::Result<()> {
+    profile_span!("emit_event");
+    let narration = narration::narrate(&event);
+    send(app, event)?;
+    match narration {
+        Some(narration) => send(app, narration),
+        None => Ok(()),
+    }
+}
+
+fn send(app: &AppHandle, event: BackendEvent) -> tauri::Result<()> {
+    let subscriptions = panic_guard::lock_or_recover(&SUBSCRIPTIONS, "event subscriptions").clone();
+    let redactor = redaction::current();
+    if subscriptions.is_empty() && redactor.is_none() {
+        return app.emit("horseman-event", event);
+    }
+    let mut payload = serde_json::to_value(&event)?;
+    // Every event the UI gets passes here, whatever its variant
+    if let Some(redactor) = redactor {
+        redactor.redact_json(&mut payload);
+    }
+    if subscriptions.is_empty() {
+        return app.emit("horseman-event", payload);
+    }
+    app.emit_filter("horseman-event", &payload, |target| {
+        let label = match target {
+            EventTarget::Window { label } | EventTarget::Webview { label } | EventTarget::WebviewWindow { label } => {
+                label
+            }
+            _ => return true,
+        };
+        subscriptions.get(label).is_none_or(|filter| filter.matches(&payload))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_carry_type_tag_and_schema_version() {
+        let event = BackendEvent::SlashStarted { command_id: "c1".to_string() };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "slash.started");
+        assert_eq!(json["commandId"], "c1");
+        assert_eq!(json["schemaVersion"], SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn filters_by_session_and_kind() {
+        let filter = EventFilter {
+            session_ids: Some(vec!["s1".to_string()]),
+            kinds: Some(vec!["session.".to_string(), "slash.started".to_string()]),
+        };
+        let started = |id: &str| {
+            serde_json::json!({ "type": "session.started", "uiSessionId": id, "claudeSessionId": "c" })
+        };
+        assert!(filter.matches(&started("s1")));
+        assert!(!filter.matches(&started("s2")));
+        assert!(filter.matches(&serde_json::json!({ "type": "slash.started", "commandId": "c1" })));
+        assert!(!filter.matches(&serde_json::json!({ "type": "stream.delta", "uiSessionId": "s1" })));
+        assert!(EventFilter::default().matches(&started("s2")));
+    }
 }
@@ -0,0 +1,324 @@
+//! Open an Edit/MultiEdit/Write tool call in the user's own diff tool
+//! (`externalDiffTool`, e.g. `ksdiff`, `meld` or `code --diff --wait`).
+//! The call is looked up in its transcript, and before and after are
+//! rebuilt from the file as Claude Code recorded it before the call (or,
+//! failing that, the file on disk), written to temp files and handed to
+//! the tool. Temp files are kept a day, since the tool may outlive Horseman.
+
+use crate::claude::{extract_agent_id_from_result, normalize_output, transcript_lines, MAX_SUBAGENT_DEPTH};
+use crate::config;
+use crate::debug_log;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Diffs older than this are removed when the next one is opened
+const KEEP_DIFFS_FOR: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Tools whose input is enough to show the change they made
+const FILE_CHANGE_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalDiff {
+    pub file_path: String,
+    pub before_path: String,
+    pub after_path: String,
+    /// False when the file no longer matches the edit, so only the edited
+    /// snippets could be shown
+    pub whole_file: bool,
+}
+
+fn text<'a>(input: &'a serde_json::Value, key: &str) -> &'a str {
+    input.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+/// Edits as (old, new, replace_all), in the order they were applied
+fn edits(name: &str, input: &serde_json::Value) -> Vec<(String, String, bool)> {
+    let edit = |e: &serde_json::Value| {
+        let replace_all = e.get("replace_all").and_then(|v| v.as_bool()).unwrap_or(false);
+        (text(e, "old_string").to_string(), text(e, "new_string").to_string(), replace_all)
+    };
+    match name {
+        "MultiEdit" => input
+            .get("edits")
+            .and_then(|v| v.as_array())
+            .map(|edits| edits.iter().map(edit).collect())
+            .unwrap_or_default(),
+        _ => vec![edit(input)],
+    }
+}
+
+/// A file-changing tool call as its transcript recorded it
+struct RecordedCall {
+    name: String,
+    input: serde_json::Value,
+    /// The file before the call, from the result's `toolUseResult` ("" when
+    /// a Write created it)
+    original: Option<String>,
+}
+
+fn content_items(event: &serde_json::Value) -> impl Iterator<Item = &serde_json::Value> {
+    event.pointer("/message/content").and_then(|c| c.as_array()).into_iter().flatten()
+}
+
+fn original_file(event: &serde_json::Value) -> Option<String> {
+    let result = event.get("toolUseResult")?;
+    match result.get("originalFile").and_then(|v| v.as_str()) {
+        Some(original) => Some(original.to_string()),
+        None => (result.get("type").and_then(|v| v.as_str()) == Some("create")).then(String::new),
+    }
+}
+
+/// Find tool call `tool_id` in a transcript, or in the subagent transcripts
+/// of the Task calls in it
+fn find_call(transcript_path: &Path, tool_id: &str, depth: usize) -> Option<RecordedCall> {
+    let mut call: Option<RecordedCall> = None;
+    let mut agent_ids = Vec::new();
+    for line in transcript_lines(transcript_path).ok()? {
+        if !line.contains(tool_id) && !line.contains("agentId") {
+            continue;
+        }
+        let event: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        for item in content_items(&event) {
+            let is_call = |key: &str| item.get(key).and_then(|v| v.as_str()) == Some(tool_id);
+            match item.get("type").and_then(|v| v.as_str()) {
+                Some("tool_use") if is_call("id") => {
+                    call = Some(RecordedCall {
+                        name: text(item, "name").to_string(),
+                        input: item.get("input").cloned().unwrap_or_default(),
+                        original: None,
+                    });
+                }
+                Some("tool_result") if is_call("tool_use_id") => {
+                    if let Some(call) = call.as_mut() {
+                        call.original = original_file(&event);
+                    }
+                    return call;
+                }
+                Some("tool_result") => {
+                    agent_ids.extend(extract_agent_id_from_result(&normalize_output(item.get("content"))));
+                }
+                _ => {}
+            }
+        }
+    }
+    // Found but not finished yet
+    if call.is_some() || depth >= MAX_SUBAGENT_DEPTH {
+        return call;
+    }
+    let dir = transcript_path.parent()?;
+    agent_ids.iter().find_map(|id| find_call(&dir.join(format!("{}.jsonl", id)), tool_id, depth + 1))
+}
+
+/// `original` with the call applied, None if an edit doesn't match it
+fn apply(name: &str, input: &serde_json::Value, original: &str) -> Option<String> {
+    if name == "Write" {
+        return Some(text(input, "content").to_string());
+    }
+    let mut after = original.to_string();
+    for (old, new, replace_all) in edits(name, input) {
+        if old.is_empty() || !after.contains(old.as_str()) {
+            return None;
+        }
+        after = if replace_all { after.replace(&old, &new) } else { after.replacen(&old, &new, 1) };
+    }
+    Some(after)
+}
+
+/// Before and after contents of a file-changing tool call. With the file
+/// as it was before the call, the call is replayed on it; otherwise edits
+/// are undone on the current file when it still contains them. Failing
+/// both, only the replaced snippets (or a Write's content) are returned.
+/// The bool is whether the whole file was rebuilt.
+fn reconstruct(
+    name: &str,
+    input: &serde_json::Value,
+    original: Option<&str>,
+    current: Option<&str>,
+) -> (String, String, bool) {
+    if let Some(original) = original {
+        if let Some(after) = apply(name, input, original) {
+            return (original.to_string(), after, true);
+        }
+    }
+    if name == "Write" {
+        return (String::new(), text(input, "content").to_string(), false);
+    }
+    let edits = edits(name, input);
+    if let Some(current) = current {
+        let mut before = current.to_string();
+        let mut undone = true;
+        for (old, new, replace_all) in edits.iter().rev() {
+            if new.is_empty() || !before.contains(new.as_str()) {
+                undone = false;
+                break;
+            }
+            before = if *replace_all { before.replace(new, old) } else { before.replacen(new, old, 1) };
+        }
+        if undone {
+            return (before, current.to_string(), true);
+        }
+    }
+    let join = |pick: fn(&(String, String, bool)) -> &String| {
+        edits.iter().map(|e| pick(e).as_str()).collect::<Vec<_>>().join("\n\n")
+    };
+    (join(|e| &e.0), join(|e| &e.1), false)
+}
+
+fn quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"))
+}
+
+fn launch(tool: &str, before: &Path, after: &Path) -> Result<(), String> {
+    let command = if tool.contains("{before}") || tool.contains("{after}") {
+        tool.replace("{before}", &quote(before)).replace("{after}", &quote(after))
+    } else {
+        format!("{} {} {}", tool, quote(before), quote(after))
+    };
+    debug_log!("EXTERNAL_DIFF", "Launching: {}", command);
+    // Login shell, so tools installed via Homebrew etc. are on PATH
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+    let mut child = Command::new(&shell)
+        .args(["-l", "-c", &command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to launch diff tool: {}", e))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Remove diffs older than `KEEP_DIFFS_FOR`
+fn prune(root: &Path) {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > KEEP_DIFFS_FOR);
+        if stale {
+            if let Err(e) = fs::remove_dir_all(entry.path()) {
+                debug_log!("EXTERNAL_DIFF", "Failed to remove {:?}: {}", entry.path(), e);
+            }
+        }
+    }
+}
+
+/// Write the before/after of tool call `tool_id` in `transcript_path` to
+/// temp files and open them in the configured diff tool
+pub fn open(tool_id: &str, transcript_path: &Path) -> Result<ExternalDiff, String> {
+    let tool = config::external_diff_tool()
+        .ok_or("No external diff tool configured (set externalDiffTool)")?;
+    let call = find_call(transcript_path, tool_id, 0)
+        .filter(|call| FILE_CHANGE_TOOLS.contains(&call.name.as_str()))
+        .ok_or_else(|| format!("No Edit or Write tool call found: {}", tool_id))?;
+    let file_path = text(&call.input, "file_path").to_string();
+    let path = Path::new(&file_path);
+    let current = fs::read_to_string(path).ok();
+    let original = call.original.as_deref();
+    let (before, after, whole_file) = reconstruct(&call.name, &call.input, original, current.as_deref());
+
+    // Keep the file name so the diff tool picks the right syntax highlighting
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let safe_id: String = tool_id.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-').collect();
+    let root = std::env::temp_dir().join("horseman-diff");
+    prune(&root);
+    let dir: PathBuf = root.join(safe_id);
+    let before_path = dir.join("before").join(&file_name);
+    let after_path = dir.join("after").join(&file_name);
+    for (target, content) in [(&before_path, &before), (&after_path, &after)] {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+        }
+        fs::write(target, content).map_err(|e| format!("Failed to write {:?}: {}", target, e))?;
+    }
+
+    launch(&tool, &before_path, &after_path)?;
+    Ok(ExternalDiff {
+        file_path,
+        before_path: before_path.to_string_lossy().to_string(),
+        after_path: after_path.to_string_lossy().to_string(),
+        whole_file,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn undoes_edits_on_the_current_file() {
+        let input = json!({"edits": [
+            {"old_string": "a", "new_string": "b"},
+            {"old_string": "x", "new_string": "y", "replace_all": true},
+        ]});
+        let (before, after, whole) = reconstruct("MultiEdit", &input, None, Some("b y y"));
+        assert_eq!((before.as_str(), after.as_str(), whole), ("a x x", "b y y", true));
+
+        let edit = json!({"old_string": "old", "new_string": "new"});
+        let (before, after, whole) = reconstruct("Edit", &edit, None, Some("changed since"));
+        assert_eq!((before.as_str(), after.as_str(), whole), ("old", "new", false));
+    }
+
+    #[test]
+    fn replays_calls_found_in_subagent_transcripts() {
+        let dir = std::env::temp_dir().join(format!("horseman-external-diff-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let tool_use = |id: &str, name: &str, input: serde_json::Value| {
+            json!({"type": "assistant", "message": {"content": [
+                {"type": "tool_use", "id": id, "name": name, "input": input},
+            ]}})
+        };
+        let tool_result = |id: &str, content: &str, result: serde_json::Value| {
+            json!({"type": "user", "toolUseResult": result, "message": {"content": [
+                {"type": "tool_result", "tool_use_id": id, "content": content},
+            ]}})
+        };
+        let main = [
+            tool_use("w1", "Write", json!({"file_path": "/p/a.rs", "content": "new"})),
+            tool_result("w1", "ok", json!({"type": "update", "originalFile": "uncommitted"})),
+            tool_use("t1", "Task", json!({"prompt": "edit"})),
+            tool_result("t1", "done\nagentId: agent-1", json!({})),
+        ];
+        let agent = [
+            tool_use("e1", "Edit", json!({"file_path": "/p/b.rs", "old_string": "a", "new_string": "b"})),
+            tool_result("e1", "ok", json!({"originalFile": "a a"})),
+        ];
+        let write = |name: &str, events: &[serde_json::Value]| {
+            let lines: Vec<String> = events.iter().map(|e| e.to_string()).collect();
+            fs::write(dir.join(name), lines.join("\n")).unwrap();
+        };
+        write("main.jsonl", &main);
+        write("agent-1.jsonl", &agent);
+
+        let call = find_call(&dir.join("main.jsonl"), "w1", 0).unwrap();
+        let (before, after, whole) = reconstruct(&call.name, &call.input, call.original.as_deref(), None);
+        assert_eq!((before.as_str(), after.as_str(), whole), ("uncommitted", "new", true));
+
+        // The file has changed since, but the recorded original still replays
+        let call = find_call(&dir.join("main.jsonl"), "e1", 0).unwrap();
+        let (before, after, whole) = reconstruct(&call.name, &call.input, call.original.as_deref(), Some("c"));
+        assert_eq!((before.as_str(), after.as_str(), whole), ("a a", "b a", true));
+
+        assert!(find_call(&dir.join("main.jsonl"), "missing", 0).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
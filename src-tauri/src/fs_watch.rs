@@ -0,0 +1,272 @@
+//! File watching with a polling fallback. Native events (inotify, FSEvents)
+//! never arrive on some filesystems, like network mounts and some container
+//! volumes, so a watcher switches to `notify`'s poll watcher when the native
+//! one can't be created, a watch fails, or a path is on a network
+//! filesystem. Polling is adaptive: frequent right after a change, backing
+//! off while the files are quiet. `get_diagnostics` lists each watcher's mode.
+
+use crate::config;
+use crate::debug_log;
+use crate::panic_guard;
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub type EventSender = mpsc::Sender<notify::Result<Event>>;
+
+/// Poll interval right after a change
+const POLL_MIN: Duration = Duration::from_secs(1);
+/// Poll interval once the files have been quiet for a while
+const POLL_MAX: Duration = Duration::from_secs(16);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WatchMode {
+    Native,
+    Polling,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    pub name: String,
+    pub mode: WatchMode,
+    /// Why the watcher is polling
+    pub reason: Option<String>,
+    pub watched_paths: usize,
+    /// Current poll interval (polling only)
+    pub poll_interval_ms: Option<u64>,
+}
+
+static STATUS: Lazy<Mutex<BTreeMap<String, WatcherStatus>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Every live watcher and how it gets its events
+pub fn statuses() -> Vec<WatcherStatus> {
+    panic_guard::lock_or_recover(&STATUS, "watcher status").values().cloned().collect()
+}
+
+enum Backend {
+    Native(RecommendedWatcher),
+    Polling(PollWatcher),
+}
+
+pub struct FsWatcher {
+    name: String,
+    tx: EventSender,
+    backend: Backend,
+    paths: Vec<(PathBuf, RecursiveMode)>,
+    reason: Option<String>,
+    /// Set by the poll watcher's handler when it reported a change
+    activity: Arc<AtomicBool>,
+    interval: Duration,
+    next_poll: Instant,
+}
+
+/// Next poll interval: back to the minimum after a change, else doubled
+fn next_interval(current: Duration, changed: bool) -> Duration {
+    if changed {
+        POLL_MIN
+    } else {
+        (current * 2).min(POLL_MAX)
+    }
+}
+
+fn poll_watcher(tx: &EventSender, activity: &Arc<AtomicBool>) -> Result<PollWatcher, String> {
+    let tx = tx.clone();
+    let activity = activity.clone();
+    let handler = move |event: notify::Result<Event>| {
+        if event.is_ok() {
+            activity.store(true, Ordering::Relaxed);
+        }
+        let _ = tx.send(event);
+    };
+    PollWatcher::new(handler, Config::default().with_manual_polling())
+        .map_err(|e| format!("Failed to create poll watcher: {}", e))
+}
+
+/// Linux `statfs` magic numbers of network and FUSE filesystems
+#[cfg(target_os = "linux")]
+const REMOTE_FS_MAGIC: &[u32] = &[
+    0x6969,     // NFS
+    0x517B,     // SMB
+    0xFF534D42, // CIFS
+    0xFE534D42, // SMB2
+    0x65735546, // FUSE (sshfs, ...)
+    0x01021997, // 9P (VM and container shares)
+    0x5346414F, // AFS
+];
+
+/// Whether `path` is on a filesystem that doesn't deliver native events
+#[cfg(target_os = "linux")]
+fn remote_filesystem(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = match std::ffi::CString::new(path.as_os_str().as_bytes()) {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return false;
+    }
+    REMOTE_FS_MAGIC.contains(&(stat.f_type as u32))
+}
+
+/// Whether `path` is on a filesystem that doesn't deliver native events
+#[cfg(target_os = "macos")]
+fn remote_filesystem(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = match std::ffi::CString::new(path.as_os_str().as_bytes()) {
+        Ok(p) => p,
+        Err(_) => return false,
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return false;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    matches!(name.to_bytes(), b"nfs" | b"smbfs" | b"afpfs" | b"webdav" | b"cifs")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn remote_filesystem(_path: &Path) -> bool {
+    false
+}
+
+impl FsWatcher {
+    /// Create a watcher sending to `tx`. `name` identifies it in diagnostics.
+    pub fn new(name: &str, tx: EventSender) -> Result<Self, String> {
+        let activity = Arc::new(AtomicBool::new(false));
+        let forced = config::poll_file_watchers();
+        let (backend, reason) = if forced == Some(true) {
+            (Backend::Polling(poll_watcher(&tx, &activity)?), Some("pollFileWatchers is on".to_string()))
+        } else {
+            match notify::recommended_watcher(tx.clone()) {
+                Ok(watcher) => (Backend::Native(watcher), None),
+                Err(e) if forced.is_none() => (
+                    Backend::Polling(poll_watcher(&tx, &activity)?),
+                    Some(format!("Native watcher unavailable: {}", e)),
+                ),
+                Err(e) => return Err(format!("Failed to create watcher: {}", e)),
+            }
+        };
+        let watcher = Self {
+            name: name.to_string(),
+            tx,
+            backend,
+            paths: Vec::new(),
+            reason,
+            activity,
+            interval: POLL_MIN,
+            next_poll: Instant::now(),
+        };
+        watcher.update_status();
+        Ok(watcher)
+    }
+
+    pub fn mode(&self) -> WatchMode {
+        match self.backend {
+            Backend::Native(_) => WatchMode::Native,
+            Backend::Polling(_) => WatchMode::Polling,
+        }
+    }
+
+    /// Watch `path`, switching the whole watcher to polling if it can't be
+    /// watched natively (unless `pollFileWatchers` is false)
+    pub fn watch(&mut self, path: &Path, mode: RecursiveMode) -> Result<(), String> {
+        if let Backend::Native(ref mut watcher) = self.backend {
+            let auto = config::poll_file_watchers().is_none();
+            let native = if auto && remote_filesystem(path) {
+                Err("network filesystem".to_string())
+            } else {
+                watcher.watch(path, mode).map_err(|e| e.to_string())
+            };
+            match native {
+                Ok(()) => {}
+                Err(e) if auto => self.switch_to_polling(format!("Can't watch {:?} natively: {}", path, e))?,
+                Err(e) => return Err(format!("Failed to watch {:?}: {}", path, e)),
+            }
+        }
+        if let Backend::Polling(ref mut watcher) = self.backend {
+            watcher
+                .watch(path, mode)
+                .map_err(|e| format!("Failed to watch {:?}: {}", path, e))?;
+        }
+        self.paths.push((path.to_path_buf(), mode));
+        self.update_status();
+        Ok(())
+    }
+
+    fn switch_to_polling(&mut self, reason: String) -> Result<(), String> {
+        debug_log!("FS_WATCH", "[{}] Switching to polling: {}", self.name, reason);
+        let mut watcher = poll_watcher(&self.tx, &self.activity)?;
+        for (path, mode) in &self.paths {
+            if let Err(e) = watcher.watch(path, *mode) {
+                debug_log!("FS_WATCH", "[{}] Failed to poll {:?}: {}", self.name, path, e);
+            }
+        }
+        self.backend = Backend::Polling(watcher);
+        self.reason = Some(reason);
+        self.interval = POLL_MIN;
+        self.next_poll = Instant::now();
+        Ok(())
+    }
+
+    /// Poll for changes if due. A no-op for native watchers; event loops call
+    /// it on every wakeup (they wake at least every second or so).
+    pub fn tick(&mut self) {
+        let watcher = match self.backend {
+            Backend::Polling(ref watcher) => watcher,
+            Backend::Native(_) => return,
+        };
+        if Instant::now() < self.next_poll {
+            return;
+        }
+        if let Err(e) = watcher.poll() {
+            debug_log!("FS_WATCH", "[{}] Poll failed: {}", self.name, e);
+        }
+        let interval = next_interval(self.interval, self.activity.swap(false, Ordering::Relaxed));
+        self.next_poll = Instant::now() + interval;
+        if interval != self.interval {
+            self.interval = interval;
+            self.update_status();
+        }
+    }
+
+    fn update_status(&self) {
+        let status = WatcherStatus {
+            name: self.name.clone(),
+            mode: self.mode(),
+            reason: self.reason.clone(),
+            watched_paths: self.paths.len(),
+            poll_interval_ms: (self.mode() == WatchMode::Polling).then_some(self.interval.as_millis() as u64),
+        };
+        panic_guard::lock_or_recover(&STATUS, "watcher status").insert(self.name.clone(), status);
+    }
+}
+
+impl Drop for FsWatcher {
+    fn drop(&mut self) {
+        panic_guard::lock_or_recover(&STATUS, "watcher status").remove(&self.name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polling_backs_off_while_quiet() {
+        let mut interval = POLL_MIN;
+        for _ in 0..10 {
+            interval = next_interval(interval, false);
+        }
+        assert_eq!(interval, POLL_MAX);
+        assert_eq!(next_interval(interval, true), POLL_MIN);
+        assert_eq!(next_interval(POLL_MIN, false), POLL_MIN * 2);
+    }
+}
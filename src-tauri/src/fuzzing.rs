@@ -0,0 +1,80 @@
+//! Entry points for the cargo-fuzz targets in `src-tauri/fuzz`. Only built
+//! with the `fuzzing` feature; each function must never panic, whatever the
+//! input. They take raw bytes and decode them the way the app does, so
+//! invalid UTF-8 and oversized lines reach the same code as in production.
+
+use crate::claude::process::{parse_transcript_lines, BoundedLines};
+use crate::claude::{
+    extract_agent_id_from_result, normalize_output, parse_assistant_event, parse_usage, process_event,
+    resume_recovery, schema_drift, StreamTrackingState,
+};
+use std::sync::{Arc, Mutex};
+
+/// Line limit for fuzz input, low enough that inputs reach the stand-ins
+/// for oversized lines
+const MAX_LINE_BYTES: usize = 4096;
+
+/// `data` split into lines like the stdout reader and transcript parser
+/// split them: on `\n`, each line decoded lossily, oversized ones replaced
+fn lines(data: &[u8]) -> BoundedLines<&[u8]> {
+    BoundedLines::new(data, MAX_LINE_BYTES)
+}
+
+/// Parse a whole transcript file's content
+pub fn parse_transcript(data: &[u8]) {
+    let _ = parse_transcript_lines(lines(data));
+    // Recovery reads the file as a string, skipping it if that fails
+    if let Ok(content) = std::str::from_utf8(data) {
+        let _ = resume_recovery::condense(content);
+    }
+}
+
+/// Run a session's stdout through `process_event` line by line, sharing one
+/// session's tracking state (without an app, so nothing is emitted)
+pub fn process_events(data: &[u8]) {
+    let tracking = Arc::new(Mutex::new(StreamTrackingState::default()));
+    for line in lines(data) {
+        if let Ok(event) = serde_json::from_str::<serde_json::Value>(&line) {
+            let _ = process_event(&event, &tracking, None, "fuzz");
+        }
+    }
+}
+
+/// Run each stdout line through the parts of stream-event processing that
+/// don't need a running app (emitting events is skipped)
+pub fn process_stream(data: &[u8]) {
+    for line in lines(data) {
+        process_stream_line(&line);
+    }
+}
+
+fn process_stream_line(line: &str) {
+    let event: serde_json::Value = match serde_json::from_str(line) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    let _ = schema_drift::check_event(&event);
+
+    let tracking = Arc::new(Mutex::new(StreamTrackingState::default()));
+    match event.get("type").and_then(|t| t.as_str()) {
+        Some("assistant") => {
+            // Streaming and transcript modes take different paths
+            let _ = parse_assistant_event(&event, &tracking, true);
+            let _ = parse_assistant_event(&event, &tracking, false);
+        }
+        Some("user") => {
+            let blocks = event
+                .get("message")
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_array());
+            for item in blocks.into_iter().flatten() {
+                let output = normalize_output(item.get("content"));
+                let _ = extract_agent_id_from_result(&output);
+            }
+        }
+        Some("result") => {
+            let _ = parse_usage(&event);
+        }
+        _ => {}
+    }
+}
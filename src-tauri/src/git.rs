@@ -0,0 +1,133 @@
+//! Git branch/worktree lookup for session naming and filtering, and the
+//! working-tree diff that session summaries (suggested commit messages)
+//! are written from.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::Command;
+
+/// Transcript lines to scan for a `gitBranch` before giving up
+const TRANSCRIPT_SCAN_LINES: usize = 50;
+
+/// Where a session was started, as far as git is concerned
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitContext {
+    /// Checked-out branch; None on a detached HEAD
+    pub branch: Option<String>,
+    /// Top-level directory of the worktree
+    pub worktree: Option<String>,
+}
+
+fn rev_parse(dir: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(["-C", dir, "rev-parse"])
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Branch and worktree for `dir`, None outside a git repository
+pub fn context(dir: &str) -> Option<GitContext> {
+    let worktree = rev_parse(dir, &["--show-toplevel"])?;
+    let branch = rev_parse(dir, &["--abbrev-ref", "HEAD"]).filter(|b| b != "HEAD");
+    Some(GitContext {
+        branch,
+        worktree: Some(worktree),
+    })
+}
+
+/// Commit checked out in `dir`, None outside a git repository or before
+/// the first commit
+pub fn head_commit(dir: &str) -> Option<String> {
+    rev_parse(dir, &["HEAD"])
+}
+
+/// Branch Claude logged in a transcript (`gitBranch` on each entry), for
+/// sessions Horseman didn't spawn
+pub fn branch_from_transcript(path: &Path) -> Option<String> {
+    let reader = BufReader::new(fs::File::open(path).ok()?);
+    reader
+        .lines()
+        .map_while(Result::ok)
+        .take(TRANSCRIPT_SCAN_LINES)
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(&line).ok())
+        .find_map(|event| {
+            event
+                .get("gitBranch")
+                .and_then(|v| v.as_str())
+                .filter(|b| !b.is_empty() && *b != "HEAD")
+                .map(str::to_string)
+        })
+}
+
+fn git_output(dir: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(["-C", dir]).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Uncommitted changes in `dir` (stat, untracked files and the diff against
+/// HEAD, truncated to `max_chars`), or None when the tree is clean
+pub fn working_diff(dir: &str, max_chars: usize) -> Option<String> {
+    let stat = git_output(dir, &["diff", "HEAD", "--stat"])?;
+    let untracked = git_output(dir, &["ls-files", "--others", "--exclude-standard"]).unwrap_or_default();
+    if stat.trim().is_empty() && untracked.trim().is_empty() {
+        return None;
+    }
+    let mut diff = git_output(dir, &["diff", "HEAD"]).unwrap_or_default();
+    if diff.len() > max_chars {
+        let mut end = max_chars;
+        while !diff.is_char_boundary(end) {
+            end -= 1;
+        }
+        diff.truncate(end);
+        diff.push_str("\n[diff truncated]\n");
+    }
+    let mut out = stat;
+    if !untracked.trim().is_empty() {
+        out.push_str("\nUntracked files:\n");
+        out.push_str(&untracked);
+    }
+    out.push('\n');
+    out.push_str(&diff);
+    Some(out)
+}
+
+/// Identifies a working-tree state, so a summary can tell whether it still
+/// describes the current changes
+pub fn diff_hash(diff: &str) -> String {
+    format!("{:x}", Sha256::digest(diff.as_bytes()))
+}
+
+/// Default session title: `branch — first prompt`, or whichever is known
+pub fn default_title(branch: Option<&str>, first_prompt: Option<&str>) -> Option<String> {
+    match (branch, first_prompt) {
+        (Some(branch), Some(prompt)) => Some(format!("{} — {}", branch, prompt)),
+        (Some(branch), None) => Some(branch.to_string()),
+        (None, prompt) => prompt.map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_prefixes_branch() {
+        assert_eq!(
+            default_title(Some("feat/login"), Some("Fix the form")).as_deref(),
+            Some("feat/login — Fix the form")
+        );
+        assert_eq!(default_title(None, Some("Fix the form")).as_deref(), Some("Fix the form"));
+        assert_eq!(default_title(Some("main"), None).as_deref(), Some("main"));
+        assert_eq!(default_title(None, None), None);
+    }
+}
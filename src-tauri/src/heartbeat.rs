@@ -0,0 +1,89 @@
+//! Runtime state file for external supervisors: `<config dir>/heartbeat.json`
+//! holds the PID, hook port and session counts, rewritten every few seconds
+//! and removed on exit. `horseman-mcp --status` reads it. The writer takes
+//! the ClaudeManager lock, so a wedged manager shows up as a stale file.
+
+use crate::atomic_file;
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::supervisor;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const INTERVAL: Duration = Duration::from_secs(5);
+
+/// Set on exit so the writer doesn't put the file back
+static STOPPED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Heartbeat {
+    pid: u32,
+    version: &'static str,
+    hook_port: u16,
+    running_sessions: usize,
+    queued_sessions: usize,
+    /// Unix millis
+    started_at: u64,
+    updated_at: u64,
+    /// How often `updated_at` advances, so readers can tell a stale file
+    interval_ms: u64,
+}
+
+fn heartbeat_path() -> Option<PathBuf> {
+    config::config_dir().map(|d| d.join("heartbeat.json"))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn write(app: &AppHandle, path: &Path, hook_port: u16, started_at: u64) -> Result<(), String> {
+    let (running_sessions, queued_sessions) = {
+        let state = app.state::<ClaudeState>();
+        let mut manager = state.lock();
+        (manager.running_sessions(), manager.queued_sessions())
+    };
+    let heartbeat = Heartbeat {
+        pid: std::process::id(),
+        version: env!("CARGO_PKG_VERSION"),
+        hook_port,
+        running_sessions,
+        queued_sessions,
+        started_at,
+        updated_at: now_ms(),
+        interval_ms: INTERVAL.as_millis() as u64,
+    };
+    let content = serde_json::to_string_pretty(&heartbeat).map_err(|e| e.to_string())?;
+    atomic_file::write(path, content).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Start rewriting the heartbeat file
+pub fn spawn(app: AppHandle, hook_port: u16) {
+    let path = match heartbeat_path() {
+        Some(path) => path,
+        None => return,
+    };
+    let started_at = now_ms();
+    supervisor::spawn_thread("heartbeat", move || loop {
+        // After `remove`, leave the file gone until the process exits
+        if !STOPPED.load(Ordering::SeqCst) {
+            if let Err(e) = write(&app, &path, hook_port, started_at) {
+                debug_log!("HEARTBEAT", "{}", e);
+            }
+        }
+        std::thread::sleep(INTERVAL);
+    });
+}
+
+/// Remove the file so readers see Horseman as stopped rather than hung
+pub fn remove() {
+    STOPPED.store(true, Ordering::SeqCst);
+    if let Some(path) = heartbeat_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,132 @@
+//! Auto-approval of Bash commands by prefix. A rule like `cargo build`
+//! allows `cargo build` and `cargo build --release`, but not `cargo
+//! build-std` or anything chained after it: commands that combine several
+//! with `&&`, pipes, redirects or substitutions always ask.
+
+use crate::config;
+
+/// Shell syntax that could run something besides the matched command
+const COMPOUND_MARKERS: &[&str] = &["&", "|", ";", "`", "$(", ">", "<", "\n", "\r"];
+
+fn is_compound(command: &str) -> bool {
+    COMPOUND_MARKERS.iter().any(|m| command.contains(m))
+}
+
+/// Whether `command` is `prefix` or `prefix` followed by more arguments
+pub fn matches(command: &str, prefix: &str) -> bool {
+    let (command, prefix) = (command.trim(), prefix.trim());
+    if prefix.is_empty() || is_compound(command) {
+        return false;
+    }
+    match command.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with(char::is_whitespace),
+        None => false,
+    }
+}
+
+/// The configured rule that allows `command`, if any
+pub fn allowing_rule(command: &str) -> Option<String> {
+    config::bash_allow_prefixes().into_iter().find(|rule| matches(command, rule))
+}
+
+/// Programs that run whatever their arguments say, so no rule starting
+/// with them is safe
+const WRAPPERS: &[&str] = &[
+    "bash", "sh", "zsh", "fish", "dash", "ksh", "python", "python3", "node", "deno", "bun", "perl", "ruby", "php",
+    "env", "sudo", "doas", "su", "xargs", "eval", "exec", "command", "builtin", "nohup", "nice", "time", "timeout",
+    "watch", "ssh",
+];
+
+fn is_wrapper(program: &str) -> bool {
+    let name = program.rsplit('/').next().unwrap_or(program);
+    WRAPPERS.contains(&name)
+}
+
+/// `command` without its leading `VAR=value` assignments and wrapper
+/// programs with their options (`sudo -E`, `env X=1`, `timeout 10`), so a
+/// deny rule for `rm` also catches `sudo rm`
+pub fn strip_wrappers(command: &str) -> &str {
+    let mut rest = command.trim_start();
+    let mut after_wrapper = false;
+    while let Some(word) = rest.split_whitespace().next() {
+        let is_assignment = word
+            .split_once('=')
+            .is_some_and(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        let is_option = after_wrapper && (word.starts_with('-') || word.starts_with(|c: char| c.is_ascii_digit()));
+        if is_wrapper(word) {
+            after_wrapper = true;
+        } else if !is_assignment && !is_option {
+            break;
+        }
+        rest = rest[word.len()..].trim_start();
+    }
+    // `bash -c 'rm -rf /'`
+    rest.trim_start_matches(['\'', '"'])
+}
+
+/// A rule for commands like `command`: the program plus its subcommand
+/// (`git status`, `npm test`), otherwise the whole command. Never the bare
+/// program, and never for interpreters and wrappers like `bash` or `sudo`.
+pub fn suggest_prefix(command: &str) -> Option<String> {
+    if is_compound(command) {
+        return None;
+    }
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let is_subcommand = |w: &str| {
+        !w.starts_with('-') && w.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == ':')
+    };
+    match words.as_slice() {
+        [] | [_] => None,
+        [program, ..] if is_wrapper(program) => None,
+        [program, subcommand, ..] if is_subcommand(subcommand) => Some(format!("{} {}", program, subcommand)),
+        _ => Some(words.join(" ")),
+    }
+}
+
+/// Save a rule for `command` (or the given `prefix`, which must match it)
+/// to `bashAllowPrefixes`. Returns the rule.
+pub fn promote(command: &str, prefix: Option<String>) -> Result<String, String> {
+    let rule = match prefix {
+        Some(prefix) if prefix.split_whitespace().next().is_some_and(is_wrapper) => {
+            return Err(format!("`{}` can run any command, so it can't be always allowed", prefix.trim()))
+        }
+        Some(prefix) if matches(command, &prefix) => prefix.trim().to_string(),
+        Some(prefix) => return Err(format!("`{}` does not match `{}`", prefix, command)),
+        None => suggest_prefix(command).ok_or_else(|| format!("No rule can safely allow `{}`", command))?,
+    };
+    let snapshot = config::get_config_snapshot();
+    let mut rules = snapshot.config.bash_allow_prefixes.unwrap_or_default();
+    if !rules.contains(&rule) {
+        rules.push(rule.clone());
+        config::patch_config(&serde_json::json!({ "bashAllowPrefixes": rules }), Some(snapshot.revision))?;
+    }
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_whole_words_of_simple_commands() {
+        assert!(matches("git status", "git status"));
+        assert!(matches("  cargo build --release", "cargo build"));
+        assert!(!matches("cargo build-std", "cargo build"));
+        assert!(!matches("git status && rm -rf /", "git status"));
+        assert!(!matches("npm test | tee out", "npm test"));
+        assert!(!matches("npm test > out", "npm test"));
+        assert!(!matches("git status $(rm x)", "git status"));
+        assert!(!matches("git status", ""));
+
+        assert_eq!(suggest_prefix("npm test -- --watch").as_deref(), Some("npm test"));
+        assert_eq!(suggest_prefix("ls -la").as_deref(), Some("ls -la"));
+        assert_eq!(suggest_prefix("rm -rf build").as_deref(), Some("rm -rf build"));
+        assert_eq!(suggest_prefix("make"), None);
+        assert_eq!(suggest_prefix("python script.py"), None);
+        assert_eq!(suggest_prefix("bash -c 'echo hi'"), None);
+        assert_eq!(suggest_prefix("/usr/bin/env node x.js"), None);
+        assert_eq!(suggest_prefix("sudo apt install x"), None);
+        assert_eq!(suggest_prefix("make && make install"), None);
+        assert!(promote("bash -c 'echo hi'", Some("bash".to_string())).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Integrity check for the bundled horseman-mcp sidecar. The binary gets
+//! to answer permission prompts through the callback server, so a swapped
+//! binary must never be written into an MCP config.
+
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// SHA-256 of `binaries/horseman-mcp-<target>`, baked in by build.rs
+const EXPECTED_SHA256: Option<&str> = option_env!("HORSEMAN_MCP_SHA256");
+
+/// Last verdict, keyed by path + mtime + size so edits are re-checked
+type CacheKey = (String, Option<SystemTime>, u64);
+type CachedVerdict = (CacheKey, Result<(), String>);
+static VERDICT_CACHE: Lazy<Mutex<Option<CachedVerdict>>> =
+    Lazy::new(|| Mutex::new(None));
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether `path` is the sidecar shipped next to our own executable
+fn is_bundled(path: &Path) -> bool {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|d| d.join("horseman-mcp")))
+        .map(|bundled| bundled == path)
+        .unwrap_or(false)
+}
+
+/// TeamIdentifier from `codesign -dv`, None if unsigned or unavailable
+#[cfg(target_os = "macos")]
+fn team_identifier(path: &Path) -> Option<String> {
+    let output = std::process::Command::new("codesign")
+        .args(["-dv", "--verbose=2"])
+        .arg(path)
+        .output()
+        .ok()?;
+    // codesign prints its details to stderr
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .find_map(|l| l.strip_prefix("TeamIdentifier="))
+        .map(|t| t.trim().to_string())
+        .filter(|t| t != "not set")
+}
+
+/// Signed builds: the sidecar's signature must be valid and from our team.
+/// Returns None when the app itself is unsigned.
+#[cfg(target_os = "macos")]
+fn check_signature(path: &Path) -> Option<Result<(), String>> {
+    let exe = std::env::current_exe().ok()?;
+    let app_team = team_identifier(&exe)?;
+
+    let valid = std::process::Command::new("codesign")
+        .args(["--verify", "--strict"])
+        .arg(path)
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if !valid {
+        return Some(Err("code signature is invalid".to_string()));
+    }
+    match team_identifier(path) {
+        Some(team) if team == app_team => Some(Ok(())),
+        Some(team) => Some(Err(format!("signed by team {} instead of {}", team, app_team))),
+        None => Some(Err("binary is not signed".to_string())),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn check_signature(_path: &Path) -> Option<Result<(), String>> {
+    None
+}
+
+fn check(path: &Path) -> Result<(), String> {
+    if !is_bundled(path) {
+        debug_log!("MCP", "Skipping integrity check for development binary {:?}", path);
+        return Ok(());
+    }
+
+    // Signing rewrites the binary, so a signed app checks the signature instead of the hash
+    if let Some(result) = check_signature(path) {
+        if result.is_ok() {
+            debug_log!("MCP", "horseman-mcp code signature verified");
+        }
+        return result;
+    }
+
+    // A bundled sidecar always had its hash baked in (Tauri won't build
+    // without the sidecar), so a build without one can't vouch for it
+    let expected = EXPECTED_SHA256.ok_or("no SHA-256 was baked into this build to check it against")?;
+    let bytes = fs::read(path).map_err(|e| format!("could not read binary: {}", e))?;
+    let actual = sha256_hex(&bytes);
+    if actual == expected {
+        debug_log!("MCP", "horseman-mcp hash verified");
+        Ok(())
+    } else {
+        Err(format!("SHA-256 {} does not match the build ({})", actual, expected))
+    }
+}
+
+/// Verify the sidecar before use. Err carries the reason it was rejected.
+pub fn verify_mcp_binary(path: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    let meta = fs::metadata(path).map_err(|e| format!("could not stat binary: {}", e))?;
+    let key: CacheKey = (path.to_string_lossy().to_string(), meta.modified().ok(), meta.len());
+
+    let mut cache = panic_guard::lock_or_recover(&VERDICT_CACHE, "mcp integrity");
+    if let Some((cached_key, verdict)) = cache.as_ref() {
+        if *cached_key == key {
+            return verdict.clone();
+        }
+    }
+    let verdict = check(path);
+    if let Err(ref reason) = verdict {
+        debug_log!("MCP", "horseman-mcp failed integrity check: {}", reason);
+    }
+    *cache = Some((key, verdict.clone()));
+    verdict
+}
+
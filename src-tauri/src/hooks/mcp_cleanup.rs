@@ -0,0 +1,148 @@
+//! Removal of the `.horseman-mcp.json` files `write_mcp_config` leaves in
+//! project directories. Every path written is recorded in
+//! `<artifacts>/mcp-configs.json`; a file is deleted once no session
+//! spawned with it is running, and the record is swept at startup and exit
+//! in case that never happened. `keepMcpConfigs` leaves them all in place.
+
+use crate::atomic_file;
+use crate::config;
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Serializes updates to the record file
+static RECORD_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn record_path() -> Option<PathBuf> {
+    config::artifacts_dir().map(|d| d.join("mcp-configs.json"))
+}
+
+fn read_record(path: &Path) -> BTreeSet<PathBuf> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn update_record(update: impl FnOnce(&mut BTreeSet<PathBuf>)) {
+    let path = match record_path() {
+        Some(path) => path,
+        None => return,
+    };
+    let _guard = panic_guard::lock_or_recover(&RECORD_LOCK, "mcp config record");
+    let mut paths = read_record(&path);
+    update(&mut paths);
+    let result = serde_json::to_string_pretty(&paths)
+        .map_err(|e| e.to_string())
+        .and_then(|content| atomic_file::write(&path, content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        debug_log!("MCP", "Failed to update {:?}: {}", path, e);
+    }
+}
+
+/// Note a config file just written, so a later sweep finds it
+pub fn record(config_path: &Path) {
+    let config_path = config_path.to_path_buf();
+    update_record(|paths| {
+        paths.insert(config_path);
+    });
+}
+
+/// Whether `content` is a config `write_mcp_config` wrote, rather than a
+/// user's file that happens to share the name
+fn is_horseman_config(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|config| config.get("mcpServers")?.get("horseman")?.get("env").cloned())
+        .is_some_and(|env| env.get("HORSEMAN_CALLBACK_PORT").is_some())
+}
+
+/// Delete one config file. Already gone counts as deleted; Ok(false) means
+/// the file isn't ours and was left alone.
+fn delete(config_path: &Path) -> Result<bool, String> {
+    let content = match fs::read_to_string(config_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(format!("Failed to read {:?}: {}", config_path, e)),
+    };
+    if !is_horseman_config(&content) {
+        debug_log!("MCP", "{:?} is not a Horseman MCP config, leaving it", config_path);
+        return Ok(false);
+    }
+    match fs::remove_file(config_path) {
+        Ok(()) => {
+            debug_log!("MCP", "Removed MCP config {:?}", config_path);
+            Ok(true)
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(format!("Failed to remove {:?}: {}", config_path, e)),
+    }
+}
+
+/// Delete a config no running session uses any more
+pub fn remove(config_path: &Path) {
+    if config::keep_mcp_configs() {
+        return;
+    }
+    if let Err(e) = delete(config_path) {
+        debug_log!("MCP", "{}", e);
+    }
+    let config_path = config_path.to_path_buf();
+    update_record(|paths| {
+        paths.remove(&config_path);
+    });
+}
+
+/// Delete every recorded config. Only safe while no claude process is
+/// running: at startup and on exit.
+pub fn sweep() {
+    if config::keep_mcp_configs() {
+        return;
+    }
+    let mut removed = 0;
+    update_record(|paths| {
+        paths.retain(|path| match delete(path) {
+            Ok(deleted) => {
+                removed += usize::from(deleted);
+                false
+            }
+            // Try again next time
+            Err(e) => {
+                debug_log!("MCP", "{}", e);
+                true
+            }
+        });
+    });
+    if removed > 0 {
+        debug_log!("MCP", "Swept {} stale MCP configs", removed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deletes_only_horseman_configs() {
+        let dir = std::env::temp_dir().join(format!("horseman-mcp-cleanup-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let ours = dir.join("ours.json");
+        let theirs = dir.join("theirs.json");
+        let config = r#"{"mcpServers":{"horseman":{"command":"x","env":{"HORSEMAN_CALLBACK_PORT":"1"}}}}"#;
+        fs::write(&ours, config).unwrap();
+        fs::write(&theirs, r#"{"mcpServers":{"other":{}}}"#).unwrap();
+
+        assert_eq!(delete(&ours), Ok(true));
+        assert!(!ours.exists());
+        // Idempotent
+        assert_eq!(delete(&ours), Ok(true));
+        assert_eq!(delete(&theirs), Ok(false));
+        assert!(theirs.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
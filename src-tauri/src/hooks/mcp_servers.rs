@@ -0,0 +1,181 @@
+//! Extra MCP servers from the config (`mcpServers`, and `projectMcpServers`
+//! per project directory), merged into the `--mcp-config` Horseman writes
+//! next to its own server. `${VAR}` and `${VAR:-default}` are written as
+//! they are, for Claude to expand as in a project's `.mcp.json`, so secrets
+//! never land in the file. Servers that can't start (missing command, bad
+//! URL, unset variable) are left out, checked against the login shell's
+//! environment Claude runs with.
+
+use crate::config;
+use crate::login_shell;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Name of Horseman's own server, which config can't replace
+pub const HORSEMAN_SERVER: &str = "horseman";
+
+static ENV_VAR: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}").unwrap());
+
+/// One server, in the shape Claude's MCP config uses
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+    /// "stdio" (default), "sse" or "http"
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+/// Why a configured server was left out
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedServer {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Expand `${VAR}` / `${VAR:-default}` in `value`, erroring on unset
+/// variables without a default
+fn expand(value: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut missing = None;
+    let expanded = ENV_VAR.replace_all(value, |caps: &Captures| {
+        match (lookup(&caps[1]), caps.get(2)) {
+            (Some(value), _) => value,
+            (None, Some(default)) => default.as_str().to_string(),
+            (None, None) => {
+                missing.get_or_insert_with(|| caps[1].to_string());
+                String::new()
+            }
+        }
+    });
+    match missing {
+        Some(name) => Err(format!("environment variable {} is not set", name)),
+        None => Ok(expanded.into_owned()),
+    }
+}
+
+fn expand_server(
+    server: &McpServerConfig,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<McpServerConfig, String> {
+    let expand_map = |map: &HashMap<String, String>| -> Result<HashMap<String, String>, String> {
+        map.iter().map(|(k, v)| Ok((k.clone(), expand(v, lookup)?))).collect()
+    };
+    Ok(McpServerConfig {
+        transport: server.transport.clone(),
+        command: server.command.as_deref().map(|c| expand(c, lookup)).transpose()?,
+        args: server.args.iter().map(|a| expand(a, lookup)).collect::<Result<_, _>>()?,
+        env: expand_map(&server.env)?,
+        url: server.url.as_deref().map(|u| expand(u, lookup)).transpose()?,
+        headers: expand_map(&server.headers)?,
+    })
+}
+
+/// Whether `command` names an existing file, directly or on the login
+/// shell's PATH
+fn command_exists(command: &str, working_directory: &Path) -> bool {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return working_directory.join(path).is_file();
+    }
+    login_shell::find_program(command).is_some()
+}
+
+/// Check an expanded server can be started
+fn validate(server: &McpServerConfig, working_directory: &Path) -> Result<(), String> {
+    match server.transport.as_deref().unwrap_or("stdio") {
+        "stdio" => {
+            let command = server.command.as_deref().filter(|c| !c.trim().is_empty()).ok_or("no command given")?;
+            if !command_exists(command, working_directory) {
+                return Err(format!("command {} not found", command));
+            }
+            Ok(())
+        }
+        "sse" | "http" => {
+            let url = server.url.as_deref().ok_or("no url given")?;
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(format!("url {} is not http(s)", url));
+            }
+            Ok(())
+        }
+        other => Err(format!("unknown server type {}", other)),
+    }
+}
+
+/// Configured servers for a project, project entries overriding global
+/// ones of the same name
+fn configured(working_directory: &str) -> BTreeMap<String, McpServerConfig> {
+    let mut servers: BTreeMap<String, McpServerConfig> = config::mcp_servers().into_iter().collect();
+    servers.extend(config::project_mcp_servers(working_directory));
+    servers
+}
+
+fn resolve_with(
+    servers: BTreeMap<String, McpServerConfig>,
+    working_directory: &Path,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> (BTreeMap<String, McpServerConfig>, Vec<SkippedServer>) {
+    let mut valid = BTreeMap::new();
+    let mut skipped = Vec::new();
+    for (name, server) in servers {
+        let result = if name == HORSEMAN_SERVER {
+            Err(format!("the name {} is reserved", HORSEMAN_SERVER))
+        } else {
+            expand_server(&server, lookup).and_then(|s| validate(&s, working_directory)).map(|_| server)
+        };
+        match result {
+            Ok(server) => {
+                valid.insert(name, server);
+            }
+            Err(reason) => skipped.push(SkippedServer { name, reason }),
+        }
+    }
+    (valid, skipped)
+}
+
+/// The servers to add for a session in `working_directory`, unexpanded,
+/// and the ones left out
+pub fn resolve(working_directory: &str) -> (BTreeMap<String, McpServerConfig>, Vec<SkippedServer>) {
+    resolve_with(configured(working_directory), Path::new(working_directory), &login_shell::var)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_variables_and_skips_invalid_servers() {
+        let lookup = |name: &str| (name == "TOKEN").then(|| "secret".to_string());
+        assert_eq!(expand("Bearer ${TOKEN}", &lookup).unwrap(), "Bearer secret");
+        assert_eq!(expand("${PORT:-8080}", &lookup).unwrap(), "8080");
+        assert!(expand("${MISSING}", &lookup).is_err());
+
+        let server = |json: serde_json::Value| serde_json::from_value::<McpServerConfig>(json).unwrap();
+        let servers = BTreeMap::from([
+            ("api".to_string(), server(serde_json::json!({
+                "type": "http", "url": "https://example.com/mcp", "headers": { "Authorization": "Bearer ${TOKEN}" }
+            }))),
+            ("local".to_string(), server(serde_json::json!({ "command": "./does-not-exist" }))),
+            ("unset".to_string(), server(serde_json::json!({ "type": "sse", "url": "${MCP_URL}" }))),
+            (HORSEMAN_SERVER.to_string(), server(serde_json::json!({ "type": "http", "url": "https://x" }))),
+        ]);
+        let (valid, skipped) = resolve_with(servers, Path::new("/nonexistent"), &lookup);
+        assert_eq!(valid.keys().collect::<Vec<_>>(), vec!["api"]);
+        assert_eq!(valid["api"].headers["Authorization"], "Bearer ${TOKEN}");
+        let mut names: Vec<&str> = skipped.iter().map(|s| s.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec![HORSEMAN_SERVER, "local", "unset"]);
+    }
+}
@@ -1,10 +1,22 @@
+pub mod bash_rules;
+pub mod integrity;
+pub mod mcp_cleanup;
+pub mod mcp_servers;
+pub mod path_scope;
+pub mod permission_rules;
 pub mod server;
 pub mod types;
 
-pub use server::{start_hook_server, respond_permission, HookServerState};
+pub use server::{
+    deny_all_pending, forget_session, permission_queue, respond_permission, sessions_awaiting_answer,
+    set_session_priority, start_hook_server, HookServerState,
+};
 
+use crate::atomic_file;
+use crate::config;
 use crate::debug_log;
-use std::fs;
+use mcp_servers::{McpServerConfig, HORSEMAN_SERVER};
+use std::collections::BTreeMap;
 use std::path::Path;
 
 /// Write MCP server configuration to the working directory
@@ -14,26 +26,33 @@ pub fn write_mcp_config(
     port: u16,
     mcp_binary_path: &str,
     ui_session_id: &str,
+    extra_servers: &BTreeMap<String, McpServerConfig>,
 ) -> Result<String, String> {
     let config_path = working_dir.join(".horseman-mcp.json");
 
-    let config = serde_json::json!({
-        "mcpServers": {
-            "horseman": {
-                "command": mcp_binary_path,
-                "args": [],
-                "env": {
-                    "HORSEMAN_CALLBACK_PORT": port.to_string(),
-                    "HORSEMAN_UI_SESSION_ID": ui_session_id
-                }
+    let mut servers = serde_json::Map::new();
+    for (name, server) in extra_servers {
+        let server = serde_json::to_value(server).map_err(|e| format!("Failed to serialize MCP server {}: {}", name, e))?;
+        servers.insert(name.clone(), server);
+    }
+    servers.insert(
+        HORSEMAN_SERVER.to_string(),
+        serde_json::json!({
+            "command": mcp_binary_path,
+            "args": [],
+            "env": {
+                "HORSEMAN_CALLBACK_PORT": port.to_string(),
+                "HORSEMAN_UI_SESSION_ID": ui_session_id,
+                "HORSEMAN_PERMISSION_TIMEOUT_SECS": config::permission_timeout().as_secs().to_string()
             }
-        }
-    });
+        }),
+    );
+    let config = serde_json::json!({ "mcpServers": servers });
 
     let content = serde_json::to_string_pretty(&config)
         .map_err(|e| format!("Failed to serialize MCP config: {}", e))?;
 
-    fs::write(&config_path, content)
+    atomic_file::write(&config_path, content)
         .map_err(|e| format!("Failed to write MCP config: {}", e))?;
 
     debug_log!("MCP", "Wrote MCP config to {:?}", config_path);
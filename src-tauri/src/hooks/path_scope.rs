@@ -0,0 +1,135 @@
+//! Approvals scoped to a directory: "allow Write under src/" approves that
+//! tool for any file below the prefix instead of prompting per file. Paths
+//! are compared whole components at a time, after `.`, `..` and symlinks
+//! are resolved, so neither `src/../.env` nor a `src/link` pointing at `/`
+//! is under `src/`.
+
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+
+/// Tools whose input names the single file they touch
+const PATH_SCOPED_TOOLS: &[&str] = &["Read", "Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathApproval {
+    pub tool_name: String,
+    /// Absolute, with symlinks resolved
+    pub prefix: PathBuf,
+    /// Session the approval was given in; None applies to every session
+    pub ui_session_id: Option<String>,
+}
+
+impl PathApproval {
+    pub fn covers(&self, tool_name: &str, ui_session_id: Option<&str>, path: &Path) -> bool {
+        self.tool_name == tool_name
+            && (self.ui_session_id.is_none() || self.ui_session_id.as_deref() == ui_session_id)
+            && path.starts_with(&self.prefix)
+    }
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Resolve symlinks in the deepest part of `path` that exists, then `.`
+/// and `..` in the rest (which can't hold symlinks yet)
+pub fn resolve(path: &Path) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    for existing in (1..=components.len()).rev() {
+        let ancestor: PathBuf = components[..existing].iter().collect();
+        if let Ok(mut resolved) = ancestor.canonicalize() {
+            resolved.extend(&components[existing..]);
+            return normalize(&resolved);
+        }
+    }
+    normalize(path)
+}
+
+/// The file a path-scoped tool call touches, if it's one and names it
+/// absolutely
+pub fn target_path(tool_name: &str, tool_input: &serde_json::Value) -> Option<PathBuf> {
+    if !PATH_SCOPED_TOOLS.contains(&tool_name) {
+        return None;
+    }
+    let path = tool_input
+        .get("file_path")
+        .or_else(|| tool_input.get("notebook_path"))
+        .and_then(|v| v.as_str())
+        .map(Path::new)?;
+    path.is_absolute().then(|| resolve(path))
+}
+
+/// Make an approval prefix absolute; relative ones are taken from the
+/// session's working directory
+pub fn resolve_prefix(prefix: &str, working_directory: Option<&str>) -> Result<PathBuf, String> {
+    let prefix = prefix.trim();
+    if prefix.is_empty() {
+        return Err("Path prefix is empty".to_string());
+    }
+    let path = Path::new(prefix);
+    if path.is_absolute() {
+        return Ok(resolve(path));
+    }
+    let base = working_directory.ok_or_else(|| format!("No working directory to resolve {} against", prefix))?;
+    Ok(resolve(&Path::new(base).join(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approvals_cover_paths_under_the_prefix() {
+        let prefix = resolve_prefix("src/", Some("/work/app")).unwrap();
+        assert_eq!(prefix, PathBuf::from("/work/app/src"));
+        let approval = PathApproval {
+            tool_name: "Write".to_string(),
+            prefix,
+            ui_session_id: Some("s1".to_string()),
+        };
+        let target = |path: &str| target_path("Write", &serde_json::json!({ "file_path": path })).unwrap();
+
+        assert!(approval.covers("Write", Some("s1"), &target("/work/app/src/lib/a.rs")));
+        assert!(!approval.covers("Edit", Some("s1"), &target("/work/app/src/a.rs")));
+        assert!(!approval.covers("Write", Some("s2"), &target("/work/app/src/a.rs")));
+        assert!(!approval.covers("Write", Some("s1"), &target("/work/app/src/../.env")));
+        assert!(!approval.covers("Write", Some("s1"), &target("/work/app/srcfoo/a.rs")));
+        assert_eq!(target_path("Bash", &serde_json::json!({ "file_path": "/x" })), None);
+        assert_eq!(target_path("Read", &serde_json::json!({ "file_path": "rel/x" })), None);
+        assert!(resolve_prefix("src", None).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_escape_the_prefix() {
+        let dir = std::env::temp_dir().join(format!("horseman-scope-{}", uuid::Uuid::new_v4()));
+        let outside = dir.join("outside");
+        std::fs::create_dir_all(dir.join("work/src")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("work/src/link")).unwrap();
+        let work = dir.join("work");
+        let approval = PathApproval {
+            tool_name: "Write".to_string(),
+            prefix: resolve_prefix("src", work.to_str()).unwrap(),
+            ui_session_id: None,
+        };
+        let target = |path: &Path| target_path("Write", &serde_json::json!({ "file_path": path })).unwrap();
+
+        assert!(approval.covers("Write", None, &target(&work.join("src/new/a.rs"))));
+        assert!(!approval.covers("Write", None, &target(&work.join("src/link/a.rs"))));
+        assert!(!approval.covers("Write", None, &target(&work.join("src/link/new/../a.rs"))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
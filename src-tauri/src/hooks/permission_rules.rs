@@ -0,0 +1,408 @@
+//! Tool permission rules in Claude's `settings.json` syntax, so Horseman
+//! and the CLI can share one policy. `Tool` covers a whole tool,
+//! `mcp__server` every tool of an MCP server, `Bash(npm test)` one exact
+//! command and `Bash(npm run:*)` a command prefix. Deny rules refuse
+//! without asking and win over every approval; allow rules approve without
+//! asking. Rules can be imported from `~/.claude/settings.json` and a
+//! project's `.claude/settings.json` / `settings.local.json`, and written
+//! back to them. Path and domain rules (`Read(./.env)`,
+//! `WebFetch(domain:…)`) are left to Claude, which still applies them.
+//! Imported rules remember their settings file and are only ever written
+//! back to it, so `settings.local.json` rules never reach the shared file.
+
+use super::bash_rules;
+use crate::atomic_file;
+use crate::config;
+use crate::debug_log;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PermissionRules {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    /// Settings file each imported rule came from; rules added in Horseman
+    /// have none
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub sources: HashMap<String, String>,
+}
+
+/// `Tool` or `Tool(specifier)`
+#[derive(Debug, PartialEq)]
+struct Rule<'a> {
+    tool: &'a str,
+    specifier: Option<&'a str>,
+}
+
+fn parse(rule: &str) -> Option<Rule<'_>> {
+    let rule = rule.trim();
+    match rule.split_once('(') {
+        Some((tool, rest)) => Some(Rule {
+            tool: tool.trim(),
+            specifier: Some(rest.strip_suffix(')')?.trim()),
+        }),
+        None if !rule.is_empty() => Some(Rule {
+            tool: rule,
+            specifier: None,
+        }),
+        None => None,
+    }
+}
+
+/// Why Horseman can't evaluate a rule, None when it can
+fn unsupported(rule: &str) -> Option<String> {
+    match parse(rule) {
+        None => Some("not a valid rule".to_string()),
+        Some(Rule { specifier: None, .. }) | Some(Rule { tool: "Bash", .. }) => None,
+        Some(Rule { tool, .. }) => Some(format!("{} rules with a specifier are left to Claude", tool)),
+    }
+}
+
+/// Pieces of a command between shell operators, each of which could run
+/// something: `a && b`, `a | b`, `$(b)`, `a > b`
+fn segments(command: &str) -> impl Iterator<Item = &str> {
+    command.split(['&', '|', ';', '\n', '`', '(', ')', '<', '>']).map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// Allowing takes the whole command matching (a prefix never covers a
+/// compound command); denying takes any piece of it, also with wrappers
+/// like `sudo` or `env X=1` and variable assignments stripped
+fn bash_matches(command: &str, specifier: &str, deny: bool) -> bool {
+    let (prefix, exact) = match specifier.strip_suffix(":*") {
+        Some(prefix) => (prefix.trim(), false),
+        None => (specifier, true),
+    };
+    let starts_with = |piece: &str| match piece.strip_prefix(prefix) {
+        Some(rest) => !prefix.is_empty() && (rest.is_empty() || (!exact && rest.starts_with(char::is_whitespace))),
+        None => false,
+    };
+    if deny {
+        return starts_with(command.trim())
+            || segments(command).any(|piece| starts_with(piece) || starts_with(bash_rules::strip_wrappers(piece)));
+    }
+    if exact {
+        command.trim() == specifier
+    } else {
+        bash_rules::matches(command, prefix)
+    }
+}
+
+fn rule_matches(rule: &str, tool_name: &str, tool_input: &serde_json::Value, deny: bool) -> bool {
+    match parse(rule) {
+        Some(Rule { tool, specifier: None }) => {
+            tool == tool_name || (tool.starts_with("mcp__") && tool_name.starts_with(&format!("{}__", tool)))
+        }
+        Some(Rule { tool: "Bash", specifier: Some(specifier) }) if tool_name == "Bash" => {
+            let command = tool_input.get("command").and_then(|v| v.as_str()).unwrap_or("");
+            bash_matches(command, specifier, deny)
+        }
+        _ => false,
+    }
+}
+
+/// The configured rule deciding a tool call, if any: `(false, rule)` for a
+/// deny rule, which is checked first, `(true, rule)` for an allow rule.
+/// Project rules apply to sessions in `working_directory`.
+pub fn decide(
+    tool_name: &str,
+    tool_input: &serde_json::Value,
+    working_directory: Option<&str>,
+) -> Option<(bool, String)> {
+    let mut rules = config::permission_rules();
+    if let Some(project) = working_directory.map(config::project_permission_rules) {
+        rules.allow.extend(project.allow);
+        rules.deny.extend(project.deny);
+    }
+    if let Some(rule) = rules.deny.into_iter().find(|r| rule_matches(r, tool_name, tool_input, true)) {
+        return Some((false, rule));
+    }
+    rules
+        .allow
+        .into_iter()
+        .find(|r| rule_matches(r, tool_name, tool_input, false))
+        .map(|rule| (true, rule))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedRule {
+    pub rule: String,
+    pub source: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleImport {
+    /// Settings files read
+    pub sources: Vec<String>,
+    /// Rules added to the config
+    pub allow_added: Vec<String>,
+    pub deny_added: Vec<String>,
+    /// Rules Horseman can't evaluate; Claude still applies them
+    pub skipped: Vec<SkippedRule>,
+    /// Settings files written back
+    pub written: Vec<String>,
+}
+
+fn user_settings_path() -> Result<PathBuf, String> {
+    config::claude_settings_path().ok_or_else(|| "Could not determine Claude's settings directory".to_string())
+}
+
+fn project_settings_paths(project: &str) -> [PathBuf; 2] {
+    let dir = Path::new(project).join(".claude");
+    [dir.join("settings.json"), dir.join("settings.local.json")]
+}
+
+fn read_settings(path: &Path) -> Result<Option<serde_json::Value>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse {:?}: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {:?}: {}", path, e)),
+    }
+}
+
+fn settings_rules(settings: &serde_json::Value) -> PermissionRules {
+    let list = |key: &str| -> Vec<String> {
+        settings
+            .pointer(&format!("/permissions/{}", key))
+            .and_then(|v| v.as_array())
+            .map(|rules| rules.iter().filter_map(|r| r.as_str()).map(str::to_string).collect())
+            .unwrap_or_default()
+    };
+    PermissionRules {
+        allow: list("allow"),
+        deny: list("deny"),
+        sources: HashMap::new(),
+    }
+}
+
+/// Add the supported rules from `source` to `into`, noting what was added
+/// and skipped in `report`
+fn merge(into: &mut PermissionRules, from: PermissionRules, source: &Path, report: &mut RuleImport) {
+    let sources = &mut into.sources;
+    let lists = [
+        (&mut into.allow, from.allow, &mut report.allow_added),
+        (&mut into.deny, from.deny, &mut report.deny_added),
+    ];
+    for (rules, imported, added) in lists {
+        for rule in imported {
+            if let Some(reason) = unsupported(&rule) {
+                report.skipped.push(SkippedRule {
+                    rule,
+                    source: source.to_string_lossy().to_string(),
+                    reason,
+                });
+            } else if !rules.contains(&rule) {
+                sources.insert(rule.clone(), source.to_string_lossy().to_string());
+                rules.push(rule.clone());
+                added.push(rule);
+            }
+        }
+    }
+}
+
+/// Add `rules` to the permissions of a settings file, keeping everything
+/// else in it. Returns whether the file changed.
+fn write_settings(path: &Path, rules: &PermissionRules) -> Result<bool, String> {
+    let mut settings = read_settings(path)?.unwrap_or_else(|| serde_json::json!({}));
+    let before = settings.clone();
+    let permissions = settings
+        .as_object_mut()
+        .ok_or_else(|| format!("{:?} is not a JSON object", path))?
+        .entry("permissions")
+        .or_insert_with(|| serde_json::json!({}));
+    for (key, rules) in [("allow", &rules.allow), ("deny", &rules.deny)] {
+        let list = permissions
+            .as_object_mut()
+            .ok_or_else(|| format!("permissions in {:?} is not a JSON object", path))?
+            .entry(key)
+            .or_insert_with(|| serde_json::json!([]))
+            .as_array_mut()
+            .ok_or_else(|| format!("permissions.{} in {:?} is not a list", key, path))?;
+        for rule in rules {
+            if !list.iter().any(|r| r.as_str() == Some(rule)) {
+                list.push(rule.clone().into());
+            }
+        }
+    }
+    if settings == before {
+        return Ok(false);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    }
+    back_up(path);
+    let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    atomic_file::write(path, content).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(true)
+}
+
+/// Keep the previous version of a settings file among Horseman's own
+/// files, never next to it in a project
+fn back_up(path: &Path) {
+    let previous = match fs::read(path) {
+        Ok(previous) if !previous.is_empty() => previous,
+        _ => return,
+    };
+    let hash = Sha256::digest(path.to_string_lossy().as_bytes());
+    let backup = match config::artifacts_dir() {
+        Some(dir) => dir.join("settings_backups").join(format!("{:x}.json", hash)),
+        None => return,
+    };
+    let written = backup
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| atomic_file::write(&backup, previous));
+    if let Err(e) = written {
+        debug_log!("PERMISSIONS", "Failed to back up {:?}: {}", path, e);
+    }
+}
+
+/// Split `rules` by the settings file to write each to: imported rules go
+/// back to their source, the rest to `default`
+fn by_destination(rules: &PermissionRules, default: &Path) -> Vec<(PathBuf, PermissionRules)> {
+    let mut destinations: Vec<(PathBuf, PermissionRules)> = Vec::new();
+    for (deny, list) in [(false, &rules.allow), (true, &rules.deny)] {
+        for rule in list {
+            let path = rules.sources.get(rule).map_or_else(|| default.to_path_buf(), PathBuf::from);
+            let index = match destinations.iter().position(|(p, _)| *p == path) {
+                Some(index) => index,
+                None => {
+                    destinations.push((path, PermissionRules::default()));
+                    destinations.len() - 1
+                }
+            };
+            let target = &mut destinations[index].1;
+            if deny {
+                target.deny.push(rule.clone());
+            } else {
+                target.allow.push(rule.clone());
+            }
+        }
+    }
+    destinations
+}
+
+/// Import the permission rules from Claude's user settings, and from
+/// `project`'s settings into that project's rules. With `write_back`,
+/// Horseman's own rules (`bashAllowPrefixes` included) are then added to
+/// the user settings and the project's `settings.json`.
+pub fn import(project: Option<&str>, write_back: bool) -> Result<RuleImport, String> {
+    let project = project.map(|p| p.trim_end_matches('/'));
+    let mut report = RuleImport::default();
+    let snapshot = config::get_config_snapshot();
+    let mut global = snapshot.config.permission_rules.clone().unwrap_or_default();
+    let mut by_project = snapshot.config.project_permission_rules.clone().unwrap_or_default();
+
+    let user_path = user_settings_path()?;
+    if let Some(settings) = read_settings(&user_path)? {
+        report.sources.push(user_path.to_string_lossy().to_string());
+        merge(&mut global, settings_rules(&settings), &user_path, &mut report);
+    }
+    if let Some(project) = project {
+        let project_rules = by_project.entry(project.to_string()).or_default();
+        for path in project_settings_paths(project) {
+            if let Some(settings) = read_settings(&path)? {
+                report.sources.push(path.to_string_lossy().to_string());
+                merge(project_rules, settings_rules(&settings), &path, &mut report);
+            }
+        }
+        if *project_rules == PermissionRules::default() {
+            by_project.remove(project);
+        }
+    }
+
+    if !report.allow_added.is_empty() || !report.deny_added.is_empty() {
+        config::patch_config(
+            &serde_json::json!({ "permissionRules": global, "projectPermissionRules": by_project }),
+            Some(snapshot.revision),
+        )?;
+    }
+
+    if write_back {
+        let mut user_rules = global;
+        for prefix in snapshot.config.bash_allow_prefixes.unwrap_or_default() {
+            let rule = format!("Bash({}:*)", prefix.trim());
+            if !user_rules.allow.contains(&rule) {
+                user_rules.allow.push(rule);
+            }
+        }
+        let mut destinations = by_destination(&user_rules, &user_path);
+        if let Some((project, rules)) = project.and_then(|p| by_project.get(p).map(|rules| (p, rules))) {
+            let [shared, _] = project_settings_paths(project);
+            destinations.extend(by_destination(rules, &shared));
+        }
+        for (path, rules) in destinations {
+            if write_settings(&path, &rules)? {
+                report.written.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_claude_rules() {
+        let bash = |command: &str| serde_json::json!({ "command": command });
+        assert!(rule_matches("Bash(npm run:*)", "Bash", &bash("npm run build"), false));
+        assert!(!rule_matches("Bash(npm run:*)", "Bash", &bash("npm run build && rm -rf /"), false));
+        assert!(rule_matches("Bash(curl:*)", "Bash", &bash("echo hi && curl example.com"), true));
+        assert!(rule_matches("Bash(curl:*)", "Bash", &bash("echo $(curl example.com)"), true));
+        assert!(!rule_matches("Bash(curl:*)", "Bash", &bash("curlie example.com"), true));
+        assert!(rule_matches("Bash(git status)", "Bash", &bash("git status"), false));
+        assert!(!rule_matches("Bash(git status)", "Bash", &bash("git status --short"), false));
+        assert!(rule_matches("WebSearch", "WebSearch", &serde_json::json!({}), false));
+        assert!(rule_matches("mcp__github", "mcp__github__create_issue", &serde_json::json!({}), false));
+        assert!(!rule_matches("mcp__git", "mcp__github__create_issue", &serde_json::json!({}), false));
+
+        let settings = serde_json::json!({
+            "permissions": { "allow": ["Bash(npm test:*)", "Read(./src/**)", "WebSearch"], "deny": ["Bash(curl:*)"] }
+        });
+        let mut rules = PermissionRules {
+            allow: vec!["WebSearch".to_string()],
+            ..Default::default()
+        };
+        let mut report = RuleImport::default();
+        merge(&mut rules, settings_rules(&settings), Path::new("settings.local.json"), &mut report);
+        assert_eq!(rules.allow, vec!["WebSearch", "Bash(npm test:*)"]);
+        assert_eq!(report.allow_added, vec!["Bash(npm test:*)"]);
+        assert_eq!(report.deny_added, vec!["Bash(curl:*)"]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].rule, "Read(./src/**)");
+
+        // Imported rules go back to where they came from, the rest to the default file
+        let destinations = by_destination(&rules, Path::new("settings.json"));
+        let local = &destinations.iter().find(|(p, _)| p == Path::new("settings.local.json")).unwrap().1;
+        let shared = &destinations.iter().find(|(p, _)| p == Path::new("settings.json")).unwrap().1;
+        assert_eq!(local.allow, vec!["Bash(npm test:*)"]);
+        assert_eq!(local.deny, vec!["Bash(curl:*)"]);
+        assert_eq!(shared.allow, vec!["WebSearch"]);
+        assert!(shared.deny.is_empty());
+    }
+
+    #[test]
+    fn deny_rules_see_through_wrappers() {
+        let denied = |command: &str| bash_matches(command, "rm -rf:*", true);
+        assert!(denied("sudo rm -rf /"));
+        assert!(denied("sudo -E /usr/bin/env X=1 rm -rf /"));
+        assert!(denied("env X=1 rm -rf /"));
+        assert!(denied("X=1 Y=2 rm -rf /"));
+        assert!(denied("command rm -rf /"));
+        assert!(denied("nohup rm -rf / &"));
+        assert!(denied("find . | xargs rm -rf"));
+        assert!(denied("timeout 10 bash -c 'rm -rf /'"));
+        assert!(!denied("echo rm -rf /"));
+        assert!(!denied("sudo rmdir build"));
+    }
+}
@@ -1,6 +1,15 @@
-use super::types::{PermissionRequest, PermissionResponse};
+use super::{bash_rules, permission_rules};
+use super::path_scope::{self, PathApproval};
+use super::types::{
+    ApprovalScope, PermissionLane, PermissionRequest, PermissionResponse, PermissionTimeoutAction, QueuedPrompt,
+    SessionPriority,
+};
+use crate::claude::annotations::{self, Annotation, AnnotationKind};
+use crate::commands::ClaudeState;
+use crate::config;
 use crate::debug_log;
-use crate::events::{BackendEvent, PendingQuestion, Question};
+use crate::events::{self, BackendEvent, PendingQuestion, Question};
+use crate::supervisor;
 use axum::{
     extract::State,
     routing::post,
@@ -8,17 +17,40 @@ use axum::{
 };
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
 use tokio::net::TcpListener;
 use tokio::sync::{oneshot, Mutex};
+use tokio::time::Instant;
 use uuid::Uuid;
 
+/// Tools that can't change anything, for `PermissionTimeoutAction::AllowReadOnly`
+const READ_ONLY_TOOLS: &[&str] = &["Read", "Glob", "Grep", "LS", "NotebookRead"];
+/// How often `permission.expiring` counts down
+const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A permission or question waiting for the user
+pub struct PendingRequest {
+    pub tx: oneshot::Sender<PermissionResponse>,
+    pub ui_session_id: Option<String>,
+    pub tool_name: String,
+    /// Unix millis when the request arrived
+    pub requested_at: i64,
+}
+
 /// State for pending permission requests
 pub struct HookServerState {
-    /// Pending permission responses: request_id -> oneshot sender
-    pub pending: Mutex<HashMap<String, oneshot::Sender<PermissionResponse>>>,
-    /// Tools approved for the session (auto-approve without UI)
-    pub session_approved: Mutex<HashSet<String>>,
+    /// Pending permission responses: request_id -> request
+    pub pending: Mutex<HashMap<String, PendingRequest>>,
+    /// Tools approved for a session (auto-approve without UI), by UI session ID
+    pub session_approved: Mutex<HashMap<String, HashSet<String>>>,
+    /// File tools approved below a directory (see `path_scope`)
+    pub path_approved: Mutex<Vec<PathApproval>>,
+    /// Per-session priority for ordering the permission queue (default Normal)
+    pub priorities: Mutex<HashMap<String, SessionPriority>>,
+    /// Working directory of each session that has asked for permission,
+    /// so requests don't wait on the session manager's lock
+    pub working_directories: Mutex<HashMap<String, String>>,
     /// Tauri app handle for emitting events
     pub app: AppHandle,
 }
@@ -28,7 +60,10 @@ pub struct HookServerState {
 pub async fn start_hook_server(app: AppHandle) -> Result<(u16, Arc<HookServerState>), String> {
     let state = Arc::new(HookServerState {
         pending: Mutex::new(HashMap::new()),
-        session_approved: Mutex::new(HashSet::new()),
+        session_approved: Mutex::new(HashMap::new()),
+        path_approved: Mutex::new(Vec::new()),
+        priorities: Mutex::new(HashMap::new()),
+        working_directories: Mutex::new(HashMap::new()),
         app,
     });
 
@@ -47,16 +82,138 @@ pub async fn start_hook_server(app: AppHandle) -> Result<(u16, Arc<HookServerSta
 
     debug_log!("MCP", "Permission callback server starting on port {}", port);
 
-    // Spawn server task
-    tokio::spawn(async move {
-        if let Err(e) = axum::serve(listener, router).await {
-            debug_log!("MCP", "Permission server error: {}", e);
+    // Supervised so a crashed server comes back on the same port (already
+    // baked into MCP configs); the first run reuses the bound listener
+    let initial = Arc::new(std::sync::Mutex::new(Some(listener)));
+    supervisor::spawn_async("hookServer", move || {
+        let router = router.clone();
+        let listener = initial.lock().ok().and_then(|mut l| l.take());
+        async move {
+            let listener = match listener {
+                Some(l) => l,
+                None => TcpListener::bind(("127.0.0.1", port))
+                    .await
+                    .map_err(|e| format!("Failed to rebind port {}: {}", port, e))?,
+            };
+            axum::serve(listener, router)
+                .await
+                .map_err(|e| format!("Permission server error: {}", e))
         }
     });
 
     Ok((port, state))
 }
 
+/// Register a request as waiting for the user; the receiver gets the answer
+async fn add_pending(
+    state: &HookServerState,
+    request_id: &str,
+    input: &PermissionRequest,
+) -> oneshot::Receiver<PermissionResponse> {
+    let (tx, rx) = oneshot::channel();
+    state.pending.lock().await.insert(
+        request_id.to_string(),
+        PendingRequest {
+            tx,
+            ui_session_id: input.ui_session_id.clone(),
+            tool_name: input.tool_name.clone(),
+            requested_at: chrono::Utc::now().timestamp_millis(),
+        },
+    );
+    rx
+}
+
+/// How waiting for the user ended
+enum Wait {
+    Answered(PermissionResponse),
+    /// The sender was dropped
+    Cancelled,
+    TimedOut,
+}
+
+/// Wait for the user's answer to a pending request, sending
+/// `permission.expiring` now and every `COUNTDOWN_INTERVAL` until
+/// `permissionTimeoutSecs` runs out. A timed-out request is removed.
+async fn wait_for_answer(
+    state: &HookServerState,
+    request_id: &str,
+    ui_session_id: Option<&str>,
+    mut rx: oneshot::Receiver<PermissionResponse>,
+) -> Wait {
+    let deadline = Instant::now() + config::permission_timeout();
+    // The first tick is immediate, so the UI learns the timeout right away
+    let mut countdown = tokio::time::interval(COUNTDOWN_INTERVAL);
+    loop {
+        tokio::select! {
+            // An answer that's ready wins over a deadline that passed at the same time
+            biased;
+            answer = &mut rx => {
+                return match answer {
+                    Ok(response) => Wait::Answered(response),
+                    Err(_) => Wait::Cancelled,
+                };
+            }
+            _ = tokio::time::sleep_until(deadline) => {
+                state.pending.lock().await.remove(request_id);
+                // The user may have answered while the lock was awaited
+                return match rx.try_recv() {
+                    Ok(response) => Wait::Answered(response),
+                    Err(_) => Wait::TimedOut,
+                };
+            }
+            _ = countdown.tick() => {
+                let _ = events::emit(
+                    &state.app,
+                    BackendEvent::PermissionExpiring {
+                        request_id: request_id.to_string(),
+                        ui_session_id: ui_session_id.map(str::to_string),
+                        seconds_left: deadline.saturating_duration_since(Instant::now()).as_secs(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// The answer to a permission prompt nobody answered in time
+fn timeout_response(action: PermissionTimeoutAction, tool_name: &str) -> PermissionResponse {
+    let allow = action == PermissionTimeoutAction::AllowReadOnly && READ_ONLY_TOOLS.contains(&tool_name);
+    let message = if allow {
+        "Approval timed out; allowed as a read-only tool"
+    } else {
+        "Timed out waiting for approval"
+    };
+    PermissionResponse {
+        allow,
+        message: Some(message.to_string()),
+        answers: None,
+        updated_input: None,
+    }
+}
+
+async fn session_priority(state: &HookServerState, ui_session_id: Option<&str>) -> SessionPriority {
+    match ui_session_id {
+        Some(id) => state.priorities.lock().await.get(id).copied().unwrap_or_default(),
+        None => SessionPriority::default(),
+    }
+}
+
+/// A session's working directory, looked up in the session manager on a
+/// blocking thread the first time and cached until the session is removed
+async fn working_directory_of(state: &HookServerState, ui_session_id: &str) -> Option<String> {
+    if let Some(dir) = state.working_directories.lock().await.get(ui_session_id) {
+        return Some(dir.clone());
+    }
+    let app = state.app.clone();
+    let id = ui_session_id.to_string();
+    let dir = tauri::async_runtime::spawn_blocking(move || app.state::<ClaudeState>().lock().working_directory(&id))
+        .await
+        .ok()
+        .flatten()?;
+    state.working_directories.lock().await.insert(ui_session_id.to_string(), dir.clone());
+    Some(dir)
+}
+
 /// Handle permission request from MCP server
 /// Blocks until user responds or timeout
 async fn handle_permission(
@@ -70,70 +227,126 @@ async fn handle_permission(
         return handle_ask_user_question(state, input).await;
     }
 
-    // Check if tool is already approved for session
-    {
+    // Configured rules; a deny rule wins over every approval
+    let working_directory = match input.ui_session_id.as_deref() {
+        Some(id) => working_directory_of(&state, id).await,
+        None => None,
+    };
+    let rule = permission_rules::decide(&input.tool_name, &input.tool_input, working_directory.as_deref());
+    if let Some((false, ref rule)) = rule {
+        debug_log!("MCP", "Tool '{}' denied by rule '{}'", input.tool_name, rule);
+        return Json(PermissionResponse {
+            allow: false,
+            message: Some(format!("Denied by the permission rule {}", rule)),
+            answers: None,
+            updated_input: None,
+        });
+    }
+
+    // Check if tool is already approved for this session
+    if let Some(ref ui_session_id) = input.ui_session_id {
         let approved = state.session_approved.lock().await;
-        if approved.contains(&input.tool_name) {
+        if approved.get(ui_session_id).is_some_and(|tools| tools.contains(&input.tool_name)) {
             debug_log!("MCP", "Tool '{}' is session-approved, auto-allowing", input.tool_name);
             return Json(PermissionResponse {
                 allow: true,
                 message: None,
                 answers: None,
+                updated_input: None,
             });
         }
     }
 
-    let request_id = Uuid::new_v4().to_string();
-    let (tx, rx) = oneshot::channel();
+    // Or a Bash command a configured rule allows
+    if input.tool_name == "Bash" {
+        let command = input.tool_input.get("command").and_then(|v| v.as_str()).unwrap_or("");
+        if let Some(rule) = bash_rules::allowing_rule(command) {
+            debug_log!("MCP", "Bash command allowed by rule '{}', auto-allowing", rule);
+            return Json(PermissionResponse {
+                allow: true,
+                message: None,
+                answers: None,
+                updated_input: None,
+            });
+        }
+    }
 
-    // Store the sender
-    {
-        let mut pending = state.pending.lock().await;
-        pending.insert(request_id.clone(), tx);
+    // Or one of the permission rules
+    if let Some((true, rule)) = rule {
+        debug_log!("MCP", "Tool '{}' allowed by rule '{}', auto-allowing", input.tool_name, rule);
+        return Json(PermissionResponse {
+            allow: true,
+            message: None,
+            answers: None,
+            updated_input: None,
+        });
     }
 
+    // Or approved for the directory the file is in
+    if let Some(path) = path_scope::target_path(&input.tool_name, &input.tool_input) {
+        let approved = state.path_approved.lock().await;
+        let ui_session_id = input.ui_session_id.as_deref();
+        if let Some(approval) = approved.iter().find(|a| a.covers(&input.tool_name, ui_session_id, &path)) {
+            debug_log!("MCP", "{} of {:?} is approved under {:?}, auto-allowing", input.tool_name, path, approval.prefix);
+            return Json(PermissionResponse {
+                allow: true,
+                message: None,
+                answers: None,
+                updated_input: None,
+            });
+        }
+    }
+
+    let request_id = Uuid::new_v4().to_string();
+    let rx = add_pending(&state, &request_id, &input).await;
+
     // Emit event to frontend
     debug_log!("MCP", "Emitting permission request: {} for {} (session: {:?})", request_id, input.tool_name, input.ui_session_id);
 
-    let _ = state.app.emit(
-        "horseman-event",
+    let _ = events::emit(
+        &state.app,
         BackendEvent::PermissionRequested {
             request_id: request_id.clone(),
             tool_name: input.tool_name.clone(),
             tool_input: input.tool_input.clone(),
             ui_session_id: input.ui_session_id.clone(),
+            priority: session_priority(&state, input.ui_session_id.as_deref()).await,
         },
     );
 
-    // Wait for response with timeout (170s to beat Claude's 180s timeout)
-    match tokio::time::timeout(
-        std::time::Duration::from_secs(170),
-        rx,
-    ).await {
-        Ok(Ok(response)) => {
+    let response = match wait_for_answer(&state, &request_id, input.ui_session_id.as_deref(), rx).await {
+        Wait::Answered(response) => {
             debug_log!("MCP", "Permission {} resolved: allow={}", request_id, response.allow);
-            Json(response)
+            response
         }
-        Ok(Err(_)) => {
+        Wait::Cancelled => {
             debug_log!("MCP", "Permission {} channel dropped", request_id);
-            Json(PermissionResponse {
+            return Json(PermissionResponse {
                 allow: false,
                 message: Some("Request cancelled".to_string()),
                 answers: None,
-            })
+                updated_input: None,
+            });
         }
-        Err(_) => {
-            debug_log!("MCP", "Permission {} timed out", request_id);
-            // Clean up pending entry
-            let mut pending = state.pending.lock().await;
-            pending.remove(&request_id);
-            Json(PermissionResponse {
-                allow: false,
-                message: Some("Timed out waiting for approval".to_string()),
-                answers: None,
-            })
+        Wait::TimedOut => {
+            let response = timeout_response(config::permission_timeout_action(), &input.tool_name);
+            debug_log!("MCP", "Permission {} timed out: allow={}", request_id, response.allow);
+            let _ = events::emit(&state.app, BackendEvent::PermissionResolved { request_id });
+            response
         }
+    };
+    if let Some(ref ui_session_id) = input.ui_session_id {
+        let mut decision = Annotation::new(AnnotationKind::PermissionDecision);
+        decision.tool_use_id = Some(input.tool_use_id.clone());
+        decision.text = response.message.clone();
+        decision.data = Some(serde_json::json!({
+            "toolName": input.tool_name,
+            "allow": response.allow,
+            "updatedInput": response.updated_input,
+        }));
+        annotations::record_for_ui_session(&state.app, ui_session_id, decision);
     }
+    Json(response)
 }
 
 /// Handle AskUserQuestion tool - extract questions and wait for user answers
@@ -153,6 +366,7 @@ async fn handle_ask_user_question(
                     allow: false,
                     message: Some(format!("Failed to parse questions: {}", e)),
                     answers: None,
+                    updated_input: None,
                 });
             }
         },
@@ -162,6 +376,7 @@ async fn handle_ask_user_question(
                 allow: false,
                 message: Some("No questions provided".to_string()),
                 answers: None,
+                updated_input: None,
             });
         }
     };
@@ -173,13 +388,7 @@ async fn handle_ask_user_question(
     );
 
     let request_id = Uuid::new_v4().to_string();
-    let (tx, rx) = oneshot::channel();
-
-    // Store the sender
-    {
-        let mut pending = state.pending.lock().await;
-        pending.insert(request_id.clone(), tx);
-    }
+    let rx = add_pending(&state, &request_id, &input).await;
 
     // Emit question event to frontend
     // Use session ID from MCP env var, or "orphan" if not available
@@ -199,80 +408,134 @@ async fn handle_ask_user_question(
         input.tool_use_id
     );
 
-    let _ = state.app.emit(
-        "horseman-event",
+    let _ = events::emit(
+        &state.app,
         BackendEvent::QuestionRequested {
             request_id: request_id.clone(),
             question: pending_question,
         },
     );
 
-    // Wait for response with timeout (170s to beat Claude's 180s timeout)
-    match tokio::time::timeout(
-        std::time::Duration::from_secs(170),
-        rx,
-    ).await {
-        Ok(Ok(response)) => {
+    match wait_for_answer(&state, &request_id, input.ui_session_id.as_deref(), rx).await {
+        Wait::Answered(response) => {
             debug_log!("MCP", "Question {} resolved: allow={}, answers={:?}", request_id, response.allow, response.answers);
             Json(response)
         }
-        Ok(Err(_)) => {
+        Wait::Cancelled => {
             debug_log!("MCP", "Question {} channel dropped", request_id);
             Json(PermissionResponse {
                 allow: false,
                 message: Some("Request cancelled".to_string()),
                 answers: None,
+                updated_input: None,
             })
         }
-        Err(_) => {
+        Wait::TimedOut => {
             debug_log!("MCP", "Question {} timed out", request_id);
-            let mut pending = state.pending.lock().await;
-            pending.remove(&request_id);
+            let _ = events::emit(&state.app, BackendEvent::QuestionResolved { request_id });
             Json(PermissionResponse {
                 allow: false,
                 message: Some("Timed out waiting for answer".to_string()),
                 answers: None,
+                updated_input: None,
             })
         }
     }
 }
 
+/// UI sessions with a permission or question waiting for the user. Blocks
+/// on the lock, so only for threads outside the async runtime.
+pub fn sessions_awaiting_answer(state: &HookServerState) -> HashSet<String> {
+    state.pending.blocking_lock().values().filter_map(|p| p.ui_session_id.clone()).collect()
+}
+
+/// Deny every pending permission and question, returning their request IDs
+pub async fn deny_all_pending(state: &Arc<HookServerState>, message: &str) -> Vec<String> {
+    let mut pending = state.pending.lock().await;
+    let mut denied = Vec::new();
+    for (request_id, request) in pending.drain() {
+        let _ = request.tx.send(PermissionResponse {
+            allow: false,
+            message: Some(message.to_string()),
+            answers: None,
+            updated_input: None,
+        });
+        // The map doesn't record which kind it was; resolving an unknown ID is a no-op
+        let _ = events::emit(
+            &state.app,
+            BackendEvent::PermissionResolved { request_id: request_id.clone() },
+        );
+        let _ = events::emit(
+            &state.app,
+            BackendEvent::QuestionResolved { request_id: request_id.clone() },
+        );
+        denied.push(request_id);
+    }
+    debug_log!("MCP", "Denied {} pending requests", denied.len());
+    denied
+}
+
 /// Respond to a pending permission request
 /// Called by Tauri command from frontend
 pub async fn respond_permission(
     state: &Arc<HookServerState>,
     request_id: String,
-    allow: bool,
-    message: Option<String>,
+    response: PermissionResponse,
     tool_name: Option<String>,
-    allow_for_session: bool,
-    answers: Option<HashMap<String, String>>,
+    scope: ApprovalScope,
 ) -> Result<(), String> {
+    let allow = response.allow;
     debug_log!(
         "MCP",
-        "Responding to request {} allow={} answers_len={}",
+        "Responding to request {} allow={} answers_len={} edited={}",
         request_id,
         allow,
-        answers.as_ref().map(|a| a.len()).unwrap_or(0)
+        response.answers.as_ref().map(|a| a.len()).unwrap_or(0),
+        response.updated_input.is_some()
     );
-    // If approved for session, add to approved set
-    if allow && allow_for_session {
-        if let Some(ref name) = tool_name {
-            let mut approved = state.session_approved.lock().await;
-            approved.insert(name.clone());
-            debug_log!("MCP", "Added '{}' to session-approved tools", name);
+    if response.updated_input.as_ref().is_some_and(|input| !input.is_object()) {
+        return Err("Updated tool input must be an object".to_string());
+    }
+    // Looked up before taking `pending`, so no request waits on the session manager
+    let working_directory = match (&scope, state.pending.lock().await.get(&request_id)) {
+        (ApprovalScope::PathPrefix(_), Some(request)) => request.ui_session_id.clone(),
+        _ => None,
+    };
+    let working_directory = match working_directory {
+        Some(id) => working_directory_of(state, &id).await,
+        None => None,
+    };
+    let mut pending = state.pending.lock().await;
+
+    // If approved for session, add to that session's approved set
+    if let (true, ApprovalScope::Session, Some(request)) = (allow, &scope, pending.get(&request_id)) {
+        let name = tool_name.clone().unwrap_or_else(|| request.tool_name.clone());
+        match request.ui_session_id {
+            Some(ref ui_session_id) => {
+                let mut approved = state.session_approved.lock().await;
+                approved.entry(ui_session_id.clone()).or_default().insert(name.clone());
+                debug_log!("MCP", "Added '{}' to session-approved tools of {}", name, ui_session_id);
+            }
+            None => debug_log!("MCP", "Request {} has no session to approve '{}' for, allowing once", request_id, name),
         }
     }
 
-    let mut pending = state.pending.lock().await;
+    if let (true, ApprovalScope::PathPrefix(prefix), Some(request)) = (allow, &scope, pending.get(&request_id)) {
+        let approval = PathApproval {
+            tool_name: tool_name.clone().unwrap_or_else(|| request.tool_name.clone()),
+            prefix: path_scope::resolve_prefix(prefix, working_directory.as_deref())?,
+            ui_session_id: request.ui_session_id.clone(),
+        };
+        debug_log!("MCP", "Approved '{}' under {:?}", approval.tool_name, approval.prefix);
+        state.path_approved.lock().await.push(approval);
+    }
 
-    if let Some(tx) = pending.remove(&request_id) {
-        let is_question = answers.is_some();
-        let response = PermissionResponse { allow, message, answers };
-        tx.send(response).map_err(|_| "Failed to send response".to_string())?;
+    if let Some(request) = pending.remove(&request_id) {
+        let is_question = response.answers.is_some();
+        request.tx.send(response).map_err(|_| "Failed to send response".to_string())?;
         debug_log!("MCP", "Permission {} responded: allow={}", request_id, allow);
-        let _ = state.app.emit(
-            "horseman-event",
+        let _ = events::emit(
+            &state.app,
             if is_question {
                 BackendEvent::QuestionResolved {
                     request_id: request_id.clone(),
@@ -288,3 +551,118 @@ pub async fn respond_permission(
         Err(format!("No pending request with id: {}", request_id))
     }
 }
+
+/// Set how urgently a session's prompts are shown; Normal clears the override
+pub async fn set_session_priority(state: &Arc<HookServerState>, ui_session_id: String, priority: SessionPriority) {
+    debug_log!("MCP", "Session {} priority -> {:?}", ui_session_id, priority);
+    let mut priorities = state.priorities.lock().await;
+    if priority == SessionPriority::default() {
+        priorities.remove(&ui_session_id);
+    } else {
+        priorities.insert(ui_session_id, priority);
+    }
+}
+
+/// Drop a removed session's tool and directory approvals
+pub async fn forget_session(state: &Arc<HookServerState>, ui_session_id: &str) {
+    state.session_approved.lock().await.remove(ui_session_id);
+    state.working_directories.lock().await.remove(ui_session_id);
+    state
+        .path_approved
+        .lock()
+        .await
+        .retain(|a| a.ui_session_id.as_deref() != Some(ui_session_id));
+}
+
+/// Pending prompts grouped by session, the lane to answer first at the front
+pub async fn permission_queue(state: &Arc<HookServerState>) -> Vec<PermissionLane> {
+    let pending = state.pending.lock().await;
+    let priorities = state.priorities.lock().await;
+    group_into_lanes(
+        pending.iter().map(|(request_id, request)| {
+            (
+                request.ui_session_id.clone(),
+                QueuedPrompt {
+                    request_id: request_id.clone(),
+                    tool_name: request.tool_name.clone(),
+                    requested_at: request.requested_at,
+                },
+            )
+        }),
+        &priorities,
+    )
+}
+
+/// Group prompts by session and order the lanes: higher priority first, then
+/// the lane that has been blocked longest
+fn group_into_lanes(
+    prompts: impl Iterator<Item = (Option<String>, QueuedPrompt)>,
+    priorities: &HashMap<String, SessionPriority>,
+) -> Vec<PermissionLane> {
+    let mut lanes: Vec<PermissionLane> = Vec::new();
+    for (ui_session_id, prompt) in prompts {
+        match lanes.iter_mut().find(|lane| lane.ui_session_id == ui_session_id) {
+            Some(lane) => lane.prompts.push(prompt),
+            None => lanes.push(PermissionLane {
+                priority: ui_session_id
+                    .as_ref()
+                    .and_then(|id| priorities.get(id).copied())
+                    .unwrap_or_default(),
+                ui_session_id,
+                prompts: vec![prompt],
+            }),
+        }
+    }
+    for lane in &mut lanes {
+        lane.prompts.sort_by_key(|p| p.requested_at);
+    }
+    lanes.sort_by_key(|lane| {
+        (
+            std::cmp::Reverse(lane.priority),
+            lane.prompts.first().map(|p| p.requested_at).unwrap_or(i64::MAX),
+        )
+    });
+    lanes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(session: &str, request_id: &str, requested_at: i64) -> (Option<String>, QueuedPrompt) {
+        (
+            Some(session.to_string()),
+            QueuedPrompt {
+                request_id: request_id.to_string(),
+                tool_name: "Bash".to_string(),
+                requested_at,
+            },
+        )
+    }
+
+    #[test]
+    fn oldest_high_priority_lane_comes_first() {
+        let priorities = HashMap::from([
+            ("b".to_string(), SessionPriority::High),
+            ("c".to_string(), SessionPriority::High),
+        ]);
+        let prompts = vec![
+            prompt("a", "a1", 10),
+            prompt("c", "c1", 40),
+            prompt("b", "b2", 50),
+            prompt("b", "b1", 30),
+        ];
+        let lanes = group_into_lanes(prompts.into_iter(), &priorities);
+        let order: Vec<_> = lanes.iter().map(|l| l.ui_session_id.as_deref().unwrap()).collect();
+        assert_eq!(order, ["b", "c", "a"]);
+        let b: Vec<_> = lanes[0].prompts.iter().map(|p| p.request_id.as_str()).collect();
+        assert_eq!(b, ["b1", "b2"]);
+    }
+
+    #[test]
+    fn timeouts_allow_only_read_only_tools_when_configured() {
+        assert!(timeout_response(PermissionTimeoutAction::AllowReadOnly, "Grep").allow);
+        assert!(!timeout_response(PermissionTimeoutAction::AllowReadOnly, "Bash").allow);
+        assert!(!timeout_response(PermissionTimeoutAction::Deny, "Read").allow);
+    }
+}
@@ -17,4 +17,61 @@ pub struct PermissionResponse {
     pub message: Option<String>,
     /// For AskUserQuestion: the user's answers
     pub answers: Option<HashMap<String, String>>,
+    /// Input to run the tool with instead of the one Claude asked for,
+    /// edited by the user before approving
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_input: Option<serde_json::Value>,
+}
+
+/// What an approval covers beyond the request it answers
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalScope {
+    Once,
+    /// The tool, for the rest of the session
+    Session,
+    /// The tool, for files below this directory (relative to the session's
+    /// working directory unless absolute)
+    PathPrefix(String),
+}
+
+/// What an unanswered permission prompt resolves to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionTimeoutAction {
+    #[default]
+    Deny,
+    /// Allow tools that only read (Read, Glob, Grep, ...), deny the rest
+    AllowReadOnly,
+}
+
+/// How urgently a session's permission prompts should be shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// A prompt waiting in the permission queue
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPrompt {
+    pub request_id: String,
+    pub tool_name: String,
+    /// Unix millis when the request arrived
+    pub requested_at: i64,
+}
+
+/// The pending prompts of one session. Lanes are ordered highest priority
+/// first, then by the oldest prompt.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionLane {
+    /// None for requests that didn't say which session they came from
+    pub ui_session_id: Option<String>,
+    pub priority: SessionPriority,
+    /// Oldest first
+    pub prompts: Vec<QueuedPrompt>,
 }
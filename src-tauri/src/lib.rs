@@ -1,10 +1,57 @@
+#[cfg(feature = "analytics")]
+mod activity_report;
+mod atomic_file;
+mod background_ai;
+#[cfg(feature = "analytics")]
+mod budget;
+mod capability_audit;
+mod checkpoint;
 mod claude;
 mod commands;
 mod config;
+mod connectivity;
+#[cfg(feature = "analytics")]
+mod cost_ledger;
+mod data_transfer;
 mod debug;
+#[cfg(feature = "e2e")]
+pub mod e2e;
 mod events;
+mod external_diff;
+mod fs_watch;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+mod git;
+mod heartbeat;
 mod hooks;
+mod login_shell;
+mod memory_watch;
+mod narration;
+mod network;
+mod open_sessions;
+mod panic_guard;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod prompt_history;
+mod providers;
+mod pending_questions;
+mod redaction;
+#[cfg(feature = "search")]
+mod search_index;
+mod session_cache;
+mod session_env;
+mod session_expiry;
+mod session_watch;
+mod shutdown;
 mod slash;
+mod slow_io;
+mod store;
+mod supervisor;
+#[cfg(feature = "analytics")]
+mod time_tracking;
+mod todo_sync;
+mod verification;
+mod workspace_diff;
 
 use commands::{
     ClaudeState,
@@ -28,8 +75,83 @@ use commands::{
     cancel_slash_command,
     get_status_info,
     get_diagnostics,
+    apply_suggested_edit,
+    list_checkpoints,
+    undo_checkpoint,
+    run_verification,
+    get_verification_fix_prompt,
+    send_verification_fix,
+    start_fix_loop,
+    stop_fix_loop,
+    get_raw_session_log,
+    get_session_metadata,
+    reparse_all_sessions,
+    get_backend_schema_version,
+    negotiate_event_schema,
+    export_horseman_data,
+    import_horseman_data,
+    reset_backend_state,
+    get_service_health,
+    recover_failed_resume,
+    get_startup_pending_questions,
+    list_suspended_sessions,
+    emergency_stop_all,
+    kill_all_sessions,
+    read_text_file,
+    load_ui_state,
+    save_ui_state,
+    get_capability_audit,
+    add_session_annotation,
+    list_session_annotations,
+    remove_session_annotation,
+    get_schema_drift_report,
+    fork_claude_session,
+    list_available_models,
+    restart_claude_session,
+    generate_claude_md,
+    write_claude_md,
+    save_clipboard_image,
+    consolidate_sessions,
+    restore_sessions,
+    get_background_ai_status,
+    set_background_ai_paused,
+    list_provider_profiles,
+    get_permission_queue,
+    set_session_priority,
+    get_session_meta,
+    set_session_title,
+    set_session_pinned,
+    set_session_tags,
+    clear_session_meta,
+    delete_claude_session,
+    export_session_archive,
+    import_session_archive,
+    get_time_report,
+    get_session_summary,
+    get_suggested_commit_message,
+    search_transcripts,
+    generate_activity_report,
+    subscribe_events,
+    tail_transcript,
+    untail_transcript,
+    get_enabled_features,
+    parse_session_transcript_page,
+    get_prompt_history,
+    clear_prompt_history,
+    undo_last_workspace_change,
+    redo_last_workspace_change,
+    get_tool_timeline,
+    open_external_diff,
+    get_cost_report,
+    list_transcript_backups,
+    restore_transcript_backup,
+    add_bash_allow_rule,
+    check_mcp_servers,
+    get_tool_catalog,
+    get_session_workspace_diff,
+    import_claude_permission_rules,
 };
-use config::{get_horseman_config, update_horseman_config, get_config_path};
+use config::{get_horseman_config, update_horseman_config, get_horseman_config_snapshot, update_horseman_config_patch, get_effective_config, get_config_path};
 use slash::SlashState;
 use claude::ClaudeManager;
 use std::sync::Mutex;
@@ -41,25 +163,136 @@ fn greet(name: &str) -> String {
 }
 
 /// Wrapper to keep the tokio runtime alive for the app's lifetime.
-/// The runtime is stored but never directly accessed - its presence
-/// keeps async tasks (like the hook server) running.
-struct TokioRuntime(#[allow(dead_code)] tokio::runtime::Runtime);
+/// Its presence keeps async tasks (like the hook server) running; the
+/// shutdown hook also uses it to deny pending permission requests.
+struct TokioRuntime(tokio::runtime::Runtime);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     debug::clear_log();
     debug_log!("APP", "Horseman starting...");
+    #[cfg(feature = "profiling")]
+    profiling::init();
+
+    let commands: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![
+        greet,
+        spawn_claude_session,
+        send_claude_message,
+        interrupt_claude_session,
+        is_claude_running,
+        remove_claude_session,
+        list_claude_sessions,
+        list_sessions_for_directory,
+        read_session_transcript,
+        parse_session_transcript,
+        extract_transcript_summary,
+        get_transcript_path,
+        respond_permission,
+        get_hook_server_port,
+        glob_files,
+        run_slash_command,
+        cancel_slash_command,
+        get_horseman_config,
+        update_horseman_config,
+        get_horseman_config_snapshot,
+        update_horseman_config_patch,
+        get_effective_config,
+        get_config_path,
+        get_status_info,
+        get_diagnostics,
+        apply_suggested_edit,
+        list_checkpoints,
+        undo_checkpoint,
+        run_verification,
+        get_verification_fix_prompt,
+        send_verification_fix,
+        start_fix_loop,
+        stop_fix_loop,
+        get_raw_session_log,
+        get_session_metadata,
+        reparse_all_sessions,
+        get_backend_schema_version,
+        negotiate_event_schema,
+        export_horseman_data,
+        import_horseman_data,
+        reset_backend_state,
+        get_service_health,
+        recover_failed_resume,
+        get_startup_pending_questions,
+        list_suspended_sessions,
+        emergency_stop_all,
+        kill_all_sessions,
+        read_text_file,
+        load_ui_state,
+        save_ui_state,
+        get_capability_audit,
+        add_session_annotation,
+        list_session_annotations,
+        remove_session_annotation,
+        get_schema_drift_report,
+        fork_claude_session,
+        list_available_models,
+        restart_claude_session,
+        generate_claude_md,
+        write_claude_md,
+        save_clipboard_image,
+        consolidate_sessions,
+        restore_sessions,
+        get_background_ai_status,
+        set_background_ai_paused,
+        list_provider_profiles,
+        get_permission_queue,
+        set_session_priority,
+        get_session_meta,
+        set_session_title,
+        set_session_pinned,
+        set_session_tags,
+        clear_session_meta,
+        delete_claude_session,
+        export_session_archive,
+        import_session_archive,
+        get_time_report,
+        get_session_summary,
+        get_suggested_commit_message,
+        search_transcripts,
+        generate_activity_report,
+        subscribe_events,
+        tail_transcript,
+        untail_transcript,
+        get_enabled_features,
+        parse_session_transcript_page,
+        get_prompt_history,
+        clear_prompt_history,
+        undo_last_workspace_change,
+        redo_last_workspace_change,
+        get_tool_timeline,
+        open_external_diff,
+        get_cost_report,
+        list_transcript_backups,
+        restore_transcript_backup,
+        add_bash_allow_rule,
+        check_mcp_servers,
+        get_tool_catalog,
+        get_session_workspace_diff,
+        import_claude_permission_rules,
+    ];
 
     tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                events::subscribe(window.label(), None);
+            }
+        })
         .setup(|app| {
             debug_log!("APP", "Running setup...");
+            capability_audit::log_startup_audit();
+            // Left over from a run that didn't exit cleanly
+            hooks::mcp_cleanup::sweep();
 
             // Start hook server
             let app_handle = app.handle().clone();
@@ -77,7 +310,7 @@ pub fn run() {
 
             // Set hook port in ClaudeManager
             {
-                let mut manager = claude_state.0.lock().unwrap();
+                let mut manager = claude_state.lock();
                 manager.set_hook_port(port);
             }
 
@@ -91,32 +324,34 @@ pub fn run() {
             app.manage(slash_state);
             app.manage(TokioRuntime(rt)); // Keep runtime alive!
 
+            pending_questions::spawn_startup_scan(app.handle().clone());
+            claude::idle_suspend::spawn(app.handle().clone());
+            claude::reaper::spawn(app.handle().clone());
+            heartbeat::spawn(app.handle().clone(), port);
+            session_watch::spawn(app.handle().clone());
+            session_expiry::spawn();
+            memory_watch::spawn(app.handle().clone());
+            background_ai::spawn();
+            connectivity::spawn(app.handle().clone());
+            todo_sync::spawn(app.handle().clone());
+            #[cfg(feature = "analytics")]
+            time_tracking::spawn(app.handle().clone());
+            #[cfg(feature = "search")]
+            if config::search_enabled() {
+                search_index::spawn_warmup();
+            }
+
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            greet,
-            spawn_claude_session,
-            send_claude_message,
-            interrupt_claude_session,
-            is_claude_running,
-            remove_claude_session,
-            list_claude_sessions,
-            list_sessions_for_directory,
-            read_session_transcript,
-            parse_session_transcript,
-            extract_transcript_summary,
-            get_transcript_path,
-            respond_permission,
-            get_hook_server_port,
-            glob_files,
-            run_slash_command,
-            cancel_slash_command,
-            get_horseman_config,
-            update_horseman_config,
-            get_config_path,
-            get_status_info,
-            get_diagnostics,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(move |invoke| {
+            profile_span!("command", name = invoke.message.command());
+            commands(invoke)
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown::run(app);
+            }
+        });
 }
@@ -0,0 +1,103 @@
+//! The user's login shell environment. An app started from the Dock or a
+//! desktop launcher gets a minimal PATH and none of the variables set in
+//! the user's profile; Claude is spawned through `$SHELL -l`, so anything
+//! Horseman looks up on its behalf (programs, `${VAR}`s) has to come from
+//! the same place. Captured once, on first use.
+
+use crate::debug_log;
+use crate::slow_io;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// How long a login shell may take to read the profile
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Printed before `env` so profile output on stdout can be told apart
+const MARKER: &str = "__HORSEMAN_ENV__";
+
+static ENVIRONMENT: Lazy<HashMap<String, String>> = Lazy::new(capture);
+
+/// The user's shell
+pub fn shell() -> String {
+    std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string())
+}
+
+/// `env` output after `MARKER`. Lines that don't start a variable continue
+/// the previous value (multi-line values).
+fn parse_env(output: &str) -> HashMap<String, String> {
+    let mut vars: HashMap<String, String> = HashMap::new();
+    let mut last: Option<String> = None;
+    let after_marker = output.split_once(MARKER).map_or("", |(_, rest)| rest);
+    for line in after_marker.lines().skip_while(|l| l.is_empty()) {
+        let name = line
+            .split_once('=')
+            .map(|(name, _)| name)
+            .filter(|n| n.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+            .filter(|n| n.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        match (name, &last) {
+            (Some(name), _) => {
+                vars.insert(name.to_string(), line[name.len() + 1..].to_string());
+                last = Some(name.to_string());
+            }
+            (None, Some(previous)) => {
+                if let Some(value) = vars.get_mut(previous) {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    vars
+}
+
+fn capture() -> HashMap<String, String> {
+    let shell = shell();
+    let script = format!("echo {}; env", MARKER);
+    let output = slow_io::with_timeout(CAPTURE_TIMEOUT, move || {
+        Command::new(shell).args(["-l", "-c", &script]).stdin(Stdio::null()).stderr(Stdio::null()).output()
+    });
+    match output {
+        Some(Ok(output)) if output.status.success() => {
+            let vars = parse_env(&String::from_utf8_lossy(&output.stdout));
+            if vars.contains_key("PATH") {
+                return vars;
+            }
+            debug_log!("SHELL", "Login shell printed no PATH, using Horseman's environment");
+        }
+        Some(Ok(output)) => debug_log!("SHELL", "Login shell exited with {:?}", output.status.code()),
+        Some(Err(e)) => debug_log!("SHELL", "Failed to start login shell: {}", e),
+        None => debug_log!("SHELL", "Login shell took over {:?}", CAPTURE_TIMEOUT),
+    }
+    std::env::vars().collect()
+}
+
+/// A variable as the login shell sets it
+pub fn var(name: &str) -> Option<String> {
+    ENVIRONMENT.get(name).cloned()
+}
+
+/// Find `program` on the login shell's PATH
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    let path = ENVIRONMENT.get("PATH")?;
+    std::env::split_paths(path).map(|dir| dir.join(program)).find(|p| p.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_env_after_profile_output() {
+        let output = "Welcome back!\nPATH=/nope\n__HORSEMAN_ENV__\nPATH=/opt/bin:/usr/bin\nGREETING=a=b\n\
+                      MULTI=one\ntwo\nHOME=/home/me\n";
+        let vars = parse_env(output);
+        assert_eq!(vars["PATH"], "/opt/bin:/usr/bin");
+        assert_eq!(vars["GREETING"], "a=b");
+        assert_eq!(vars["MULTI"], "one\ntwo");
+        assert_eq!(vars.len(), 4);
+        assert!(parse_env("PATH=/usr/bin\n").is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+//! Watches Claude's memory files (CLAUDE.md, CLAUDE.local.md and rules) for
+//! the user and every project with a session, and emits `memory.changed`.
+//! Keep-alive sessions read memory once at spawn, so edits only take effect
+//! after a restart.
+
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, MemoryScope};
+use crate::panic_guard;
+use crate::supervisor;
+use crate::fs_watch::FsWatcher;
+use notify::RecursiveMode;
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Editors write a file in several steps; wait for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+const MEMORY_FILE_NAMES: &[&str] = &["CLAUDE.md", "CLAUDE.local.md"];
+
+/// Project directories whose memory is watched
+static PROJECTS: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// The live watcher, so projects added later can be watched too
+static WATCHER: Lazy<Mutex<Option<FsWatcher>>> = Lazy::new(|| Mutex::new(None));
+
+fn user_claude_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".claude"))
+}
+
+/// Whether `path` is a memory file: CLAUDE.md / CLAUDE.local.md, or a
+/// markdown file under a `.claude/rules` directory
+fn is_memory_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if MEMORY_FILE_NAMES.contains(&name) {
+        return true;
+    }
+    path.extension().and_then(|e| e.to_str()) == Some("md")
+        && path.ancestors().any(|a| {
+            a.file_name().and_then(|n| n.to_str()) == Some("rules")
+                && a.parent().and_then(|p| p.file_name()).and_then(|n| n.to_str()) == Some(".claude")
+        })
+}
+
+/// Scope and project of a memory file, None if it isn't one we watch
+pub(crate) fn classify(
+    path: &Path,
+    user_dir: Option<&Path>,
+    projects: &HashSet<PathBuf>,
+) -> Option<(MemoryScope, Option<PathBuf>)> {
+    if !is_memory_file(path) {
+        return None;
+    }
+    if user_dir.is_some_and(|d| path.starts_with(d)) {
+        return Some((MemoryScope::User, None));
+    }
+    projects
+        .iter()
+        .filter(|p| path.starts_with(p))
+        .max_by_key(|p| p.as_os_str().len())
+        .map(|p| (MemoryScope::Project, Some(p.clone())))
+}
+
+/// Watch the places Claude reads a project's memory from. Missing
+/// directories are skipped; CLAUDE.md itself is caught by watching its parent.
+fn watch_project_dirs(watcher: &mut FsWatcher, project: &Path) {
+    let claude_dir = project.join(".claude");
+    let targets = [
+        (project.to_path_buf(), RecursiveMode::NonRecursive),
+        (claude_dir.clone(), RecursiveMode::NonRecursive),
+        (claude_dir.join("rules"), RecursiveMode::Recursive),
+    ];
+    for (dir, mode) in targets {
+        if dir.is_dir() {
+            if let Err(e) = watcher.watch(&dir, mode) {
+                debug_log!("MEMORY_WATCH", "{}", e);
+            }
+        }
+    }
+}
+
+/// Start watching a project's memory files (no-op if already watched)
+pub fn watch_project(working_directory: &str) {
+    let project = PathBuf::from(working_directory.trim_end_matches('/'));
+    if !panic_guard::lock_or_recover(&PROJECTS, "memory projects").insert(project.clone()) {
+        return;
+    }
+    if let Some(watcher) = panic_guard::lock_or_recover(&WATCHER, "memory watcher").as_mut() {
+        watch_project_dirs(watcher, &project);
+        debug_log!("MEMORY_WATCH", "Watching memory for {:?}", project);
+    }
+}
+
+fn emit_change(app: &AppHandle, path: &Path, scope: MemoryScope, project: Option<PathBuf>) {
+    let working_directory = project.map(|p| p.to_string_lossy().to_string());
+    let affected_sessions = app
+        .state::<ClaudeState>()
+        .lock()
+        .live_persistent_sessions(working_directory.as_deref());
+    let prompt_restart = !affected_sessions.is_empty() && config::prompt_restart_on_memory_change();
+    debug_log!(
+        "MEMORY_WATCH",
+        "{:?} changed ({} live sessions affected)",
+        path,
+        affected_sessions.len()
+    );
+    let _ = events::emit(
+        app,
+        BackendEvent::MemoryChanged {
+            path: path.to_string_lossy().to_string(),
+            scope,
+            working_directory,
+            affected_sessions,
+            prompt_restart,
+        },
+    );
+}
+
+fn run(app: &AppHandle) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = FsWatcher::new("memoryWatcher", tx)?;
+
+    let user_dir = user_claude_dir();
+    if let Some(ref dir) = user_dir {
+        for (target, mode) in [(dir.clone(), RecursiveMode::NonRecursive), (dir.join("rules"), RecursiveMode::Recursive)] {
+            if target.is_dir() {
+                watcher.watch(&target, mode)?;
+            }
+        }
+    }
+    let projects: Vec<PathBuf> = panic_guard::lock_or_recover(&PROJECTS, "memory projects")
+        .iter()
+        .cloned()
+        .collect();
+    for project in &projects {
+        watch_project_dirs(&mut watcher, project);
+    }
+    *panic_guard::lock_or_recover(&WATCHER, "memory watcher") = Some(watcher);
+    debug_log!("MEMORY_WATCH", "Watching user memory and {} projects", projects.len());
+
+    let mut dirty: HashSet<PathBuf> = HashSet::new();
+    loop {
+        if let Some(watcher) = panic_guard::lock_or_recover(&WATCHER, "memory watcher").as_mut() {
+            watcher.tick();
+        }
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(Ok(event)) => {
+                if event.kind.is_access() {
+                    continue;
+                }
+                dirty.extend(event.paths.into_iter().filter(|p| is_memory_file(p)));
+                continue;
+            }
+            Ok(Err(e)) => return Err(format!("Watch error: {}", e)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Err("Watcher stopped".to_string()),
+        }
+        if dirty.is_empty() {
+            continue;
+        }
+        let projects = panic_guard::lock_or_recover(&PROJECTS, "memory projects").clone();
+        for path in std::mem::take(&mut dirty) {
+            if let Some((scope, project)) = classify(&path, user_dir.as_deref(), &projects) {
+                emit_change(app, &path, scope, project);
+            }
+        }
+    }
+}
+
+/// Start the supervised memory watcher
+pub fn spawn(app: AppHandle) {
+    supervisor::spawn_thread("memoryWatcher", move || run(&app));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_memory_files() {
+        let user = PathBuf::from("/home/me/.claude");
+        let projects: HashSet<PathBuf> = [PathBuf::from("/work/app"), PathBuf::from("/work/app/sub")].into();
+        let classify = |p: &str| classify(Path::new(p), Some(&user), &projects);
+
+        assert_eq!(classify("/home/me/.claude/CLAUDE.md"), Some((MemoryScope::User, None)));
+        assert_eq!(classify("/home/me/.claude/rules/team/style.md"), Some((MemoryScope::User, None)));
+        assert_eq!(
+            classify("/work/app/CLAUDE.local.md"),
+            Some((MemoryScope::Project, Some(PathBuf::from("/work/app"))))
+        );
+        assert_eq!(
+            classify("/work/app/sub/.claude/rules/db.md"),
+            Some((MemoryScope::Project, Some(PathBuf::from("/work/app/sub"))))
+        );
+        assert_eq!(classify("/work/app/README.md"), None);
+        assert_eq!(classify("/work/other/CLAUDE.md"), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Plain-text narration of backend events for screen-reader announcements
+//! and minimal log views (see `narrationEnabled`). Each significant event is
+//! also sent as a `narration` event carrying one sentence ("Claude edited
+//! src/main.rs", "Waiting for your approval to run npm install"); streaming
+//! text, usage and listing updates are not narrated.
+
+use crate::config;
+use crate::events::{BackendEvent, SessionEndReason, ToolCall};
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Longest command or description quoted in a sentence
+const MAX_DETAIL_CHARS: usize = 80;
+/// Started tools remembered for their completion sentence
+const MAX_PENDING_TOOLS: usize = 1000;
+
+/// What to say when a started tool finishes: (on success, on failure)
+static PENDING_TOOLS: Lazy<Mutex<HashMap<String, (String, String)>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// `narrationEnabled` with the config revision it was read at; every emit
+/// asks, so the config isn't cloned each time
+static ENABLED: Lazy<Mutex<Option<(u64, bool)>>> = Lazy::new(|| Mutex::new(None));
+
+fn enabled() -> bool {
+    let revision = config::revision();
+    let mut cached = panic_guard::lock_or_recover(&ENABLED, "narration flag");
+    if let Some((read_at, enabled)) = *cached {
+        if read_at == revision {
+            return enabled;
+        }
+    }
+    let enabled = config::narration_enabled();
+    *cached = Some((revision, enabled));
+    enabled
+}
+
+fn detail(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("").trim();
+    if line.chars().count() <= MAX_DETAIL_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(MAX_DETAIL_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Last two components of a path, which usually identify the file
+fn short_path(path: &str) -> String {
+    let components: Vec<_> = Path::new(path).components().collect();
+    let start = components.len().saturating_sub(2);
+    components[start..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn input_str<'a>(input: &'a serde_json::Value, key: &str) -> &'a str {
+    input.get(key).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+/// What a tool is about to do, for approval prompts ("run npm install")
+fn tool_action(name: &str, input: &serde_json::Value) -> String {
+    match name {
+        "Bash" => format!("run {}", detail(input_str(input, "command"))),
+        "Edit" | "MultiEdit" | "NotebookEdit" => format!("edit {}", short_path(input_str(input, "file_path"))),
+        "Write" => format!("write {}", short_path(input_str(input, "file_path"))),
+        "WebFetch" => format!("fetch {}", detail(input_str(input, "url"))),
+        _ => format!("use {}", name),
+    }
+}
+
+/// Sentences for a started tool, its success and its failure. Reads and
+/// searches are too frequent to be worth announcing.
+fn tool_sentences(tool: &ToolCall) -> Option<(Option<String>, String, String)> {
+    let input = &tool.input;
+    let file = || short_path(input_str(input, "file_path"));
+    match tool.name.as_str() {
+        "Edit" | "MultiEdit" | "NotebookEdit" => {
+            Some((None, format!("Claude edited {}", file()), format!("Editing {} failed", file())))
+        }
+        "Write" => Some((None, format!("Claude wrote {}", file()), format!("Writing {} failed", file()))),
+        "Bash" => {
+            let command = detail(input_str(input, "command"));
+            Some((
+                Some(format!("Claude is running {}", command)),
+                format!("Finished running {}", command),
+                format!("Command failed: {}", command),
+            ))
+        }
+        "Task" => {
+            let description = detail(input_str(input, "description"));
+            Some((
+                Some(format!("Claude started a subagent to {}", description)),
+                format!("Subagent finished: {}", description),
+                format!("Subagent failed: {}", description),
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn finish_tool(tool_id: &str, failed: bool) -> Option<String> {
+    let (done, error) = panic_guard::lock_or_recover(&PENDING_TOOLS, "narration tools").remove(tool_id)?;
+    Some(if failed { error } else { done })
+}
+
+/// The sentence announcing `event`, if it's worth announcing
+fn sentence(event: &BackendEvent) -> Option<String> {
+    match event {
+        BackendEvent::SessionStarted { .. } => Some("Session started".to_string()),
+        BackendEvent::SessionEnded { error: Some(error), .. } => {
+            Some(format!("Session ended with an error: {}", detail(error)))
+        }
+        BackendEvent::SessionEnded { reason: Some(SessionEndReason::MaxTurns), .. } => {
+            Some("Claude stopped at the turn limit".to_string())
+        }
+        BackendEvent::SessionEnded { .. } | BackendEvent::TurnCompleted { .. } => Some("Claude finished".to_string()),
+        BackendEvent::ToolStarted { tool, .. } => {
+            let (started, done, failed) = tool_sentences(tool)?;
+            let mut pending = panic_guard::lock_or_recover(&PENDING_TOOLS, "narration tools");
+            if pending.len() >= MAX_PENDING_TOOLS {
+                pending.clear();
+            }
+            pending.insert(tool.id.clone(), (done, failed));
+            started
+        }
+        BackendEvent::ToolCompleted { tool_id, .. } => finish_tool(tool_id, false),
+        BackendEvent::ToolError { tool_id, .. } => finish_tool(tool_id, true),
+        BackendEvent::PermissionRequested { tool_name, tool_input, .. } => {
+            Some(format!("Waiting for your approval to {}", tool_action(tool_name, tool_input)))
+        }
+        // Countdown ticks are 10s apart, so exactly one lands in this window
+        BackendEvent::PermissionExpiring { seconds_left, .. } if (21..=30).contains(seconds_left) => {
+            Some(format!("Approval times out in {} seconds", seconds_left))
+        }
+        BackendEvent::QuestionRequested { question, .. } => match question.questions.first() {
+            Some(q) => Some(format!("Claude is asking: {}", detail(&q.question))),
+            None => Some("Claude has a question for you".to_string()),
+        },
+        BackendEvent::VerificationCompleted { passed: true, .. } => Some("Checks passed".to_string()),
+        BackendEvent::VerificationCompleted { passed: false, .. } => Some("Checks failed".to_string()),
+        BackendEvent::WorkspaceConflictRisk { path, .. } => {
+            Some(format!("Another session is also editing {}", short_path(path)))
+        }
+        BackendEvent::SessionQueued { position, .. } => Some(format!("Session queued, number {} in line", position)),
+        BackendEvent::SessionRestarting { attempt, max_retries, .. } => {
+            Some(format!("Claude crashed, restarting (attempt {} of {})", attempt, max_retries))
+        }
+        BackendEvent::SessionLineTruncated { .. } => Some("Some output was too large to show".to_string()),
+        BackendEvent::SessionRateLimited { attempt: Some(attempt), max_retries, .. } => Some(format!(
+            "Claude is rate limited, retrying (attempt {} of {})",
+            attempt,
+            max_retries.unwrap_or(*attempt)
+        )),
+        BackendEvent::SessionRateLimited { .. } => Some("Claude is rate limited".to_string()),
+        BackendEvent::UsageThreshold { level, .. } => Some(format!("Context is {}% full", level)),
+        BackendEvent::SessionCompacting { .. } => Some("Compacting the conversation".to_string()),
+        BackendEvent::SessionCompacted { error: None, .. } => Some("Conversation compacted".to_string()),
+        BackendEvent::SessionCompacted { error: Some(_), .. } => Some("Compacting the conversation failed".to_string()),
+        BackendEvent::SessionSuspended { .. } => Some("Idle session paused".to_string()),
+        BackendEvent::SessionResumeFailed { .. } => Some("Could not resume the session".to_string()),
+        BackendEvent::ConnectivityChanged { online: false, .. } => Some("Connection lost".to_string()),
+        BackendEvent::ConnectivityChanged { online: true, .. } => Some("Back online".to_string()),
+        BackendEvent::EmergencyStopped { .. } => Some("All sessions stopped".to_string()),
+        BackendEvent::LoopIteration { iteration, max_iterations, status, .. } => {
+            Some(format!("Fix loop iteration {} of {}: {}", iteration, max_iterations, status))
+        }
+        BackendEvent::BudgetWarning { period, spent_usd, limit_usd } => Some(format!(
+            "{} spending is at ${:.2} of the ${:.2} budget",
+            period.label(),
+            spent_usd,
+            limit_usd
+        )),
+        BackendEvent::BudgetExceeded { period, limit_usd, .. } => {
+            Some(format!("{} budget of ${:.2} exceeded", period.label(), limit_usd))
+        }
+        _ => None,
+    }
+}
+
+/// UI session a narrated event belongs to, if any
+fn session_of(event: &BackendEvent) -> Option<String> {
+    match event {
+        BackendEvent::SessionStarted { ui_session_id, .. }
+        | BackendEvent::SessionEnded { ui_session_id, .. }
+        | BackendEvent::TurnCompleted { ui_session_id, .. }
+        | BackendEvent::ToolStarted { ui_session_id, .. }
+        | BackendEvent::ToolCompleted { ui_session_id, .. }
+        | BackendEvent::ToolError { ui_session_id, .. }
+        | BackendEvent::VerificationCompleted { ui_session_id, .. }
+        | BackendEvent::WorkspaceConflictRisk { ui_session_id, .. }
+        | BackendEvent::SessionQueued { ui_session_id, .. }
+        | BackendEvent::SessionRestarting { ui_session_id, .. }
+        | BackendEvent::SessionLineTruncated { ui_session_id, .. }
+        | BackendEvent::SessionRateLimited { ui_session_id, .. }
+        | BackendEvent::UsageThreshold { ui_session_id, .. }
+        | BackendEvent::SessionCompacting { ui_session_id, .. }
+        | BackendEvent::SessionCompacted { ui_session_id, .. }
+        | BackendEvent::SessionSuspended { ui_session_id, .. }
+        | BackendEvent::SessionResumeFailed { ui_session_id, .. }
+        | BackendEvent::LoopIteration { ui_session_id, .. } => Some(ui_session_id.clone()),
+        BackendEvent::PermissionRequested { ui_session_id, .. }
+        | BackendEvent::PermissionExpiring { ui_session_id, .. } => ui_session_id.clone(),
+        _ => None,
+    }
+}
+
+/// The `narration` event to send after `event`, when narration is on
+pub fn narrate(event: &BackendEvent) -> Option<BackendEvent> {
+    // Frequent events that are never narrated skip the config lookup
+    let streaming = matches!(
+        event,
+        BackendEvent::MessageDelta { .. }
+            | BackendEvent::MessageAssistant { .. }
+            | BackendEvent::MessageThinking { .. }
+            | BackendEvent::UsageUpdated { .. }
+            | BackendEvent::TranscriptAppended { .. }
+            | BackendEvent::SlashOutput { .. }
+            | BackendEvent::Narration { .. }
+    );
+    if streaming || !enabled() {
+        return None;
+    }
+    Some(BackendEvent::Narration {
+        text: sentence(event)?,
+        ui_session_id: session_of(event),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::types::SessionPriority;
+    use serde_json::json;
+
+    #[test]
+    fn narrates_edits_and_approvals() {
+        let edit = ToolCall {
+            id: "t1".to_string(),
+            name: "Edit".to_string(),
+            input: json!({"file_path": "/repo/src/main.rs"}),
+            status: "running".to_string(),
+            output: None,
+            error: None,
+            parent_tool_id: None,
+            started_at: None,
+            ended_at: None,
+            subagent: None,
+            usage: None,
+        };
+        let session = || "s1".to_string();
+        assert_eq!(sentence(&BackendEvent::ToolStarted { ui_session_id: session(), tool: edit }), None);
+        let completed = BackendEvent::ToolCompleted {
+            ui_session_id: session(),
+            tool_id: "t1".to_string(),
+            output: String::new(),
+            ended_at: String::new(),
+        };
+        assert_eq!(sentence(&completed).as_deref(), Some("Claude edited src/main.rs"));
+        assert_eq!(sentence(&completed), None);
+        assert_eq!(session_of(&completed).as_deref(), Some("s1"));
+
+        let permission = BackendEvent::PermissionRequested {
+            request_id: "r1".to_string(),
+            tool_name: "Bash".to_string(),
+            tool_input: json!({"command": "npm install"}),
+            ui_session_id: Some(session()),
+            priority: SessionPriority::default(),
+        };
+        assert_eq!(sentence(&permission).as_deref(), Some("Waiting for your approval to run npm install"));
+        assert_eq!(session_of(&permission).as_deref(), Some("s1"));
+    }
+}
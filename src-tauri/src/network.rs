@@ -0,0 +1,60 @@
+//! Proxy and custom CA settings (`httpsProxy`, `noProxy`, `caCertPath`) for
+//! everything that leaves the machine: spawned claude processes (and through
+//! them horseman-mcp) get the standard env vars, the updater gets the proxy.
+
+use crate::config;
+
+/// Hosts that must never go through the proxy: the permission callback
+/// from horseman-mcp is plain HTTP to the local hook server
+const LOOPBACK: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+/// `NO_PROXY` value: the configured list plus loopback
+fn no_proxy_list(configured: Option<&str>) -> String {
+    let mut hosts: Vec<String> = configured
+        .unwrap_or("")
+        .split(',')
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .collect();
+    for host in LOOPBACK {
+        if !hosts.iter().any(|h| h == host) {
+            hosts.push(host.to_string());
+        }
+    }
+    hosts.join(",")
+}
+
+/// Env vars for a spawned claude process. Empty when no proxy or CA is
+/// configured, so the user's own environment is left alone.
+pub fn spawn_env() -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    if let Some(proxy) = config::https_proxy() {
+        for key in ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"] {
+            env.push((key.to_string(), proxy.clone()));
+        }
+        let no_proxy = no_proxy_list(config::no_proxy().as_deref());
+        env.push(("NO_PROXY".to_string(), no_proxy.clone()));
+        env.push(("no_proxy".to_string(), no_proxy));
+    }
+    if let Some(ca) = config::ca_cert_path() {
+        let ca = ca.to_string_lossy().to_string();
+        // Node (the CLI) and horseman-mcp (reqwest) read different variables
+        env.push(("NODE_EXTRA_CA_CERTS".to_string(), ca.clone()));
+        env.push(("HORSEMAN_CA_CERT".to_string(), ca));
+    }
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_proxy_always_covers_loopback() {
+        assert_eq!(no_proxy_list(None), "localhost,127.0.0.1,::1");
+        assert_eq!(
+            no_proxy_list(Some(" .corp.example, localhost ")),
+            ".corp.example,localhost,127.0.0.1,::1"
+        );
+    }
+}
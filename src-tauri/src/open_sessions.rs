@@ -0,0 +1,158 @@
+//! Sessions open in the UI, persisted to `open_sessions.json` in the config
+//! dir so a relaunch can bring them back (UI session → Claude session,
+//! working directory and spawn options).
+
+use crate::atomic_file;
+use crate::claude::SessionOptions;
+use crate::config;
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Serializes read-modify-write cycles on the store file
+static STORE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenSession {
+    pub ui_session_id: String,
+    /// Known once the CLI reports it; entries without one can't be resumed
+    pub claude_session_id: Option<String>,
+    pub working_directory: String,
+    /// What the last spawn used (model, keep-alive, permission mode, tool
+    /// lists, extra directories, turn limit, provider, ...)
+    #[serde(flatten)]
+    pub options: SessionOptions,
+    /// RFC 3339 time of the last spawn
+    pub last_active_at: String,
+}
+
+fn store_path() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|d| d.join("open_sessions.json"))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn load() -> Vec<OpenSession> {
+    store_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn update(change: impl FnOnce(&mut Vec<OpenSession>)) -> Result<(), String> {
+    let _guard = panic_guard::lock_or_recover(&STORE_LOCK, "open sessions");
+    let mut sessions = load();
+    let before = sessions.clone();
+    change(&mut sessions);
+    if sessions == before {
+        return Ok(());
+    }
+    let path = store_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let content = serde_json::to_string_pretty(&sessions)
+        .map_err(|e| format!("Failed to serialize open sessions: {}", e))?;
+    atomic_file::write(&path, content).map_err(|e| format!("Failed to write open sessions: {}", e))
+}
+
+fn log_error(result: Result<(), String>) {
+    if let Err(e) = result {
+        debug_log!("OPEN_SESSIONS", "{}", e);
+    }
+}
+
+/// Remember a session at spawn; keeps the Claude session ID already known
+/// unless `claude_session_id` replaces it
+pub fn record(
+    ui_session_id: &str,
+    claude_session_id: Option<&str>,
+    working_directory: &str,
+    options: &SessionOptions,
+) {
+    log_error(update(|sessions| {
+        let previous = sessions.iter().position(|s| s.ui_session_id == ui_session_id).map(|i| sessions.remove(i));
+        sessions.push(OpenSession {
+            ui_session_id: ui_session_id.to_string(),
+            claude_session_id: claude_session_id
+                .map(str::to_string)
+                .or_else(|| previous.and_then(|p| p.claude_session_id)),
+            working_directory: working_directory.to_string(),
+            options: options.clone(),
+            last_active_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }));
+}
+
+/// Attach the Claude session ID reported by the CLI
+pub fn set_claude_session_id(ui_session_id: &str, claude_session_id: &str) {
+    log_error(update(|sessions| {
+        if let Some(session) = sessions.iter_mut().find(|s| s.ui_session_id == ui_session_id) {
+            session.claude_session_id = Some(claude_session_id.to_string());
+        }
+    }));
+}
+
+/// Drop a session the user closed
+pub fn forget(ui_session_id: &str) {
+    log_error(update(|sessions| sessions.retain(|s| s.ui_session_id != ui_session_id)));
+}
+
+/// Drop every entry that resumes `claude_session_id` (its transcript is gone)
+pub fn forget_claude_session(claude_session_id: &str) {
+    log_error(update(|sessions| {
+        sessions.retain(|s| s.claude_session_id.as_deref() != Some(claude_session_id))
+    }));
+}
+
+/// Sessions that can be resumed, most recently active first
+pub fn resumable() -> Vec<OpenSession> {
+    let mut sessions: Vec<OpenSession> = load().into_iter().filter(|s| s.claude_session_id.is_some()).collect();
+    sessions.sort_by(|a, b| b.last_active_at.cmp(&a.last_active_at));
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::PermissionMode;
+
+    #[test]
+    fn keeps_every_spawn_option() {
+        let session = OpenSession {
+            ui_session_id: "ui".to_string(),
+            claude_session_id: Some("c1".to_string()),
+            working_directory: "/work".to_string(),
+            options: SessionOptions {
+                model: Some("opus".to_string()),
+                persistent: true,
+                permission_mode: Some(PermissionMode::Plan),
+                allowed_tools: vec!["Read".to_string()],
+                disallowed_tools: vec!["WebFetch".to_string()],
+                max_turns: Some(5),
+                add_dirs: vec!["/shared".to_string()],
+                fork_session: false,
+                thinking_budget: Some(2048),
+                partial_messages: true,
+                provider: Some("bedrock".to_string()),
+                confirm_over_budget: false,
+            },
+            last_active_at: "2026-01-01T00:00:00Z".to_string(),
+        };
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(serde_json::from_str::<OpenSession>(&json).unwrap(), session);
+
+        // Entries written before options were kept whole still load
+        let old = r#"{"uiSessionId":"ui","claudeSessionId":"c1","workingDirectory":"/work",
+                      "model":"opus","persistent":true,"lastActiveAt":"2026-01-01T00:00:00Z"}"#;
+        let old: OpenSession = serde_json::from_str(old).unwrap();
+        assert_eq!((old.options.model.as_deref(), old.options.persistent), (Some("opus"), true));
+        assert_eq!(old.options.permission_mode, None);
+    }
+}
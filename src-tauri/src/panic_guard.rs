@@ -0,0 +1,59 @@
+//! Helpers for surviving panics in background reader threads. A panic there
+//! would otherwise silently stop a session from updating.
+
+use crate::debug_log;
+use crate::events::{self, BackendEvent};
+use std::any::Any;
+use std::sync::{Mutex, MutexGuard};
+use tauri::AppHandle;
+
+/// Extract the message from a caught panic payload
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Log and emit `session.reader_crashed`
+pub fn emit_reader_crashed(
+    app: &AppHandle,
+    ui_session_id: Option<&str>,
+    command_id: Option<&str>,
+    reader: &str,
+    payload: &(dyn Any + Send),
+    fallback: bool,
+) {
+    let message = panic_message(payload);
+    debug_log!(
+        "PANIC",
+        "{} reader crashed (session {:?}, command {:?}): {}",
+        reader,
+        ui_session_id,
+        command_id,
+        message
+    );
+    let _ = events::emit(
+        app,
+        BackendEvent::SessionReaderCrashed {
+            ui_session_id: ui_session_id.map(|s| s.to_string()),
+            command_id: command_id.map(|s| s.to_string()),
+            reader: reader.to_string(),
+            message,
+            fallback,
+        },
+    );
+}
+
+/// Lock a mutex, recovering the inner state if a previous holder panicked.
+/// The data may be mid-update; `reset_backend_state` rebuilds it if needed.
+pub fn lock_or_recover<'a, T>(mutex: &'a Mutex<T>, name: &str) -> MutexGuard<'a, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        debug_log!("PANIC", "Recovering poisoned {} lock", name);
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
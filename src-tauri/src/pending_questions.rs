@@ -0,0 +1,93 @@
+//! Startup scan for AskUserQuestion calls left unanswered in recent
+//! transcripts, so blocked sessions are visible as soon as the app opens.
+
+use crate::claude::parse_transcript_file;
+use crate::commands::discover_sessions;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, SessionPendingQuestion};
+use crate::panic_guard;
+use crate::slow_io;
+use crate::supervisor;
+use once_cell::sync::Lazy;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::AppHandle;
+
+/// Only transcripts modified this recently are scanned
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Upper bound on transcripts parsed per scan
+const MAX_SESSIONS: usize = 50;
+const LIST_TIMEOUT: Duration = Duration::from_secs(10);
+const PARSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Result of the last startup scan (None until it finishes)
+static STARTUP_RESULT: Lazy<Mutex<Option<Vec<SessionPendingQuestion>>>> =
+    Lazy::new(|| Mutex::new(None));
+
+fn is_recent(path: &str) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| SystemTime::now().duration_since(t).ok())
+        .map(|age| age <= MAX_AGE)
+        .unwrap_or(false)
+}
+
+/// Scan recent transcripts for unanswered questions
+fn scan() -> Vec<SessionPendingQuestion> {
+    let listing = match discover_sessions(LIST_TIMEOUT) {
+        Ok(l) => l,
+        Err(e) => {
+            debug_log!("QUESTIONS", "Startup scan could not list sessions: {}", e);
+            return vec![];
+        }
+    };
+
+    let mut found = Vec::new();
+    // Listing is newest first
+    for session in listing
+        .sessions
+        .into_iter()
+        .take(MAX_SESSIONS)
+        .take_while(|s| is_recent(&s.transcript_path))
+    {
+        let path = session.transcript_path.clone();
+        let parsed = slow_io::with_timeout(PARSE_TIMEOUT, move || {
+            parse_transcript_file(Path::new(&path)).ok()
+        });
+        let pending = match parsed.flatten().and_then(|p| p.pending_question) {
+            Some(p) => p,
+            None => continue,
+        };
+        found.push(SessionPendingQuestion {
+            session_id: session.id,
+            working_directory: session.working_directory,
+            transcript_path: session.transcript_path,
+            tool_use_id: pending.tool_use_id,
+            questions: pending.questions,
+        });
+    }
+    found
+}
+
+/// Run the scan on a supervised thread and emit `questions.pending_on_startup`.
+/// The thread parks afterwards, since the supervisor restarts a service that
+/// returns; a scan that panics is retried.
+pub fn spawn_startup_scan(app: AppHandle) {
+    supervisor::spawn_thread("startupQuestionScan", move || {
+        let sessions = scan();
+        debug_log!("QUESTIONS", "Startup scan found {} sessions waiting on a question", sessions.len());
+        *panic_guard::lock_or_recover(&STARTUP_RESULT, "startup questions") = Some(sessions.clone());
+        let _ = events::emit(&app, BackendEvent::QuestionsPendingOnStartup { sessions });
+        loop {
+            std::thread::park();
+        }
+    });
+}
+
+/// Result of the startup scan, for a frontend that subscribed late
+pub fn startup_result() -> Option<Vec<SessionPendingQuestion>> {
+    panic_guard::lock_or_recover(&STARTUP_RESULT, "startup questions").clone()
+}
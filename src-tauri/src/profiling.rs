@@ -0,0 +1,105 @@
+//! Field profiling. Hot paths (stream parsing, event emission, transcript
+//! parsing, command dispatch) are wrapped in `profile_span!`; while
+//! `profilingEnabled` is on, the spans are written as a Chrome trace to
+//! `<artifacts>/profiles/`, which Perfetto or chrome://tracing opens as a
+//! flame chart. Turning it off finishes the file. Needs the `profiling`
+//! build feature; without it the spans compile away.
+
+use crate::config;
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::{Lazy, OnceCell};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_chrome::{ChromeLayer, ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{reload, Registry};
+
+/// The trace layer while profiling, None otherwise
+type TraceSlot = Option<ChromeLayer<Registry>>;
+
+static RELOAD: OnceCell<reload::Handle<TraceSlot, Registry>> = OnceCell::new();
+/// Flushes the trace file when dropped
+static ACTIVE: Lazy<Mutex<Option<(FlushGuard, PathBuf)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Install the (initially empty) trace layer and apply the config. Called
+/// once at startup.
+pub fn init() {
+    let (layer, handle) = reload::Layer::new(None);
+    if tracing::subscriber::set_global_default(Registry::default().with(layer)).is_err() {
+        debug_log!("PROFILE", "Another tracing subscriber is installed; profiling unavailable");
+        return;
+    }
+    let _ = RELOAD.set(handle);
+    apply_config();
+}
+
+fn start(handle: &reload::Handle<TraceSlot, Registry>, dir: &Path) -> Result<(FlushGuard, PathBuf), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create profiles directory: {}", e))?;
+    let path = dir.join(format!("trace-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    let (layer, guard) = ChromeLayerBuilder::new().file(&path).include_args(true).build();
+    handle
+        .modify(|slot| *slot = Some(layer))
+        .map_err(|e| format!("Failed to start tracing: {}", e))?;
+    Ok((guard, path))
+}
+
+/// Start or stop writing a trace to match `profilingEnabled`. Returns the
+/// trace file being written, if any.
+pub fn apply_config() -> Option<PathBuf> {
+    let handle = RELOAD.get()?;
+    let dir = config::artifacts_dir().map(|d| d.join("profiles"));
+    apply(handle, config::profiling_enabled(), dir)
+}
+
+/// Start or stop the trace. The lock is held throughout so concurrent calls
+/// can't both start one.
+fn apply(handle: &reload::Handle<TraceSlot, Registry>, enabled: bool, dir: Option<PathBuf>) -> Option<PathBuf> {
+    let mut active = panic_guard::lock_or_recover(&ACTIVE, "profiling");
+    match (enabled, active.as_ref()) {
+        (true, Some((_, path))) => Some(path.clone()),
+        (true, None) => {
+            let started = dir
+                .ok_or_else(|| "Could not determine artifacts directory".to_string())
+                .and_then(|dir| start(handle, &dir));
+            match started {
+                Ok((guard, path)) => {
+                    debug_log!("PROFILE", "Writing trace to {:?}", path);
+                    *active = Some((guard, path.clone()));
+                    Some(path)
+                }
+                Err(e) => {
+                    debug_log!("PROFILE", "{}", e);
+                    None
+                }
+            }
+        }
+        (false, _) => {
+            let _ = handle.modify(|slot| *slot = None);
+            if let Some((guard, path)) = active.take() {
+                drop(guard);
+                debug_log!("PROFILE", "Finished trace {:?}", path);
+            }
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_once_and_finishes_the_trace() {
+        let dir = std::env::temp_dir().join(format!("horseman-profiles-{}", uuid::Uuid::new_v4()));
+        let (_layer, handle) = reload::Layer::<TraceSlot, Registry>::new(None);
+
+        let path = apply(&handle, true, Some(dir.clone())).unwrap();
+        assert_eq!(apply(&handle, true, Some(dir.clone())), Some(path.clone()));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert_eq!(apply(&handle, false, Some(dir.clone())), None);
+        assert!(path.is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
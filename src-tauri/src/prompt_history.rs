@@ -0,0 +1,118 @@
+//! Prompts the user sent, per project, for up-arrow history and "reuse a
+//! previous prompt". Stored in the metadata store so they survive restarts,
+//! for `promptHistoryDays` or until cleared; recall ranks them with a small
+//! fuzzy matcher.
+
+use crate::config;
+use crate::debug_log;
+use crate::store::{self, PromptEntry};
+
+/// Prompts returned when the caller doesn't ask for a number
+pub const DEFAULT_LIMIT: usize = 50;
+
+fn project_key(working_directory: &str) -> &str {
+    match working_directory.trim_end_matches('/') {
+        "" => "/",
+        dir => dir,
+    }
+}
+
+/// Remember a prompt sent in `working_directory`. Failures are only logged;
+/// they must never block sending.
+pub fn record(working_directory: &str, prompt: &str, claude_session_id: Option<&str>) {
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return;
+    }
+    let entry = PromptEntry {
+        project: project_key(working_directory).to_string(),
+        prompt: prompt.to_string(),
+        claude_session_id: claude_session_id.map(str::to_string),
+        sent_at: chrono::Utc::now().to_rfc3339(),
+    };
+    if let Err(e) = store::record_prompt(&entry) {
+        debug_log!("PROMPT_HISTORY", "Failed to record prompt: {}", e);
+    }
+    if let Some(days) = config::prompt_history_days() {
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339();
+        match store::prune_prompts_before(&cutoff) {
+            Ok(0) => {}
+            Ok(removed) => debug_log!("PROMPT_HISTORY", "Dropped {} prompts older than {} days", removed, days),
+            Err(e) => debug_log!("PROMPT_HISTORY", "Failed to prune prompts: {}", e),
+        }
+    }
+}
+
+/// Forget the prompts of `working_directory`, or of every project
+pub fn clear(working_directory: Option<&str>) -> Result<usize, String> {
+    store::delete_prompts(working_directory.map(project_key))
+}
+
+/// Greedy match of `query` in `text` with its first character at `start`
+fn score_from(query: &[char], text: &[char], start: usize) -> Option<u32> {
+    let mut score = 0;
+    let mut next = start;
+    for (i, q) in query.iter().enumerate() {
+        let found = if i == 0 { start } else { next + text.get(next..)?.iter().position(|c| c == q)? };
+        score += 1;
+        if i > 0 && found == next {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        next = found + 1;
+    }
+    Some(score)
+}
+
+/// How well `text` matches `query`: None unless every query character
+/// appears in order (case-insensitive). Runs of consecutive characters and
+/// matches at word starts score higher; the best starting point wins.
+fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let first = match query.first() {
+        Some(c) => c,
+        None => return Some(0),
+    };
+    (0..text.len())
+        .filter(|&i| text[i] == *first)
+        .filter_map(|i| score_from(&query, &text, i))
+        .max()
+}
+
+/// Up to `limit` prompts from `working_directory`: newest first without a
+/// query, otherwise the best fuzzy matches (newest first among equals)
+pub fn recall(working_directory: &str, query: Option<&str>, limit: usize) -> Result<Vec<PromptEntry>, String> {
+    let prompts = store::prompts(project_key(working_directory))?;
+    let query = query.map(str::trim).unwrap_or_default();
+    if query.is_empty() {
+        return Ok(prompts.into_iter().take(limit).collect());
+    }
+    let mut scored: Vec<(u32, PromptEntry)> = prompts
+        .into_iter()
+        .filter_map(|entry| fuzzy_score(query, &entry.prompt).map(|score| (score, entry)))
+        .collect();
+    // Stable, so recency breaks ties
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    Ok(scored.into_iter().take(limit).map(|(_, entry)| entry).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_prefers_contiguous_and_word_start_matches() {
+        assert_eq!(fuzzy_score("xyz", "fix the tests"), None);
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        let contiguous = fuzzy_score("test", "run the tests").unwrap();
+        let scattered = fuzzy_score("test", "the best setup").unwrap();
+        assert!(contiguous > scattered);
+        let word_start = fuzzy_score("fix", "Fix lint").unwrap();
+        let mid_word = fuzzy_score("fix", "prefix lint").unwrap();
+        assert!(word_start > mid_word);
+        assert_eq!(project_key("/repo/"), "/repo");
+    }
+}
@@ -0,0 +1,141 @@
+//! Model provider presets. A provider profile (`providerProfiles` in the
+//! config, chosen per session or by `activeProvider`) turns into the env vars
+//! the CLI reads to talk to the Anthropic API, Bedrock or Vertex, after
+//! checking that the credentials it needs are present.
+
+use crate::config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Anthropic,
+    Bedrock,
+    Vertex,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProviderProfile {
+    pub kind: ProviderKind,
+    /// AWS region (Bedrock) or Vertex region (`CLOUD_ML_REGION`)
+    pub region: Option<String>,
+    /// Google Cloud project (Vertex)
+    pub project_id: Option<String>,
+    /// Named AWS credentials profile (Bedrock)
+    pub aws_profile: Option<String>,
+    /// Provider model ID used for the main model (`ANTHROPIC_MODEL`)
+    pub model: Option<String>,
+    /// Provider model ID for background tasks (`ANTHROPIC_SMALL_FAST_MODEL`)
+    pub small_fast_model: Option<String>,
+    /// Any further env vars, applied last
+    pub env: HashMap<String, String>,
+}
+
+/// Env vars that select and configure the provider. Fails if a setting the
+/// provider requires is missing.
+pub fn env_for(profile: &ProviderProfile) -> Result<Vec<(String, String)>, String> {
+    let mut env: Vec<(String, String)> = Vec::new();
+    let mut set = |key: &str, value: &str| env.push((key.to_string(), value.to_string()));
+    match profile.kind {
+        ProviderKind::Anthropic => {}
+        ProviderKind::Bedrock => {
+            let region = profile.region.as_deref().ok_or("Bedrock provider needs a region")?;
+            set("CLAUDE_CODE_USE_BEDROCK", "1");
+            set("AWS_REGION", region);
+            if let Some(ref aws_profile) = profile.aws_profile {
+                set("AWS_PROFILE", aws_profile);
+            }
+        }
+        ProviderKind::Vertex => {
+            let region = profile.region.as_deref().ok_or("Vertex provider needs a region")?;
+            let project = profile.project_id.as_deref().ok_or("Vertex provider needs a projectId")?;
+            set("CLAUDE_CODE_USE_VERTEX", "1");
+            set("CLOUD_ML_REGION", region);
+            set("ANTHROPIC_VERTEX_PROJECT_ID", project);
+        }
+    }
+    if let Some(ref model) = profile.model {
+        set("ANTHROPIC_MODEL", model);
+    }
+    if let Some(ref model) = profile.small_fast_model {
+        set("ANTHROPIC_SMALL_FAST_MODEL", model);
+    }
+    let mut extra: Vec<(&String, &String)> = profile.env.iter().collect();
+    extra.sort();
+    env.extend(extra.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+    Ok(env)
+}
+
+fn has_env(profile: &ProviderProfile, key: &str) -> bool {
+    profile.env.contains_key(key) || std::env::var_os(key).is_some_and(|v| !v.is_empty())
+}
+
+/// Why the provider's credentials look unusable, if they do. Only checks
+/// that something is configured; it doesn't call the provider.
+pub fn credential_problem(profile: &ProviderProfile) -> Option<String> {
+    let home = dirs::home_dir().unwrap_or_default();
+    match profile.kind {
+        // `claude login` or ANTHROPIC_API_KEY; the CLI reports either missing
+        ProviderKind::Anthropic => None,
+        ProviderKind::Bedrock => {
+            let found = profile.aws_profile.is_some()
+                || has_env(profile, "AWS_PROFILE")
+                || has_env(profile, "AWS_ACCESS_KEY_ID")
+                || has_env(profile, "AWS_BEARER_TOKEN_BEDROCK")
+                || home.join(".aws/credentials").is_file()
+                || home.join(".aws/config").is_file();
+            (!found).then(|| {
+                "No AWS credentials found (set awsProfile, AWS_ACCESS_KEY_ID or ~/.aws/credentials)".to_string()
+            })
+        }
+        ProviderKind::Vertex => {
+            let adc = home.join(".config/gcloud/application_default_credentials.json");
+            let found = has_env(profile, "GOOGLE_APPLICATION_CREDENTIALS") || adc.is_file();
+            (!found).then(|| {
+                "No Google Cloud credentials found (run `gcloud auth application-default login` \
+                 or set GOOGLE_APPLICATION_CREDENTIALS)"
+                    .to_string()
+            })
+        }
+    }
+}
+
+/// Env for a spawned claude process: the named profile, else `activeProvider`,
+/// else nothing (the CLI's own defaults)
+pub fn spawn_env(name: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let name = match name.map(str::to_string).or_else(config::active_provider) {
+        Some(name) => name,
+        None => return Ok(Vec::new()),
+    };
+    let profile = config::provider_profiles()
+        .remove(&name)
+        .ok_or_else(|| format!("Unknown provider profile: {}", name))?;
+    if let Some(problem) = credential_problem(&profile) {
+        return Err(format!("Provider '{}': {}", name, problem));
+    }
+    env_for(&profile).map_err(|e| format!("Provider '{}': {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bedrock_env_requires_region_and_maps_models() {
+        let mut profile = ProviderProfile {
+            kind: ProviderKind::Bedrock,
+            model: Some("us.anthropic.claude-sonnet-4-5".to_string()),
+            ..Default::default()
+        };
+        assert!(env_for(&profile).is_err());
+
+        profile.region = Some("us-east-1".to_string());
+        let env = env_for(&profile).unwrap();
+        assert!(env.contains(&("CLAUDE_CODE_USE_BEDROCK".to_string(), "1".to_string())));
+        assert!(env.contains(&("AWS_REGION".to_string(), "us-east-1".to_string())));
+        assert!(env.contains(&("ANTHROPIC_MODEL".to_string(), "us.anthropic.claude-sonnet-4-5".to_string())));
+    }
+}
@@ -0,0 +1,417 @@
+//! Full-text search over every transcript in the projects dir, backed by a
+//! SQLite FTS5 index in the artifacts dir. The index is built once at
+//! startup (or on the first search), then kept current by the session
+//! watcher re-indexing the transcripts it sees change. Transcripts are read
+//! without holding the index lock, so searches don't wait on indexing.
+
+use crate::claude::resume_recovery;
+use crate::config;
+use crate::debug_log;
+use crate::panic_guard;
+use crate::redaction;
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::fs;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Results returned per search unless the caller asks for fewer
+const DEFAULT_LIMIT: usize = 50;
+/// Tokens of context around each match in a snippet
+const SNIPPET_TOKENS: i32 = 16;
+
+static INDEX: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+/// Set once a full refresh has started; from then on the session watcher
+/// keeps the index current
+static BUILT: AtomicBool = AtomicBool::new(false);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS indexed_files (
+        path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        modified_ms INTEGER NOT NULL
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS messages USING fts5(
+        text,
+        session_id UNINDEXED,
+        working_directory UNINDEXED,
+        path UNINDEXED,
+        line UNINDEXED,
+        byte_offset UNINDEXED,
+        role UNINDEXED,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// Top-level session the message belongs to (subagent messages included)
+    pub session_id: String,
+    pub working_directory: String,
+    pub transcript_path: String,
+    /// 0-based line of the message in the transcript
+    pub line: u64,
+    /// Byte offset of that line
+    pub byte_offset: u64,
+    /// "user" or "assistant"
+    pub role: String,
+    /// Text around the match, matches wrapped in `[` `]`
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+fn open() -> Result<Connection, String> {
+    let dir = config::artifacts_dir().ok_or("Could not determine artifacts directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create artifacts directory: {}", e))?;
+    let conn = Connection::open(dir.join("search.db")).map_err(|e| format!("Failed to open search index: {}", e))?;
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to create search index: {}", e))?;
+    Ok(conn)
+}
+
+fn with_index<T>(f: impl FnOnce(&mut Connection) -> Result<T, String>) -> Result<T, String> {
+    let mut index = panic_guard::lock_or_recover(&INDEX, "search index");
+    if index.is_none() {
+        *index = Some(open()?);
+    }
+    match index.as_mut() {
+        Some(conn) => f(conn),
+        None => Err("Search index unavailable".to_string()),
+    }
+}
+
+/// Owning session ID and working directory of a transcript under the
+/// projects dir. Subagent transcripts belong to the session named in their
+/// records.
+fn describe(projects_dir: &Path, path: &Path) -> Option<(Option<String>, String)> {
+    if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+        return None;
+    }
+    let project = path.strip_prefix(projects_dir).ok()?.components().next()?;
+    let working_directory = crate::commands::sessions::decode_dir_name(&project.as_os_str().to_string_lossy());
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string());
+    // Top-level `<id>.jsonl` names its session; agent files don't
+    let top_level = path.parent().and_then(Path::parent) == Some(projects_dir);
+    let owner = stem.filter(|s| !s.starts_with("agent-") && top_level);
+    Some((owner, working_directory))
+}
+
+/// Every transcript under the projects dir
+fn transcripts(projects_dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let projects = match fs::read_dir(projects_dir) {
+        Ok(p) => p,
+        Err(_) => return found,
+    };
+    for project in projects.flatten() {
+        let project_path = project.path();
+        if !project_path.is_dir() {
+            continue;
+        }
+        let mut pending = vec![project_path];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.extension().and_then(|e| e.to_str()) == Some("jsonl") {
+                    found.push(path);
+                }
+            }
+        }
+    }
+    found
+}
+
+/// One indexed user/assistant message
+struct Message {
+    text: String,
+    session_id: String,
+    line: usize,
+    byte_offset: usize,
+    role: String,
+}
+
+/// A transcript's user/assistant messages
+fn read_messages(path: &Path, owner: Option<&str>) -> Result<Vec<Message>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let mut messages = Vec::new();
+    let mut session_id = owner.map(str::to_string);
+    let mut offset = 0usize;
+    for (line_no, line) in content.split('\n').enumerate() {
+        let line_offset = offset;
+        offset += line.len() + 1;
+        let json: serde_json::Value = match serde_json::from_str(line) {
+            Ok(j) => j,
+            Err(_) => continue,
+        };
+        if session_id.is_none() {
+            session_id = json.get("sessionId").and_then(|s| s.as_str()).map(str::to_string);
+        }
+        let role = match json.get("type").and_then(|t| t.as_str()) {
+            Some(role @ ("user" | "assistant")) => role,
+            _ => continue,
+        };
+        let (text, session) = match (resume_recovery::message_text(&json), session_id.as_deref()) {
+            (Some(text), Some(session)) => (text, session),
+            _ => continue,
+        };
+        messages.push(Message {
+            text,
+            session_id: session.to_string(),
+            line: line_no,
+            byte_offset: line_offset,
+            role: role.to_string(),
+        });
+    }
+    Ok(messages)
+}
+
+/// Size and mtime a transcript was indexed at
+fn stamp_of(path: &Path) -> Option<(i64, i64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    Some((meta.len() as i64, modified_ms))
+}
+
+fn indexed_stamp(conn: &Connection, key: &str) -> Option<(i64, i64)> {
+    conn.query_row(
+        "SELECT size, modified_ms FROM indexed_files WHERE path = ?1",
+        params![key],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .ok()
+}
+
+/// Replace a transcript's rows with `messages`
+fn store(
+    conn: &mut Connection,
+    key: &str,
+    working_directory: &str,
+    (size, modified_ms): (i64, i64),
+    messages: &[Message],
+) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM messages WHERE path = ?1", params![key])
+        .map_err(|e| e.to_string())?;
+    {
+        let mut insert = tx
+            .prepare_cached(
+                "INSERT INTO messages (text, session_id, working_directory, path, line, byte_offset, role)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .map_err(|e| e.to_string())?;
+        for message in messages {
+            insert
+                .execute(params![
+                    message.text,
+                    message.session_id,
+                    working_directory,
+                    key,
+                    message.line as i64,
+                    message.byte_offset as i64,
+                    message.role
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO indexed_files (path, size, modified_ms) VALUES (?1, ?2, ?3)",
+        params![key, size, modified_ms],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| format!("Failed to update search index: {}", e))
+}
+
+fn remove(conn: &mut Connection, key: &str) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM messages WHERE path = ?1", params![key])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM indexed_files WHERE path = ?1", params![key])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| format!("Failed to update search index: {}", e))
+}
+
+/// Re-index one transcript if it changed since it was indexed, or drop it
+/// once it's gone. Reads the file before taking the index lock.
+fn reindex(projects_dir: &Path, path: &Path, indexed: Option<(i64, i64)>) -> Result<bool, String> {
+    let key = path.to_string_lossy().to_string();
+    let ((owner, working_directory), stamp) = match (describe(projects_dir, path), stamp_of(path)) {
+        (Some(info), Some(stamp)) => (info, stamp),
+        _ => return with_index(|conn| remove(conn, &key)).map(|_| indexed.is_some()),
+    };
+    if Some(stamp) == indexed {
+        return Ok(false);
+    }
+    let messages = read_messages(path, owner.as_deref())?;
+    with_index(|conn| store(conn, &key, &working_directory, stamp, &messages))?;
+    Ok(true)
+}
+
+/// Bring the whole index up to date with the projects dir
+pub fn refresh() -> Result<IndexStats, String> {
+    BUILT.store(true, Ordering::SeqCst);
+    let projects_dir = config::projects_dir();
+    let files = transcripts(&projects_dir);
+    let mut known: HashMap<String, (i64, i64)> = match with_index(|conn| {
+        let mut stmt = conn
+            .prepare("SELECT path, size, modified_ms FROM indexed_files")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| e.to_string())?;
+        Ok(rows.filter_map(Result::ok).collect())
+    }) {
+        Ok(known) => known,
+        Err(e) => {
+            BUILT.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+
+    let mut stats = IndexStats::default();
+    for path in &files {
+        let indexed = known.remove(path.to_string_lossy().as_ref());
+        match reindex(&projects_dir, path, indexed) {
+            Ok(true) => stats.indexed += 1,
+            Ok(false) => stats.unchanged += 1,
+            Err(e) => debug_log!("SEARCH", "Skipping {:?}: {}", path, e),
+        }
+    }
+    // Whatever is left was indexed but no longer exists
+    for path in known.into_keys() {
+        with_index(|conn| remove(conn, &path))?;
+        stats.removed += 1;
+    }
+    Ok(stats)
+}
+
+/// Re-index transcripts the session watcher saw change. Does nothing until
+/// the index has been built; the first search or warm-up picks them up.
+pub fn update<'a>(projects_dir: &Path, paths: impl IntoIterator<Item = &'a PathBuf>) {
+    if !BUILT.load(Ordering::SeqCst) {
+        return;
+    }
+    for path in paths {
+        let key = path.to_string_lossy().to_string();
+        let indexed = match with_index(|conn| Ok(indexed_stamp(conn, &key))) {
+            Ok(indexed) => indexed,
+            Err(e) => {
+                debug_log!("SEARCH", "Index unavailable: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = reindex(projects_dir, path, indexed) {
+            debug_log!("SEARCH", "Failed to re-index {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Quote each word so user input can't form FTS5 syntax errors. Every word
+/// matches as a prefix, so partial words and plurals still find results.
+pub(crate) fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.trim_end_matches('*').replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Messages matching every word of `query`, best matches first
+pub fn search(query: &str, working_directory: Option<&str>, limit: Option<usize>) -> Result<Vec<SearchHit>, String> {
+    let fts = match fts_query(query) {
+        Some(q) => q,
+        None => return Ok(vec![]),
+    };
+    if !BUILT.load(Ordering::SeqCst) {
+        if let Err(e) = refresh() {
+            debug_log!("SEARCH", "Index build failed, searching stale index: {}", e);
+        }
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT) as i64;
+    with_index(|conn| {
+        let mut stmt = conn
+            .prepare(
+                "SELECT session_id, working_directory, path, line, byte_offset, role,
+                        snippet(messages, 0, '[', ']', '…', ?2)
+                 FROM messages
+                 WHERE messages MATCH ?1 AND (?3 IS NULL OR working_directory = ?3)
+                 ORDER BY rank LIMIT ?4",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![fts, SNIPPET_TOKENS, working_directory, limit], |row| {
+                Ok(SearchHit {
+                    session_id: row.get(0)?,
+                    working_directory: row.get(1)?,
+                    transcript_path: row.get(2)?,
+                    line: row.get::<_, i64>(3)? as u64,
+                    byte_offset: row.get::<_, i64>(4)? as u64,
+                    role: row.get(5)?,
+                    snippet: row.get(6)?,
+                })
+            })
+            .map_err(|e| format!("Search failed: {}", e))?;
+        let mut hits = rows.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| format!("Search failed: {}", e))?;
+        // The index holds transcript text as is
+        if let Some(redactor) = redaction::current() {
+            for hit in &mut hits {
+                hit.snippet = redactor.redact(&hit.snippet);
+            }
+        }
+        Ok(hits)
+    })
+}
+
+/// Build the index in the background so the first search is fast
+pub fn spawn_warmup() {
+    std::thread::spawn(|| match refresh() {
+        Ok(stats) => debug_log!("SEARCH", "Index ready: {:?}", stats),
+        Err(e) => debug_log!("SEARCH", "Index warm-up failed: {}", e),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_words_are_quoted() {
+        assert_eq!(fts_query("auth bug").as_deref(), Some("\"auth\"* \"bug\"*"));
+        assert_eq!(fts_query("refact* \"x").as_deref(), Some("\"refact\"* \"x\"*"));
+        assert_eq!(fts_query("  \"\" "), None);
+    }
+
+    #[test]
+    fn transcripts_are_described_by_their_path() {
+        let projects = Path::new("/p");
+        assert_eq!(
+            describe(projects, Path::new("/p/-home-me-app/abc.jsonl")),
+            Some((Some("abc".to_string()), "/home/me/app".to_string()))
+        );
+        assert_eq!(
+            describe(projects, Path::new("/p/-home-me-app/abc/subagents/agent-1.jsonl")),
+            Some((None, "/home/me/app".to_string()))
+        );
+        assert_eq!(describe(projects, Path::new("/p/-home-me-app/notes.txt")), None);
+        assert_eq!(describe(projects, Path::new("/elsewhere/abc.jsonl")), None);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::atomic_file;
+use crate::claude::raw_log::safe_file_stem;
+use crate::claude::{parse_transcript_with_subagents, PARSER_VERSION};
+use crate::config;
+use crate::debug_log;
+use crate::git::{self, GitContext};
+use crate::session_env::SessionEnvironment;
+use crate::workspace_diff::{self, WorkspaceSnapshot};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Metadata derived from a parsed transcript, cached so the session list
+/// doesn't have to re-parse every transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMetadata {
+    pub session_id: String,
+    pub transcript_path: String,
+    /// PARSER_VERSION that produced this entry
+    pub parser_version: u32,
+    /// Transcript mtime (RFC 3339) when the entry was built
+    pub transcript_modified_at: String,
+    pub first_message: Option<String>,
+    pub message_count: usize,
+    pub tool_count: usize,
+    pub total_cost_usd: Option<f64>,
+    /// Most recent compaction summary
+    pub summary: Option<String>,
+    /// Branch the session was started on
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    /// Worktree the session was started in
+    #[serde(default)]
+    pub worktree: Option<String>,
+    /// Default display title (`branch — first prompt`)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// What Horseman last ran the session with
+    #[serde(default)]
+    pub environment: Option<SessionEnvironment>,
+}
+
+/// Result of refreshing one cache entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheUpdate {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+fn cache_dir() -> Result<PathBuf, String> {
+    config::artifacts_dir()
+        .map(|d| d.join("session_cache"))
+        .ok_or_else(|| "Could not determine artifacts directory".to_string())
+}
+
+/// `<session_id>.json`; IDs come from the frontend, so they're made safe
+/// first and can't point outside the cache
+fn file_name(session_id: &str) -> Result<String, String> {
+    Ok(format!("{}.json", safe_file_stem(session_id)?))
+}
+
+fn entry_path(session_id: &str) -> Result<PathBuf, String> {
+    Ok(cache_dir()?.join(file_name(session_id)?))
+}
+
+fn modified_at(transcript_path: &Path) -> Result<String, String> {
+    let modified = fs::metadata(transcript_path)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("Failed to stat {:?}: {}", transcript_path, e))?;
+    let datetime: chrono::DateTime<chrono::Utc> = modified.into();
+    Ok(datetime.to_rfc3339())
+}
+
+fn git_context_path(session_id: &str) -> Result<PathBuf, String> {
+    Ok(cache_dir()?.join("git").join(file_name(session_id)?))
+}
+
+/// Remember the git context a session was spawned in. The first record wins,
+/// so resuming on another branch keeps the original one.
+pub fn record_git_context(session_id: &str, context: &GitContext) -> Result<(), String> {
+    let path = git_context_path(session_id)?;
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create git context directory: {}", e))?;
+    }
+    let content = serde_json::to_string(context)
+        .map_err(|e| format!("Failed to serialize git context: {}", e))?;
+    atomic_file::write(&path, content).map_err(|e| format!("Failed to write git context: {}", e))
+}
+
+/// Git context recorded at spawn time, if Horseman started the session
+pub fn git_context(session_id: &str) -> Option<GitContext> {
+    let content = fs::read_to_string(git_context_path(session_id).ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn environment_path(session_id: &str) -> Result<PathBuf, String> {
+    Ok(cache_dir()?.join("environment").join(file_name(session_id)?))
+}
+
+/// Remember what a session was run with. Unlike the git context, each run
+/// replaces the last, since the latest run is the one to reproduce.
+pub fn record_environment(session_id: &str, environment: &SessionEnvironment) -> Result<(), String> {
+    let path = environment_path(session_id)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create environment directory: {}", e))?;
+    }
+    let content = serde_json::to_string(environment)
+        .map_err(|e| format!("Failed to serialize environment: {}", e))?;
+    atomic_file::write(&path, content).map_err(|e| format!("Failed to write environment: {}", e))
+}
+
+/// Environment of the session's latest Horseman run, if any
+pub fn environment(session_id: &str) -> Option<SessionEnvironment> {
+    let content = fs::read_to_string(environment_path(session_id).ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn workspace_snapshot_path(session_id: &str) -> Result<PathBuf, String> {
+    Ok(cache_dir()?.join("workspace").join(file_name(session_id)?))
+}
+
+/// Remember the working tree a session started from, pinning its tree in
+/// the repository. Like the git context, the first record wins, so the
+/// review diff covers the whole session.
+pub fn record_workspace_snapshot(session_id: &str, snapshot: &WorkspaceSnapshot) -> Result<(), String> {
+    let path = workspace_snapshot_path(session_id)?;
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create workspace snapshot directory: {}", e))?;
+    }
+    let content = serde_json::to_string(snapshot)
+        .map_err(|e| format!("Failed to serialize workspace snapshot: {}", e))?;
+    atomic_file::write(&path, content).map_err(|e| format!("Failed to write workspace snapshot: {}", e))?;
+    workspace_diff::pin(snapshot, session_id)
+}
+
+/// Working tree snapshot taken when Horseman first ran the session
+pub fn workspace_snapshot(session_id: &str) -> Option<WorkspaceSnapshot> {
+    let content = fs::read_to_string(workspace_snapshot_path(session_id).ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Parse a transcript with the current parser and derive its metadata
+pub fn build(session_id: &str, transcript_path: &Path) -> Result<SessionMetadata, String> {
+    let transcript_modified_at = modified_at(transcript_path)?;
+    let parsed = parse_transcript_with_subagents(transcript_path);
+
+    let first_message = parsed
+        .messages
+        .iter()
+        .find(|m| m.role == "user" && !m.text.trim().is_empty())
+        .map(|m| m.text.trim().chars().take(100).collect::<String>());
+    let tool_count = parsed
+        .messages
+        .iter()
+        .filter_map(|m| m.tool_calls.as_ref())
+        .map(|t| t.len())
+        .sum::<usize>()
+        + parsed.subagent_tools.len();
+
+    // Sessions started outside Horseman only have the branch Claude logged
+    let git = git_context(session_id).unwrap_or_else(|| GitContext {
+        branch: git::branch_from_transcript(transcript_path),
+        worktree: None,
+    });
+    let title = git::default_title(git.branch.as_deref(), first_message.as_deref());
+
+    Ok(SessionMetadata {
+        session_id: session_id.to_string(),
+        transcript_path: transcript_path.to_string_lossy().to_string(),
+        parser_version: PARSER_VERSION,
+        transcript_modified_at,
+        first_message,
+        message_count: parsed.messages.len(),
+        tool_count,
+        total_cost_usd: parsed.total_cost_usd,
+        summary: parsed.summaries.last().map(|s| s.summary.clone()),
+        git_branch: git.branch,
+        worktree: git.worktree,
+        title,
+        environment: environment(session_id),
+    })
+}
+
+/// Load a cached entry (regardless of version)
+pub fn load(session_id: &str) -> Option<SessionMetadata> {
+    let content = fs::read_to_string(entry_path(session_id).ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn store(metadata: &SessionMetadata) -> Result<(), String> {
+    let dir = cache_dir()?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create session cache directory: {}", e))?;
+    let content = serde_json::to_string(metadata)
+        .map_err(|e| format!("Failed to serialize session metadata: {}", e))?;
+    atomic_file::write(&entry_path(&metadata.session_id)?, content)
+        .map_err(|e| format!("Failed to write session metadata: {}", e))
+}
+
+/// Drop a session's cache entry, recorded git context, environment and
+/// workspace snapshot (with the ref pinning its tree)
+pub fn forget(session_id: &str) -> Result<(), String> {
+    if let Some(snapshot) = workspace_snapshot(session_id) {
+        if let Err(e) = workspace_diff::unpin(&snapshot, session_id) {
+            debug_log!("SESSION_CACHE", "Failed to unpin the snapshot of {}: {}", session_id, e);
+        }
+    }
+    let paths = [
+        entry_path(session_id)?,
+        git_context_path(session_id)?,
+        environment_path(session_id)?,
+        workspace_snapshot_path(session_id)?,
+    ];
+    for path in paths {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to remove {:?}: {}", path, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Get metadata for a session, rebuilding the cache entry if it is missing,
+/// was produced by an older parser, or the transcript changed since.
+pub fn get(session_id: &str, transcript_path: &Path) -> Result<SessionMetadata, String> {
+    if let Some(cached) = load(session_id) {
+        if cached.parser_version == PARSER_VERSION
+            && modified_at(transcript_path).ok().as_deref() == Some(cached.transcript_modified_at.as_str())
+        {
+            return Ok(cached);
+        }
+    }
+    refresh(session_id, transcript_path).map(|(metadata, _)| metadata)
+}
+
+/// Rebuild a session's cache entry unconditionally and report what changed
+pub fn refresh(session_id: &str, transcript_path: &Path) -> Result<(SessionMetadata, CacheUpdate), String> {
+    let metadata = build(session_id, transcript_path)?;
+    let update = match load(session_id) {
+        None => CacheUpdate::Created,
+        Some(ref old) if *old == metadata => CacheUpdate::Unchanged,
+        Some(_) => CacheUpdate::Updated,
+    };
+    if update != CacheUpdate::Unchanged {
+        store(&metadata)?;
+        debug_log!("SESSION_CACHE", "{:?} {} (parser v{})", update, session_id, PARSER_VERSION);
+    }
+    Ok((metadata, update))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_ids_stay_inside_the_cache() {
+        assert_eq!(file_name("3f2a-b1_c").unwrap(), "3f2a-b1_c.json");
+        assert_eq!(file_name("../../config").unwrap(), "______config.json");
+        assert_eq!(file_name("/etc/passwd").unwrap(), "_etc_passwd.json");
+        assert!(file_name("").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! What a session's latest run was started with (CLI version, model, flags,
+//! config, git commit, OS), so a problematic run can be reproduced or
+//! reported with full context. Captured at spawn, completed from the CLI's
+//! init event on the reader thread (so git never runs under the session
+//! manager's lock) and recorded per Claude session by `session_cache`.
+
+use crate::config;
+use crate::git;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::process::Command;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionEnvironment {
+    /// When the process was spawned (RFC 3339)
+    pub recorded_at: String,
+    pub app_version: String,
+    pub claude_binary: String,
+    pub claude_version: Option<String>,
+    /// Model Claude reported using, else the one requested
+    pub model: Option<String>,
+    /// CLI arguments the process got, without the prompt
+    pub args: Vec<String>,
+    /// SHA-256 of the Horseman config in effect; tells configs apart
+    /// without exposing their values
+    pub config_hash: String,
+    pub git_commit: Option<String>,
+    /// OS name, version and architecture
+    pub os: String,
+}
+
+static OS: Lazy<String> = Lazy::new(|| {
+    let version_command: (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("sw_vers", &["-productVersion"])
+    } else {
+        ("uname", &["-r"])
+    };
+    let version = Command::new(version_command.0)
+        .args(version_command.1)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default();
+    format!("{} {} ({})", std::env::consts::OS, version, std::env::consts::ARCH)
+        .replace("  ", " ")
+});
+
+fn config_hash() -> String {
+    let config = serde_json::to_string(&config::get_config()).unwrap_or_default();
+    format!("{:x}", Sha256::digest(config.as_bytes()))
+}
+
+/// Snapshot taken at spawn. The CLI version, resolved model and git commit
+/// are filled in by `complete` once the process reports in.
+pub fn capture(args: &[String], model: Option<&str>) -> SessionEnvironment {
+    SessionEnvironment {
+        recorded_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        claude_binary: config::claude_binary(),
+        claude_version: None,
+        model: model.map(str::to_string),
+        args: args.to_vec(),
+        config_hash: config_hash(),
+        git_commit: None,
+        os: OS.clone(),
+    }
+}
+
+/// Fill in what the CLI's `system` init event reports, and the commit
+/// checked out in `working_directory`
+pub fn complete(environment: &mut SessionEnvironment, init_event: &serde_json::Value, working_directory: &str) {
+    if let Some(version) = init_event.get("claude_code_version").and_then(|v| v.as_str()) {
+        environment.claude_version = Some(version.to_string());
+    }
+    if let Some(model) = init_event.get("model").and_then(|v| v.as_str()) {
+        environment.model = Some(model.to_string());
+    }
+    environment.git_commit = git::head_commit(working_directory);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes_the_spawn_snapshot_from_the_init_event() {
+        let args = vec!["--output-format".to_string(), "stream-json".to_string()];
+        let mut environment = capture(&args, Some("sonnet"));
+        assert_eq!((environment.model.as_deref(), environment.git_commit.as_deref()), (Some("sonnet"), None));
+
+        let dir = std::env::temp_dir().join(format!("horseman-env-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let init = serde_json::json!({
+            "type": "system",
+            "subtype": "init",
+            "claude_code_version": "2.1.0",
+            "model": "claude-sonnet-4-5"
+        });
+        complete(&mut environment, &init, &dir.to_string_lossy());
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(environment.claude_version.as_deref(), Some("2.1.0"));
+        assert_eq!(environment.model.as_deref(), Some("claude-sonnet-4-5"));
+        // Not a git repository
+        assert_eq!(environment.git_commit, None);
+        assert_eq!(environment.args, args);
+    }
+}
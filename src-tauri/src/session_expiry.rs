@@ -0,0 +1,107 @@
+//! Claude deletes transcripts idle for longer than `cleanupPeriodDays`
+//! (default 30) when it starts. The listing shows how long each session has
+//! left, and with `archiveExpiringSessions` a background pass copies soon
+//! to be deleted sessions into the transcript backups, where they can be
+//! restored from after Claude prunes them.
+
+use crate::claude::transcript_backup;
+use crate::commands::discover_sessions;
+use crate::config;
+use crate::debug_log;
+use crate::supervisor;
+use std::fs;
+use std::time::Duration;
+
+/// Claude's default when settings don't say
+const DEFAULT_CLEANUP_PERIOD_DAYS: i64 = 30;
+/// Sessions with this many days left or fewer get archived
+pub const ARCHIVE_WITHIN_DAYS: i64 = 3;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `cleanupPeriodDays` from Claude's user settings
+pub fn cleanup_period_days() -> i64 {
+    config::claude_settings_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|settings| settings.get("cleanupPeriodDays")?.as_i64())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_CLEANUP_PERIOD_DAYS)
+}
+
+/// Whole days before Claude deletes a transcript last written at
+/// `modified_at_ms`; 0 or less means its next start will
+fn days_left(modified_at_ms: i64, period_days: i64, now_ms: i64) -> i64 {
+    let expires_at = modified_at_ms + period_days * DAY_MS;
+    (expires_at - now_ms).div_euclid(DAY_MS)
+}
+
+/// Days left for a transcript, None if its modification time is unknown
+pub fn expires_in_days(modified_at_ms: i64, period_days: i64) -> Option<i64> {
+    (modified_at_ms > 0).then(|| days_left(modified_at_ms, period_days, chrono::Utc::now().timestamp_millis()))
+}
+
+/// Whether the session was archived after its last change
+fn is_archived(claude_session_id: &str, modified_at_ms: i64) -> bool {
+    transcript_backup::list(claude_session_id).iter().any(|backup| {
+        backup.reason == transcript_backup::EXPIRY_REASON
+            && chrono::DateTime::parse_from_rfc3339(&backup.created_at)
+                .is_ok_and(|created| created.timestamp_millis() >= modified_at_ms)
+    })
+}
+
+/// Archive every session within `ARCHIVE_WITHIN_DAYS` of deletion
+fn archive_expiring() {
+    let listing = match discover_sessions(LIST_TIMEOUT) {
+        Ok(listing) => listing,
+        Err(e) => {
+            debug_log!("EXPIRY", "Could not list sessions: {}", e);
+            return;
+        }
+    };
+    let mut archived = 0;
+    for session in listing.sessions {
+        let expiring = session.expires_in_days.is_some_and(|days| days <= ARCHIVE_WITHIN_DAYS);
+        if !expiring || is_archived(&session.id, session.modified_at_ms) {
+            continue;
+        }
+        match transcript_backup::archive(&session.id) {
+            Ok(_) => archived += 1,
+            Err(e) => debug_log!("EXPIRY", "Failed to archive {}: {}", session.id, e),
+        }
+    }
+    if archived > 0 {
+        debug_log!("EXPIRY", "Archived {} sessions nearing cleanupPeriodDays", archived);
+    }
+}
+
+/// Start the supervised archiving pass; it does nothing while
+/// `archiveExpiringSessions` is off
+pub fn spawn() {
+    supervisor::spawn_thread("sessionArchiver", || {
+        std::thread::sleep(STARTUP_DELAY);
+        loop {
+            if config::archive_expiring_sessions() {
+                archive_expiring();
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_whole_days_until_cleanup() {
+        let now = 100 * DAY_MS;
+        assert_eq!(days_left(now, 30, now), 30);
+        assert_eq!(days_left(now - DAY_MS / 2, 30, now), 29);
+        assert_eq!(days_left(now - 30 * DAY_MS, 30, now), 0);
+        assert_eq!(days_left(now - 40 * DAY_MS, 30, now), -10);
+        assert_eq!(days_left(now, 0, now), 0);
+    }
+}
@@ -0,0 +1,105 @@
+//! Watches Claude's projects directory and emits `sessions.added`,
+//! `sessions.updated` and `sessions.removed` so the sidebar stays current
+//! without re-listing every transcript.
+
+use crate::claude::parse_cache;
+use crate::commands::{discover_sessions, session_for_transcript, DiscoveredSession};
+use crate::config;
+use crate::debug_log;
+#[cfg(feature = "search")]
+use crate::search_index;
+use crate::events::{self, BackendEvent};
+use crate::session_expiry;
+use crate::supervisor;
+use crate::fs_watch::FsWatcher;
+use notify::RecursiveMode;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// Quiet period before a burst of file events is flushed
+const DEBOUNCE: Duration = Duration::from_millis(500);
+/// Flush at least this often while a transcript is being streamed into
+const MAX_DELAY: Duration = Duration::from_secs(2);
+const SEED_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Re-check each dirty path against the snapshot and emit what changed
+fn flush(app: &AppHandle, projects_dir: &Path, known: &mut HashMap<PathBuf, DiscoveredSession>, dirty: HashSet<PathBuf>) {
+    let cleanup_period_days = session_expiry::cleanup_period_days();
+    for path in &dirty {
+        parse_cache::invalidate(path);
+        let event = match (session_for_transcript(projects_dir, path, cleanup_period_days), known.remove(path)) {
+            (Some(session), None) => {
+                known.insert(path.clone(), session.clone());
+                BackendEvent::SessionsAdded { session }
+            }
+            (Some(session), Some(previous)) => {
+                known.insert(path.clone(), session.clone());
+                if session.modified_at_ms == previous.modified_at_ms
+                    && session.first_message == previous.first_message
+                {
+                    continue;
+                }
+                BackendEvent::SessionsUpdated { session }
+            }
+            (None, Some(session)) => BackendEvent::SessionsRemoved { session },
+            (None, None) => continue,
+        };
+        let _ = events::emit(app, event);
+    }
+    #[cfg(feature = "search")]
+    search_index::update(projects_dir, &dirty);
+}
+
+fn run(app: &AppHandle) -> Result<(), String> {
+    let projects_dir = config::projects_dir();
+    if !projects_dir.is_dir() {
+        return Err(format!("Projects directory does not exist: {:?}", projects_dir));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = FsWatcher::new("sessionWatcher", tx)?;
+    watcher.watch(&projects_dir, RecursiveMode::Recursive)?;
+
+    // Seed after watching so nothing written in between is missed
+    let mut known: HashMap<PathBuf, DiscoveredSession> = discover_sessions(SEED_TIMEOUT)?
+        .sessions
+        .into_iter()
+        .map(|s| (PathBuf::from(&s.transcript_path), s))
+        .collect();
+    debug_log!("SESSION_WATCH", "Watching {:?} ({} sessions, {:?})", projects_dir, known.len(), watcher.mode());
+
+    let mut dirty: HashSet<PathBuf> = HashSet::new();
+    let mut first_dirty: Option<Instant> = None;
+    loop {
+        watcher.tick();
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(Ok(event)) => {
+                dirty.extend(event.paths.into_iter().filter(|p| {
+                    p.extension().and_then(|e| e.to_str()) == Some("jsonl")
+                }));
+                if !dirty.is_empty() {
+                    first_dirty.get_or_insert_with(Instant::now);
+                }
+                if first_dirty.is_none_or(|t| t.elapsed() < MAX_DELAY) {
+                    continue;
+                }
+            }
+            Ok(Err(e)) => return Err(format!("Watch error: {}", e)),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Err("Watcher stopped".to_string()),
+        }
+        if !dirty.is_empty() {
+            flush(app, &projects_dir, &mut known, std::mem::take(&mut dirty));
+        }
+        first_dirty = None;
+    }
+}
+
+/// Start the supervised watcher (retries with backoff until the projects
+/// directory exists)
+pub fn spawn(app: AppHandle) {
+    supervisor::spawn_thread("sessionWatcher", move || run(&app));
+}
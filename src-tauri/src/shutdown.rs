@@ -0,0 +1,47 @@
+//! Exit hook: without it, quitting Horseman leaves claude and slash command
+//! processes running, permission requests hanging and MCP configs behind.
+
+use crate::background_ai;
+use crate::commands::{ClaudeState, HookState};
+use crate::debug;
+use crate::debug_log;
+use crate::heartbeat;
+use crate::hooks;
+use crate::slash::SlashState;
+use crate::TokioRuntime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How long claude processes get to exit after SIGTERM before SIGKILL
+const TERMINATE_GRACE: Duration = Duration::from_secs(2);
+
+static DONE: AtomicBool = AtomicBool::new(false);
+
+/// Deny pending permission requests, SIGTERM every child, remove MCP
+/// configs and the heartbeat file, and flush the debug log. Runs once, on `RunEvent::Exit`.
+pub fn run(app: &AppHandle) {
+    if DONE.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    debug_log!("SHUTDOWN", "App exiting, stopping child processes");
+
+    if let (Some(runtime), Some(hook_state)) = (app.try_state::<TokioRuntime>(), app.try_state::<HookState>()) {
+        let denied = runtime.0.block_on(hooks::deny_all_pending(&hook_state.0, "Horseman is quitting"));
+        debug_log!("SHUTDOWN", "Denied {} pending requests", denied.len());
+    }
+
+    if let Some(slash_state) = app.try_state::<SlashState>() {
+        slash_state.lock().cancel_all();
+    }
+    background_ai::set_paused(true);
+    if let Some(claude_state) = app.try_state::<ClaudeState>() {
+        let stopped = claude_state.lock().terminate_all(TERMINATE_GRACE);
+        debug_log!("SHUTDOWN", "Stopped {} claude processes", stopped);
+    }
+    hooks::mcp_cleanup::sweep();
+    heartbeat::remove();
+
+    debug_log!("SHUTDOWN", "Done");
+    debug::flush();
+}
@@ -1,20 +1,40 @@
 pub mod pty;
 
+use crate::claude::annotations::{self, Annotation, AnnotationKind};
 use crate::debug_log;
-use crate::events::BackendEvent;
+use crate::events::{self, BackendEvent};
+use crate::panic_guard;
+use once_cell::sync::Lazy;
 use pty::PtySession;
+use regex::Regex;
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
+
+/// Characters of output kept when a command is recorded in the session's
+/// annotations (the end, where the result is)
+const MAX_RECORDED_OUTPUT_CHARS: usize = 4_000;
+
+/// Terminal escape sequences (CSI, OSC and two-byte escapes)
+static ANSI_ESCAPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-_]").unwrap());
 
 /// State wrapper for SlashManager
 pub struct SlashState(pub Mutex<SlashManager>);
 
+impl SlashState {
+    /// Lock the manager, recovering from a poisoned mutex
+    pub fn lock(&self) -> MutexGuard<'_, SlashManager> {
+        panic_guard::lock_or_recover(&self.0, "SlashManager")
+    }
+}
+
 /// Manages PTY-based slash command execution
 pub struct SlashManager {
     active_commands: HashMap<String, Arc<Mutex<CommandState>>>,
@@ -25,6 +45,12 @@ struct CommandState {
     cancelled: bool,
 }
 
+/// What was run, for the session's annotation record
+struct Invocation {
+    claude_session_id: String,
+    slash_command: String,
+}
+
 impl SlashManager {
     pub fn new() -> Self {
         Self {
@@ -76,8 +102,8 @@ impl SlashManager {
         );
 
         // Emit started event
-        let _ = app.emit(
-            "horseman-event",
+        let _ = events::emit(
+            app,
             BackendEvent::SlashStarted {
                 command_id: command_id.clone(),
             },
@@ -92,13 +118,13 @@ impl SlashManager {
 
         // Get reader before writing command
         let reader = {
-            let guard = state.lock().unwrap();
+            let guard = panic_guard::lock_or_recover(&state, "slash command");
             guard.session.as_ref().unwrap().take_reader()?
         };
 
         // Write the slash command
         {
-            let guard = state.lock().unwrap();
+            let guard = panic_guard::lock_or_recover(&state, "slash command");
             guard.session.as_ref().unwrap().write_command(&slash_command)?;
         }
 
@@ -109,16 +135,36 @@ impl SlashManager {
         let cmd_id = command_id.clone();
         let state_clone = state.clone();
         let transcript_path_clone = transcript_path.clone();
+        let invocation = Invocation {
+            claude_session_id,
+            slash_command,
+        };
 
         thread::spawn(move || {
-            Self::read_pty_output(
-                app_clone,
-                cmd_id,
-                state_clone,
-                reader,
-                transcript_path_clone,
-                start_position,
-            );
+            let app_for_panic = app_clone.clone();
+            let cmd_id_for_panic = cmd_id.clone();
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                Self::read_pty_output(
+                    app_clone,
+                    cmd_id,
+                    state_clone,
+                    reader,
+                    transcript_path_clone,
+                    start_position,
+                    invocation,
+                );
+            }));
+            if let Err(payload) = result {
+                panic_guard::emit_reader_crashed(&app_for_panic, None, Some(&cmd_id_for_panic), "pty", payload.as_ref(), false);
+                // Unblock the UI waiting on this command
+                let _ = events::emit(
+                    &app_for_panic,
+                    BackendEvent::SlashError {
+                        command_id: cmd_id_for_panic,
+                        message: format!("Output reader crashed: {}", panic_guard::panic_message(payload.as_ref())),
+                    },
+                );
+            }
         });
 
         Ok(command_id)
@@ -132,19 +178,22 @@ impl SlashManager {
         mut reader: Box<dyn Read + Send>,
         transcript_path: Option<PathBuf>,
         start_position: u64,
+        invocation: Invocation,
     ) {
         let mut buf = [0u8; 4096];
         let mut accumulated_output = String::new();
         let start_time = Instant::now();
         let timeout = Duration::from_secs(120);
         let mut detection_method: Option<String> = None;
+        let mut cancelled = false;
 
         loop {
             // Check cancellation
             {
-                let guard = state.lock().unwrap();
+                let guard = panic_guard::lock_or_recover(&state, "slash command");
                 if guard.cancelled {
                     debug_log!("SLASH", "Command {} cancelled", command_id);
+                    cancelled = true;
                     break;
                 }
             }
@@ -152,8 +201,8 @@ impl SlashManager {
             // Check timeout
             if start_time.elapsed() > timeout {
                 debug_log!("SLASH", "Command {} timed out", command_id);
-                    let _ = app.emit(
-                        "horseman-event",
+                    let _ = events::emit(
+                        &app,
                         BackendEvent::SlashError {
                             command_id: command_id.clone(),
                             message: "Slash command timed out after 120s".to_string(),
@@ -175,8 +224,8 @@ impl SlashManager {
                     accumulated_output.push_str(&text);
 
                     // Emit output event
-                    let _ = app.emit(
-                        "horseman-event",
+                    let _ = events::emit(
+                        &app,
                         BackendEvent::SlashOutput {
                             command_id: command_id.clone(),
                             data: text,
@@ -222,8 +271,8 @@ impl SlashManager {
                 command_id,
                 method
             );
-            let _ = app.emit(
-                "horseman-event",
+            let _ = events::emit(
+                &app,
                 BackendEvent::SlashDetected {
                     command_id: command_id.clone(),
                     method: method.clone(),
@@ -234,7 +283,7 @@ impl SlashManager {
         // Kill the process if it's still running (Claude waits at prompt after /compact)
         // Then wait for exit with timeout
         let exit_code = {
-            let mut guard = state.lock().unwrap();
+            let mut guard = panic_guard::lock_or_recover(&state, "slash command");
             if let Some(ref mut session) = guard.session {
                 // If we detected completion, kill the process since Claude is waiting at prompt
                 if detection_method.is_some() {
@@ -284,8 +333,23 @@ impl SlashManager {
             exit_code
         );
 
-        let _ = app.emit(
-            "horseman-event",
+        if !cancelled {
+            let mut record = Annotation::new(AnnotationKind::SlashCommand);
+            record.text = Some(invocation.slash_command.clone());
+            record.data = Some(serde_json::json!({
+                "command": invocation.slash_command,
+                "durationMs": start_time.elapsed().as_millis() as u64,
+                "detectionMethod": detection_method,
+                "exitCode": exit_code,
+                "output": recorded_output(&accumulated_output),
+            }));
+            if let Err(e) = annotations::append(&invocation.claude_session_id, &record) {
+                debug_log!("SLASH", "Failed to record command {}: {}", command_id, e);
+            }
+        }
+
+        let _ = events::emit(
+            &app,
             BackendEvent::SlashCompleted {
                 command_id: command_id.clone(),
                 exit_code,
@@ -296,7 +360,7 @@ impl SlashManager {
     /// Cancel a running slash command
     pub fn cancel(&mut self, command_id: &str) -> Result<(), String> {
         if let Some(state) = self.active_commands.get(command_id) {
-            let mut guard = state.lock().unwrap();
+            let mut guard = panic_guard::lock_or_recover(state, "slash command");
             guard.cancelled = true;
 
             if let Some(ref mut session) = guard.session {
@@ -318,6 +382,51 @@ impl SlashManager {
         self.active_commands.remove(command_id);
         Ok(())
     }
+
+    /// Kill every slash command's whole process group, returning their IDs
+    pub fn kill_all(&mut self) -> Vec<String> {
+        let mut killed = Vec::new();
+        for (command_id, state) in self.active_commands.drain() {
+            let mut guard = panic_guard::lock_or_recover(&state, "slash command");
+            guard.cancelled = true;
+            if let Some(ref mut session) = guard.session {
+                // PTY children lead their own session, so pgid == pid
+                #[cfg(unix)]
+                if let Some(pid) = session.process_id() {
+                    unsafe {
+                        libc::killpg(pid as i32, libc::SIGKILL);
+                    }
+                    debug_log!("SLASH", "Killed process group {}", pid);
+                }
+
+                let _ = session.kill();
+            }
+            killed.push(command_id);
+        }
+        killed
+    }
+
+    /// Cancel every running slash command
+    pub fn cancel_all(&mut self) {
+        let ids: Vec<String> = self.active_commands.keys().cloned().collect();
+        for id in ids {
+            let _ = self.cancel(&id);
+        }
+    }
+}
+
+/// PTY output as plain text, without escape sequences or carriage-return
+/// redraws, cut to its last `MAX_RECORDED_OUTPUT_CHARS` characters
+fn recorded_output(output: &str) -> String {
+    let plain = ANSI_ESCAPE.replace_all(output, "");
+    let lines: Vec<&str> = plain
+        .split('\n')
+        .map(|line| line.rsplit('\r').find(|part| !part.trim().is_empty()).unwrap_or("").trim_end())
+        .filter(|line| !line.is_empty())
+        .collect();
+    let text = lines.join("\n");
+    let skip = text.chars().count().saturating_sub(MAX_RECORDED_OUTPUT_CHARS);
+    text.chars().skip(skip).collect()
 }
 
 /// Get the transcript path for a Claude session
@@ -345,6 +454,8 @@ fn get_transcript_path(working_directory: &str, claude_session_id: &str) -> Opti
 }
 
 /// Check transcript for completion markers
+// Skips unreadable lines rather than stopping at the first one
+#[allow(clippy::manual_flatten)]
 fn check_transcript_completion(path: &PathBuf, start_position: u64) -> Option<String> {
     let file = File::open(path).ok()?;
     let mut reader = BufReader::new(file);
@@ -412,3 +523,16 @@ fn check_pty_completion(output: &str) -> Option<String> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_output_is_plain_text() {
+        let output = "\x1b[2J\x1b[1;1H\x1b]0;claude\x07> /compact\r\n\
+                      Compacting...\r\x1b[32mCompacted\x1b[0m (ctrl+r to see)\r\n\r\n";
+        assert_eq!(recorded_output(output), "> /compact\nCompacted (ctrl+r to see)");
+        assert_eq!(recorded_output(&"x".repeat(MAX_RECORDED_OUTPUT_CHARS + 10)).len(), MAX_RECORDED_OUTPUT_CHARS);
+    }
+}
@@ -2,6 +2,7 @@ use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize}
 use std::io::{Read, Write};
 use std::path::Path;
 use crate::config;
+use crate::network;
 
 /// Active PTY session for running slash commands
 pub struct PtySession {
@@ -32,6 +33,9 @@ impl PtySession {
         cmd.arg("--resume");
         cmd.arg(claude_session_id);
         cmd.cwd(Path::new(working_directory));
+        for (key, value) in network::spawn_env() {
+            cmd.env(key, value);
+        }
 
         let child = pair
             .slave
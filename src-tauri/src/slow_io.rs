@@ -0,0 +1,20 @@
+//! Timeouts for filesystem reads that may block for seconds when the projects
+//! dir lives on a network drive (NFS/SMB) or a cloud-synced folder.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Run a blocking closure on a helper thread, giving up after `timeout`.
+/// A timed-out closure keeps running in the background (IO can't be
+/// cancelled); its result is dropped.
+pub fn with_timeout<T, F>(timeout: Duration, f: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    rx.recv_timeout(timeout).ok()
+}
@@ -0,0 +1,824 @@
+//! User-assigned session metadata (titles, pins, tags), kept in a SQLite
+//! database in the config dir and keyed by Claude session ID. Transcripts
+//! stay read-only; the listing merges this on top of what it discovers.
+//! The same database holds active time per session and day, the cost
+//! ledger, the end-of-session summaries and the per-project prompt history.
+
+use crate::config;
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Opened on first use; None until then (or if opening failed)
+static DB: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
+/// The database's file name in the config dir
+pub const DB_FILE: &str = "horseman.db";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS session_meta (
+        claude_session_id TEXT PRIMARY KEY,
+        title TEXT,
+        pinned INTEGER NOT NULL DEFAULT 0,
+        updated_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS session_tags (
+        claude_session_id TEXT NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (claude_session_id, tag)
+    );
+    CREATE TABLE IF NOT EXISTS session_time (
+        ui_session_id TEXT NOT NULL,
+        day TEXT NOT NULL,
+        project TEXT NOT NULL,
+        claude_session_id TEXT,
+        active_ms INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (ui_session_id, day)
+    );
+    CREATE TABLE IF NOT EXISTS project_time (
+        project TEXT NOT NULL,
+        day TEXT NOT NULL,
+        start_ms INTEGER NOT NULL,
+        end_ms INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS project_time_day ON project_time (day);
+    CREATE TABLE IF NOT EXISTS session_summaries (
+        claude_session_id TEXT PRIMARY KEY,
+        working_directory TEXT NOT NULL,
+        summary TEXT NOT NULL,
+        commit_message TEXT NOT NULL,
+        diff_hash TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS prompt_history (
+        project TEXT NOT NULL,
+        prompt TEXT NOT NULL,
+        claude_session_id TEXT,
+        sent_at TEXT NOT NULL,
+        PRIMARY KEY (project, prompt)
+    );
+    CREATE TABLE IF NOT EXISTS cost_ledger (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        ui_session_id TEXT NOT NULL,
+        claude_session_id TEXT,
+        project TEXT NOT NULL,
+        model TEXT,
+        day TEXT NOT NULL,
+        ended_at TEXT NOT NULL,
+        duration_ms INTEGER NOT NULL DEFAULT 0,
+        cost_usd REAL NOT NULL DEFAULT 0,
+        input_tokens INTEGER NOT NULL DEFAULT 0,
+        output_tokens INTEGER NOT NULL DEFAULT 0,
+        cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+        cache_creation_tokens INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS cost_ledger_day ON cost_ledger (day);
+";
+
+/// Prompts kept per project; the oldest are dropped beyond this
+const PROMPT_HISTORY_LIMIT: usize = 1000;
+
+/// Active time of one UI session on one local day
+#[cfg(feature = "analytics")]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntry {
+    pub ui_session_id: String,
+    /// Local date, YYYY-MM-DD
+    pub day: String,
+    /// Working directory
+    pub project: String,
+    pub claude_session_id: Option<String>,
+    pub active_ms: u64,
+}
+
+/// Wall time during which a project had at least one active session, in ms
+/// since the Unix epoch. Concurrent sessions share the same span.
+#[cfg(feature = "analytics")]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSpan {
+    /// Working directory
+    pub project: String,
+    /// Local date, YYYY-MM-DD
+    pub day: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// Cost and usage of one finished turn
+#[cfg(feature = "analytics")]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEntry {
+    pub ui_session_id: String,
+    pub claude_session_id: Option<String>,
+    /// Working directory
+    pub project: String,
+    /// Model that did most of the turn's work
+    pub model: Option<String>,
+    /// Local date, YYYY-MM-DD
+    pub day: String,
+    pub ended_at: String,
+    pub duration_ms: u64,
+    pub cost_usd: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+}
+
+/// How cost totals are grouped
+#[cfg(feature = "analytics")]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CostGrouping {
+    Day,
+    /// Weeks starting on Monday, keyed by that Monday (`YYYY-MM-DD`), so a
+    /// week spanning New Year stays one group
+    Week,
+    Project,
+}
+
+/// Totals of the ledger entries sharing a day, week or project
+#[cfg(feature = "analytics")]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostTotal {
+    pub key: String,
+    pub cost_usd: f64,
+    pub turns: u64,
+    pub sessions: u64,
+    pub duration_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+}
+
+/// "What changed and why", written when a session's process ends
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub claude_session_id: String,
+    pub working_directory: String,
+    pub summary: String,
+    /// Subject line, blank line, body
+    pub commit_message: String,
+    /// `git::diff_hash` of the changes it describes
+    pub diff_hash: String,
+    pub created_at: String,
+}
+
+/// A prompt the user sent; sending the same prompt again moves it to the front
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptEntry {
+    /// Working directory it was sent in
+    pub project: String,
+    pub prompt: String,
+    pub claude_session_id: Option<String>,
+    pub sent_at: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMeta {
+    pub claude_session_id: String,
+    /// Replaces the default `branch — first prompt` title
+    pub title: Option<String>,
+    pub pinned: bool,
+    /// Sorted, without duplicates
+    pub tags: Vec<String>,
+}
+
+fn open() -> Result<Connection, String> {
+    let dir = config::config_dir().ok_or("Could not determine config directory")?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    let conn = Connection::open(dir.join(DB_FILE)).map_err(|e| format!("Failed to open metadata store: {}", e))?;
+    init(&conn)?;
+    Ok(conn)
+}
+
+/// Run `f` with the database closed (e.g. while its file is replaced); the
+/// next query reopens it
+pub fn while_closed<T>(f: impl FnOnce() -> T) -> T {
+    let mut db = panic_guard::lock_or_recover(&DB, "metadata store");
+    *db = None;
+    f()
+}
+
+fn init(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to create metadata tables: {}", e))
+}
+
+/// Run `f` against the shared connection, opening it if needed
+fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let mut db = panic_guard::lock_or_recover(&DB, "metadata store");
+    if db.is_none() {
+        *db = Some(open()?);
+    }
+    match db.as_ref() {
+        Some(conn) => f(conn).map_err(|e| format!("Metadata store error: {}", e)),
+        None => Err("Metadata store unavailable".to_string()),
+    }
+}
+
+fn touch(conn: &Connection, id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO session_meta (claude_session_id, updated_at) VALUES (?1, ?2)
+         ON CONFLICT(claude_session_id) DO UPDATE SET updated_at = excluded.updated_at",
+        params![id, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+fn load_all(conn: &Connection) -> rusqlite::Result<HashMap<String, SessionMeta>> {
+    let mut all: HashMap<String, SessionMeta> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT claude_session_id, title, pinned FROM session_meta")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SessionMeta {
+            claude_session_id: row.get(0)?,
+            title: row.get(1)?,
+            pinned: row.get(2)?,
+            tags: Vec::new(),
+        })
+    })?;
+    for meta in rows {
+        let meta = meta?;
+        all.insert(meta.claude_session_id.clone(), meta);
+    }
+    let mut stmt = conn.prepare("SELECT claude_session_id, tag FROM session_tags ORDER BY tag")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+    for row in rows {
+        let (id, tag) = row?;
+        all.entry(id.clone())
+            .or_insert_with(|| SessionMeta { claude_session_id: id, ..Default::default() })
+            .tags
+            .push(tag);
+    }
+    Ok(all)
+}
+
+fn load_one(conn: &Connection, id: &str) -> rusqlite::Result<Option<SessionMeta>> {
+    let row = conn
+        .query_row(
+            "SELECT title, pinned FROM session_meta WHERE claude_session_id = ?1",
+            params![id],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, bool>(1)?)),
+        )
+        .optional()?;
+    let (title, pinned) = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+    let mut stmt = conn.prepare("SELECT tag FROM session_tags WHERE claude_session_id = ?1 ORDER BY tag")?;
+    let tags = stmt.query_map(params![id], |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(Some(SessionMeta {
+        claude_session_id: id.to_string(),
+        title,
+        pinned,
+        tags,
+    }))
+}
+
+fn write_tags(conn: &Connection, id: &str, tags: &[String]) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM session_tags WHERE claude_session_id = ?1", params![id])?;
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        conn.execute(
+            "INSERT OR IGNORE INTO session_tags (claude_session_id, tag) VALUES (?1, ?2)",
+            params![id, tag],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "analytics")]
+fn add_time(conn: &Connection, entry: &TimeEntry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO session_time (ui_session_id, day, project, claude_session_id, active_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(ui_session_id, day) DO UPDATE SET
+             active_ms = active_ms + excluded.active_ms,
+             project = excluded.project,
+             claude_session_id = COALESCE(excluded.claude_session_id, claude_session_id)",
+        params![
+            entry.ui_session_id,
+            entry.day,
+            entry.project,
+            entry.claude_session_id,
+            entry.active_ms as i64
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "analytics")]
+fn load_time(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<Vec<TimeEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT ui_session_id, day, project, claude_session_id, active_ms FROM session_time
+         WHERE day >= ?1 AND day <= ?2 ORDER BY day, project, ui_session_id",
+    )?;
+    let rows = stmt.query_map(params![from, to], |row| {
+        Ok(TimeEntry {
+            ui_session_id: row.get(0)?,
+            day: row.get(1)?,
+            project: row.get(2)?,
+            claude_session_id: row.get(3)?,
+            active_ms: row.get::<_, i64>(4)?.max(0) as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Extend the project's span ending where this one starts, or add it
+#[cfg(feature = "analytics")]
+fn add_span(conn: &Connection, span: &ProjectSpan) -> rusqlite::Result<()> {
+    let extended = conn.execute(
+        "UPDATE project_time SET end_ms = ?4 WHERE project = ?1 AND day = ?2 AND end_ms = ?3",
+        params![span.project, span.day, span.start_ms, span.end_ms],
+    )?;
+    if extended == 0 {
+        conn.execute(
+            "INSERT INTO project_time (project, day, start_ms, end_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![span.project, span.day, span.start_ms, span.end_ms],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "analytics")]
+fn load_spans(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<Vec<ProjectSpan>> {
+    let mut stmt = conn.prepare(
+        "SELECT project, day, start_ms, end_ms FROM project_time
+         WHERE day >= ?1 AND day <= ?2 ORDER BY day, project, start_ms",
+    )?;
+    let rows = stmt.query_map(params![from, to], |row| {
+        Ok(ProjectSpan {
+            project: row.get(0)?,
+            day: row.get(1)?,
+            start_ms: row.get(2)?,
+            end_ms: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+#[cfg(feature = "analytics")]
+fn add_cost(conn: &Connection, entry: &CostEntry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO cost_ledger (ui_session_id, claude_session_id, project, model, day, ended_at, duration_ms,
+             cost_usd, input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            entry.ui_session_id,
+            entry.claude_session_id,
+            entry.project,
+            entry.model,
+            entry.day,
+            entry.ended_at,
+            entry.duration_ms as i64,
+            entry.cost_usd,
+            entry.input_tokens as i64,
+            entry.output_tokens as i64,
+            entry.cache_read_tokens as i64,
+            entry.cache_creation_tokens as i64
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "analytics")]
+fn load_cost_totals(
+    conn: &Connection,
+    from: &str,
+    to: &str,
+    project: Option<&str>,
+    grouping: CostGrouping,
+) -> rusqlite::Result<Vec<CostTotal>> {
+    let key = match grouping {
+        CostGrouping::Day => "day",
+        // %w is 0 for Sunday; step back to the Monday on or before `day`
+        CostGrouping::Week => "date(day, '-' || ((CAST(strftime('%w', day) AS INTEGER) + 6) % 7) || ' days')",
+        CostGrouping::Project => "project",
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {key}, SUM(cost_usd), COUNT(*), COUNT(DISTINCT COALESCE(claude_session_id, ui_session_id)),
+             SUM(duration_ms), SUM(input_tokens), SUM(output_tokens), SUM(cache_read_tokens),
+             SUM(cache_creation_tokens)
+         FROM cost_ledger WHERE day >= ?1 AND day <= ?2 AND (?3 IS NULL OR project = ?3)
+         GROUP BY {key} ORDER BY {key}",
+        key = key
+    ))?;
+    let count = |row: &rusqlite::Row, i: usize| row.get::<_, i64>(i).map(|n| n.max(0) as u64);
+    let rows = stmt.query_map(params![from, to, project], |row| {
+        Ok(CostTotal {
+            key: row.get(0)?,
+            cost_usd: row.get(1)?,
+            turns: count(row, 2)?,
+            sessions: count(row, 3)?,
+            duration_ms: count(row, 4)?,
+            input_tokens: count(row, 5)?,
+            output_tokens: count(row, 6)?,
+            cache_read_tokens: count(row, 7)?,
+            cache_creation_tokens: count(row, 8)?,
+        })
+    })?;
+    rows.collect()
+}
+
+#[cfg(feature = "analytics")]
+fn load_session_costs(conn: &Connection, from: &str, to: &str) -> rusqlite::Result<HashMap<String, f64>> {
+    let mut stmt = conn.prepare(
+        "SELECT claude_session_id, SUM(cost_usd) FROM cost_ledger
+         WHERE day >= ?1 AND day <= ?2 AND claude_session_id IS NOT NULL
+         GROUP BY claude_session_id",
+    )?;
+    let rows = stmt.query_map(params![from, to], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+fn add_prompt(conn: &Connection, entry: &PromptEntry) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO prompt_history (project, prompt, claude_session_id, sent_at) VALUES (?1, ?2, ?3, ?4)",
+        params![entry.project, entry.prompt, entry.claude_session_id, entry.sent_at],
+    )?;
+    conn.execute(
+        "DELETE FROM prompt_history WHERE project = ?1 AND prompt NOT IN
+         (SELECT prompt FROM prompt_history WHERE project = ?1 ORDER BY sent_at DESC LIMIT ?2)",
+        params![entry.project, PROMPT_HISTORY_LIMIT as i64],
+    )?;
+    Ok(())
+}
+
+/// Drop prompts sent before `before` (RFC 3339), in every project
+fn prune_prompts(conn: &Connection, before: &str) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM prompt_history WHERE sent_at < ?1", params![before])
+}
+
+fn clear_prompts(conn: &Connection, project: Option<&str>) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM prompt_history WHERE ?1 IS NULL OR project = ?1", params![project])
+}
+
+fn load_prompts(conn: &Connection, project: &str) -> rusqlite::Result<Vec<PromptEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT project, prompt, claude_session_id, sent_at FROM prompt_history
+         WHERE project = ?1 ORDER BY sent_at DESC",
+    )?;
+    let rows = stmt.query_map(params![project], |row| {
+        Ok(PromptEntry {
+            project: row.get(0)?,
+            prompt: row.get(1)?,
+            claude_session_id: row.get(2)?,
+            sent_at: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn summary_from_row(row: &rusqlite::Row) -> rusqlite::Result<SessionSummary> {
+    Ok(SessionSummary {
+        claude_session_id: row.get(0)?,
+        working_directory: row.get(1)?,
+        summary: row.get(2)?,
+        commit_message: row.get(3)?,
+        diff_hash: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+const SUMMARY_COLUMNS: &str = "claude_session_id, working_directory, summary, commit_message, diff_hash, created_at";
+
+fn remove(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    conn.execute("DELETE FROM session_summaries WHERE claude_session_id = ?1", params![id])?;
+    conn.execute("DELETE FROM session_tags WHERE claude_session_id = ?1", params![id])?;
+    Ok(conn.execute("DELETE FROM session_meta WHERE claude_session_id = ?1", params![id])? > 0)
+}
+
+/// Metadata for every session that has any, keyed by Claude session ID
+pub fn all() -> HashMap<String, SessionMeta> {
+    with_db(load_all).unwrap_or_else(|e| {
+        debug_log!("STORE", "{}", e);
+        HashMap::new()
+    })
+}
+
+/// Metadata for one session; defaults if none was ever set
+pub fn get(claude_session_id: &str) -> Result<SessionMeta, String> {
+    Ok(with_db(|conn| load_one(conn, claude_session_id))?.unwrap_or_else(|| SessionMeta {
+        claude_session_id: claude_session_id.to_string(),
+        ..Default::default()
+    }))
+}
+
+/// Set or clear (None / blank) the user title
+pub fn set_title(claude_session_id: &str, title: Option<String>) -> Result<SessionMeta, String> {
+    let title = title.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    with_db(|conn| {
+        touch(conn, claude_session_id)?;
+        conn.execute(
+            "UPDATE session_meta SET title = ?2 WHERE claude_session_id = ?1",
+            params![claude_session_id, title],
+        )?;
+        Ok(())
+    })?;
+    get(claude_session_id)
+}
+
+pub fn set_pinned(claude_session_id: &str, pinned: bool) -> Result<SessionMeta, String> {
+    with_db(|conn| {
+        touch(conn, claude_session_id)?;
+        conn.execute(
+            "UPDATE session_meta SET pinned = ?2 WHERE claude_session_id = ?1",
+            params![claude_session_id, pinned],
+        )?;
+        Ok(())
+    })?;
+    get(claude_session_id)
+}
+
+/// Replace the session's tags (blank tags are dropped)
+pub fn set_tags(claude_session_id: &str, tags: &[String]) -> Result<SessionMeta, String> {
+    with_db(|conn| {
+        touch(conn, claude_session_id)?;
+        write_tags(conn, claude_session_id, tags)
+    })?;
+    get(claude_session_id)
+}
+
+/// Drop all metadata for a session; true if there was any
+pub fn delete(claude_session_id: &str) -> Result<bool, String> {
+    with_db(|conn| remove(conn, claude_session_id))
+}
+
+/// Add active time (`entry.active_ms`) to a session's total for the day
+#[cfg(feature = "analytics")]
+pub fn record_time(entry: &TimeEntry) -> Result<(), String> {
+    with_db(|conn| add_time(conn, entry))
+}
+
+/// Time entries for days in `from..=to` (YYYY-MM-DD)
+#[cfg(feature = "analytics")]
+pub fn time_entries(from: &str, to: &str) -> Result<Vec<TimeEntry>, String> {
+    with_db(|conn| load_time(conn, from, to))
+}
+
+/// Record wall time a project was active
+#[cfg(feature = "analytics")]
+pub fn record_span(span: &ProjectSpan) -> Result<(), String> {
+    with_db(|conn| add_span(conn, span))
+}
+
+/// Project spans for days in `from..=to` (YYYY-MM-DD)
+#[cfg(feature = "analytics")]
+pub fn project_spans(from: &str, to: &str) -> Result<Vec<ProjectSpan>, String> {
+    with_db(|conn| load_spans(conn, from, to))
+}
+
+/// Append a finished turn to the cost ledger
+#[cfg(feature = "analytics")]
+pub fn record_cost(entry: &CostEntry) -> Result<(), String> {
+    with_db(|conn| add_cost(conn, entry))
+}
+
+/// Cost ledger totals for days in `from..=to` (YYYY-MM-DD), optionally for one project
+#[cfg(feature = "analytics")]
+pub fn cost_totals(
+    from: &str,
+    to: &str,
+    project: Option<&str>,
+    grouping: CostGrouping,
+) -> Result<Vec<CostTotal>, String> {
+    with_db(|conn| load_cost_totals(conn, from, to, project, grouping))
+}
+
+/// Ledger spend per Claude session for days in `from..=to` (YYYY-MM-DD)
+#[cfg(feature = "analytics")]
+pub fn session_costs(from: &str, to: &str) -> Result<HashMap<String, f64>, String> {
+    with_db(|conn| load_session_costs(conn, from, to))
+}
+
+/// Add a prompt to its project's history
+pub fn record_prompt(entry: &PromptEntry) -> Result<(), String> {
+    with_db(|conn| add_prompt(conn, entry))
+}
+
+/// Drop prompts sent before `before` (RFC 3339)
+pub fn prune_prompts_before(before: &str) -> Result<usize, String> {
+    with_db(|conn| prune_prompts(conn, before))
+}
+
+/// Forget one project's prompts, or every project's; returns how many
+pub fn delete_prompts(project: Option<&str>) -> Result<usize, String> {
+    with_db(|conn| clear_prompts(conn, project))
+}
+
+/// A project's prompts, newest first
+pub fn prompts(project: &str) -> Result<Vec<PromptEntry>, String> {
+    with_db(|conn| load_prompts(conn, project))
+}
+
+/// Store (replacing) a session's summary
+pub fn set_summary(summary: &SessionSummary) -> Result<(), String> {
+    with_db(|conn| {
+        conn.execute(
+            &format!("INSERT OR REPLACE INTO session_summaries ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", SUMMARY_COLUMNS),
+            params![
+                summary.claude_session_id,
+                summary.working_directory,
+                summary.summary,
+                summary.commit_message,
+                summary.diff_hash,
+                summary.created_at
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+pub fn summary(claude_session_id: &str) -> Result<Option<SessionSummary>, String> {
+    with_db(|conn| {
+        conn.query_row(
+            &format!("SELECT {} FROM session_summaries WHERE claude_session_id = ?1", SUMMARY_COLUMNS),
+            params![claude_session_id],
+            summary_from_row,
+        )
+        .optional()
+    })
+}
+
+/// Newest summary in `working_directory` written for the changes `diff_hash` identifies
+pub fn summary_for_diff(working_directory: &str, diff_hash: &str) -> Option<SessionSummary> {
+    with_db(|conn| {
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM session_summaries WHERE working_directory = ?1 AND diff_hash = ?2
+                 ORDER BY created_at DESC LIMIT 1",
+                SUMMARY_COLUMNS
+            ),
+            params![working_directory, diff_hash],
+            summary_from_row,
+        )
+        .optional()
+    })
+    .unwrap_or_else(|e| {
+        debug_log!("STORE", "{}", e);
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_and_pins_round_trip() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        touch(&conn, "s1").unwrap();
+        conn.execute("UPDATE session_meta SET pinned = 1 WHERE claude_session_id = 's1'", [])
+            .unwrap();
+        write_tags(&conn, "s1", &["wip".into(), " bug ".into(), "wip".into(), "".into()]).unwrap();
+
+        let meta = load_one(&conn, "s1").unwrap().unwrap();
+        assert!(meta.pinned);
+        assert_eq!(meta.tags, ["bug", "wip"]);
+        assert_eq!(load_all(&conn).unwrap().get("s1"), Some(&meta));
+
+        assert!(remove(&conn, "s1").unwrap());
+        assert_eq!(load_one(&conn, "s1").unwrap(), None);
+        assert!(load_all(&conn).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "analytics")]
+    #[test]
+    fn time_accumulates_per_session_and_day() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        let mut entry = TimeEntry {
+            ui_session_id: "ui".into(),
+            day: "2026-03-01".into(),
+            project: "/repo".into(),
+            claude_session_id: None,
+            active_ms: 30_000,
+        };
+        add_time(&conn, &entry).unwrap();
+        entry.claude_session_id = Some("c1".into());
+        add_time(&conn, &entry).unwrap();
+        entry.day = "2026-03-02".into();
+        add_time(&conn, &entry).unwrap();
+
+        let entries = load_time(&conn, "2026-03-01", "2026-03-01").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].active_ms, 60_000);
+        assert_eq!(entries[0].claude_session_id.as_deref(), Some("c1"));
+        assert_eq!(load_time(&conn, "2026-03-01", "2026-03-31").unwrap().len(), 2);
+
+        // Back-to-back ticks extend one span
+        let span = |start_ms, end_ms| ProjectSpan { project: "/repo".into(), day: "2026-03-01".into(), start_ms, end_ms };
+        add_span(&conn, &span(0, 30_000)).unwrap();
+        add_span(&conn, &span(30_000, 60_000)).unwrap();
+        add_span(&conn, &span(90_000, 120_000)).unwrap();
+        let spans: Vec<(i64, i64)> =
+            load_spans(&conn, "2026-03-01", "2026-03-01").unwrap().iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        assert_eq!(spans, vec![(0, 60_000), (90_000, 120_000)]);
+    }
+
+    #[cfg(feature = "analytics")]
+    #[test]
+    fn weeks_spanning_new_year_stay_whole() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        // Monday 2025-12-29 to Sunday 2026-01-04
+        for day in ["2025-12-28", "2025-12-29", "2025-12-31", "2026-01-01", "2026-01-04", "2026-01-05"] {
+            add_cost(
+                &conn,
+                &CostEntry {
+                    ui_session_id: "a".into(),
+                    claude_session_id: None,
+                    project: "/repo".into(),
+                    model: None,
+                    day: day.into(),
+                    ended_at: format!("{}T12:00:00Z", day),
+                    duration_ms: 0,
+                    cost_usd: 1.0,
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    cache_read_tokens: 0,
+                    cache_creation_tokens: 0,
+                },
+            )
+            .unwrap();
+        }
+        let weeks = load_cost_totals(&conn, "2025-12-01", "2026-01-31", None, CostGrouping::Week).unwrap();
+        let by_week: Vec<(&str, u64)> = weeks.iter().map(|t| (t.key.as_str(), t.turns)).collect();
+        assert_eq!(by_week, vec![("2025-12-22", 1), ("2025-12-29", 4), ("2026-01-05", 1)]);
+    }
+
+    #[cfg(feature = "analytics")]
+    #[test]
+    fn cost_totals_group_by_day_week_and_project() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        let entry = |ui: &str, project: &str, day: &str, cost_usd: f64| CostEntry {
+            ui_session_id: ui.into(),
+            claude_session_id: Some(ui.into()),
+            project: project.into(),
+            model: Some("claude-sonnet".into()),
+            day: day.into(),
+            ended_at: format!("{}T12:00:00Z", day),
+            duration_ms: 1_000,
+            cost_usd,
+            input_tokens: 10,
+            output_tokens: 5,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
+        };
+        // 2026-03-01 is a Sunday, so it falls in the week before the 2nd
+        add_cost(&conn, &entry("a", "/repo", "2026-03-01", 0.5)).unwrap();
+        add_cost(&conn, &entry("a", "/repo", "2026-03-02", 0.25)).unwrap();
+        add_cost(&conn, &entry("b", "/other", "2026-03-02", 1.0)).unwrap();
+
+        let days = load_cost_totals(&conn, "2026-03-01", "2026-03-31", None, CostGrouping::Day).unwrap();
+        let by_day: Vec<(&str, f64, u64, u64)> =
+            days.iter().map(|t| (t.key.as_str(), t.cost_usd, t.turns, t.sessions)).collect();
+        assert_eq!(by_day, vec![("2026-03-01", 0.5, 1, 1), ("2026-03-02", 1.25, 2, 2)]);
+
+        let weeks = load_cost_totals(&conn, "2026-03-01", "2026-03-31", None, CostGrouping::Week).unwrap();
+        let by_week: Vec<(&str, f64)> = weeks.iter().map(|t| (t.key.as_str(), t.cost_usd)).collect();
+        assert_eq!(by_week, vec![("2026-02-23", 0.5), ("2026-03-02", 1.25)]);
+        let repo = load_cost_totals(&conn, "2026-03-01", "2026-03-31", Some("/repo"), CostGrouping::Project).unwrap();
+        assert_eq!(repo.len(), 1);
+        assert_eq!((repo[0].cost_usd, repo[0].turns, repo[0].sessions, repo[0].input_tokens), (0.75, 2, 1, 20));
+
+        // Per-session spend only counts the days asked for
+        let sessions = load_session_costs(&conn, "2026-03-02", "2026-03-02").unwrap();
+        assert_eq!(sessions, HashMap::from([("a".to_string(), 0.25), ("b".to_string(), 1.0)]));
+    }
+
+    #[test]
+    fn prompts_are_pruned_by_age_and_cleared() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        let prompt = |project: &str, prompt: &str, sent_at: &str| PromptEntry {
+            project: project.into(),
+            prompt: prompt.into(),
+            claude_session_id: None,
+            sent_at: sent_at.into(),
+        };
+        add_prompt(&conn, &prompt("/a", "old", "2026-01-01T00:00:00+00:00")).unwrap();
+        add_prompt(&conn, &prompt("/a", "new", "2026-03-01T00:00:00+00:00")).unwrap();
+        add_prompt(&conn, &prompt("/b", "other", "2026-03-01T00:00:00+00:00")).unwrap();
+
+        assert_eq!(prune_prompts(&conn, "2026-02-01T00:00:00+00:00").unwrap(), 1);
+        let kept: Vec<String> = load_prompts(&conn, "/a").unwrap().into_iter().map(|p| p.prompt).collect();
+        assert_eq!(kept, vec!["new"]);
+        assert_eq!(clear_prompts(&conn, Some("/a")).unwrap(), 1);
+        assert_eq!(load_prompts(&conn, "/b").unwrap().len(), 1);
+        assert_eq!(clear_prompts(&conn, None).unwrap(), 1);
+    }
+}
@@ -0,0 +1,157 @@
+//! Keeps long-running background services (hook server, watchers,
+//! schedulers) alive: restarts them with exponential backoff when they fail
+//! and records their health for `get_service_health`.
+
+use crate::debug_log;
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A run this long counts as healthy and resets the backoff
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServiceStatus {
+    Running,
+    /// Failed and waiting to restart
+    Backoff,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceHealth {
+    pub name: String,
+    pub status: ServiceStatus,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    /// Unix millis of the most recent (re)start
+    pub started_at: u64,
+}
+
+static SERVICES: Lazy<Mutex<HashMap<String, ServiceHealth>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Delay before the nth consecutive restart (1-based)
+fn backoff_delay(consecutive_failures: u32) -> Duration {
+    let exp = consecutive_failures.saturating_sub(1).min(16);
+    INITIAL_BACKOFF.saturating_mul(1 << exp).min(MAX_BACKOFF)
+}
+
+fn mark_running(name: &str) {
+    let mut services = panic_guard::lock_or_recover(&SERVICES, "supervisor");
+    let entry = services.entry(name.to_string()).or_insert_with(|| ServiceHealth {
+        name: name.to_string(),
+        status: ServiceStatus::Running,
+        restarts: 0,
+        last_error: None,
+        started_at: 0,
+    });
+    entry.status = ServiceStatus::Running;
+    entry.started_at = now_millis();
+}
+
+/// Record a failure and return how long to wait before restarting
+fn mark_failed(name: &str, error: String, ran_for: Duration, consecutive: &mut u32) -> Duration {
+    if ran_for >= HEALTHY_RUN {
+        *consecutive = 0;
+    }
+    *consecutive += 1;
+    let delay = backoff_delay(*consecutive);
+    debug_log!("SUPERVISOR", "{} failed: {} (restarting in {:?})", name, error, delay);
+
+    let mut services = panic_guard::lock_or_recover(&SERVICES, "supervisor");
+    if let Some(entry) = services.get_mut(name) {
+        entry.status = ServiceStatus::Backoff;
+        entry.restarts += 1;
+        entry.last_error = Some(error);
+    }
+    delay
+}
+
+/// Supervise an async service on the current tokio runtime. `make` builds
+/// a fresh run of the service; any return or panic triggers a restart.
+pub fn spawn_async<F, Fut>(name: &str, make: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let name = name.to_string();
+    mark_running(&name);
+    tokio::spawn(async move {
+        let mut consecutive = 0;
+        loop {
+            mark_running(&name);
+            let started = Instant::now();
+            let error = match tokio::spawn(make()).await {
+                Ok(Ok(())) => "exited unexpectedly".to_string(),
+                Ok(Err(e)) => e,
+                Err(e) if e.is_panic() => {
+                    format!("panicked: {}", panic_guard::panic_message(e.into_panic().as_ref()))
+                }
+                Err(e) => e.to_string(),
+            };
+            let delay = mark_failed(&name, error, started.elapsed(), &mut consecutive);
+            tokio::time::sleep(delay).await;
+        }
+    });
+}
+
+/// Supervise a blocking service on its own thread
+pub fn spawn_thread<F>(name: &str, run: F)
+where
+    F: Fn() -> Result<(), String> + Send + 'static,
+{
+    let name = name.to_string();
+    mark_running(&name);
+    std::thread::spawn(move || {
+        let mut consecutive = 0;
+        loop {
+            mark_running(&name);
+            let started = Instant::now();
+            let error = match catch_unwind(AssertUnwindSafe(&run)) {
+                Ok(Ok(())) => "exited unexpectedly".to_string(),
+                Ok(Err(e)) => e,
+                Err(payload) => format!("panicked: {}", panic_guard::panic_message(payload.as_ref())),
+            };
+            let delay = mark_failed(&name, error, started.elapsed(), &mut consecutive);
+            std::thread::sleep(delay);
+        }
+    });
+}
+
+/// Health of every supervised service, sorted by name
+pub fn health() -> Vec<ServiceHealth> {
+    let services = panic_guard::lock_or_recover(&SERVICES, "supervisor");
+    let mut list: Vec<ServiceHealth> = services.values().cloned().collect();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        assert_eq!(backoff_delay(1), Duration::from_secs(1));
+        assert_eq!(backoff_delay(2), Duration::from_secs(2));
+        assert_eq!(backoff_delay(4), Duration::from_secs(8));
+        assert_eq!(backoff_delay(10), MAX_BACKOFF);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,212 @@
+//! Active wall-clock time per session, for billing Claude-assisted work.
+//! A session counts as active while its process runs and it is mid-turn or
+//! has had output or input in the last few minutes; idle keep-alive
+//! processes don't count. Totals are kept per session and local day in the
+//! metadata store, along with the spans of wall time each project was
+//! active, so `report` counts time shared by concurrent sessions once.
+
+use crate::commands::ClaudeState;
+use crate::config;
+use crate::debug_log;
+use crate::store::{self, ProjectSpan, TimeEntry};
+use crate::supervisor;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+const TICK: Duration = Duration::from_secs(30);
+/// Quiet time after which a running session stops counting
+const ACTIVE_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// A tick starting within this of the last one's end continues its spans
+const SNAP_MS: i64 = 1000;
+
+/// One project's time on one day
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectDay {
+    pub day: String,
+    pub project: String,
+    /// Wall time with any session active; less than the sessions' sum when
+    /// they ran at the same time
+    pub active_secs: u64,
+    pub sessions: Vec<TimeEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReport {
+    pub from: String,
+    pub to: String,
+    /// By day, then project
+    pub days: Vec<ProjectDay>,
+    pub total_secs: u64,
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Length of the union of `(start, end)` spans, so overlaps count once
+fn merged_ms(mut spans: Vec<(i64, i64)>) -> u64 {
+    spans.sort();
+    let mut total = 0;
+    let mut current: Option<(i64, i64)> = None;
+    for (start, end) in spans {
+        current = match current {
+            Some((from, to)) if start <= to => Some((from, to.max(end))),
+            Some((from, to)) => {
+                total += (to - from).max(0) as u64;
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+    if let Some((from, to)) = current {
+        total += (to - from).max(0) as u64;
+    }
+    total
+}
+
+/// Roll entries up per (day, project) and total them. Project-days with
+/// spans count their merged spans; older ones without sum their sessions.
+fn group(entries: Vec<TimeEntry>, spans: Vec<ProjectSpan>) -> (Vec<ProjectDay>, u64) {
+    let mut by_project: HashMap<(String, String), Vec<(i64, i64)>> = HashMap::new();
+    for span in &spans {
+        by_project
+            .entry((span.day.clone(), span.project.clone()))
+            .or_default()
+            .push((span.start_ms, span.end_ms));
+    }
+    let mut grouped: BTreeMap<(String, String), Vec<TimeEntry>> = BTreeMap::new();
+    for entry in entries {
+        grouped.entry((entry.day.clone(), entry.project.clone())).or_default().push(entry);
+    }
+
+    let mut counted: BTreeSet<(String, String)> = BTreeSet::new();
+    let mut unspanned_ms = 0;
+    let days = grouped
+        .into_iter()
+        .map(|(key, sessions)| {
+            let active_ms = match by_project.get(&key) {
+                Some(project_spans) => {
+                    counted.insert(key.clone());
+                    merged_ms(project_spans.clone())
+                }
+                None => {
+                    let sum = sessions.iter().map(|s| s.active_ms).sum::<u64>();
+                    unspanned_ms += sum;
+                    sum
+                }
+            };
+            let (day, project) = key;
+            ProjectDay { active_secs: active_ms / 1000, day, project, sessions }
+        })
+        .collect();
+    let all_spans = spans
+        .into_iter()
+        .filter(|s| counted.contains(&(s.day.clone(), s.project.clone())))
+        .map(|s| (s.start_ms, s.end_ms))
+        .collect();
+    (days, (merged_ms(all_spans) + unspanned_ms) / 1000)
+}
+
+/// Time per project and day between `from` and `to` (YYYY-MM-DD, inclusive;
+/// default: today), optionally for one project only
+pub fn report(from: Option<String>, to: Option<String>, project: Option<&str>) -> Result<TimeReport, String> {
+    let from = from.unwrap_or_else(today);
+    let to = to.unwrap_or_else(today);
+    let mut entries = store::time_entries(&from, &to)?;
+    let mut spans = store::project_spans(&from, &to)?;
+    if let Some(project) = project {
+        let project = project.trim_end_matches('/');
+        entries.retain(|e| e.project.trim_end_matches('/') == project);
+        spans.retain(|s| s.project.trim_end_matches('/') == project);
+    }
+    let (days, total_secs) = group(entries, spans);
+    Ok(TimeReport { from, to, days, total_secs })
+}
+
+/// Start the supervised time tracker
+pub fn spawn(app: AppHandle) {
+    supervisor::spawn_thread("timeTracker", move || {
+        let mut last_tick = Instant::now();
+        let mut last_end_ms: Option<i64> = None;
+        loop {
+            std::thread::sleep(TICK);
+            // A longer gap means the machine slept; don't bill it
+            let elapsed = last_tick.elapsed().min(TICK * 2);
+            last_tick = Instant::now();
+            if !config::analytics_enabled() {
+                last_end_ms = None;
+                continue;
+            }
+
+            let active = app.state::<ClaudeState>().lock().active_sessions(ACTIVE_WINDOW);
+            let day = today();
+            let end_ms = chrono::Utc::now().timestamp_millis();
+            let elapsed_ms = elapsed.as_millis() as i64;
+            let start_ms = match last_end_ms {
+                Some(previous) if (end_ms - elapsed_ms - previous).abs() < SNAP_MS => previous,
+                _ => end_ms - elapsed_ms,
+            };
+            last_end_ms = Some(end_ms);
+            let projects: BTreeSet<&str> = active.iter().map(|(_, _, project)| project.as_str()).collect();
+            for project in projects {
+                let span = ProjectSpan { project: project.to_string(), day: day.clone(), start_ms, end_ms };
+                if let Err(e) = store::record_span(&span) {
+                    debug_log!("TIME", "Failed to record time for {}: {}", project, e);
+                }
+            }
+            for (ui_session_id, claude_session_id, project) in active {
+                let entry = TimeEntry {
+                    ui_session_id,
+                    day: day.clone(),
+                    project,
+                    claude_session_id,
+                    active_ms: elapsed.as_millis() as u64,
+                };
+                if let Err(e) = store::record_time(&entry) {
+                    debug_log!("TIME", "[{}] Failed to record time: {}", entry.ui_session_id, e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ui_session_id: &str, project: &str, active_ms: u64) -> TimeEntry {
+        TimeEntry {
+            ui_session_id: ui_session_id.into(),
+            day: "2026-03-01".into(),
+            project: project.into(),
+            claude_session_id: None,
+            active_ms,
+        }
+    }
+
+    fn span(project: &str, start_ms: i64, end_ms: i64) -> ProjectSpan {
+        ProjectSpan { project: project.into(), day: "2026-03-01".into(), start_ms, end_ms }
+    }
+
+    #[test]
+    fn concurrent_sessions_count_once() {
+        assert_eq!(merged_ms(vec![(0, 10), (5, 20), (30, 40)]), 30);
+        assert_eq!(merged_ms(vec![]), 0);
+
+        // Two sessions in /a for the same 60s, one in /b overlapping 30s of it
+        let entries = vec![entry("1", "/a", 60_000), entry("2", "/a", 60_000), entry("3", "/b", 60_000)];
+        let spans = vec![span("/a", 0, 60_000), span("/b", 30_000, 90_000)];
+        let (days, total_secs) = group(entries, spans);
+        let by_project: Vec<(&str, u64)> = days.iter().map(|d| (d.project.as_str(), d.active_secs)).collect();
+        assert_eq!(by_project, vec![("/a", 60), ("/b", 60)]);
+        assert_eq!(total_secs, 90);
+
+        // Recorded before spans were: falls back to the sessions' sum
+        let (days, total_secs) = group(vec![entry("1", "/a", 60_000), entry("2", "/a", 60_000)], vec![]);
+        assert_eq!((days[0].active_secs, total_secs), (120, 120));
+    }
+}
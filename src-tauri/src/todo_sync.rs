@@ -0,0 +1,367 @@
+//! Mirrors each session's TodoWrite list into a Markdown file in the repo or
+//! GitHub issues (`todoSyncTarget`), on a schedule. Only completion syncs
+//! both ways: whichever side changed since the last sync wins, the app when
+//! both did. Items are matched by their text, within the session's own
+//! Markdown section or issues (which carry the same marker in their body).
+
+use crate::atomic_file;
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, TodoItem};
+use crate::login_shell;
+use crate::panic_guard;
+use crate::supervisor;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+/// Label put on (and used to find) mirrored GitHub issues
+const ISSUE_LABEL: &str = "horseman-todo";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoSyncTarget {
+    /// A section per session in `todoSyncFile`
+    Markdown,
+    /// Issues in the repo's GitHub project, via the `gh` CLI
+    Github,
+}
+
+/// Latest list and sync state of one UI session
+#[derive(Clone)]
+struct Tracked {
+    working_directory: String,
+    todos: Vec<TodoItem>,
+    /// Completion of each item as of the last sync
+    base: HashMap<String, bool>,
+}
+
+static TRACKED: Lazy<Mutex<HashMap<String, Tracked>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// What a sync has to do, by item text
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SyncPlan {
+    /// Write the app's completion to the external side (creating it if missing)
+    pub push: Vec<(String, bool)>,
+    /// Take the external completion into the app
+    pub pull: Vec<(String, bool)>,
+}
+
+/// Three-way compare of the app list against the external state, relative
+/// to what both agreed on at the last sync
+pub(crate) fn plan(base: &HashMap<String, bool>, local: &[(String, bool)], remote: &HashMap<String, bool>) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    for (content, done) in local {
+        match remote.get(content) {
+            None => plan.push.push((content.clone(), *done)),
+            Some(remote_done) if remote_done == done => {}
+            Some(remote_done) if base.get(content) == Some(done) => plan.pull.push((content.clone(), *remote_done)),
+            Some(_) => plan.push.push((content.clone(), *done)),
+        }
+    }
+    plan
+}
+
+fn is_done(todo: &TodoItem) -> bool {
+    todo.status == "completed"
+}
+
+fn section_markers(ui_session_id: &str) -> (String, String) {
+    (
+        format!("<!-- horseman-todos:{} -->", ui_session_id),
+        "<!-- /horseman-todos -->".to_string(),
+    )
+}
+
+/// Checklist items in the session's section of a Markdown file
+pub(crate) fn parse_markdown(content: &str, ui_session_id: &str) -> Vec<(String, bool)> {
+    let (start, end) = section_markers(ui_session_id);
+    let section = match content.split_once(&start) {
+        Some((_, rest)) => rest.split(&end).next().unwrap_or(""),
+        None => return vec![],
+    };
+    section
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(text) = line.strip_prefix("- [ ] ") {
+                Some((text.trim().to_string(), false))
+            } else {
+                let text = line.strip_prefix("- [x] ").or_else(|| line.strip_prefix("- [X] "))?;
+                Some((text.trim().to_string(), true))
+            }
+        })
+        .collect()
+}
+
+/// Replace (or append) the session's section with `items`
+pub(crate) fn render_markdown(content: &str, ui_session_id: &str, items: &[(String, bool)]) -> String {
+    let (start, end) = section_markers(ui_session_id);
+    let mut section = format!("{}\n", start);
+    for (text, done) in items {
+        section.push_str(&format!("- [{}] {}\n", if *done { "x" } else { " " }, text));
+    }
+    section.push_str(&end);
+
+    if let Some((before, rest)) = content.split_once(&start) {
+        let after = rest.split_once(&end).map(|(_, after)| after).unwrap_or("");
+        return format!("{}{}{}", before, section, after);
+    }
+    let mut out = content.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&section);
+    out.push('\n');
+    out
+}
+
+fn sync_markdown(
+    ui_session_id: &str,
+    working_directory: &str,
+    local: &[(String, bool)],
+    base: &HashMap<String, bool>,
+) -> Result<SyncPlan, String> {
+    let path = Path::new(working_directory).join(config::todo_sync_file());
+    let content = match fs::read_to_string(&path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {:?}: {}", path, e)),
+    };
+    let existing = parse_markdown(&content, ui_session_id);
+    let remote: HashMap<String, bool> = existing.iter().cloned().collect();
+    let plan = plan(base, local, &remote);
+    if plan.push.is_empty() {
+        return Ok(plan);
+    }
+
+    // The app's list in its order, then lines only the file has
+    let pulled: HashMap<&str, bool> = plan.pull.iter().map(|(t, d)| (t.as_str(), *d)).collect();
+    let mut items: Vec<(String, bool)> = local
+        .iter()
+        .map(|(text, done)| (text.clone(), pulled.get(text.as_str()).copied().unwrap_or(*done)))
+        .collect();
+    items.extend(existing.into_iter().filter(|(text, _)| !local.iter().any(|(t, _)| t == text)));
+    atomic_file::write(&path, render_markdown(&content, ui_session_id, &items))
+        .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    Ok(plan)
+}
+
+fn gh(working_directory: &str, args: &[&str]) -> Result<String, String> {
+    // The app isn't started from a shell, so `gh` is looked up on the login shell's PATH
+    let program = login_shell::find_program("gh").ok_or("gh not found on the login shell's PATH")?;
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(working_directory)
+        .output()
+        .map_err(|e| format!("Failed to run gh: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("gh {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[derive(Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    state: String,
+    #[serde(default)]
+    body: String,
+}
+
+/// The issues mirrored from `ui_session_id`, so sessions (and issues people
+/// labelled by hand) with the same item text don't share state
+fn session_issues(issues: Vec<Issue>, ui_session_id: &str) -> Vec<Issue> {
+    let (marker, _) = section_markers(ui_session_id);
+    issues.into_iter().filter(|issue| issue.body.contains(&marker)).collect()
+}
+
+fn sync_github(
+    ui_session_id: &str,
+    working_directory: &str,
+    local: &[(String, bool)],
+    base: &HashMap<String, bool>,
+) -> Result<SyncPlan, String> {
+    let listing = gh(
+        working_directory,
+        &[
+            "issue", "list", "--label", ISSUE_LABEL, "--state", "all", "--limit", "500", "--json",
+            "number,title,state,body",
+        ],
+    )?;
+    let issues: Vec<Issue> = serde_json::from_str(&listing).map_err(|e| format!("Unexpected gh output: {}", e))?;
+    let issues = session_issues(issues, ui_session_id);
+    let numbers: HashMap<&str, u64> = issues.iter().map(|i| (i.title.as_str(), i.number)).collect();
+    let remote: HashMap<String, bool> = issues.iter().map(|i| (i.title.clone(), i.state == "CLOSED")).collect();
+
+    let plan = plan(base, local, &remote);
+    for (title, done) in &plan.push {
+        match numbers.get(title.as_str()) {
+            Some(number) => {
+                let number = number.to_string();
+                gh(working_directory, &["issue", if *done { "close" } else { "reopen" }, &number])?;
+            }
+            None => {
+                let description = "Mirrored from a Horseman todo list";
+                gh(working_directory, &["label", "create", ISSUE_LABEL, "--force", "--description", description])?;
+                let (marker, _) = section_markers(ui_session_id);
+                let body = format!("Mirrored from Horseman session `{}`.\n\n{}", ui_session_id, marker);
+                let url = gh(
+                    working_directory,
+                    &["issue", "create", "--title", title, "--label", ISSUE_LABEL, "--body", &body],
+                )?;
+                if *done {
+                    gh(working_directory, &["issue", "close", url.trim()])?;
+                }
+            }
+        }
+    }
+    Ok(plan)
+}
+
+/// Remember a session's latest TodoWrite list for the next sync
+pub fn observe(ui_session_id: &str, working_directory: &str, todos: &[TodoItem]) {
+    if config::todo_sync_target().is_none() {
+        return;
+    }
+    let mut tracked = panic_guard::lock_or_recover(&TRACKED, "todo sync");
+    let entry = tracked.entry(ui_session_id.to_string()).or_insert_with(|| Tracked {
+        working_directory: working_directory.to_string(),
+        todos: Vec::new(),
+        base: HashMap::new(),
+    });
+    entry.working_directory = working_directory.to_string();
+    entry.todos = todos.to_vec();
+}
+
+/// Stop syncing a closed session
+pub fn forget(ui_session_id: &str) {
+    panic_guard::lock_or_recover(&TRACKED, "todo sync").remove(ui_session_id);
+}
+
+/// Sync every tracked session once
+fn sync_all(app: &AppHandle, target: TodoSyncTarget) {
+    let sessions: Vec<(String, Tracked)> = panic_guard::lock_or_recover(&TRACKED, "todo sync")
+        .iter()
+        .map(|(id, t)| (id.clone(), t.clone()))
+        .collect();
+
+    for (ui_session_id, Tracked { working_directory, mut todos, base }) in sessions {
+        if todos.is_empty() {
+            continue;
+        }
+        let local: Vec<(String, bool)> = todos.iter().map(|t| (t.content.clone(), is_done(t))).collect();
+        let result = match target {
+            TodoSyncTarget::Markdown => sync_markdown(&ui_session_id, &working_directory, &local, &base),
+            TodoSyncTarget::Github => sync_github(&ui_session_id, &working_directory, &local, &base),
+        };
+        let plan = match result {
+            Ok(plan) => plan,
+            Err(e) => {
+                debug_log!("TODO_SYNC", "[{}] Sync failed: {}", ui_session_id, e);
+                continue;
+            }
+        };
+        if !plan.push.is_empty() || !plan.pull.is_empty() {
+            debug_log!("TODO_SYNC", "[{}] Pushed {}, pulled {}", ui_session_id, plan.push.len(), plan.pull.len());
+        }
+
+        let synced: Vec<(String, String)> = todos.iter().map(|t| (t.content.clone(), t.status.clone())).collect();
+        for (content, done) in &plan.pull {
+            if let Some(todo) = todos.iter_mut().find(|t| &t.content == content) {
+                todo.status = if *done { "completed" } else { "pending" }.to_string();
+            }
+        }
+        {
+            let mut tracked = panic_guard::lock_or_recover(&TRACKED, "todo sync");
+            let entry = match tracked.get_mut(&ui_session_id) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let unchanged = entry.todos.len() == synced.len()
+                && entry.todos.iter().zip(&synced).all(|(t, (c, s))| &t.content == c && &t.status == s);
+            entry.base = todos.iter().map(|t| (t.content.clone(), is_done(t))).collect();
+            // A newer list that arrived mid-sync wins; it is compared against
+            // the new base next round
+            if !unchanged {
+                continue;
+            }
+            entry.todos = todos.clone();
+        }
+        if !plan.pull.is_empty() {
+            let _ = events::emit(
+                app,
+                BackendEvent::TodosUpdated {
+                    ui_session_id: ui_session_id.clone(),
+                    todos,
+                },
+            );
+        }
+    }
+}
+
+/// Start the supervised sync loop. Checks the config each round, so
+/// enabling sync doesn't need a restart.
+pub fn spawn(app: AppHandle) {
+    supervisor::spawn_thread("todoSync", move || loop {
+        std::thread::sleep(config::todo_sync_interval());
+        if let Some(target) = config::todo_sync_target() {
+            sync_all(&app, target);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_prefers_the_side_that_changed() {
+        let base = HashMap::from([("a".to_string(), false), ("b".to_string(), false), ("c".to_string(), false)]);
+        let local = vec![
+            ("a".to_string(), false),
+            ("b".to_string(), true),
+            ("c".to_string(), true),
+            ("d".to_string(), false),
+        ];
+        // a: checked externally; b: completed in app; c: both; d: new
+        let remote = HashMap::from([("a".to_string(), true), ("b".to_string(), false), ("c".to_string(), true)]);
+        let plan = plan(&base, &local, &remote);
+        assert_eq!(plan.pull, [("a".to_string(), true)]);
+        assert_eq!(plan.push, [("b".to_string(), true), ("d".to_string(), false)]);
+    }
+
+    #[test]
+    fn markdown_section_round_trips() {
+        let items = vec![("Write tests".to_string(), true), ("Ship it".to_string(), false)];
+        let content = render_markdown("# Notes\n", "ui-1", &items);
+        assert!(content.starts_with("# Notes\n\n<!-- horseman-todos:ui-1 -->"));
+        assert_eq!(parse_markdown(&content, "ui-1"), items);
+        assert!(parse_markdown(&content, "ui-2").is_empty());
+
+        let updated = render_markdown(&content, "ui-1", &items[1..]);
+        assert_eq!(parse_markdown(&updated, "ui-1"), &items[1..]);
+        assert_eq!(updated.matches("horseman-todos:ui-1").count(), 1);
+    }
+
+    #[test]
+    fn issues_are_scoped_to_their_session() {
+        let issues: Vec<Issue> = serde_json::from_value(serde_json::json!([
+            { "number": 1, "title": "Ship it", "state": "OPEN", "body": "From `ui-1`.\n\n<!-- horseman-todos:ui-1 -->" },
+            { "number": 2, "title": "Ship it", "state": "CLOSED", "body": "<!-- horseman-todos:ui-2 -->" },
+            { "number": 3, "title": "Ship it", "state": "OPEN", "body": "Labelled by hand" },
+        ]))
+        .unwrap();
+        let numbers: Vec<u64> = session_issues(issues, "ui-1").iter().map(|i| i.number).collect();
+        assert_eq!(numbers, [1]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Opt-in fix-and-retry loop: after each turn that edits files, run
+//! verification and, if it fails, send the failures back to Claude as the
+//! next message until it passes or the iteration/cost budget runs out.
+
+use crate::commands::ClaudeState;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, VerificationStep};
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Default max number of turns in a loop (including the first)
+pub const DEFAULT_MAX_ITERATIONS: u32 = 5;
+
+/// Limits for a single loop run
+#[derive(Debug, Clone)]
+pub struct LoopLimits {
+    pub max_iterations: u32,
+    /// Stop once the summed turn cost reaches this (USD)
+    pub max_cost_usd: Option<f64>,
+}
+
+#[derive(Debug)]
+struct LoopState {
+    limits: LoopLimits,
+    /// 1-based turn currently running
+    iteration: u32,
+    cost_usd: f64,
+    working_directory: String,
+    model: Option<String>,
+    /// Claude session to resume for the next iteration (from the last turn)
+    claude_session_id: Option<String>,
+}
+
+/// Active loops keyed by UI session ID
+static ACTIVE_LOOPS: Lazy<Mutex<HashMap<String, LoopState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn emit_iteration(app: &AppHandle, ui_session_id: &str, state: &LoopState, status: &str) {
+    let _ = events::emit(
+        app,
+        BackendEvent::LoopIteration {
+            ui_session_id: ui_session_id.to_string(),
+            iteration: state.iteration,
+            max_iterations: state.limits.max_iterations,
+            cost_usd: state.cost_usd,
+            status: status.to_string(),
+        },
+    );
+}
+
+/// Register a loop for a session. The caller sends the first message.
+pub fn start(
+    app: &AppHandle,
+    ui_session_id: &str,
+    working_directory: &str,
+    model: Option<String>,
+    claude_session_id: Option<String>,
+    limits: LoopLimits,
+) -> Result<(), String> {
+    if limits.max_iterations == 0 {
+        return Err("maxIterations must be at least 1".to_string());
+    }
+
+    let state = LoopState {
+        limits,
+        iteration: 1,
+        cost_usd: 0.0,
+        working_directory: working_directory.to_string(),
+        model,
+        claude_session_id,
+    };
+    emit_iteration(app, ui_session_id, &state, "running");
+
+    let mut loops = panic_guard::lock_or_recover(&ACTIVE_LOOPS, "fix loops");
+    loops.insert(ui_session_id.to_string(), state);
+    debug_log!("LOOP", "[{}] Started fix loop", ui_session_id);
+    Ok(())
+}
+
+/// Whether a loop is running for `ui_session_id`
+pub fn is_running(ui_session_id: &str) -> bool {
+    panic_guard::lock_or_recover(&ACTIVE_LOOPS, "fix loops").contains_key(ui_session_id)
+}
+
+/// End a loop with a final status. Returns false if none was running.
+pub fn stop(app: &AppHandle, ui_session_id: &str, status: &str) -> bool {
+    let removed = panic_guard::lock_or_recover(&ACTIVE_LOOPS, "fix loops").remove(ui_session_id);
+    let state = match removed {
+        Some(s) => s,
+        None => return false,
+    };
+    debug_log!("LOOP", "[{}] Loop ended: {}", ui_session_id, status);
+    emit_iteration(app, ui_session_id, &state, status);
+    true
+}
+
+/// End every active loop, e.g. when backend state is reset
+pub fn stop_all(app: &AppHandle, status: &str) {
+    let ids: Vec<String> = panic_guard::lock_or_recover(&ACTIVE_LOOPS, "fix loops").keys().cloned().collect();
+    for id in ids {
+        stop(app, &id, status);
+    }
+}
+
+/// Record a finished turn's Claude session ID and cost
+pub fn record_turn(ui_session_id: &str, claude_session_id: Option<String>, cost_usd: Option<f64>) {
+    let mut loops = panic_guard::lock_or_recover(&ACTIVE_LOOPS, "fix loops");
+    if let Some(state) = loops.get_mut(ui_session_id) {
+        if claude_session_id.is_some() {
+            state.claude_session_id = claude_session_id;
+        }
+        state.cost_usd += cost_usd.unwrap_or(0.0);
+    }
+}
+
+/// Continue or finish the loop after a verification run
+pub fn on_verification_completed(app: &AppHandle, ui_session_id: &str, passed: bool, steps: &[VerificationStep]) {
+    if passed {
+        stop(app, ui_session_id, "passed");
+        return;
+    }
+
+    let (prompt, claude_session_id, working_directory, model) = {
+        let mut loops = panic_guard::lock_or_recover(&ACTIVE_LOOPS, "fix loops");
+        let state = match loops.get_mut(ui_session_id) {
+            Some(s) => s,
+            None => return,
+        };
+
+        let over_budget = state
+            .limits
+            .max_cost_usd
+            .map(|max| state.cost_usd >= max)
+            .unwrap_or(false);
+        let out_of_iterations = state.iteration >= state.limits.max_iterations;
+        let prompt = super::format_failure_prompt(steps);
+        let claude_session_id = state.claude_session_id.clone();
+
+        if over_budget || out_of_iterations || prompt.is_none() || claude_session_id.is_none() {
+            let status = if over_budget { "budgetExceeded" } else { "failed" };
+            if let Some(state) = loops.remove(ui_session_id) {
+                drop(loops);
+                debug_log!("LOOP", "[{}] Loop ended: {}", ui_session_id, status);
+                emit_iteration(app, ui_session_id, &state, status);
+            }
+            return;
+        }
+
+        state.iteration += 1;
+        emit_iteration(app, ui_session_id, state, "retrying");
+        (
+            prompt.unwrap_or_default(),
+            claude_session_id.unwrap_or_default(),
+            state.working_directory.clone(),
+            state.model.clone(),
+        )
+    };
+
+    debug_log!("LOOP", "[{}] Sending verification failures back to Claude", ui_session_id);
+    let claude_state = app.state::<ClaudeState>();
+    let result = claude_state.lock().send_message(
+        app,
+        ui_session_id,
+        working_directory,
+        prompt,
+        claude_session_id,
+        model,
+    );
+    if let Err(e) = result {
+        debug_log!("LOOP", "[{}] Failed to send retry: {}", ui_session_id, e);
+        stop(app, ui_session_id, "error");
+    }
+}
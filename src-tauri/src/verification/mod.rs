@@ -0,0 +1,241 @@
+pub mod fix_loop;
+pub mod test_output;
+
+use crate::claude::annotations::{self, Annotation, AnnotationKind};
+use crate::config;
+use crate::debug_log;
+use crate::events::{self, BackendEvent, VerificationStep};
+use crate::panic_guard;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+/// Max time a single verification command may run
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Max characters of output kept per step (tail)
+const MAX_OUTPUT_CHARS: usize = 8000;
+
+/// Tools whose use means the turn edited files
+pub const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Most recent verification steps per UI session (for follow-up prompts)
+static LAST_RESULTS: Lazy<Mutex<HashMap<String, Vec<VerificationStep>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Keep only the last `max` characters of output (errors are usually at the end)
+fn tail(output: &str, max: usize) -> String {
+    let trimmed = output.trim();
+    let count = trimmed.chars().count();
+    if count <= max {
+        return trimmed.to_string();
+    }
+    let skipped: String = trimmed.chars().skip(count - max).collect();
+    format!("[... truncated ...]\n{}", skipped)
+}
+
+/// Run one command via login shell (same PATH setup as claude spawns)
+fn run_step(command: &str, working_directory: &str) -> VerificationStep {
+    let start = Instant::now();
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
+
+    let mut shell = Command::new(&shell);
+    shell
+        .args(["-l", "-c", command])
+        .current_dir(working_directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Own process group, so a timeout also stops what the shell started
+    // (test runners, compilers, watchers holding the pipes open)
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+
+    let mut child = match shell.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            return VerificationStep {
+                command: command.to_string(),
+                passed: false,
+                exit_code: None,
+                timed_out: false,
+                duration_ms: start.elapsed().as_millis() as u64,
+                output: format!("Failed to spawn: {}", e),
+                tests: None,
+            };
+        }
+    };
+
+    // Drain pipes on separate threads so a chatty command can't block on a full pipe
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(ref mut out) = stdout {
+            let _ = out.read_to_string(&mut buf);
+        }
+        buf
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(ref mut err) = stderr {
+            let _ = err.read_to_string(&mut buf);
+        }
+        buf
+    });
+
+    let mut timed_out = false;
+    let exit_code = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status.code(),
+            Ok(None) => {
+                if start.elapsed() > COMMAND_TIMEOUT {
+                    #[cfg(unix)]
+                    unsafe {
+                        libc::killpg(child.id() as i32, libc::SIGKILL);
+                    }
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    timed_out = true;
+                    break None;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => {
+                debug_log!("VERIFY", "try_wait error for '{}': {}", command, e);
+                break None;
+            }
+        }
+    };
+
+    let mut output = stdout_reader.join().unwrap_or_default();
+    let stderr_output = stderr_reader.join().unwrap_or_default();
+    if !stderr_output.is_empty() {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&stderr_output);
+    }
+
+    VerificationStep {
+        command: command.to_string(),
+        passed: exit_code == Some(0),
+        exit_code,
+        timed_out,
+        duration_ms: start.elapsed().as_millis() as u64,
+        tests: test_output::parse_test_output(&output),
+        output: tail(&output, MAX_OUTPUT_CHARS),
+    }
+}
+
+/// Run all commands in order, stopping at the first failure
+pub fn run_commands(working_directory: &str, commands: &[String]) -> Vec<VerificationStep> {
+    let mut steps = Vec::new();
+    for command in commands {
+        debug_log!("VERIFY", "Running '{}' in {}", command, working_directory);
+        let step = run_step(command, working_directory);
+        debug_log!(
+            "VERIFY",
+            "'{}' finished: passed={} exit={:?} ({}ms)",
+            command,
+            step.passed,
+            step.exit_code,
+            step.duration_ms
+        );
+        let passed = step.passed;
+        steps.push(step);
+        if !passed {
+            break;
+        }
+    }
+    steps
+}
+
+/// Run the project's configured verification commands on a background thread
+/// and emit `verification.completed`. Returns false if none are configured.
+pub fn spawn_verification(app: &AppHandle, ui_session_id: &str, working_directory: &str) -> bool {
+    let commands = config::verification_commands(working_directory);
+    if commands.is_empty() {
+        return false;
+    }
+
+    let app = app.clone();
+    let ui_session_id = ui_session_id.to_string();
+    let working_directory = working_directory.to_string();
+    std::thread::spawn(move || {
+        let steps = run_commands(&working_directory, &commands);
+        let passed = steps.iter().all(|s| s.passed);
+        panic_guard::lock_or_recover(&LAST_RESULTS, "verification results")
+            .insert(ui_session_id.clone(), steps.clone());
+        let _ = events::emit(
+            &app,
+            BackendEvent::VerificationCompleted {
+                ui_session_id: ui_session_id.clone(),
+                passed,
+                steps: steps.clone(),
+            },
+        );
+        let mut record = Annotation::new(AnnotationKind::Verification);
+        record.data = Some(serde_json::json!({ "passed": passed, "steps": steps }));
+        annotations::record_for_ui_session(&app, &ui_session_id, record);
+        fix_loop::on_verification_completed(&app, &ui_session_id, passed, &steps);
+    });
+    true
+}
+
+/// Get the most recent verification steps for a session
+pub fn last_results(ui_session_id: &str) -> Option<Vec<VerificationStep>> {
+    panic_guard::lock_or_recover(&LAST_RESULTS, "verification results").get(ui_session_id).cloned()
+}
+
+/// Format failed verification steps into a follow-up prompt for Claude.
+/// Returns None if every step passed.
+pub fn format_failure_prompt(steps: &[VerificationStep]) -> Option<String> {
+    let failed: Vec<&VerificationStep> = steps.iter().filter(|s| !s.passed).collect();
+    if failed.is_empty() {
+        return None;
+    }
+
+    let has_test_failures = failed
+        .iter()
+        .any(|s| s.tests.as_ref().map(|t| !t.failures.is_empty()).unwrap_or(false));
+    let mut prompt = if has_test_failures {
+        "Verification failed. Please fix these failing tests:\n".to_string()
+    } else {
+        "Verification failed. Please fix the following errors:\n".to_string()
+    };
+
+    for step in failed {
+        prompt.push_str(&format!("\n`{}`", step.command));
+        if step.timed_out {
+            prompt.push_str(" timed out");
+        } else if let Some(code) = step.exit_code {
+            prompt.push_str(&format!(" exited with code {}", code));
+        }
+        prompt.push('\n');
+
+        match step.tests {
+            Some(ref report) if !report.failures.is_empty() => {
+                for failure in &report.failures {
+                    prompt.push_str(&format!("- {}", failure.name));
+                    if let Some(ref location) = failure.location {
+                        prompt.push_str(&format!(" ({})", location));
+                    }
+                    if let Some(ref message) = failure.message {
+                        prompt.push_str(&format!(": {}", message));
+                    }
+                    prompt.push('\n');
+                }
+            }
+            _ => {
+                prompt.push_str(&format!("```\n{}\n```\n", tail(&step.output, 4000)));
+            }
+        }
+    }
+
+    Some(prompt)
+}
@@ -0,0 +1,225 @@
+//! Best-effort parsing of test runner output (cargo test, jest, pytest)
+//! into structured failures.
+
+use crate::events::{TestFailure, TestReport};
+
+/// Parse test output, detecting the framework from its summary lines
+pub fn parse_test_output(output: &str) -> Option<TestReport> {
+    if output.contains("test result:") && output.contains("running ") {
+        return Some(parse_cargo(output));
+    }
+    if output.lines().any(|l| l.trim_start().starts_with("Tests:") && l.contains("total")) {
+        return Some(parse_jest(output));
+    }
+    if output.contains("short test summary info")
+        || output.lines().any(|l| l.starts_with("FAILED ") && l.contains("::"))
+        || output.lines().any(is_pytest_summary_line)
+    {
+        return Some(parse_pytest(output));
+    }
+    None
+}
+
+/// Extract "N <word>" counts from a summary line, e.g. "3 passed; 1 failed"
+fn count_before(line: &str, word: &str) -> Option<u32> {
+    let words: Vec<&str> = line
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|w| !w.is_empty())
+        .collect();
+    words
+        .windows(2)
+        .rev()
+        .filter(|w| w[1].starts_with(word))
+        .find_map(|w| w[0].parse::<u32>().ok())
+}
+
+fn parse_cargo(output: &str) -> TestReport {
+    let mut failures: Vec<TestFailure> = Vec::new();
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("test ") {
+            if let Some(name) = rest.strip_suffix(" ... FAILED") {
+                if !failures.iter().any(|f| f.name == name) {
+                    failures.push(TestFailure { name: name.to_string(), location: None, message: None });
+                }
+            }
+        }
+        if line.starts_with("test result:") {
+            passed += count_before(line, "passed").unwrap_or(0);
+            failed += count_before(line, "failed").unwrap_or(0);
+        }
+    }
+
+    // Attach panic location + message from "---- name stdout ----" sections
+    let lines: Vec<&str> = output.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let name = match line.strip_prefix("---- ").and_then(|l| l.strip_suffix(" stdout ----")) {
+            Some(n) => n,
+            None => continue,
+        };
+        let failure = match failures.iter_mut().find(|f| f.name == name) {
+            Some(f) => f,
+            None => continue,
+        };
+        for (j, detail) in lines.iter().enumerate().skip(i + 1) {
+            if detail.starts_with("---- ") || detail.trim().is_empty() {
+                break;
+            }
+            if let Some(pos) = detail.find("panicked at ") {
+                let loc = detail[pos + "panicked at ".len()..].trim_end_matches(':');
+                // Older format: "panicked at 'msg', src/lib.rs:1:1"
+                let loc = loc.rsplit(", ").next().unwrap_or(loc);
+                failure.location = Some(loc.to_string());
+                if let Some(next) = lines.get(j + 1) {
+                    if !next.trim().is_empty() && !next.starts_with("note:") {
+                        failure.message = Some(next.trim().to_string());
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    TestReport { framework: "cargo".to_string(), passed, failed, failures }
+}
+
+fn parse_jest(output: &str) -> TestReport {
+    let mut failures: Vec<TestFailure> = Vec::new();
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix("● ") {
+            // Skip the "● Test suite failed to run" style headers that repeat
+            if !failures.iter().any(|f| f.name == name) {
+                failures.push(TestFailure { name: name.to_string(), location: None, message: None });
+            }
+            continue;
+        }
+        if trimmed.starts_with("Tests:") {
+            passed = count_before(trimmed, "passed").unwrap_or(0);
+            failed = count_before(trimmed, "failed").unwrap_or(0);
+            continue;
+        }
+        if let Some(last) = failures.last_mut() {
+            if last.message.is_none() && !trimmed.is_empty() && !trimmed.starts_with("at ") {
+                last.message = Some(trimmed.to_string());
+            }
+            // First non-node_modules stack frame is the test location
+            if last.location.is_none() && trimmed.starts_with("at ") && !trimmed.contains("node_modules") {
+                let frame = trimmed.trim_end_matches(')');
+                let loc = frame.rsplit('(').next().unwrap_or(frame).trim_start_matches("at ");
+                last.location = Some(loc.to_string());
+            }
+        }
+    }
+
+    TestReport { framework: "jest".to_string(), passed, failed, failures }
+}
+
+fn is_pytest_summary_line(line: &str) -> bool {
+    line.starts_with('=') && line.ends_with('=') && (line.contains(" passed") || line.contains(" failed")) && line.contains(" in ")
+}
+
+fn parse_pytest(output: &str) -> TestReport {
+    let mut failures: Vec<TestFailure> = Vec::new();
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("FAILED ") {
+            let (name, message) = match rest.split_once(" - ") {
+                Some((n, m)) => (n.trim(), Some(m.trim().to_string())),
+                None => (rest.trim(), None),
+            };
+            let location = name.split("::").next().map(|f| f.to_string());
+            failures.push(TestFailure { name: name.to_string(), location, message });
+        } else if is_pytest_summary_line(line) {
+            passed = count_before(line, "passed").unwrap_or(0);
+            failed = count_before(line, "failed").unwrap_or(0);
+        }
+    }
+
+    // Refine locations with "path.py:LINE: Error" lines from the tracebacks
+    for line in output.lines() {
+        let mut parts = line.splitn(3, ':');
+        let (file, line_no) = match (parts.next(), parts.next()) {
+            (Some(f), Some(n)) if f.ends_with(".py") && n.parse::<u32>().is_ok() => (f, n),
+            _ => continue,
+        };
+        for failure in failures.iter_mut() {
+            if failure.location.as_deref() == Some(file) {
+                failure.location = Some(format!("{}:{}", file, line_no));
+                break;
+            }
+        }
+    }
+
+    TestReport { framework: "pytest".to_string(), passed, failed, failures }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cargo_failures() {
+        let output = "running 3 tests\n\
+            test a::ok ... ok\n\
+            test a::bad ... FAILED\n\
+            \n\
+            failures:\n\
+            \n\
+            ---- a::bad stdout ----\n\
+            thread 'a::bad' panicked at src/a.rs:10:5:\n\
+            assertion failed: false\n\
+            \n\
+            test result: FAILED. 2 passed; 1 failed; 0 ignored\n";
+        let report = parse_test_output(output).unwrap();
+        assert_eq!(report.framework, "cargo");
+        assert_eq!(report.passed, 2);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].name, "a::bad");
+        assert_eq!(report.failures[0].location.as_deref(), Some("src/a.rs:10:5"));
+        assert_eq!(report.failures[0].message.as_deref(), Some("assertion failed: false"));
+    }
+
+    #[test]
+    fn parses_jest_failures() {
+        let output = "FAIL src/sum.test.js\n  \
+            ● sum › adds numbers\n\n    \
+            expect(received).toBe(expected)\n\n      \
+            at Object.<anonymous> (src/sum.test.js:4:15)\n\n\
+            Tests:       1 failed, 3 passed, 4 total\n";
+        let report = parse_test_output(output).unwrap();
+        assert_eq!(report.framework, "jest");
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.passed, 3);
+        assert_eq!(report.failures[0].name, "sum › adds numbers");
+        assert_eq!(report.failures[0].location.as_deref(), Some("src/sum.test.js:4:15"));
+    }
+
+    #[test]
+    fn parses_pytest_failures() {
+        let output = "tests/test_x.py:12: AssertionError\n\
+            =========================== short test summary info ============================\n\
+            FAILED tests/test_x.py::test_add - assert 1 == 2\n\
+            ========================= 1 failed, 4 passed in 0.12s ==========================\n";
+        let report = parse_test_output(output).unwrap();
+        assert_eq!(report.framework, "pytest");
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.passed, 4);
+        assert_eq!(report.failures[0].name, "tests/test_x.py::test_add");
+        assert_eq!(report.failures[0].location.as_deref(), Some("tests/test_x.py:12"));
+        assert_eq!(report.failures[0].message.as_deref(), Some("assert 1 == 2"));
+    }
+
+    #[test]
+    fn ignores_non_test_output() {
+        assert!(parse_test_output("error[E0308]: mismatched types").is_none());
+    }
+}
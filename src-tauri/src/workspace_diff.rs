@@ -0,0 +1,266 @@
+//! Everything a session changed in its repository, for reviewing a session
+//! as a whole rather than tool call by tool call. The first time Horseman
+//! spawns a session, the working tree (uncommitted and untracked files
+//! included) is written to a git tree object through a scratch index; the
+//! review diff is that tree against one written the same way now. Nothing
+//! is committed and the repository's own index is left alone; the tree is
+//! kept from `git gc` by `refs/horseman/sessions/<session id>` until the
+//! session is deleted.
+
+use crate::claude::raw_log::safe_file_stem;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Longest patch returned per file; the rest is cut off
+const MAX_FILE_PATCH_CHARS: usize = 100_000;
+
+/// The working tree as it was when a session started
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSnapshot {
+    /// Top-level directory of the worktree
+    pub worktree: String,
+    /// Tree object holding the working tree's contents
+    pub tree: String,
+    /// Commit checked out at the time
+    pub head_commit: Option<String>,
+    /// RFC 3339
+    pub taken_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChange {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    /// Relative to the worktree
+    pub path: String,
+    /// Previous path of a renamed file
+    pub old_path: Option<String>,
+    pub change: FileChange,
+    /// None for binary files
+    pub additions: Option<u64>,
+    pub deletions: Option<u64>,
+    pub patch: String,
+    /// Whether `patch` was cut at `MAX_FILE_PATCH_CHARS`
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDiff {
+    pub claude_session_id: String,
+    pub snapshot: WorkspaceSnapshot,
+    pub files: Vec<FileDiff>,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+fn git(worktree: &str, index: Option<&Path>, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    command.args(["-C", worktree]).args(args);
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    let output = command.output().map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Removes the scratch index however `write_tree` returns
+struct ScratchIndex(PathBuf);
+
+impl Drop for ScratchIndex {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Write the working tree, untracked files included and ignored ones left
+/// out, to a tree object. Starts from a copy of the repository's index so
+/// unchanged files aren't hashed again.
+fn write_tree(worktree: &str) -> Result<String, String> {
+    let index = ScratchIndex(std::env::temp_dir().join(format!("horseman-index-{}", uuid::Uuid::new_v4())));
+    if let Ok(real_index) = git(worktree, None, &["rev-parse", "--git-path", "index"]) {
+        let _ = fs::copy(Path::new(worktree).join(real_index.trim()), &index.0);
+    }
+    git(worktree, Some(&index.0), &["add", "--all", "--", "."])?;
+    Ok(git(worktree, Some(&index.0), &["write-tree"])?.trim().to_string())
+}
+
+/// Snapshot the working tree around `dir`, None outside a git repository
+pub fn capture(dir: &str) -> Option<WorkspaceSnapshot> {
+    let worktree = git(dir, None, &["rev-parse", "--show-toplevel"]).ok()?.trim().to_string();
+    let tree = write_tree(&worktree).ok()?;
+    Some(WorkspaceSnapshot {
+        head_commit: git(&worktree, None, &["rev-parse", "HEAD"]).ok().map(|h| h.trim().to_string()),
+        worktree,
+        tree,
+        taken_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+fn pin_ref(session_id: &str) -> Result<String, String> {
+    Ok(format!("refs/horseman/sessions/{}", safe_file_stem(session_id)?))
+}
+
+/// Keep the snapshot's tree alive for as long as the session exists
+pub fn pin(snapshot: &WorkspaceSnapshot, session_id: &str) -> Result<(), String> {
+    git(&snapshot.worktree, None, &["update-ref", &pin_ref(session_id)?, &snapshot.tree]).map(|_| ())
+}
+
+/// Let git collect the snapshot's tree again
+pub fn unpin(snapshot: &WorkspaceSnapshot, session_id: &str) -> Result<(), String> {
+    git(&snapshot.worktree, None, &["update-ref", "-d", &pin_ref(session_id)?]).map(|_| ())
+}
+
+/// A line of `git diff --numstat`; counts are None for binary files
+struct Numstat {
+    additions: Option<u64>,
+    deletions: Option<u64>,
+    path: String,
+    old_path: Option<String>,
+}
+
+/// Entries of `git diff --numstat -z`
+fn parse_numstat(output: &str) -> Vec<Numstat> {
+    let mut entries = Vec::new();
+    let mut fields = output.split('\0');
+    while let Some(field) = fields.next() {
+        let mut parts = field.splitn(3, '\t');
+        let (additions, deletions, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(a), Some(d), Some(path)) => (a.parse().ok(), d.parse().ok(), path),
+            _ => continue,
+        };
+        // Renames leave the path empty and follow with old and new paths
+        let (path, old_path) = if path.is_empty() {
+            let old = fields.next().unwrap_or_default().to_string();
+            (fields.next().unwrap_or_default().to_string(), Some(old))
+        } else {
+            (path.to_string(), None)
+        };
+        entries.push(Numstat {
+            additions,
+            deletions,
+            path,
+            old_path,
+        });
+    }
+    entries
+}
+
+/// A full patch split into one patch per file, in order
+fn split_patch(patch: &str) -> Vec<&str> {
+    let mut starts: Vec<usize> = patch.match_indices("\ndiff --git ").map(|(i, _)| i + 1).collect();
+    if patch.starts_with("diff --git ") {
+        starts.insert(0, 0);
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| &patch[*start..starts.get(i + 1).copied().unwrap_or(patch.len())])
+        .collect()
+}
+
+fn change_of(patch: &str, old_path: Option<&str>) -> FileChange {
+    let header = patch.split("\n@@").next().unwrap_or_default();
+    if old_path.is_some() {
+        FileChange::Renamed
+    } else if header.contains("\nnew file mode") {
+        FileChange::Added
+    } else if header.contains("\ndeleted file mode") {
+        FileChange::Deleted
+    } else {
+        FileChange::Modified
+    }
+}
+
+fn truncate(patch: &str) -> (String, bool) {
+    if patch.len() <= MAX_FILE_PATCH_CHARS {
+        return (patch.to_string(), false);
+    }
+    let mut end = MAX_FILE_PATCH_CHARS;
+    while !patch.is_char_boundary(end) {
+        end -= 1;
+    }
+    (patch[..end].to_string(), true)
+}
+
+fn file_diffs(numstat: &str, patch: &str) -> Vec<FileDiff> {
+    let patches = split_patch(patch);
+    parse_numstat(numstat)
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let file_patch = patches.get(i).copied().unwrap_or_default();
+            let (patch, truncated) = truncate(file_patch);
+            FileDiff {
+                change: change_of(file_patch, entry.old_path.as_deref()),
+                path: entry.path,
+                old_path: entry.old_path,
+                additions: entry.additions,
+                deletions: entry.deletions,
+                patch,
+                truncated,
+            }
+        })
+        .collect()
+}
+
+/// What changed in the worktree since `snapshot` was taken
+pub fn diff(claude_session_id: &str, snapshot: WorkspaceSnapshot) -> Result<WorkspaceDiff, String> {
+    let worktree = snapshot.worktree.as_str();
+    git(worktree, None, &["cat-file", "-e", &snapshot.tree])
+        .map_err(|_| format!("The snapshot of {} is no longer in the repository", worktree))?;
+    let current = write_tree(worktree)?;
+    let diff_args = |extra: &[&'static str]| -> Vec<&str> {
+        let mut args = vec!["diff", "--no-color", "--no-ext-diff", "-M"];
+        args.extend_from_slice(extra);
+        args.extend([snapshot.tree.as_str(), current.as_str()]);
+        args
+    };
+    let numstat = git(worktree, None, &diff_args(&["--numstat", "-z"]))?;
+    let patch = git(worktree, None, &diff_args(&[]))?;
+    let files = file_diffs(&numstat, &patch);
+    Ok(WorkspaceDiff {
+        claude_session_id: claude_session_id.to_string(),
+        additions: files.iter().filter_map(|f| f.additions).sum(),
+        deletions: files.iter().filter_map(|f| f.deletions).sum(),
+        snapshot,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_patch_by_file() {
+        let numstat = "1\t1\tsrc/main.rs\0-\t-\tlogo.png\0\
+                       0\t0\t\0old.rs\0new.rs\0";
+        let patch = "diff --git a/src/main.rs b/src/main.rs\nindex 1..2 100644\n--- a/src/main.rs\n\
+                     +++ b/src/main.rs\n@@ -1 +1 @@\n-a\n+b\n\
+                     diff --git a/logo.png b/logo.png\nnew file mode 100644\nBinary files differ\n\
+                     diff --git a/old.rs b/new.rs\nsimilarity index 100%\nrename from old.rs\nrename to new.rs\n";
+        let files = file_diffs(numstat, patch);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].path, "src/main.rs");
+        assert_eq!((files[0].change, files[0].additions), (FileChange::Modified, Some(1)));
+        assert!(files[0].patch.ends_with("+b\n"));
+        assert_eq!((files[1].change, files[1].additions), (FileChange::Added, None));
+        assert_eq!(files[2].path, "new.rs");
+        assert_eq!((files[2].old_path.as_deref(), files[2].change), (Some("old.rs"), FileChange::Renamed));
+    }
+}
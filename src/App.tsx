@@ -1,5 +1,6 @@
 import { useState, useEffect, useCallback } from 'react'
 import { open } from '@tauri-apps/plugin-dialog'
+import { listen } from '@tauri-apps/api/event'
 import { nanoid } from 'nanoid'
 import { useHorsemanEvents } from '@/hooks/useHorsemanEvents'
 import { useUpdater } from '@/hooks/useUpdater'
@@ -14,7 +15,7 @@ import {
   useSessions,
 } from '@/store/selectors'
 import { ipc, DiscoveredSession, TranscriptMessage } from '@/lib/ipc'
-import type { FileBlock, Message, Session } from '@/domain'
+import type { BackendEvent, FileBlock, Message, Session } from '@/domain'
 import { AppLayout, Sidebar, TabBar } from '@/components/layout'
 import { ChatView } from '@/components/chat'
 import { MessageSquare } from 'lucide-react'
@@ -124,7 +125,10 @@ function App() {
   useEffect(() => {
     const loadSessions = async () => {
       try {
-        const sessions = await ipc.sessions.listAll()
+        const { sessions, slow_storage } = await ipc.sessions.listAll()
+        if (slow_storage) {
+          console.warn('Projects directory is slow to read; session list may be incomplete')
+        }
         setDiscoveredSessions(sessions)
       } catch (e) {
         console.error('Failed to load discovered sessions:', e)
@@ -135,6 +139,140 @@ function App() {
     loadSessions()
   }, [])
 
+  // Bring back sessions that were open when the app last quit
+  useEffect(() => {
+    const restore = async () => {
+      try {
+        // Persisted tabs load asynchronously; wait so they aren't duplicated
+        if (!useStore.persist.hasHydrated()) {
+          await new Promise<void>((resolve) => {
+            const unsub = useStore.persist.onFinishHydration(() => {
+              unsub()
+              resolve()
+            })
+          })
+        }
+        const restored = await ipc.claude.restoreSessions()
+        const { sessions: known, hiddenSessionIds } = useStore.getState()
+        for (const rs of restored) {
+          if (known[rs.ui_session_id] || hiddenSessionIds.includes(rs.ui_session_id)) continue
+          addSession({
+            id: rs.ui_session_id,
+            name: rs.claude_session_id.slice(0, 8),
+            workingDirectory: rs.working_directory,
+            createdAt: rs.last_active_at,
+            lastActiveAt: rs.last_active_at,
+            status: 'idle',
+            permissionMode: 'default',
+            claudeSessionId: rs.claude_session_id,
+          })
+          if (rs.transcript_path) {
+            await loadTranscriptForSession(rs.ui_session_id, rs.transcript_path)
+          }
+        }
+      } catch (e) {
+        console.error('Failed to restore sessions:', e)
+      }
+    }
+    restore()
+  }, []) // eslint-disable-line react-hooks/exhaustive-deps
+
+  // Apply watcher updates to the discovered list instead of re-listing
+  useEffect(() => {
+    const unlisten = listen<BackendEvent>('horseman-event', ({ payload }) => {
+      switch (payload.type) {
+        case 'sessions.added':
+        case 'sessions.updated': {
+          const { session } = payload
+          setDiscoveredSessions((prev) =>
+            [session, ...prev.filter((s) => s.transcript_path !== session.transcript_path)]
+              .sort((a, b) => b.modified_at_ms - a.modified_at_ms)
+          )
+          break
+        }
+        case 'memory.changed': {
+          if (!payload.promptRestart) break
+          const { affectedSessions } = payload
+          const file = payload.path.split('/').pop()
+          toast.info(`${file} changed`, {
+            description: `${affectedSessions.length} running session(s) still use the old version.`,
+            duration: 10000,
+            action: {
+              label: 'Restart',
+              onClick: () => {
+                for (const id of affectedSessions) {
+                  ipc.claude.restart(id).catch((e) => console.error('Failed to restart session:', e))
+                }
+              },
+            },
+          })
+          break
+        }
+        case 'sessions.removed':
+          setDiscoveredSessions((prev) =>
+            prev.filter((s) => s.transcript_path !== payload.session.transcript_path)
+          )
+          break
+        case 'session.deleted':
+          setDiscoveredSessions((prev) => prev.filter((s) => s.id !== payload.claudeSessionId))
+          break
+        case 'transcript.appended': {
+          // A tailed transcript grew (e.g. the CLI is running it in a terminal)
+          const { sessions, addMessage, updateMessage, setMessages, updateSession } = useStore.getState()
+          const messages = payload.messages.map(normalizeTranscriptMessage)
+          for (const [id, state] of Object.entries(sessions)) {
+            if (id !== payload.claudeSessionId && state.session.claudeSessionId !== payload.claudeSessionId) continue
+            if (payload.reset) {
+              setMessages(id, messages)
+            } else {
+              for (const message of messages) {
+                if (state.messageIndexById[message.id] != null) {
+                  updateMessage(id, message.id, message)
+                } else {
+                  addMessage(id, message)
+                }
+              }
+            }
+            const updates: Partial<Session> = {}
+            if (payload.todos) updates.currentTodos = payload.todos
+            if (payload.usage) updates.usage = payload.usage
+            if (payload.totalCostUsd !== null) updates.totalCostUsd = payload.totalCostUsd
+            if (Object.keys(updates).length > 0) updateSession(id, updates)
+          }
+          break
+        }
+        case 'session.summarized':
+          toast('Session summary ready', {
+            description: payload.summary.commitMessage.split('\n')[0],
+            action: {
+              label: 'Copy commit message',
+              onClick: () => {
+                navigator.clipboard.writeText(payload.summary.commitMessage).catch(console.error)
+              },
+            },
+          })
+          break
+        case 'connectivity.changed':
+          if (payload.online) {
+            toast.dismiss('offline')
+            toast.success('Back online', {
+              description: payload.queuedSends > 0 ? `Sending ${payload.queuedSends} queued message(s).` : undefined,
+            })
+          } else {
+            toast.warning('Offline', {
+              description: 'Messages will be queued until the connection returns.',
+              duration: Infinity,
+              id: 'offline',
+            })
+          }
+          break
+      }
+    })
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
   // Tab management
   const openTabIds = useStore((s) => s.openTabIds)
   const closeTab = useStore((s) => s.closeTab)
@@ -337,10 +475,10 @@ function App() {
       // For discovered sessions, the session ID IS the Claude session ID
       addSession({
         id: ds.id,
-        name: ds.first_message?.slice(0, 30) || ds.id.slice(0, 8),
+        name: (ds.custom_title ?? ds.title ?? ds.first_message)?.slice(0, 30) || ds.id.slice(0, 8),
         workingDirectory: ds.working_directory,
-        createdAt: ds.modified_at,
-        lastActiveAt: ds.modified_at,
+        createdAt: new Date(ds.modified_at_ms).toISOString(),
+        lastActiveAt: new Date(ds.modified_at_ms).toISOString(),
         status: 'idle',
         permissionMode: 'default',
         claudeSessionId: ds.id,  // Discovered sessions already have a Claude ID
@@ -355,6 +493,19 @@ function App() {
     }
   }, [sessions, addSession, setActiveSession, loadTranscriptForSession])
 
+  // Follow the transcript of an idle CLI-started session so it updates live
+  // if the CLI is still writing it elsewhere
+  const tailedTranscriptPath = activeSession?.isDiscovered && activeSession.status === 'idle'
+    ? discoveredSessions.find((ds) => ds.id === activeSession.claudeSessionId)?.transcript_path
+    : undefined
+  useEffect(() => {
+    if (!tailedTranscriptPath) return
+    ipc.sessions.tailTranscript(tailedTranscriptPath).catch((e) => console.error('Failed to tail transcript:', e))
+    return () => {
+      ipc.sessions.untailTranscript(tailedTranscriptPath).catch(console.error)
+    }
+  }, [tailedTranscriptPath])
+
   // Rename session
   const handleRenameSession = useCallback((id: string, newName: string) => {
     updateSession(id, { name: newName })
@@ -363,6 +514,7 @@ function App() {
   // Delete session (UI only, doesn't touch transcript files)
   const handleDeleteSession = useCallback((id: string) => {
     removeSession(id)
+    ipc.claude.remove(id).catch((e) => console.error('Failed to forget session:', e))
   }, [removeSession])
 
   // Send message to Claude
@@ -6,10 +6,10 @@
  */
 import { useState, useMemo, memo, useEffect, useCallback } from 'react'
 import { ChevronDown, ChevronRight, X, FileText, AtSign, Loader2, Folder } from 'lucide-react'
-import { readTextFile } from '@tauri-apps/plugin-fs'
 import { CodeDisplay } from './CodeDisplay'
 import { usePreHighlight } from '@/hooks/usePreHighlight'
 import type { PendingFile } from '@/domain'
+import { ipc } from '@/lib/ipc'
 import { cn } from '@/lib/utils'
 
 interface FilePillProps {
@@ -69,7 +69,7 @@ export const FilePill = memo(function FilePill({
     if (file.isReference && !file.isDirectory && fullPath && loadedContent === null && !isLoadingContent) {
       setIsLoadingContent(true)
       setLoadError(null)
-      readTextFile(fullPath)
+      ipc.files.readText(fullPath)
         .then((fileContent) => {
           setLoadedContent(fileContent)
         })
@@ -6,8 +6,8 @@
  */
 import { useState, useEffect, useMemo, memo } from 'react'
 import { ChevronDown, ChevronRight, FileCode, Loader2 } from 'lucide-react'
-import { readTextFile } from '@tauri-apps/plugin-fs'
 import { CodeDisplay } from './CodeDisplay'
+import { ipc } from '@/lib/ipc'
 import { cn } from '@/lib/utils'
 
 interface FileRefDisplayProps {
@@ -35,7 +35,7 @@ export const FileRefDisplay = memo(function FileRefDisplay({
     setIsLoading(true)
     setError(null)
 
-    readTextFile(fullPath)
+    ipc.files.readText(fullPath)
       .then(setContent)
       .catch((err) => {
         console.error('Failed to read file:', fullPath, err)
@@ -6,6 +6,7 @@ import {
   MessageAction,
 } from '@/components/ai-elements/message'
 import { Shimmer } from '@/components/ai-elements/shimmer'
+import { Reasoning, ReasoningContent, ReasoningTrigger } from '@/components/ai-elements/reasoning'
 import { ToolDisplay } from './ToolDisplay'
 import { FileBlockDisplay } from './FileBlockDisplay'
 import { HighlightableContent } from './HighlightableContent'
@@ -121,6 +122,7 @@ const MessageItem = memo(function MessageItem({
   }
 
   const hasText = !!message.text?.trim()
+  const hasThinking = !!message.thinking?.trim()
   const hasFileBlocks = (message.fileBlocks?.length ?? 0) > 0
   const topLevelTools = (message.toolCalls || []).filter((tool) => {
     if (tool.parentToolId) return false
@@ -167,6 +169,14 @@ const MessageItem = memo(function MessageItem({
         </div>
       )}
 
+      {/* Extended thinking - collapsed above the reply */}
+      {hasThinking && message.role === 'assistant' && (
+        <Reasoning isStreaming={isMessageStreaming} defaultOpen={false}>
+          <ReasoningTrigger />
+          <ReasoningContent>{message.thinking!}</ReasoningContent>
+        </Reasoning>
+      )}
+
       {/* Message bubble - only show if there's text content */}
       {(hasCleanText || (hasText && message.role !== 'user')) && (
         <Message from={message.role}>
@@ -283,7 +293,8 @@ export function MessageList({
         return true
       })
       const hasTools = topLevelTools.length > 0
-      return hasText || hasTools || hasFileBlocks
+      const hasThinking = !!message.thinking?.trim()
+      return hasText || hasThinking || hasTools || hasFileBlocks
     }),
     [messages, hasRunningTask]
   )
@@ -1,6 +1,7 @@
-import { memo } from 'react'
+import { memo, useEffect, useState } from 'react'
 import { useStore } from '@/store'
 import type { ModelAlias } from '@/store/types'
+import { ipc } from '@/lib/ipc'
 
 const MODEL_DISPLAY: Record<ModelAlias, string> = {
   sonnet: 'Sonnet 4.5',
@@ -17,6 +18,14 @@ const MODEL_COLORS: Record<ModelAlias, string> = {
 export const ModelBadge = memo(function ModelBadge() {
   const model = useStore((s) => s.model)
   const cycleModel = useStore((s) => s.cycleModel)
+  const [displayNames, setDisplayNames] = useState<Record<string, string>>({})
+
+  useEffect(() => {
+    ipc.models
+      .list()
+      .then((models) => setDisplayNames(Object.fromEntries(models.map((m) => [m.id, m.displayName]))))
+      .catch(() => {})
+  }, [])
 
   return (
     <button
@@ -25,7 +34,7 @@ export const ModelBadge = memo(function ModelBadge() {
       className={`px-2 py-0.5 rounded-full text-[11px] font-medium transition-all hover:opacity-80 active:scale-95 ${MODEL_COLORS[model]}`}
       title="Click to switch model"
     >
-      {MODEL_DISPLAY[model]}
+      {displayNames[model] ?? MODEL_DISPLAY[model]}
     </button>
   )
 })
@@ -26,6 +26,9 @@ import { Plus, Settings, FolderOpen, Loader2, MessageSquare, ChevronRight, Chevr
 import type { DiscoveredSession } from '@/lib/ipc'
 import type { Session } from '@/domain'
 
+/** Discovered sessions this close to Claude's cleanup get a warning */
+const EXPIRY_WARNING_DAYS = 7
+
 // Truncate name to max length with ellipsis
 const truncateName = (name: string, max = 20) =>
   name.length > max ? name.slice(0, max) + '...' : name
@@ -37,6 +40,8 @@ interface SessionItemProps {
     name: string
     fullName: string // untruncated name for editing
     date: string
+    /** Epoch millis of last activity, used for ordering */
+    timestamp: number
     status: SessionStatus
     isDiscovered: boolean
     discoveredSession?: DiscoveredSession
@@ -70,6 +75,7 @@ const SessionItem = memo(function SessionItem({
   onDelete,
 }: SessionItemProps) {
   const inputRef = useRef<HTMLInputElement>(null)
+  const expiresInDays = session.discoveredSession?.expires_in_days
 
   // Focus input when editing starts
   useEffect(() => {
@@ -162,6 +168,12 @@ const SessionItem = memo(function SessionItem({
           </div>
           <div className="text-xs text-muted-foreground/70">
             {formatDate(session.date)}
+            {expiresInDays != null && expiresInDays <= EXPIRY_WARNING_DAYS && (
+              <span className="text-amber-600 dark:text-amber-400">
+                {' · '}
+                {expiresInDays <= 0 ? 'Claude deletes it on next start' : `Claude deletes it in ${expiresInDays}d`}
+              </span>
+            )}
           </div>
         </button>
       </ContextMenuTrigger>
@@ -258,6 +270,7 @@ function groupByProject(
       name: truncateName(session.name),
       fullName: session.name,
       date: session.lastActiveAt,
+      timestamp: Date.parse(session.lastActiveAt) || 0,
       status: session.status,
       isDiscovered: session.isDiscovered || false,
       claudeSessionId: session.claudeSessionId,
@@ -285,19 +298,20 @@ function groupByProject(
         sessions: [],
       })
     }
-    const dsName = ds.first_message || ds.id.slice(0, 8)
+    const dsName = ds.custom_title || ds.title || ds.first_message || ds.id.slice(0, 8)
     sessionStatusMap.set(ds.id, 'idle') // Discovered sessions default to idle
     groups.get(path)!.sessions.push({
       id: ds.id,
       name: truncateName(dsName),
       fullName: dsName,
       date: ds.modified_at,
+      timestamp: ds.modified_at_ms,
       status: 'idle',
       isDiscovered: true,
       discoveredSession: ds,
       claudeSessionId: ds.id, // For discovered sessions, the id IS the Claude session ID
       workingDirectory: ds.working_directory,
-      createdAt: ds.modified_at, // Use modified_at as best approximation
+      createdAt: new Date(ds.modified_at_ms).toISOString(), // Best approximation
     })
   }
 
@@ -320,16 +334,16 @@ function groupByProject(
         const bMinPriority = Math.min(...b.sessions.map((s) => STATUS_PRIORITY[sessionStatusMap.get(s.id) || 'idle'] ?? 3))
         if (aMinPriority !== bMinPriority) return aMinPriority - bMinPriority
         // Tie-breaker: most recent
-        const aLatest = a.sessions.reduce((max, s) => (s.date > max ? s.date : max), '')
-        const bLatest = b.sessions.reduce((max, s) => (s.date > max ? s.date : max), '')
-        return bLatest.localeCompare(aLatest)
+        const aLatest = a.sessions.reduce((max, s) => Math.max(max, s.timestamp), 0)
+        const bLatest = b.sessions.reduce((max, s) => Math.max(max, s.timestamp), 0)
+        return bLatest - aLatest
       })
     } else {
       // 'recent' - default
       groupList.sort((a, b) => {
-        const aLatest = a.sessions.reduce((max, s) => (s.date > max ? s.date : max), '')
-        const bLatest = b.sessions.reduce((max, s) => (s.date > max ? s.date : max), '')
-        return bLatest.localeCompare(aLatest)
+        const aLatest = a.sessions.reduce((max, s) => Math.max(max, s.timestamp), 0)
+        const bLatest = b.sessions.reduce((max, s) => Math.max(max, s.timestamp), 0)
+        return bLatest - aLatest
       })
     }
   }
@@ -347,10 +361,10 @@ function groupByProject(
           const aPriority = STATUS_PRIORITY[sessionStatusMap.get(a.id) || 'idle'] ?? 3
           const bPriority = STATUS_PRIORITY[sessionStatusMap.get(b.id) || 'idle'] ?? 3
           if (aPriority !== bPriority) return aPriority - bPriority
-          return b.date.localeCompare(a.date)
+          return b.timestamp - a.timestamp
         })
       } else {
-        group.sessions.sort((a, b) => b.date.localeCompare(a.date))
+        group.sessions.sort((a, b) => b.timestamp - a.timestamp)
       }
     }
   }
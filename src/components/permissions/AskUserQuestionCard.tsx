@@ -26,6 +26,9 @@ type FormAction =
   | { type: 'SET_ACTIVE_QUESTION'; index: number }
   | { type: 'RESET' }
 
+// Backend default (permissionTimeoutSecs), until its first countdown event
+const DEFAULT_TIMEOUT_SECS = 170
+
 const initialFormState: FormState = {
   answers: {},
   otherInputs: {},
@@ -74,7 +77,12 @@ export function AskUserQuestionCard({ question, queueTotal = 1 }: AskUserQuestio
   const { answers, otherInputs, isSubmitting, activeQuestionIndex } = formState
 
   // Timer kept separate (high-frequency updates)
-  const [secondsLeft, setSecondsLeft] = useState(170)
+  const [secondsLeft, setSecondsLeft] = useState(question.secondsLeft ?? DEFAULT_TIMEOUT_SECS)
+
+  // Resync with the backend's permission.expiring countdown
+  useEffect(() => {
+    if (question.secondsLeft !== undefined) setSecondsLeft(question.secondsLeft)
+  }, [question.secondsLeft])
 
   // Countdown timer
   useEffect(() => {
@@ -1,8 +1,8 @@
 // Permission card UI for tool approval requests
 // Shows approve/deny buttons for tools requiring user authorization
-import { useState, useEffect, useCallback, useRef } from 'react'
+import { useState, useEffect, useCallback, useMemo } from 'react'
 import { Button } from '@/components/ui/button'
-import { Check, X, ShieldAlert } from 'lucide-react'
+import { Check, X, ShieldAlert, Pencil } from 'lucide-react'
 import { ipc } from '@/lib/ipc'
 import { useStore } from '@/store'
 import type { PendingPermission } from '@/store/types'
@@ -22,6 +22,30 @@ function formatToolInput(input: Record<string, unknown>): string {
   return JSON.stringify(input, null, 2)
 }
 
+// Backend default (permissionTimeoutSecs), until its first countdown event
+const DEFAULT_TIMEOUT_SECS = 170
+
+// Tools that can be approved for a whole directory
+const PATH_SCOPED_TOOLS = ['Read', 'Edit', 'MultiEdit', 'Write', 'NotebookEdit']
+
+function getTargetDirectory(toolName: string, input: Record<string, unknown>): string | null {
+  if (!PATH_SCOPED_TOOLS.includes(toolName)) return null
+  const path = input.file_path ?? input.notebook_path
+  if (typeof path !== 'string' || !path.startsWith('/')) return null
+  const slash = path.lastIndexOf('/')
+  return slash > 0 ? path.slice(0, slash) : null
+}
+
+// The input field the user can edit before approving
+function getEditableField(toolName: string, input: Record<string, unknown>): string | null {
+  const field = toolName === 'Bash'
+    ? 'command'
+    : PATH_SCOPED_TOOLS.includes(toolName)
+      ? (input.notebook_path !== undefined ? 'notebook_path' : 'file_path')
+      : null
+  return field && typeof input[field] === 'string' ? field : null
+}
+
 function getToolVerb(toolName: string): string {
   switch (toolName) {
     case 'Edit': return 'edit'
@@ -38,7 +62,17 @@ export function PermissionCard({ permission, queueTotal }: PermissionCardProps)
   const removePendingPermission = useStore((s) => s.removePendingPermission)
   const appendSessionEvent = useStore((s) => s.appendSessionEvent)
   const [isProcessing, setIsProcessing] = useState(false)
-  const [secondsLeft, setSecondsLeft] = useState(170)
+  const [secondsLeft, setSecondsLeft] = useState(permission.secondsLeft ?? DEFAULT_TIMEOUT_SECS)
+  // Edited value of the editable field; null while not editing
+  const [editedValue, setEditedValue] = useState<string | null>(null)
+  const editableField = getEditableField(permission.toolName, permission.toolInput)
+  const updatedInput = useMemo(
+    () => editableField && editedValue !== null && editedValue !== permission.toolInput[editableField]
+      ? { ...permission.toolInput, [editableField]: editedValue }
+      : undefined,
+    [editableField, editedValue, permission.toolInput]
+  )
+  const effectiveInput = updatedInput ?? permission.toolInput
 
   // sessionId is now required - always use permission's session
   const eventSessionId = permission.sessionId
@@ -68,32 +102,33 @@ export function PermissionCard({ permission, queueTotal }: PermissionCardProps)
     }
   }, [permission.requestId, permission.toolName, permission.toolInput.file_path, isProcessing, removePendingPermission, eventSessionId, appendSessionEvent])
 
-  // Ref to avoid stale closure in interval
-  const handleDenyRef = useRef(handleDeny)
-  handleDenyRef.current = handleDeny
+  // Countdown timer, resynced by the backend's permission.expiring events.
+  // At zero the backend applies permissionTimeoutAction and resolves the prompt.
+  useEffect(() => {
+    if (permission.secondsLeft !== undefined) setSecondsLeft(permission.secondsLeft)
+  }, [permission.secondsLeft])
 
-  // Countdown timer
   useEffect(() => {
     const interval = setInterval(() => {
-      setSecondsLeft((prev) => {
-        if (prev <= 1) {
-          handleDenyRef.current('Timed out')
-          return 0
-        }
-        return prev - 1
-      })
+      setSecondsLeft((prev) => Math.max(prev - 1, 0))
     }, 1000)
     return () => clearInterval(interval)
   }, [permission.requestId])
 
-  const handleAllow = useCallback(async (forSession: boolean) => {
+  const handleAllow = useCallback(async (forSession: boolean, pathPrefix?: string, alwaysAllow?: boolean) => {
     if (isProcessing) return
     setIsProcessing(true)
     try {
       await ipc.permissions.respond(permission.requestId, true, {
         toolName: permission.toolName,
         allowForSession: forSession,
+        allowPathPrefix: pathPrefix,
+        updatedInput,
       })
+      if (alwaysAllow && typeof effectiveInput.command === 'string') {
+        await ipc.permissions.addBashRule(effectiveInput.command)
+          .catch((err) => console.error('Failed to save Bash rule:', err))
+      }
       removePendingPermission(permission.requestId)
 
       // Log permission event
@@ -103,20 +138,31 @@ export function PermissionCard({ permission, queueTotal }: PermissionCardProps)
           timestamp: new Date().toISOString(),
           tool: permission.toolName,
           allowed: true,
-          path: permission.toolInput.file_path as string | undefined,
+          path: effectiveInput.file_path as string | undefined,
         })
       }
     } catch (err) {
       console.error('Failed to approve permission:', err)
       setIsProcessing(false)
     }
-  }, [permission.requestId, permission.toolName, permission.toolInput.file_path, isProcessing, removePendingPermission, eventSessionId, appendSessionEvent])
+  }, [permission.requestId, permission.toolName, updatedInput, effectiveInput, isProcessing, removePendingPermission, eventSessionId, appendSessionEvent])
 
   const handleAllowOnce = useCallback(() => handleAllow(false), [handleAllow])
   const handleAllowSession = useCallback(() => handleAllow(true), [handleAllow])
+  const handleAlwaysAllow = useCallback(() => handleAllow(false, undefined, true), [handleAllow])
+  const targetDirectory = getTargetDirectory(permission.toolName, effectiveInput)
+  const handleAllowDirectory = useCallback(
+    () => targetDirectory && handleAllow(false, targetDirectory),
+    [handleAllow, targetDirectory]
+  )
   const handleDenyClick = useCallback(() => handleDeny(), [handleDeny])
 
-  const inputPreview = formatToolInput(permission.toolInput)
+  const handleToggleEdit = useCallback(() => {
+    if (!editableField) return
+    setEditedValue((prev) => (prev === null ? String(permission.toolInput[editableField]) : null))
+  }, [editableField, permission.toolInput])
+
+  const inputPreview = formatToolInput(effectiveInput)
   const isLongInput = inputPreview.length > 100
   const timerUrgent = secondsLeft <= 30
   const queueCount = queueTotal ?? 0
@@ -161,7 +207,18 @@ export function PermissionCard({ permission, queueTotal }: PermissionCardProps)
           "border border-border/50",
           isLongInput && "max-h-32 overflow-y-auto"
         )}>
-          <pre className="whitespace-pre-wrap break-all">{inputPreview}</pre>
+          {editedValue !== null ? (
+            <textarea
+              value={editedValue}
+              onChange={(e) => setEditedValue(e.target.value)}
+              disabled={isProcessing}
+              rows={Math.min(6, editedValue.split('\n').length)}
+              className="w-full resize-y bg-transparent text-foreground outline-none"
+              autoFocus
+            />
+          ) : (
+            <pre className="whitespace-pre-wrap break-all">{inputPreview}</pre>
+          )}
         </div>
 
         {/* Actions */}
@@ -188,6 +245,48 @@ export function PermissionCard({ permission, queueTotal }: PermissionCardProps)
             Allow for session
           </Button>
 
+          {permission.toolName === 'Bash' && (
+            <Button
+              variant="ghost"
+              size="sm"
+              onClick={handleAlwaysAllow}
+              disabled={isProcessing}
+              title="Allow and save a rule for commands like this one"
+              className="h-7 px-3 text-xs font-medium text-green-600 hover:text-green-700 hover:bg-green-500/10"
+            >
+              <Check className="h-3.5 w-3.5 mr-1.5" />
+              Always allow
+            </Button>
+          )}
+
+          {targetDirectory && (
+            <Button
+              variant="ghost"
+              size="sm"
+              onClick={handleAllowDirectory}
+              disabled={isProcessing}
+              title={targetDirectory}
+              className="h-7 px-3 text-xs font-medium text-green-600 hover:text-green-700 hover:bg-green-500/10"
+            >
+              <Check className="h-3.5 w-3.5 mr-1.5" />
+              Allow in {targetDirectory.split('/').pop()}/
+            </Button>
+          )}
+
+          {editableField && (
+            <Button
+              variant="ghost"
+              size="sm"
+              onClick={handleToggleEdit}
+              disabled={isProcessing}
+              title={editedValue === null ? `Edit the ${editableField.replace('_', ' ')} before allowing` : 'Discard edits'}
+              className="h-7 px-3 text-xs font-medium text-muted-foreground hover:text-foreground"
+            >
+              <Pencil className="h-3.5 w-3.5 mr-1.5" />
+              {editedValue === null ? 'Edit' : 'Reset'}
+            </Button>
+          )}
+
           <Button
             variant="ghost"
             size="sm"
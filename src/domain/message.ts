@@ -1,3 +1,5 @@
+import type { SessionUsage } from './session'
+
 export interface SubagentInfo {
   type: string
   description: string
@@ -37,6 +39,8 @@ export interface ToolCall {
   startedAt?: string
   endedAt?: string
   subagent?: SubagentInfo
+  /** Task tools: tokens of the subagent's turns */
+  usage?: SessionUsage
 }
 
 export type MessageRole = 'user' | 'assistant' | 'system'
@@ -45,10 +49,14 @@ export interface Message {
   id: string
   role: MessageRole
   text: string
+  /** Extended thinking, shown collapsed above the text */
+  thinking?: string
   toolCalls?: ToolCall[]
   isStreaming?: boolean
   timestamp: Date
   fileBlocks?: FileBlock[]
+  /** Assistant messages: tokens of the API call */
+  usage?: SessionUsage
 }
 
 export type ParsedMessage = Message
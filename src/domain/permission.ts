@@ -1,3 +1,5 @@
+export type SessionPriority = 'low' | 'normal' | 'high'
+
 export interface PendingPermission {
   requestId: string
   toolName: string
@@ -5,4 +7,20 @@ export interface PendingPermission {
   toolUseId?: string
   sessionId: string
   timestamp: number
+  priority?: SessionPriority
+  /** Latest countdown from permission.expiring */
+  secondsLeft?: number
+}
+
+export interface QueuedPrompt {
+  requestId: string
+  toolName: string
+  requestedAt: number
+}
+
+/** One session's pending prompts, as ordered by `get_permission_queue` */
+export interface PermissionLane {
+  uiSessionId: string | null
+  priority: SessionPriority
+  prompts: QueuedPrompt[]
 }
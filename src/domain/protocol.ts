@@ -1,28 +1,133 @@
 import type { Message, ToolCall } from './message'
-import type { PendingQuestion } from './question'
+import type { SessionPriority } from './permission'
+import type { PendingQuestion, SessionPendingQuestion } from './question'
 import type { TodoItem } from './todo'
-import type { SessionUsage } from './session'
+import type { DiscoveredSession, PermissionMode, SessionSummary, SessionUsage } from './session'
+
+export interface VerificationStep {
+  command: string
+  passed: boolean
+  exitCode: number | null
+  timedOut: boolean
+  durationMs: number
+  output: string
+  tests?: TestReport
+}
+
+export interface EmergencyStopReport {
+  interruptedSessions: string[]
+  killedSlashCommands: string[]
+  deniedRequests: string[]
+}
+
+export interface TestReport {
+  framework: 'cargo' | 'jest' | 'pytest'
+  passed: number
+  failed: number
+  failures: TestFailure[]
+}
+
+export interface TestFailure {
+  name: string
+  location?: string
+  message?: string
+}
+
+export type LoopStatus =
+  | 'running'
+  | 'retrying'
+  | 'passed'
+  | 'failed'
+  | 'budgetExceeded'
+  | 'noEdits'
+  | 'unverified'
+  | 'stopped'
+  | 'error'
 
 export type BackendMessage = Omit<Message, 'timestamp'> & { timestamp: string }
 
-export type BackendEvent =
-  | { type: 'session.started'; uiSessionId: string; claudeSessionId: string }
-  | { type: 'session.ended'; uiSessionId: string; exitCode: number | null; error?: string }
+/** Event schema version this frontend understands (see get_backend_schema_version) */
+export const EVENT_SCHEMA_VERSION = 2
+
+/** Why a session stopped early without an error */
+export type SessionEndReason = 'maxTurns'
+
+export type MemoryScope = 'user' | 'project'
+
+export type BudgetPeriod = 'daily' | 'monthly'
+
+export type RateLimitKind = 'rateLimited' | 'overloaded'
+
+export type BackendEvent = { schemaVersion?: number } & (
+  | { type: 'session.started'; uiSessionId: string; claudeSessionId: string; permissionMode: PermissionMode | null }
+  | { type: 'session.ended'; uiSessionId: string; exitCode: number | null; error?: string; reason?: SessionEndReason }
   | { type: 'message.user'; uiSessionId: string; message: BackendMessage }
   | { type: 'message.assistant'; uiSessionId: string; message: BackendMessage }
+  | { type: 'message.thinking'; uiSessionId: string; messageId: string; thinking: string }
+  | { type: 'message.delta'; uiSessionId: string; messageId: string; text?: string; thinking?: string }
   | { type: 'message.streaming'; uiSessionId: string; messageId: string; delta: string }
   | { type: 'tool.started'; uiSessionId: string; tool: ToolCall }
   | { type: 'tool.updated'; uiSessionId: string; toolId: string; update: Partial<ToolCall> }
-  | { type: 'tool.completed'; uiSessionId: string; toolId: string; output: string }
-  | { type: 'tool.error'; uiSessionId: string; toolId: string; error: string }
+  | { type: 'tool.completed'; uiSessionId: string; toolId: string; output: string; endedAt: string }
+  | { type: 'tool.error'; uiSessionId: string; toolId: string; error: string; endedAt: string }
   | { type: 'todos.updated'; uiSessionId: string; todos: TodoItem[] }
   | { type: 'usage.updated'; uiSessionId: string; usage: SessionUsage }
-  | { type: 'permission.requested'; requestId: string; toolName: string; toolInput: Record<string, unknown>; uiSessionId?: string }
+  | { type: 'permission.requested'; requestId: string; toolName: string; toolInput: Record<string, unknown>; uiSessionId?: string; priority: SessionPriority }
   | { type: 'permission.resolved'; requestId: string }
+  | { type: 'permission.expiring'; requestId: string; uiSessionId: string | null; secondsLeft: number }
   | { type: 'question.requested'; requestId: string; question: PendingQuestion }
   | { type: 'question.resolved'; requestId: string }
+  | { type: 'sessions.added'; session: DiscoveredSession }
+  | { type: 'sessions.updated'; session: DiscoveredSession }
+  | { type: 'session.deleted'; claudeSessionId: string; removedFiles: string[] }
+  | { type: 'session.summarized'; uiSessionId: string; summary: SessionSummary }
+  | { type: 'usage.threshold'; uiSessionId: string; level: number; contextPercent: number; remainingTurns: number | null }
+  | { type: 'session.compacting'; uiSessionId: string; contextPercent: number }
+  | { type: 'session.compacted'; uiSessionId: string; error?: string }
+  | { type: 'suggestions.updated'; uiSessionId: string; suggestions: string[]; polished: boolean }
+  | { type: 'narration'; uiSessionId?: string; text: string }
+  | { type: 'budget.warning'; period: BudgetPeriod; spentUsd: number; limitUsd: number }
+  | { type: 'budget.exceeded'; period: BudgetPeriod; spentUsd: number; limitUsd: number }
+  | { type: 'sessions.removed'; session: DiscoveredSession }
+  | {
+      type: 'transcript.appended'
+      claudeSessionId: string
+      transcriptPath: string
+      /** New or changed messages; the whole list when `reset` */
+      messages: Message[]
+      todos: TodoItem[] | null
+      usage: SessionUsage | null
+      totalCostUsd: number | null
+      reset: boolean
+    }
+  | {
+      type: 'memory.changed'
+      path: string
+      scope: MemoryScope
+      workingDirectory: string | null
+      /** Keep-alive sessions still running with the old memory */
+      affectedSessions: string[]
+      promptRestart: boolean
+    }
+  | { type: 'questions.pending_on_startup'; sessions: SessionPendingQuestion[] }
   | { type: 'slash.started'; commandId: string }
   | { type: 'slash.output'; commandId: string; data: string }
   | { type: 'slash.detected'; commandId: string; method: string }
   | { type: 'slash.completed'; commandId: string; exitCode: number | null }
   | { type: 'slash.error'; commandId: string; message: string }
+  | { type: 'verification.completed'; uiSessionId: string; passed: boolean; steps: VerificationStep[] }
+  | { type: 'workspace.conflict_risk'; uiSessionId: string; workingDirectory: string; path: string; otherSessionIds: string[] }
+  | { type: 'session.reader_crashed'; uiSessionId?: string; commandId?: string; reader: 'stdout' | 'stderr' | 'pty' | 'transcript'; message: string; fallback: boolean }
+  | { type: 'session.line_truncated'; uiSessionId: string; lineNumber: number; bytes: number; limitBytes: number; eventType: string | null; toolUseId: string | null; spillPath: string | null }
+  | { type: 'turn.completed'; uiSessionId: string }
+  | { type: 'session.suspended'; uiSessionId: string; claudeSessionId: string; idleSeconds: number }
+  | { type: 'session.queued'; uiSessionId: string; position: number; limit: number }
+  | { type: 'session.restarting'; uiSessionId: string; claudeSessionId: string; attempt: number; maxRetries: number; delayMs: number; exitCode: number | null }
+  | { type: 'session.rate_limited'; uiSessionId: string; kind: RateLimitKind; message: string; resetsAt: string | null; attempt: number | null; maxRetries: number | null; delayMs: number | null }
+  | { type: 'connectivity.changed'; online: boolean; queuedSends: number }
+  | { type: 'session.resume_failed'; uiSessionId: string; claudeSessionId: string; reason: string; primerAvailable: boolean }
+  | { type: 'mcp.integrity_failed'; path: string; reason: string }
+  | { type: 'mcp.server_skipped'; uiSessionId: string; name: string; reason: string }
+  | { type: 'emergency.stopped'; report: EmergencyStopReport }
+  | { type: 'loop.iteration'; uiSessionId: string; iteration: number; maxIterations: number; costUsd: number; status: LoopStatus }
+)
@@ -16,4 +16,15 @@ export interface PendingQuestion {
   toolUseId: string
   questions: Question[]
   timestamp: number
+  /** Latest countdown from permission.expiring */
+  secondsLeft?: number
+}
+
+/** Unanswered AskUserQuestion found in a transcript by the startup scan */
+export interface SessionPendingQuestion {
+  sessionId: string
+  workingDirectory: string
+  transcriptPath: string
+  toolUseId: string
+  questions: Question[]
 }
@@ -49,3 +49,44 @@ export interface Session {
   /** Timestamp of the last compaction event for which we injected context */
   lastCompactionInjectedAt?: string
 }
+
+/** Session found in Claude's projects directory */
+export interface DiscoveredSession {
+  id: string
+  working_directory: string
+  transcript_path: string
+  /** Local time for display; sort by modified_at_ms */
+  modified_at: string
+  /** Unix millis of the last transcript write */
+  modified_at_ms: number
+  first_message: string | null
+  /** Branch the session was started on */
+  git_branch: string | null
+  /** Default title: `branch — first prompt` */
+  title: string | null
+  /** Title the user gave the session; shown instead of `title` */
+  custom_title: string | null
+  pinned: boolean
+  tags: string[]
+  /** Days until Claude's cleanupPeriodDays deletes the transcript (<= 0: on its next start) */
+  expires_in_days: number | null
+}
+
+/** "What changed and why", written when a session's process ends */
+export interface SessionSummary {
+  claudeSessionId: string
+  workingDirectory: string
+  summary: string
+  /** Subject line, blank line, body */
+  commitMessage: string
+  diffHash: string
+  createdAt: string
+}
+
+/** User-assigned session metadata, keyed by Claude session ID */
+export interface SessionMeta {
+  claudeSessionId: string
+  title: string | null
+  pinned: boolean
+  tags: string[]
+}
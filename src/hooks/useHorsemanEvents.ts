@@ -3,6 +3,7 @@ import { listen, UnlistenFn } from '@tauri-apps/api/event'
 import { ipc, SpawnSessionArgs } from '@/lib/ipc'
 import { useStore } from '@/store'
 import { createUserMessage } from '@/lib/parseClaudeEvents'
+import { EVENT_SCHEMA_VERSION } from '@/domain'
 import type { BackendEvent, BackendMessage, FileBlock, Message } from '@/domain'
 import type { SessionState } from '@/store/types'
 
@@ -32,6 +33,8 @@ export function useHorsemanEvents({
   const addPendingQuestion = useStore((s) => s.addPendingQuestion)
   const removePendingPermission = useStore((s) => s.removePendingPermission)
   const removePendingQuestion = useStore((s) => s.removePendingQuestion)
+  const setPermissionSecondsLeft = useStore((s) => s.setPermissionSecondsLeft)
+  const setQuestionSecondsLeft = useStore((s) => s.setQuestionSecondsLeft)
   const startSlashCommand = useStore((s) => s.startSlashCommand)
   const appendSlashOutput = useStore((s) => s.appendSlashOutput)
   const setSlashDetectionMethod = useStore((s) => s.setSlashDetectionMethod)
@@ -88,16 +91,26 @@ export function useHorsemanEvents({
     }
 
     const setup = async () => {
+      // Backend and frontend hot-reload separately in dev; agree on a schema
+      await ipc.schema.negotiate(EVENT_SCHEMA_VERSION).catch((err) => {
+        console.error('Event schema mismatch:', err)
+        setError(String(err))
+      })
+
       const unlistenFn = await listen<BackendEvent>('horseman-event', (event) => {
         if (!isMounted) return
 
         const payload = event.payload
+        if (payload.schemaVersion !== undefined && payload.schemaVersion !== EVENT_SCHEMA_VERSION) {
+          console.warn(`Backend event schema v${payload.schemaVersion}, expected v${EVENT_SCHEMA_VERSION}`)
+        }
 
         switch (payload.type) {
           case 'session.started': {
             updateSession(payload.uiSessionId, {
               claudeSessionId: payload.claudeSessionId,
               status: 'running',
+              ...(payload.permissionMode && { permissionMode: payload.permissionMode }),
             })
             if (payload.uiSessionId === uiSessionIdRef.current) {
               activeClaudeSessionRef.current = payload.claudeSessionId
@@ -118,10 +131,32 @@ export function useHorsemanEvents({
               markStreamingComplete(uiSessionIdRef.current)
               if (payload.error) {
                 setError(payload.error)
+              } else if (payload.reason === 'maxTurns') {
+                setError('Stopped: turn limit reached. Send a message to continue.')
               }
             }
             break
           }
+          case 'turn.completed': {
+            // Keep-alive sessions stay up between turns, so there is no session.ended
+            completeAllRunningTools(payload.uiSessionId)
+            updateSession(payload.uiSessionId, { status: 'idle' })
+            if (payload.uiSessionId === uiSessionIdRef.current) {
+              setIsStreaming(false)
+              markStreamingComplete(uiSessionIdRef.current)
+            }
+            break
+          }
+          case 'session.restarting': {
+            // Process died mid-turn; the backend resumes it after a backoff
+            completeAllRunningTools(payload.uiSessionId)
+            updateSession(payload.uiSessionId, { status: 'running' })
+            if (payload.uiSessionId === uiSessionIdRef.current) {
+              const code = payload.exitCode === null ? 'a signal' : `code ${payload.exitCode}`
+              setError(`Claude exited unexpectedly (${code}). Restarting (${payload.attempt}/${payload.maxRetries})...`)
+            }
+            break
+          }
           case 'message.assistant': {
             const message = normalizeMessage(payload.message)
             addMessage(payload.uiSessionId, message)
@@ -132,6 +167,53 @@ export function useHorsemanEvents({
             }
             break
           }
+          case 'message.delta': {
+            const sessionState = useStore.getState().sessions[payload.uiSessionId]
+            const index = sessionState?.messageIndexById[payload.messageId]
+            const existing = index != null ? sessionState.messages[index] : undefined
+            if (existing) {
+              updateMessage(payload.uiSessionId, payload.messageId, {
+                text: existing.text + (payload.text ?? ''),
+                ...(payload.thinking ? { thinking: (existing.thinking ?? '') + payload.thinking } : {}),
+              })
+            } else {
+              addMessage(payload.uiSessionId, {
+                id: payload.messageId,
+                role: 'assistant',
+                text: payload.text ?? '',
+                thinking: payload.thinking,
+                isStreaming: true,
+                timestamp: new Date(),
+              })
+              updateSession(payload.uiSessionId, { status: 'running' })
+            }
+            if (payload.uiSessionId === uiSessionIdRef.current) {
+              lastAssistantMessageIdRef.current = payload.messageId
+              setIsStreaming(true)
+            }
+            break
+          }
+          case 'message.thinking': {
+            const sessionState = useStore.getState().sessions[payload.uiSessionId]
+            const index = sessionState?.messageIndexById[payload.messageId]
+            const existing = index != null ? sessionState.messages[index] : undefined
+            if (existing) {
+              updateMessage(payload.uiSessionId, payload.messageId, {
+                thinking: (existing.thinking ?? '') + payload.thinking,
+              })
+            } else {
+              addMessage(payload.uiSessionId, {
+                id: payload.messageId,
+                role: 'assistant',
+                text: '',
+                thinking: payload.thinking,
+                isStreaming: true,
+                timestamp: new Date(),
+              })
+            }
+            updateSession(payload.uiSessionId, { status: 'running' })
+            break
+          }
           case 'message.user':
           case 'message.streaming':
             break
@@ -204,7 +286,7 @@ export function useHorsemanEvents({
                 }
               }
             }
-            updateToolOutput(targetSession, '', payload.toolId, payload.output)
+            updateToolOutput(targetSession, '', payload.toolId, payload.output, payload.endedAt)
             break
           }
           case 'tool.error': {
@@ -224,7 +306,7 @@ export function useHorsemanEvents({
               status: 'error',
               error: payload.error,
               output: payload.error,
-              endedAt: new Date().toISOString(),
+              endedAt: payload.endedAt,
             })
             break
           }
@@ -285,6 +367,7 @@ export function useHorsemanEvents({
               toolName: payload.toolName,
               toolInput: payload.toolInput,
               timestamp: Date.now(),
+              priority: payload.priority,
             })
             if (permSessionId !== 'orphan') {
               updateSession(permSessionId, { status: 'waiting_permission' })
@@ -318,6 +401,12 @@ export function useHorsemanEvents({
             }
             break
           }
+          case 'permission.expiring': {
+            // Same countdown for permissions and questions
+            setPermissionSecondsLeft(payload.requestId, payload.secondsLeft)
+            setQuestionSecondsLeft(payload.requestId, payload.secondsLeft)
+            break
+          }
           case 'question.resolved': {
             // Get question's sessionId BEFORE removing
             const state = useStore.getState()
@@ -375,6 +464,8 @@ export function useHorsemanEvents({
     addPendingQuestion,
     removePendingPermission,
     removePendingQuestion,
+    setPermissionSecondsLeft,
+    setQuestionSecondsLeft,
     startSlashCommand,
     appendSlashOutput,
     setSlashDetectionMethod,
@@ -408,6 +499,7 @@ export function useHorsemanEvents({
         initial_prompt: sendContent ?? initialPrompt,
         resume_session: claudeSessionId,
         model,
+        permission_mode: useStore.getState().permissionMode,
       }
 
       const result = await ipc.claude.spawn(args)
@@ -445,13 +537,17 @@ export function useHorsemanEvents({
       addMessage(currentUiSessionId, createUserMessage(content, undefined, fileBlocks))
 
       // Send modified content to Claude if provided, otherwise use display content
-      await ipc.claude.sendMessage(
+      const result = await ipc.claude.sendMessage(
         currentUiSessionId,
         activeClaudeSession,
         workingDirectory,
         sendContent ?? content,
         model
       )
+      if (result.queued) {
+        setIsStreaming(false)
+        setError('Offline: message queued and will be sent when the connection returns.')
+      }
       return currentUiSessionId
     } catch (e) {
       setError(String(e))
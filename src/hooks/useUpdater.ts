@@ -2,6 +2,7 @@ import { useState, useCallback, useEffect } from 'react'
 import { check, type Update } from '@tauri-apps/plugin-updater'
 import { relaunch } from '@tauri-apps/plugin-process'
 import { toast } from 'sonner'
+import { ipc } from '@/lib/ipc'
 
 interface UpdaterState {
   checking: boolean
@@ -23,7 +24,8 @@ export function useUpdater() {
   const checkForUpdates = useCallback(async (silent = false) => {
     setState(s => ({ ...s, checking: true, error: null }))
     try {
-      const update = await check()
+      const config = await ipc.config.get().catch(() => null)
+      const update = await check(config?.httpsProxy ? { proxy: config.httpsProxy } : undefined)
       setState(s => ({ ...s, checking: false, update }))
 
       if (update && !silent) {
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core'
-import type { Message, Question, SessionUsage, TodoItem, ToolCall } from '@/domain'
+import type { DiscoveredSession, EmergencyStopReport, Message, PermissionLane, PermissionMode, Question, SessionMeta, SessionPendingQuestion, SessionSummary, SessionPriority, SessionUsage, TodoItem, ToolCall } from '@/domain'
 
 export interface SpawnSessionArgs {
   ui_session_id: string
@@ -7,18 +7,86 @@ export interface SpawnSessionArgs {
   initial_prompt?: string
   resume_session?: string
   model?: 'sonnet' | 'opus' | 'haiku'
+  /** Keep the process alive between turns (follow-ups go to stdin) */
+  persistent?: boolean
+  /** Passed to claude as --permission-mode */
+  permission_mode?: PermissionMode
+  /** Pre-authorized tools or rules, e.g. 'Read', 'Bash(git log:*)' */
+  allowed_tools?: string[]
+  /** Tools or rules that are always blocked */
+  disallowed_tools?: string[]
+  /** --max-turns for this session (overrides the maxTurns config) */
+  max_turns?: number
+  /** Extra directories Claude may access (--add-dir) */
+  add_dirs?: string[]
+  /** Extended thinking token budget (MAX_THINKING_TOKENS); 0 disables */
+  thinking_budget?: number
+  /** Stream text token by token as message.delta (--include-partial-messages) */
+  stream_partial?: boolean
+  /** Provider profile name; defaults to the activeProvider config */
+  provider?: string
+  /** Start even when over budget and budgetConfirmSpawn is on */
+  confirm_over_budget?: boolean
+}
+
+export interface ForkSessionArgs {
+  /** New UI session that receives the fork */
+  ui_session_id: string
+  /** Claude session to branch from (left untouched) */
+  claude_session_id: string
+  working_directory: string
+  /** First message of the new branch */
+  prompt: string
+  model?: 'sonnet' | 'opus' | 'haiku'
+  persistent?: boolean
+  permission_mode?: PermissionMode
+}
+
+export interface ConsolidateSessionsArgs {
+  /** New UI session that receives the merged context */
+  ui_session_id: string
+  /** Claude sessions to merge, in any order */
+  claude_session_ids: string[]
+  working_directory: string
+  /** First message of the new session */
+  prompt: string
+  model?: 'sonnet' | 'opus' | 'haiku'
+  persistent?: boolean
+}
+
+export interface RecoverResumeArgs {
+  ui_session_id: string
+  claude_session_id: string
+  working_directory: string
+  content: string
+  model?: 'sonnet' | 'opus' | 'haiku'
+}
+
+export interface StartFixLoopArgs {
+  ui_session_id: string
+  working_directory: string
+  prompt: string
+  claude_session_id?: string
+  model?: 'sonnet' | 'opus' | 'haiku'
+  max_iterations?: number
+  max_cost_usd?: number
 }
 
 export interface SpawnSessionResult {
   session_id: string
+  /** Held back while offline; sent when the network returns */
+  queued?: boolean
 }
 
-export interface DiscoveredSession {
-  id: string
-  working_directory: string
-  transcript_path: string
-  modified_at: string
-  first_message: string | null
+export type { DiscoveredSession }
+
+/** Listing order; project/title fall back to newest first */
+export type SessionSort = 'modified' | 'project' | 'title'
+
+export interface SessionListing {
+  sessions: DiscoveredSession[]
+  /** Storage was too slow to list everything; sessions is partial */
+  slow_storage: boolean
 }
 
 export interface FileEntry {
@@ -26,20 +94,330 @@ export interface FileEntry {
   is_dir: boolean
 }
 
+export interface RedactionConfig {
+  /** Mask secrets in everything Horseman shows or keeps (default false) */
+  enabled?: boolean | null
+  /** Use the built-in API key, AWS, bearer token and private key patterns (default true) */
+  builtinPatterns?: boolean | null
+  /** Extra regexes; a `secret` group limits the mask to that group */
+  patterns?: string[]
+  /** Text secrets are replaced with (default "[REDACTED]") */
+  replacement?: string | null
+}
+
 export interface HorsemanConfig {
   claudeBinary: string | null
   projectsDir: string | null
   debugLogPath: string | null
   contextWindow: number | null
+  /** Commands run after a turn that edited files, keyed by project directory */
+  verificationCommands?: Record<string, string[]> | null
+  /** Suspend sessions whose process is idle this long (null = never) */
+  idleSuspendMinutes?: number | null
+  /** Default --max-turns for new sessions (null = unlimited) */
+  maxTurns?: number | null
+  /** Claude processes allowed to run at once; more spawns are queued (null = unlimited) */
+  maxConcurrentSessions?: number | null
+  /** Offer to restart keep-alive sessions when CLAUDE.md or rules change (default true) */
+  promptRestartOnMemoryChange?: boolean | null
+  crashMaxRetries?: number | null
+  crashRetryBackoffMs?: number | null
+  /** Bash commands auto-approved by prefix, e.g. "git status" */
+  bashAllowPrefixes?: string[] | null
+  /** Tool rules in Claude's settings syntax, e.g. "WebSearch" or "Bash(npm run:*)"; deny wins */
+  permissionRules?: PermissionRules | null
+  /** Permission rules keyed by project directory */
+  projectPermissionRules?: Record<string, PermissionRules> | null
+  /** Extra MCP servers for every session */
+  mcpServers?: Record<string, McpServerConfig> | null
+  /** Extra MCP servers keyed by project directory */
+  projectMcpServers?: Record<string, Record<string, McpServerConfig>> | null
+  /** Leave .horseman-mcp.json files behind after sessions end, for debugging */
+  keepMcpConfigs?: boolean | null
+  /** Longest stdout line parsed; longer ones are spilled to disk (default 16 MiB) */
+  maxStdoutLineBytes?: number | null
+  /** Continue turns refused for rate limits or overload (default 0, never) */
+  rateLimitMaxRetries?: number | null
+  rateLimitRetryBackoffMs?: number | null
+  /** Seconds a permission prompt or question waits (default 170, min 10) */
+  permissionTimeoutSecs?: number | null
+  /** What an unanswered permission prompt resolves to (default deny) */
+  permissionTimeoutAction?: 'deny' | 'allowReadOnly' | null
+  connectivityProbeHost?: string | null
+  providerProfiles?: Record<string, ProviderProfile> | null
+  activeProvider?: string | null
+  /** Proxy for Claude processes and the updater */
+  httpsProxy?: string | null
+  /** Comma-separated hosts that bypass the proxy (loopback always does) */
+  noProxy?: string | null
+  /** Extra PEM CA bundle to trust */
+  caCertPath?: string | null
+  backgroundAiEnabled?: boolean | null
+  backgroundAiDailyBudgetUsd?: number | null
+  backgroundAiMinIntervalSecs?: number | null
+  /** Mirror session todo lists to a Markdown file or GitHub issues */
+  todoSyncTarget?: 'markdown' | 'github' | null
+  /** File the Markdown target writes, relative to the working directory (default TODO.md) */
+  todoSyncFile?: string | null
+  todoSyncIntervalSecs?: number | null
+  /** Summarize uncommitted changes when a session ends (default false) */
+  sessionSummaryEnabled?: boolean | null
+  /** Also send a plain-sentence `narration` event per significant event, for screen readers (default false) */
+  narrationEnabled?: boolean | null
+  /** Context usage percents at which usage.threshold is sent (default [70, 90, 95]; [] = never) */
+  contextWarningLevels?: number[] | null
+  /** Transcript backups kept per session, taken before /clear and /compact (default 5; 0 = none) */
+  transcriptBackupsKept?: number | null
+  /** Size all transcript backups together may take; the oldest are deleted past it (default 1 GiB, 0 = no limit) */
+  transcriptBackupsMaxBytes?: number | null
+  /** Back up sessions shortly before Claude's cleanupPeriodDays deletes them (default false) */
+  archiveExpiringSessions?: boolean | null
+  /** Send /compact between turns once context usage reaches this percent (null = never) */
+  autoCompactPercent?: number | null
+  /** Suggested follow-ups after each turn; polished rewrites them with a background AI job (default rules) */
+  quickReplies?: 'off' | 'rules' | 'polished' | null
+  /** Cut tool outputs longer than this many bytes when parsing transcripts (default 1 MiB, 0 = keep whole) */
+  transcriptMaxToolOutputBytes?: number | null
+  /** Also keep parsed transcripts on disk across restarts (default false) */
+  transcriptDiskCache?: boolean | null
+  /** true always polls watched files, false never does (null = only where native events can't work) */
+  pollFileWatchers?: boolean | null
+  /** Secret masking in parsed transcripts and live tool output */
+  redaction?: RedactionConfig | null
+  /** Diff command for openExternalDiff; before/after files are appended or replace {before}/{after} */
+  externalDiffTool?: string | null
+  /** Keep the transcript search index warm (default true; needs the `search` build feature) */
+  searchEnabled?: boolean | null
+  /** Track active time per session (default true; needs the `analytics` build feature) */
+  analyticsEnabled?: boolean | null
+  /** Write a Chrome trace of hot-path spans to <artifacts>/profiles/ (default false; needs the `profiling` build feature) */
+  profilingEnabled?: boolean | null
+  /** Spend per day in USD before `budget.exceeded` is sent (null = no limit) */
+  dailyBudgetUsd?: number | null
+  /** Spend per calendar month in USD (null = no limit) */
+  monthlyBudgetUsd?: number | null
+  /** Percent of a budget at which `budget.warning` is sent (default 80) */
+  budgetWarningPercent?: number | null
+  /** Refuse new sessions while over budget unless confirmOverBudget is set (default false) */
+  budgetConfirmSpawn?: boolean | null
+  /** Days sent prompts stay in the prompt history (default 90, 0 = until cleared) */
+  promptHistoryDays?: number | null
+}
+
+/** An optional subsystem: included in this build (`compiled`) and turned on in config (`enabled`) */
+export interface FeatureStatus {
+  name: 'search' | 'analytics' | 'profiling'
+  compiled: boolean
+  enabled: boolean
+}
+
+/** Session that was open when Horseman last quit */
+export interface RestoredSession {
+  ui_session_id: string
+  claude_session_id: string
+  working_directory: string
+  model: string | null
+  persistent: boolean
+  last_active_at: string
+  /** null if Claude has since pruned the transcript */
+  transcript_path: string | null
+}
+
+export interface SuspendedSession {
+  ui_session_id: string
+  claude_session_id: string
+}
+
+// Supervised background service health
+/** Queue, spend and pause state of background Claude calls */
+export interface BackgroundAiStatus {
+  /** Paused at runtime or disabled in config */
+  paused: boolean
+  /** Jobs are held until the network returns */
+  offline: boolean
+  queued: number
+  /** Label of the job being run */
+  running: string | null
+  spentTodayUsd: number
+  dailyBudgetUsd: number
+}
+
+export interface ServiceHealth {
+  name: string
+  status: 'running' | 'backoff'
+  restarts: number
+  lastError: string | null
+  startedAt: number
+}
+
+// Sensitive webview capabilities
+export interface CapabilityAudit {
+  totalPermissions: number
+  sensitive: { identifier: string; reason: string }[]
+}
+
+// Stream-json output the parser did not expect
+export interface DriftEntry {
+  kind: 'unknownEventType' | 'unknownContentBlock' | 'unexpectedShape'
+  key: string
+  detail: string
+  count: number
+  firstSeen: number
+  lastSeen: number
+  /** Truncated event that first triggered the entry */
+  example: string
+  cliVersion: string | null
+}
+
+export interface DriftReport {
+  cliVersion: string | null
+  eventsChecked: number
+  entries: DriftEntry[]
 }
 
 // Diagnostics types
+export interface ModelInfo {
+  /** Value for `model` (alias or full model ID) */
+  id: string
+  displayName: string
+  contextWindow: number
+  /** Alias that tracks the latest model of its family */
+  alias: boolean
+}
+
+export type ProviderKind = 'anthropic' | 'bedrock' | 'vertex'
+
+/** Provider preset; turned into CLAUDE_CODE_USE_BEDROCK / _VERTEX etc. */
+export interface ProviderProfile {
+  kind?: ProviderKind
+  /** AWS region (Bedrock) or Vertex region */
+  region?: string
+  /** Google Cloud project (Vertex) */
+  projectId?: string
+  /** Named AWS credentials profile (Bedrock) */
+  awsProfile?: string
+  /** Provider model ID for the main model (ANTHROPIC_MODEL) */
+  model?: string
+  /** Provider model ID for background tasks (ANTHROPIC_SMALL_FAST_MODEL) */
+  smallFastModel?: string
+  env?: Record<string, string>
+}
+
+/** Claude-style permission rules (`Bash(npm run:*)`, `WebSearch`, ...) */
+export interface PermissionRules {
+  allow: string[]
+  deny: string[]
+  /** Settings file each imported rule came from */
+  sources?: Record<string, string>
+}
+
+/** Result of importing permission rules from Claude's settings files */
+export interface RuleImport {
+  sources: string[]
+  allowAdded: string[]
+  denyAdded: string[]
+  /** Rules Horseman can't evaluate (paths, domains); Claude still applies them */
+  skipped: { rule: string; source: string; reason: string }[]
+  /** Settings files written back */
+  written: string[]
+}
+
+/** Extra MCP server for sessions; Claude expands `${VAR}` / `${VAR:-default}` */
+export interface McpServerConfig {
+  type?: 'stdio' | 'sse' | 'http'
+  command?: string
+  args?: string[]
+  env?: Record<string, string>
+  url?: string
+  headers?: Record<string, string>
+}
+
+/** A configured MCP server sessions leave out, and why */
+export interface SkippedMcpServer {
+  name: string
+  reason: string
+}
+
+export interface CatalogTool {
+  /** As used in allowed/disallowed tool lists, e.g. `mcp__github__search` */
+  name: string
+  /** MCP server; null for built-ins */
+  server: string | null
+  description: string | null
+}
+
+export interface ServerProbe {
+  name: string
+  status: 'ok' | 'failed' | 'skipped' | 'notProbed'
+  error: string | null
+  toolCount: number
+}
+
+export interface ToolCatalog {
+  tools: CatalogTool[]
+  servers: ServerProbe[]
+  /** Built-ins come from the session's init event rather than the known set */
+  reportedBySession: boolean
+}
+
+export interface ProviderStatus {
+  name: string
+  kind: ProviderKind
+  /** Used by sessions that don't pick a provider */
+  active: boolean
+  /** Missing setting or credentials; null if usable */
+  problem: string | null
+}
+
+export interface ProjectCommand {
+  /** build / test / lint / dev / typecheck / format */
+  purpose: string
+  command: string
+  /** Manifest the command came from */
+  source: string
+}
+
+export interface ProjectProfile {
+  name: string | null
+  languages: string[]
+  tools: string[]
+  commands: ProjectCommand[]
+}
+
+export interface ClaudeMdDraft {
+  content: string
+  profile: ProjectProfile
+  /** Claude rewrote the template draft */
+  refined: boolean
+  /** The project already has a CLAUDE.md */
+  exists: boolean
+}
+
+export interface ClipboardImage {
+  /** Temporary PNG (deleted after a day) */
+  path: string
+  width: number
+  height: number
+}
+
 export interface DiagnosticsInfo {
   claude: ClaudeDiagnostics
   config: ConfigDiagnostics
   fileAccess: FileAccessTest[]
   spawnTest: SpawnTestResult
   environment: EnvironmentInfo
+  /** File watchers and whether they use native events or polling */
+  watchers: WatcherStatus[]
+}
+
+export interface WatcherStatus {
+  name: string
+  mode: 'native' | 'polling'
+  /** Why the watcher is polling */
+  reason: string | null
+  watchedPaths: number
+  /** Current poll interval (polling only) */
+  pollIntervalMs: number | null
 }
 
 export interface SpawnTestResult {
@@ -132,6 +510,301 @@ export interface TranscriptParseResult {
   summaries: TranscriptSummary[]
   /** Tools from subagent transcripts, with parentToolId set to their Task tool */
   subagentTools: ToolCall[]
+  /** Combined subagent token usage; the CLI's totalCostUsd already covers subagents */
+  subagentUsage: SessionUsage | null
+  /** Storage was too slow; result is empty/partial */
+  slowStorage: boolean
+  /** Horseman's sidecar annotations (never stored in Claude's transcript);
+   *  absent on transcript pages for sessions without any */
+  annotations?: Annotation[]
+}
+
+/** One page of a transcript; todos, usage and cost cover this page only */
+export interface TranscriptPage extends TranscriptParseResult {
+  cursor: number
+  /** Pass back as `cursor` for the next page; null on the last page */
+  nextCursor: number | null
+  totalBytes: number
+}
+
+export interface ToolSpan {
+  toolId: string
+  name: string
+  status: string
+  /** Task the tool ran under, for subagent tools */
+  parentToolId: string | null
+  /** Nesting level (0 = called by the main session) */
+  depth: number
+  startedAt: string
+  endedAt: string | null
+  /** Offset from the timeline start */
+  startMs: number
+  /** null while the tool is still running */
+  durationMs: number | null
+}
+
+/** Gantt-style tool spans; parents come before their children */
+export interface ToolTimeline {
+  startedAt: string | null
+  durationMs: number
+  spans: ToolSpan[]
+}
+
+export type AnnotationKind = 'note' | 'pin' | 'permissionDecision' | 'verification' | 'slashCommand'
+
+export interface Annotation {
+  id: string
+  kind: AnnotationKind
+  /** Unix millis */
+  createdAt: number
+  messageId?: string
+  toolUseId?: string
+  text?: string
+  /** Kind-specific payload */
+  data?: unknown
+}
+
+/** Copy of a session's transcripts taken before /clear or /compact */
+export interface TranscriptBackup {
+  id: string
+  claudeSessionId: string
+  createdAt: string
+  /** e.g. "/compact", or "restore" for the copy taken before a restore */
+  reason: string
+  projectDir: string
+  /** Relative to projectDir; the transcript comes first */
+  files: string[]
+  totalBytes: number
+}
+
+export interface AddAnnotationArgs {
+  claude_session_id: string
+  kind: AnnotationKind
+  message_id?: string
+  text?: string
+  data?: unknown
+}
+
+export interface SuggestedEditResult {
+  targetPath: string
+  preview: string
+  applied: boolean
+  checkpointId: string | null
+}
+
+export interface CheckpointEntry {
+  id: string
+  path: string
+  before: string | null
+  after: string | null
+  label: string
+  createdAt: string
+  /** Set while the change is undone and can be redone */
+  undoneAt?: string
+}
+
+export interface ExternalDiff {
+  filePath: string
+  beforePath: string
+  afterPath: string
+  /** False when the file before the call is unknown, so only the edited snippets (or a Write's content) are shown */
+  wholeFile: boolean
+}
+
+export interface SessionMetadata {
+  sessionId: string
+  transcriptPath: string
+  parserVersion: number
+  transcriptModifiedAt: string
+  firstMessage: string | null
+  messageCount: number
+  toolCount: number
+  totalCostUsd: number | null
+  summary: string | null
+  /** Branch the session was started on */
+  gitBranch: string | null
+  /** Worktree the session was started in */
+  worktree: string | null
+  /** Default title: `branch — first prompt` */
+  title: string | null
+  /** What Horseman last ran the session with */
+  environment: SessionEnvironment | null
+}
+
+/** Snapshot of a session's latest run, for reproducing or reporting it */
+export interface SessionEnvironment {
+  recordedAt: string
+  appVersion: string
+  claudeBinary: string
+  claudeVersion: string | null
+  /** Model Claude reported using, else the one requested */
+  model: string | null
+  /** CLI arguments, without the prompt */
+  args: string[]
+  /** SHA-256 of the Horseman config in effect */
+  configHash: string
+  gitCommit: string | null
+  os: string
+}
+
+/** Working tree a session started from, stored as a git tree object */
+export interface WorkspaceSnapshot {
+  worktree: string
+  tree: string
+  headCommit: string | null
+  takenAt: string
+}
+
+export interface WorkspaceFileDiff {
+  /** Relative to the worktree */
+  path: string
+  /** Previous path of a renamed file */
+  oldPath: string | null
+  change: 'added' | 'modified' | 'deleted' | 'renamed'
+  /** Null for binary files */
+  additions: number | null
+  deletions: number | null
+  patch: string
+  /** The patch was cut off at 100k characters */
+  truncated: boolean
+}
+
+/** Everything a session changed since it started, grouped by file */
+export interface WorkspaceDiff {
+  claudeSessionId: string
+  snapshot: WorkspaceSnapshot
+  files: WorkspaceFileDiff[]
+  additions: number
+  deletions: number
+}
+
+/** A prompt sent in a project; resending it moves it to the front */
+export interface PromptEntry {
+  project: string
+  prompt: string
+  claudeSessionId: string | null
+  sentAt: string
+}
+
+export interface ReparseReport {
+  parserVersion: number
+  scanned: number
+  created: number
+  updated: number
+  unchanged: number
+  changedSessions: string[]
+  errors: string[]
+}
+
+export interface SchemaVersionInfo {
+  version: number
+  minSupported: number
+  negotiated: number
+}
+
+/** Which `horseman-event`s this window receives; omitted fields match everything */
+export interface EventFilter {
+  /** UI sessions; events not tied to a session always pass */
+  sessionIds?: string[]
+  /** Event types (`stream.delta`) or prefixes ending in `.` (`session.`) */
+  kinds?: string[]
+}
+
+export interface ExportReport {
+  archivePath: string
+  fileCount: number
+  totalBytes: number
+  includesTranscripts: boolean
+}
+
+export interface ImportReport {
+  filesWritten: number
+  filesSkipped: number
+  includesTranscripts: boolean
+}
+
+export interface TimeEntry {
+  uiSessionId: string
+  /** Local date, YYYY-MM-DD */
+  day: string
+  project: string
+  claudeSessionId: string | null
+  activeMs: number
+}
+
+export interface TimeReport {
+  from: string
+  to: string
+  /** By day, then project. activeSecs counts time concurrent sessions share once. */
+  days: { day: string; project: string; activeSecs: number; sessions: TimeEntry[] }[]
+  /** Wall time with any session active */
+  totalSecs: number
+}
+
+export interface CostTotal {
+  /** Day (YYYY-MM-DD), week (its Monday, YYYY-MM-DD) or project, per the report's groupBy */
+  key: string
+  costUsd: number
+  turns: number
+  sessions: number
+  durationMs: number
+  inputTokens: number
+  outputTokens: number
+  cacheReadTokens: number
+  cacheCreationTokens: number
+}
+
+export interface CostReport {
+  from: string
+  to: string
+  groupBy: 'day' | 'week' | 'project'
+  groups: CostTotal[]
+  totalCostUsd: number
+  totalTurns: number
+}
+
+export interface ActivityReport {
+  period: 'day' | 'week'
+  /** Local dates, YYYY-MM-DD */
+  from: string
+  to: string
+  markdown: string
+  /** Rewritten by a background AI job */
+  polished: boolean
+  sessionCount: number
+  projectCount: number
+  totalCostUsd: number
+}
+
+export interface TranscriptSearchHit {
+  /** Top-level session (subagent messages report their parent) */
+  sessionId: string
+  workingDirectory: string
+  transcriptPath: string
+  /** 0-based line of the message in the transcript */
+  line: number
+  byteOffset: number
+  role: 'user' | 'assistant'
+  /** Matches wrapped in [ ] */
+  snippet: string
+}
+
+export interface SessionImportReport {
+  claudeSessionId: string
+  transcriptPath: string
+  filesWritten: number
+}
+
+export interface EffectiveConfigValue {
+  key: keyof HorsemanConfig
+  value: unknown
+  source: 'default' | 'file' | 'env' | 'project'
+  envVar?: string
+}
+
+export interface ConfigSnapshot {
+  config: HorsemanConfig
+  revision: number
 }
 
 export const ipc = {
@@ -152,26 +825,111 @@ export const ipc = {
         content,
         model,
       }),
+    recoverFailedResume: (args: RecoverResumeArgs) =>
+      invoke<SpawnSessionResult>('recover_failed_resume', { args }),
+    fork: (args: ForkSessionArgs) =>
+      invoke<SpawnSessionResult>('fork_claude_session', { args }),
+    consolidate: (args: ConsolidateSessionsArgs) =>
+      invoke<SpawnSessionResult>('consolidate_sessions', { args }),
     interrupt: (uiSessionId: string) =>
       invoke<void>('interrupt_claude_session', { uiSessionId }),
+    /** Restart a keep-alive session so it re-reads CLAUDE.md and rules */
+    restart: (uiSessionId: string) =>
+      invoke<void>('restart_claude_session', { uiSessionId }),
+    listSuspended: () =>
+      invoke<SuspendedSession[]>('list_suspended_sessions'),
+    restoreSessions: () =>
+      invoke<RestoredSession[]>('restore_sessions'),
     isRunning: (uiSessionId: string) =>
       invoke<boolean>('is_claude_running', { uiSessionId }),
     remove: (uiSessionId: string) =>
       invoke<void>('remove_claude_session', { uiSessionId }),
+    /** Starts configured MCP servers briefly to list their tools */
+    getToolCatalog: (uiSessionId: string | null, workingDirectory?: string) =>
+      invoke<ToolCatalog>('get_tool_catalog', { uiSessionId, workingDirectory }),
   },
   sessions: {
-    listAll: () =>
-      invoke<DiscoveredSession[]>('list_claude_sessions'),
-    listForDirectory: (workingDirectory: string) =>
-      invoke<DiscoveredSession[]>('list_sessions_for_directory', { workingDirectory }),
+    /** `branch` limits the listing to sessions started on that git branch */
+    listAll: (sort?: SessionSort, branch?: string) =>
+      invoke<SessionListing>('list_claude_sessions', { sort, branch }),
+    listForDirectory: (workingDirectory: string, sort?: SessionSort, branch?: string) =>
+      invoke<SessionListing>('list_sessions_for_directory', { workingDirectory, sort, branch }),
     readTranscript: (transcriptPath: string) =>
       invoke<string>('read_session_transcript', { transcriptPath }),
     parseTranscript: (transcriptPath: string) =>
       invoke<TranscriptParseResult>('parse_session_transcript', { transcriptPath }),
+    /** Parse a transcript a page at a time, starting at byte `cursor` */
+    parseTranscriptPage: (transcriptPath: string, cursor?: number, limit?: number) =>
+      invoke<TranscriptPage>('parse_session_transcript_page', { transcriptPath, cursor, limit }),
+    getToolTimeline: (transcriptPath: string) =>
+      invoke<ToolTimeline>('get_tool_timeline', { transcriptPath }),
+    /** Follow a transcript written elsewhere; updates arrive as `transcript.appended` */
+    tailTranscript: (transcriptPath: string) =>
+      invoke<TranscriptParseResult>('tail_transcript', { transcriptPath }),
+    untailTranscript: (transcriptPath: string) =>
+      invoke<void>('untail_transcript', { transcriptPath }),
     extractSummary: (transcriptPath: string) =>
       invoke<string | null>('extract_transcript_summary', { transcriptPath }),
     getTranscriptPath: (workingDirectory: string, sessionId: string) =>
       invoke<string>('get_transcript_path', { workingDirectory, sessionId }),
+    getRawLog: (uiSessionId: string) =>
+      invoke<string>('get_raw_session_log', { uiSessionId }),
+    getMetadata: (sessionId: string, transcriptPath: string) =>
+      invoke<SessionMetadata>('get_session_metadata', { sessionId, transcriptPath }),
+    reparseAll: () =>
+      invoke<ReparseReport>('reparse_all_sessions'),
+    addAnnotation: (args: AddAnnotationArgs) =>
+      invoke<Annotation>('add_session_annotation', { args }),
+    listAnnotations: (claudeSessionId: string) =>
+      invoke<Annotation[]>('list_session_annotations', { claudeSessionId }),
+    removeAnnotation: (claudeSessionId: string, annotationId: string) =>
+      invoke<boolean>('remove_session_annotation', { claudeSessionId, annotationId }),
+    listTranscriptBackups: (claudeSessionId: string) =>
+      invoke<TranscriptBackup[]>('list_transcript_backups', { claudeSessionId }),
+    /** Fails while the session is running; returns the backup of what was replaced */
+    restoreTranscriptBackup: (claudeSessionId: string, backupId: string) =>
+      invoke<TranscriptBackup | null>('restore_transcript_backup', { claudeSessionId, backupId }),
+    getMeta: (claudeSessionId: string) =>
+      invoke<SessionMeta>('get_session_meta', { claudeSessionId }),
+    setTitle: (claudeSessionId: string, title: string | null) =>
+      invoke<SessionMeta>('set_session_title', { claudeSessionId, title }),
+    setPinned: (claudeSessionId: string, pinned: boolean) =>
+      invoke<SessionMeta>('set_session_pinned', { claudeSessionId, pinned }),
+    setTags: (claudeSessionId: string, tags: string[]) =>
+      invoke<SessionMeta>('set_session_tags', { claudeSessionId, tags }),
+    clearMeta: (claudeSessionId: string) =>
+      invoke<boolean>('clear_session_meta', { claudeSessionId }),
+    /** Prompts sent in a project, newest first, or fuzzy-ranked against `query` */
+    getPromptHistory: (project: string, query?: string, limit?: number) =>
+      invoke<PromptEntry[]>('get_prompt_history', { project, query, limit }),
+    /** Forget a project's prompt history, or every project's without one; returns how many were removed */
+    clearPromptHistory: (project?: string) =>
+      invoke<number>('clear_prompt_history', { project }),
+    /** Active time per project and day; dates are YYYY-MM-DD and default to today */
+    getTimeReport: (from?: string, to?: string, project?: string) =>
+      invoke<TimeReport>('get_time_report', { from, to, project }),
+    /** Spend per day, week or project from the cost ledger; dates are YYYY-MM-DD and default to today */
+    getCostReport: (from?: string, to?: string, groupBy?: 'day' | 'week' | 'project', project?: string) =>
+      invoke<CostReport>('get_cost_report', { from, to, groupBy, project }),
+    /** Standup report; optionally polished, written to `exportPath`, or copied to the clipboard */
+    generateActivityReport: (
+      period: 'day' | 'week',
+      options: { polish?: boolean; exportPath?: string; copyToClipboard?: boolean } = {},
+    ) => invoke<ActivityReport>('generate_activity_report', { period, ...options }),
+    getSummary: (claudeSessionId: string) =>
+      invoke<SessionSummary | null>('get_session_summary', { claudeSessionId }),
+    /** Changes to the working tree since Horseman first ran the session */
+    getWorkspaceDiff: (claudeSessionId: string) =>
+      invoke<WorkspaceDiff>('get_session_workspace_diff', { claudeSessionId }),
+    /** From the session summary written for exactly the uncommitted changes, if any */
+    getSuggestedCommitMessage: (workingDirectory: string) =>
+      invoke<string | null>('get_suggested_commit_message', { workingDirectory }),
+    /** Deletes the transcript from disk; returns the removed paths */
+    delete: (claudeSessionId: string) =>
+      invoke<string[]>('delete_claude_session', { claudeSessionId }),
+    /** Full-text search across all transcripts, best matches first */
+    search: (query: string, workingDirectory?: string, limit?: number) =>
+      invoke<TranscriptSearchHit[]>('search_transcripts', { query, workingDirectory, limit }),
   },
   permissions: {
     respond: (
@@ -181,7 +939,11 @@ export const ipc = {
         message?: string
         toolName?: string
         allowForSession?: boolean
+        /** Also allow the tool for files under this directory */
+        allowPathPrefix?: string
         answers?: Record<string, string>
+        /** Run the tool with this input instead of Claude's */
+        updatedInput?: Record<string, unknown>
       }
     ) =>
       invoke<void>('respond_permission', {
@@ -190,10 +952,25 @@ export const ipc = {
         message: options?.message,
         toolName: options?.toolName,
         allowForSession: options?.allowForSession,
+        allowPathPrefix: options?.allowPathPrefix,
         answers: options?.answers,
+        updatedInput: options?.updatedInput,
       }),
+    /** Save a rule auto-approving Bash commands like this one; returns the rule */
+    addBashRule: (command: string, prefix?: string) =>
+      invoke<string>('add_bash_allow_rule', { command, prefix }),
+    /** Import allow/deny rules from ~/.claude and the project's .claude settings; optionally write ours back */
+    importClaudeRules: (project?: string, writeBack?: boolean) =>
+      invoke<RuleImport>('import_claude_permission_rules', { project, writeBack }),
     getHookServerPort: () =>
       invoke<number>('get_hook_server_port'),
+    /** Configured MCP servers sessions in this project would leave out */
+    checkMcpServers: (workingDirectory: string) =>
+      invoke<SkippedMcpServer[]>('check_mcp_servers', { workingDirectory }),
+    getQueue: () =>
+      invoke<PermissionLane[]>('get_permission_queue'),
+    setSessionPriority: (uiSessionId: string, priority: SessionPriority) =>
+      invoke<void>('set_session_priority', { uiSessionId, priority }),
   },
   questions: {
     respond: (requestId: string, answers: Record<string, string>) =>
@@ -202,10 +979,21 @@ export const ipc = {
         allow: true,
         answers,
       }),
+    getPendingOnStartup: () =>
+      invoke<SessionPendingQuestion[] | null>('get_startup_pending_questions'),
   },
   files: {
     glob: (workingDirectory: string, query: string, maxResults?: number) =>
       invoke<FileEntry[]>('glob_files', { workingDirectory, query, maxResults }),
+    readText: (path: string) =>
+      invoke<string>('read_text_file', { path }),
+    /** Save the clipboard image as a temporary PNG to attach to a prompt */
+    saveClipboardImage: () =>
+      invoke<ClipboardImage>('save_clipboard_image'),
+  },
+  storage: {
+    load: () => invoke<string | null>('load_ui_state'),
+    save: (content: string) => invoke<void>('save_ui_state', { content }),
   },
   greet: (name: string) =>
     invoke<string>('greet', { name }),
@@ -222,14 +1010,99 @@ export const ipc = {
       invoke<HorsemanConfig>('get_horseman_config'),
     update: (config: HorsemanConfig) =>
       invoke<HorsemanConfig>('update_horseman_config', { config }),
+    getEffective: (workingDirectory?: string) =>
+      invoke<EffectiveConfigValue[]>('get_effective_config', { workingDirectory }),
+    getSnapshot: () =>
+      invoke<ConfigSnapshot>('get_horseman_config_snapshot'),
+    /** Update only the given fields (null clears). Fails on conflict if expectedRevision is stale. */
+    patch: (patch: Partial<Record<keyof HorsemanConfig, unknown>>, expectedRevision?: number) =>
+      invoke<ConfigSnapshot>('update_horseman_config_patch', { patch, expectedRevision }),
     getPath: () =>
       invoke<string | null>('get_config_path'),
   },
+  models: {
+    /** Models supported by the installed Claude CLI, aliases first */
+    list: () => invoke<ModelInfo[]>('list_available_models'),
+    /** Configured provider profiles with validation problems */
+    listProviders: () => invoke<ProviderStatus[]>('list_provider_profiles'),
+  },
+  claudeMd: {
+    /** Draft a CLAUDE.md from the project's manifests (nothing is written) */
+    generate: (workingDirectory: string, refine?: boolean) =>
+      invoke<ClaudeMdDraft>('generate_claude_md', { workingDirectory, refine }),
+    /** Save a reviewed draft; returns the written path */
+    write: (workingDirectory: string, content: string, overwrite?: boolean) =>
+      invoke<string>('write_claude_md', { workingDirectory, content, overwrite }),
+  },
   status: {
     get: (workingDirectory: string) =>
       invoke<StatusInfo>('get_status_info', { workingDirectory }),
   },
+  data: {
+    export: (archivePath: string, includeTranscripts?: boolean) =>
+      invoke<ExportReport>('export_horseman_data', { archivePath, includeTranscripts }),
+    import: (archivePath: string, overwrite?: boolean) =>
+      invoke<ImportReport>('import_horseman_data', { archivePath, overwrite }),
+    exportSession: (claudeSessionId: string, archivePath: string) =>
+      invoke<ExportReport>('export_session_archive', { claudeSessionId, archivePath }),
+    /** `workingDirectory` files the session under another project than the one it was exported from */
+    importSession: (archivePath: string, workingDirectory?: string, overwrite?: boolean) =>
+      invoke<SessionImportReport>('import_session_archive', { archivePath, workingDirectory, overwrite }),
+  },
+  schema: {
+    getBackendVersion: () =>
+      invoke<SchemaVersionInfo>('get_backend_schema_version'),
+    negotiate: (frontendVersion: number) =>
+      invoke<number>('negotiate_event_schema', { frontendVersion }),
+    /** Only deliver matching events to this window; `null` receives everything again */
+    subscribe: (filter: EventFilter | null) =>
+      invoke<void>('subscribe_events', { filter }),
+  },
   diagnostics: {
     get: () => invoke<DiagnosticsInfo>('get_diagnostics'),
   },
+  backend: {
+    reset: () => invoke<void>('reset_backend_state'),
+    emergencyStopAll: () => invoke<EmergencyStopReport>('emergency_stop_all'),
+    /** Same as emergencyStopAll */
+    killAllSessions: () => invoke<EmergencyStopReport>('kill_all_sessions'),
+    getServiceHealth: () => invoke<ServiceHealth[]>('get_service_health'),
+    getCapabilityAudit: () => invoke<CapabilityAudit>('get_capability_audit'),
+    getSchemaDriftReport: () => invoke<DriftReport>('get_schema_drift_report'),
+    getEnabledFeatures: () => invoke<FeatureStatus[]>('get_enabled_features'),
+    getBackgroundAiStatus: () => invoke<BackgroundAiStatus>('get_background_ai_status'),
+    setBackgroundAiPaused: (paused: boolean) =>
+      invoke<BackgroundAiStatus>('set_background_ai_paused', { paused }),
+  },
+  verification: {
+    run: (uiSessionId: string, workingDirectory: string) =>
+      invoke<boolean>('run_verification', { uiSessionId, workingDirectory }),
+    getFixPrompt: (uiSessionId: string) =>
+      invoke<string | null>('get_verification_fix_prompt', { uiSessionId }),
+    sendFix: (uiSessionId: string, claudeSessionId: string, workingDirectory: string, model?: string) =>
+      invoke<SpawnSessionResult>('send_verification_fix', {
+        uiSessionId,
+        claudeSessionId,
+        workingDirectory,
+        model,
+      }),
+    startFixLoop: (args: StartFixLoopArgs) =>
+      invoke<SpawnSessionResult>('start_fix_loop', { args }),
+    stopFixLoop: (uiSessionId: string) =>
+      invoke<boolean>('stop_fix_loop', { uiSessionId }),
+  },
+  edits: {
+    applySuggested: (messageId: string, blockIndex: number, targetPath: string, dryRun?: boolean) =>
+      invoke<SuggestedEditResult>('apply_suggested_edit', { messageId, blockIndex, targetPath, dryRun }),
+    listCheckpoints: () =>
+      invoke<CheckpointEntry[]>('list_checkpoints'),
+    undoCheckpoint: (checkpointId: string, force?: boolean) =>
+      invoke<CheckpointEntry>('undo_checkpoint', { checkpointId, force }),
+    undoLastWorkspaceChange: (force?: boolean) =>
+      invoke<CheckpointEntry>('undo_last_workspace_change', { force }),
+    redoLastWorkspaceChange: (force?: boolean) =>
+      invoke<CheckpointEntry>('redo_last_workspace_change', { force }),
+    openExternalDiff: (toolId: string, transcriptPath: string) =>
+      invoke<ExternalDiff>('open_external_diff', { toolId, transcriptPath }),
+  },
 }
@@ -1,35 +1,9 @@
-import { appDataDir } from '@tauri-apps/api/path'
-import {
-  readTextFile,
-  writeTextFile,
-  mkdir,
-  exists,
-} from '@tauri-apps/plugin-fs'
 import type { StateStorage } from 'zustand/middleware'
+import { ipc } from './ipc'
 
-const STATE_FILE = 'state.json'
-let cachedBasePath: string | null = null
 let writeTimeout: ReturnType<typeof setTimeout> | null = null
 let pendingWrite: string | null = null
 
-async function getBasePath(): Promise<string> {
-  if (cachedBasePath) return cachedBasePath
-  cachedBasePath = await appDataDir()
-  return cachedBasePath
-}
-
-async function ensureDir(path: string): Promise<void> {
-  if (!(await exists(path))) {
-    await mkdir(path, { recursive: true })
-  }
-}
-
-async function getStatePath(): Promise<string> {
-  const base = await getBasePath()
-  await ensureDir(base)
-  return `${base}${STATE_FILE}`
-}
-
 // Debounced write - 500ms
 async function debouncedWrite(value: string): Promise<void> {
   pendingWrite = value
@@ -41,8 +15,7 @@ async function debouncedWrite(value: string): Promise<void> {
   writeTimeout = setTimeout(async () => {
     if (pendingWrite === null) return
     try {
-      const path = await getStatePath()
-      await writeTextFile(path, pendingWrite)
+      await ipc.storage.save(pendingWrite)
       pendingWrite = null
     } catch (err) {
       console.error('[storage] Write failed:', err)
@@ -53,12 +26,7 @@ async function debouncedWrite(value: string): Promise<void> {
 export const tauriStorage: StateStorage = {
   getItem: async (_name: string): Promise<string | null> => {
     try {
-      const path = await getStatePath()
-      if (!(await exists(path))) {
-        return null
-      }
-      const content = await readTextFile(path)
-      return content
+      return await ipc.storage.load()
     } catch (err) {
       console.error('[storage] Read failed:', err)
       return null
@@ -71,8 +39,7 @@ export const tauriStorage: StateStorage = {
 
   removeItem: async (_name: string): Promise<void> => {
     try {
-      const path = await getStatePath()
-      await writeTextFile(path, '{}')
+      await ipc.storage.save('{}')
     } catch (err) {
       console.error('[storage] Remove failed:', err)
     }
@@ -103,7 +103,7 @@ export const createChatSlice: StateCreator<AppStore, [], [], ChatSlice> = (set,
       return { sessions: nextSessions }
     }),
 
-  updateToolOutput: (sessionId: string, _messageId: string, toolId: string, output: string) =>
+  updateToolOutput: (sessionId: string, _messageId: string, toolId: string, output: string, endedAt?: string) =>
     set((state) => {
       const sessionState = state.sessions[sessionId]
       if (!sessionState) return state
@@ -129,7 +129,7 @@ export const createChatSlice: StateCreator<AppStore, [], [], ChatSlice> = (set,
               ...tool,
               output,
               status: 'completed' as const,
-              endedAt: new Date().toISOString(),
+              endedAt: endedAt ?? new Date().toISOString(),
             }
           : tool
       )
@@ -1,5 +1,7 @@
 import type { StateCreator } from 'zustand'
-import type { AppStore, PermissionsSlice, PendingPermission } from '../types'
+import type { AppStore, PermissionsSlice, PendingPermission, SessionPriority } from '../types'
+
+const PRIORITY_RANK: Record<SessionPriority, number> = { low: 0, normal: 1, high: 2 }
 
 export const createPermissionsSlice: StateCreator<AppStore, [], [], PermissionsSlice> = (set, get) => ({
   pendingPermissions: [],
@@ -19,8 +21,32 @@ export const createPermissionsSlice: StateCreator<AppStore, [], [], PermissionsS
       pendingPermissions: state.pendingPermissions.filter((p) => p.sessionId !== sessionId),
     })),
 
+  setSessionPermissionPriority: (sessionId: string, priority: SessionPriority) =>
+    set((state) => ({
+      pendingPermissions: state.pendingPermissions.map((p) =>
+        p.sessionId === sessionId ? { ...p, priority } : p
+      ),
+    })),
+
+  setPermissionSecondsLeft: (requestId: string, secondsLeft: number) =>
+    set((state) => ({
+      pendingPermissions: state.pendingPermissions.map((p) =>
+        p.requestId === requestId ? { ...p, secondsLeft } : p
+      ),
+    })),
+
+  // Oldest prompt of the highest-priority session, matching the backend's lane order
   getNextPendingPermission: () => {
     const { pendingPermissions } = get()
-    return pendingPermissions[0] ?? null
+    let next: PendingPermission | null = null
+    for (const p of pendingPermissions) {
+      if (!next) {
+        next = p
+        continue
+      }
+      const rank = PRIORITY_RANK[p.priority ?? 'normal'] - PRIORITY_RANK[next.priority ?? 'normal']
+      if (rank > 0 || (rank === 0 && p.timestamp < next.timestamp)) next = p
+    }
+    return next
   },
 })
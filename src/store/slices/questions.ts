@@ -18,4 +18,11 @@ export const createQuestionsSlice: StateCreator<AppStore, [], [], QuestionsSlice
     set((state) => ({
       pendingQuestions: state.pendingQuestions.filter((q) => q.sessionId !== sessionId),
     })),
+
+  setQuestionSecondsLeft: (requestId: string, secondsLeft: number) =>
+    set((state) => ({
+      pendingQuestions: state.pendingQuestions.map((q) =>
+        q.requestId === requestId ? { ...q, secondsLeft } : q
+      ),
+    })),
 })
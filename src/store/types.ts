@@ -1,5 +1,5 @@
-import type { ParsedMessage, PendingPermission, PendingQuestion, PermissionMode, Session, SessionEvent, ToolCall } from '@/domain'
-export type { PendingPermission, PendingQuestion, PermissionMode, Question, QuestionOption, SessionEvent } from '@/domain'
+import type { ParsedMessage, PendingPermission, PendingQuestion, PermissionMode, Session, SessionEvent, SessionPriority, ToolCall } from '@/domain'
+export type { PendingPermission, PendingQuestion, PermissionMode, Question, QuestionOption, SessionEvent, SessionPriority } from '@/domain'
 
 export interface SessionState {
   session: Session
@@ -14,6 +14,8 @@ export interface PermissionsSlice {
   addPendingPermission: (permission: PendingPermission) => void
   removePendingPermission: (requestId: string) => void
   clearPendingPermissions: (sessionId: string) => void
+  setSessionPermissionPriority: (sessionId: string, priority: SessionPriority) => void
+  setPermissionSecondsLeft: (requestId: string, secondsLeft: number) => void
   getNextPendingPermission: () => PendingPermission | null
 }
 
@@ -22,6 +24,7 @@ export interface QuestionsSlice {
   addPendingQuestion: (question: PendingQuestion) => void
   removePendingQuestion: (requestId: string) => void
   clearPendingQuestions: (sessionId: string) => void
+  setQuestionSecondsLeft: (requestId: string, secondsLeft: number) => void
 }
 
 export interface SessionsSlice {
@@ -46,7 +49,7 @@ export interface ChatSlice {
   updateMessage: (sessionId: string, messageId: string, updates: Partial<ParsedMessage>) => void
 
   // Update a specific tool's output within a message
-  updateToolOutput: (sessionId: string, messageId: string, toolId: string, output: string) => void
+  updateToolOutput: (sessionId: string, messageId: string, toolId: string, output: string, endedAt?: string) => void
 
   // Update tool fields (parentToolId, subagent, etc.) with indexed lookup
   updateToolFields: (sessionId: string, toolId: string, updates: Partial<ToolCall>) => void